@@ -0,0 +1,198 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Persisting the vNV-Heap with interrupts disabled, based on the [`critical-section`](https://crates.io/crates/critical-section) crate.
+//!
+//! `vnv_persist_all` requires that no other code runs while it persists the heap. Instead of hand-rolling
+//! `irq_lock`/`irq_unlock` helpers for every platform, this crate uses the critical section implementation
+//! of the target (e.g. provided by `cortex-m` or `riscv` with their `critical-section-single-hart` feature).
+
+#![cfg_attr(not(test), no_std)]
+
+use core::cell::{Cell, UnsafeCell};
+
+use critical_section::RestoreState;
+use vnv_heap::{modules::lock::LockModule, vnv_persist_all};
+
+/// Persists all heaps (see `vnv_persist_all`) inside of a critical section.
+///
+/// Call this from your power failure interrupt handler or persist trigger.
+///
+/// ### Safety
+///
+/// The critical section has to stop all other code that could access a heap.
+/// This is the case for single core targets, but not necessarily for multi core targets.
+pub unsafe fn persist_all_in_critical_section() {
+    critical_section::with(|_| vnv_persist_all());
+}
+
+/// Disables interrupts as long as it exists (e.g. while a persist trigger runs its own code around `vnv_persist_all`).
+///
+/// Guards can be nested, but have to be dropped in reverse order.
+pub struct CriticalSectionPersistGuard {
+    restore_state: RestoreState,
+}
+
+impl CriticalSectionPersistGuard {
+    pub fn new() -> Self {
+        Self {
+            restore_state: unsafe { critical_section::acquire() },
+        }
+    }
+
+    /// Persists all heaps (see `vnv_persist_all`).
+    ///
+    /// ### Safety
+    ///
+    /// Same as for `persist_all_in_critical_section`
+    pub unsafe fn persist_all(&self) {
+        vnv_persist_all();
+    }
+}
+
+impl Drop for CriticalSectionPersistGuard {
+    fn drop(&mut self) {
+        unsafe { critical_section::release(self.restore_state) };
+    }
+}
+
+/// Lock module for `SyncVNVHeap` that accesses the heap inside of a critical section.
+///
+/// Critical sections are reentrant, so the lock can be acquired again while it is held (e.g. by nested calls of `SyncVNVHeap::with`).
+/// Interrupts are enabled again once the outermost lock is released.
+///
+/// Only use this for single core targets, as other cores are not stopped by most critical section implementations.
+pub struct CriticalSectionLockModule {
+    /// State of the outermost `lock` call
+    restore_state: UnsafeCell<RestoreState>,
+
+    /// How many times the lock is currently held
+    depth: Cell<usize>,
+}
+
+impl CriticalSectionLockModule {
+    pub const fn new() -> Self {
+        Self {
+            restore_state: UnsafeCell::new(RestoreState::invalid()),
+            depth: Cell::new(0),
+        }
+    }
+}
+
+// `restore_state` and `depth` are only accessed inside of the critical section
+unsafe impl Sync for CriticalSectionLockModule {}
+
+unsafe impl LockModule for CriticalSectionLockModule {
+    fn lock(&self) {
+        let restore_state = unsafe { critical_section::acquire() };
+
+        // we are inside of the critical section now, so no one else can access this
+        let depth = self.depth.get();
+        if depth == 0 {
+            unsafe { *self.restore_state.get() = restore_state };
+        } else {
+            // nested critical section: releasing it right away keeps the outer one active
+            unsafe { critical_section::release(restore_state) };
+        }
+        self.depth.set(depth + 1);
+    }
+
+    fn unlock(&self) {
+        let depth = self.depth.get() - 1;
+        self.depth.set(depth);
+        if depth == 0 {
+            let restore_state = unsafe { *self.restore_state.get() };
+            unsafe { critical_section::release(restore_state) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    };
+
+    use critical_section::RawRestoreState;
+    use vnv_heap::modules::lock::LockModule;
+
+    use super::{CriticalSectionLockModule, CriticalSectionPersistGuard};
+
+    /// Simulated interrupt enable flag of a single core target
+    static INTERRUPTS_ENABLED: AtomicBool = AtomicBool::new(true);
+
+    /// The tests share `INTERRUPTS_ENABLED`, so they must not run at the same time
+    static TEST_MUTEX: Mutex<()> = Mutex::new(());
+
+    struct TestCriticalSection;
+    critical_section::set_impl!(TestCriticalSection);
+
+    unsafe impl critical_section::Impl for TestCriticalSection {
+        unsafe fn acquire() -> RawRestoreState {
+            INTERRUPTS_ENABLED.swap(false, Ordering::SeqCst)
+        }
+
+        unsafe fn release(was_enabled: RawRestoreState) {
+            if was_enabled {
+                INTERRUPTS_ENABLED.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+
+    fn interrupts_enabled() -> bool {
+        INTERRUPTS_ENABLED.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn test_lock_module_nested() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        let lock = CriticalSectionLockModule::new();
+
+        lock.lock();
+        assert!(!interrupts_enabled());
+        lock.lock();
+        lock.unlock();
+
+        // the outer lock is still held
+        assert!(!interrupts_enabled());
+        lock.unlock();
+        assert!(interrupts_enabled());
+
+        // the lock can be used again afterwards
+        lock.lock();
+        assert!(!interrupts_enabled());
+        lock.unlock();
+        assert!(interrupts_enabled());
+    }
+
+    #[test]
+    fn test_lock_module_inside_guard() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        let lock = CriticalSectionLockModule::new();
+
+        {
+            let _persist_guard = CriticalSectionPersistGuard::new();
+            lock.lock();
+            lock.unlock();
+
+            // interrupts were already disabled by the guard
+            assert!(!interrupts_enabled());
+        }
+        assert!(interrupts_enabled());
+    }
+}
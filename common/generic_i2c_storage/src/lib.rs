@@ -0,0 +1,407 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Platform independent I2C FRAM and EEPROM storage module for the vNV-Heap.
+//!
+//! This module only depends on the `embedded-hal` `I2c` trait. Thus, it can be used on bare metal
+//! (e.g. with RTIC or embassy).
+
+#![no_std]
+
+use embedded_hal::i2c::{Error, ErrorKind, I2c, Operation};
+use vnv_heap::modules::persistent_storage::PersistentStorageModule;
+
+/// Describes the I2C FRAM or EEPROM chip that is connected
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct I2cChip {
+    /// Size of the memory in bytes
+    pub size: usize,
+
+    /// How many bytes are used to transmit an address (1 or 2).
+    ///
+    /// Address bits that do not fit into these bytes are transmitted in the lower bits
+    /// of the device address (e.g. for the AT24C16).
+    pub address_bytes: usize,
+
+    /// Maximum number of bytes that can be written at once.
+    /// Writes are split at page boundaries, as they would wrap around inside of the page otherwise.
+    /// If `None`, the whole memory can be written at once (e.g. for FRAM).
+    pub page_size: Option<usize>,
+
+    /// How often the device is polled after a write until it acknowledges again (write cycle of EEPROMs).
+    /// If `0`, the device is not polled (e.g. for FRAM).
+    pub max_ack_polls: usize,
+}
+
+/// Fujitsu MB85RC256V (32KB FRAM)
+pub const MB85RC256V: I2cChip = I2cChip {
+    size: 32768,
+    address_bytes: 2,
+    page_size: None,
+    max_ack_polls: 0,
+};
+
+/// Microchip AT24C02 (256B EEPROM)
+pub const AT24C02: I2cChip = I2cChip {
+    size: 256,
+    address_bytes: 1,
+    page_size: Some(8),
+    // write cycle of 5ms takes less than 1000 polls at 100kHz and 400kHz
+    max_ack_polls: 1000,
+};
+
+/// Microchip AT24C16 (2KB EEPROM)
+pub const AT24C16: I2cChip = I2cChip {
+    size: 2048,
+    address_bytes: 1,
+    page_size: Some(16),
+    max_ack_polls: 1000,
+};
+
+/// Microchip AT24C256 (32KB EEPROM)
+pub const AT24C256: I2cChip = I2cChip {
+    size: 32768,
+    address_bytes: 2,
+    page_size: Some(64),
+    max_ack_polls: 1000,
+};
+
+/// I2C FRAM or EEPROM storage module that uses an `embedded-hal` I2C bus.
+///
+/// Compatible with chips that use the common 24xx command set
+/// (e.g. Fujitsu MB85RC or Microchip AT24C series), see `I2cChip`.
+pub struct I2cStorageModule<I2C: I2c> {
+    i2c: I2C,
+    address: u8,
+    chip: I2cChip,
+}
+
+impl<I2C: I2c> I2cStorageModule<I2C> {
+    /// Creates a new storage module for the chip with the 7 bit device `address` (e.g. `0x50`).
+    ///
+    /// If address bits are transmitted in the device address (see `I2cChip::address_bytes`),
+    /// the corresponding bits of `address` have to be zero.
+    pub fn new(i2c: I2C, address: u8, chip: I2cChip) -> Self {
+        assert!(
+            chip.address_bytes == 1 || chip.address_bytes == 2,
+            "only 1 or 2 address bytes are supported"
+        );
+        assert!(chip.page_size != Some(0), "page size must not be zero");
+
+        let instance = Self { i2c, address, chip };
+        let block_bits = ((chip.size - 1) / instance.block_size()) as u8;
+        assert!(
+            address & block_bits == 0 && (address | block_bits) <= 0x7f,
+            "address bits do not fit into the device address"
+        );
+
+        instance
+    }
+
+    pub fn get_chip(&self) -> &I2cChip {
+        &self.chip
+    }
+
+    /// Returns the I2C bus
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    /// Number of bytes that can be addressed without changing the device address
+    fn block_size(&self) -> usize {
+        1 << (8 * self.chip.address_bytes)
+    }
+
+    fn device_address(&self, offset: usize) -> u8 {
+        self.address | (offset / self.block_size()) as u8
+    }
+
+    /// Builds the memory address that is sent after the device address and returns its length
+    fn header(&self, offset: usize) -> ([u8; 2], usize) {
+        let offset = offset % self.block_size();
+        if self.chip.address_bytes == 1 {
+            ([offset as u8, 0], 1)
+        } else {
+            ([(offset >> 8) as u8, offset as u8], 2)
+        }
+    }
+
+    /// Maximum number of bytes that can be read or written at once beginning at `offset`
+    fn chunk_len(&self, offset: usize, page_size: Option<usize>, remaining: usize) -> usize {
+        let block_size = self.block_size();
+        let mut len = remaining.min(block_size - offset % block_size);
+        if let Some(page_size) = page_size {
+            len = len.min(page_size - offset % page_size);
+        }
+
+        len
+    }
+
+    /// Waits until the device finished its write cycle and acknowledges again
+    fn poll_ack(&mut self, offset: usize) -> Result<(), ()> {
+        if self.chip.max_ack_polls == 0 {
+            return Ok(());
+        }
+
+        // only sets the address pointer, does not start a new write cycle
+        let (header, header_len) = self.header(offset);
+        let device_address = self.device_address(offset);
+        for _ in 0..self.chip.max_ack_polls {
+            match self.i2c.write(device_address, &header[..header_len]) {
+                Ok(()) => return Ok(()),
+                Err(err) if matches!(err.kind(), ErrorKind::NoAcknowledge(_)) => {}
+                Err(_) => return Err(()),
+            }
+        }
+
+        Err(())
+    }
+}
+
+impl<I2C: I2c> PersistentStorageModule for I2cStorageModule<I2C> {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        debug_assert!(offset + dest.len() <= self.get_max_size());
+
+        let mut done = 0;
+        while done < dest.len() {
+            let curr_offset = offset + done;
+            let len = self.chunk_len(curr_offset, None, dest.len() - done);
+
+            let (header, header_len) = self.header(curr_offset);
+            self.i2c
+                .write_read(
+                    self.device_address(curr_offset),
+                    &header[..header_len],
+                    &mut dest[done..done + len],
+                )
+                .map_err(|_| ())?;
+
+            done += len;
+        }
+
+        Ok(())
+    }
+
+    fn get_max_size(&self) -> usize {
+        self.chip.size
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        debug_assert!(offset + src.len() <= self.get_max_size());
+
+        let mut done = 0;
+        while done < src.len() {
+            let curr_offset = offset + done;
+            let len = self.chunk_len(curr_offset, self.chip.page_size, src.len() - done);
+
+            // adjacent write operations are sent without a restart in between
+            let (header, header_len) = self.header(curr_offset);
+            self.i2c
+                .transaction(
+                    self.device_address(curr_offset),
+                    &mut [
+                        Operation::Write(&header[..header_len]),
+                        Operation::Write(&src[done..done + len]),
+                    ],
+                )
+                .map_err(|_| ())?;
+
+            // written data has to be persistent as soon as write returns
+            self.poll_ack(curr_offset)?;
+
+            done += len;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use std::{vec, vec::Vec};
+
+    use embedded_hal::i2c::{self, ErrorKind, NoAcknowledgeSource, Operation};
+    use vnv_heap::modules::persistent_storage::PersistentStorageModule;
+
+    use crate::{I2cChip, I2cStorageModule, AT24C02, AT24C16, AT24C256, MB85RC256V};
+
+    const ADDRESS: u8 = 0x50;
+
+    /// How often the mock does not acknowledge after a write
+    const WRITE_CYCLE_POLLS: usize = 3;
+
+    /// Simulates an I2C FRAM or EEPROM chip
+    struct MockMemory {
+        chip: I2cChip,
+        data: Vec<u8>,
+        /// current address pointer
+        pointer: usize,
+        /// remaining transactions that are not acknowledged
+        busy: usize,
+        write_transactions: usize,
+        nacks: usize,
+    }
+
+    impl MockMemory {
+        fn new(chip: I2cChip) -> Self {
+            Self {
+                chip,
+                data: vec![0; chip.size],
+                pointer: 0,
+                busy: 0,
+                write_transactions: 0,
+                nacks: 0,
+            }
+        }
+    }
+
+    impl i2c::ErrorType for MockMemory {
+        type Error = ErrorKind;
+    }
+
+    impl i2c::I2c for MockMemory {
+        fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+            let block_size = 1 << (8 * self.chip.address_bytes);
+            let block_count = (self.chip.size + block_size - 1) / block_size;
+            if address < ADDRESS || address as usize >= ADDRESS as usize + block_count {
+                return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+            }
+            if self.busy > 0 {
+                assert!(self.chip.max_ack_polls > 0, "chip has no write cycle");
+                self.busy -= 1;
+                self.nacks += 1;
+                return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+            }
+
+            let block = (address - ADDRESS) as usize;
+            let mut written = Vec::new();
+            for op in operations {
+                match op {
+                    Operation::Write(buf) => written.extend_from_slice(buf),
+                    Operation::Read(buf) => {
+                        if written.len() >= self.chip.address_bytes {
+                            self.set_pointer(block, &written);
+                        }
+                        for byte in buf.iter_mut() {
+                            *byte = self.data[self.pointer];
+                            self.pointer = (self.pointer + 1) % self.chip.size;
+                        }
+                    }
+                }
+            }
+
+            if written.len() >= self.chip.address_bytes {
+                self.set_pointer(block, &written);
+            }
+            if written.len() > self.chip.address_bytes {
+                let page_size = self.chip.page_size.unwrap_or(self.chip.size);
+                let page_start = self.pointer - self.pointer % page_size;
+                for (i, byte) in written[self.chip.address_bytes..].iter().enumerate() {
+                    // writes wrap around inside of the page
+                    let index = page_start + (self.pointer % page_size + i) % page_size;
+                    self.data[index] = *byte;
+                }
+
+                self.write_transactions += 1;
+                if self.chip.max_ack_polls > 0 {
+                    self.busy = WRITE_CYCLE_POLLS;
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    impl MockMemory {
+        fn set_pointer(&mut self, block: usize, written: &[u8]) {
+            let offset = written[..self.chip.address_bytes]
+                .iter()
+                .fold(0, |acc, x| (acc << 8) | (*x as usize));
+            self.pointer = (block << (8 * self.chip.address_bytes)) | offset;
+        }
+    }
+
+    fn test_read_write(chip: I2cChip) {
+        let mut storage = I2cStorageModule::new(MockMemory::new(chip), ADDRESS, chip);
+        assert_eq!(storage.get_max_size(), chip.size);
+
+        let offsets = [0, 1, 7, 100, 250, chip.size / 2 - 3, chip.size - 100];
+        for (i, offset) in offsets.iter().filter(|offset| **offset + 100 <= chip.size).enumerate() {
+            let data: Vec<u8> = (0..100).map(|x| (x * 7 + i + 1) as u8).collect();
+            storage.write(*offset, &data).unwrap();
+
+            let mut read = vec![0u8; data.len()];
+            storage.read(*offset, &mut read).unwrap();
+            assert_eq!(read, data);
+        }
+
+        let mock = storage.release();
+        assert_eq!(mock.busy, 0, "write cycle has to be finished after write");
+    }
+
+    #[test]
+    fn test_read_write_fram() {
+        test_read_write(MB85RC256V);
+    }
+
+    #[test]
+    fn test_read_write_eeprom() {
+        test_read_write(AT24C02);
+        test_read_write(AT24C256);
+    }
+
+    #[test]
+    fn test_read_write_device_address_bits() {
+        // addresses above 255 are transmitted in the device address
+        test_read_write(AT24C16);
+
+        let mut storage = I2cStorageModule::new(MockMemory::new(AT24C16), ADDRESS, AT24C16);
+        storage.write(1020, &[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        let mock = storage.release();
+        assert_eq!(&mock.data[1020..1028], &[1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_page_writes() {
+        let mut storage = I2cStorageModule::new(MockMemory::new(AT24C256), ADDRESS, AT24C256);
+
+        // 10 bytes in the first page, two full pages, 20 bytes in the last page
+        storage.write(54, &[3u8; 158]).unwrap();
+        let mock = storage.release();
+        assert_eq!(mock.write_transactions, 4);
+        assert_eq!(mock.nacks, 4 * WRITE_CYCLE_POLLS);
+        assert!(mock.data[54..212].iter().all(|x| *x == 3));
+        assert!(mock.data[..54].iter().chain(mock.data[212..].iter()).all(|x| *x == 0));
+
+        // fram is written at once
+        let mut storage = I2cStorageModule::new(MockMemory::new(MB85RC256V), ADDRESS, MB85RC256V);
+        storage.write(54, &[3u8; 1000]).unwrap();
+        assert_eq!(storage.release().write_transactions, 1);
+    }
+
+    #[test]
+    fn test_ack_polling_timeout() {
+        let chip = I2cChip {
+            max_ack_polls: WRITE_CYCLE_POLLS - 1,
+            ..AT24C02
+        };
+        let mut storage = I2cStorageModule::new(MockMemory::new(chip), ADDRESS, chip);
+        assert!(storage.write(0, &[1, 2, 3]).is_err());
+    }
+}
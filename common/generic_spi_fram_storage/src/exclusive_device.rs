@@ -0,0 +1,74 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use embedded_hal::{
+    digital::OutputPin,
+    spi::{self, ErrorKind, Operation, SpiBus, SpiDevice},
+};
+
+/// `SpiDevice` that has exclusive access to an SPI bus and selects the device with a chip select pin.
+///
+/// Delays (`Operation::DelayNs`) are not supported, as no delay source is available.
+/// Transactions that contain a delay fail with `ErrorKind::Other` (operations before the delay are executed).
+pub struct ExclusiveSpiDevice<SPI: SpiBus, CS: OutputPin> {
+    spi: SPI,
+    cs: CS,
+}
+
+impl<SPI: SpiBus, CS: OutputPin> ExclusiveSpiDevice<SPI, CS> {
+    /// Creates a new device and deselects it
+    pub fn new(spi: SPI, mut cs: CS) -> Result<Self, ()> {
+        cs.set_high().map_err(|_| ())?;
+
+        Ok(Self { spi, cs })
+    }
+
+    /// Returns the SPI bus and the chip select pin
+    pub fn release(self) -> (SPI, CS) {
+        (self.spi, self.cs)
+    }
+}
+
+impl<SPI: SpiBus, CS: OutputPin> spi::ErrorType for ExclusiveSpiDevice<SPI, CS> {
+    type Error = ErrorKind;
+}
+
+impl<SPI: SpiBus, CS: OutputPin> SpiDevice for ExclusiveSpiDevice<SPI, CS> {
+    /// Executes all operations while the device is selected.
+    /// The device is always deselected afterwards, even if an operation fails.
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        self.cs.set_low().map_err(|_| ErrorKind::ChipSelectFault)?;
+
+        let res = operations
+            .iter_mut()
+            .try_for_each(|op| {
+                let res = match op {
+                    Operation::Read(buf) => self.spi.read(buf),
+                    Operation::Write(buf) => self.spi.write(buf),
+                    Operation::Transfer(read, write) => self.spi.transfer(read, write),
+                    Operation::TransferInPlace(buf) => self.spi.transfer_in_place(buf),
+                    Operation::DelayNs(_) => return Err(ErrorKind::Other),
+                };
+                res.map_err(|err| spi::Error::kind(&err))
+            })
+            .and_then(|()| self.spi.flush().map_err(|err| spi::Error::kind(&err)));
+        let cs_res = self.cs.set_high();
+
+        res?;
+        cs_res.map_err(|_| ErrorKind::ChipSelectFault)
+    }
+}
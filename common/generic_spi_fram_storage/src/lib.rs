@@ -0,0 +1,436 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Platform independent SPI FRAM storage module for the vNV-Heap.
+//!
+//! This module only depends on the `embedded-hal` traits. Thus, it can be used on bare metal
+//! (e.g. with RTIC or embassy). The `spi_fram_storage` crate wraps it for Zephyr.
+
+#![no_std]
+
+mod exclusive_device;
+
+use embedded_hal::{
+    digital::OutputPin,
+    spi::{Operation, SpiBus, SpiDevice},
+};
+use vnv_heap::modules::persistent_storage::PersistentStorageModule;
+
+pub use exclusive_device::ExclusiveSpiDevice;
+
+const MANUFACTURER_ID_CMD: u8 = 0x9f;
+const WRITE_ENABLE_CMD: u8 = 0x06;
+const READ_CMD: u8 = 0x03;
+const WRITE_CMD: u8 = 0x02;
+
+/// Describes the FRAM chip that is connected
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FramChip {
+    /// Size of the FRAM in bytes
+    pub size: usize,
+
+    /// How many bytes are used to transmit an address (2 or 3)
+    pub address_bytes: usize,
+
+    /// Expected response of the manufacturer id command.
+    /// If `None`, the id is not checked.
+    pub device_id: Option<[u8; 4]>,
+}
+
+/// Fujitsu MB85RS4MT (512KB)
+pub const MB85RS4MT: FramChip = FramChip {
+    size: 524288,
+    address_bytes: 3,
+    // in spec 0x49 is specified, but the chips we tested return 0x48
+    device_id: Some([0x04, 0x7f, 0x48, 0x03]),
+};
+
+/// Fujitsu MB85RS64V (8KB)
+pub const MB85RS64V: FramChip = FramChip {
+    size: 8192,
+    address_bytes: 2,
+    device_id: Some([0x04, 0x7f, 0x03, 0x02]),
+};
+
+/// SPI FRAM storage module on top of an `embedded-hal` `SpiDevice` (which manages the chip select).
+///
+/// Compatible with FRAM chips that use the common command set
+/// (e.g. Fujitsu MB85RS or Cypress FM25 series), see `FramChip`.
+pub struct SpiDeviceFramStorageModule<SPI: SpiDevice> {
+    spi: SPI,
+    chip: FramChip,
+}
+
+/// SPI FRAM storage module that uses an `embedded-hal` SPI bus and chip select pin.
+///
+/// Use `SpiDeviceFramStorageModule` directly if the bus is shared with other devices.
+pub type GenericSpiFramStorageModule<SPI, CS> = SpiDeviceFramStorageModule<ExclusiveSpiDevice<SPI, CS>>;
+
+impl<SPI: SpiDevice> SpiDeviceFramStorageModule<SPI> {
+    /// Creates a new storage module and checks the device id of the chip (if set in `chip`).
+    pub fn from_device(spi: SPI, chip: FramChip) -> Result<Self, ()> {
+        assert!(
+            chip.address_bytes == 2 || chip.address_bytes == 3,
+            "only 2 or 3 address bytes are supported"
+        );
+
+        let mut instance = Self { spi, chip };
+
+        if let Some(expected_id) = chip.device_id {
+            if instance.read_device_id()? != expected_id {
+                return Err(());
+            }
+        }
+
+        Ok(instance)
+    }
+
+    /// Reads the manufacturer and product id of the chip
+    pub fn read_device_id(&mut self) -> Result<[u8; 4], ()> {
+        let mut id = [0u8; 4];
+        self.spi
+            .transaction(&mut [Operation::Write(&[MANUFACTURER_ID_CMD]), Operation::Read(&mut id)])
+            .map_err(|_| ())?;
+
+        Ok(id)
+    }
+
+    pub fn get_chip(&self) -> &FramChip {
+        &self.chip
+    }
+
+    /// Returns the SPI device
+    pub fn into_device(self) -> SPI {
+        self.spi
+    }
+
+    /// Builds the command header (command and address) and returns its length
+    fn header(&self, cmd: u8, address: usize) -> ([u8; 4], usize) {
+        let mut header = [cmd, 0, 0, 0];
+        for i in 0..self.chip.address_bytes {
+            header[1 + i] = (address >> (8 * (self.chip.address_bytes - 1 - i))) as u8;
+        }
+
+        (header, 1 + self.chip.address_bytes)
+    }
+}
+
+impl<SPI: SpiBus, CS: OutputPin> GenericSpiFramStorageModule<SPI, CS> {
+    /// Creates a new storage module and checks the device id of the chip (if set in `chip`).
+    pub fn new(spi: SPI, cs: CS, chip: FramChip) -> Result<Self, ()> {
+        Self::from_device(ExclusiveSpiDevice::new(spi, cs)?, chip)
+    }
+
+    /// Returns the SPI bus and the chip select pin
+    pub fn release(self) -> (SPI, CS) {
+        self.spi.release()
+    }
+}
+
+impl<SPI: SpiDevice> PersistentStorageModule for SpiDeviceFramStorageModule<SPI> {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        debug_assert!(offset + dest.len() <= self.get_max_size());
+
+        let (header, header_len) = self.header(READ_CMD, offset);
+        self.spi
+            .transaction(&mut [Operation::Write(&header[..header_len]), Operation::Read(dest)])
+            .map_err(|_| ())
+    }
+
+    fn get_max_size(&self) -> usize {
+        self.chip.size
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        debug_assert!(offset + src.len() <= self.get_max_size());
+
+        self.spi.write(&[WRITE_ENABLE_CMD]).map_err(|_| ())?;
+
+        let (header, header_len) = self.header(WRITE_CMD, offset);
+        self.spi
+            .transaction(&mut [Operation::Write(&header[..header_len]), Operation::Write(src)])
+            .map_err(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use std::{cell::RefCell, rc::Rc, vec, vec::Vec};
+
+    use embedded_hal::{digital, spi};
+    use vnv_heap::modules::persistent_storage::PersistentStorageModule;
+
+    use crate::{
+        ExclusiveSpiDevice, FramChip, GenericSpiFramStorageModule, SpiDeviceFramStorageModule, MB85RS64V, MB85RS4MT,
+    };
+
+    /// Simulates the command set of an SPI FRAM chip
+    struct MockFram {
+        chip: FramChip,
+        data: Vec<u8>,
+        selected: bool,
+        write_enabled: bool,
+        /// bytes received since the chip was selected
+        received: Vec<u8>,
+        /// current address of a read or write command
+        address: usize,
+    }
+
+    impl MockFram {
+        fn header_len(&self) -> usize {
+            1 + self.chip.address_bytes
+        }
+
+        fn parse_address(&mut self) {
+            self.address = self.received[1..self.header_len()]
+                .iter()
+                .fold(0, |acc, x| (acc << 8) | (*x as usize));
+        }
+
+        fn on_write(&mut self, byte: u8) {
+            assert!(self.selected, "chip is not selected");
+            self.received.push(byte);
+
+            match self.received[0] {
+                WRITE_ENABLE => {
+                    assert_eq!(self.received.len(), 1);
+                    self.write_enabled = true;
+                }
+                READ => {
+                    assert!(self.received.len() <= self.header_len());
+                    if self.received.len() == self.header_len() {
+                        self.parse_address();
+                    }
+                }
+                WRITE => {
+                    if self.received.len() == self.header_len() {
+                        self.parse_address();
+                    } else if self.received.len() > self.header_len() {
+                        assert!(self.write_enabled, "write is not enabled");
+                        self.data[self.address] = byte;
+                        self.address += 1;
+                    }
+                }
+                ID => assert_eq!(self.received.len(), 1),
+                cmd => panic!("unknown command {:#x}", cmd),
+            }
+        }
+
+        fn on_read(&mut self) -> u8 {
+            assert!(self.selected, "chip is not selected");
+            match self.received[0] {
+                READ => {
+                    assert_eq!(self.received.len(), self.header_len());
+                    let res = self.data[self.address];
+                    self.address += 1;
+                    res
+                }
+                ID => {
+                    let id = self.chip.device_id.unwrap();
+                    let res = id[self.address];
+                    self.address += 1;
+                    res
+                }
+                cmd => panic!("cannot read during command {:#x}", cmd),
+            }
+        }
+    }
+
+    const WRITE_ENABLE: u8 = 0x06;
+    const READ: u8 = 0x03;
+    const WRITE: u8 = 0x02;
+    const ID: u8 = 0x9f;
+
+    struct MockSpi(Rc<RefCell<MockFram>>);
+    struct MockCs(Rc<RefCell<MockFram>>);
+
+    impl spi::ErrorType for MockSpi {
+        type Error = spi::ErrorKind;
+    }
+
+    impl spi::SpiBus for MockSpi {
+        fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+            let mut fram = self.0.borrow_mut();
+            for word in words {
+                *word = fram.on_read();
+            }
+            Ok(())
+        }
+
+        fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+            let mut fram = self.0.borrow_mut();
+            for word in words {
+                fram.on_write(*word);
+            }
+            Ok(())
+        }
+
+        fn transfer(&mut self, _read: &mut [u8], _write: &[u8]) -> Result<(), Self::Error> {
+            unimplemented!()
+        }
+
+        fn transfer_in_place(&mut self, _words: &mut [u8]) -> Result<(), Self::Error> {
+            unimplemented!()
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    impl digital::ErrorType for MockCs {
+        type Error = digital::ErrorKind;
+    }
+
+    impl digital::OutputPin for MockCs {
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            let mut fram = self.0.borrow_mut();
+            assert!(!fram.selected, "chip is already selected");
+            fram.selected = true;
+            fram.received.clear();
+            fram.address = 0;
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            let mut fram = self.0.borrow_mut();
+            // a completed write disables writes again
+            if fram.received.len() > fram.header_len() && fram.received[0] == WRITE {
+                fram.write_enabled = false;
+            }
+            fram.selected = false;
+            Ok(())
+        }
+    }
+
+    /// Device with its own chip select handling (e.g. a shared bus)
+    struct MockDevice(MockSpi, MockCs);
+
+    impl spi::ErrorType for MockDevice {
+        type Error = spi::ErrorKind;
+    }
+
+    impl spi::SpiDevice for MockDevice {
+        fn transaction(&mut self, operations: &mut [spi::Operation<'_, u8>]) -> Result<(), Self::Error> {
+            use digital::OutputPin;
+            use spi::SpiBus;
+
+            self.1.set_low().unwrap();
+            for op in operations {
+                match op {
+                    spi::Operation::Read(buf) => self.0.read(buf)?,
+                    spi::Operation::Write(buf) => self.0.write(buf)?,
+                    _ => unimplemented!(),
+                }
+            }
+            self.1.set_high().unwrap();
+            Ok(())
+        }
+    }
+
+    fn get_mock(chip: FramChip) -> (Rc<RefCell<MockFram>>, MockSpi, MockCs) {
+        let fram = Rc::new(RefCell::new(MockFram {
+            chip,
+            data: vec![0; chip.size],
+            selected: false,
+            write_enabled: false,
+            received: Vec::new(),
+            address: 0,
+        }));
+
+        (fram.clone(), MockSpi(fram.clone()), MockCs(fram))
+    }
+
+    fn test_read_write(chip: FramChip) {
+        let (fram, spi, cs) = get_mock(chip);
+        let mut storage = GenericSpiFramStorageModule::new(spi, cs, chip).unwrap();
+        assert_eq!(storage.get_max_size(), chip.size);
+
+        let offsets = [0, 1, 255, 256, 257, chip.size / 2, chip.size - 100];
+        for (i, offset) in offsets.iter().enumerate() {
+            let data: Vec<u8> = (0..100).map(|x| (x * 7 + i) as u8).collect();
+            storage.write(*offset, &data).unwrap();
+
+            assert_eq!(&fram.borrow().data[*offset..*offset + data.len()], &data[..]);
+
+            let mut read = vec![0u8; data.len()];
+            storage.read(*offset, &mut read).unwrap();
+            assert_eq!(read, data);
+        }
+
+        assert!(!fram.borrow().selected);
+    }
+
+    #[test]
+    fn test_read_write_3_byte_address() {
+        test_read_write(MB85RS4MT);
+    }
+
+    #[test]
+    fn test_read_write_2_byte_address() {
+        test_read_write(MB85RS64V);
+    }
+
+    #[test]
+    fn test_read_write_spi_device() {
+        let (fram, spi, cs) = get_mock(MB85RS64V);
+        let mut storage = SpiDeviceFramStorageModule::from_device(MockDevice(spi, cs), MB85RS64V).unwrap();
+
+        let data: Vec<u8> = (0..200).map(|x| (x * 3) as u8).collect();
+        storage.write(1000, &data).unwrap();
+        assert_eq!(&fram.borrow().data[1000..1200], &data[..]);
+
+        let mut read = vec![0u8; data.len()];
+        storage.read(1000, &mut read).unwrap();
+        assert_eq!(read, data);
+        assert!(!fram.borrow().selected);
+    }
+
+    #[test]
+    fn test_exclusive_device_delay() {
+        use spi::SpiDevice;
+
+        let (fram, spi, cs) = get_mock(MB85RS64V);
+        let mut device = ExclusiveSpiDevice::new(spi, cs).unwrap();
+
+        // delays are not supported, but the chip is deselected again
+        let res = device.transaction(&mut [spi::Operation::Write(&[ID]), spi::Operation::DelayNs(100)]);
+        assert_eq!(res, Err(spi::ErrorKind::Other));
+        assert!(!fram.borrow().selected);
+    }
+
+    #[test]
+    fn test_device_id() {
+        let (_, spi, cs) = get_mock(MB85RS64V);
+        let mut storage = GenericSpiFramStorageModule::new(spi, cs, MB85RS64V).unwrap();
+        assert_eq!(storage.read_device_id().unwrap(), MB85RS64V.device_id.unwrap());
+
+        // device responds with the id of another chip
+        let (_, spi, cs) = get_mock(MB85RS64V);
+        assert!(GenericSpiFramStorageModule::new(spi, cs, MB85RS4MT).is_err());
+
+        // id check disabled
+        let unchecked = FramChip {
+            device_id: None,
+            ..MB85RS4MT
+        };
+        let (_, spi, cs) = get_mock(MB85RS64V);
+        assert!(GenericSpiFramStorageModule::new(spi, cs, unchecked).is_ok());
+    }
+}
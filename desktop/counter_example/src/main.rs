@@ -22,7 +22,7 @@ use vnv_heap::{
         object_management::DefaultObjectManagementModule,
         persistent_storage::FilePersistentStorageModule,
     },
-    vnv_persist_all, VNVConfig, VNVHeap,
+    vnv_persist_all, VNVConfig, VNVHeap, WriteBack,
 };
 
 struct Counter {
@@ -48,6 +48,8 @@ fn main() {
     let storage = FilePersistentStorageModule::new("test.data".to_string(), 4096).unwrap();
     let config = VNVConfig {
         max_dirty_bytes: 1024,
+        write_back: WriteBack::Lazy,
+        persist_latency_budget: None,
     };
     let mut buffer = [0u8; 2048];
     let alloc_module = LinkedListAllocatorModule::new();
@@ -15,14 +15,19 @@
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{thread, time::Instant};
+use std::{
+    env, fs,
+    process::exit,
+    thread,
+    time::{Duration, Instant},
+};
 
 use vnv_heap::{
     benchmarks::{
-        run_all_benchmarks, BenchmarkRunOptions, DummyPersistTrigger,
-        RunAllBenchmarkOptions, Timer,
+        run_all_benchmarks, BenchmarkBaseline, BenchmarkComparison, BenchmarkComparisonOptions,
+        BenchmarkRunOptions, BenchmarkSuiteOptions, DummyPersistTrigger, RunAllBenchmarkOptions, Timer,
     },
-    modules::persistent_storage::FilePersistentStorageModule,
+    modules::persistent_storage::{DelaySimulationStorageModule, DelaySimulationTiming, FilePersistentStorageModule},
 };
 
 struct DesktopTimer {
@@ -55,15 +60,21 @@ fn main() {
         .format_module_path(false)
         .init();*/
 
+    let (mut comparison, storage_timing) = parse_args();
+
     // avoid stack overflow
     let builder = thread::Builder::new().stack_size(20 * 1024 * 1024);
-        let handler = builder.spawn(|| {
-            run_all_benchmarks::<DesktopTimer, DummyPersistTrigger, FilePersistentStorageModule, _>(
+        let handler = builder.spawn(move || {
+            run_all_benchmarks::<DesktopTimer, DummyPersistTrigger, DelaySimulationStorageModule<FilePersistentStorageModule>, _>(
                 BenchmarkRunOptions {
                     cold_start: 0,
+                    cold_start_buffer: &mut [],
                     machine_name: "desktop",
                     repetitions: 5,
                     result_buffer: &mut [0; 5],
+                    comparison: comparison.as_mut(),
+                    sizes: None,
+                    suites: BenchmarkSuiteOptions::new(),
                 },
                 // RunAllBenchmarkOptions::all_except_persist(),
                 RunAllBenchmarkOptions {
@@ -81,15 +92,81 @@ fn main() {
                     run_kvs_benchmarks: true,
                     run_locked_wcet_benchmarks: true,
                 },
-                get_storage,
+                || get_storage(storage_timing),
                 || 0,
             );
-        
 
+            comparison.map_or(0, |comparison| comparison.get_regression_count())
     }).unwrap();
-    handler.join().unwrap();
+
+    let regressions = handler.join().unwrap();
+    if regressions > 0 {
+        // allows to use this in scripts
+        exit(1);
+    }
 }
 
-fn get_storage() -> FilePersistentStorageModule {
-    FilePersistentStorageModule::new("test.data".into(), 512 * 1024).unwrap()
+/// Usage: `desktop_benchmark [--baseline <results.json>] [--threshold <percent>] [--min-ticks <ticks>] [--noise-factor <factor>] [--simulate-spi <clock hz>]`
+///
+/// The baseline can either be the output of a previous run or a JSON file created by `record_benchmark.py`.
+/// With `--simulate-spi`, the storage is slowed down to the speed of a MB85RS4MT FRAM module on a SPI bus with the given clock.
+fn parse_args() -> (Option<BenchmarkComparison>, DelaySimulationTiming) {
+    let mut baseline_path = None;
+    let mut storage_timing = NO_DELAY;
+    let mut options = BenchmarkComparisonOptions::default();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage_error(&format!("missing value for {}", arg)));
+        match arg.as_str() {
+            "--baseline" => baseline_path = Some(value()),
+            "--threshold" => {
+                options.relative_threshold_percent = value().parse().unwrap_or_else(|_| usage_error("invalid threshold"))
+            }
+            "--min-ticks" => {
+                options.absolute_threshold_ticks = value().parse().unwrap_or_else(|_| usage_error("invalid tick count"))
+            }
+            "--noise-factor" => {
+                options.noise_factor = value().parse().unwrap_or_else(|_| usage_error("invalid noise factor"))
+            }
+            "--simulate-spi" => {
+                let spi_clock_hz = value().parse().unwrap_or_else(|_| usage_error("invalid spi clock"));
+                if spi_clock_hz == 0 {
+                    usage_error("invalid spi clock");
+                }
+                storage_timing = DelaySimulationTiming::mb85rs4mt(spi_clock_hz, Duration::from_micros(10));
+            }
+            _ => usage_error(&format!("unknown argument {}", arg)),
+        }
+    }
+
+    let Some(baseline_path) = baseline_path else {
+        return (None, storage_timing);
+    };
+    let text = fs::read_to_string(&baseline_path)
+        .unwrap_or_else(|err| usage_error(&format!("could not read {}: {}", baseline_path, err)));
+    let baseline = BenchmarkBaseline::from_json(&text)
+        .unwrap_or_else(|_| usage_error(&format!("{} does not contain any benchmark results", baseline_path)));
+
+    println!("Comparing against {} results from {}", baseline.len(), baseline_path);
+    (Some(BenchmarkComparison::new(baseline, options)), storage_timing)
+}
+
+fn usage_error(msg: &str) -> ! {
+    eprintln!("error: {}", msg);
+    eprintln!("usage: desktop_benchmark [--baseline <results.json>] [--threshold <percent>] [--min-ticks <ticks>] [--noise-factor <factor>] [--simulate-spi <clock hz>]");
+    exit(2);
+}
+
+/// Storage timing if `--simulate-spi` is not used
+const NO_DELAY: DelaySimulationTiming = DelaySimulationTiming {
+    read_latency: Duration::ZERO,
+    write_latency: Duration::ZERO,
+    byte_time: Duration::ZERO,
+    busy_wait: false,
+};
+
+fn get_storage(timing: DelaySimulationTiming) -> DelaySimulationStorageModule<FilePersistentStorageModule> {
+    let storage = FilePersistentStorageModule::new("test.data".into(), 512 * 1024).unwrap();
+    DelaySimulationStorageModule::new(storage, timing)
 }
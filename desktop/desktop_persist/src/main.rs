@@ -29,7 +29,7 @@ use vnv_heap::{
         object_management::DefaultObjectManagementModule,
         persistent_storage::FilePersistentStorageModule,
     },
-    vnv_persist_all, VNVConfig, VNVHeap,
+    vnv_persist_all, VNVConfig, VNVHeap, WriteBack,
 };
 
 static mut PERSIST_TIMER: Option<DesktopTimer> = None;
@@ -60,6 +60,8 @@ fn main() {
     let storage = FilePersistentStorageModule::new("/tmp/vnv_desktop_persist.data".to_string(), 4096 * 4).unwrap();
     let config = VNVConfig {
         max_dirty_bytes: 1500,
+        write_back: WriteBack::Lazy,
+        persist_latency_budget: None,
     };
     let mut buffer = [0u8; 2000];
     let heap = LinkedListAllocatorModule::new();
@@ -0,0 +1,219 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    mem,
+    ptr::{null_mut, slice_from_raw_parts_mut},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use vnv_heap::{
+    modules::{
+        allocator::LinkedListAllocatorModule,
+        nonresident_allocator::NonResidentBuddyAllocatorModule,
+        object_management::DefaultObjectManagementModule,
+        persistent_storage::FilePersistentStorageModule,
+    },
+    vnv_persist_all, VNVConfig, VNVHeap, VNVLink, WriteBack,
+};
+
+type Heap<'a> = VNVHeap<
+    'a,
+    LinkedListAllocatorModule,
+    NonResidentBuddyAllocatorModule<16>,
+    DefaultObjectManagementModule,
+    FilePersistentStorageModule,
+>;
+
+const STORAGE_SIZE: usize = 4096 * 4;
+const SNAPSHOT_SIZE: usize = STORAGE_SIZE + 4096;
+
+const SAMPLE_COUNT: usize = 32;
+const ITERATIONS: u32 = 10_000;
+
+/// Every `POWER_FAILURE_INTERVAL` iterations, a power failure is simulated
+const POWER_FAILURE_INTERVAL: u32 = 997;
+
+static POWER_FAILURES: AtomicUsize = AtomicUsize::new(0);
+
+/// The last `SAMPLE_COUNT` readings of a sensor
+struct Samples {
+    values: [u32; SAMPLE_COUNT],
+}
+
+/// Summary of `Samples` that has to stay consistent with it
+struct Stats {
+    count: u32,
+    sum: u64,
+    next: usize,
+}
+
+/// Links to the objects that make up the state of the application.
+///
+/// In a real application, these are stored at a fixed location (e.g. in a settings partition)
+/// to find the objects again after a restart.
+#[derive(Clone, Copy)]
+struct Roots {
+    samples: VNVLink<Samples>,
+    stats: VNVLink<Stats>,
+}
+
+// called once power failure is imminent
+// can also be triggered from outside with `kill -USR1 <pid>`
+extern "C" fn signal_handler(_sig: libc::c_int) {
+    unsafe { vnv_persist_all() };
+}
+
+fn setup_handler() {
+    let mut new: libc::sigaction = unsafe { mem::zeroed() };
+    new.sa_sigaction = signal_handler as usize;
+
+    if unsafe { libc::sigaction(libc::SIGUSR1, &new, null_mut()) } != 0 {
+        panic!("failed to execute sigaction");
+    }
+}
+
+fn persist_handler(base_ptr: *mut u8, size: usize) {
+    // the state is persisted now, simulate that the volatile memory is lost
+    let buffer = unsafe { slice_from_raw_parts_mut(base_ptr, size).as_mut() }.unwrap();
+    buffer.fill(0);
+
+    POWER_FAILURES.fetch_add(1, Ordering::SeqCst);
+}
+
+fn config() -> VNVConfig {
+    VNVConfig {
+        max_dirty_bytes: 1024,
+        write_back: WriteBack::Lazy,
+        persist_latency_budget: None,
+    }
+}
+
+/// Deterministic sensor readings, so the state can be verified later
+fn read_sensor(i: u32) -> u32 {
+    i.wrapping_mul(2654435761) >> 16
+}
+
+/// ### Safety
+///
+/// `roots` have to point to objects of `heap`.
+unsafe fn record(heap: &Heap, roots: &Roots, value: u32) {
+    let next = heap.with_link(&roots.stats, |stats| stats.get().unwrap().next);
+    let old = heap.with_link(&roots.samples, |samples| {
+        mem::replace(&mut samples.get_mut().unwrap().values[next], value)
+    });
+    heap.with_link(&roots.stats, |stats| {
+        let mut stats = stats.get_mut().unwrap();
+        stats.count += 1;
+        stats.sum = stats.sum - old as u64 + value as u64;
+        stats.next = (next + 1) % SAMPLE_COUNT;
+    });
+}
+
+/// Checks that the state of the application matches `count` recorded samples.
+///
+/// ### Safety
+///
+/// `roots` have to point to objects of `heap`.
+unsafe fn verify(heap: &Heap, roots: &Roots, count: u32) {
+    let mut expected = [0u32; SAMPLE_COUNT];
+    for i in 0..count {
+        expected[i as usize % SAMPLE_COUNT] = read_sensor(i);
+    }
+
+    let values = heap.with_link(&roots.samples, |samples| samples.get().unwrap().values);
+    assert_eq!(values, expected, "samples do not match");
+
+    heap.with_link(&roots.stats, |stats| {
+        let stats = stats.get().unwrap();
+        assert_eq!(stats.count, count, "sample count does not match");
+        assert_eq!(stats.sum, values.iter().map(|x| *x as u64).sum::<u64>(), "sum does not match");
+        assert_eq!(stats.next, count as usize % SAMPLE_COUNT, "ring buffer position does not match");
+    });
+}
+
+fn main() {
+    setup_handler();
+
+    let mut snapshot =
+        FilePersistentStorageModule::new("/tmp/vnv_recovery_example.snapshot".to_string(), SNAPSHOT_SIZE).unwrap();
+
+    let roots = {
+        let storage =
+            FilePersistentStorageModule::new("/tmp/vnv_recovery_example.data".to_string(), STORAGE_SIZE).unwrap();
+        let mut buffer = [0u8; 2048];
+        let mut heap: Heap =
+            VNVHeap::new(&mut buffer, storage, LinkedListAllocatorModule::new(), config(), persist_handler).unwrap();
+
+        let samples = heap.allocate(Samples { values: [0; SAMPLE_COUNT] }).unwrap();
+        let stats = heap.allocate(Stats { count: 0, sum: 0, next: 0 }).unwrap();
+        let roots = Roots {
+            samples: samples.link(),
+            stats: stats.link(),
+        };
+
+        // the objects are part of the persistent state now and are only accessed with their links
+        mem::forget(samples);
+        mem::forget(stats);
+
+        for i in 0..ITERATIONS {
+            unsafe { record(&heap, &roots, read_sensor(i)) };
+
+            if i % POWER_FAILURE_INTERVAL == 0 {
+                // execution continues after the heap was restored
+                unsafe { libc::raise(libc::SIGUSR1) };
+                unsafe { verify(&heap, &roots, i + 1) };
+            }
+        }
+        println!(
+            "recorded {} samples with {} power failures in between",
+            ITERATIONS,
+            POWER_FAILURES.load(Ordering::SeqCst)
+        );
+
+        // keep a copy of the whole heap before shutting down
+        heap.snapshot_to(&mut snapshot).unwrap();
+        roots
+    };
+
+    // simulate a cold restart on a new device: the heap and its storage are gone, only the snapshot is left
+    let storage = FilePersistentStorageModule::new(
+        "/tmp/vnv_recovery_example_restored.data".to_string(),
+        STORAGE_SIZE,
+    )
+    .unwrap();
+    let mut buffer = [0u8; 2048];
+    let heap: Heap = VNVHeap::restore_from(
+        &mut buffer,
+        storage,
+        LinkedListAllocatorModule::new(),
+        config(),
+        persist_handler,
+        &mut snapshot,
+    )
+    .unwrap();
+
+    unsafe { verify(&heap, &roots, ITERATIONS) };
+    println!("restored state of {} samples from snapshot", ITERATIONS);
+
+    // the restored heap can be used as before
+    for i in ITERATIONS..2 * ITERATIONS {
+        unsafe { record(&heap, &roots, read_sensor(i)) };
+    }
+    unsafe { verify(&heap, &roots, 2 * ITERATIONS) };
+    println!("recovered state is consistent");
+}
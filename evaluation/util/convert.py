@@ -76,10 +76,14 @@ def convert_data(raw_data, bench_name: str, columns: list[str], unwrapped: bool
         new["min"] = min(item["data"])
         new["max"] = max(item["data"])
 
+        # latency of the first run after the benchmark was set up (if recorded)
+        if len(item.get("cold_start_data", [])) > 0:
+            new["cold_start_first"] = item["cold_start_data"][0]
+
         conv_object(item["bench_options"], "options", new)
 
         for key in item:
-            if key != "bench_options" and key != "data":
+            if key != "bench_options" and key != "data" and key != "cold_start_data":
                 new[key] = item[key]
 
         if unwrapped:
@@ -115,6 +119,8 @@ def scale_data(data, scale: Literal["ms", "us", "µs", "ns"]):
     data["mean"] = (scale_value * data["mean"]) / (data["ticks_per_ms"])
     data["min"] = (scale_value * data["min"]) / (data["ticks_per_ms"])
     data["max"] = (scale_value * data["max"]) / (data["ticks_per_ms"])
+    if "cold_start_first" in data:
+        data["cold_start_first"] = (scale_value * data["cold_start_first"]) / (data["ticks_per_ms"])
 
     return data
 
@@ -128,3 +134,7 @@ def get_storage_measurement(raw_data, max_obj_size: int = -1):
         storage_write = storage_write.loc[storage_write["options.object_size"] <= max_obj_size]
 
     return (storage_read, storage_write)
+
+def get_header_write_measurement(raw_data):
+    # header_size == 0 is the baseline without headers, batched == False the write path without staging buffer
+    return convert_data(raw_data, "persistent_storage_header_write", ["mean", "min", "max", "options.object_size", "options.object_count", "options.header_size", "options.batched", "machine_name", "cold_start", "repetitions"])
@@ -0,0 +1,365 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{cmp::min, mem::size_of};
+
+use crate::modules::persistent_storage::PersistentStorageModule;
+
+/// Number of entries the allocator journal can hold between two commits.
+///
+/// Every entry covers up to 8 bytes that were written by the non-resident allocator
+/// or changed in its state. If the journal is full, the write fails.
+pub const ALLOCATOR_JOURNAL_CAPACITY: usize = 64;
+
+const JOURNAL_ENTRY_DATA_SIZE: usize = size_of::<u64>();
+
+/// Undo entry of the allocator journal, all values are stored in little endian
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct JournalEntry {
+    offset: usize,
+    len: usize,
+
+    /// Content of `[offset, offset + len)` before it was overwritten
+    data: [u8; JOURNAL_ENTRY_DATA_SIZE],
+}
+
+impl JournalEntry {
+    const SERIALIZED_SIZE: usize = 2 * size_of::<u64>() + JOURNAL_ENTRY_DATA_SIZE;
+
+    fn to_bytes(&self) -> [u8; Self::SERIALIZED_SIZE] {
+        let mut bytes = [0u8; Self::SERIALIZED_SIZE];
+        bytes[..size_of::<u64>()].copy_from_slice(&(self.offset as u64).to_le_bytes());
+        bytes[size_of::<u64>()..2 * size_of::<u64>()].copy_from_slice(&(self.len as u64).to_le_bytes());
+        bytes[2 * size_of::<u64>()..].copy_from_slice(&self.data);
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8; Self::SERIALIZED_SIZE]) -> JournalEntry {
+        let offset = u64::from_le_bytes(bytes[..size_of::<u64>()].try_into().unwrap());
+        let len = u64::from_le_bytes(bytes[size_of::<u64>()..2 * size_of::<u64>()].try_into().unwrap());
+        JournalEntry {
+            offset: offset as usize,
+            len: len as usize,
+            data: bytes[2 * size_of::<u64>()..].try_into().unwrap(),
+        }
+    }
+}
+
+/// Undo journal of the non-resident allocator in the recovery area (see `allocator_journal` feature).
+///
+/// Before the allocator overwrites a location in storage, its previous content is appended to the journal.
+/// Committing clears the journal, so a power failure in between two commits is reverted with `rollback`.
+/// The journal consists of the number of entries as `u64`, followed by `ALLOCATOR_JOURNAL_CAPACITY` entries.
+///
+/// The number of entries is only kept in storage, so no RAM is needed for the journal.
+///
+/// **Note**: Writes of a single `u64` have to be atomic and storage modules that defer writes
+/// have to keep their order, otherwise a power failure can still corrupt the allocator.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AllocatorJournal {
+    offset: usize,
+}
+
+impl AllocatorJournal {
+    /// Size of the journal in storage
+    pub(crate) const SIZE: usize = size_of::<u64>() + ALLOCATOR_JOURNAL_CAPACITY * JournalEntry::SERIALIZED_SIZE;
+
+    pub(crate) const fn new(offset: usize) -> Self {
+        Self { offset }
+    }
+
+    const fn get_entry_offset(&self, index: usize) -> usize {
+        self.offset + size_of::<u64>() + index * JournalEntry::SERIALIZED_SIZE
+    }
+
+    fn read_len<S: PersistentStorageModule>(&self, storage: &mut S) -> Result<usize, ()> {
+        let mut bytes = [0u8; size_of::<u64>()];
+        storage.read(self.offset, &mut bytes)?;
+        usize::try_from(u64::from_le_bytes(bytes)).map_err(|_| ())
+    }
+
+    fn write_len<S: PersistentStorageModule>(&self, storage: &mut S, len: usize) -> Result<(), ()> {
+        storage.write(self.offset, &(len as u64).to_le_bytes())
+    }
+
+    /// Clears the journal of a new heap
+    pub(crate) fn init<S: PersistentStorageModule>(&self, storage: &mut S) -> Result<(), ()> {
+        self.write_len(storage, 0)
+    }
+
+    /// Appends the current content of `[offset, offset + len)` to the journal
+    fn append<S: PersistentStorageModule>(&self, storage: &mut S, offset: usize, len: usize) -> Result<(), ()> {
+        let index = self.read_len(storage)?;
+        if index >= ALLOCATOR_JOURNAL_CAPACITY {
+            return Err(());
+        }
+
+        let mut entry = JournalEntry {
+            offset,
+            len,
+            data: [0u8; JOURNAL_ENTRY_DATA_SIZE],
+        };
+        storage.read(offset, &mut entry.data[..len])?;
+
+        // the entry has to be complete before it is counted
+        storage.write(self.get_entry_offset(index), &entry.to_bytes())?;
+        self.write_len(storage, index + 1)
+    }
+
+    /// Writes `src` to `offset` after its previous content was journaled
+    pub(crate) fn write<S: PersistentStorageModule>(&self, storage: &mut S, offset: usize, src: &[u8]) -> Result<(), ()> {
+        let mut done = 0;
+        while done < src.len() {
+            let len = min(JOURNAL_ENTRY_DATA_SIZE, src.len() - done);
+            self.append(storage, offset + done, len)?;
+            storage.write(offset + done, &src[done..done + len])?;
+            done += len;
+        }
+        Ok(())
+    }
+
+    /// Like `write`, but only the chunks of `src` that differ from the storage content are journaled and written
+    pub(crate) fn write_changes<S: PersistentStorageModule>(&self, storage: &mut S, offset: usize, src: &[u8]) -> Result<(), ()> {
+        let mut current = [0u8; JOURNAL_ENTRY_DATA_SIZE];
+        for (i, chunk) in src.chunks(JOURNAL_ENTRY_DATA_SIZE).enumerate() {
+            let chunk_offset = offset + i * JOURNAL_ENTRY_DATA_SIZE;
+            storage.read(chunk_offset, &mut current[..chunk.len()])?;
+            if current[..chunk.len()] != *chunk {
+                self.write(storage, chunk_offset, chunk)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes all writes since the last commit permanent
+    pub(crate) fn commit<S: PersistentStorageModule>(&self, storage: &mut S) -> Result<(), ()> {
+        if self.read_len(storage)? == 0 {
+            return Ok(());
+        }
+        self.write_len(storage, 0)
+    }
+
+    /// Reverts all writes since the last commit in reverse order.
+    ///
+    /// If this is interrupted, calling it again has the same result.
+    pub(crate) fn rollback<S: PersistentStorageModule>(&self, storage: &mut S) -> Result<(), ()> {
+        let len = self.read_len(storage)?;
+        if len > ALLOCATOR_JOURNAL_CAPACITY {
+            return Err(());
+        }
+
+        for index in (0..len).rev() {
+            let mut bytes = [0u8; JournalEntry::SERIALIZED_SIZE];
+            storage.read(self.get_entry_offset(index), &mut bytes)?;
+            let entry = JournalEntry::from_bytes(&bytes);
+            if entry.len > JOURNAL_ENTRY_DATA_SIZE {
+                return Err(());
+            }
+            storage.write(entry.offset, &entry.data[..entry.len])?;
+        }
+
+        self.write_len(storage, 0)
+    }
+}
+
+/// Storage that journals every write with an `AllocatorJournal`, which is passed to the non-resident allocator
+pub(crate) struct JournaledStorage<'s, S: PersistentStorageModule> {
+    storage: &'s mut S,
+    journal: AllocatorJournal,
+}
+
+impl<'s, S: PersistentStorageModule> JournaledStorage<'s, S> {
+    pub(crate) fn new(storage: &'s mut S, journal: AllocatorJournal) -> Self {
+        Self { storage, journal }
+    }
+}
+
+impl<S: PersistentStorageModule> PersistentStorageModule for JournaledStorage<'_, S> {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        self.storage.read(offset, dest)
+    }
+
+    fn get_max_size(&self) -> usize {
+        self.storage.get_max_size()
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        self.journal.write(self.storage, offset, src)
+    }
+
+    fn forget_region(&mut self, offset: usize, size: usize) {
+        self.storage.forget_region(offset, size)
+    }
+
+    fn flush(&mut self) -> Result<(), ()> {
+        self.storage.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::{alloc::Layout, mem::size_of, slice};
+
+    use crate::modules::{
+        nonresident_allocator::{NonResidentAllocatorModule, NonResidentBuddyAllocatorModule},
+        persistent_storage::{test::get_test_storage, FilePersistentStorageModule, PersistentStorageModule},
+    };
+
+    use super::{AllocatorJournal, JournaledStorage};
+
+    type Allocator = NonResidentBuddyAllocatorModule<16>;
+
+    const HEAP_SIZE: usize = 2048;
+    const STATE_OFFSET: usize = HEAP_SIZE;
+    const JOURNAL_OFFSET: usize = STATE_OFFSET + size_of::<Allocator>();
+
+    /// Storage that loses power after `writes_left` writes
+    struct PowerFailureStorage<'a> {
+        inner: &'a mut FilePersistentStorageModule,
+        writes_left: usize,
+    }
+
+    impl PersistentStorageModule for PowerFailureStorage<'_> {
+        fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+            self.inner.read(offset, dest)
+        }
+
+        fn get_max_size(&self) -> usize {
+            self.inner.get_max_size()
+        }
+
+        fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+            if self.writes_left == 0 {
+                return Err(());
+            }
+            self.writes_left -= 1;
+            self.inner.write(offset, src)
+        }
+    }
+
+    fn state_bytes(allocator: &Allocator) -> &[u8] {
+        unsafe { slice::from_raw_parts(allocator as *const Allocator as *const u8, size_of::<Allocator>()) }
+    }
+
+    fn sync<S: PersistentStorageModule>(allocator: &Allocator, journal: AllocatorJournal, storage: &mut S) -> Result<(), ()> {
+        journal.write_changes(storage, STATE_OFFSET, state_bytes(allocator))?;
+        journal.commit(storage)
+    }
+
+    /// Reads the allocator state like `VNVHeap::recover` does
+    fn recover(storage: &mut FilePersistentStorageModule) -> Allocator {
+        AllocatorJournal::new(JOURNAL_OFFSET).rollback(storage).unwrap();
+
+        let mut allocator = Allocator::new();
+        storage
+            .read(STATE_OFFSET, unsafe {
+                slice::from_raw_parts_mut(&mut allocator as *mut Allocator as *mut u8, size_of::<Allocator>())
+            })
+            .unwrap();
+        allocator
+    }
+
+    /// Returns all free blocks of the allocator
+    fn free_blocks(allocator: &Allocator, storage: &mut FilePersistentStorageModule) -> Vec<(usize, usize)> {
+        let mut blocks = vec![];
+        for (class, list) in allocator.get_free_list().iter().enumerate() {
+            let mut iter = list.iter();
+            while let Some(item) = iter.next(storage).unwrap() {
+                blocks.push((class, item.get_base_offset()));
+            }
+        }
+        blocks.sort();
+        blocks
+    }
+
+    #[test]
+    fn test_allocator_journal_power_failure() {
+        let layout = Layout::new::<[u8; 24]>();
+        let mut write_count = 1;
+        let mut completed = false;
+
+        // cut the power after every possible write of the allocation and the sync
+        while !completed {
+            let mut storage = get_test_storage("test_allocator_journal_power_failure", JOURNAL_OFFSET + AllocatorJournal::SIZE);
+            let journal = AllocatorJournal::new(JOURNAL_OFFSET);
+            let mut allocator = Allocator::new();
+            allocator.init(0, HEAP_SIZE, &mut storage).unwrap();
+            journal.init(&mut storage).unwrap();
+            storage.write(STATE_OFFSET, state_bytes(&allocator)).unwrap();
+
+            let offsets: Vec<usize> = (0..6)
+                .map(|_| allocator.allocate(layout, &mut JournaledStorage::new(&mut storage, journal)).unwrap())
+                .collect();
+            for offset in offsets.iter().step_by(2) {
+                allocator.deallocate(*offset, layout, &mut JournaledStorage::new(&mut storage, journal)).unwrap();
+            }
+            sync(&allocator, journal, &mut storage).unwrap();
+            let committed = free_blocks(&allocator, &mut storage);
+
+            let mut failing = PowerFailureStorage {
+                inner: &mut storage,
+                writes_left: write_count,
+            };
+            let res = allocator
+                .deallocate(offsets[1], layout, &mut JournaledStorage::new(&mut failing, journal))
+                .and_then(|()| sync(&allocator, journal, &mut failing));
+            completed = res.is_ok();
+
+            let recovered = recover(&mut storage);
+            if completed {
+                assert_eq!(free_blocks(&recovered, &mut storage), free_blocks(&allocator, &mut storage));
+                assert_ne!(free_blocks(&recovered, &mut storage), committed);
+            } else {
+                assert_eq!(free_blocks(&recovered, &mut storage), committed, "power failure after {} writes", write_count);
+            }
+
+            write_count += 1;
+        }
+    }
+
+    #[test]
+    fn test_allocator_journal_rollback() {
+        let mut storage = get_test_storage("test_allocator_journal_rollback", 1024);
+        let journal = AllocatorJournal::new(512);
+        journal.init(&mut storage).unwrap();
+
+        storage.write(0, &[1u8; 20]).unwrap();
+        journal.write(&mut storage, 4, &[2u8; 12]).unwrap();
+        journal.write(&mut storage, 8, &[3u8; 4]).unwrap();
+
+        let mut data = [0u8; 20];
+        storage.read(0, &mut data).unwrap();
+        assert_eq!(data, [1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 2, 2, 2, 2, 1, 1, 1, 1]);
+
+        // unchanged chunks are not journaled
+        journal.write_changes(&mut storage, 0, &[1, 1, 1, 1, 2, 2, 2, 2, 9, 9, 9, 9, 2, 2, 2, 2, 1, 1, 1, 1]).unwrap();
+        assert_eq!(journal.read_len(&mut storage), Ok(4));
+
+        // rolling back twice has the same result
+        AllocatorJournal::new(512).rollback(&mut storage).unwrap();
+        AllocatorJournal::new(512).rollback(&mut storage).unwrap();
+        storage.read(0, &mut data).unwrap();
+        assert_eq!(data, [1u8; 20]);
+
+        // committed writes are kept
+        journal.write(&mut storage, 0, &[5u8; 2]).unwrap();
+        journal.commit(&mut storage).unwrap();
+        AllocatorJournal::new(512).rollback(&mut storage).unwrap();
+        storage.read(0, &mut data[..4]).unwrap();
+        assert_eq!(data[..4], [5, 5, 1, 1]);
+    }
+}
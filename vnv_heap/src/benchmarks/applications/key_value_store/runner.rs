@@ -28,7 +28,7 @@ use crate::{
             vnv_heap::VNVHeapKeyValueStoreImplementation,
         },
         common::multi_page::multi_page_calc_base_metadata_size,
-    }, modules::object_management::DefaultObjectManagementModule, util::div_ceil, VNVConfig
+    }, modules::object_management::{DefaultObjectManagementModule, ObjectManagementModule, PrefetchingObjectManagementModule}, util::div_ceil, VNVConfig, WriteBack
 };
 
 use super::{super::super::*, calc_object_count_kvs_application, AccessType, KVS_APP_DIVERSE_OBJ_LEN_OBJ_SIZES};
@@ -61,6 +61,18 @@ fn get_access_types() -> [AccessType; 4] {
 
 const PAGE_SIZES: [usize; 5] = [32, 64, 128, 256, 512];
 
+/// only a part of the objects fits into the resident buffer, so that objects are loaded again and again
+const PREFETCH_BUF_SIZE: usize = RAM_SIZE / 4;
+
+/// remembers one transition for each object
+type P = PrefetchingObjectManagementModule<M, OBJ_CNT>;
+
+/// access patterns that may profit from prefetching
+fn get_prefetch_access_types() -> [AccessType; 2] {
+    let [_, sequential, partitioned, _] = get_access_types();
+    [sequential, partitioned]
+}
+
 pub(crate) struct KVSBenchmarkRunner;
 
 impl BenchmarkRunner for KVSBenchmarkRunner {
@@ -73,6 +85,9 @@ impl BenchmarkRunner for KVSBenchmarkRunner {
 
             // ### vNV-Heap ###
             iteration_count += access_types.len();
+
+            // ### vNV-Heap with and without prefetching ###
+            iteration_count += 2 * get_prefetch_access_types().len();
         }
 
         iteration_count
@@ -203,6 +218,8 @@ impl BenchmarkRunner for KVSBenchmarkRunner {
                 ) -> VNVHeap<'a, A, N, M, S2> {
                     let config = VNVConfig {
                         max_dirty_bytes: max_dirty,
+                        write_back: WriteBack::Lazy,
+                        persist_latency_budget: None,
                     };
 
                     let heap: VNVHeap<A, N, M, S2> = VNVHeap::new(
@@ -244,10 +261,62 @@ impl BenchmarkRunner for KVSBenchmarkRunner {
                 
                 }
             }
+
+            {
+                // ###### vNV-Heap with and without prefetching ######
+
+                // the dirty size is the same for both, the size of the prediction table is part of the heap size
+                let vnv_heap_max_dirty = max_dirty - size_of::<VNVHeap<A, N, M, S>>();
+
+                let mut buf = [0u8; PREFETCH_BUF_SIZE];
+                for access_type in get_prefetch_access_types() {
+                    handle_curr_iteration();
+                    run_prefetch_bench::<M, TIMER, S>(run_options, &mut buf, vnv_heap_max_dirty, get_storage(), access_type.clone(), false);
+
+                    handle_curr_iteration();
+                    run_prefetch_bench::<P, TIMER, S>(run_options, &mut buf, vnv_heap_max_dirty, get_storage(), access_type, true);
+                }
+            }
         }
     }
 }
 
+fn run_prefetch_bench<M2: ObjectManagementModule, TIMER: Timer, S: PersistentStorageModule + 'static>(
+    run_options: &mut BenchmarkRunOptions,
+    buf: &mut [u8],
+    max_dirty: usize,
+    storage: S,
+    access_type: AccessType,
+    prefetching: bool,
+) {
+    let config = VNVConfig {
+        max_dirty_bytes: max_dirty,
+        write_back: WriteBack::Lazy,
+        persist_latency_budget: None,
+    };
+    let buffer_size = buf.len();
+
+    let heap: VNVHeap<A, N, M2, S> =
+        VNVHeap::new(buf, storage, LinkedListAllocatorModule::new(), config, |_, _| {}).unwrap();
+    let kvs_impl = VNVHeapKeyValueStoreImplementation::new_with_unloading(heap);
+    let bench = KeyValueStoreBenchmark::new(
+        kvs_impl,
+        "kvs_prefetch",
+        VNVHeapKeyValueStoreBenchmarkGeneralOptions {
+            iterations: ITERATION_COUNT,
+            object_count: OBJ_CNT,
+            access_type,
+            kvs_options: PrefetchKVSOptions {
+                max_dirty,
+                buffer_size,
+                heap_size: size_of::<VNVHeap<A, N, M2, S>>(),
+                prefetching,
+            },
+        },
+    );
+    bench.run_benchmark::<TIMER>(run_options);
+}
+
 #[derive(Serialize, Clone)]
 struct PagedKVSOptions {
     page_cnt: usize,
@@ -260,3 +329,11 @@ struct PagedKVSOptions {
 struct VNVHeapKVSOptions {
     max_dirty: usize,
 }
+
+#[derive(Serialize, Clone)]
+struct PrefetchKVSOptions {
+    max_dirty: usize,
+    buffer_size: usize,
+    heap_size: usize,
+    prefetching: bool,
+}
@@ -33,6 +33,8 @@ pub(super) struct VNVHeapKeyValueStoreImplementation<
     S: PersistentStorageModule + 'static,
 > {
     manager: VNVHeap<'a, A, N, M, S>,
+    /// if `false`, the resident buffer is smaller than the data and objects may be unloaded
+    all_resident: bool,
 }
 
 impl<
@@ -44,7 +46,12 @@ impl<
     > VNVHeapKeyValueStoreImplementation<'a, A, N, M, S>
 {
     pub(super) fn new(heap: VNVHeap<'a, A, N, M, S>) -> Self {
-        Self { manager: heap }
+        Self { manager: heap, all_resident: true }
+    }
+
+    /// Same as `new`, but the resident buffer does not have to fit all objects
+    pub(super) fn new_with_unloading(heap: VNVHeap<'a, A, N, M, S>) -> Self {
+        Self { manager: heap, all_resident: false }
     }
 }
 
@@ -64,18 +71,18 @@ impl<
 {
     fn allocate<T>(&self, data: T) -> Result<InternalPointer, ()> {
         let mut inner = self.manager.get_inner().borrow_mut();
-        let identifier = unsafe { inner.allocate(data, false)? };
+        let identifier = unsafe { inner.allocate(data, false).map_err(|_| ())? };
 
         debug_assert!(inner.is_resident(&identifier));
 
-        inner.flush_object::<T>(&identifier)?;
+        inner.flush_object::<T>(&identifier).map_err(|_| ())?;
         Ok(identifier.offset)
     }
 
     fn deallocate<T>(&self, ptr: &InternalPointer) {
         let mut inner = self.manager.get_inner().borrow_mut();
         let identifier = pointer_to_identifier::<T>(*ptr);
-        debug_assert!(inner.is_resident(&identifier));
+        debug_assert!(!self.all_resident || inner.is_resident(&identifier));
 
         unsafe {
             inner.deallocate(&identifier, false).unwrap();
@@ -85,10 +92,10 @@ impl<
     fn get<T: Copy>(&mut self, ptr: &InternalPointer) -> Result<T, ()> {
         let mut inner = self.manager.get_inner().borrow_mut();
         let identifier = pointer_to_identifier::<T>(*ptr);
-        debug_assert!(inner.is_resident(&identifier));
+        debug_assert!(!self.all_resident || inner.is_resident(&identifier));
 
         unsafe {
-            let data = inner.get_ref(&identifier, false)?;
+            let data = inner.get_ref(&identifier, false).map_err(|_| ())?;
             let copy = data.as_ref().unwrap().clone();
             inner.release_ref(&identifier);
             Ok(copy)
@@ -98,10 +105,10 @@ impl<
     fn update<T>(&mut self, ptr: &InternalPointer, data: T) -> Result<(), ()> {
         let mut inner = self.manager.get_inner().borrow_mut();
         let identifier = pointer_to_identifier::<T>(*ptr);
-        debug_assert!(inner.is_resident(&identifier));
+        debug_assert!(!self.all_resident || inner.is_resident(&identifier));
 
         unsafe {
-            let data_ptr = inner.get_mut(&identifier, false)?;
+            let data_ptr = inner.get_mut(&identifier, false).map_err(|_| ())?;
             *data_ptr = data;
             inner.release_mut(&identifier);
         }
@@ -113,6 +120,7 @@ impl<
         let mut inner = self.manager.get_inner().borrow_mut();
         let identifier = pointer_to_identifier::<T>(*ptr);
 
-        inner.flush_object::<T>(&identifier)
+        inner.flush_object::<T>(&identifier).map_err(|_| ())?;
+        Ok(())
     }
 }
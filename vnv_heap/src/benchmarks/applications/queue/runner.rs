@@ -33,7 +33,7 @@ use crate::{
         persistent_storage::{DummyStorageModule, TruncatedStorageModule},
     },
     vnv_list::{ListItemContainer, VNVList},
-    VNVConfig,
+    VNVConfig, WriteBack,
 };
 
 use super::super::super::*;
@@ -54,7 +54,7 @@ const RAM_LIMIT: usize = 4 * 1024;
 
 const VNV_HEAP_RAM_OVERHEAD: usize = {
     size_of::<VNVList<'_, '_, [u8; OBJ_SIZE], A, N, M>>()
-        + size_of::<VNVHeap<'_, A, N, M, DummyStorageModule>>()
+        + size_of::<VNVHeap<'_, A, N, M, DummyStorageModule>>() - crate::benchmarks::common::FEATURE_HEAP_SIZE
         + VNVHeap::<'_, A, N, M, DummyStorageModule>::get_layout_info().persist_access_point_size
 };
 const VNV_HEAP_BUF_SIZE: usize = RAM_LIMIT - VNV_HEAP_RAM_OVERHEAD;
@@ -153,6 +153,8 @@ impl BenchmarkRunner for QueueBenchmarkRunner {
                 ) -> VNVHeap<'a, A, N, M, S2> {
                     let config = VNVConfig {
                         max_dirty_bytes: max_dirty,
+                        write_back: WriteBack::Lazy,
+                        persist_latency_budget: None,
                     };
 
                     let heap: VNVHeap<A, N, M, S2> = VNVHeap::new(
@@ -18,5 +18,19 @@
 pub(super) mod single_page;
 pub(super) mod multi_page;
 
+/// Additional size of the resident buffer cutoff (see `calc_resident_buf_cutoff_size`) that depends on enabled features
+pub(crate) const FEATURE_CUTOFF_SIZE: usize = crate::vnv_heap::calc_resident_buf_unpersisted_size();
+
+/// Size of the heap that is only used for debugging statistics (see `VNVHeap::stats_iter`).
+/// It is not counted as RAM overhead of the heap, so that results with and without these features can be compared.
+pub(crate) const FEATURE_HEAP_SIZE: usize = {
+    #[cfg(feature = "object_stats")]
+    let size = core::mem::size_of::<crate::resident_object_manager::object_stats::ObjectStatsTable>();
+    #[cfg(not(feature = "object_stats"))]
+    let size = 0;
+
+    size
+};
+
 use super::*;
 
@@ -0,0 +1,366 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use core::mem::size_of;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::modules::persistent_storage::{
+    persistent_storage_util::{read_storage_data, write_storage_data},
+    PersistentStorageModule,
+};
+
+/// Prefix of the lines that contain the results of a benchmark
+const BENCH_INFO_PREFIX: &str = "[BENCH-INFO] ";
+
+/// A single benchmark result of a previous run.
+///
+/// This matches the output of `BenchmarkRunInfo` (other fields are ignored).
+#[derive(Deserialize)]
+struct BaselineEntry {
+    bench_name: String,
+    bench_options: Value,
+    ticks_per_ms: u32,
+    data: Vec<u32>,
+}
+
+/// Results of a previous benchmark run that new results can be compared to.
+pub struct BenchmarkBaseline {
+    entries: Vec<BaselineEntry>,
+}
+
+impl BenchmarkBaseline {
+    /// Parses a previous result set.
+    ///
+    /// Supports both the JSON array that is created by the `record_benchmark.py` scripts
+    /// and the raw output of a benchmark run (lines starting with `[BENCH-INFO]`).
+    pub fn from_json(text: &str) -> Result<Self, ()> {
+        if let Ok(entries) = serde_json::from_str::<Vec<BaselineEntry>>(text) {
+            return Ok(Self { entries });
+        }
+
+        let mut entries = vec![];
+        for line in text.lines() {
+            if let Some(json) = line.trim().strip_prefix(BENCH_INFO_PREFIX) {
+                entries.push(serde_json::from_str(json).map_err(|_| ())?);
+            }
+        }
+
+        if entries.is_empty() {
+            return Err(());
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Reads a result set that was previously stored with `write_to_storage`.
+    ///
+    /// This can be used on devices that do not have a file system.
+    pub fn from_storage<S: PersistentStorageModule>(
+        storage: &mut S,
+        offset: usize,
+    ) -> Result<Self, ()> {
+        let len: usize = unsafe { read_storage_data(storage, offset)? };
+        let end = (offset + size_of::<usize>()).checked_add(len).ok_or(())?;
+        if end > storage.get_max_size() {
+            return Err(());
+        }
+
+        let mut buf = vec![0u8; len];
+        storage.read(offset + size_of::<usize>(), &mut buf)?;
+
+        let text = core::str::from_utf8(&buf).map_err(|_| ())?;
+        Self::from_json(text)
+    }
+
+    /// Stores a result set (see `from_json` for supported formats) so it can be loaded with `from_storage` later on.
+    pub fn write_to_storage<S: PersistentStorageModule>(
+        text: &str,
+        storage: &mut S,
+        offset: usize,
+    ) -> Result<(), ()> {
+        if offset + size_of::<usize>() + text.len() > storage.get_max_size() {
+            return Err(());
+        }
+
+        write_storage_data(storage, offset, &text.len())?;
+        storage.write(offset + size_of::<usize>(), text.as_bytes())
+    }
+
+    /// Number of benchmark results in this baseline
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn find(&self, bench_name: &str, bench_options: &Value) -> Option<&BaselineEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.bench_name == bench_name && &entry.bench_options == bench_options)
+    }
+}
+
+pub struct BenchmarkComparisonOptions {
+    /// Minimum change of the mean latency in percent to be reported as regression/improvement
+    pub relative_threshold_percent: f64,
+
+    /// Minimum change of the mean latency in ticks to be reported as regression/improvement.
+    ///
+    /// This filters out changes that are caused by the resolution of the timer.
+    pub absolute_threshold_ticks: f64,
+
+    /// The change of the mean latency also has to be larger than
+    /// `noise_factor` times the standard error of the difference of both means
+    pub noise_factor: f64,
+}
+
+impl Default for BenchmarkComparisonOptions {
+    fn default() -> Self {
+        Self {
+            relative_threshold_percent: 5.0,
+            absolute_threshold_ticks: 1.0,
+            noise_factor: 2.0,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BenchmarkVerdict {
+    Regression,
+    Improvement,
+    Unchanged,
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct BenchmarkDelta {
+    /// Mean latency of the baseline, converted to the ticks of the current run
+    pub baseline_mean: f64,
+    pub current_mean: f64,
+    pub relative_change_percent: f64,
+    pub verdict: BenchmarkVerdict,
+}
+
+/// Compares the results of the current run to a baseline.
+///
+/// Pass this to `BenchmarkRunOptions::comparison` to print the delta of each benchmark after it finished.
+pub struct BenchmarkComparison {
+    baseline: BenchmarkBaseline,
+    options: BenchmarkComparisonOptions,
+    regressions: usize,
+    improvements: usize,
+    unchanged: usize,
+    missing: usize,
+}
+
+impl BenchmarkComparison {
+    pub fn new(baseline: BenchmarkBaseline, options: BenchmarkComparisonOptions) -> Self {
+        Self {
+            baseline,
+            options,
+            regressions: 0,
+            improvements: 0,
+            unchanged: 0,
+            missing: 0,
+        }
+    }
+
+    pub fn get_regression_count(&self) -> usize {
+        self.regressions
+    }
+
+    pub fn get_improvement_count(&self) -> usize {
+        self.improvements
+    }
+
+    pub fn get_unchanged_count(&self) -> usize {
+        self.unchanged
+    }
+
+    /// Number of benchmarks that are not part of the baseline
+    pub fn get_missing_count(&self) -> usize {
+        self.missing
+    }
+
+    /// Compares the results of a benchmark to the baseline.
+    ///
+    /// Returns `None` if the baseline does not contain this benchmark (with the same options).
+    pub fn compare<O: Serialize>(
+        &mut self,
+        bench_name: &str,
+        bench_options: &O,
+        ticks_per_ms: u32,
+        data: &[u32],
+    ) -> Option<BenchmarkDelta> {
+        let bench_options = serde_json::to_value(bench_options).ok()?;
+        let entry = match self.baseline.find(bench_name, &bench_options) {
+            Some(entry) if !entry.data.is_empty() && !data.is_empty() => entry,
+            _ => {
+                self.missing += 1;
+                return None;
+            }
+        };
+
+        // baseline could have been recorded with a timer of a different resolution
+        let scale = ticks_per_ms as f64 / entry.ticks_per_ms as f64;
+        let (baseline_mean, baseline_var) = mean_and_variance(entry.data.iter().map(|x| *x as f64 * scale));
+        let (current_mean, current_var) = mean_and_variance(data.iter().map(|x| *x as f64));
+
+        let diff = current_mean - baseline_mean;
+        let relative_change_percent = if baseline_mean == 0.0 {
+            if diff == 0.0 { 0.0 } else { f64::INFINITY.copysign(diff) }
+        } else {
+            100.0 * diff / baseline_mean
+        };
+
+        let std_error = (baseline_var / entry.data.len() as f64 + current_var / data.len() as f64).sqrt();
+        let significant = relative_change_percent.abs() >= self.options.relative_threshold_percent
+            && diff.abs() > self.options.absolute_threshold_ticks
+            && diff.abs() > self.options.noise_factor * std_error;
+
+        let verdict = if !significant {
+            self.unchanged += 1;
+            BenchmarkVerdict::Unchanged
+        } else if diff > 0.0 {
+            self.regressions += 1;
+            BenchmarkVerdict::Regression
+        } else {
+            self.improvements += 1;
+            BenchmarkVerdict::Improvement
+        };
+
+        Some(BenchmarkDelta {
+            baseline_mean,
+            current_mean,
+            relative_change_percent,
+            verdict,
+        })
+    }
+
+    /// Compares the results of a benchmark and prints the delta
+    pub(super) fn compare_and_print<O: Serialize>(
+        &mut self,
+        bench_name: &str,
+        bench_options: &O,
+        ticks_per_ms: u32,
+        data: &[u32],
+    ) {
+        match self.compare(bench_name, bench_options, ticks_per_ms, data) {
+            Some(delta) => println!(
+                "-> Compared to baseline: mean {:.1} -> {:.1} ({:+.1}%) {:?}",
+                delta.baseline_mean, delta.current_mean, delta.relative_change_percent, delta.verdict
+            ),
+            None => println!("-> Compared to baseline: no matching baseline result"),
+        }
+    }
+
+    pub(super) fn print_summary(&self) {
+        println!(
+            "[BENCH-COMPARISON] regressions: {}, improvements: {}, unchanged: {}, without baseline: {}",
+            self.regressions, self.improvements, self.unchanged, self.missing
+        );
+    }
+}
+
+fn mean_and_variance<I: Iterator<Item = f64> + Clone>(values: I) -> (f64, f64) {
+    let count = values.clone().count() as f64;
+    let mean = values.clone().sum::<f64>() / count;
+    if count < 2.0 {
+        return (mean, 0.0);
+    }
+
+    let variance = values.map(|x| (x - mean) * (x - mean)).sum::<f64>() / (count - 1.0);
+    (mean, variance)
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Serialize;
+
+    use super::{BenchmarkBaseline, BenchmarkComparison, BenchmarkComparisonOptions, BenchmarkVerdict};
+    use crate::modules::persistent_storage::test::get_test_storage;
+
+    #[derive(Serialize)]
+    struct TestOptions {
+        object_size: usize,
+    }
+
+    const BASELINE: &str = r#"[
+        {"bench_name":"get_min","bench_options":{"object_size":8},"machine_name":"desktop","cold_start":0,"repetitions":4,"ticks_per_ms":1000,"data":[100,102,98,100]},
+        {"bench_name":"get_min","bench_options":{"object_size":16},"machine_name":"desktop","cold_start":0,"repetitions":4,"ticks_per_ms":1000,"data":[200,190,210,200]},
+        {"bench_name":"noisy","bench_options":{"object_size":8},"machine_name":"desktop","cold_start":0,"repetitions":4,"ticks_per_ms":1000,"data":[10,300,20,270]},
+        {"bench_name":"tiny","bench_options":{"object_size":8},"machine_name":"desktop","cold_start":0,"repetitions":4,"ticks_per_ms":1000,"data":[1,1,1,1]}
+    ]"#;
+
+    #[test]
+    fn test_compare_to_baseline() {
+        let baseline = BenchmarkBaseline::from_json(BASELINE).unwrap();
+        assert_eq!(baseline.len(), 4);
+
+        let mut comparison = BenchmarkComparison::new(baseline, BenchmarkComparisonOptions::default());
+
+        let delta = comparison.compare("get_min", &TestOptions { object_size: 8 }, 1000, &[120, 121, 119, 120]).unwrap();
+        assert_eq!(delta.verdict, BenchmarkVerdict::Regression);
+        assert!((delta.relative_change_percent - 20.0).abs() < 0.01);
+
+        // options have to match
+        let delta = comparison.compare("get_min", &TestOptions { object_size: 16 }, 1000, &[150, 151, 149, 150]).unwrap();
+        assert_eq!(delta.verdict, BenchmarkVerdict::Improvement);
+
+        // below threshold
+        let delta = comparison.compare("get_min", &TestOptions { object_size: 8 }, 1000, &[101, 103, 99, 101]).unwrap();
+        assert_eq!(delta.verdict, BenchmarkVerdict::Unchanged);
+
+        // large change, but within noise
+        let delta = comparison.compare("noisy", &TestOptions { object_size: 8 }, 1000, &[200, 150, 180, 210]).unwrap();
+        assert_eq!(delta.verdict, BenchmarkVerdict::Unchanged);
+
+        // below the timer resolution
+        let delta = comparison.compare("tiny", &TestOptions { object_size: 8 }, 1000, &[2, 2, 2, 2]).unwrap();
+        assert_eq!(delta.verdict, BenchmarkVerdict::Unchanged);
+
+        // different timer resolution
+        let delta = comparison.compare("get_min", &TestOptions { object_size: 8 }, 2000, &[200, 204, 196, 200]).unwrap();
+        assert_eq!(delta.verdict, BenchmarkVerdict::Unchanged);
+
+        assert!(comparison.compare("get_min", &TestOptions { object_size: 32 }, 1000, &[1]).is_none());
+        assert!(comparison.compare("unknown", &TestOptions { object_size: 8 }, 1000, &[1]).is_none());
+
+        assert_eq!(comparison.get_regression_count(), 1);
+        assert_eq!(comparison.get_improvement_count(), 1);
+        assert_eq!(comparison.get_unchanged_count(), 4);
+        assert_eq!(comparison.get_missing_count(), 2);
+    }
+
+    #[test]
+    fn test_baseline_formats() {
+        let output = "Running Benchmark \"get_min\" with options {\"object_size\":8}\n\
+            [BENCH-INFO] {\"bench_name\":\"get_min\",\"bench_options\":{\"object_size\":8},\"machine_name\":\"desktop\",\"cold_start\":0,\"repetitions\":2,\"ticks_per_ms\":1000,\"data\":[1,2]}\n\
+            -> Finished get_min: mean=1, min=1, max=2\n";
+
+        assert_eq!(BenchmarkBaseline::from_json(output).unwrap().len(), 1);
+        assert!(BenchmarkBaseline::from_json("no results").is_err());
+        assert!(BenchmarkBaseline::from_json("[BENCH-INFO] {\"invalid\"").is_err());
+
+        let mut storage = get_test_storage("test_benchmark_baseline_storage", 4096);
+        BenchmarkBaseline::write_to_storage(BASELINE, &mut storage, 100).unwrap();
+        assert_eq!(BenchmarkBaseline::from_storage(&mut storage, 100).unwrap().len(), 4);
+        assert!(BenchmarkBaseline::write_to_storage(BASELINE, &mut storage, 4000).is_err());
+    }
+}
@@ -30,7 +30,7 @@ use std::mem::{align_of, size_of};
 
 use super::*;
 use crate::{
-    benchmarks::{BenchmarkRunOptions, BenchmarkRunner, RunAllBenchmarkOptions, Timer}, calc_resident_buf_cutoff_size, modules::{object_management::DefaultObjectManagementModule, persistent_storage::DummyStorageModule}, resident_object_manager::resident_object_metadata::ResidentObjectMetadata, util::round_up_to_nearest, VNVObject
+    benchmarks::{BenchmarkRunOptions, BenchmarkRunner, RunAllBenchmarkOptions, Timer}, calc_resident_buf_cutoff_size, modules::{allocator::TlsfAllocatorModule, object_management::DefaultObjectManagementModule, persistent_storage::DummyStorageModule}, resident_object_manager::resident_object_metadata::ResidentObjectMetadata, util::round_up_to_nearest, VNVObject
 };
 
 use super::{GetCurrentTicks, PersistTrigger, PersistentStorageModule};
@@ -42,10 +42,10 @@ type M = DefaultObjectManagementModule;
 const RESIDENT_CUTOFF_SIZE: usize = {
     let tmp = if size_of::<usize>() == 8 {
         // desktop with File Storage Module
-        96 + size_of::<usize>()
+        96 + size_of::<usize>() + crate::benchmarks::common::FEATURE_CUTOFF_SIZE
     } else if size_of::<usize>() == 4 {
         // zephyr with SPI Fram Storage module
-        52 + size_of::<usize>()
+        52 + size_of::<usize>() + crate::benchmarks::common::FEATURE_CUTOFF_SIZE
     } else {
         panic!("uhhm");
     };
@@ -54,7 +54,7 @@ const RESIDENT_CUTOFF_SIZE: usize = {
 };
 
 const VNV_HEAP_RAM_OVERHEAD: usize = {
-    size_of::<VNVHeap<'_, A, N, M, DummyStorageModule>>()
+    size_of::<VNVHeap<'_, A, N, M, DummyStorageModule>>() - crate::benchmarks::common::FEATURE_HEAP_SIZE
         + size_of::<VNVObject<'_, '_, (), A, N, M>>()
         + VNVHeap::<'_, A, N, M, DummyStorageModule>::get_layout_info().persist_access_point_size
 };
@@ -62,7 +62,18 @@ const VNV_HEAP_RAM_OVERHEAD: usize = {
 
 const STEP_SIZE: usize = 32;
 
-const MIN_BUFFER_SIZE: usize = 512 - VNV_HEAP_RAM_OVERHEAD;
+/// Total RAM (buffer and heap overhead) of the smallest measured configuration.
+///
+/// Starts at 512 bytes, but the buffer has to fit the cutoff and at least one more step.
+/// As the heap is larger in test environments (see `VNVHeap::_mutex_guard`), this differs between them.
+const MIN_TOTAL_SIZE: usize = {
+    let mut total = 512;
+    while total < VNV_HEAP_RAM_OVERHEAD + RESIDENT_CUTOFF_SIZE + STEP_SIZE {
+        total += STEP_SIZE;
+    }
+    total
+};
+const MIN_BUFFER_SIZE: usize = MIN_TOTAL_SIZE - VNV_HEAP_RAM_OVERHEAD;
 const MAX_BUFFER_SIZE: usize = 4 * 1024 - VNV_HEAP_RAM_OVERHEAD;
 
 const STEP_COUNT: usize = (MAX_BUFFER_SIZE - MIN_BUFFER_SIZE) / STEP_SIZE + 1;
@@ -91,7 +102,7 @@ impl BenchmarkRunner for LockedWCETRunner {
         let mut iteration_count = 0;
         if options.run_locked_wcet_benchmarks {
             iteration_count += 1;
-            iteration_count += (3 + 2 * 3) * STEP_COUNT;
+            iteration_count += 2 * (3 + 2 * 3) * STEP_COUNT;
         }
 
         iteration_count
@@ -116,61 +127,9 @@ impl BenchmarkRunner for LockedWCETRunner {
         );
 
         if options.run_locked_wcet_benchmarks {
-            let mut buffer = [0u8; MAX_BUFFER_SIZE];
-
-            for_buffer_size!(buffer_size, {
-                handle_curr_iteration();
-                let mut a = A::new();
-                let mut storage = get_storage();
-                let executor = ObjectManager1LockedWCETExecutor::<TIMER>::new(&mut buffer[0..buffer_size], size_of::<usize>());
-                let bench = LockedWCETBenchmark::new(&mut storage, &mut a, executor);
-                bench.run_benchmark::<TIMER>(run_options);
-            });
-
-            for_buffer_size!(buffer_size, {
-                handle_curr_iteration();
-                let mut a = A::new();
-                let mut storage = get_storage();
-                let executor = ResidentObjectMetadata1LockedWCETExecutor::<TIMER>::new(&mut buffer[0..buffer_size], size_of::<usize>());
-                let bench = LockedWCETBenchmark::new(&mut storage, &mut a, executor);
-                bench.run_benchmark::<TIMER>(run_options);
-            });
-            
-
-            for_buffer_size!(buffer_size, {
-                handle_curr_iteration();
-                let mut a = A::new();
-                let mut storage = get_storage();
-                let executor = ResidentObjectManager1LockedWCETExecutor::<TIMER>::new(&mut buffer[0..buffer_size], size_of::<usize>());
-                let bench = LockedWCETBenchmark::new(&mut storage, &mut a, executor);
-                bench.run_benchmark::<TIMER>(run_options);
-            });
-            for variant in [false, true] {
-                for_buffer_size!(buffer_size, {
-                    handle_curr_iteration();
-                    let mut a = A::new();
-                    let mut storage = get_storage();
-                    let executor = ResidentObjectManager2LockedWCETExecutor::<TIMER>::new(variant, &mut buffer[0..buffer_size], size_of::<usize>());
-                    let bench = LockedWCETBenchmark::new(&mut storage, &mut a, executor);
-                    bench.run_benchmark::<TIMER>(run_options);
-                });
-                for_buffer_size!(buffer_size, {
-                    handle_curr_iteration();
-                    let mut a = A::new();
-                    let mut storage = get_storage();
-                    let executor = ResidentObjectManager3LockedWCETExecutor::<TIMER>::new(variant, &mut buffer[0..buffer_size], size_of::<usize>());
-                    let bench = LockedWCETBenchmark::new(&mut storage, &mut a, executor);
-                    bench.run_benchmark::<TIMER>(run_options);
-                });
-                for_buffer_size!(buffer_size, {
-                    handle_curr_iteration();
-                    let mut a = A::new();
-                    let mut storage = get_storage();
-                    let executor = ResidentObjectManager4LockedWCETExecutor::<TIMER>::new(variant, &mut buffer[0..buffer_size], size_of::<usize>());
-                    let bench = LockedWCETBenchmark::new(&mut storage, &mut a, executor);
-                    bench.run_benchmark::<TIMER>(run_options);
-                });
-            }
+            // the resident allocator is part of most critical sections, so they are measured with every allocator
+            run_allocator_benchmarks::<_, TIMER, S, F, G>(A::new, run_options, get_storage, handle_curr_iteration);
+            run_allocator_benchmarks::<_, TIMER, S, F, G>(TlsfAllocatorModule::<16>::new, run_options, get_storage, handle_curr_iteration);
 
             {
                 handle_curr_iteration();
@@ -184,3 +143,72 @@ impl BenchmarkRunner for LockedWCETRunner {
         }
     }
 }
+
+/// Runs all benchmarks that use the resident allocator with allocators created by `new_allocator`
+fn run_allocator_benchmarks<
+    A2: AllocatorModule,
+    TIMER: Timer,
+    S: PersistentStorageModule + 'static,
+    F: Fn() -> S,
+    G: FnMut(),
+>(
+    new_allocator: fn() -> A2,
+    run_options: &mut BenchmarkRunOptions,
+    get_storage: &F,
+    handle_curr_iteration: &mut G,
+) {
+    let mut buffer = [0u8; MAX_BUFFER_SIZE];
+
+    for_buffer_size!(buffer_size, {
+        handle_curr_iteration();
+        let mut a = new_allocator();
+        let mut storage = get_storage();
+        let executor = ObjectManager1LockedWCETExecutor::<TIMER>::new(&mut buffer[0..buffer_size], size_of::<usize>());
+        let bench = LockedWCETBenchmark::new(&mut storage, &mut a, executor);
+        bench.run_benchmark::<TIMER>(run_options);
+    });
+
+    for_buffer_size!(buffer_size, {
+        handle_curr_iteration();
+        let mut a = new_allocator();
+        let mut storage = get_storage();
+        let executor = ResidentObjectMetadata1LockedWCETExecutor::<TIMER>::new(&mut buffer[0..buffer_size], size_of::<usize>());
+        let bench = LockedWCETBenchmark::new(&mut storage, &mut a, executor);
+        bench.run_benchmark::<TIMER>(run_options);
+    });
+
+    for_buffer_size!(buffer_size, {
+        handle_curr_iteration();
+        let mut a = new_allocator();
+        let mut storage = get_storage();
+        let executor = ResidentObjectManager1LockedWCETExecutor::<TIMER>::new(&mut buffer[0..buffer_size], size_of::<usize>());
+        let bench = LockedWCETBenchmark::new(&mut storage, &mut a, executor);
+        bench.run_benchmark::<TIMER>(run_options);
+    });
+    for variant in [false, true] {
+        for_buffer_size!(buffer_size, {
+            handle_curr_iteration();
+            let mut a = new_allocator();
+            let mut storage = get_storage();
+            let executor = ResidentObjectManager2LockedWCETExecutor::<TIMER>::new(variant, &mut buffer[0..buffer_size], size_of::<usize>());
+            let bench = LockedWCETBenchmark::new(&mut storage, &mut a, executor);
+            bench.run_benchmark::<TIMER>(run_options);
+        });
+        for_buffer_size!(buffer_size, {
+            handle_curr_iteration();
+            let mut a = new_allocator();
+            let mut storage = get_storage();
+            let executor = ResidentObjectManager3LockedWCETExecutor::<TIMER>::new(variant, &mut buffer[0..buffer_size], size_of::<usize>());
+            let bench = LockedWCETBenchmark::new(&mut storage, &mut a, executor);
+            bench.run_benchmark::<TIMER>(run_options);
+        });
+        for_buffer_size!(buffer_size, {
+            handle_curr_iteration();
+            let mut a = new_allocator();
+            let mut storage = get_storage();
+            let executor = ResidentObjectManager4LockedWCETExecutor::<TIMER>::new(variant, &mut buffer[0..buffer_size], size_of::<usize>());
+            let bench = LockedWCETBenchmark::new(&mut storage, &mut a, executor);
+            bench.run_benchmark::<TIMER>(run_options);
+        });
+    }
+}
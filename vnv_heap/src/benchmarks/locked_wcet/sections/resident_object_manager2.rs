@@ -15,7 +15,7 @@
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{alloc::Layout, marker::PhantomData, mem::size_of};
+use std::{any::type_name, alloc::Layout, marker::PhantomData, mem::size_of};
 
 use memoffset::offset_of;
 use serde::Serialize;
@@ -31,6 +31,7 @@ use crate::{
 pub(crate) struct ResidentObjectManager2LockedWCETExecutorOptions {
     buffer_size: usize,
     object_size: usize,
+    allocator: &'static str,
     variant: u8,
 }
 
@@ -155,6 +156,7 @@ impl<'a, 'b, A: AllocatorModule, TIMER: Timer>
         ResidentObjectManager2LockedWCETExecutorOptions {
             buffer_size: self.buffer.len(),
             object_size: self.object_size,
+            allocator: type_name::<A>(),
             variant: match self.variant {
                 true => 1,
                 false => 0
@@ -15,7 +15,7 @@
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{alloc::Layout, marker::PhantomData, mem::size_of};
+use std::{any::type_name, alloc::Layout, marker::PhantomData, mem::size_of};
 
 use serde::Serialize;
 
@@ -30,6 +30,7 @@ use crate::{
 pub(crate) struct ResidentObjectManager3LockedWCETExecutorOptions {
     buffer_size: usize,
     object_size: usize,
+    allocator: &'static str,
     variant: u8,
 }
 
@@ -120,6 +121,7 @@ impl<'a, 'b, A: AllocatorModule, TIMER: Timer>
         ResidentObjectManager3LockedWCETExecutorOptions {
             buffer_size: self.buffer.len(),
             object_size: self.object_size,
+            allocator: type_name::<A>(),
             variant: match self.variant {
                 true => 1,
                 false => 0
@@ -15,7 +15,7 @@
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{alloc::Layout, hint::black_box, marker::PhantomData, mem::size_of};
+use std::{any::type_name, alloc::Layout, hint::black_box, marker::PhantomData, mem::size_of};
 
 use serde::Serialize;
 
@@ -30,6 +30,7 @@ use crate::{
 pub(crate) struct ResidentObjectManager4LockedWCETExecutorOptions {
     buffer_size: usize,
     object_size: usize,
+    allocator: &'static str,
     variant: u8,
 }
 
@@ -120,6 +121,7 @@ impl<'a, 'b, A: AllocatorModule, TIMER: Timer>
                 false,
             );
             metadata.inner.status.set_data_dirty(true);
+            metadata.inner.status.set_backup_missing(true);
             unsafe { ptr.write(metadata) };
     
             {
@@ -150,6 +152,7 @@ impl<'a, 'b, A: AllocatorModule, TIMER: Timer>
         ResidentObjectManager4LockedWCETExecutorOptions {
             buffer_size: self.buffer.len(),
             object_size: self.object_size,
+            allocator: type_name::<A>(),
             variant: match self.variant {
                 true => 1,
                 false => 0
@@ -238,6 +238,13 @@ impl PersistentStorageModule for BenchmarkableSharedStorageReference<'_, '_> {
         let s_ref = unsafe { guard.as_mut().unwrap() };
         s_ref.write(offset, src)
     }
+
+    fn flush(&mut self) -> Result<(), ()> {
+        let guard = self.lock.try_lock().ok_or(())?;
+
+        let s_ref = unsafe { guard.as_mut().unwrap() };
+        s_ref.flush()
+    }
 }
 
 impl BenchmarkableSharedStorageReference<'_, '_> {
@@ -108,126 +108,150 @@ impl BenchmarkRunner for BaselineBenchmarkRunner {
             for_obj_size!(I, {
                 handle_curr_iteration();
                 const SIZE: usize = I * STEP_SIZE + MIN_OBJ_SIZE;
-                let mut buffer = [0u8; BUCKET_SIZE];
-                let mut storage = get_storage();
-                let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
+                if run_options.runs_size(SIZE) {
+                    let mut buffer = [0u8; BUCKET_SIZE];
+                    let mut storage = get_storage();
+                    let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
 
-                let bench: BaselineGetMinBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineGetMinBenchmark::new(&mut memory_manager);
-                bench.run_benchmark::<TIMER>(run_options);
+                    let bench: BaselineGetMinBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineGetMinBenchmark::new(&mut memory_manager);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
             for_obj_size!(I, {
                 handle_curr_iteration();
                 const SIZE: usize = I * STEP_SIZE + MIN_OBJ_SIZE;
-                let mut buffer = [0u8; BUCKET_SIZE];
-                let mut storage = get_storage();
-                let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
+                if run_options.runs_size(SIZE) {
+                    let mut buffer = [0u8; BUCKET_SIZE];
+                    let mut storage = get_storage();
+                    let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
 
-                let bench: BaselineGetMaxMinBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineGetMaxMinBenchmark::new(&mut memory_manager);
-                bench.run_benchmark::<TIMER>(run_options);
+                    let bench: BaselineGetMaxMinBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineGetMaxMinBenchmark::new(&mut memory_manager);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
             for_obj_size!(I, {
                 handle_curr_iteration();
                 const SIZE: usize = I * STEP_SIZE + MIN_OBJ_SIZE;
-                let mut buffer = [0u8; BUCKET_SIZE];
-                let mut storage = get_storage();
-                let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
+                if run_options.runs_size(SIZE) {
+                    let mut buffer = [0u8; BUCKET_SIZE];
+                    let mut storage = get_storage();
+                    let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
 
-                let bench: BaselineGetMinMaxBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineGetMinMaxBenchmark::new(&mut memory_manager);
-                bench.run_benchmark::<TIMER>(run_options);
+                    let bench: BaselineGetMinMaxBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineGetMinMaxBenchmark::new(&mut memory_manager);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
             for_obj_size!(I, {
                 handle_curr_iteration();
                 const SIZE: usize = I * STEP_SIZE + MIN_OBJ_SIZE;
-                let mut buffer = [0u8; BUCKET_SIZE];
-                let mut storage = get_storage();
-                let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
+                if run_options.runs_size(SIZE) {
+                    let mut buffer = [0u8; BUCKET_SIZE];
+                    let mut storage = get_storage();
+                    let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
 
-                let bench: BaselineGetMaxBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineGetMaxBenchmark::new(&mut memory_manager);
-                bench.run_benchmark::<TIMER>(run_options);
+                    let bench: BaselineGetMaxBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineGetMaxBenchmark::new(&mut memory_manager);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
         }
         if options.run_baseline_allocate_benchmarks {
             for_obj_size!(I, {
                 handle_curr_iteration();
                 const SIZE: usize = I * STEP_SIZE + MIN_OBJ_SIZE;
-                let mut buffer = [0u8; BUCKET_SIZE];
-                let mut storage = get_storage();
-                let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
+                if run_options.runs_size(SIZE) {
+                    let mut buffer = [0u8; BUCKET_SIZE];
+                    let mut storage = get_storage();
+                    let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
 
-                let bench: BaselineAllocateMinBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineAllocateMinBenchmark::new(&mut memory_manager);
-                bench.run_benchmark::<TIMER>(run_options);
+                    let bench: BaselineAllocateMinBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineAllocateMinBenchmark::new(&mut memory_manager);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
             for_obj_size!(I, {
                 handle_curr_iteration();
                 const SIZE: usize = I * STEP_SIZE + MIN_OBJ_SIZE;
-                let mut buffer = [0u8; BUCKET_SIZE];
-                let mut storage = get_storage();
-                let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
+                if run_options.runs_size(SIZE) {
+                    let mut buffer = [0u8; BUCKET_SIZE];
+                    let mut storage = get_storage();
+                    let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
 
-                let bench: BaselineAllocateMaxMinBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineAllocateMaxMinBenchmark::new(&mut memory_manager);
-                bench.run_benchmark::<TIMER>(run_options);
+                    let bench: BaselineAllocateMaxMinBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineAllocateMaxMinBenchmark::new(&mut memory_manager);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
             for_obj_size!(I, {
                 handle_curr_iteration();
                 const SIZE: usize = I * STEP_SIZE + MIN_OBJ_SIZE;
-                let mut buffer = [0u8; BUCKET_SIZE];
-                let mut storage = get_storage();
-                let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
+                if run_options.runs_size(SIZE) {
+                    let mut buffer = [0u8; BUCKET_SIZE];
+                    let mut storage = get_storage();
+                    let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
 
-                let bench: BaselineAllocateMinMaxBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineAllocateMinMaxBenchmark::new(&mut memory_manager);
-                bench.run_benchmark::<TIMER>(run_options);
+                    let bench: BaselineAllocateMinMaxBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineAllocateMinMaxBenchmark::new(&mut memory_manager);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
             for_obj_size!(I, {
                 handle_curr_iteration();
                 const SIZE: usize = I * STEP_SIZE + MIN_OBJ_SIZE;
-                let mut buffer = [0u8; BUCKET_SIZE];
-                let mut storage = get_storage();
-                let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
+                if run_options.runs_size(SIZE) {
+                    let mut buffer = [0u8; BUCKET_SIZE];
+                    let mut storage = get_storage();
+                    let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
 
-                let bench: BaselineAllocateMaxBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineAllocateMaxBenchmark::new(&mut memory_manager);
-                bench.run_benchmark::<TIMER>(run_options);
+                    let bench: BaselineAllocateMaxBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineAllocateMaxBenchmark::new(&mut memory_manager);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
         }
         if options.run_baseline_deallocate_benchmarks {
             for_obj_size!(I, {
                 handle_curr_iteration();
                 const SIZE: usize = I * STEP_SIZE + MIN_OBJ_SIZE;
-                let mut buffer = [0u8; BUCKET_SIZE];
-                let mut storage = get_storage();
-                let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
+                if run_options.runs_size(SIZE) {
+                    let mut buffer = [0u8; BUCKET_SIZE];
+                    let mut storage = get_storage();
+                    let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
 
-                let bench: BaselineDeallocateMinBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineDeallocateMinBenchmark::new(&mut memory_manager);
-                bench.run_benchmark::<TIMER>(run_options);
+                    let bench: BaselineDeallocateMinBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineDeallocateMinBenchmark::new(&mut memory_manager);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
             for_obj_size!(I, {
                 handle_curr_iteration();
                 const SIZE: usize = I * STEP_SIZE + MIN_OBJ_SIZE;
-                let mut buffer = [0u8; BUCKET_SIZE];
-                let mut storage = get_storage();
-                let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
+                if run_options.runs_size(SIZE) {
+                    let mut buffer = [0u8; BUCKET_SIZE];
+                    let mut storage = get_storage();
+                    let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
 
-                let bench: BaselineDeallocateMaxMinBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineDeallocateMaxMinBenchmark::new(&mut memory_manager);
-                bench.run_benchmark::<TIMER>(run_options);
+                    let bench: BaselineDeallocateMaxMinBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineDeallocateMaxMinBenchmark::new(&mut memory_manager);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
             for_obj_size!(I, {
                 handle_curr_iteration();
                 const SIZE: usize = I * STEP_SIZE + MIN_OBJ_SIZE;
-                let mut buffer = [0u8; BUCKET_SIZE];
-                let mut storage = get_storage();
-                let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
+                if run_options.runs_size(SIZE) {
+                    let mut buffer = [0u8; BUCKET_SIZE];
+                    let mut storage = get_storage();
+                    let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
 
-                let bench: BaselineDeallocateMinMaxBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineDeallocateMinMaxBenchmark::new(&mut memory_manager);
-                bench.run_benchmark::<TIMER>(run_options);
+                    let bench: BaselineDeallocateMinMaxBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineDeallocateMinMaxBenchmark::new(&mut memory_manager);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
             for_obj_size!(I, {
                 handle_curr_iteration();
                 const SIZE: usize = I * STEP_SIZE + MIN_OBJ_SIZE;
-                let mut buffer = [0u8; BUCKET_SIZE];
-                let mut storage = get_storage();
-                let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
+                if run_options.runs_size(SIZE) {
+                    let mut buffer = [0u8; BUCKET_SIZE];
+                    let mut storage = get_storage();
+                    let mut memory_manager = MemoryManager::new(&mut buffer, &mut storage, 2, LinkedListAllocatorModule::new);
 
-                let bench: BaselineDeallocateMaxBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineDeallocateMaxBenchmark::new(&mut memory_manager);
-                bench.run_benchmark::<TIMER>(run_options);
+                    let bench: BaselineDeallocateMaxBenchmark<SIZE, BUCKET_SIZE, A, S> = BaselineDeallocateMaxBenchmark::new(&mut memory_manager);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
         }
     }
@@ -0,0 +1,112 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::{
+    modules::{
+        allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule, object_management::ObjectManagementModule, persistent_storage::PersistentStorageModule
+    },
+    VNVHeap, VNVObject,
+};
+use core::hint::black_box;
+use serde::Serialize;
+
+use super::{Benchmark, ModuleOptions, Timer};
+
+#[derive(Serialize)]
+pub struct GetHotBenchmarkOptions {
+    object_size: usize,
+    resident_objects: usize,
+    cached: bool,
+    modules: ModuleOptions
+}
+
+/// Accesses the same resident object over and over again.
+///
+/// All other resident objects are located before this object in the resident list.
+/// If `cached` is `false`, the residency token of the object is reset before every access,
+/// so the resident list has to be searched every time.
+pub struct GetHotBenchmark<
+    'a,
+    'b: 'a,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+    const OBJ_SIZE: usize,
+> {
+    object: VNVObject<'a, 'b, [u8; OBJ_SIZE], A, N, M>,
+    others: Vec<VNVObject<'a, 'b, usize, A, N, M>>,
+    cached: bool,
+}
+
+impl<'a, 'b: 'a, A: AllocatorModule + 'static, N: NonResidentAllocatorModule, M: ObjectManagementModule, const OBJ_SIZE: usize>
+    GetHotBenchmark<'a, 'b, A, N, M, OBJ_SIZE>
+{
+    pub fn new<S: PersistentStorageModule>(heap: &'a VNVHeap<'b, A, N, M, S>, other_cnt: usize, cached: bool) -> Self {
+        let mut others = vec![];
+        for _ in 0..other_cnt {
+            let mut other = heap.allocate::<usize>(0).unwrap();
+            drop(other.get().unwrap());
+            others.push(other);
+        }
+
+        let mut object = heap.allocate::<[u8; OBJ_SIZE]>([0u8; OBJ_SIZE]).unwrap();
+        drop(object.get().unwrap());
+
+        assert!(others.iter().all(|other| other.is_resident()), "all objects should be resident");
+
+        Self {
+            object,
+            others,
+            cached,
+        }
+    }
+}
+
+impl<'a, 'b: 'a, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule, const OBJ_SIZE: usize>
+    Benchmark<GetHotBenchmarkOptions> for GetHotBenchmark<'a, 'b, A, N, M, OBJ_SIZE>
+{
+    #[inline]
+    fn get_name(&self) -> &'static str {
+        "get_hot"
+    }
+
+    #[inline]
+    fn execute<T: Timer>(&mut self) -> u32 {
+        if !self.cached {
+            self.object.invalidate_residency_token();
+        }
+
+        let timer = T::start();
+
+        let item_ref = black_box(self.object.get().unwrap());
+        let res = timer.stop();
+
+        drop(item_ref);
+
+        res
+    }
+
+    #[inline]
+    fn get_bench_options(&self) -> GetHotBenchmarkOptions {
+        GetHotBenchmarkOptions {
+            object_size: OBJ_SIZE,
+            resident_objects: self.others.len(),
+            cached: self.cached,
+            modules: ModuleOptions::new::<A, N>()
+        }
+    }
+}
@@ -21,6 +21,7 @@
 // mod deallocate_case1;
 // mod deallocate_max;
 // mod deallocate_min;
+mod get_hot;
 mod get_max;
 mod get_max_old;
 mod get_max_min;
@@ -33,6 +34,7 @@ mod get_min_max;
 // pub use deallocate_case1::*;
 // pub use deallocate_max::*;
 // pub use deallocate_min::*;
+pub use get_hot::*;
 pub use get_max::*;
 pub use get_max_old::*;
 pub use get_max_min::*;
@@ -42,7 +44,7 @@ pub use get_min_max::*;
 use core::mem::size_of;
 
 use crate::{
-    calc_resident_buf_cutoff_size, modules::object_management::DefaultObjectManagementModule, resident_object_manager::resident_object_metadata::ResidentObjectMetadata, VNVConfig
+    calc_resident_buf_cutoff_size, modules::object_management::DefaultObjectManagementModule, resident_object_manager::resident_object_metadata::ResidentObjectMetadata, VNVConfig, WriteBack
 };
 
 use super::*;
@@ -50,10 +52,10 @@ use super::*;
 const RESIDENT_CUTOFF_SIZE: usize = {
     if size_of::<usize>() == 8 {
         // desktop with File Storage Module
-        96 + size_of::<usize>()
+        96 + size_of::<usize>() + crate::benchmarks::common::FEATURE_CUTOFF_SIZE
     } else if size_of::<usize>() == 4 {
         // zephyr with SPI Fram Storage module
-        52 + size_of::<usize>()
+        52 + size_of::<usize>() + crate::benchmarks::common::FEATURE_CUTOFF_SIZE
     } else {
         panic!("uhhm");
     }
@@ -94,6 +96,9 @@ const MAX_OBJ_SIZE: usize = {
 };
 
 
+// object size of the hot object access benchmark
+const HOT_OBJ_SIZE: usize = MIN_OBJ_SIZE;
+
 const STEP_COUNT: usize = (MAX_OBJ_SIZE_RANGE - MIN_OBJ_SIZE_RANGE) / STEP_SIZE + 1;
 
 macro_rules! for_obj_size_impl {
@@ -170,6 +175,8 @@ impl BenchmarkRunner for ImplementationBenchmarkRunner {
             if MAX_OBJ_SIZE != MAX_OBJ_SIZE_RANGE {
                 iteration_count += 5;
             }
+            // hot object access with and without residency token
+            iteration_count += 2;
         }
 
         iteration_count
@@ -203,6 +210,8 @@ impl BenchmarkRunner for ImplementationBenchmarkRunner {
         > {
             let config = VNVConfig {
                 max_dirty_bytes: max_dirty,
+                write_back: WriteBack::Lazy,
+                persist_latency_budget: None,
             };
 
             let heap: VNVHeap<
@@ -297,69 +306,92 @@ impl BenchmarkRunner for ImplementationBenchmarkRunner {
         if options.run_get_benchmarks {
             for_obj_size!(SIZE, {
                 handle_curr_iteration();
-                let res_size = buf.len();
-                let heap = get_bench_heap(&mut buf, res_size, get_storage());
-                let bench: GetMinBenchmark<A, NonResidentBuddyAllocatorModule<19>, M, SIZE> =
-                    GetMinBenchmark::new(&heap);
-                bench.run_benchmark::<TIMER>(run_options);
+                if run_options.runs_size(SIZE) {
+                    let res_size = buf.len();
+                    let heap = get_bench_heap(&mut buf, res_size, get_storage());
+                    let bench: GetMinBenchmark<A, NonResidentBuddyAllocatorModule<19>, M, SIZE> =
+                        GetMinBenchmark::new(&heap);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
             for_obj_size!(SIZE, {
                 handle_curr_iteration();
-
-                const METADATA_SIZE: usize = get_resident_size::<()>();
-                const BLOCKER_SIZE: usize = BUF_SIZE - METADATA_SIZE - RESIDENT_CUTOFF_SIZE;
-
-                let res_size = buf.len();
-                let heap = get_bench_heap(&mut buf, res_size, get_storage());
-                let start_res_size = res_size - RESIDENT_CUTOFF_SIZE;
-                let bench: GetMaxBenchmark<
-                    A,
-                    NonResidentBuddyAllocatorModule<19>,
-                    M,
-                    SIZE,
-                    BLOCKER_SIZE,
-                > = GetMaxBenchmark::new(&heap, start_res_size);
-                bench.run_benchmark::<TIMER>(run_options);
+                if run_options.runs_size(SIZE) {
+                    const METADATA_SIZE: usize = get_resident_size::<()>();
+                    const BLOCKER_SIZE: usize = BUF_SIZE - METADATA_SIZE - RESIDENT_CUTOFF_SIZE;
+
+                    let res_size = buf.len();
+                    let heap = get_bench_heap(&mut buf, res_size, get_storage());
+                    let start_res_size = res_size - RESIDENT_CUTOFF_SIZE;
+                    let bench: GetMaxBenchmark<
+                        A,
+                        NonResidentBuddyAllocatorModule<19>,
+                        M,
+                        SIZE,
+                        BLOCKER_SIZE,
+                    > = GetMaxBenchmark::new(&heap, start_res_size);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
             for_obj_size!(SIZE, {
                 handle_curr_iteration();
-
-                const RES: (usize, usize) = calc_obj_cnt_and_rem_size_get_max(SIZE, BUF_SIZE - RESIDENT_CUTOFF_SIZE);
-                const BLOCKER_CNT: usize = RES.0;
-                const REM_SIZE: usize = RES.1;
-
-                let res_size = buf.len();
-                let heap = get_bench_heap(&mut buf, res_size, get_storage());
-                let start_res_size = res_size - RESIDENT_CUTOFF_SIZE;
-                let bench: GetMaxOldBenchmark<
-                    A,
-                    NonResidentBuddyAllocatorModule<19>,
-                    M,
-                    SIZE,
-                    REM_SIZE
-                > = GetMaxOldBenchmark::new(&heap, start_res_size, BLOCKER_CNT);
-                bench.run_benchmark::<TIMER>(run_options);
+                if run_options.runs_size(SIZE) {
+                    const RES: (usize, usize) = calc_obj_cnt_and_rem_size_get_max(SIZE, BUF_SIZE - RESIDENT_CUTOFF_SIZE);
+                    const BLOCKER_CNT: usize = RES.0;
+                    const REM_SIZE: usize = RES.1;
+
+                    let res_size = buf.len();
+                    let heap = get_bench_heap(&mut buf, res_size, get_storage());
+                    let start_res_size = res_size - RESIDENT_CUTOFF_SIZE;
+                    let bench: GetMaxOldBenchmark<
+                        A,
+                        NonResidentBuddyAllocatorModule<19>,
+                        M,
+                        SIZE,
+                        REM_SIZE
+                    > = GetMaxOldBenchmark::new(&heap, start_res_size, BLOCKER_CNT);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
             for_obj_size!(SIZE, {
                 handle_curr_iteration();
-
-                let res_size = buf.len();
-                let heap = get_bench_heap(&mut buf, res_size, get_storage());
-                let start_res_size = res_size - RESIDENT_CUTOFF_SIZE;
-                let bench: GetMaxMinBenchmark<A, NonResidentBuddyAllocatorModule<19>, M, S, SIZE> =
-                    GetMaxMinBenchmark::new(&heap, start_res_size);
-                bench.run_benchmark::<TIMER>(run_options);
+                if run_options.runs_size(SIZE) {
+                    let res_size = buf.len();
+                    let heap = get_bench_heap(&mut buf, res_size, get_storage());
+                    let start_res_size = res_size - RESIDENT_CUTOFF_SIZE;
+                    let bench: GetMaxMinBenchmark<A, NonResidentBuddyAllocatorModule<19>, M, S, SIZE> =
+                        GetMaxMinBenchmark::new(&heap, start_res_size);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
             for_obj_size!(SIZE, {
                 handle_curr_iteration();
-
-                let res_size = buf.len();
-                let heap = get_bench_heap(&mut buf, res_size, get_storage());
-                let start_res_size = res_size - RESIDENT_CUTOFF_SIZE;
-                let bench: GetMinMaxBenchmark<A, NonResidentBuddyAllocatorModule<19>, M, S, SIZE> =
-                    GetMinMaxBenchmark::new(&heap, start_res_size);
-                bench.run_benchmark::<TIMER>(run_options);
+                if run_options.runs_size(SIZE) {
+                    let res_size = buf.len();
+                    let heap = get_bench_heap(&mut buf, res_size, get_storage());
+                    let start_res_size = res_size - RESIDENT_CUTOFF_SIZE;
+                    let bench: GetMinMaxBenchmark<A, NonResidentBuddyAllocatorModule<19>, M, S, SIZE> =
+                        GetMinMaxBenchmark::new(&heap, start_res_size);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
+            for cached in [false, true] {
+                handle_curr_iteration();
+                if run_options.runs_size(HOT_OBJ_SIZE) {
+                    // fill the rest of the resident buffer with other objects
+                    const OTHER_CNT: usize = (BUF_SIZE
+                        - RESIDENT_CUTOFF_SIZE
+                        - get_resident_size::<[u8; HOT_OBJ_SIZE]>()
+                        - ADDITIONAL_ALLOCATOR_COST)
+                        / get_resident_size::<usize>();
+
+                    let res_size = buf.len();
+                    let heap = get_bench_heap(&mut buf, res_size, get_storage());
+                    let bench: GetHotBenchmark<A, NonResidentBuddyAllocatorModule<19>, M, HOT_OBJ_SIZE> =
+                        GetHotBenchmark::new(&heap, OTHER_CNT, cached);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
+            }
         }
     }
 }
@@ -15,13 +15,18 @@
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+mod persistent_storage_header_write;
 mod persistent_storage_read;
 mod persistent_storage_write;
 
+pub use persistent_storage_header_write::*;
 pub use persistent_storage_read::*;
 pub use persistent_storage_write::*;
 
+use std::mem::size_of;
+
 use super::*;
+use crate::ObjectChecksum;
 
 const STEP_SIZE: usize = 4;
 const MIN_OBJ_SIZE: usize = 0;
@@ -29,6 +34,11 @@ const MAX_OBJ_SIZE: usize = 8 * 1024;
 
 const STEP_COUNT: usize = (MAX_OBJ_SIZE - MIN_OBJ_SIZE) / STEP_SIZE + 1;
 
+/// Object sizes of `PersistentStorageHeaderWriteBenchmark` (headers matter most for small objects)
+const HEADER_WRITE_OBJ_SIZES: [usize; 6] = [4, 8, 16, 32, 64, 128];
+const HEADER_WRITE_OBJ_COUNT: usize = 16;
+const HEADER_WRITE_HEADER_SIZES: [usize; 2] = [0, size_of::<ObjectChecksum>()];
+
 macro_rules! for_buffer {
     ($buffer_name: ident, $inner: expr) => {
         {
@@ -49,6 +59,7 @@ impl BenchmarkRunner for StorageBenchmarkRunner {
         let mut iteration_count = 0;
         if options.run_persistent_storage_benchmarks {
             iteration_count += 2 * STEP_COUNT;
+            iteration_count += 2 * HEADER_WRITE_HEADER_SIZES.len() * HEADER_WRITE_OBJ_SIZES.len();
         }
         if options.run_long_persistent_storage_benchmarks {
             iteration_count += 2;
@@ -72,18 +83,42 @@ impl BenchmarkRunner for StorageBenchmarkRunner {
         if options.run_persistent_storage_benchmarks {
             for_buffer!(buffer, {
                 handle_curr_iteration();
-                let mut storage_module = get_storage();
+                if run_options.runs_size(buffer.len()) {
+                    let mut storage_module = get_storage();
 
-                let bench: PersistentStorageReadBenchmark<S> = PersistentStorageReadBenchmark::new(buffer, &mut storage_module);
-                bench.run_benchmark::<TIMER>(run_options);
+                    let bench: PersistentStorageReadBenchmark<S> = PersistentStorageReadBenchmark::new(buffer, &mut storage_module);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
             for_buffer!(buffer, {
                 handle_curr_iteration();
-                let mut storage_module = get_storage();
+                if run_options.runs_size(buffer.len()) {
+                    let mut storage_module = get_storage();
 
-                let bench: PersistentStorageWriteBenchmark<S> = PersistentStorageWriteBenchmark::new(buffer, &mut storage_module);
-                bench.run_benchmark::<TIMER>(run_options);
+                    let bench: PersistentStorageWriteBenchmark<S> = PersistentStorageWriteBenchmark::new(buffer, &mut storage_module);
+                    bench.run_benchmark::<TIMER>(run_options);
+                }
             });
+            for header_size in HEADER_WRITE_HEADER_SIZES {
+                for obj_size in HEADER_WRITE_OBJ_SIZES {
+                    for batched in [false, true] {
+                        handle_curr_iteration();
+                        if run_options.runs_size(obj_size) {
+                            let mut storage_module = get_storage();
+                            let data = [0u8; 128];
+
+                            let bench: PersistentStorageHeaderWriteBenchmark<S> = PersistentStorageHeaderWriteBenchmark::new(
+                                &data[..obj_size],
+                                HEADER_WRITE_OBJ_COUNT,
+                                header_size,
+                                batched,
+                                &mut storage_module,
+                            );
+                            bench.run_benchmark::<TIMER>(run_options);
+                        }
+                    }
+                }
+            }
         }
     
         if options.run_long_persistent_storage_benchmarks {
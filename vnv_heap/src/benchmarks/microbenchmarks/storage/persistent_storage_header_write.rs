@@ -0,0 +1,99 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::{any::type_name, hint::black_box};
+
+use serde::Serialize;
+
+use crate::{modules::persistent_storage::PersistentStorageModule, resident_object_manager::StagedWriter};
+
+use super::Benchmark;
+
+#[derive(Serialize)]
+pub struct PersistentStorageHeaderWriteBenchmarkOptions {
+    object_size: usize,
+    object_count: usize,
+    header_size: usize,
+    batched: bool,
+    persistent_storage_module: &'static str
+}
+
+/// Writes back `object_count` objects that are stored right behind each other,
+/// each as its data followed by its header (like `persist_budget` does with `object_checksums` enabled).
+///
+/// Comparing a `header_size` of zero with a non-zero one isolates the cost of the header writes,
+/// `batched` writes through the same staging buffer as `persist_budget` (before/after the staging buffer was introduced).
+pub struct PersistentStorageHeaderWriteBenchmark<'a, S: PersistentStorageModule> {
+    storage_module: &'a mut S,
+    data: &'a [u8],
+    object_count: usize,
+    header_size: usize,
+    batched: bool
+}
+
+impl<'a, S: PersistentStorageModule> PersistentStorageHeaderWriteBenchmark<'a, S> {
+    pub fn new(data: &'a [u8], object_count: usize, header_size: usize, batched: bool, storage_module: &'a mut S) -> Self {
+        Self {
+            storage_module,
+            data,
+            object_count,
+            header_size,
+            batched
+        }
+    }
+}
+
+fn write_objects<S: PersistentStorageModule>(storage: &mut S, data: &[u8], object_count: usize, header_size: usize) {
+    let header = [0u8; 8];
+    for i in 0..object_count {
+        let offset = i * (header_size + data.len());
+        black_box(storage.write(offset + header_size, black_box(data))).unwrap();
+        if header_size > 0 {
+            black_box(storage.write(offset, black_box(&header[..header_size]))).unwrap();
+        }
+    }
+}
+
+impl<'a, S: PersistentStorageModule> Benchmark<PersistentStorageHeaderWriteBenchmarkOptions> for PersistentStorageHeaderWriteBenchmark<'a, S> {
+    fn get_name(&self) -> &'static str {
+        "persistent_storage_header_write"
+    }
+
+    fn get_bench_options(&self) -> PersistentStorageHeaderWriteBenchmarkOptions {
+        PersistentStorageHeaderWriteBenchmarkOptions {
+            object_size: self.data.len(),
+            object_count: self.object_count,
+            header_size: self.header_size,
+            batched: self.batched,
+            persistent_storage_module: type_name::<S>()
+        }
+    }
+
+    fn execute<T: super::Timer>(&mut self) -> u32 {
+        let timer = T::start();
+
+        if self.batched {
+            let mut writer = StagedWriter::new(&mut *self.storage_module);
+            write_objects(&mut writer, self.data, self.object_count, self.header_size);
+            black_box(writer.flush()).unwrap();
+        } else {
+            write_objects(self.storage_module, self.data, self.object_count, self.header_size);
+            black_box(self.storage_module.flush()).unwrap();
+        }
+
+        timer.stop()
+    }
+}
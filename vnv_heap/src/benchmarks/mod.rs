@@ -37,6 +37,12 @@ pub(crate) mod common;
 mod applications;
 use applications::*;
 
+mod comparison;
+pub use comparison::*;
+
+mod suites;
+pub use suites::*;
+
 use crate::{
     modules::{
         allocator::{AllocatorModule, LinkedListAllocatorModule}, nonresident_allocator::{NonResidentAllocatorModule, NonResidentBuddyAllocatorModule}, persistent_storage::PersistentStorageModule
@@ -186,31 +192,41 @@ pub fn run_all_benchmarks<
 
     // run benchmarks
     if options.run_allocate_benchmarks || options.run_get_benchmarks || options.run_deallocate_benchmarks {
-        ImplementationBenchmarkRunner::run::<TIMER, TRIGGER, S, F, _>(&mut run_options, &options, &get_storage, &mut handle_it, get_ticks.clone());
+        ImplementationBenchmarkRunner::run::<TIMER, TRIGGER, S, F, _>(&mut run_options.for_suite(BenchmarkSuite::Implementation), &options, &get_storage, &mut handle_it, get_ticks.clone());
     }
     if options.run_baseline_allocate_benchmarks || options.run_baseline_deallocate_benchmarks || options.run_baseline_get_benchmarks {
-        BaselineBenchmarkRunner::run::<TIMER, TRIGGER, S, F, _>(&mut run_options, &options, &get_storage, &mut handle_it, get_ticks.clone());
+        BaselineBenchmarkRunner::run::<TIMER, TRIGGER, S, F, _>(&mut run_options.for_suite(BenchmarkSuite::Baseline), &options, &get_storage, &mut handle_it, get_ticks.clone());
     }
-    if options.run_persistent_storage_benchmarks || options.run_long_persistent_storage_benchmarks {
-        StorageBenchmarkRunner::run::<TIMER, TRIGGER, S, F, _>(&mut run_options, &options, &get_storage, &mut handle_it, get_ticks.clone());
+    // both storage suites share a runner, but can be configured separately
+    if options.run_persistent_storage_benchmarks {
+        let storage_options = RunAllBenchmarkOptions { run_persistent_storage_benchmarks: true, ..Default::default() };
+        StorageBenchmarkRunner::run::<TIMER, TRIGGER, S, F, _>(&mut run_options.for_suite(BenchmarkSuite::PersistentStorage), &storage_options, &get_storage, &mut handle_it, get_ticks.clone());
+    }
+    if options.run_long_persistent_storage_benchmarks {
+        let storage_options = RunAllBenchmarkOptions { run_long_persistent_storage_benchmarks: true, ..Default::default() };
+        StorageBenchmarkRunner::run::<TIMER, TRIGGER, S, F, _>(&mut run_options.for_suite(BenchmarkSuite::LongPersistentStorage), &storage_options, &get_storage, &mut handle_it, get_ticks.clone());
     }
     if options.run_dirty_size_persist_latency {
-        DirtySizePersistLatencyRunner::run::<TIMER, TRIGGER, S, F, _>(&mut run_options, &options, &get_storage, &mut handle_it, get_ticks.clone());
+        DirtySizePersistLatencyRunner::run::<TIMER, TRIGGER, S, F, _>(&mut run_options.for_suite(BenchmarkSuite::DirtySizePersistLatency), &options, &get_storage, &mut handle_it, get_ticks.clone());
     }
     if options.run_buffer_size_persist_latency {
-        BufferSizePersistLatencyRunner::run::<TIMER, TRIGGER, S, F, _>(&mut run_options, &options, &get_storage, &mut handle_it, get_ticks.clone());
+        BufferSizePersistLatencyRunner::run::<TIMER, TRIGGER, S, F, _>(&mut run_options.for_suite(BenchmarkSuite::BufferSizePersistLatency), &options, &get_storage, &mut handle_it, get_ticks.clone());
     }
     if options.run_queue_benchmarks {
-        QueueBenchmarkRunner::run::<TIMER, TRIGGER, S, F, _>(&mut run_options, &options, &get_storage, &mut handle_it, get_ticks.clone());
+        QueueBenchmarkRunner::run::<TIMER, TRIGGER, S, F, _>(&mut run_options.for_suite(BenchmarkSuite::Queue), &options, &get_storage, &mut handle_it, get_ticks.clone());
     }
     if options.run_kvs_benchmarks {
-        KVSBenchmarkRunner::run::<TIMER, TRIGGER, S, F, _>(&mut run_options, &options, &get_storage, &mut handle_it, get_ticks.clone());
+        KVSBenchmarkRunner::run::<TIMER, TRIGGER, S, F, _>(&mut run_options.for_suite(BenchmarkSuite::KeyValueStore), &options, &get_storage, &mut handle_it, get_ticks.clone());
     }
     if options.run_locked_wcet_benchmarks {
-        LockedWCETRunner::run::<TIMER, TRIGGER, S, F, _>(&mut run_options, &options, &get_storage, &mut handle_it, get_ticks.clone());
+        LockedWCETRunner::run::<TIMER, TRIGGER, S, F, _>(&mut run_options.for_suite(BenchmarkSuite::LockedWCET), &options, &get_storage, &mut handle_it, get_ticks.clone());
     }
     debug_assert_eq!(curr_iteration, iteration_count);
-    println!("")
+    println!("");
+
+    if let Some(comparison) = run_options.comparison.as_deref() {
+        comparison.print_summary();
+    }
 }
 
 pub(self) trait BenchmarkRunner {
@@ -245,6 +261,7 @@ pub trait Benchmark<O: Serialize> {
         Self: Sized,
     {
         assert_eq!(options.repetitions as usize, options.result_buffer.len());
+        assert_eq!(options.cold_start as usize, options.cold_start_buffer.len());
 
         print!("Running Benchmark \"{}\" with options ", self.get_name());
 
@@ -252,8 +269,11 @@ pub trait Benchmark<O: Serialize> {
         serde_json::to_writer(stdout(), &self.get_bench_options()).unwrap();
         println!();
 
-        for _ in 0..options.cold_start {
-            self.execute::<T>();
+        // the first runs include first access costs (e.g. loading objects after the benchmark was set up)
+        // record them separately, so that they are not mixed up with the actual measurements
+        for i in 0..options.cold_start_buffer.len() {
+            let res = self.execute::<T>();
+            options.cold_start_buffer[i] = res;
         }
 
         for i in 0..options.result_buffer.len() {
@@ -270,6 +290,7 @@ pub trait Benchmark<O: Serialize> {
                 bench_options: &self.get_bench_options(),
                 machine_name: options.machine_name,
                 cold_start: options.cold_start,
+                cold_start_data: options.cold_start_buffer,
                 repetitions: options.repetitions,
                 ticks_per_ms: T::get_ticks_per_ms(),
                 data: &options.result_buffer,
@@ -286,6 +307,19 @@ pub trait Benchmark<O: Serialize> {
             res.min_latency,
             res.max_latency
         );
+        if !options.cold_start_buffer.is_empty() {
+            let cold_start_res = BenchmarkRunResult::from_buffer(options.cold_start_buffer);
+            println!(
+                "   Cold start: first={}, mean={}, min={}, max={}",
+                options.cold_start_buffer[0],
+                cold_start_res.mean_latency,
+                cold_start_res.min_latency,
+                cold_start_res.max_latency
+            );
+        }
+        if let Some(comparison) = options.comparison.as_deref_mut() {
+            comparison.compare_and_print(self.get_name(), &self.get_bench_options(), T::get_ticks_per_ms(), options.result_buffer);
+        }
         println!();
 
         res
@@ -297,8 +331,23 @@ pub struct BenchmarkRunOptions<'a> {
     pub result_buffer: &'a mut [u32],
 
     pub cold_start: u32,
+    /// Latencies of the cold start runs (length has to match `cold_start`).
+    /// The first entry is the first run after the benchmark was set up.
+    pub cold_start_buffer: &'a mut [u32],
 
     pub machine_name: &'static str,
+
+    /// If set, the results of each benchmark are compared to a previous run
+    pub comparison: Option<&'a mut BenchmarkComparison>,
+
+    /// If set, only benchmarks with one of these object sizes are run.
+    ///
+    /// Applies to the suites that measure different object sizes
+    /// (`Implementation`, `Baseline` and `PersistentStorage`, see `BenchmarkSuite`).
+    pub sizes: Option<&'a [usize]>,
+
+    /// Overrides of these options for single benchmark suites
+    pub suites: BenchmarkSuiteOptions<'a>,
 }
 
 #[derive(Serialize)]
@@ -323,6 +372,7 @@ pub struct BenchmarkRunInfo<'a, O: Serialize> {
     bench_options: &'a O,
     machine_name: &'static str,
     cold_start: u32,
+    cold_start_data: &'a [u32],
     repetitions: u32,
     ticks_per_ms: u32,
     data: &'a [u32],
@@ -32,10 +32,10 @@ use super::{GetCurrentTicks, PersistTrigger, PersistentStorageModule};
 const RESIDENT_CUTOFF_SIZE: usize = {
     let tmp = if size_of::<usize>() == 8 {
         // desktop with File Storage Module
-        96 + size_of::<usize>()
+        96 + size_of::<usize>() + crate::benchmarks::common::FEATURE_CUTOFF_SIZE
     } else if size_of::<usize>() == 4 {
         // zephyr with SPI Fram Storage module
-        52 + size_of::<usize>()
+        52 + size_of::<usize>() + crate::benchmarks::common::FEATURE_CUTOFF_SIZE
     } else {
         panic!("uhhm");
     };
@@ -44,7 +44,7 @@ const RESIDENT_CUTOFF_SIZE: usize = {
 };
 
 const VNV_HEAP_RAM_OVERHEAD: usize = {
-    size_of::<VNVHeap<'_, A, N, M, DummyStorageModule>>()
+    size_of::<VNVHeap<'_, A, N, M, DummyStorageModule>>() - crate::benchmarks::common::FEATURE_HEAP_SIZE
         + size_of::<VNVObject<'_, '_, (), A, N, M>>()
         + VNVHeap::<'_, A, N, M, DummyStorageModule>::get_layout_info().persist_access_point_size
 };
@@ -54,18 +54,33 @@ const VNV_HEAP_RAM_OVERHEAD: usize = {
 const MAX_DIRTY_SIZE: usize = 2 * 1024 - VNV_HEAP_RAM_OVERHEAD;
 const STEP_SIZE: usize = 32;
 
-const MIN_BUFFER_SIZE: usize = 512 - VNV_HEAP_RAM_OVERHEAD;
+/// Total RAM (buffer and heap overhead) of the smallest measured configuration.
+///
+/// Starts at 512 bytes, but the buffer has to fit the cutoff and at least one more step.
+/// As the heap is larger in test environments (see `VNVHeap::_mutex_guard`), this differs between them.
+const MIN_TOTAL_SIZE: usize = {
+    let mut total = 512;
+    while total < VNV_HEAP_RAM_OVERHEAD + RESIDENT_CUTOFF_SIZE + STEP_SIZE {
+        total += STEP_SIZE;
+    }
+    total
+};
+const MIN_BUFFER_SIZE: usize = MIN_TOTAL_SIZE - VNV_HEAP_RAM_OVERHEAD;
 const MAX_BUFFER_SIZE: usize = 4 * 1024 - VNV_HEAP_RAM_OVERHEAD;
 
 const STEP_COUNT: usize = (MAX_BUFFER_SIZE - MIN_BUFFER_SIZE) / STEP_SIZE + 1;
 
 macro_rules! for_buffer_size_impl {
     ($index: ident, $inner: expr, $value: expr) => {
-        static_assertions::const_assert_eq!($value, STEP_COUNT);
+        static_assertions::const_assert!(STEP_COUNT <= $value);
         static_assertions::const_assert_eq!((MAX_BUFFER_SIZE - MIN_BUFFER_SIZE) % STEP_SIZE, 0);
         seq_macro::seq!(I in 0..$value {
-            {
-                const $index: usize = I * STEP_SIZE + MIN_BUFFER_SIZE;
+            // iterations behind STEP_COUNT are skipped (their size is clamped to stay valid)
+            if I < STEP_COUNT {
+                const $index: usize = {
+                    let size = I * STEP_SIZE + MIN_BUFFER_SIZE;
+                    if size > MAX_BUFFER_SIZE { MAX_BUFFER_SIZE } else { size }
+                };
                 $inner
             }
         });
@@ -74,6 +89,7 @@ macro_rules! for_buffer_size_impl {
 
 macro_rules! for_buffer_size {
     ($index: ident, $inner: expr) => {
+        // the third argument has to be at least STEP_COUNT (i.e. the step count for a total size of 512 bytes)
         for_buffer_size_impl!($index, $inner, 113);
     };
 }
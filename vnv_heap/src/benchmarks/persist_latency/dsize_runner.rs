@@ -34,10 +34,10 @@ use super::{GetCurrentTicks, PersistTrigger, PersistentStorageModule};
 const RESIDENT_CUTOFF_SIZE: usize = {
     let tmp = if size_of::<usize>() == 8 {
         // desktop with File Storage Module
-        96 + size_of::<usize>()
+        96 + size_of::<usize>() + crate::benchmarks::common::FEATURE_CUTOFF_SIZE
     } else if size_of::<usize>() == 4 {
         // zephyr with SPI Fram Storage module
-        52 + size_of::<usize>()
+        52 + size_of::<usize>() + crate::benchmarks::common::FEATURE_CUTOFF_SIZE
     } else {
         panic!("uhhm");
     };
@@ -46,7 +46,7 @@ const RESIDENT_CUTOFF_SIZE: usize = {
 };
 
 const VNV_HEAP_RAM_OVERHEAD: usize = {
-    size_of::<VNVHeap<'_, A, N, M, DummyStorageModule>>()
+    size_of::<VNVHeap<'_, A, N, M, DummyStorageModule>>() - crate::benchmarks::common::FEATURE_HEAP_SIZE
         + size_of::<VNVObject<'_, '_, (), A, N, M>>()
         + VNVHeap::<'_, A, N, M, DummyStorageModule>::get_layout_info().persist_access_point_size
 };
@@ -66,7 +66,7 @@ const STEP_COUNT: usize = (MAX_DIRTY_SIZE - MIN_DIRTY_SIZE_ROUNDED) / STEP_SIZE
 
 macro_rules! for_dirty_size_impl {
     ($index: ident, $inner: expr, $value: expr) => {
-        static_assertions::const_assert_eq!($value, STEP_COUNT);
+        static_assertions::const_assert!(STEP_COUNT <= $value);
         static_assertions::const_assert_eq!((MAX_DIRTY_SIZE - MIN_DIRTY_SIZE_ROUNDED) % STEP_SIZE, 0);
         if MIN_DIRTY_SIZE != MIN_DIRTY_SIZE_ROUNDED {
             const $index: usize = MIN_DIRTY_SIZE;
@@ -74,8 +74,12 @@ macro_rules! for_dirty_size_impl {
         }
 
         seq_macro::seq!(I in 0..$value {
-            {
-                const $index: usize = I * STEP_SIZE + MIN_DIRTY_SIZE_ROUNDED;
+            // iterations behind STEP_COUNT are skipped (their size is clamped to stay valid)
+            if I < STEP_COUNT {
+                const $index: usize = {
+                    let size = I * STEP_SIZE + MIN_DIRTY_SIZE_ROUNDED;
+                    if size > MAX_DIRTY_SIZE { MAX_DIRTY_SIZE } else { size }
+                };
                 $inner
             }
         });
@@ -84,20 +88,11 @@ macro_rules! for_dirty_size_impl {
 
 macro_rules! for_dirty_size {
     ($index: ident, $inner: expr) => {
-        // the third argument has to be equal to STEP_COUNT!
+        // the third argument has to be at least STEP_COUNT!
 
-        // because of the size of the metadata
-        // STEP_COUNT has a different value for different target platforms!
-        #[cfg(target_pointer_width = "32")]
-        for_dirty_size_impl!($index, $inner, 121);
-
-        #[cfg(target_pointer_width = "64")]
-        #[cfg(test)]
-        for_dirty_size_impl!($index, $inner, 113);
-
-        #[cfg(target_pointer_width = "64")]
-        #[cfg(not(test))]
-        for_dirty_size_impl!($index, $inner, 114);
+        // because of the size of the metadata and the heap itself,
+        // STEP_COUNT has a different value for different target platforms and features
+        for_dirty_size_impl!($index, $inner, 128);
     };
 }
 
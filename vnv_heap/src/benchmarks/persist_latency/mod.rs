@@ -43,7 +43,7 @@ use crate::{
         object_management::DefaultObjectManagementModule,
         persistent_storage::PersistentStorageModule,
     },
-    vnv_persist_all, VNVConfig, VNVHeap,
+    vnv_persist_all, VNVConfig, VNVHeap, WriteBack,
 };
 
 use super::{BenchmarkRunOptions, Timer};
@@ -80,6 +80,8 @@ fn get_persist_bench_heap<'a, S: PersistentStorageModule + 'static>(
 ) -> VNVHeap<'a, A, N, M, S> {
     let config = VNVConfig {
         max_dirty_bytes: max_dirty,
+        write_back: WriteBack::Lazy,
+        persist_latency_budget: None,
     };
 
     let heap: VNVHeap<
@@ -171,6 +173,7 @@ trait PersistBenchmark<O: Serialize> {
                 bench_options: &self.get_bench_options(),
                 machine_name: options.machine_name,
                 cold_start: 0,
+                cold_start_data: &[],
                 repetitions: options.repetitions,
                 ticks_per_ms: T::get_ticks_per_ms(),
                 data: &options.result_buffer,
@@ -187,6 +190,9 @@ trait PersistBenchmark<O: Serialize> {
             res.min_latency,
             res.max_latency
         );
+        if let Some(comparison) = options.comparison.as_deref_mut() {
+            comparison.compare_and_print(self.get_name(), &self.get_bench_options(), T::get_ticks_per_ms(), options.result_buffer);
+        }
         println!();
 
         unsafe {
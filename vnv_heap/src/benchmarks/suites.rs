@@ -0,0 +1,124 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::BenchmarkRunOptions;
+
+/// Groups of benchmarks whose run options can be overridden with `BenchmarkSuiteOptions`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BenchmarkSuite {
+    /// `allocate`, `deallocate` and `get` benchmarks of the vNV-Heap
+    Implementation,
+    /// `allocate`, `deallocate` and `get` benchmarks of the baseline
+    Baseline,
+    PersistentStorage,
+    LongPersistentStorage,
+    DirtySizePersistLatency,
+    BufferSizePersistLatency,
+    Queue,
+    KeyValueStore,
+    LockedWCET,
+}
+
+impl BenchmarkSuite {
+    const COUNT: usize = 9;
+}
+
+/// Options of a single suite, `None` falls back to the value of `BenchmarkRunOptions`
+#[derive(Debug, Clone, Copy, Default)]
+struct SuiteOverride<'a> {
+    repetitions: Option<u32>,
+    cold_start: Option<u32>,
+    sizes: Option<&'a [usize]>,
+}
+
+/// Builder for per-suite overrides of `BenchmarkRunOptions`.
+///
+/// For example, long-running storage benchmarks can use fewer repetitions than fast `get` microbenchmarks:
+///
+/// ```ignore
+/// let suites = BenchmarkSuiteOptions::new()
+///     .repetitions(BenchmarkSuite::LongPersistentStorage, 2)
+///     .cold_start(BenchmarkSuite::LongPersistentStorage, 0)
+///     .sizes(BenchmarkSuite::Implementation, &[16, 256, 1024]);
+/// ```
+///
+/// The result buffers of `BenchmarkRunOptions` are shared by all suites,
+/// so overridden repetitions and cold starts must not exceed their lengths.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BenchmarkSuiteOptions<'a> {
+    overrides: [SuiteOverride<'a>; BenchmarkSuite::COUNT],
+}
+
+impl<'a> BenchmarkSuiteOptions<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs each benchmark of `suite` `repetitions` times
+    pub fn repetitions(mut self, suite: BenchmarkSuite, repetitions: u32) -> Self {
+        self.overrides[suite as usize].repetitions = Some(repetitions);
+        self
+    }
+
+    /// Records `cold_start` runs before the actual measurements of each benchmark of `suite`
+    pub fn cold_start(mut self, suite: BenchmarkSuite, cold_start: u32) -> Self {
+        self.overrides[suite as usize].cold_start = Some(cold_start);
+        self
+    }
+
+    /// Only runs benchmarks of `suite` with one of these object sizes (see `BenchmarkRunOptions::sizes`)
+    pub fn sizes(mut self, suite: BenchmarkSuite, sizes: &'a [usize]) -> Self {
+        self.overrides[suite as usize].sizes = Some(sizes);
+        self
+    }
+}
+
+impl BenchmarkRunOptions<'_> {
+    /// Returns the options that are used to run the benchmarks of `suite`
+    pub fn for_suite(&mut self, suite: BenchmarkSuite) -> BenchmarkRunOptions<'_> {
+        let suite_override = self.suites.overrides[suite as usize];
+        let repetitions = suite_override.repetitions.unwrap_or(self.repetitions);
+        let cold_start = suite_override.cold_start.unwrap_or(self.cold_start);
+
+        assert!(
+            repetitions as usize <= self.result_buffer.len(),
+            "result buffer is too small for the repetitions of {:?}",
+            suite
+        );
+        assert!(
+            cold_start as usize <= self.cold_start_buffer.len(),
+            "cold start buffer is too small for the cold starts of {:?}",
+            suite
+        );
+
+        BenchmarkRunOptions {
+            repetitions,
+            result_buffer: &mut self.result_buffer[..repetitions as usize],
+            cold_start,
+            cold_start_buffer: &mut self.cold_start_buffer[..cold_start as usize],
+            machine_name: self.machine_name,
+            comparison: self.comparison.as_deref_mut(),
+            sizes: suite_override.sizes.or(self.sizes),
+            suites: BenchmarkSuiteOptions::new(),
+        }
+    }
+
+    /// Returns `true` if benchmarks with objects of `size` bytes should be run
+    pub(crate) fn runs_size(&self, size: usize) -> bool {
+        self.sizes.map_or(true, |sizes| sizes.contains(&size))
+    }
+}
@@ -0,0 +1,303 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{
+    alloc::Layout,
+    cell::UnsafeCell,
+    ffi::c_void,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{
+    modules::{
+        allocator::LinkedListAllocatorModule,
+        nonresident_allocator::NonResidentBuddyAllocatorModule,
+        object_management::DefaultObjectManagementModule,
+        persistent_storage::{CallbackStorageModule, VNVStorageCallbacks},
+    },
+    vnv_persist_all, VNVConfig, VNVError, VNVHeap, VNVRawObject, WriteBack,
+};
+
+/// How many objects can be allocated with `vnv_alloc` at the same time
+pub const CAPI_OBJECT_CAPACITY: usize = 64;
+
+type CApiHeap = VNVHeap<
+    'static,
+    LinkedListAllocatorModule,
+    NonResidentBuddyAllocatorModule<16>,
+    DefaultObjectManagementModule,
+    CallbackStorageModule,
+>;
+
+type CApiObject = VNVRawObject<
+    'static,
+    'static,
+    LinkedListAllocatorModule,
+    NonResidentBuddyAllocatorModule<16>,
+    DefaultObjectManagementModule,
+>;
+
+/// Result of the C API functions, the errors match `VNVError`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VNVStatus {
+    Ok = 0,
+    ResidentBufferExhausted = -1,
+    ResidentObjectsInUse = -2,
+    PinnedBytesExhausted = -3,
+    DirtyBudgetExhausted = -4,
+    DirtyPoolExhausted = -5,
+    NonResidentSpaceExhausted = -6,
+    StorageError = -7,
+    CorruptedData = -8,
+    ObjectInUse = -9,
+    NotResident = -10,
+    RootsExhausted = -11,
+    CapacityExhausted = -12,
+    Unsupported = -13,
+    SerializationFailed = -14,
+
+    /// The heap was not initialized with `vnv_heap_init`
+    NotInitialized = -15,
+
+    /// The handle does not belong to an allocated object
+    InvalidHandle = -16,
+
+    /// An argument is invalid (e.g. a null pointer or an alignment that is not a power of two)
+    InvalidArgument = -17,
+}
+
+impl From<VNVError> for VNVStatus {
+    fn from(err: VNVError) -> Self {
+        match err {
+            VNVError::ResidentBufferExhausted => VNVStatus::ResidentBufferExhausted,
+            VNVError::ResidentObjectsInUse => VNVStatus::ResidentObjectsInUse,
+            VNVError::PinnedBytesExhausted => VNVStatus::PinnedBytesExhausted,
+            VNVError::DirtyBudgetExhausted => VNVStatus::DirtyBudgetExhausted,
+            VNVError::DirtyPoolExhausted => VNVStatus::DirtyPoolExhausted,
+            VNVError::NonResidentSpaceExhausted => VNVStatus::NonResidentSpaceExhausted,
+            VNVError::StorageError => VNVStatus::StorageError,
+            VNVError::CorruptedData => VNVStatus::CorruptedData,
+            VNVError::ObjectInUse => VNVStatus::ObjectInUse,
+            VNVError::NotResident => VNVStatus::NotResident,
+            VNVError::RootsExhausted => VNVStatus::RootsExhausted,
+            VNVError::CapacityExhausted => VNVStatus::CapacityExhausted,
+            VNVError::Unsupported => VNVStatus::Unsupported,
+            VNVError::SerializationFailed => VNVStatus::SerializationFailed,
+        }
+    }
+}
+
+/// Opaque handle of an object that was allocated with `vnv_alloc` (`0` is never a valid handle)
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VNVHandle(u32);
+
+/// Heap of the C API and the objects that C code allocated
+struct CApiState {
+    initialized: AtomicBool,
+    heap: UnsafeCell<MaybeUninit<CApiHeap>>,
+    objects: UnsafeCell<[Option<CApiObject>; CAPI_OBJECT_CAPACITY]>,
+}
+
+// the state is only accessed through the C API functions, which may only be called from one thread
+// (see `vnv_heap_init`), so this is sound for the same reasons as for `StaticVNVHeap`
+unsafe impl Sync for CApiState {}
+
+const NO_OBJECT: Option<CApiObject> = None;
+
+static STATE: CApiState = CApiState {
+    initialized: AtomicBool::new(false),
+    heap: UnsafeCell::new(MaybeUninit::uninit()),
+    objects: UnsafeCell::new([NO_OBJECT; CAPI_OBJECT_CAPACITY]),
+};
+
+unsafe fn get_heap() -> Result<&'static CApiHeap, VNVStatus> {
+    if !STATE.initialized.load(Ordering::SeqCst) {
+        return Err(VNVStatus::NotInitialized);
+    }
+    Ok((*STATE.heap.get()).assume_init_ref())
+}
+
+unsafe fn get_object(handle: VNVHandle) -> Result<&'static mut CApiObject, VNVStatus> {
+    get_heap()?;
+    let index = (handle.0 as usize).checked_sub(1).ok_or(VNVStatus::InvalidHandle)?;
+    (*STATE.objects.get())
+        .get_mut(index)
+        .and_then(|slot| slot.as_mut())
+        .ok_or(VNVStatus::InvalidHandle)
+}
+
+fn to_status(res: Result<(), VNVStatus>) -> VNVStatus {
+    match res {
+        Ok(()) => VNVStatus::Ok,
+        Err(status) => status,
+    }
+}
+
+/// Creates the heap with the resident buffer `buffer` of `buffer_size` bytes and the storage accessed by `storage`.
+///
+/// Fails with `VNVStatus::Unsupported` if the heap was already initialized.
+///
+/// ### Safety
+///
+/// `buffer` must not be used by anything else until `vnv_heap_deinit` is called and `storage` has to fulfill the
+/// requirements of `CallbackStorageModule::new`. All C API functions may only be called by one thread
+/// (interrupts by `vnv_persist` are fine).
+// unit tests link this crate twice (as it is also its own dev-dependency), so the symbols are only exported outside of tests
+#[cfg_attr(not(test), no_mangle)]
+pub unsafe extern "C" fn vnv_heap_init(
+    buffer: *mut u8,
+    buffer_size: usize,
+    storage: VNVStorageCallbacks,
+    max_dirty_bytes: usize,
+) -> VNVStatus {
+    if buffer.is_null() {
+        return VNVStatus::InvalidArgument;
+    }
+    if STATE.initialized.load(Ordering::SeqCst) {
+        return VNVStatus::Unsupported;
+    }
+
+    let resident_buffer = core::slice::from_raw_parts_mut(buffer, buffer_size);
+    let config = VNVConfig {
+        max_dirty_bytes,
+        write_back: WriteBack::Lazy,
+        persist_latency_budget: None,
+    };
+    match VNVHeap::new(
+        resident_buffer,
+        CallbackStorageModule::new(storage),
+        LinkedListAllocatorModule::new(),
+        config,
+        |_, _| {},
+    ) {
+        Ok(heap) => {
+            (*STATE.heap.get()).write(heap);
+            STATE.initialized.store(true, Ordering::SeqCst);
+            VNVStatus::Ok
+        }
+        Err(err) => err.into(),
+    }
+}
+
+/// Frees all objects and drops the heap, so `vnv_heap_init` can be called again.
+///
+/// ### Safety
+///
+/// Pointers returned by `vnv_get` must not be used anymore.
+#[cfg_attr(not(test), no_mangle)]
+pub unsafe extern "C" fn vnv_heap_deinit() -> VNVStatus {
+    if !STATE.initialized.load(Ordering::SeqCst) {
+        return VNVStatus::NotInitialized;
+    }
+
+    // objects borrow the heap, so they are dropped first
+    for slot in (*STATE.objects.get()).iter_mut() {
+        *slot = None;
+    }
+    STATE.initialized.store(false, Ordering::SeqCst);
+    (*STATE.heap.get()).assume_init_drop();
+    VNVStatus::Ok
+}
+
+/// Allocates a zeroed object of `size` bytes aligned to `align` bytes and stores its handle in `handle`
+/// (see `VNVHeap::allocate_layout`).
+///
+/// ### Safety
+///
+/// `handle` has to be valid for writes.
+#[cfg_attr(not(test), no_mangle)]
+pub unsafe extern "C" fn vnv_alloc(size: usize, align: usize, handle: *mut VNVHandle) -> VNVStatus {
+    to_status((|| {
+        let heap = get_heap()?;
+        if handle.is_null() {
+            return Err(VNVStatus::InvalidArgument);
+        }
+        let layout = Layout::from_size_align(size, align).map_err(|_| VNVStatus::InvalidArgument)?;
+
+        let objects = &mut *STATE.objects.get();
+        let index = objects
+            .iter()
+            .position(|slot| slot.is_none())
+            .ok_or(VNVStatus::CapacityExhausted)?;
+        objects[index] = Some(heap.allocate_layout(layout)?);
+
+        *handle = VNVHandle(index as u32 + 1);
+        Ok(())
+    })())
+}
+
+/// Frees the object of `handle`, pointers returned by `vnv_get` for it must not be used anymore
+#[cfg_attr(not(test), no_mangle)]
+pub unsafe extern "C" fn vnv_free(handle: VNVHandle) -> VNVStatus {
+    to_status((|| {
+        get_object(handle)?;
+        (*STATE.objects.get())[handle.0 as usize - 1] = None;
+        Ok(())
+    })())
+}
+
+/// Makes the object of `handle` resident and stores a pointer to its data in `ptr`.
+///
+/// The pointer is valid until `vnv_release` or `vnv_free` is called for the object. Modified bytes have to be
+/// marked with `vnv_mark_dirty` before they are modified, otherwise the changes can be lost (see `VNVRawObject`).
+///
+/// ### Safety
+///
+/// `ptr` has to be valid for writes.
+#[cfg_attr(not(test), no_mangle)]
+pub unsafe extern "C" fn vnv_get(handle: VNVHandle, ptr: *mut *mut c_void) -> VNVStatus {
+    to_status((|| {
+        let object = get_object(handle)?;
+        if ptr.is_null() {
+            return Err(VNVStatus::InvalidArgument);
+        }
+
+        *ptr = object.get_ptr()? as *mut c_void;
+        Ok(())
+    })())
+}
+
+/// Marks `len` bytes starting at `offset` of the object of `handle` as dirty, `vnv_get` has to be called before
+#[cfg_attr(not(test), no_mangle)]
+pub unsafe extern "C" fn vnv_mark_dirty(handle: VNVHandle, offset: usize, len: usize) -> VNVStatus {
+    to_status((|| {
+        let object = get_object(handle)?;
+        if offset.checked_add(len).map_or(true, |end| end > object.len()) {
+            return Err(VNVStatus::InvalidArgument);
+        }
+
+        Ok(object.mark_dirty(offset, len)?)
+    })())
+}
+
+/// Invalidates the pointer returned by `vnv_get`, so that the object of `handle` can be unloaded again
+#[cfg_attr(not(test), no_mangle)]
+pub unsafe extern "C" fn vnv_release(handle: VNVHandle) -> VNVStatus {
+    to_status((|| {
+        get_object(handle)?.release();
+        Ok(())
+    })())
+}
+
+/// Persists the heap (see `vnv_persist_all`), e.g. from the interrupt of a power failure
+#[cfg_attr(not(test), no_mangle)]
+pub unsafe extern "C" fn vnv_persist() {
+    vnv_persist_all();
+}
@@ -0,0 +1,101 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::vnv_error::VNVError;
+
+/// System-wide budget of bytes that can be written back in the power-fail window.
+///
+/// Heaps draw their maximum dirty bytes from the pool (see `VNVHeap::set_dirty_budget_pool`),
+/// so that the dirty data of all registered heaps never exceeds the budget in total.
+/// Other components that have to be saved in the same window (e.g. in a post persist hook)
+/// can reserve their share with `try_reserve` as well.
+pub struct DirtyBudgetPool {
+    available_bytes: AtomicUsize,
+}
+
+impl DirtyBudgetPool {
+    pub const fn new(total_bytes: usize) -> Self {
+        Self {
+            available_bytes: AtomicUsize::new(total_bytes),
+        }
+    }
+
+    /// Returns how many bytes are not reserved right now
+    pub fn get_available_bytes(&self) -> usize {
+        self.available_bytes.load(Ordering::SeqCst)
+    }
+
+    /// Reserves `bytes` from the pool.
+    ///
+    /// Returns `false` and reserves nothing if less than `bytes` are available.
+    pub fn try_reserve(&self, bytes: usize) -> bool {
+        self.available_bytes
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |available| available.checked_sub(bytes))
+            .is_ok()
+    }
+
+    /// Returns `bytes` that were reserved with `try_reserve` to the pool
+    pub fn release(&self, bytes: usize) {
+        self.available_bytes.fetch_add(bytes, Ordering::SeqCst);
+    }
+}
+
+/// Bytes a heap has drawn from a `DirtyBudgetPool`, they are returned to the pool when this is dropped
+pub(crate) struct DirtyBudgetReservation {
+    pool: &'static DirtyBudgetPool,
+    reserved_bytes: usize,
+}
+
+impl DirtyBudgetReservation {
+    pub(crate) fn new(pool: &'static DirtyBudgetPool, bytes: usize) -> Result<Self, VNVError> {
+        if !pool.try_reserve(bytes) {
+            return Err(VNVError::DirtyBudgetExhausted);
+        }
+
+        Ok(Self {
+            pool,
+            reserved_bytes: bytes,
+        })
+    }
+
+    pub(crate) fn is_from(&self, pool: &DirtyBudgetPool) -> bool {
+        core::ptr::eq(self.pool, pool)
+    }
+
+    /// Changes the reserved bytes to `bytes`.
+    ///
+    /// Growing fails with `VNVError::DirtyBudgetExhausted` if the pool has not enough bytes left, shrinking always succeeds.
+    pub(crate) fn resize(&mut self, bytes: usize) -> Result<(), VNVError> {
+        if bytes > self.reserved_bytes {
+            if !self.pool.try_reserve(bytes - self.reserved_bytes) {
+                return Err(VNVError::DirtyBudgetExhausted);
+            }
+        } else {
+            self.pool.release(self.reserved_bytes - bytes);
+        }
+
+        self.reserved_bytes = bytes;
+        Ok(())
+    }
+}
+
+impl Drop for DirtyBudgetReservation {
+    fn drop(&mut self) {
+        self.pool.release(self.reserved_bytes);
+    }
+}
@@ -0,0 +1,135 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::{
+    modules::{
+        allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
+        object_management::ObjectManagementModule, persistent_storage::PersistentStorageModule,
+    },
+    vnv_config::StorageThroughput,
+    vnv_error::VNVError,
+    vnv_heap::VNVHeap,
+};
+
+/// Energy model of the capacitor that powers the device while persisting (see `EnergyMonitor`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnergyConfig {
+    /// Capacitance of the energy buffer in µF
+    pub capacitance_uf: u32,
+
+    /// Supply voltage below which the device stops working in mV (e.g. its brown-out voltage)
+    pub min_voltage_mv: u32,
+
+    /// Supply voltage at which persisting is triggered in mV
+    pub persist_threshold_mv: u32,
+
+    /// Average power consumption of the device while persisting in mW
+    pub persist_power_mw: u32,
+
+    /// How fast the persistent storage module can be written to
+    pub write_throughput: StorageThroughput,
+
+    /// Share of the stored energy that is not used for persisting (e.g. for tolerances of the capacitor) in percent
+    pub safety_margin_percent: u8,
+}
+
+impl EnergyConfig {
+    /// Returns the energy in nJ that is stored in the capacitor at `voltage_mv` and can be used before the device stops working
+    pub const fn available_energy_nj(&self, voltage_mv: u32) -> u64 {
+        if voltage_mv <= self.min_voltage_mv {
+            return 0;
+        }
+
+        // E = C * (V² - V_min²) / 2 (µF * mV² = pJ)
+        let voltage_squared = voltage_mv as u64 * voltage_mv as u64;
+        let min_voltage_squared = self.min_voltage_mv as u64 * self.min_voltage_mv as u64;
+        self.capacitance_uf as u64 * (voltage_squared - min_voltage_squared) / 2 / 1000
+    }
+
+    /// Returns how long persisting may take in µs if it starts at `voltage_mv` (without the safety margin)
+    pub const fn persist_time_us(&self, voltage_mv: u32) -> u32 {
+        let margin = if self.safety_margin_percent > 100 { 100 } else { self.safety_margin_percent as u64 };
+        let energy_nj = self.available_energy_nj(voltage_mv) * (100 - margin) / 100;
+
+        // nJ / mW = µs
+        let time_us = energy_nj / if self.persist_power_mw == 0 { 1 } else { self.persist_power_mw as u64 };
+        if time_us > u32::MAX as u64 {
+            u32::MAX
+        } else {
+            time_us as u32
+        }
+    }
+
+    /// Returns how many bytes can be dirty if the supply voltage is `voltage_mv` right now.
+    ///
+    /// Persisting starts at `persist_threshold_mv` at the latest, so higher voltages do not increase the budget.
+    pub const fn safe_dirty_bytes(&self, voltage_mv: u32) -> usize {
+        let voltage_mv = if voltage_mv < self.persist_threshold_mv { voltage_mv } else { self.persist_threshold_mv };
+        self.write_throughput.max_bytes(self.persist_time_us(voltage_mv))
+    }
+}
+
+/// Measures the supply voltage with a user-defined callback (e.g. reading an ADC)
+/// and derives when to persist and how many bytes can be dirty from it.
+///
+/// Call `update_dirty_limit` periodically, so the dirty bytes of the heap are limited
+/// to what can be persisted with the energy that is left.
+pub struct EnergyMonitor {
+    read_voltage_mv: fn() -> u32,
+    config: EnergyConfig,
+}
+
+impl EnergyMonitor {
+    /// `read_voltage_mv` has to return the current supply voltage in mV
+    pub const fn new(read_voltage_mv: fn() -> u32, config: EnergyConfig) -> Self {
+        Self { read_voltage_mv, config }
+    }
+
+    pub fn get_config(&self) -> &EnergyConfig {
+        &self.config
+    }
+
+    /// Returns the current supply voltage in mV
+    pub fn read_voltage_mv(&self) -> u32 {
+        (self.read_voltage_mv)()
+    }
+
+    /// Returns `true` if the supply voltage reached `EnergyConfig::persist_threshold_mv`, i.e. `vnv_persist_all` should be called now
+    pub fn should_persist(&self) -> bool {
+        self.read_voltage_mv() <= self.config.persist_threshold_mv
+    }
+
+    /// Returns how many bytes can be dirty with the current supply voltage (see `EnergyConfig::safe_dirty_bytes`)
+    pub fn safe_dirty_bytes(&self) -> usize {
+        self.config.safe_dirty_bytes(self.read_voltage_mv())
+    }
+
+    /// Limits the dirty bytes of `heap` to `safe_dirty_bytes` (see `VNVHeap::set_max_dirty_bytes`) and returns the new limit
+    pub fn update_dirty_limit<
+        A: AllocatorModule + 'static,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+        S: PersistentStorageModule + 'static,
+    >(
+        &self,
+        heap: &VNVHeap<'_, A, N, M, S>,
+    ) -> Result<usize, VNVError> {
+        let max_dirty_bytes = self.safe_dirty_bytes().min(heap.get_dirty_capacity());
+        heap.set_max_dirty_bytes(max_dirty_bytes)?;
+        Ok(max_dirty_bytes)
+    }
+}
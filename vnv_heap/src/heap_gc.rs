@@ -0,0 +1,241 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{
+    alloc::Layout,
+    mem::{size_of, ManuallyDrop, MaybeUninit},
+    slice,
+};
+
+use crate::{heap_recovery::PendingRelocations, vnv_link::VNVLink, VNVError};
+
+/// Number of objects that are tracked in the allocation table of `VNVHeap::gc`.
+///
+/// Objects that are allocated while the table is full are not tracked, so they are never reclaimed.
+pub const ALLOCATION_TABLE_CAPACITY: usize = 32;
+
+// the slots are stored as a bitmap
+static_assertions::const_assert!(ALLOCATION_TABLE_CAPACITY <= u64::BITS as usize);
+
+/// Offset of an entry that is not used
+const EMPTY_ALLOCATION_OFFSET: u64 = u64::MAX;
+
+/// Entry of the allocation table in the recovery area, all values are stored in little endian.
+///
+/// The table only contains objects that are allocated. Objects are always stored byte aligned
+/// (see `calc_backup_obj_layout_static`), so their size is enough to deallocate them.
+/// Entries are added after an object was allocated and removed before it is deallocated,
+/// so an interrupted (de)allocation can only leak the object, but never free it twice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct AllocationEntry {
+    pub(crate) offset: usize,
+    pub(crate) size: usize,
+}
+
+impl AllocationEntry {
+    pub(crate) const SERIALIZED_SIZE: usize = 2 * size_of::<u64>();
+
+    pub(crate) const EMPTY: AllocationEntry = AllocationEntry {
+        offset: EMPTY_ALLOCATION_OFFSET as usize,
+        size: 0,
+    };
+
+    pub(crate) fn new(offset: usize, layout: Layout) -> Self {
+        debug_assert_eq!(layout.align(), 1, "objects are stored byte aligned");
+        Self {
+            offset,
+            size: layout.size(),
+        }
+    }
+
+    pub(crate) fn to_bytes(&self) -> [u8; Self::SERIALIZED_SIZE] {
+        let mut bytes = [0u8; Self::SERIALIZED_SIZE];
+        let values = [self.offset as u64, self.size as u64];
+        for (chunk, value) in bytes.chunks_exact_mut(size_of::<u64>()).zip(values) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub(crate) fn from_bytes(bytes: &[u8; Self::SERIALIZED_SIZE]) -> AllocationEntry {
+        let mut values = [0u64; 2];
+        for (value, chunk) in values.iter_mut().zip(bytes.chunks_exact(size_of::<u64>())) {
+            *value = u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+        if values[0] == EMPTY_ALLOCATION_OFFSET {
+            return AllocationEntry::EMPTY;
+        }
+        AllocationEntry {
+            offset: values[0] as usize,
+            size: values[1] as usize,
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        *self == AllocationEntry::EMPTY
+    }
+
+    pub(crate) fn get_layout(&self) -> Result<Layout, VNVError> {
+        Layout::from_size_align(self.size, 1).map_err(|_| VNVError::CorruptedData)
+    }
+
+    /// Returns `true` if `offset` points into this allocation (e.g. to a part of a split object)
+    pub(crate) fn contains(&self, offset: usize) -> bool {
+        !self.is_empty() && offset >= self.offset && offset - self.offset < self.size.max(1)
+    }
+}
+
+/// Set of slots of the allocation table, one bit per slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct AllocationSlots(u64);
+
+impl AllocationSlots {
+    pub(crate) const EMPTY: AllocationSlots = AllocationSlots(0);
+
+    pub(crate) fn contains(&self, slot: usize) -> bool {
+        self.0 & (1 << slot) != 0
+    }
+
+    pub(crate) fn insert(&mut self, slot: usize) {
+        self.0 |= 1 << slot;
+    }
+
+    pub(crate) fn remove(&mut self, slot: usize) {
+        self.0 &= !(1 << slot);
+    }
+
+    /// Returns the first slot that is not in this set
+    pub(crate) fn find_free(&self) -> Option<usize> {
+        (0..ALLOCATION_TABLE_CAPACITY).find(|slot| !self.contains(*slot))
+    }
+
+    pub(crate) fn iter(self) -> impl Iterator<Item = usize> {
+        (0..ALLOCATION_TABLE_CAPACITY).filter(move |slot| self.contains(*slot))
+    }
+}
+
+/// Types that store links to other objects of the heap.
+///
+/// `VNVHeap::gc` uses this to find all objects that are reachable from the roots.
+pub trait VNVTrace {
+    /// Passes every link that is stored in `self` to `marker.mark` (or `marker.mark_leaf`)
+    fn trace(&self, marker: &mut GcMarker);
+}
+
+/// Access to the heap while marking reachable objects
+pub(crate) trait GcHeap {
+    /// Returns the slot of the allocation that contains `offset`, or `None` if it is not tracked
+    fn find_allocation(&mut self, offset: usize) -> Result<Option<usize>, VNVError>;
+
+    /// Copies the current user data of the object at `offset` to `data` without making it resident
+    fn read_object_data(&mut self, offset: usize, data: &mut [u8]) -> Result<(), VNVError>;
+}
+
+/// Reads the object at `offset` and marks the objects it links to
+type TraceFn = fn(&mut GcMarker, usize) -> Result<(), VNVError>;
+
+/// Marks the objects that are reachable from the roots of `VNVHeap::gc`
+pub struct GcMarker<'m> {
+    heap: &'m mut dyn GcHeap,
+
+    /// Links to objects that were moved after the last committed persist are resolved (see `VNVLinkFixup`)
+    relocations: PendingRelocations,
+    marked: AllocationSlots,
+    traced: AllocationSlots,
+
+    /// Objects that were marked, but whose links were not marked yet
+    pending: [Option<(usize, TraceFn)>; ALLOCATION_TABLE_CAPACITY],
+    error: Option<VNVError>,
+}
+
+impl<'m> GcMarker<'m> {
+    pub(crate) fn new(heap: &'m mut dyn GcHeap, relocations: PendingRelocations) -> Self {
+        Self {
+            heap,
+            relocations,
+            marked: AllocationSlots::EMPTY,
+            traced: AllocationSlots::EMPTY,
+            pending: [None; ALLOCATION_TABLE_CAPACITY],
+            error: None,
+        }
+    }
+
+    /// Marks the object `link` points to and all objects that are reachable from it as reachable
+    pub fn mark<T: VNVTrace>(&mut self, link: &VNVLink<T>) {
+        self.mark_offset(link.get_alloc_id().offset, Some(trace_object::<T>));
+    }
+
+    /// Marks the object `link` points to as reachable, links that are stored in it are not followed
+    pub fn mark_leaf<T: Sized>(&mut self, link: &VNVLink<T>) {
+        self.mark_offset(link.get_alloc_id().offset, None);
+    }
+
+    fn mark_offset(&mut self, offset: usize, tracer: Option<TraceFn>) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let offset = self.relocations.resolve(offset);
+        let slot = match self.heap.find_allocation(offset) {
+            Ok(Some(slot)) => slot,
+            // untracked objects are never reclaimed anyway
+            Ok(None) => return,
+            Err(err) => {
+                self.error = Some(err);
+                return;
+            }
+        };
+
+        self.marked.insert(slot);
+        if let Some(tracer) = tracer {
+            // the object may have been marked with `mark_leaf` before
+            if !self.traced.contains(slot) {
+                self.traced.insert(slot);
+                self.pending[slot] = Some((offset, tracer));
+            }
+        }
+    }
+
+    /// Follows the links of all marked objects and returns the slots of the reachable objects
+    pub(crate) fn finish(mut self) -> Result<AllocationSlots, VNVError> {
+        // objects are traced one after another instead of recursively, so long lists do not exhaust the stack
+        while let Some(slot) = self.pending.iter().position(|pending| pending.is_some()) {
+            if let Some(err) = self.error {
+                return Err(err);
+            }
+            let (offset, tracer) = self.pending[slot].take().unwrap();
+            tracer(&mut self, offset)?;
+        }
+
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(self.marked),
+        }
+    }
+}
+
+fn trace_object<T: VNVTrace>(marker: &mut GcMarker, offset: usize) -> Result<(), VNVError> {
+    let mut data = MaybeUninit::<T>::uninit();
+    marker.heap.read_object_data(offset, unsafe {
+        slice::from_raw_parts_mut(data.as_mut_ptr() as *mut u8, size_of::<T>())
+    })?;
+
+    // this is only a copy of the object, so it must not be dropped
+    let data = ManuallyDrop::new(unsafe { data.assume_init() });
+    data.trace(marker);
+    Ok(())
+}
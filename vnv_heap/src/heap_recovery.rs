@@ -0,0 +1,503 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::mem::size_of;
+
+use crate::{
+    heap_snapshot::SnapshotHeader,
+    modules::persistent_storage::{persistent_storage_util::read_storage_data, PersistentStorageModule},
+    util::{crc32, crc32_update},
+};
+#[cfg(feature = "allocator_journal")]
+use crate::allocator_journal::AllocatorJournal;
+#[cfg(feature = "gc")]
+use crate::heap_gc::{AllocationEntry, ALLOCATION_TABLE_CAPACITY};
+
+/// Number of roots that can be stored with `VNVHeap::set_root`
+pub const RECOVERY_ROOT_COUNT: usize = 8;
+
+/// Key of a root that is not used
+const EMPTY_ROOT_KEY: u64 = u64::MAX;
+
+/// Bit that is set in the encoded keys of names, so they do not collide with ids
+const ROOT_NAME_BIT: u64 = 1 << 63;
+
+/// Key of a root of the heap (see `VNVHeap::set_root`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootKey<'a> {
+    /// Names are only stored as 64-bit hash, so two names could refer to the same root
+    Name(&'a str),
+    Id(u32),
+}
+
+impl RootKey<'_> {
+    pub(crate) fn encode(&self) -> u64 {
+        match self {
+            RootKey::Name(name) => {
+                // FNV-1a
+                let mut hash: u64 = 0xcbf29ce484222325;
+                for byte in name.bytes() {
+                    hash ^= byte as u64;
+                    hash = hash.wrapping_mul(0x100000001b3);
+                }
+                (hash | ROOT_NAME_BIT).min(EMPTY_ROOT_KEY - 1)
+            }
+            RootKey::Id(id) => *id as u64,
+        }
+    }
+}
+
+impl<'a> From<&'a str> for RootKey<'a> {
+    fn from(name: &'a str) -> Self {
+        RootKey::Name(name)
+    }
+}
+
+impl From<u32> for RootKey<'_> {
+    fn from(id: u32) -> Self {
+        RootKey::Id(id)
+    }
+}
+
+/// Entry of the root registry in the recovery area, all values are stored in little endian
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RootEntry {
+    pub(crate) key: u64,
+    pub(crate) offset: usize,
+
+    /// Size of the object, so accessing a root with the wrong type is detected (in most cases)
+    pub(crate) size: usize,
+}
+
+impl RootEntry {
+    pub(crate) const SERIALIZED_SIZE: usize = 3 * size_of::<u64>();
+
+    pub(crate) const EMPTY: RootEntry = RootEntry {
+        key: EMPTY_ROOT_KEY,
+        offset: 0,
+        size: 0,
+    };
+
+    pub(crate) fn to_bytes(&self) -> [u8; Self::SERIALIZED_SIZE] {
+        let mut bytes = [0u8; Self::SERIALIZED_SIZE];
+        let values = [self.key, self.offset as u64, self.size as u64];
+        for (chunk, value) in bytes.chunks_exact_mut(size_of::<u64>()).zip(values) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub(crate) fn from_bytes(bytes: &[u8; Self::SERIALIZED_SIZE]) -> RootEntry {
+        let mut values = [0u64; 3];
+        for (value, chunk) in values.iter_mut().zip(bytes.chunks_exact(size_of::<u64>())) {
+            *value = u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+        RootEntry {
+            key: values[0],
+            offset: values[1] as usize,
+            size: values[2] as usize,
+        }
+    }
+}
+
+/// Number of objects that `VNVHeap::defragment` can move between two persists (see `RelocationEntry`)
+pub const RELOCATION_LOG_COUNT: usize = 4;
+
+/// Entry of the relocation log in the recovery area, all values are stored in little endian.
+///
+/// Each object that is moved by `VNVHeap::defragment` is logged, as links to it that are stored in other objects
+/// are only updated in the resident buffer. If the power fails before the next persist commits,
+/// these updates are lost and `VNVHeap::recover_with_fixups` and `VNVHeap::fixup_links` use the log instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RelocationEntry {
+    /// Commit number of the first persist after the object was moved,
+    /// the entry is not needed anymore once this persist committed
+    pub(crate) sequence: u64,
+    pub(crate) old_offset: usize,
+    pub(crate) new_offset: usize,
+}
+
+impl RelocationEntry {
+    pub(crate) const SERIALIZED_SIZE: usize = 3 * size_of::<u64>();
+
+    /// Commit numbers start at 1, so this entry is never pending
+    pub(crate) const EMPTY: RelocationEntry = RelocationEntry {
+        sequence: 0,
+        old_offset: 0,
+        new_offset: 0,
+    };
+
+    pub(crate) fn to_bytes(&self) -> [u8; Self::SERIALIZED_SIZE] {
+        let mut bytes = [0u8; Self::SERIALIZED_SIZE];
+        let values = [self.sequence, self.old_offset as u64, self.new_offset as u64];
+        for (chunk, value) in bytes.chunks_exact_mut(size_of::<u64>()).zip(values) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub(crate) fn from_bytes(bytes: &[u8; Self::SERIALIZED_SIZE]) -> RelocationEntry {
+        let mut values = [0u64; 3];
+        for (value, chunk) in values.iter_mut().zip(bytes.chunks_exact(size_of::<u64>())) {
+            *value = u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+        RelocationEntry {
+            sequence: values[0],
+            old_offset: values[1] as usize,
+            new_offset: values[2] as usize,
+        }
+    }
+
+    /// Returns `true` if the object was moved after the persist with the commit number `committed_sequence`
+    pub(crate) fn is_pending(&self, committed_sequence: u64) -> bool {
+        self.sequence > committed_sequence
+    }
+}
+
+/// Relocations of the log that happened after the last committed persist
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PendingRelocations {
+    entries: [RelocationEntry; RELOCATION_LOG_COUNT],
+    len: usize,
+}
+
+impl PendingRelocations {
+    pub(crate) const fn new() -> Self {
+        Self {
+            entries: [RelocationEntry::EMPTY; RELOCATION_LOG_COUNT],
+            len: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, entry: RelocationEntry) {
+        self.entries[self.len] = entry;
+        self.len += 1;
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the current offset of the object that was located at `offset`.
+    ///
+    /// Objects are only moved to lower offsets, so following an object that was moved multiple times terminates.
+    pub(crate) fn resolve(&self, offset: usize) -> usize {
+        let mut offset = offset;
+        while let Some(entry) = self.entries[..self.len].iter().find(|entry| entry.old_offset == offset) {
+            offset = entry.new_offset;
+        }
+        offset
+    }
+}
+
+/// Status of the last persist, which is determined while recovering the heap (see `VNVHeap::recover_with_status`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PersistCommitStatus {
+    /// There was no persisted state to recover,
+    /// e.g. because the heap was not persisted since it was created or the state was restored after persisting
+    Empty,
+
+    /// The persist with the commit number `sequence` was completed and its state was recovered
+    Committed { sequence: u64 },
+
+    /// The state of the last persist was incomplete and no earlier committed state is left to fall back to,
+    /// so only the data that was synchronized to storage before is recovered.
+    ///
+    /// An interrupted persist alone does not cause this, as it writes to the slice slot
+    /// that does not hold the last committed state (see `get_slice_offsets`).
+    RolledBack,
+}
+
+/// Number of commit records, which are written alternately so that one valid record always remains.
+///
+/// Each record belongs to the slice slot with the same index, so the slice of the last committed persist
+/// is not overwritten by the next one either.
+pub(crate) const COMMIT_RECORD_COUNT: usize = 2;
+
+/// Record that is written after the slice of a persist (see `persist`) was written completely.
+///
+/// All values are stored in little endian, followed by a CRC-32 of the record itself to detect torn writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CommitRecord {
+    /// Commit number of the persist, starting at 1
+    pub(crate) sequence: u64,
+    pub(crate) slice_size: usize,
+    pub(crate) slice_checksum: u32,
+}
+
+impl CommitRecord {
+    pub(crate) const SERIALIZED_SIZE: usize = 2 * size_of::<u64>() + 2 * size_of::<u32>();
+
+    const RECORD_CHECKSUM_OFFSET: usize = Self::SERIALIZED_SIZE - size_of::<u32>();
+
+    pub(crate) fn to_bytes(&self) -> [u8; Self::SERIALIZED_SIZE] {
+        let mut bytes = [0u8; Self::SERIALIZED_SIZE];
+        bytes[..8].copy_from_slice(&self.sequence.to_le_bytes());
+        bytes[8..16].copy_from_slice(&(self.slice_size as u64).to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.slice_checksum.to_le_bytes());
+
+        let record_checksum = crc32(&bytes[..Self::RECORD_CHECKSUM_OFFSET]);
+        bytes[Self::RECORD_CHECKSUM_OFFSET..].copy_from_slice(&record_checksum.to_le_bytes());
+        bytes
+    }
+
+    /// Returns `None` if the record was never written or its write was interrupted
+    pub(crate) fn from_bytes(bytes: &[u8; Self::SERIALIZED_SIZE]) -> Option<CommitRecord> {
+        let record_checksum = u32::from_le_bytes(bytes[Self::RECORD_CHECKSUM_OFFSET..].try_into().unwrap());
+        if record_checksum != crc32(&bytes[..Self::RECORD_CHECKSUM_OFFSET]) {
+            return None;
+        }
+
+        let sequence = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+        if sequence == 0 {
+            return None;
+        }
+
+        Some(CommitRecord {
+            sequence,
+            slice_size: usize::try_from(u64::from_le_bytes(bytes[8..16].try_into().unwrap())).ok()?,
+            slice_checksum: u32::from_le_bytes(bytes[16..20].try_into().unwrap()),
+        })
+    }
+}
+
+/// Reads the commit records of all slots, records that are not valid are `None`
+fn read_commit_records<S: PersistentStorageModule>(storage: &mut S) -> Result<[Option<CommitRecord>; COMMIT_RECORD_COUNT], ()> {
+    let storage_size = storage.get_max_size();
+    let mut records = [None; COMMIT_RECORD_COUNT];
+    for (slot, record) in records.iter_mut().enumerate() {
+        let mut bytes = [0u8; CommitRecord::SERIALIZED_SIZE];
+        storage.read(RecoveryArea::get_commit_record_offset(storage_size, slot), &mut bytes)?;
+        *record = CommitRecord::from_bytes(&bytes);
+    }
+    Ok(records)
+}
+
+/// Returns the slot of the record with the highest commit number (slot 0 if there are no valid records)
+fn get_latest_slot(records: &[Option<CommitRecord>; COMMIT_RECORD_COUNT]) -> usize {
+    let sequence = |slot: usize| records[slot].map_or(0, |record| record.sequence);
+    (0..COMMIT_RECORD_COUNT).fold(0, |latest, slot| if sequence(slot) > sequence(latest) { slot } else { latest })
+}
+
+/// Reads all commit records and returns the slot and the record with the highest commit number
+fn read_latest_commit_record<S: PersistentStorageModule>(storage: &mut S) -> Result<Option<(usize, CommitRecord)>, ()> {
+    let records = read_commit_records(storage)?;
+    let slot = get_latest_slot(&records);
+    Ok(records[slot].map(|record| (slot, record)))
+}
+
+/// Returns the commit number of the latest committed persist, or 0 if there is none
+pub(crate) fn read_committed_sequence<S: PersistentStorageModule>(storage: &mut S) -> Result<u64, ()> {
+    Ok(read_latest_commit_record(storage)?.map_or(0, |(_, record)| record.sequence))
+}
+
+/// Slot and commit number of the next commit record.
+///
+/// This is kept in RAM (see `PersistAccessPoint`), so `persist` does not have to read the records first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CommitCursor {
+    slot: usize,
+    sequence: u64,
+
+    /// Offsets of the slice slots (see `get_slice_offsets`)
+    slice_offsets: [usize; COMMIT_RECORD_COUNT],
+}
+
+impl CommitCursor {
+    /// Cursor of a storage without any commit records
+    pub(crate) const fn new(slice_offsets: [usize; COMMIT_RECORD_COUNT]) -> CommitCursor {
+        CommitCursor {
+            slot: 0,
+            sequence: 1,
+            slice_offsets,
+        }
+    }
+
+    /// Continues after the latest commit record in `storage`
+    pub(crate) fn read<S: PersistentStorageModule>(storage: &mut S, slice_offsets: [usize; COMMIT_RECORD_COUNT]) -> Result<CommitCursor, ()> {
+        Ok(match read_latest_commit_record(storage)? {
+            Some((slot, latest)) => CommitCursor {
+                slot: (slot + 1) % COMMIT_RECORD_COUNT,
+                sequence: latest.sequence + 1,
+                slice_offsets,
+            },
+            None => Self::new(slice_offsets),
+        })
+    }
+
+    /// Offset of the slice slot that the next persist writes to, which never holds the latest committed slice
+    pub(crate) const fn get_slice_offset(&self) -> usize {
+        self.slice_offsets[self.slot]
+    }
+}
+
+/// Commits the `slice` that was just written by `persist` to `cursor.get_slice_offset()` and advances `cursor`.
+///
+/// The record is written to the slot that does not hold the latest record. Until it is written completely,
+/// the slice is incomplete for `read_persist_commit_status` and the previous commit is recovered instead.
+pub(crate) fn commit_persisted_slice<S: PersistentStorageModule>(
+    storage: &mut S,
+    slice: &[u8],
+    cursor: &mut CommitCursor,
+) -> Result<(), ()> {
+    let record = CommitRecord {
+        sequence: cursor.sequence,
+        slice_size: slice.len(),
+        slice_checksum: crc32(slice),
+    };
+    let offset = RecoveryArea::get_commit_record_offset(storage.get_max_size(), cursor.slot);
+    storage.write(offset, &record.to_bytes())?;
+    storage.flush()?;
+
+    *cursor = CommitCursor {
+        slot: (cursor.slot + 1) % COMMIT_RECORD_COUNT,
+        sequence: cursor.sequence + 1,
+        slice_offsets: cursor.slice_offsets,
+    };
+    Ok(())
+}
+
+/// Checks which slice slot holds the state of the last committed persist.
+///
+/// If the slice of the latest commit record is incomplete, the slice of the previous record is used instead.
+/// Returns the offset of the committed slice as well. Slices that are larger than `max_slice_size` are treated as incomplete.
+pub(crate) fn read_persist_commit_status<S: PersistentStorageModule>(
+    storage: &mut S,
+    slice_offsets: &[usize; COMMIT_RECORD_COUNT],
+    max_slice_size: usize,
+) -> Result<(PersistCommitStatus, Option<usize>), ()> {
+    let records = read_commit_records(storage)?;
+    let latest_slot = get_latest_slot(&records);
+
+    // the state of the latest persist was restored already (or nothing was persisted yet),
+    // so the older slices are outdated as well
+    let slice_size: usize = unsafe { read_storage_data(storage, slice_offsets[latest_slot]) }?;
+    if slice_size == size_of::<usize>() {
+        return Ok((PersistCommitStatus::Empty, None));
+    }
+
+    for slot in (0..COMMIT_RECORD_COUNT).map(|i| (latest_slot + COMMIT_RECORD_COUNT - i) % COMMIT_RECORD_COUNT) {
+        if let Some(record) = records[slot] {
+            if is_slice_committed(storage, slice_offsets[slot], &record, max_slice_size)? {
+                let status = PersistCommitStatus::Committed { sequence: record.sequence };
+                return Ok((status, Some(slice_offsets[slot])));
+            }
+        }
+    }
+    Ok((PersistCommitStatus::RolledBack, None))
+}
+
+/// Checks whether the slice at `offset` is the one that was committed by `record`
+fn is_slice_committed<S: PersistentStorageModule>(
+    storage: &mut S,
+    offset: usize,
+    record: &CommitRecord,
+    max_slice_size: usize,
+) -> Result<bool, ()> {
+    let slice_size: usize = unsafe { read_storage_data(storage, offset) }?;
+    if record.slice_size != slice_size || slice_size > max_slice_size {
+        return Ok(false);
+    }
+
+    let mut buffer = [0u8; 64];
+    let mut crc = !0u32;
+    let mut read = 0;
+    while read < slice_size {
+        let len = buffer.len().min(slice_size - read);
+        storage.read(offset + read, &mut buffer[..len])?;
+        crc = crc32_update(crc, &buffer[..len]);
+        read += len;
+    }
+    Ok(!crc == record.slice_checksum)
+}
+
+/// Offsets of the slots that `persist` writes its slice to alternately, each of them `slice_slot_size` bytes large.
+///
+/// The slots are located at the start of the storage, in front of the region of the non-resident allocator.
+pub(crate) fn get_slice_offsets(slice_slot_size: usize) -> [usize; COMMIT_RECORD_COUNT] {
+    core::array::from_fn(|slot| slot * slice_slot_size)
+}
+
+/// Region at the end of the storage that is needed to recover the heap after a reboot (see `VNVHeap::recover`).
+///
+/// It consists of a `SnapshotHeader`, the state of the non-resident allocator, the registry of roots and the relocation log.
+/// With the `gc` feature, the allocation table follows the relocation log.
+/// With the `allocator_journal` feature, the journal of the non-resident allocator follows after that.
+/// The commit records of the persisted state are located at the very end,
+/// so `persist` can find them without knowing the non-resident allocator.
+pub(crate) struct RecoveryArea {
+    offset: usize,
+    allocator_state_size: usize,
+}
+
+impl RecoveryArea {
+    pub(crate) const fn new(storage_size: usize, allocator_state_size: usize) -> Self {
+        let size = SnapshotHeader::SERIALIZED_SIZE
+            + allocator_state_size
+            + RECOVERY_ROOT_COUNT * RootEntry::SERIALIZED_SIZE
+            + RELOCATION_LOG_COUNT * RelocationEntry::SERIALIZED_SIZE;
+
+        #[cfg(feature = "gc")]
+        let size = size + ALLOCATION_TABLE_CAPACITY * AllocationEntry::SERIALIZED_SIZE;
+
+        #[cfg(feature = "allocator_journal")]
+        let size = size + AllocatorJournal::SIZE;
+
+        let size = size + COMMIT_RECORD_COUNT * CommitRecord::SERIALIZED_SIZE;
+
+        Self {
+            offset: storage_size.saturating_sub(size),
+            allocator_state_size,
+        }
+    }
+
+    /// Offset of the header, which is also the end of the region that is managed by the non-resident allocator
+    pub(crate) const fn get_offset(&self) -> usize {
+        self.offset
+    }
+
+    pub(crate) const fn get_allocator_state_offset(&self) -> usize {
+        self.offset + SnapshotHeader::SERIALIZED_SIZE
+    }
+
+    pub(crate) const fn get_root_offset(&self, slot: usize) -> usize {
+        self.get_allocator_state_offset() + self.allocator_state_size + slot * RootEntry::SERIALIZED_SIZE
+    }
+
+    pub(crate) const fn get_relocation_offset(&self, slot: usize) -> usize {
+        self.get_root_offset(RECOVERY_ROOT_COUNT) + slot * RelocationEntry::SERIALIZED_SIZE
+    }
+
+    #[cfg(feature = "gc")]
+    pub(crate) const fn get_allocation_table_offset(&self, slot: usize) -> usize {
+        self.get_relocation_offset(RELOCATION_LOG_COUNT) + slot * AllocationEntry::SERIALIZED_SIZE
+    }
+
+    #[cfg(feature = "allocator_journal")]
+    pub(crate) const fn get_journal_offset(&self) -> usize {
+        let offset = self.get_relocation_offset(RELOCATION_LOG_COUNT);
+
+        #[cfg(feature = "gc")]
+        let offset = self.get_allocation_table_offset(ALLOCATION_TABLE_CAPACITY);
+
+        offset
+    }
+
+    pub(crate) const fn get_commit_record_offset(storage_size: usize, slot: usize) -> usize {
+        storage_size - (COMMIT_RECORD_COUNT - slot) * CommitRecord::SERIALIZED_SIZE
+    }
+}
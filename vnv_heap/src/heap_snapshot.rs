@@ -0,0 +1,110 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::mem::size_of;
+
+use crate::vnv_error::VNVError;
+
+/// Version of the snapshot format that is written by `VNVHeap::snapshot_to`.
+///
+/// Snapshots of other versions are rejected by `VNVHeap::restore_from`.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+const MAGIC_SIZE: usize = 8;
+
+pub(crate) const SNAPSHOT_MAGIC: [u8; MAGIC_SIZE] = *b"VNVSNAPS";
+
+/// Magic of the recovery area (see `VNVHeap::recover`), which uses the same header format as snapshots
+#[cfg(feature = "recovery")]
+pub(crate) const RECOVERY_MAGIC: [u8; MAGIC_SIZE] = *b"VNVRECOV";
+
+/// Header at the start of every snapshot.
+///
+/// A snapshot consists of this header, followed by the state of the non-resident allocator
+/// and the content of the whole storage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SnapshotHeader {
+    pub(crate) storage_size: usize,
+    pub(crate) non_resident_offset: usize,
+    pub(crate) allocator_state_size: usize,
+}
+
+impl SnapshotHeader {
+    /// Size of the representation returned by `to_bytes`
+    pub(crate) const SERIALIZED_SIZE: usize = MAGIC_SIZE + 2 * size_of::<u32>() + 3 * size_of::<u64>();
+
+    /// Offset of the non-resident allocator state in the snapshot
+    pub(crate) const fn allocator_state_offset(&self) -> usize {
+        Self::SERIALIZED_SIZE
+    }
+
+    /// Offset of the storage content in the snapshot
+    pub(crate) const fn storage_offset(&self) -> usize {
+        Self::SERIALIZED_SIZE + self.allocator_state_size
+    }
+
+    /// Total size of the snapshot
+    pub(crate) const fn snapshot_size(&self) -> usize {
+        self.storage_offset() + self.storage_size
+    }
+
+    /// Little-endian representation that does not depend on the target
+    pub(crate) fn to_bytes(&self, magic: &[u8; MAGIC_SIZE]) -> [u8; Self::SERIALIZED_SIZE] {
+        let mut bytes = [0u8; Self::SERIALIZED_SIZE];
+        bytes[..MAGIC_SIZE].copy_from_slice(magic);
+
+        // the second u32 is reserved and keeps the following values aligned
+        let version_offset = MAGIC_SIZE;
+        bytes[version_offset..version_offset + size_of::<u32>()].copy_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+
+        let values = [self.storage_size, self.non_resident_offset, self.allocator_state_size];
+        let values_offset = version_offset + 2 * size_of::<u32>();
+        for (chunk, value) in bytes[values_offset..].chunks_exact_mut(size_of::<u64>()).zip(values) {
+            chunk.copy_from_slice(&(value as u64).to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Parses a header that was written by `to_bytes` with the same `magic`.
+    ///
+    /// Fails with `VNVError::CorruptedData` if `bytes` is no such header at all
+    /// and with `VNVError::Unsupported` if it was written by another version.
+    pub(crate) fn from_bytes(bytes: &[u8; Self::SERIALIZED_SIZE], magic: &[u8; MAGIC_SIZE]) -> Result<SnapshotHeader, VNVError> {
+        if bytes[..MAGIC_SIZE] != *magic {
+            return Err(VNVError::CorruptedData);
+        }
+
+        let version_offset = MAGIC_SIZE;
+        let version = u32::from_le_bytes(bytes[version_offset..version_offset + size_of::<u32>()].try_into().unwrap());
+        if version != SNAPSHOT_VERSION {
+            return Err(VNVError::Unsupported);
+        }
+
+        let mut values = [0usize; 3];
+        let values_offset = version_offset + 2 * size_of::<u32>();
+        for (value, chunk) in values.iter_mut().zip(bytes[values_offset..].chunks_exact(size_of::<u64>())) {
+            *value = usize::try_from(u64::from_le_bytes(chunk.try_into().unwrap()))
+                .map_err(|_| VNVError::Unsupported)?;
+        }
+
+        Ok(SnapshotHeader {
+            storage_size: values[0],
+            non_resident_offset: values[1],
+            allocator_state_size: values[2],
+        })
+    }
+}
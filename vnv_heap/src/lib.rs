@@ -15,32 +15,116 @@
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+#[cfg(feature = "allocator_journal")]
+mod allocator_journal;
 mod allocation_identifier;
+#[cfg(feature = "global_dirty_budget")]
+mod dirty_budget_pool;
+#[cfg(feature = "energy")]
+mod energy;
+#[cfg(feature = "gc")]
+mod heap_gc;
+#[cfg(feature = "recovery")]
+mod heap_recovery;
+mod heap_snapshot;
 mod resident_object_manager;
 mod persist_access_point;
+mod persist_sync;
 mod shared_persist_lock;
+mod static_vnv_heap;
+mod storage_calibration;
+mod sync_vnv_heap;
+mod vnv_aligned;
+#[cfg(feature = "dirty_pools")]
+mod vnv_background_object;
+mod vnv_box;
+mod vnv_bytes;
+mod vnv_bytes_mut_ref;
+mod vnv_bytes_ref;
+mod vnv_channel;
+mod vnv_compressed_object;
 mod vnv_config;
+mod vnv_encrypted_object;
+mod vnv_error;
 mod vnv_heap;
+mod vnv_link;
 mod vnv_list;
 mod vnv_list_mut_ref;
 mod vnv_list_ref;
+mod vnv_map;
+mod vnv_map_mut_ref;
+mod vnv_map_ref;
+mod vnv_mapped_ref;
+mod vnv_stack;
+mod vnv_stack_mut_ref;
+mod vnv_stack_ref;
+mod vnv_storage_slice;
 mod vnv_array;
 mod vnv_array_mut_ref;
 mod vnv_mut_ref;
 mod vnv_object;
+mod vnv_queue;
+mod vnv_raw_object;
 mod vnv_ref;
+#[cfg(not(any(feature = "access_counters", feature = "object_checksums")))]
+mod vnv_split_object;
 mod util;
 
 #[cfg(test)]
 mod test;
 
-#[cfg(any(feature = "benchmarks", test))]
+// the benchmarks measure the real lock types, which are replaced for model checking (see `persist_sync`)
+#[cfg(all(any(feature = "benchmarks", test), not(all(loom, test))))]
 pub mod benchmarks;
 
 pub use crate::vnv_heap::*;
 pub use crate::vnv_object::VNVObject;
-pub use crate::vnv_array::VNVArray;
-pub use vnv_config::VNVConfig;
+pub use crate::vnv_box::VNVBox;
+pub use crate::vnv_aligned::{Alignment, SupportedAlignment, VNVAligned};
+pub use crate::static_vnv_heap::StaticVNVHeap;
+pub use crate::sync_vnv_heap::SyncVNVHeap;
+pub use crate::vnv_bytes::VNVBytes;
+#[cfg(feature = "dirty_pools")]
+pub use crate::vnv_background_object::VNVBackgroundObject;
+pub use crate::vnv_link::VNVLink;
+#[cfg(feature = "recovery")]
+pub use crate::vnv_link::{LinkFixup, VNVLinkFixup, LINK_FIXUP_CAPACITY};
+pub use crate::vnv_array::{VNVArray, VNVArrayChunks};
+pub use crate::vnv_list::{VNVList, VNVListIter};
+pub use crate::vnv_stack::VNVStack;
+pub use crate::vnv_queue::VNVQueue;
+pub use crate::vnv_raw_object::VNVRawObject;
+pub use crate::vnv_channel::{VNVChannel, VNVChannelConsumer, VNVChannelProducer};
+pub use crate::vnv_map::{VNVMap, VNVMapIter};
+pub use crate::vnv_storage_slice::VNVStorageSlice;
+pub use crate::vnv_encrypted_object::VNVEncryptedObject;
+pub use crate::vnv_compressed_object::VNVCompressedObject;
+pub use vnv_config::{PersistLatencyBudget, StorageThroughput, StorageTiming, VNVConfig, WriteBack};
+pub use vnv_error::VNVError;
+#[cfg(feature = "energy")]
+pub use energy::{EnergyConfig, EnergyMonitor};
+#[cfg(feature = "global_dirty_budget")]
+pub use dirty_budget_pool::DirtyBudgetPool;
+pub use heap_snapshot::SNAPSHOT_VERSION;
+#[cfg(feature = "object_stats")]
+pub use resident_object_manager::object_stats::OBJECT_STATS_CAPACITY;
+#[cfg(feature = "persist_priority")]
+pub use resident_object_manager::persist_priority::{PersistPriority, PERSIST_PRIORITY_CAPACITY};
+#[cfg(feature = "recovery")]
+pub use heap_recovery::{PersistCommitStatus, RootKey, RECOVERY_ROOT_COUNT, RELOCATION_LOG_COUNT};
+#[cfg(feature = "allocator_journal")]
+pub use allocator_journal::ALLOCATOR_JOURNAL_CAPACITY;
+#[cfg(feature = "gc")]
+pub use heap_gc::{GcMarker, VNVTrace, ALLOCATION_TABLE_CAPACITY};
+pub use resident_object_manager::resident_object_backup::{ObjectAccessCount, ObjectChecksum};
 pub use vnv_ref::VNVRef;
+pub use vnv_mapped_ref::VNVMappedRef;
 pub use vnv_mut_ref::VNVMutRef;
+pub use vnv_bytes_ref::VNVBytesRef;
+pub use vnv_bytes_mut_ref::VNVBytesMutRef;
+#[cfg(not(any(feature = "access_counters", feature = "object_checksums")))]
+pub use vnv_split_object::{SplittableObject, VNVObjectPart, VNVSplitObject};
 pub mod modules;
+
+#[cfg(feature = "capi")]
+pub mod capi;
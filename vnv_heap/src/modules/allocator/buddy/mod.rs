@@ -18,9 +18,9 @@
 mod internal;
 mod linked_list;
 
-use core::{alloc::Layout, ptr::NonNull};
+use core::{alloc::Layout, mem::size_of, ptr::NonNull};
 
-use super::AllocatorModule;
+use super::{AllocationRoundingInfo, AllocatorModule, ResidentAllocationRounding};
 use internal::Heap;
 
 /// Buddy allocator module
@@ -28,6 +28,11 @@ pub struct BuddyAllocatorModule<const ORDER: usize> {
     inner: Heap<ORDER>,
 }
 
+impl<const ORDER: usize> AllocationRoundingInfo for BuddyAllocatorModule<ORDER> {
+    const ALLOCATION_ROUNDING: ResidentAllocationRounding =
+        ResidentAllocationRounding::PowerOfTwo { min_size: size_of::<usize>() };
+}
+
 impl<const ORDER: usize> AllocatorModule for BuddyAllocatorModule<ORDER> {
     unsafe fn init(&mut self, start: *mut u8, size: usize) {
         self.inner.init(start as usize, size)
@@ -458,26 +458,27 @@ impl HoleList {
     // ADDED FUNCTION FOR VNV HEAP
     pub(crate) unsafe fn allocate_at(&mut self, layout: Layout, ptr: *mut u8) -> Result<Layout, ()> {
         let aligned_layout = Self::align_layout(layout);
-        let ptr = ptr as usize;
-        let end_ptr = ptr + aligned_layout.size();
+        // only compare addresses here, `ptr` itself is passed on to keep its provenance
+        let addr = ptr as usize;
+        let end_addr = addr + aligned_layout.size();
         let mut cursor = self.cursor().ok_or(())?;
 
         loop {
             let curr = cursor.hole.as_ptr() as usize;
-            if curr > ptr {
+            if curr > addr {
                 // hole not found, cancel search
                 return Err(());
             }
             
-            let end_ptr_curr = curr + cursor.current().size;
-            if end_ptr > end_ptr_curr {
+            let end_addr_curr = curr + cursor.current().size;
+            if end_addr > end_addr_curr {
                 // hole is too small to allocate the layout
                 // continue search
                 cursor = cursor.next().ok_or(())?;
             } else {
                 // hole found
                 // split here
-                match cursor.split_at(aligned_layout.clone(), ptr as *mut u8) {
+                match cursor.split_at(aligned_layout.clone(), ptr) {
                     Ok(()) => {
                         return Ok(aligned_layout);
                     }
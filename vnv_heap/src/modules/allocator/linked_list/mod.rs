@@ -18,9 +18,13 @@
 mod hole;
 mod internal;
 
-use core::{alloc::Layout, ptr::NonNull};
+use core::{
+    alloc::Layout,
+    mem::{align_of, size_of},
+    ptr::NonNull,
+};
 
-use super::AllocatorModule;
+use super::{AllocationRoundingInfo, AllocatorModule, ResidentAllocationRounding};
 use internal::Heap;
 
 /// Linked list allocator module that uses first fit
@@ -28,6 +32,13 @@ pub struct LinkedListAllocatorModule {
     inner: Heap,
 }
 
+impl AllocationRoundingInfo for LinkedListAllocatorModule {
+    const ALLOCATION_ROUNDING: ResidentAllocationRounding = ResidentAllocationRounding::Multiple {
+        block_size: align_of::<usize>(),
+        min_size: 2 * size_of::<usize>(),
+    };
+}
+
 impl AllocatorModule for LinkedListAllocatorModule {
     unsafe fn init(&mut self, start: *mut u8, size: usize) {
         self.inner.init(start, size)
@@ -17,12 +17,58 @@
 
 mod buddy;
 mod linked_list;
+mod slab;
+mod tlsf;
 
 pub use buddy::BuddyAllocatorModule;
 pub use linked_list::LinkedListAllocatorModule;
+pub use slab::SlabAllocatorModule;
+pub use tlsf::TlsfAllocatorModule;
 
 use core::{alloc::Layout, ptr::NonNull};
 
+use crate::util::div_ceil;
+
+/// Describes how an `AllocatorModule` rounds up the size of allocations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResidentAllocationRounding {
+    /// Sizes are rounded up to the next power of two, but at least to `min_size`
+    PowerOfTwo { min_size: usize },
+
+    /// Sizes are rounded up to a multiple of `block_size`, but at least to `min_size`
+    Multiple { block_size: usize, min_size: usize },
+
+    /// Sizes are rounded up to a multiple of `block_size` and a header of `header_size` bytes is added,
+    /// but at least `min_size` bytes are used
+    MultipleWithHeader { block_size: usize, header_size: usize, min_size: usize },
+}
+
+impl ResidentAllocationRounding {
+    /// Returns the amount of bytes that are used up by an allocation of `size` bytes
+    pub const fn apply(&self, size: usize) -> usize {
+        let (size, min_size) = match self {
+            Self::PowerOfTwo { min_size } => (size.next_power_of_two(), *min_size),
+            Self::Multiple { block_size, min_size } => (div_ceil(size, *block_size) * *block_size, *min_size),
+            Self::MultipleWithHeader { block_size, header_size, min_size } => {
+                (div_ceil(size, *block_size) * *block_size + *header_size, *min_size)
+            }
+        };
+        if size < min_size {
+            min_size
+        } else {
+            size
+        }
+    }
+}
+
+/// Allocator modules that know how they round up the size of allocations
+/// (used for capacity planning, see `VNVHeap::required_buffer_size`).
+///
+/// This is not part of `AllocatorModule`, as `AllocatorModule` is also used as trait object.
+pub trait AllocationRoundingInfo {
+    const ALLOCATION_ROUNDING: ResidentAllocationRounding;
+}
+
 pub trait AllocatorModule {
     /// Initializes the allocator module with a memory area
     /// `[start, start+size)`
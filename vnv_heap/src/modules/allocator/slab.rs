@@ -0,0 +1,220 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{alloc::Layout, mem::size_of, ptr::NonNull};
+
+use super::{AllocationRoundingInfo, AllocatorModule, ResidentAllocationRounding};
+
+/// Slab allocator module that splits the resident buffer into slots of `SLOT_SIZE` bytes
+///
+/// Allocating pops a slot from a free list and deallocating pushes it back, there are no headers.
+/// This fits applications whose resident objects all have the same size (e.g. many sensor records),
+/// `SLOT_SIZE` should be `VNVHeap::get_object_layout_info::<T>().resident_size` of the biggest object type.
+/// Allocations that are bigger than `SLOT_SIZE` fail.
+///
+/// Slots are aligned to the lowest set bit of `SLOT_SIZE` (at most 64).
+pub struct SlabAllocatorModule<const SLOT_SIZE: usize> {
+    start: usize,
+    end: usize,
+
+    /// First slot that was never allocated, all slots after it are free as well
+    next_unused: usize,
+
+    /// First free slot before `next_unused` (or 0), each free slot stores the address of the next one
+    free_list: usize,
+}
+
+impl<const SLOT_SIZE: usize> SlabAllocatorModule<SLOT_SIZE> {
+    const SLOT_ALIGN: usize = if SLOT_SIZE & (!SLOT_SIZE + 1) < 64 {
+        SLOT_SIZE & (!SLOT_SIZE + 1)
+    } else {
+        64
+    };
+
+    pub fn new() -> Self {
+        assert!(
+            SLOT_SIZE >= size_of::<usize>() && SLOT_SIZE % size_of::<usize>() == 0,
+            "SLOT_SIZE has to be a multiple of size_of::<usize>()"
+        );
+
+        Self {
+            start: 0,
+            end: 0,
+            next_unused: 0,
+            free_list: 0,
+        }
+    }
+
+    fn fits(layout: &Layout) -> bool {
+        layout.size() <= SLOT_SIZE && layout.align() <= Self::SLOT_ALIGN
+    }
+}
+
+impl<const SLOT_SIZE: usize> AllocationRoundingInfo for SlabAllocatorModule<SLOT_SIZE> {
+    const ALLOCATION_ROUNDING: ResidentAllocationRounding = ResidentAllocationRounding::Multiple {
+        block_size: SLOT_SIZE,
+        min_size: SLOT_SIZE,
+    };
+}
+
+impl<const SLOT_SIZE: usize> AllocatorModule for SlabAllocatorModule<SLOT_SIZE> {
+    unsafe fn init(&mut self, start: *mut u8, size: usize) {
+        let start_addr = start as usize;
+        let aligned_start = (start_addr + Self::SLOT_ALIGN - 1) & !(Self::SLOT_ALIGN - 1);
+        let slot_count = (start_addr + size).saturating_sub(aligned_start) / SLOT_SIZE;
+
+        self.start = aligned_start;
+        self.end = aligned_start + slot_count * SLOT_SIZE;
+        self.next_unused = aligned_start;
+        self.free_list = 0;
+    }
+
+    unsafe fn allocate(&mut self, layout: Layout) -> Result<NonNull<u8>, ()> {
+        if !Self::fits(&layout) {
+            return Err(());
+        }
+
+        let slot = if self.free_list != 0 {
+            let slot = self.free_list;
+            self.free_list = (slot as *const usize).read();
+            slot
+        } else if self.next_unused < self.end {
+            let slot = self.next_unused;
+            self.next_unused += SLOT_SIZE;
+            slot
+        } else {
+            return Err(());
+        };
+
+        NonNull::new(slot as *mut u8).ok_or(())
+    }
+
+    unsafe fn deallocate(&mut self, ptr: NonNull<u8>, _layout: Layout) {
+        let slot = ptr.as_ptr() as usize;
+        debug_assert_eq!((slot - self.start) % SLOT_SIZE, 0, "pointer should point to the start of a slot");
+
+        (slot as *mut usize).write(self.free_list);
+        self.free_list = slot;
+    }
+
+    unsafe fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    unsafe fn allocate_at(&mut self, layout: Layout, ptr: *mut u8) -> Result<(), ()> {
+        let slot = ptr as usize;
+        if !Self::fits(&layout) || slot < self.start || slot >= self.end || (slot - self.start) % SLOT_SIZE != 0 {
+            return Err(());
+        }
+
+        if slot >= self.next_unused {
+            // the slots in front of this one are free
+            while self.next_unused < slot {
+                (self.next_unused as *mut usize).write(self.free_list);
+                self.free_list = self.next_unused;
+                self.next_unused += SLOT_SIZE;
+            }
+            self.next_unused += SLOT_SIZE;
+            return Ok(());
+        }
+
+        // remove the slot from the free list
+        let mut prev: *mut usize = &mut self.free_list;
+        while *prev != 0 {
+            if *prev == slot {
+                *prev = (slot as *const usize).read();
+                return Ok(());
+            }
+            prev = *prev as *mut usize;
+        }
+
+        // slot is already allocated
+        Err(())
+    }
+
+    #[cfg(debug_assertions)]
+    #[allow(unused)]
+    fn debug(&mut self) {
+        println!("{}", self.dump());
+    }
+
+    #[cfg(debug_assertions)]
+    fn dump(&mut self) -> String {
+        // the order of the free list depends on the order of deallocations, so only the allocated slots are dumped
+        let mut free = vec![];
+        let mut slot = self.free_list;
+        while slot != 0 {
+            free.push(slot);
+            slot = unsafe { (slot as *const usize).read() };
+        }
+
+        let mut res = String::new();
+        for slot in (self.start..self.next_unused).step_by(SLOT_SIZE) {
+            if !free.contains(&slot) {
+                res.push_str(format!("{},", slot).as_str());
+            }
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{alloc::Layout, ptr::NonNull};
+
+    use super::{super::test::*, SlabAllocatorModule};
+    use crate::modules::allocator::AllocatorModule;
+
+    fn check_heap_integrity(heap1: &mut SlabAllocatorModule<16>, heap2: &mut SlabAllocatorModule<16>, diff: isize) {
+        let allocated = |heap: &mut SlabAllocatorModule<16>, diff: isize| -> Vec<isize> {
+            heap.dump().split_terminator(',').map(|slot| slot.parse::<isize>().unwrap() - diff).collect()
+        };
+        assert_eq!(allocated(heap1, 0), allocated(heap2, diff));
+    }
+
+    #[test]
+    fn test_allocate_at_simple_slab() {
+        test_allocate_at_simple(SlabAllocatorModule::<16>::new(), SlabAllocatorModule::<16>::new(), check_heap_integrity)
+    }
+
+    #[test]
+    fn test_allocate_at_restore_state_slab() {
+        test_allocate_at_restore_state(SlabAllocatorModule::<16>::new(), SlabAllocatorModule::<16>::new(), check_heap_integrity)
+    }
+
+    #[test]
+    fn test_slab_reuse_slots() {
+        #[repr(C, align(64))]
+        struct Buffer([u8; 96]);
+
+        let mut buffer = Buffer([0; 96]);
+        let mut heap = SlabAllocatorModule::<32>::new();
+        let layout = Layout::new::<[u64; 3]>();
+
+        unsafe {
+            heap.init(buffer.0.as_mut_ptr(), buffer.0.len());
+            let slots: Vec<NonNull<u8>> = (0..3).map(|_| heap.allocate(layout).unwrap()).collect();
+            assert!(heap.allocate(layout).is_err());
+
+            // too big for a slot
+            heap.deallocate(slots[1], layout);
+            assert!(heap.allocate(Layout::new::<[u8; 33]>()).is_err());
+
+            assert_eq!(heap.allocate(Layout::new::<u8>()), Ok(slots[1]));
+        }
+    }
+}
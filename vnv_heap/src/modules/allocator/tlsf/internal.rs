@@ -0,0 +1,372 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Two-level segregated fit allocator
+//!
+//! Every block starts with a header word that stores its size and two flags.
+//! Free blocks additionally store their size at their last word (footer), so that
+//! they can be merged with the block that is deallocated after them.
+//! Free blocks of at least `MIN_BLOCK_SIZE` bytes are linked into one of the segregated free lists,
+//! smaller ones (e.g. the gap in front of an aligned allocation) are only merged again later.
+//!
+//! Free blocks are always merged with their free neighbors, so the physical layout of the blocks
+//! only depends on which regions are allocated. This is what allows `alloc_at` to restore the heap.
+
+use core::{alloc::Layout, cmp::max, mem::size_of, ptr::NonNull};
+
+const WORD: usize = size_of::<usize>();
+
+/// This block is free
+const FREE: usize = 0b01;
+
+/// The previous block is free (so its footer is valid)
+const PREV_FREE: usize = 0b10;
+
+const FLAGS: usize = FREE | PREV_FREE;
+
+/// Header, next and previous pointer of the free list and footer
+pub(crate) const MIN_BLOCK_SIZE: usize = 4 * WORD;
+
+/// Size of the header in front of every allocation
+pub(crate) const HEADER_SIZE: usize = WORD;
+
+const SL_LOG2: usize = 3;
+const SL_COUNT: usize = 1 << SL_LOG2;
+
+/// Blocks smaller than `SMALL_BLOCK_SIZE` are all stored in the first level 0
+const FL_SHIFT: usize = SL_LOG2 + WORD.trailing_zeros() as usize;
+const SMALL_BLOCK_SIZE: usize = 1 << FL_SHIFT;
+
+pub struct Heap<const FL: usize> {
+    /// First block of the heap (or 0 if not initialized)
+    start: usize,
+
+    /// Sentinel block with a size of 0 that is never free
+    end: usize,
+
+    fl_bitmap: usize,
+    sl_bitmap: [u8; FL],
+
+    /// First free block of each size class (or 0 if empty)
+    heads: [[usize; SL_COUNT]; FL],
+}
+
+impl<const FL: usize> Heap<FL> {
+    /// Biggest block that can be stored in the free lists
+    const MAX_BLOCK_SIZE: usize = (1 << (FL + FL_SHIFT - 1)) - WORD;
+
+    pub const fn new() -> Self {
+        assert!(FL > 0 && FL + FL_SHIFT - 1 < usize::BITS as usize, "invalid first level count");
+
+        Self {
+            start: 0,
+            end: 0,
+            fl_bitmap: 0,
+            sl_bitmap: [0; FL],
+            heads: [[0; SL_COUNT]; FL],
+        }
+    }
+
+    /// Adds the memory area `[start, start+size)` to the heap.
+    ///
+    /// At most `MAX_BLOCK_SIZE` bytes are used.
+    pub unsafe fn init(&mut self, start: usize, size: usize) {
+        let end = (start + size) & !(WORD - 1);
+        let start = (start + WORD - 1) & !(WORD - 1);
+        if end < start + MIN_BLOCK_SIZE + WORD {
+            return;
+        }
+
+        let block_size = (end - start - WORD).min(Self::MAX_BLOCK_SIZE);
+        self.start = start;
+        self.end = start + block_size;
+
+        write(self.end, 0);
+        self.mark_free(start, block_size);
+        self.insert(start, block_size);
+    }
+
+    pub fn alloc(&mut self, layout: Layout) -> Result<NonNull<u8>, ()> {
+        let size = block_size(layout);
+        let search_size = if layout.align() > WORD {
+            // the gap in front of the aligned payload stays free
+            size + layout.align() - WORD
+        } else {
+            size
+        };
+
+        let (fl, sl) = self.find_suitable(search_size).ok_or(())?;
+        let block = self.heads[fl][sl];
+        unsafe {
+            let available = block_size_of(block);
+            self.remove(block, available);
+
+            let payload = align_up(block + HEADER_SIZE, max(layout.align(), WORD));
+            self.split(block, available, payload - HEADER_SIZE, size);
+            NonNull::new(payload as *mut u8).ok_or(())
+        }
+    }
+
+    /// ### Safety
+    ///
+    /// `ptr` has to be aligned to `layout.align()`.
+    pub unsafe fn alloc_at(&mut self, layout: Layout, ptr: *mut u8) -> Result<(), ()> {
+        let start = (ptr as usize).checked_sub(HEADER_SIZE).ok_or(())?;
+        let size = block_size(layout);
+        if start < self.start || start + size > self.end {
+            return Err(());
+        }
+
+        // find the block that contains the region (free blocks are not sorted, so go through the physical blocks)
+        let mut block = self.start;
+        while block < self.end {
+            let block_size = block_size_of(block);
+            if block + block_size > start {
+                if !is_free(block) || start + size > block + block_size {
+                    return Err(());
+                }
+
+                self.remove(block, block_size);
+                self.split(block, block_size, start, size);
+                return Ok(());
+            }
+            block += block_size;
+        }
+        Err(())
+    }
+
+    pub fn dealloc(&mut self, ptr: NonNull<u8>, _layout: Layout) {
+        unsafe {
+            let mut block = ptr.as_ptr() as usize - HEADER_SIZE;
+            let header = read(block);
+            let mut size = header & !FLAGS;
+
+            let next = block + size;
+            if is_free(next) {
+                let next_size = block_size_of(next);
+                self.remove(next, next_size);
+                size += next_size;
+            }
+
+            if header & PREV_FREE != 0 {
+                let prev_size = read(block - WORD) & !FLAGS;
+                block -= prev_size;
+                self.remove(block, prev_size);
+                size += prev_size;
+            }
+
+            self.mark_free(block, size);
+            self.insert(block, size);
+        }
+    }
+
+    /// Uses the region `[start, start+size)` of the free block `block`, which was already removed from the free lists.
+    ///
+    /// The parts in front and after the region stay free.
+    unsafe fn split(&mut self, block: usize, block_size: usize, start: usize, size: usize) {
+        debug_assert!(block <= start && start + size <= block + block_size);
+
+        let front = start - block;
+        if front > 0 {
+            self.mark_free(block, front);
+            self.insert(block, front);
+        }
+
+        write(start, size | if front > 0 { PREV_FREE } else { 0 });
+        let next = start + size;
+        write(next, read(next) & !PREV_FREE);
+
+        let back = block + block_size - next;
+        if back > 0 {
+            self.mark_free(next, back);
+            self.insert(next, back);
+        }
+    }
+
+    /// Writes header and footer of a free block (its previous block is never free, as free blocks are always merged)
+    unsafe fn mark_free(&mut self, block: usize, size: usize) {
+        write(block + size - WORD, size | FREE);
+        write(block, size | FREE);
+
+        let next = block + size;
+        write(next, read(next) | PREV_FREE);
+    }
+
+    /// Inserts the free block into its free list (blocks smaller than `MIN_BLOCK_SIZE` are not stored)
+    unsafe fn insert(&mut self, block: usize, size: usize) {
+        if size < MIN_BLOCK_SIZE {
+            return;
+        }
+
+        let (fl, sl) = mapping(size);
+        let head = self.heads[fl][sl];
+        write(block + WORD, head);
+        write(block + 2 * WORD, 0);
+        if head != 0 {
+            write(head + 2 * WORD, block);
+        }
+
+        self.heads[fl][sl] = block;
+        self.fl_bitmap |= 1 << fl;
+        self.sl_bitmap[fl] |= 1 << sl;
+    }
+
+    /// Removes the free block from its free list
+    unsafe fn remove(&mut self, block: usize, size: usize) {
+        if size < MIN_BLOCK_SIZE {
+            return;
+        }
+
+        let (fl, sl) = mapping(size);
+        let next = read(block + WORD);
+        let prev = read(block + 2 * WORD);
+        if next != 0 {
+            write(next + 2 * WORD, prev);
+        }
+
+        if prev != 0 {
+            write(prev + WORD, next);
+        } else {
+            self.heads[fl][sl] = next;
+            if next == 0 {
+                self.sl_bitmap[fl] &= !(1 << sl);
+                if self.sl_bitmap[fl] == 0 {
+                    self.fl_bitmap &= !(1 << fl);
+                }
+            }
+        }
+    }
+
+    /// Returns the size class of a free list whose blocks are all at least `size` bytes big
+    fn find_suitable(&self, size: usize) -> Option<(usize, usize)> {
+        let size = if size >= SMALL_BLOCK_SIZE {
+            // round up, so that every block of the next size class is big enough
+            size + (1 << (fls(size) - SL_LOG2)) - 1
+        } else {
+            size
+        };
+        if size > Self::MAX_BLOCK_SIZE {
+            return None;
+        }
+
+        let (fl, sl) = mapping(size);
+        let sl_map = self.sl_bitmap[fl] as usize & (!0 << sl);
+        if sl_map != 0 {
+            return Some((fl, sl_map.trailing_zeros() as usize));
+        }
+
+        let fl_map = self.fl_bitmap & (!0usize).checked_shl(fl as u32 + 1).unwrap_or(0);
+        if fl_map == 0 {
+            return None;
+        }
+
+        let fl = fl_map.trailing_zeros() as usize;
+        Some((fl, (self.sl_bitmap[fl] as usize).trailing_zeros() as usize))
+    }
+
+    #[cfg(debug_assertions)]
+    pub(crate) fn dump(&self) -> String {
+        // the order of the free lists depends on the order of deallocations, so only the physical layout is dumped
+        let mut res = String::new();
+        let mut block = self.start;
+        while block < self.end {
+            let (size, free) = unsafe { (block_size_of(block), is_free(block)) };
+            res.push_str(format!("{}: {}{},", block, size, if free { "F" } else { "" }).as_str());
+            block += size;
+        }
+        res
+    }
+}
+
+/// Size of the block that is used for an allocation with `layout`
+fn block_size(layout: Layout) -> usize {
+    max(HEADER_SIZE + align_up(layout.size(), WORD), MIN_BLOCK_SIZE)
+}
+
+/// Returns the first and second level index of `size`
+fn mapping(size: usize) -> (usize, usize) {
+    if size < SMALL_BLOCK_SIZE {
+        (0, size / (SMALL_BLOCK_SIZE / SL_COUNT))
+    } else {
+        let fl = fls(size);
+        let sl = (size >> (fl - SL_LOG2)) ^ SL_COUNT;
+        (fl - FL_SHIFT + 1, sl)
+    }
+}
+
+/// Index of the most significant set bit
+fn fls(size: usize) -> usize {
+    usize::BITS as usize - 1 - size.leading_zeros() as usize
+}
+
+fn align_up(addr: usize, align: usize) -> usize {
+    (addr + align - 1) & !(align - 1)
+}
+
+unsafe fn read(addr: usize) -> usize {
+    (addr as *const usize).read()
+}
+
+unsafe fn write(addr: usize, value: usize) {
+    (addr as *mut usize).write(value)
+}
+
+unsafe fn block_size_of(block: usize) -> usize {
+    read(block) & !FLAGS
+}
+
+unsafe fn is_free(block: usize) -> bool {
+    read(block) & FREE != 0
+}
+
+#[cfg(test)]
+pub(super) mod test {
+    use super::{Heap, SL_COUNT};
+
+    /// Checks that both heaps have the same blocks and the same free blocks in each free list
+    pub(crate) fn check_heap_integrity<const FL: usize>(heap1: &mut Heap<FL>, heap2: &mut Heap<FL>, diff: isize) {
+        let blocks = |heap: &Heap<FL>, diff: isize| -> Vec<(isize, usize, bool)> {
+            let mut res = vec![];
+            let mut block = heap.start;
+            while block < heap.end {
+                let (size, free) = unsafe { (super::block_size_of(block), super::is_free(block)) };
+                res.push((block as isize - diff, size, free));
+                block += size;
+            }
+            res
+        };
+        assert_eq!(blocks(heap1, 0), blocks(heap2, diff));
+
+        let list = |heap: &Heap<FL>, fl: usize, sl: usize, diff: isize| -> Vec<isize> {
+            let mut res = vec![];
+            let mut block = heap.heads[fl][sl];
+            while block != 0 {
+                res.push(block as isize - diff);
+                block = unsafe { super::read(block + super::WORD) };
+            }
+            res.sort();
+            res
+        };
+        for fl in 0..FL {
+            for sl in 0..SL_COUNT {
+                assert_eq!(list(heap1, fl, sl, 0), list(heap2, fl, sl, diff));
+            }
+        }
+        assert_eq!(heap1.fl_bitmap, heap2.fl_bitmap);
+        assert_eq!(heap1.sl_bitmap, heap2.sl_bitmap);
+    }
+}
@@ -0,0 +1,164 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+mod internal;
+
+use core::{alloc::Layout, mem::size_of, ptr::NonNull};
+
+use super::{AllocationRoundingInfo, AllocatorModule, ResidentAllocationRounding};
+use internal::{Heap, HEADER_SIZE, MIN_BLOCK_SIZE};
+
+/// Two-level segregated fit (TLSF) allocator module
+///
+/// `allocate` and `deallocate` take constant time (independent of the amount of allocations),
+/// which makes the worst case execution time of the resident buffer easier to analyze.
+/// Blocks of up to `2^(FL + log2(8 * size_of::<usize>()) - 1)` bytes are supported,
+/// so e.g. `TlsfAllocatorModule<16>` can manage a resident buffer of up to 2 MiB on 64 bit targets.
+///
+/// Every allocation uses a header of `size_of::<usize>()` bytes.
+/// `allocate_at` has to search the block that contains the region and takes linear time.
+pub struct TlsfAllocatorModule<const FL: usize> {
+    inner: Heap<FL>,
+}
+
+impl<const FL: usize> AllocationRoundingInfo for TlsfAllocatorModule<FL> {
+    const ALLOCATION_ROUNDING: ResidentAllocationRounding = ResidentAllocationRounding::MultipleWithHeader {
+        block_size: size_of::<usize>(),
+        header_size: HEADER_SIZE,
+        min_size: MIN_BLOCK_SIZE,
+    };
+}
+
+impl<const FL: usize> AllocatorModule for TlsfAllocatorModule<FL> {
+    unsafe fn init(&mut self, start: *mut u8, size: usize) {
+        self.inner.init(start as usize, size)
+    }
+
+    unsafe fn allocate(&mut self, layout: Layout) -> Result<NonNull<u8>, ()> {
+        self.inner.alloc(layout)
+    }
+
+    unsafe fn deallocate(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        self.inner.dealloc(ptr, layout)
+    }
+
+    unsafe fn reset(&mut self) {
+        self.inner = Heap::new();
+    }
+
+    unsafe fn allocate_at(&mut self, layout: Layout, ptr: *mut u8) -> Result<(), ()> {
+        self.inner.alloc_at(layout, ptr)
+    }
+
+    #[cfg(debug_assertions)]
+    #[allow(unused)]
+    fn debug(&mut self) {
+        println!("{}", self.inner.dump());
+    }
+
+    #[cfg(debug_assertions)]
+    fn dump(&mut self) -> String {
+        self.inner.dump()
+    }
+}
+
+impl<const FL: usize> TlsfAllocatorModule<FL> {
+    pub fn new() -> Self {
+        Self {
+            inner: Heap::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{alloc::Layout, mem::size_of, ptr::NonNull};
+
+    use super::{super::test::*, internal, TlsfAllocatorModule};
+    use crate::modules::allocator::{AllocationRoundingInfo, AllocatorModule};
+
+    #[test]
+    fn test_allocate_at_simple_tlsf() {
+        test_allocate_at_simple(TlsfAllocatorModule::<16>::new(), TlsfAllocatorModule::<16>::new(), |heap1, heap2, diff| {
+            internal::test::check_heap_integrity(&mut heap1.inner, &mut heap2.inner, diff)
+        })
+    }
+
+    #[test]
+    fn test_allocate_at_restore_state_tlsf() {
+        test_allocate_at_restore_state(TlsfAllocatorModule::<16>::new(), TlsfAllocatorModule::<16>::new(), |heap1, heap2, diff| {
+            internal::test::check_heap_integrity(&mut heap1.inner, &mut heap2.inner, diff)
+        })
+    }
+
+    #[test]
+    fn test_tlsf_merge_and_align() {
+        let mut buffer = [0usize; 128];
+        let size = buffer.len() * size_of::<usize>();
+        let mut heap = TlsfAllocatorModule::<16>::new();
+
+        unsafe {
+            heap.init(buffer.as_mut_ptr() as *mut u8, size);
+            let empty = heap.dump();
+
+            let layouts = [
+                Layout::new::<u8>(),
+                Layout::from_size_align(40, 32).unwrap(),
+                Layout::new::<[u64; 5]>(),
+                Layout::from_size_align(3, 64).unwrap(),
+            ];
+            let ptrs: Vec<NonNull<u8>> = layouts.iter().map(|layout| heap.allocate(*layout).unwrap()).collect();
+            for (ptr, layout) in ptrs.iter().zip(layouts) {
+                assert_eq!(ptr.as_ptr() as usize % layout.align(), 0);
+            }
+
+            // deallocate in an order that merges with the previous and the next block
+            for i in [1, 3, 0, 2] {
+                heap.deallocate(ptrs[i], layouts[i]);
+            }
+            assert_eq!(heap.dump(), empty);
+
+            // the merged block can be used for big allocations again
+            let layout = Layout::from_size_align(size / 2, 1).unwrap();
+            let ptr = heap.allocate(layout).unwrap();
+            heap.deallocate(ptr, layout);
+            assert_eq!(heap.dump(), empty);
+        }
+    }
+
+    #[test]
+    fn test_tlsf_allocation_rounding() {
+        let mut buffer = [0usize; 64];
+        let mut heap = TlsfAllocatorModule::<16>::new();
+
+        unsafe {
+            let start = buffer.as_mut_ptr() as usize;
+            heap.init(start as *mut u8, buffer.len() * size_of::<usize>());
+
+            for size in [1, 8, 20, 33, 100] {
+                let first = heap.allocate(Layout::from_size_align(size, 1).unwrap()).unwrap();
+                let second = heap.allocate(Layout::new::<u8>()).unwrap();
+                assert_eq!(
+                    second.as_ptr() as usize - first.as_ptr() as usize,
+                    TlsfAllocatorModule::<16>::ALLOCATION_ROUNDING.apply(size)
+                );
+                heap.deallocate(second, Layout::new::<u8>());
+                heap.deallocate(first, Layout::from_size_align(size, 1).unwrap());
+            }
+        }
+    }
+}
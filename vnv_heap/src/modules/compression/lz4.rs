@@ -0,0 +1,287 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::CompressionModule;
+
+const MIN_MATCH: usize = 4;
+const MAX_OFFSET: usize = u16::MAX as usize;
+/// The last bytes of a block are always literals
+const LAST_LITERALS: usize = 5;
+/// The last match has to start at least this many bytes before the end of a block
+const MF_LIMIT: usize = 12;
+const HASH_TABLE_SIZE: usize = 256;
+const EMPTY_ENTRY: u32 = u32::MAX;
+
+/// Compression in the LZ4 block format (fast, with a small memory footprint).
+///
+/// Uses a greedy matcher with a small hash table on the stack, so its compression ratio is a bit
+/// worse than the reference implementation. The output can be decompressed by any LZ4 decoder.
+#[derive(Clone, Copy, Default)]
+pub struct Lz4CompressionModule;
+
+impl Lz4CompressionModule {
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl CompressionModule for Lz4CompressionModule {
+    fn compress(&self, src: &[u8], dest: &mut [u8]) -> Option<usize> {
+        let mut writer = Writer { dest, pos: 0 };
+        let mut anchor = 0;
+
+        if src.len() > MF_LIMIT {
+            let mut table = [EMPTY_ENTRY; HASH_TABLE_SIZE];
+            let match_limit = src.len() - LAST_LITERALS;
+            let search_end = src.len() - MF_LIMIT;
+
+            let mut pos = 0;
+            while pos < search_end {
+                let sequence = load_u32(src, pos);
+                let hash = hash(sequence);
+                let candidate = table[hash] as usize;
+                table[hash] = pos as u32;
+
+                if table_entry_matches(src, candidate, pos, sequence) {
+                    let mut len = MIN_MATCH;
+                    while pos + len < match_limit && src[candidate + len] == src[pos + len] {
+                        len += 1;
+                    }
+
+                    writer.write_sequence(&src[anchor..pos], Some((pos - candidate, len)))?;
+                    pos += len;
+                    anchor = pos;
+                } else {
+                    pos += 1;
+                }
+            }
+        }
+
+        writer.write_sequence(&src[anchor..], None)?;
+        Some(writer.pos)
+    }
+
+    fn decompress(&self, src: &[u8], dest: &mut [u8]) -> Result<(), ()> {
+        let mut reader = Reader { src, pos: 0 };
+        let mut out: usize = 0;
+
+        loop {
+            let token = reader.read_u8()?;
+
+            let literals = reader.read_length((token >> 4) as usize)?;
+            let literals_end = out.checked_add(literals).ok_or(())?;
+            dest.get_mut(out..literals_end)
+                .ok_or(())?
+                .copy_from_slice(reader.read_slice(literals)?);
+            out = literals_end;
+
+            if reader.pos == src.len() {
+                // the last sequence only contains literals
+                break;
+            }
+
+            let offset = u16::from_le_bytes([reader.read_u8()?, reader.read_u8()?]) as usize;
+            if offset == 0 || offset > out {
+                return Err(());
+            }
+
+            let len = reader.read_length((token & 0x0f) as usize)? + MIN_MATCH;
+            if len > dest.len() - out {
+                return Err(());
+            }
+
+            // matches may overlap with the bytes they produce, so copy byte by byte
+            for i in out..out + len {
+                dest[i] = dest[i - offset];
+            }
+            out += len;
+        }
+
+        if out != dest.len() {
+            return Err(());
+        }
+
+        Ok(())
+    }
+}
+
+fn table_entry_matches(src: &[u8], candidate: usize, pos: usize, sequence: u32) -> bool {
+    candidate != EMPTY_ENTRY as usize && pos - candidate <= MAX_OFFSET && load_u32(src, candidate) == sequence
+}
+
+#[inline]
+fn load_u32(src: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([src[pos], src[pos + 1], src[pos + 2], src[pos + 3]])
+}
+
+#[inline]
+fn hash(sequence: u32) -> usize {
+    (sequence.wrapping_mul(2654435761) >> 24) as usize % HASH_TABLE_SIZE
+}
+
+struct Writer<'a> {
+    dest: &'a mut [u8],
+    pos: usize,
+}
+
+impl Writer<'_> {
+    fn write_u8(&mut self, value: u8) -> Option<()> {
+        *self.dest.get_mut(self.pos)? = value;
+        self.pos += 1;
+        Some(())
+    }
+
+    fn write_slice(&mut self, data: &[u8]) -> Option<()> {
+        self.dest.get_mut(self.pos..self.pos + data.len())?.copy_from_slice(data);
+        self.pos += data.len();
+        Some(())
+    }
+
+    /// Writes the remainder of a length that did not fit into the 4 bits of the token
+    fn write_length(&mut self, len: usize) -> Option<()> {
+        if len < 15 {
+            return Some(());
+        }
+
+        let mut remaining = len - 15;
+        while remaining >= 255 {
+            self.write_u8(255)?;
+            remaining -= 255;
+        }
+        self.write_u8(remaining as u8)
+    }
+
+    /// Writes `literals`, followed by the match (offset, length) if there is one
+    fn write_sequence(&mut self, literals: &[u8], found_match: Option<(usize, usize)>) -> Option<()> {
+        let match_len = found_match.map_or(0, |(_, len)| len - MIN_MATCH);
+        self.write_u8(((literals.len().min(15) as u8) << 4) | match_len.min(15) as u8)?;
+        self.write_length(literals.len())?;
+        self.write_slice(literals)?;
+
+        if let Some((offset, _)) = found_match {
+            self.write_slice(&(offset as u16).to_le_bytes())?;
+            self.write_length(match_len)?;
+        }
+
+        Some(())
+    }
+}
+
+struct Reader<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn read_u8(&mut self) -> Result<u8, ()> {
+        let value = *self.src.get(self.pos).ok_or(())?;
+        self.pos += 1;
+        Ok(value)
+    }
+
+    fn read_slice(&mut self, len: usize) -> Result<&'a [u8], ()> {
+        let slice = self.src.get(self.pos..self.pos.checked_add(len).ok_or(())?).ok_or(())?;
+        self.pos += len;
+        Ok(slice)
+    }
+
+    /// Reads the remainder of a length if the 4 bits of the token are not enough
+    fn read_length(&mut self, token_len: usize) -> Result<usize, ()> {
+        let mut len = token_len;
+        if token_len == 15 {
+            loop {
+                let value = self.read_u8()?;
+                len = len.checked_add(value as usize).ok_or(())?;
+                if value != 255 {
+                    break;
+                }
+            }
+        }
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Lz4CompressionModule;
+    use crate::modules::compression::CompressionModule;
+
+    fn roundtrip(data: &[u8]) -> usize {
+        let module = Lz4CompressionModule::new();
+        let mut compressed = vec![0u8; data.len() + data.len() / 255 + 16];
+        let len = module.compress(data, &mut compressed).unwrap();
+
+        let mut decompressed = vec![0u8; data.len()];
+        module.decompress(&compressed[..len], &mut decompressed).unwrap();
+        assert_eq!(decompressed, data);
+        len
+    }
+
+    #[test]
+    fn test_lz4_known_answer() {
+        // "abcd" repeated: 4 literals, one match with offset 4 and 5 trailing literals
+        let encoded = [0x4f, b'a', b'b', b'c', b'd', 4, 0, 12, 0x50, b'd', b'a', b'b', b'c', b'd'];
+
+        let mut decompressed = [0u8; 40];
+        Lz4CompressionModule.decompress(&encoded, &mut decompressed).unwrap();
+        for (i, byte) in decompressed.iter().enumerate() {
+            assert_eq!(*byte, b"abcd"[i % 4]);
+        }
+
+        let mut compressed = [0u8; 32];
+        let len = Lz4CompressionModule.compress(&decompressed, &mut compressed).unwrap();
+        assert_eq!(compressed[..len], encoded);
+    }
+
+    #[test]
+    fn test_lz4_roundtrip() {
+        for len in 0..300 {
+            let sparse: Vec<u8> = (0..len).map(|i| if i % 37 == 0 { i as u8 } else { 0 }).collect();
+            roundtrip(&sparse);
+
+            let random: Vec<u8> = (0..len).map(|i| ((i * 2654435761usize) >> 13) as u8).collect();
+            roundtrip(&random);
+        }
+
+        // long literal and match lengths
+        let mut data = vec![0u8; 5000];
+        for (i, byte) in data.iter_mut().take(700).enumerate() {
+            *byte = ((i * 2654435761usize) >> 11) as u8;
+        }
+        assert!(roundtrip(&data) < 1000);
+    }
+
+    #[test]
+    fn test_lz4_insufficient_space() {
+        let data: Vec<u8> = (0..64).map(|i| ((i * 2654435761usize) >> 13) as u8).collect();
+        let mut compressed = [0u8; 64];
+        assert_eq!(Lz4CompressionModule.compress(&data, &mut compressed), None);
+    }
+
+    #[test]
+    fn test_lz4_malformed() {
+        let mut dest = [0u8; 16];
+        // offset points before the start of the output
+        assert!(Lz4CompressionModule.decompress(&[0x10, 1, 2, 0, 0x00], &mut dest).is_err());
+        // output is shorter than expected
+        assert!(Lz4CompressionModule.decompress(&[0x10, 1], &mut dest).is_err());
+        // literals are missing
+        assert!(Lz4CompressionModule.decompress(&[0xf0, 3], &mut dest).is_err());
+        // output is longer than expected
+        assert!(Lz4CompressionModule.decompress(&[0x4f, 1, 2, 3, 4, 1, 0, 20, 0x00], &mut dest).is_err());
+    }
+}
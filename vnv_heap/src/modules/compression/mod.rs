@@ -0,0 +1,33 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+mod lz4;
+pub use lz4::*;
+
+/// Lossless compression that is used for objects allocated with `VNVHeap::allocate_compressed`.
+pub trait CompressionModule {
+    /// Compresses `src` into `dest` and returns the number of bytes written.
+    ///
+    /// Returns `None` if the compressed data does not fit into `dest`.
+    /// In this case, the object is stored uncompressed.
+    fn compress(&self, src: &[u8], dest: &mut [u8]) -> Option<usize>;
+
+    /// Decompresses `src` into `dest`.
+    ///
+    /// Returns an error if `src` is malformed or does not decompress to exactly `dest.len()` bytes.
+    fn decompress(&self, src: &[u8], dest: &mut [u8]) -> Result<(), ()>;
+}
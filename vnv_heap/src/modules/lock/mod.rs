@@ -0,0 +1,35 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+mod spin;
+pub use spin::*;
+
+/// Mutual exclusion that is used by `SyncVNVHeap` to share a heap between threads.
+///
+/// This could be a spinlock, a critical section (e.g. disabling interrupts on a single core system)
+/// or a mutex of an RTOS.
+///
+/// ### Safety
+///
+/// Between a call to `lock` and the following call to `unlock`, no other call to `lock` may return.
+pub unsafe trait LockModule {
+    /// Blocks until the lock is acquired
+    fn lock(&self);
+
+    /// Releases the lock that was acquired with `lock`
+    fn unlock(&self);
+}
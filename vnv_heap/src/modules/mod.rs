@@ -16,6 +16,9 @@
  */
 
 pub mod allocator;
+pub mod compression;
+pub mod lock;
 pub mod nonresident_allocator;
+pub mod object_encryption;
 pub mod object_management;
 pub mod persistent_storage;
@@ -17,7 +17,7 @@
 
 use std::mem::size_of;
 use crate::util::div_ceil;
-use super::NonResidentAllocatorModule;
+use super::{NonResidentAllocationRounding, NonResidentAllocatorModule};
 
 type BitListType = usize;
 
@@ -39,6 +39,9 @@ pub struct NonResidentBlockAllocator<const BLOCK_SIZE: usize, const BIT_LIST_SIZ
 impl<const BLOCK_SIZE: usize, const BIT_LIST_SIZE: usize> NonResidentAllocatorModule
     for NonResidentBlockAllocator<BLOCK_SIZE, BIT_LIST_SIZE>
 {
+    const ALLOCATION_ROUNDING: NonResidentAllocationRounding =
+        NonResidentAllocationRounding::Multiple { block_size: BLOCK_SIZE };
+
     fn new() -> Self {
         Self {
             bit_list: [0; BIT_LIST_SIZE],
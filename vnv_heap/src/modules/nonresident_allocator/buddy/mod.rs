@@ -4,7 +4,7 @@
 
 use log::trace;
 
-use super::{NonResidentAllocatorModule, SimpleNonResidentLinkedList};
+use super::{NonResidentAllocationRounding, NonResidentAllocatorModule, SimpleNonResidentLinkedList};
 use crate::modules::persistent_storage::PersistentStorageModule;
 use core::alloc::Layout;
 use core::array;
@@ -18,7 +18,35 @@ pub struct NonResidentBuddyAllocatorModule<const ORDER: usize> {
     free_list: [SimpleNonResidentLinkedList; ORDER],
 }
 
+impl<const ORDER: usize> NonResidentBuddyAllocatorModule<ORDER> {
+    /// Removes a free block of size class `class`
+    #[cfg(not(feature = "deterministic_layout"))]
+    fn pop_block<S: PersistentStorageModule>(&mut self, class: usize, storage_module: &mut S) -> Result<Option<usize>, ()> {
+        self.free_list[class].pop(storage_module)
+    }
+
+    /// Removes the free block with the lowest offset of size class `class`,
+    /// so that the chosen block does not depend on the order in which blocks were freed
+    #[cfg(feature = "deterministic_layout")]
+    fn pop_block<S: PersistentStorageModule>(&mut self, class: usize, storage_module: &mut S) -> Result<Option<usize>, ()> {
+        let mut lowest: Option<usize> = None;
+        let mut iter = self.free_list[class].iter();
+        while let Some(item) = iter.next(storage_module)? {
+            let offset = item.get_base_offset();
+            lowest = Some(lowest.map_or(offset, |lowest| min(lowest, offset)));
+        }
+
+        if let Some(lowest) = lowest {
+            self.free_list[class].remove_where(storage_module, true, |offset| offset == lowest)?;
+        }
+        Ok(lowest)
+    }
+}
+
 impl<const ORDER: usize> NonResidentAllocatorModule for NonResidentBuddyAllocatorModule<ORDER> {
+    const ALLOCATION_ROUNDING: NonResidentAllocationRounding =
+        NonResidentAllocationRounding::PowerOfTwo { min_size: size_of::<usize>() };
+
     fn new() -> Self {
         Self {
             free_list: array::from_fn(|_| SimpleNonResidentLinkedList::new()),
@@ -82,7 +110,7 @@ impl<const ORDER: usize> NonResidentAllocatorModule for NonResidentBuddyAllocato
                     (class + 1..i + 1).len()
                 );
                 for j in (class + 1..i + 1).rev() {
-                    if let Some(block) = self.free_list[j].pop(storage_module)? {
+                    if let Some(block) = self.pop_block(j, storage_module)? {
                         unsafe {
                             self.free_list[j - 1].push(block + (1 << (j - 1)), storage_module)?;
                             self.free_list[j - 1].push(block, storage_module)?;
@@ -92,8 +120,8 @@ impl<const ORDER: usize> NonResidentAllocatorModule for NonResidentBuddyAllocato
                     }
                 }
 
-                return Ok(self.free_list[class]
-                    .pop(storage_module)?
+                return Ok(self
+                    .pop_block(class, storage_module)?
                     .expect("current block should have free space now"));
             }
         }
@@ -141,6 +169,80 @@ impl<const ORDER: usize> NonResidentAllocatorModule for NonResidentBuddyAllocato
 
         Ok(())
     }
+
+    fn allocate_many<S: PersistentStorageModule>(
+        &mut self,
+        layout: Layout,
+        offsets: &mut [usize],
+        storage_module: &mut S,
+    ) -> Result<(), ()> {
+        if offsets.is_empty() {
+            return Ok(());
+        }
+
+        let size = max(
+            layout.size().next_power_of_two(),
+            max(layout.align(), size_of::<usize>()),
+        );
+        let total_size = size.checked_mul(offsets.len()).ok_or(())?;
+
+        // reserve one block for all regions, so that they are placed right after each other
+        let base = self.allocate(Layout::from_size_align(total_size, size).map_err(|_| ())?, storage_module)?;
+
+        // the block was rounded up to the next power of two, give the unused tail back
+        let mut current_start = base + total_size;
+        let end = base + total_size.next_power_of_two();
+        while current_start < end {
+            let lowbit = current_start & (!current_start + 1);
+            let block_size = min(lowbit, prev_power_of_two(end - current_start));
+            self.deallocate(current_start, Layout::from_size_align(block_size, 1).unwrap(), storage_module)?;
+            current_start += block_size;
+        }
+
+        for (i, offset) in offsets.iter_mut().enumerate() {
+            *offset = base + i * size;
+        }
+
+        Ok(())
+    }
+
+    fn allocate_below<S: PersistentStorageModule>(
+        &mut self,
+        layout: Layout,
+        limit: usize,
+        storage_module: &mut S,
+    ) -> Result<Option<usize>, ()> {
+        let size = max(
+            layout.size().next_power_of_two(),
+            max(layout.align(), size_of::<usize>()),
+        );
+        let class = size.trailing_zeros() as usize;
+
+        // search the lowest free block that is big enough
+        let mut lowest: Option<(usize, usize)> = None;
+        for i in class..self.free_list.len() {
+            let mut iter = self.free_list[i].iter();
+            while let Some(item) = iter.next(storage_module)? {
+                let offset = item.get_base_offset();
+                if offset < limit && lowest.map_or(true, |(lowest, _)| offset < lowest) {
+                    lowest = Some((offset, i));
+                }
+            }
+        }
+
+        let (block, block_class) = match lowest {
+            Some(lowest) => lowest,
+            None => return Ok(None),
+        };
+        self.free_list[block_class].remove_where(storage_module, true, |offset| offset == block)?;
+
+        // split the block and keep its lowest part
+        for j in (class + 1..block_class + 1).rev() {
+            unsafe { self.free_list[j - 1].push(block + (1 << (j - 1)), storage_module)? };
+        }
+
+        Ok(Some(block))
+    }
 }
 
 impl<const ORDER: usize> NonResidentBuddyAllocatorModule<ORDER> {
@@ -162,11 +264,14 @@ fn prev_power_of_two(num: usize) -> usize {
 
 #[cfg(test)]
 mod test {
+    use core::alloc::Layout;
+
     use crate::modules::{
-        nonresident_allocator::test::{
-            test_non_resident_allocator_simple_generic, AllocatedRegion,
+        nonresident_allocator::{
+            test::{test_non_resident_allocator_simple_generic, AllocatedRegion},
+            NonResidentAllocatorModule,
         },
-        persistent_storage::PersistentStorageModule,
+        persistent_storage::{test::get_test_storage, PersistentStorageModule},
     };
 
     use super::NonResidentBuddyAllocatorModule;
@@ -176,6 +281,52 @@ mod test {
         test_non_resident_allocator_simple_generic(check_integrity, "test_non_resident_allocator_simple_buddy");
     }
 
+    #[test]
+    fn test_allocate_many() {
+        let mut storage = get_test_storage("test_allocate_many_buddy", 1024);
+        let mut allocator = NonResidentBuddyAllocatorModule::<16>::new();
+        allocator.init(0, 1024, &mut storage).unwrap();
+
+        let layout = Layout::from_size_align(5, 1).unwrap();
+        let mut offsets = [0usize; 3];
+        allocator.allocate_many(layout, &mut offsets, &mut storage).unwrap();
+        assert_eq!(offsets[1], offsets[0] + 8);
+        assert_eq!(offsets[2], offsets[0] + 16);
+
+        // the unused tail of the reserved block is free again
+        let offset = allocator.allocate(layout, &mut storage).unwrap();
+        assert_eq!(offset, offsets[0] + 24);
+        allocator.deallocate(offset, layout, &mut storage).unwrap();
+
+        for offset in offsets {
+            allocator.deallocate(offset, layout, &mut storage).unwrap();
+        }
+
+        // all blocks were merged again
+        assert_eq!(allocator.allocate(Layout::from_size_align(1024, 1).unwrap(), &mut storage), Ok(0));
+    }
+
+    #[test]
+    fn test_allocate_below() {
+        let mut storage = get_test_storage("test_allocate_below_buddy", 1024);
+        let mut allocator = NonResidentBuddyAllocatorModule::<16>::new();
+        allocator.init(0, 1024, &mut storage).unwrap();
+
+        let layout = Layout::from_size_align(64, 1).unwrap();
+        let offsets: Vec<usize> = (0..4).map(|_| allocator.allocate(layout, &mut storage).unwrap()).collect();
+        assert_eq!(offsets, [0, 64, 128, 192]);
+        allocator.deallocate(0, layout, &mut storage).unwrap();
+        allocator.deallocate(128, layout, &mut storage).unwrap();
+
+        // the lowest free block is chosen, even though 128 was freed last
+        assert_eq!(allocator.allocate_below(layout, 192, &mut storage), Ok(Some(0)));
+        assert_eq!(allocator.allocate_below(layout, 128, &mut storage), Ok(None));
+
+        // bigger blocks are split
+        assert_eq!(allocator.allocate_below(Layout::from_size_align(8, 1).unwrap(), 1024, &mut storage), Ok(Some(128)));
+        assert_eq!(allocator.allocate(Layout::from_size_align(8, 1).unwrap(), &mut storage), Ok(136));
+    }
+
     /// checks that the free list does not overlap itself
     /// and that it does no overlap with allocated regions
     fn check_integrity<S: PersistentStorageModule>(
@@ -16,11 +16,13 @@
  */
 
 use super::persistent_storage::PersistentStorageModule;
+use crate::util::div_ceil;
 use core::alloc::Layout;
 
 mod block;
 mod buddy;
 mod linked_list;
+mod slab;
 
 pub use buddy::NonResidentBuddyAllocatorModule;
 pub use linked_list::{
@@ -28,10 +30,50 @@ pub use linked_list::{
     SharedAtomicLinkedListHeadPtr, SimpleIter, SimpleNonResidentLinkedList,
 };
 pub use block::{NonResidentBlockAllocator, calc_non_resident_block_allocator_bit_list_size};
+pub use slab::NonResidentSlabAllocator;
+
+/// Describes how a `NonResidentAllocatorModule` rounds up the size of allocations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonResidentAllocationRounding {
+    /// Sizes are rounded up to the next power of two, but at least to `min_size`
+    PowerOfTwo { min_size: usize },
+
+    /// Sizes are rounded up to a multiple of `block_size`
+    Multiple { block_size: usize },
+}
+
+impl NonResidentAllocationRounding {
+    /// Smallest amount of bytes that is used up by an allocation
+    pub const fn min_size(&self) -> usize {
+        match self {
+            Self::PowerOfTwo { min_size } => *min_size,
+            Self::Multiple { block_size } => *block_size,
+        }
+    }
+
+    /// Returns the amount of bytes that are used up by an allocation of `size` bytes
+    pub const fn apply(&self, size: usize) -> usize {
+        match self {
+            Self::PowerOfTwo { min_size } => {
+                let size = size.next_power_of_two();
+                if size < *min_size {
+                    *min_size
+                } else {
+                    size
+                }
+            }
+            Self::Multiple { block_size } => div_ceil(size, *block_size) * *block_size,
+        }
+    }
+}
 
 /// An allocator module that is not stored inside RAM,
 /// but is rather stored on some kind of non volatile storage device
 pub trait NonResidentAllocatorModule {
+    /// How the size of allocations is rounded up (used for capacity planning)
+    const ALLOCATION_ROUNDING: NonResidentAllocationRounding =
+        NonResidentAllocationRounding::Multiple { block_size: 1 };
+
     /// Creates a new allocator module object.
     ///
     /// **Note**: It first will be initialized before it will be used
@@ -60,6 +102,54 @@ pub trait NonResidentAllocatorModule {
         layout: Layout,
         storage_module: &mut S,
     ) -> Result<(), ()>;
+
+    /// Allocates `offsets.len()` regions with the same `layout` and writes their offsets to `offsets`.
+    ///
+    /// Either all or none of the regions are allocated. The default implementation calls `allocate` for every region,
+    /// allocators should override this if they can reserve all regions at once.
+    fn allocate_many<S: PersistentStorageModule>(
+        &mut self,
+        layout: Layout,
+        offsets: &mut [usize],
+        storage_module: &mut S,
+    ) -> Result<(), ()> {
+        for i in 0..offsets.len() {
+            match self.allocate(layout, storage_module) {
+                Ok(offset) => offsets[i] = offset,
+                Err(()) => {
+                    for offset in offsets[..i].iter() {
+                        self.deallocate(*offset, layout, storage_module)?;
+                    }
+                    return Err(());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Allocates a region with an offset lower than `limit` and returns its offset, or `None` if there is no such region.
+    ///
+    /// This is used to move objects to the start of the storage (see `VNVHeap::defragment`).
+    /// The default implementation calls `allocate` and gives the region back if it is not below `limit`,
+    /// allocators should override this if they can search for a lower region.
+    fn allocate_below<S: PersistentStorageModule>(
+        &mut self,
+        layout: Layout,
+        limit: usize,
+        storage_module: &mut S,
+    ) -> Result<Option<usize>, ()> {
+        let offset = match self.allocate(layout, storage_module) {
+            Ok(offset) => offset,
+            Err(()) => return Ok(None),
+        };
+        if offset >= limit {
+            self.deallocate(offset, layout, storage_module)?;
+            return Ok(None);
+        }
+
+        Ok(Some(offset))
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,179 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{alloc::Layout, array};
+
+use super::{NonResidentAllocationRounding, NonResidentAllocatorModule, SimpleNonResidentLinkedList};
+use crate::modules::persistent_storage::PersistentStorageModule;
+
+/// Non resident slab allocator with `CLASSES` size classes of `MIN_SLOT_SIZE << class` bytes
+///
+/// Every size class has its own free list, so allocating and deallocating only push or pop one slot.
+/// Slots are taken from the end of the used region if their free list is empty and are never merged or split.
+/// This fits applications that only use a few object sizes, as freed slots can only be reused by objects of the same size class.
+pub struct NonResidentSlabAllocator<const MIN_SLOT_SIZE: usize, const CLASSES: usize> {
+    free_lists: [SimpleNonResidentLinkedList; CLASSES],
+
+    /// Start of the region that was never handed out
+    next: usize,
+    end: usize,
+}
+
+impl<const MIN_SLOT_SIZE: usize, const CLASSES: usize> NonResidentSlabAllocator<MIN_SLOT_SIZE, CLASSES> {
+    /// Returns the size class for allocations of `size` bytes
+    fn size_class(size: usize) -> Option<usize> {
+        let slot_size = Self::ALLOCATION_ROUNDING.apply(size);
+        let class = (slot_size / MIN_SLOT_SIZE).trailing_zeros() as usize;
+        if class < CLASSES {
+            Some(class)
+        } else {
+            None
+        }
+    }
+
+    /// Removes a free slot of size class `class`
+    #[cfg(not(feature = "deterministic_layout"))]
+    fn pop_slot<S: PersistentStorageModule>(&mut self, class: usize, storage_module: &mut S) -> Result<Option<usize>, ()> {
+        self.free_lists[class].pop(storage_module)
+    }
+
+    /// Removes the free slot with the lowest offset of size class `class`,
+    /// so that the chosen slot does not depend on the order in which slots were freed
+    #[cfg(feature = "deterministic_layout")]
+    fn pop_slot<S: PersistentStorageModule>(&mut self, class: usize, storage_module: &mut S) -> Result<Option<usize>, ()> {
+        let mut lowest: Option<usize> = None;
+        let mut iter = self.free_lists[class].iter();
+        while let Some(item) = iter.next(storage_module)? {
+            let offset = item.get_base_offset();
+            lowest = Some(lowest.map_or(offset, |lowest| lowest.min(offset)));
+        }
+
+        if let Some(lowest) = lowest {
+            self.free_lists[class].remove_where(storage_module, true, |offset| offset == lowest)?;
+        }
+        Ok(lowest)
+    }
+}
+
+impl<const MIN_SLOT_SIZE: usize, const CLASSES: usize> NonResidentAllocatorModule
+    for NonResidentSlabAllocator<MIN_SLOT_SIZE, CLASSES>
+{
+    const ALLOCATION_ROUNDING: NonResidentAllocationRounding =
+        NonResidentAllocationRounding::PowerOfTwo { min_size: MIN_SLOT_SIZE };
+
+    fn new() -> Self {
+        assert!(
+            MIN_SLOT_SIZE.is_power_of_two() && MIN_SLOT_SIZE >= SimpleNonResidentLinkedList::total_item_size(),
+            "MIN_SLOT_SIZE has to be a power of two that can hold a free list item"
+        );
+
+        Self {
+            free_lists: array::from_fn(|_| SimpleNonResidentLinkedList::new()),
+            next: 0,
+            end: 0,
+        }
+    }
+
+    fn init<S: PersistentStorageModule>(
+        &mut self,
+        offset: usize,
+        size: usize,
+        _storage_module: &mut S,
+    ) -> Result<(), ()> {
+        self.free_lists = array::from_fn(|_| SimpleNonResidentLinkedList::new());
+        self.next = offset;
+        self.end = offset + size;
+
+        Ok(())
+    }
+
+    fn allocate<S: PersistentStorageModule>(
+        &mut self,
+        layout: Layout,
+        storage_module: &mut S,
+    ) -> Result<usize, ()> {
+        // we don't need to worry about alignment on non volatile storage
+        let class = Self::size_class(layout.size()).ok_or(())?;
+        if let Some(offset) = self.pop_slot(class, storage_module)? {
+            return Ok(offset);
+        }
+
+        let slot_size = MIN_SLOT_SIZE << class;
+        if self.end - self.next < slot_size {
+            return Err(());
+        }
+
+        let offset = self.next;
+        self.next += slot_size;
+        Ok(offset)
+    }
+
+    fn deallocate<S: PersistentStorageModule>(
+        &mut self,
+        offset: usize,
+        layout: Layout,
+        storage_module: &mut S,
+    ) -> Result<(), ()> {
+        let class = Self::size_class(layout.size()).ok_or(())?;
+        unsafe { self.free_lists[class].push(offset, storage_module) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::alloc::Layout;
+
+    use crate::modules::{
+        nonresident_allocator::NonResidentAllocatorModule,
+        persistent_storage::test::get_test_storage,
+    };
+
+    use super::NonResidentSlabAllocator;
+
+    #[test]
+    fn test_non_resident_slab_allocator() {
+        let mut storage = get_test_storage("test_non_resident_slab_allocator", 256);
+        let mut allocator = NonResidentSlabAllocator::<32, 3>::new();
+        allocator.init(0, 256, &mut storage).unwrap();
+
+        let small = Layout::new::<[u8; 20]>();
+        let big = Layout::new::<[u8; 100]>();
+
+        assert_eq!(allocator.allocate(small, &mut storage), Ok(0));
+        assert_eq!(allocator.allocate(big, &mut storage), Ok(32));
+        assert_eq!(allocator.allocate(small, &mut storage), Ok(160));
+
+        // bigger than the biggest size class
+        assert!(allocator.allocate(Layout::new::<[u8; 129]>(), &mut storage).is_err());
+
+        // freed slots are only reused by the same size class
+        allocator.deallocate(32, big, &mut storage).unwrap();
+        assert_eq!(allocator.allocate(small, &mut storage), Ok(192));
+        assert_eq!(allocator.allocate(small, &mut storage), Ok(224));
+        assert!(allocator.allocate(small, &mut storage).is_err());
+        assert_eq!(allocator.allocate(big, &mut storage), Ok(32));
+
+        allocator.deallocate(0, small, &mut storage).unwrap();
+        allocator.deallocate(192, small, &mut storage).unwrap();
+        let mut offsets = [
+            allocator.allocate(small, &mut storage).unwrap(),
+            allocator.allocate(small, &mut storage).unwrap(),
+        ];
+        offsets.sort();
+        assert_eq!(offsets, [0, 192]);
+    }
+}
@@ -0,0 +1,268 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use super::{ObjectEncryptionKey, ObjectEncryptionModule, ObjectEncryptionNonce, ObjectEncryptionTag};
+
+const ASCON_128_IV: u64 = 0x80400c0600000000;
+const ASCON_128_RATE: usize = 8;
+const ROUND_CONSTANTS: [u64; 12] = [
+    0xf0, 0xe1, 0xd2, 0xc3, 0xb4, 0xa5, 0x96, 0x87, 0x78, 0x69, 0x5a, 0x4b,
+];
+
+/// Ascon-128 authenticated encryption (lightweight, suitable for embedded devices).
+///
+/// Implemented in software, without any dependencies or lookup tables.
+#[derive(Clone, Copy, Default)]
+pub struct Ascon128EncryptionModule;
+
+impl Ascon128EncryptionModule {
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl ObjectEncryptionModule for Ascon128EncryptionModule {
+    fn encrypt(
+        &self,
+        key: &ObjectEncryptionKey,
+        nonce: &ObjectEncryptionNonce,
+        associated_data: &[u8],
+        data: &mut [u8],
+    ) -> ObjectEncryptionTag {
+        let mut state = AsconState::init(key, nonce);
+        state.absorb_associated_data(associated_data);
+
+        let mut chunks = data.chunks_exact_mut(ASCON_128_RATE);
+        for block in &mut chunks {
+            state.x[0] ^= load_u64(block);
+            store_u64(block, state.x[0]);
+            state.permute(6);
+        }
+
+        let rem = chunks.into_remainder();
+        state.x[0] ^= load_u64(rem) ^ pad(rem.len());
+        store_u64(rem, state.x[0]);
+
+        state.finalize(key)
+    }
+
+    fn decrypt(
+        &self,
+        key: &ObjectEncryptionKey,
+        nonce: &ObjectEncryptionNonce,
+        associated_data: &[u8],
+        data: &mut [u8],
+        tag: &ObjectEncryptionTag,
+    ) -> Result<(), ()> {
+        let mut state = AsconState::init(key, nonce);
+        state.absorb_associated_data(associated_data);
+
+        let mut chunks = data.chunks_exact_mut(ASCON_128_RATE);
+        for block in &mut chunks {
+            let c = load_u64(block);
+            store_u64(block, state.x[0] ^ c);
+            state.x[0] = c;
+            state.permute(6);
+        }
+
+        let rem = chunks.into_remainder();
+        let c = load_u64(rem);
+        let keep_mask = if rem.is_empty() { u64::MAX } else { u64::MAX >> (8 * rem.len()) };
+        store_u64(rem, state.x[0] ^ c);
+        state.x[0] = (state.x[0] & keep_mask) ^ c ^ pad(rem.len());
+
+        let expected = state.finalize(key);
+
+        // compare in constant time
+        let diff = expected.iter().zip(tag.iter()).fold(0u8, |acc, (a, b)| acc | (a ^ b));
+        if diff == 0 {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+}
+
+struct AsconState {
+    x: [u64; 5],
+}
+
+impl AsconState {
+    fn init(key: &ObjectEncryptionKey, nonce: &ObjectEncryptionNonce) -> Self {
+        let k0 = load_u64(&key[0..8]);
+        let k1 = load_u64(&key[8..16]);
+
+        let mut state = AsconState {
+            x: [ASCON_128_IV, k0, k1, load_u64(&nonce[0..8]), load_u64(&nonce[8..16])],
+        };
+        state.permute(12);
+        state.x[3] ^= k0;
+        state.x[4] ^= k1;
+        state
+    }
+
+    fn absorb_associated_data(&mut self, associated_data: &[u8]) {
+        if !associated_data.is_empty() {
+            let mut chunks = associated_data.chunks_exact(ASCON_128_RATE);
+            for block in &mut chunks {
+                self.x[0] ^= load_u64(block);
+                self.permute(6);
+            }
+
+            let rem = chunks.remainder();
+            self.x[0] ^= load_u64(rem) ^ pad(rem.len());
+            self.permute(6);
+        }
+
+        // domain separation
+        self.x[4] ^= 1;
+    }
+
+    fn finalize(&mut self, key: &ObjectEncryptionKey) -> ObjectEncryptionTag {
+        let k0 = load_u64(&key[0..8]);
+        let k1 = load_u64(&key[8..16]);
+
+        self.x[1] ^= k0;
+        self.x[2] ^= k1;
+        self.permute(12);
+        self.x[3] ^= k0;
+        self.x[4] ^= k1;
+
+        let mut tag = [0u8; 16];
+        tag[0..8].copy_from_slice(&self.x[3].to_be_bytes());
+        tag[8..16].copy_from_slice(&self.x[4].to_be_bytes());
+        tag
+    }
+
+    fn permute(&mut self, rounds: usize) {
+        let [mut x0, mut x1, mut x2, mut x3, mut x4] = self.x;
+
+        for c in &ROUND_CONSTANTS[(12 - rounds)..] {
+            // round constant
+            x2 ^= c;
+
+            // substitution layer
+            x0 ^= x4;
+            x4 ^= x3;
+            x2 ^= x1;
+            let t0 = !x0 & x1;
+            let t1 = !x1 & x2;
+            let t2 = !x2 & x3;
+            let t3 = !x3 & x4;
+            let t4 = !x4 & x0;
+            x0 ^= t1;
+            x1 ^= t2;
+            x2 ^= t3;
+            x3 ^= t4;
+            x4 ^= t0;
+            x1 ^= x0;
+            x0 ^= x4;
+            x3 ^= x2;
+            x2 = !x2;
+
+            // linear diffusion layer
+            x0 ^= x0.rotate_right(19) ^ x0.rotate_right(28);
+            x1 ^= x1.rotate_right(61) ^ x1.rotate_right(39);
+            x2 ^= x2.rotate_right(1) ^ x2.rotate_right(6);
+            x3 ^= x3.rotate_right(10) ^ x3.rotate_right(17);
+            x4 ^= x4.rotate_right(7) ^ x4.rotate_right(41);
+        }
+
+        self.x = [x0, x1, x2, x3, x4];
+    }
+}
+
+/// Loads up to 8 bytes as big endian value (missing bytes are zero)
+fn load_u64(bytes: &[u8]) -> u64 {
+    debug_assert!(bytes.len() <= 8);
+    let mut buf = [0u8; 8];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    u64::from_be_bytes(buf)
+}
+
+/// Stores the first `bytes.len()` bytes of `value` in big endian order
+fn store_u64(bytes: &mut [u8], value: u64) {
+    debug_assert!(bytes.len() <= 8);
+    let len = bytes.len();
+    bytes.copy_from_slice(&value.to_be_bytes()[..len]);
+}
+
+/// Padding for a block that contains `len` bytes
+fn pad(len: usize) -> u64 {
+    0x80u64 << (56 - 8 * len)
+}
+
+#[cfg(test)]
+mod test {
+    use super::Ascon128EncryptionModule;
+    use crate::modules::object_encryption::ObjectEncryptionModule;
+
+    fn counting_array() -> [u8; 16] {
+        core::array::from_fn(|i| i as u8)
+    }
+
+    #[test]
+    fn test_ascon128_known_answer() {
+        // Ascon-128 reference test vector (empty plaintext and associated data)
+        let tag = Ascon128EncryptionModule.encrypt(&counting_array(), &counting_array(), &[], &mut []);
+        assert_eq!(
+            tag,
+            [
+                0xe3, 0x55, 0x15, 0x9f, 0x29, 0x29, 0x11, 0xf7, 0x94, 0xcb, 0x14, 0x32, 0xa0,
+                0x10, 0x3a, 0x8a
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ascon128_roundtrip() {
+        let module = Ascon128EncryptionModule::new();
+        let key = counting_array();
+        let nonce = [7u8; 16];
+
+        for len in 0..40 {
+            let plaintext: Vec<u8> = (0..len).map(|i| (i * 13) as u8).collect();
+            let associated_data: Vec<u8> = (0..(len % 11)).map(|i| i as u8).collect();
+
+            let mut data = plaintext.clone();
+            let tag = module.encrypt(&key, &nonce, &associated_data, &mut data);
+            if len > 0 {
+                assert_ne!(data, plaintext);
+            }
+
+            let mut decrypted = data.clone();
+            module.decrypt(&key, &nonce, &associated_data, &mut decrypted, &tag).unwrap();
+            assert_eq!(decrypted, plaintext);
+
+            // wrong tag
+            let mut wrong_tag = tag;
+            wrong_tag[3] ^= 1;
+            let mut decrypted = data.clone();
+            assert!(module.decrypt(&key, &nonce, &associated_data, &mut decrypted, &wrong_tag).is_err());
+
+            // wrong nonce
+            let mut decrypted = data.clone();
+            assert!(module.decrypt(&key, &[8u8; 16], &associated_data, &mut decrypted, &tag).is_err());
+
+            // modified ciphertext
+            if len > 0 {
+                let mut decrypted = data.clone();
+                decrypted[len - 1] ^= 0x40;
+                assert!(module.decrypt(&key, &nonce, &associated_data, &mut decrypted, &tag).is_err());
+            }
+        }
+    }
+}
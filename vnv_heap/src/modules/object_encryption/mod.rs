@@ -0,0 +1,52 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+mod ascon;
+pub use ascon::*;
+
+/// Size of keys, nonces and tags used for object encryption in bytes
+pub const OBJECT_ENCRYPTION_BLOCK_SIZE: usize = 16;
+
+pub type ObjectEncryptionKey = [u8; OBJECT_ENCRYPTION_BLOCK_SIZE];
+pub type ObjectEncryptionNonce = [u8; OBJECT_ENCRYPTION_BLOCK_SIZE];
+pub type ObjectEncryptionTag = [u8; OBJECT_ENCRYPTION_BLOCK_SIZE];
+
+/// Authenticated encryption that is used for objects allocated with `VNVHeap::allocate_encrypted`.
+///
+/// Nonces are managed by the heap. Implementations can assume that a nonce is never
+/// reused for the same key.
+pub trait ObjectEncryptionModule {
+    /// Encrypts `data` in place and returns the authentication tag over `associated_data` and `data`.
+    fn encrypt(
+        &self,
+        key: &ObjectEncryptionKey,
+        nonce: &ObjectEncryptionNonce,
+        associated_data: &[u8],
+        data: &mut [u8],
+    ) -> ObjectEncryptionTag;
+
+    /// Decrypts `data` in place.
+    ///
+    /// Returns an error if `tag` does not match. In this case, the content of `data` is undefined.
+    fn decrypt(
+        &self,
+        key: &ObjectEncryptionKey,
+        nonce: &ObjectEncryptionNonce,
+        associated_data: &[u8],
+        data: &mut [u8],
+        tag: &ObjectEncryptionTag,
+    ) -> Result<(), ()>;
+}
@@ -23,7 +23,10 @@ use crate::modules::{allocator::AllocatorModule, persistent_storage::PersistentS
 use core::{alloc::Layout, ptr::null_mut};
 use std::marker::PhantomData;
 
-// completely stateless
+/// Second chance (clock) algorithm for both syncing modified and unloading objects.
+///
+/// Instead of keeping an LRU list, every object only uses two bits of its `ResidentObjectStatus`
+/// (accessed and modified). The module itself only stores the current position of both clock hands.
 pub struct ClockObjectManagementModule {
     modified_clock: ModifiedClock,
     resident_clock: ResidentClock,
@@ -91,6 +94,29 @@ impl ObjectManagementModule for ClockObjectManagementModule {
         Err(())
     }
 
+    fn clean_dirty_data<A: AllocatorModule, S: PersistentStorageModule>(
+        &mut self,
+        budget_bytes: usize,
+        mut list: super::ObjectManagementList<'_, '_, '_, '_, A, S>,
+    ) {
+        let mut remaining = budget_bytes;
+
+        // objects that were not modified recently are cleaned first, as they are less likely to get dirty again
+        let mut iter = self.modified_clock.iter(&mut list);
+        while let Some(mut sub_iter) = iter.next() {
+            while let Some(mut item) = sub_iter.next() {
+                if item.get_metadata().get_size() > remaining {
+                    continue;
+                }
+
+                remaining = remaining.saturating_sub(item.sync_user_data().unwrap_or_default());
+                if remaining == 0 {
+                    return;
+                }
+            }
+        }
+    }
+
     fn access_object(&mut self, mut metadata: ObjectStatusWrapper) {
         metadata.access_object();
     }
@@ -168,7 +194,8 @@ impl GenericClock for ResidentClock {
     fn iter_item_valid<A: AllocatorModule, S: PersistentStorageModule>(
         item: &mut ObjectManagementIterItem<A, S>,
     ) -> bool {
-        !item.get_metadata().is_in_use()
+        let metadata = item.get_metadata();
+        !metadata.is_in_use() && !metadata.is_pinned()
     }
 }
 
@@ -318,11 +345,7 @@ impl<'a, 'b, 'e, A: AllocatorModule, S: PersistentStorageModule, C: GenericClock
 
 #[cfg(test)]
 mod test {
-    use std::sync::atomic::AtomicBool;
-
-    use try_lock::TryLock;
-
-    use crate::{allocation_identifier::AllocationIdentifier, modules::{allocator::LinkedListAllocatorModule, object_management::{clock::GenericClock, ObjectManagementList, ObjectManagementListArguments}, persistent_storage::test::get_test_storage}, resident_object_manager::{resident_list::ResidentList, resident_object_metadata::ResidentObjectMetadata, ResidentObjectManager}, shared_persist_lock::SharedPersistLock};
+    use crate::{allocation_identifier::AllocationIdentifier, persist_sync::{AtomicBool, TryLock}, modules::{allocator::LinkedListAllocatorModule, object_management::{clock::GenericClock, ObjectManagementList, ObjectManagementListArguments}, persistent_storage::test::get_test_storage}, resident_object_manager::{resident_object_backup::calc_backup_obj_layout_static, resident_list::ResidentList, resident_object_metadata::ResidentObjectMetadata, test::write_test_obj_data, ResidentObjectManager}, shared_persist_lock::SharedPersistLock, WriteBack};
 
     use super::ClockObjectManagementModule;
 
@@ -340,7 +363,7 @@ mod test {
 
         let mut resident_list = ResidentList::new();
 
-        let mut resident_object_manager = ResidentObjectManager::<_, ClockObjectManagementModule>::new(&mut buffer, BUFFER_SIZE, &mut resident_list, shared_allocator).unwrap();
+        let mut resident_object_manager = ResidentObjectManager::<_, ClockObjectManagementModule>::new(&mut buffer, BUFFER_SIZE, WriteBack::Lazy, &mut resident_list, shared_allocator).unwrap();
         let mut storage = get_test_storage("test_clock_object_management_module_flush", 4 * 1024);
         
         let mut allocated_objects = vec![];
@@ -371,7 +394,8 @@ mod test {
                     let args = ObjectManagementListArguments {
                         allocator: &mut resident_object_manager.heap,
                         remaining_dirty_size: &mut resident_object_manager.remaining_dirty_size,
-                        storage: &mut storage
+                        storage: &mut storage,
+                        stats: &mut resident_object_manager.stats,
                     };
                     args
                 }
@@ -384,6 +408,7 @@ mod test {
                     let list = ObjectManagementList{
                         arguments: &mut $args,
                         resident_list: &mut resident_object_manager.resident_list,
+                        filter: None,
                     };
                     list
                 }
@@ -458,10 +483,10 @@ mod test {
             }
 
             unsafe {
-                resident_object_manager.get_mut(&allocated_objects[i], false, &mut storage).unwrap();
+                resident_object_manager.get_mut(&allocated_objects[i], false, None, &mut storage).unwrap();
 
                 if i != 2 {
-                    resident_object_manager.release_mut(&allocated_objects[i]);
+                    resident_object_manager.release_mut(&allocated_objects[i], &mut storage);
                 }
             }
         }
@@ -511,7 +536,7 @@ mod test {
             };
         }
 
-        unsafe { resident_object_manager.release_mut(&allocated_objects[2]) };
+        unsafe { resident_object_manager.release_mut(&allocated_objects[2], &mut storage) };
         unsafe {
             let x = find_element_mut(&mut resident_object_manager, &allocated_objects[2]).unwrap().as_mut().unwrap();
             assert!(!x.inner.status.is_in_use());
@@ -553,7 +578,7 @@ mod test {
 
         let mut resident_list = ResidentList::new();
 
-        let mut resident_object_manager = ResidentObjectManager::<_, ClockObjectManagementModule>::new(&mut buffer, BUFFER_SIZE, &mut resident_list, shared_allocator).unwrap();
+        let mut resident_object_manager = ResidentObjectManager::<_, ClockObjectManagementModule>::new(&mut buffer, BUFFER_SIZE, WriteBack::Lazy, &mut resident_list, shared_allocator).unwrap();
         let mut storage = get_test_storage("test_clock_object_management_module_unload", 4 * 1024);
         
         let mut allocated_objects = vec![];        
@@ -572,7 +597,8 @@ mod test {
 
         for _ in 0..10 {
             let offset = curr_alloc_offset;
-            curr_alloc_offset += OBJ_SIZE;
+            curr_alloc_offset += calc_backup_obj_layout_static::<Object>().size();
+            write_test_obj_data(&mut storage, offset, &[0u8; OBJ_SIZE]);
             let identifier = AllocationIdentifier::<Object>::from_offset(offset);
             allocated_objects.push(identifier.clone());
             allocated_objects_is_resident.push(false);
@@ -582,7 +608,7 @@ mod test {
         for i in 0..3 {
             let identifier = &allocated_objects[i];
             unsafe {
-                resident_object_manager.get_ref::<Object, _>(&identifier, false, &mut storage).unwrap();
+                resident_object_manager.get_ref::<Object, _>(&identifier, false, None, &mut storage).unwrap();
 
                 if i != 0 {
                     resident_object_manager.release_ref(&identifier);
@@ -593,7 +619,7 @@ mod test {
 
         check_integrity!();
         unsafe {
-            resident_object_manager.get_ref::<Object, _>(&allocated_objects[3], false, &mut storage).unwrap();
+            resident_object_manager.get_ref::<Object, _>(&allocated_objects[3], false, None, &mut storage).unwrap();
             resident_object_manager.release_ref(&allocated_objects[3]);
         };
         allocated_objects_is_resident[3] = true;
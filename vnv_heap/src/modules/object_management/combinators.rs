@@ -0,0 +1,196 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::alloc::Layout;
+
+use super::{ObjectFilter, ObjectLocation, ObjectManagementList, ObjectManagementModule, ObjectStatusWrapper};
+use crate::modules::{allocator::AllocatorModule, persistent_storage::PersistentStorageModule};
+
+// Combinators wrap another ObjectManagementModule and only restrict which objects it can see
+// (see ObjectFilter). The wrapped module still decides in which order objects are synced/unloaded.
+// As combinators are ObjectManagementModules themselves, they can be stacked, e.g.:
+// PreferCleanObjectManagementModule<SizeBiasedObjectManagementModule<ClockObjectManagementModule>>
+
+struct ExcludeDirtyObjects;
+
+impl ObjectFilter for ExcludeDirtyObjects {
+    #[inline]
+    fn is_excluded(&self, object: &ObjectStatusWrapper) -> bool {
+        object.is_data_dirty()
+    }
+}
+
+/// Tries to unload objects that are not dirty first, as no data has to be written back for them.
+///
+/// Only if this does not free enough space, the wrapped module `M` may also unload dirty objects.
+pub struct PreferCleanObjectManagementModule<M: ObjectManagementModule> {
+    inner: M,
+}
+
+impl<M: ObjectManagementModule> ObjectManagementModule for PreferCleanObjectManagementModule<M> {
+    fn new() -> Self {
+        Self { inner: M::new() }
+    }
+
+    fn sync_dirty_data<A: AllocatorModule, S: PersistentStorageModule>(
+        &mut self,
+        required_bytes: usize,
+        list: ObjectManagementList<'_, '_, '_, '_, A, S>,
+    ) -> Result<(), ()> {
+        self.inner.sync_dirty_data(required_bytes, list)
+    }
+
+    fn unload_objects<A: AllocatorModule, S: PersistentStorageModule>(
+        &mut self,
+        layout: &Layout,
+        mut list: ObjectManagementList<'_, '_, '_, '_, A, S>,
+    ) -> Result<(), ()> {
+        let inner = &mut self.inner;
+        if list
+            .with_filter(ExcludeDirtyObjects, |list| inner.unload_objects(layout, list))
+            .is_ok()
+        {
+            return Ok(());
+        }
+
+        self.inner.unload_objects(layout, list)
+    }
+
+    fn clean_dirty_data<A: AllocatorModule, S: PersistentStorageModule>(
+        &mut self,
+        budget_bytes: usize,
+        list: ObjectManagementList<'_, '_, '_, '_, A, S>,
+    ) {
+        self.inner.clean_dirty_data(budget_bytes, list)
+    }
+
+    fn access_object(&mut self, metadata: ObjectStatusWrapper) {
+        self.inner.access_object(metadata)
+    }
+
+    fn modify_object(&mut self, metadata: ObjectStatusWrapper) {
+        self.inner.modify_object(metadata)
+    }
+
+    fn predict_next_access(&mut self) -> Option<ObjectLocation> {
+        self.inner.predict_next_access()
+    }
+
+    fn object_deallocated(&mut self, offset: usize) {
+        self.inner.object_deallocated(offset)
+    }
+}
+
+/// Excludes objects whose user data is smaller than `min_size`
+struct ExcludeSmallObjects {
+    min_size: usize,
+}
+
+impl ObjectFilter for ExcludeSmallObjects {
+    #[inline]
+    fn is_excluded(&self, object: &ObjectStatusWrapper) -> bool {
+        object.get_size() < self.min_size
+    }
+}
+
+/// Excludes objects that use less than `min_size` bytes of the resident buffer
+struct ExcludeSmallResidentObjects {
+    min_size: usize,
+}
+
+impl ObjectFilter for ExcludeSmallResidentObjects {
+    #[inline]
+    fn is_excluded(&self, object: &ObjectStatusWrapper) -> bool {
+        object.get_resident_size() < self.min_size
+    }
+}
+
+/// Tries to sync/unload objects that are at least as big as the requested amount of bytes first.
+///
+/// This way, a single object is often enough, instead of having to sync/unload many small objects.
+/// If this does not work out, all objects are passed to the wrapped module `M`.
+pub struct SizeBiasedObjectManagementModule<M: ObjectManagementModule> {
+    inner: M,
+}
+
+impl<M: ObjectManagementModule> ObjectManagementModule for SizeBiasedObjectManagementModule<M> {
+    fn new() -> Self {
+        Self { inner: M::new() }
+    }
+
+    fn sync_dirty_data<A: AllocatorModule, S: PersistentStorageModule>(
+        &mut self,
+        required_bytes: usize,
+        mut list: ObjectManagementList<'_, '_, '_, '_, A, S>,
+    ) -> Result<(), ()> {
+        let inner = &mut self.inner;
+        let filter = ExcludeSmallObjects {
+            min_size: required_bytes,
+        };
+        if list
+            .with_filter(filter, |list| inner.sync_dirty_data(required_bytes, list))
+            .is_ok()
+        {
+            return Ok(());
+        }
+
+        self.inner.sync_dirty_data(required_bytes, list)
+    }
+
+    fn unload_objects<A: AllocatorModule, S: PersistentStorageModule>(
+        &mut self,
+        layout: &Layout,
+        mut list: ObjectManagementList<'_, '_, '_, '_, A, S>,
+    ) -> Result<(), ()> {
+        let inner = &mut self.inner;
+        let filter = ExcludeSmallResidentObjects {
+            min_size: layout.size(),
+        };
+        if list
+            .with_filter(filter, |list| inner.unload_objects(layout, list))
+            .is_ok()
+        {
+            return Ok(());
+        }
+
+        self.inner.unload_objects(layout, list)
+    }
+
+    fn clean_dirty_data<A: AllocatorModule, S: PersistentStorageModule>(
+        &mut self,
+        budget_bytes: usize,
+        list: ObjectManagementList<'_, '_, '_, '_, A, S>,
+    ) {
+        self.inner.clean_dirty_data(budget_bytes, list)
+    }
+
+    fn access_object(&mut self, metadata: ObjectStatusWrapper) {
+        self.inner.access_object(metadata)
+    }
+
+    fn modify_object(&mut self, metadata: ObjectStatusWrapper) {
+        self.inner.modify_object(metadata)
+    }
+
+    fn predict_next_access(&mut self) -> Option<ObjectLocation> {
+        self.inner.predict_next_access()
+    }
+
+    fn object_deallocated(&mut self, offset: usize) {
+        self.inner.object_deallocated(offset)
+    }
+}
@@ -0,0 +1,200 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::alloc::Layout;
+
+use super::{ObjectManagementList, ObjectManagementModule, ObjectStatusWrapper};
+use crate::modules::{allocator::AllocatorModule, persistent_storage::PersistentStorageModule};
+
+/// Scores every candidate by how expensive it is to get rid of it and always picks the cheapest one.
+///
+/// The cost of an object is `(1 + DIRTY_WEIGHT * dirty) * (1 + RECENCY_WEIGHT * recently_used)` per freed byte,
+/// so large clean objects that were not used recently are unloaded first.
+/// Objects that free enough space on their own are always preferred over objects that do not.
+///
+/// An object counts as recently used if it was accessed (or modified, for syncing) since the last time
+/// this module had to unload (or sync) objects.
+pub struct CostAwareObjectManagementModule<const DIRTY_WEIGHT: usize = 4, const RECENCY_WEIGHT: usize = 2>;
+
+impl<const DIRTY_WEIGHT: usize, const RECENCY_WEIGHT: usize> CostAwareObjectManagementModule<DIRTY_WEIGHT, RECENCY_WEIGHT> {
+    /// Returns the cost and the freed bytes of unloading this object or `None` if it cannot be unloaded
+    fn unload_cost(object: &ObjectStatusWrapper) -> Option<(usize, usize)> {
+        if object.is_in_use() || object.is_pinned() {
+            return None;
+        }
+
+        let cost = (1 + DIRTY_WEIGHT * object.is_data_dirty() as usize)
+            .saturating_mul(1 + RECENCY_WEIGHT * object.was_accessed() as usize);
+        Some((cost, object.get_resident_size()))
+    }
+
+    /// Returns the cost and the cleaned bytes of syncing this object or `None` if it cannot be synced
+    fn sync_cost(object: &ObjectStatusWrapper) -> Option<(usize, usize)> {
+        if !object.is_data_dirty() || object.is_in_use() {
+            return None;
+        }
+
+        Some((1 + RECENCY_WEIGHT * object.was_modified() as usize, object.get_size()))
+    }
+}
+
+impl<const DIRTY_WEIGHT: usize, const RECENCY_WEIGHT: usize> ObjectManagementModule
+    for CostAwareObjectManagementModule<DIRTY_WEIGHT, RECENCY_WEIGHT>
+{
+    fn new() -> Self {
+        Self
+    }
+
+    fn sync_dirty_data<A: AllocatorModule, S: PersistentStorageModule>(
+        &mut self,
+        required_bytes: usize,
+        mut list: ObjectManagementList<'_, '_, '_, '_, A, S>,
+    ) -> Result<(), ()> {
+        let mut curr: usize = 0;
+        let res = (|| {
+            // STEP 1: Try to sync objects
+            while let Some(ptr) = find_cheapest(&mut list, required_bytes - curr, Self::sync_cost) {
+                let mut iter = list.iter();
+                while let Some(mut item) = iter.next() {
+                    if item.get_ptr() == ptr {
+                        curr += item.sync_user_data()?;
+                        break;
+                    }
+                }
+
+                if curr >= required_bytes {
+                    return Ok(());
+                }
+            }
+
+            // STEP 2: Try to unload objects so that we reduce the amount of metadata (which is currently dirty at all time)
+            while let Some(ptr) = find_cheapest(&mut list, required_bytes - curr, Self::unload_cost) {
+                let mut iter = list.iter();
+                while let Some(mut item) = iter.next() {
+                    if item.get_ptr() == ptr {
+                        curr += item.unload()?;
+                        break;
+                    }
+                }
+
+                if curr >= required_bytes {
+                    return Ok(());
+                }
+            }
+
+            // could not sync enough objects
+            Err(())
+        })();
+
+        let mut iter = list.iter();
+        while let Some(mut item) = iter.next() {
+            item.get_metadata().set_was_modified(false);
+        }
+
+        res
+    }
+
+    fn unload_objects<A: AllocatorModule, S: PersistentStorageModule>(
+        &mut self,
+        layout: &Layout,
+        mut list: ObjectManagementList<'_, '_, '_, '_, A, S>,
+    ) -> Result<(), ()> {
+        let res = (|| {
+            while let Some(ptr) = find_cheapest(&mut list, layout.size(), Self::unload_cost) {
+                let mut iter = list.iter();
+                while let Some(mut item) = iter.next() {
+                    if item.get_ptr() == ptr {
+                        if item.unload_and_check_for_space(layout)? {
+                            // unloaded enough objects to allocate layout
+                            return Ok(());
+                        }
+                        break;
+                    }
+                }
+            }
+
+            // could not unload enough objects
+            Err(())
+        })();
+
+        let mut iter = list.iter();
+        while let Some(mut item) = iter.next() {
+            item.get_metadata().set_was_accessed(false);
+        }
+
+        res
+    }
+
+    fn access_object(&mut self, mut metadata: ObjectStatusWrapper) {
+        metadata.access_object();
+    }
+
+    fn modify_object(&mut self, mut metadata: ObjectStatusWrapper) {
+        metadata.modify_object();
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Candidate {
+    ptr: *const u8,
+    cost: usize,
+    size: usize,
+    fits: bool,
+}
+
+impl Candidate {
+    fn is_cheaper_than(&self, other: &Candidate) -> bool {
+        if self.fits != other.fits {
+            return self.fits;
+        }
+
+        if self.fits {
+            // both are enough on their own: waste as few bytes as possible on equal costs
+            self.cost < other.cost || (self.cost == other.cost && self.size < other.size)
+        } else {
+            // compare the costs per freed byte
+            self.cost.saturating_mul(other.size) < other.cost.saturating_mul(self.size)
+        }
+    }
+}
+
+/// Returns the cheapest object according to `score` or `None` if there are no candidates left
+fn find_cheapest<A: AllocatorModule, S: PersistentStorageModule>(
+    list: &mut ObjectManagementList<'_, '_, '_, '_, A, S>,
+    required_bytes: usize,
+    score: fn(&ObjectStatusWrapper) -> Option<(usize, usize)>,
+) -> Option<*const u8> {
+    let mut cheapest: Option<Candidate> = None;
+
+    let mut iter = list.iter();
+    while let Some(mut item) = iter.next() {
+        let ptr = item.get_ptr();
+        if let Some((cost, size)) = score(&item.get_metadata()) {
+            let candidate = Candidate {
+                ptr,
+                cost,
+                size,
+                fits: size >= required_bytes,
+            };
+            if cheapest.map_or(true, |cheapest| candidate.is_cheaper_than(&cheapest)) {
+                cheapest = Some(candidate);
+            }
+        }
+    }
+
+    cheapest.map(|candidate| candidate.ptr)
+}
@@ -61,7 +61,7 @@ impl ObjectManagementModule for DefaultObjectManagementModule {
                 continue;
             }
 
-            if metadata.is_in_use() {
+            if metadata.is_in_use() || metadata.is_pinned() {
                 continue;
             }
 
@@ -83,7 +83,8 @@ impl ObjectManagementModule for DefaultObjectManagementModule {
         let mut iter = list.iter();
 
         while let Some(mut item) = iter.next() {
-            if item.get_metadata().is_in_use() {
+            let metadata = item.get_metadata();
+            if metadata.is_in_use() || metadata.is_pinned() {
                 continue;
             }
 
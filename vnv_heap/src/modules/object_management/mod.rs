@@ -18,8 +18,10 @@
 use super::{allocator::AllocatorModule, persistent_storage::PersistentStorageModule};
 use crate::{
     resident_object_manager::{
+        resident_object::calc_resident_obj_layout_dynamic,
         resident_list::{DeleteHandle, IterMut, ResidentList},
         resident_object_metadata::ResidentObjectMetadata,
+        HeapStats,
     },
     shared_persist_lock::SharedPersistLock,
 };
@@ -31,6 +33,15 @@ pub use default::*;
 mod clock;
 pub use clock::*;
 
+mod combinators;
+pub use combinators::*;
+
+mod cost_aware;
+pub use cost_aware::*;
+
+mod prefetching;
+pub use prefetching::*;
+
 
 pub trait ObjectManagementModule {
     fn new() -> Self;
@@ -47,9 +58,89 @@ pub trait ObjectManagementModule {
         resident_item_list: ObjectManagementList<'_, '_, '_, '_, A, S>,
     ) -> Result<(), ()>;
 
+    /// Writes back dirty data while the application is idle (see `VNVHeap::clean`), so that persisting gets faster.
+    ///
+    /// Objects whose user data is bigger than the remaining `budget_bytes` should be skipped.
+    /// By default, dirty objects are synced in the order of `dirty_item_list`.
+    fn clean_dirty_data<A: AllocatorModule, S: PersistentStorageModule>(
+        &mut self,
+        budget_bytes: usize,
+        mut dirty_item_list: ObjectManagementList<'_, '_, '_, '_, A, S>,
+    ) {
+        let mut remaining = budget_bytes;
+
+        let mut iter = dirty_item_list.iter();
+        while let Some(mut item) = iter.next() {
+            let metadata = item.get_metadata();
+            if !metadata.is_data_dirty() || metadata.is_mutable_ref_active() || metadata.get_size() > remaining {
+                continue;
+            }
+
+            remaining = remaining.saturating_sub(item.sync_user_data().unwrap_or_default());
+            if remaining == 0 {
+                return;
+            }
+        }
+    }
+
     fn access_object(&mut self, _metadata: ObjectStatusWrapper) {}
 
     fn modify_object(&mut self, _metadata: ObjectStatusWrapper) {}
+
+    /// Returns an object that will probably be accessed next, so that it is made resident ahead of time.
+    ///
+    /// Called after an object was accessed through a reference (after `access_object`).
+    /// Only objects that were passed to this module before (see `ObjectStatusWrapper::get_location`) may be returned.
+    /// Returns `None` by default, i.e. nothing is prefetched.
+    fn predict_next_access(&mut self) -> Option<ObjectLocation> {
+        None
+    }
+
+    /// Called if the object at `offset` is deallocated or moved, so that it is not predicted anymore
+    fn object_deallocated(&mut self, _offset: usize) {}
+}
+
+/// Where an object is stored in the persistent storage and how it is made resident.
+///
+/// Returned by `ObjectStatusWrapper::get_location`, so that `ObjectManagementModule::predict_next_access` can refer
+/// to objects that are not resident anymore.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ObjectLocation {
+    pub(crate) offset: usize,
+    pub(crate) layout: Layout,
+    pub(crate) partial_dirtiness_tracking: bool,
+}
+
+impl ObjectLocation {
+    /// Returns the offset of the object in the persistent storage
+    #[inline]
+    pub fn get_offset(&self) -> usize {
+        self.offset
+    }
+}
+
+/// Hides objects from an `ObjectManagementModule`.
+///
+/// Combinators (e.g. `PreferCleanObjectManagementModule`) use this with `ObjectManagementList::with_filter`
+/// to restrict which objects the module they wrap is allowed to sync or unload.
+/// This way, cross-cutting concerns can be stacked on top of any existing module.
+pub trait ObjectFilter {
+    /// Returns `true` if this object should not be visible to the wrapped module
+    fn is_excluded(&self, object: &ObjectStatusWrapper) -> bool;
+}
+
+/// Combines a new filter with the filter of the parent list
+struct ChainedObjectFilter<'a, F: ObjectFilter> {
+    parent: Option<&'a dyn ObjectFilter>,
+    filter: F,
+}
+
+impl<F: ObjectFilter> ObjectFilter for ChainedObjectFilter<'_, F> {
+    #[inline]
+    fn is_excluded(&self, object: &ObjectStatusWrapper) -> bool {
+        self.filter.is_excluded(object)
+            || self.parent.map_or(false, |parent| parent.is_excluded(object))
+    }
 }
 
 
@@ -98,10 +189,43 @@ impl ObjectStatusWrapper<'_> {
         self.metadata.inner.status.is_in_use()
     }
 
+    /// Returns `true` if this object must not be unloaded (see `VNVObject::pin`)
+    #[inline]
+    pub fn is_pinned(&self) -> bool {
+        self.metadata.inner.status.is_pinned()
+    }
+
     #[inline]
     pub fn is_mutable_ref_active(&self) -> bool {
         self.metadata.inner.status.is_mutable_ref_active()
     }
+
+    /// Returns the size of the user data of this object
+    #[inline]
+    pub fn get_size(&self) -> usize {
+        self.metadata.inner.layout.size()
+    }
+
+    /// Returns the location of this object, which stays valid after it is unloaded
+    #[inline]
+    pub fn get_location(&self) -> ObjectLocation {
+        ObjectLocation {
+            offset: self.metadata.inner.offset,
+            layout: self.metadata.inner.layout,
+            partial_dirtiness_tracking: self.metadata.inner.status.is_partial_dirtiness_tracking_enabled(),
+        }
+    }
+
+    /// Returns how many bytes of the resident buffer this object uses (including its metadata)
+    #[inline]
+    pub fn get_resident_size(&self) -> usize {
+        calc_resident_obj_layout_dynamic(
+            &self.metadata.inner.layout,
+            self.metadata.inner.status.is_partial_dirtiness_tracking_enabled(),
+        )
+        .0
+        .size()
+    }
 }
 
 
@@ -109,6 +233,7 @@ pub(crate) struct ObjectManagementListArguments<'a, 'b, A: AllocatorModule, S: P
     pub(crate) storage: &'a mut S,
     pub(crate) remaining_dirty_size: &'a mut usize,
     pub(crate) allocator: &'a SharedPersistLock<'b, *mut A>,
+    pub(crate) stats: &'a mut HeapStats,
 }
 
 pub struct ObjectManagementIterItem<'a, 'b, 'c, 'd, 'e, 'f, A: AllocatorModule, S: PersistentStorageModule> {
@@ -131,7 +256,7 @@ impl<A: AllocatorModule, S: PersistentStorageModule> ObjectManagementIterItem<'_
     /// Unloads this object and checks if `layout` can be allocated now
     #[inline]
     pub fn unload_and_check_for_space(mut self, layout: &Layout) -> Result<bool, ()> {
-        if self.get_metadata().is_in_use() {
+        if self.get_metadata().is_in_use() || self.get_metadata().is_pinned() {
             return Err(());
         }
         unsafe {
@@ -140,6 +265,7 @@ impl<A: AllocatorModule, S: PersistentStorageModule> ObjectManagementIterItem<'_
                 self.arguments.storage,
                 self.arguments.allocator,
                 self.arguments.remaining_dirty_size,
+                self.arguments.stats,
             )
         }?;
 
@@ -164,7 +290,7 @@ impl<A: AllocatorModule, S: PersistentStorageModule> ObjectManagementIterItem<'_
 
     /// Unloads this object and returns the amount of additional dirty bytes that are free now
     pub fn unload(mut self) -> Result<usize, ()> {
-        if self.get_metadata().is_in_use() {
+        if self.get_metadata().is_in_use() || self.get_metadata().is_pinned() {
             return Err(());
         }
         let prev = *self.arguments.remaining_dirty_size;
@@ -174,6 +300,7 @@ impl<A: AllocatorModule, S: PersistentStorageModule> ObjectManagementIterItem<'_
                 self.arguments.storage,
                 self.arguments.allocator,
                 self.arguments.remaining_dirty_size,
+                self.arguments.stats,
             )
         }?;
 
@@ -189,7 +316,7 @@ impl<A: AllocatorModule, S: PersistentStorageModule> ObjectManagementIterItem<'_
         let dirty_size = unsafe {
             self.delete_handle
                 .get_element()
-                .persist_user_data_dynamic(self.arguments.storage)
+                .persist_user_data_dynamic(self.arguments.storage, self.arguments.stats)
         }?;
         *self.arguments.remaining_dirty_size += dirty_size;
         Ok(dirty_size)
@@ -200,11 +327,26 @@ impl<A: AllocatorModule, S: PersistentStorageModule> ObjectManagementIterItem<'_
 pub struct ObjectManagementIter<'a, 'b, 'c, 'd, A: AllocatorModule, S: PersistentStorageModule> {
     arguments: &'c mut ObjectManagementListArguments<'a, 'b, A, S>,
     iter: IterMut<'d>,
+    filter: Option<&'d dyn ObjectFilter>,
 }
 
 impl<'a, 'b, 'c, A: AllocatorModule, S: PersistentStorageModule> ObjectManagementIter<'a, 'b, '_, 'c, A, S> {
     pub fn next<'d>(&'d mut self) -> Option<ObjectManagementIterItem<'a, 'b, '_, '_, 'c, 'd, A, S>> {
-        while let Some(item) = self.iter.next() {
+        loop {
+            // the borrow checker does not accept returning items from this loop if some of them are skipped
+            // this is safe as skipped items are not used anymore
+            let iter = unsafe { (&mut self.iter as *mut IterMut<'c>).as_mut().unwrap() };
+            let mut item = match iter.next() {
+                Some(item) => item,
+                None => break,
+            };
+
+            if let Some(filter) = self.filter {
+                if filter.is_excluded(&ObjectStatusWrapper { metadata: item.get_element() }) {
+                    continue;
+                }
+            }
+
             return Some(ObjectManagementIterItem {
                 arguments: self.arguments,
                 delete_handle: item,
@@ -219,6 +361,7 @@ impl<'a, 'b, 'c, A: AllocatorModule, S: PersistentStorageModule> ObjectManagemen
 pub struct ObjectManagementList<'a, 'b, 'c, 'd, A: AllocatorModule, S: PersistentStorageModule> {
     pub(crate) arguments: &'c mut ObjectManagementListArguments<'a, 'b, A, S>,
     pub(crate) resident_list: &'d mut ResidentList,
+    pub(crate) filter: Option<&'d dyn ObjectFilter>,
 }
 
 impl<'a, 'b, A: AllocatorModule, S: PersistentStorageModule> ObjectManagementList<'a, 'b, '_, '_, A, S> {
@@ -226,6 +369,25 @@ impl<'a, 'b, A: AllocatorModule, S: PersistentStorageModule> ObjectManagementLis
         ObjectManagementIter {
             arguments: self.arguments,
             iter: self.resident_list.iter_mut(),
+            filter: self.filter,
         }
     }
+
+    /// Calls `f` with a list that additionally hides all objects that are excluded by `filter`.
+    pub fn with_filter<F: ObjectFilter, R>(
+        &mut self,
+        filter: F,
+        f: impl FnOnce(ObjectManagementList<'a, 'b, '_, '_, A, S>) -> R,
+    ) -> R {
+        let filter = ChainedObjectFilter {
+            parent: self.filter,
+            filter,
+        };
+
+        f(ObjectManagementList {
+            arguments: self.arguments,
+            resident_list: self.resident_list,
+            filter: Some(&filter),
+        })
+    }
 }
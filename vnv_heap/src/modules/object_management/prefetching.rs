@@ -0,0 +1,151 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use core::alloc::Layout;
+
+use super::{ObjectLocation, ObjectManagementList, ObjectManagementModule, ObjectStatusWrapper};
+use crate::modules::{allocator::AllocatorModule, persistent_storage::PersistentStorageModule};
+
+/// An observed access of `to` directly after `from`
+#[derive(Clone, Copy)]
+struct Transition {
+    from: usize,
+    to: ObjectLocation,
+}
+
+/// Predicts the next accessed object with a first-order markov predictor and prefetches it.
+///
+/// For every object, the object that was accessed directly after it the last time is remembered.
+/// If the object is accessed again, its successor is made resident ahead of time.
+/// This works well for repeating access patterns (e.g. iterating over the same objects again and again).
+///
+/// At most `ENTRIES` transitions are remembered, older ones are replaced in a round robin fashion.
+/// Syncing and unloading is done by the wrapped module `M`.
+pub struct PrefetchingObjectManagementModule<M: ObjectManagementModule, const ENTRIES: usize = 16> {
+    inner: M,
+    transitions: [Option<Transition>; ENTRIES],
+    next_replaced: usize,
+    last_accessed: Option<ObjectLocation>,
+    prediction: Option<ObjectLocation>,
+}
+
+impl<M: ObjectManagementModule, const ENTRIES: usize> PrefetchingObjectManagementModule<M, ENTRIES> {
+    fn find_transition(&self, from: usize) -> Option<usize> {
+        self.transitions
+            .iter()
+            .position(|transition| transition.map_or(false, |transition| transition.from == from))
+    }
+
+    fn record_transition(&mut self, from: usize, to: ObjectLocation) {
+        if ENTRIES == 0 {
+            return;
+        }
+
+        let index = match self.find_transition(from) {
+            Some(index) => index,
+            None => match self.transitions.iter().position(|transition| transition.is_none()) {
+                Some(index) => index,
+                None => {
+                    let index = self.next_replaced;
+                    self.next_replaced = (self.next_replaced + 1) % ENTRIES;
+                    index
+                }
+            },
+        };
+
+        self.transitions[index] = Some(Transition { from, to });
+    }
+}
+
+impl<M: ObjectManagementModule, const ENTRIES: usize> ObjectManagementModule
+    for PrefetchingObjectManagementModule<M, ENTRIES>
+{
+    fn new() -> Self {
+        Self {
+            inner: M::new(),
+            transitions: [None; ENTRIES],
+            next_replaced: 0,
+            last_accessed: None,
+            prediction: None,
+        }
+    }
+
+    fn sync_dirty_data<A: AllocatorModule, S: PersistentStorageModule>(
+        &mut self,
+        required_bytes: usize,
+        list: ObjectManagementList<'_, '_, '_, '_, A, S>,
+    ) -> Result<(), ()> {
+        self.inner.sync_dirty_data(required_bytes, list)
+    }
+
+    fn unload_objects<A: AllocatorModule, S: PersistentStorageModule>(
+        &mut self,
+        layout: &Layout,
+        list: ObjectManagementList<'_, '_, '_, '_, A, S>,
+    ) -> Result<(), ()> {
+        self.inner.unload_objects(layout, list)
+    }
+
+    fn clean_dirty_data<A: AllocatorModule, S: PersistentStorageModule>(
+        &mut self,
+        budget_bytes: usize,
+        list: ObjectManagementList<'_, '_, '_, '_, A, S>,
+    ) {
+        self.inner.clean_dirty_data(budget_bytes, list)
+    }
+
+    fn access_object(&mut self, metadata: ObjectStatusWrapper) {
+        let location = metadata.get_location();
+        if let Some(last) = self.last_accessed {
+            if last.offset != location.offset {
+                self.record_transition(last.offset, location);
+            }
+        }
+
+        self.last_accessed = Some(location);
+        self.prediction = self
+            .find_transition(location.offset)
+            .and_then(|index| self.transitions[index])
+            .map(|transition| transition.to);
+
+        self.inner.access_object(metadata)
+    }
+
+    fn modify_object(&mut self, metadata: ObjectStatusWrapper) {
+        self.inner.modify_object(metadata)
+    }
+
+    fn predict_next_access(&mut self) -> Option<ObjectLocation> {
+        self.prediction.take().or_else(|| self.inner.predict_next_access())
+    }
+
+    fn object_deallocated(&mut self, offset: usize) {
+        for transition in self.transitions.iter_mut() {
+            if transition.map_or(false, |t| t.from == offset || t.to.offset == offset) {
+                *transition = None;
+            }
+        }
+
+        if self.last_accessed.map_or(false, |last| last.offset == offset) {
+            self.last_accessed = None;
+        }
+        if self.prediction.map_or(false, |prediction| prediction.offset == offset) {
+            self.prediction = None;
+        }
+
+        self.inner.object_deallocated(offset)
+    }
+}
@@ -18,11 +18,21 @@
 // Internal stuff used by VNVHeap's implementation
 
 use super::PersistentStorageModule;
-use crate::shared_persist_lock::SharedPersistLock;
+use crate::shared_persist_lock::{SharedPersistGuard, SharedPersistLock};
+#[cfg(feature = "metrics")]
+use crate::resident_object_manager::metrics::StorageCounters;
 use core::marker::PhantomData;
 
 pub(crate) struct SharedStorageReference<'a, 'b> {
     lock: SharedPersistLock<'a, *mut dyn PersistentStorageModule>,
+
+    /// Size of the underlying storage, so it is also available while the storage is locked (e.g. by `load_async`)
+    max_size: usize,
+
+    /// Counters of the heap this storage belongs to (if any)
+    #[cfg(feature = "metrics")]
+    storage_counters: Option<&'a StorageCounters>,
+
     _phantom_data: PhantomData<&'b ()>,
 }
 
@@ -31,43 +41,84 @@ impl PersistentStorageModule for SharedStorageReference<'_, '_> {
         let guard = self.lock.try_lock().ok_or(())?;
 
         let s_ref = unsafe { guard.as_mut().unwrap() };
-        s_ref.read(offset, dest)
+        s_ref.read(offset, dest)?;
+
+        // updated while the storage is locked (see `StorageCounters`)
+        #[cfg(feature = "metrics")]
+        if let Some(counters) = self.storage_counters {
+            counters.storage_read();
+        }
+
+        Ok(())
     }
 
     fn get_max_size(&self) -> usize {
-        // could be optimized more if you change the interface
-        // however this is not necessary for now as this is only called once during initializing VNVHeap
-        let guard = self.lock.try_lock().unwrap();
+        self.max_size
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        let guard = self.lock.try_lock().ok_or(())?;
 
         let s_ref = unsafe { guard.as_mut().unwrap() };
-        s_ref.get_max_size()
+        s_ref.write(offset, src)?;
+
+        // updated while the storage is locked (see `StorageCounters`)
+        #[cfg(feature = "metrics")]
+        if let Some(counters) = self.storage_counters {
+            counters.storage_written();
+        }
+
+        Ok(())
     }
 
-    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+    fn flush(&mut self) -> Result<(), ()> {
         let guard = self.lock.try_lock().ok_or(())?;
 
         let s_ref = unsafe { guard.as_mut().unwrap() };
-        s_ref.write(offset, src)
+        s_ref.flush()
     }
 }
 
 impl<'a, 'b> SharedStorageReference<'a, 'b> {
-    pub(crate) fn new(lock: SharedPersistLock<'a, *mut dyn PersistentStorageModule>) -> Self {
+    /// Has to be called before the storage is accessed by anyone else
+    pub(crate) fn new(mut lock: SharedPersistLock<'a, *mut dyn PersistentStorageModule>) -> Self {
+        let max_size = unsafe { lock.get_mut().as_ref() }.map_or(0, |storage| storage.get_max_size());
+
         Self {
             lock,
+            max_size,
+            #[cfg(feature = "metrics")]
+            storage_counters: None,
             _phantom_data: PhantomData,
         }
     }
 
+    /// Counts the accesses of this reference (and its clones) in `storage_counters`
+    #[cfg(feature = "metrics")]
+    pub(crate) fn with_storage_counters(mut self, storage_counters: &'a StorageCounters) -> Self {
+        self.storage_counters = Some(storage_counters);
+        self
+    }
+
     pub(crate) fn try_lock_clone(&self) -> Option<Self> {
         self.lock.try_lock_clone().map(|val| {
             Self {
                 lock: val,
+                max_size: self.max_size,
+                #[cfg(feature = "metrics")]
+                storage_counters: self.storage_counters,
                 _phantom_data: PhantomData,
             }
         })
     }
 
+    /// Locks the underlying storage until the returned guard is dropped.
+    ///
+    /// Persisting the heap is queued while the guard is held.
+    pub(crate) fn try_lock(&self) -> Option<SharedPersistGuard<'a, '_, *mut dyn PersistentStorageModule>> {
+        self.lock.try_lock()
+    }
+
     pub(crate) fn is_locked(&self) -> bool {
         self.lock.try_lock().is_none()
     }
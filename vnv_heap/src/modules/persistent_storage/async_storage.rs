@@ -0,0 +1,39 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::PersistentStorageModule;
+
+/// A storage module that can yield to an async executor while data is transferred (e.g. while waiting for a DMA based SPI transfer).
+///
+/// The default implementations just call the blocking `read` and `write` functions of `PersistentStorageModule`.
+/// These are still used for all accesses that happen during persisting the heap or while making space in the resident buffer.
+///
+/// The heap itself is usable by other tasks while an async read is pending, but the storage is locked until the read finished.
+/// In the meantime, persisting the heap is queued and other operations that access the storage (e.g. allocating,
+/// unloading or a second async load) return `VNVError::StorageError`.
+#[allow(async_fn_in_trait)]
+pub trait AsyncPersistentStorageModule: PersistentStorageModule {
+    /// Same as `PersistentStorageModule::read`, but may yield while waiting for the data.
+    async fn read_async(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        self.read(offset, dest)
+    }
+
+    /// Same as `PersistentStorageModule::write`, but may yield while waiting for the data to be written.
+    async fn write_async(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        self.write(offset, src)
+    }
+}
@@ -0,0 +1,256 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use super::{CachedStorageModule, PersistentStorageModule};
+
+/// Size of a block of an SD card (and most other block devices) in bytes
+pub const BLOCK_DEVICE_BLOCK_SIZE: usize = 512;
+
+/// Device that can only be accessed in whole blocks of `BLOCK_DEVICE_BLOCK_SIZE` bytes (e.g. an SD card).
+///
+/// Implement this for the driver of your platform to use the device with `BlockDeviceStorageModule`.
+pub trait BlockDevice {
+    /// Reads the block with the index `block` to `dest`
+    fn read_block(&mut self, block: usize, dest: &mut [u8; BLOCK_DEVICE_BLOCK_SIZE]) -> Result<(), ()>;
+
+    /// Writes `src` to the block with the index `block`
+    fn write_block(&mut self, block: usize, src: &[u8; BLOCK_DEVICE_BLOCK_SIZE]) -> Result<(), ()>;
+
+    /// Returns how many blocks this device has
+    fn block_count(&self) -> usize;
+
+    /// Waits until all written blocks are stored on the device (e.g. if the driver buffers writes)
+    fn flush(&mut self) -> Result<(), ()> {
+        Ok(())
+    }
+}
+
+/// Adapter for `BlockDevice`s, which can only be read and written in whole blocks.
+///
+/// Accesses that do not cover whole blocks are executed as read-modify-write of the affected blocks,
+/// so every small access transfers at least one block. Use `SdCardStorageModule` to cache recently used blocks.
+///
+/// Only the region `[first_block, first_block + block_count)` of the device is used, so the heap can be
+/// placed in a partition or in a contiguous file that was preallocated on a FAT file system.
+pub struct BlockDeviceStorageModule<D: BlockDevice> {
+    device: D,
+    first_block: usize,
+    block_count: usize,
+    buffer: [u8; BLOCK_DEVICE_BLOCK_SIZE],
+}
+
+/// Storage module for SD cards (or other `BlockDevice`s) that keeps up to `CACHE_BLOCKS` recently used blocks in RAM,
+/// so the small random accesses of the heap do not transfer a whole block each (see `CachedStorageModule`).
+pub type SdCardStorageModule<D, const CACHE_BLOCKS: usize> =
+    CachedStorageModule<CACHE_BLOCKS, BLOCK_DEVICE_BLOCK_SIZE, BlockDeviceStorageModule<D>>;
+
+impl<D: BlockDevice> BlockDeviceStorageModule<D> {
+    /// Uses the whole device as storage
+    pub fn new(device: D) -> Self {
+        let block_count = device.block_count();
+        Self::with_region(device, 0, block_count)
+    }
+
+    /// Uses `block_count` blocks beginning at `first_block` as storage
+    pub fn with_region(device: D, first_block: usize, block_count: usize) -> Self {
+        assert!(
+            first_block + block_count <= device.block_count(),
+            "region exceeds the block device"
+        );
+
+        Self {
+            device,
+            first_block,
+            block_count,
+            buffer: [0u8; BLOCK_DEVICE_BLOCK_SIZE],
+        }
+    }
+
+    pub fn get_device(&self) -> &D {
+        &self.device
+    }
+
+    /// Returns the underlying device.
+    ///
+    /// Be aware that the region of this module starts at `first_block` of the device.
+    pub fn get_device_mut(&mut self) -> &mut D {
+        &mut self.device
+    }
+
+    pub fn into_device(self) -> D {
+        self.device
+    }
+
+    /// Calls `func` for every block that overlaps with `[offset, offset + len)`
+    /// with the device block, the offset inside of the block and the range inside of the accessed region
+    fn for_each_block<F: FnMut(&mut Self, usize, usize, core::ops::Range<usize>) -> Result<(), ()>>(
+        &mut self,
+        offset: usize,
+        len: usize,
+        mut func: F,
+    ) -> Result<(), ()> {
+        debug_assert!(offset + len <= self.get_max_size());
+
+        let mut pos = 0;
+        while pos < len {
+            let block = (offset + pos) / BLOCK_DEVICE_BLOCK_SIZE;
+            let block_offset = (offset + pos) % BLOCK_DEVICE_BLOCK_SIZE;
+            let chunk_len = (BLOCK_DEVICE_BLOCK_SIZE - block_offset).min(len - pos);
+
+            func(self, self.first_block + block, block_offset, pos..pos + chunk_len)?;
+            pos += chunk_len;
+        }
+        Ok(())
+    }
+}
+
+impl<D: BlockDevice> PersistentStorageModule for BlockDeviceStorageModule<D> {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        self.for_each_block(offset, dest.len(), |this, block, block_offset, range| {
+            let len = range.len();
+            if let Ok(dest_block) = <&mut [u8; BLOCK_DEVICE_BLOCK_SIZE]>::try_from(&mut dest[range.clone()]) {
+                // whole block: no copy needed
+                return this.device.read_block(block, dest_block);
+            }
+
+            this.device.read_block(block, &mut this.buffer)?;
+            dest[range].copy_from_slice(&this.buffer[block_offset..block_offset + len]);
+            Ok(())
+        })
+    }
+
+    fn get_max_size(&self) -> usize {
+        self.block_count * BLOCK_DEVICE_BLOCK_SIZE
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        self.for_each_block(offset, src.len(), |this, block, block_offset, range| {
+            let len = range.len();
+            if let Ok(src_block) = <&[u8; BLOCK_DEVICE_BLOCK_SIZE]>::try_from(&src[range.clone()]) {
+                // blocks that are overwritten completely do not have to be read first
+                return this.device.write_block(block, src_block);
+            }
+
+            this.device.read_block(block, &mut this.buffer)?;
+            this.buffer[block_offset..block_offset + len].copy_from_slice(&src[range]);
+            this.device.write_block(block, &this.buffer)
+        })
+    }
+
+    fn flush(&mut self) -> Result<(), ()> {
+        self.device.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::modules::persistent_storage::{
+        test::{
+            test_persistent_storage_custom_type, test_persistent_storage_normal,
+            PERSISTENT_STORAGE_NORMAL_TEST_SIZE,
+        },
+        PersistentStorageModule,
+    };
+
+    use super::{BlockDevice, BlockDeviceStorageModule, SdCardStorageModule, BLOCK_DEVICE_BLOCK_SIZE};
+
+    /// Block device in RAM that counts the transferred blocks
+    struct MockBlockDevice {
+        blocks: Vec<[u8; BLOCK_DEVICE_BLOCK_SIZE]>,
+        read_count: usize,
+        write_count: usize,
+    }
+
+    impl MockBlockDevice {
+        fn new(block_count: usize) -> Self {
+            Self {
+                blocks: vec![[0u8; BLOCK_DEVICE_BLOCK_SIZE]; block_count],
+                read_count: 0,
+                write_count: 0,
+            }
+        }
+    }
+
+    impl BlockDevice for MockBlockDevice {
+        fn read_block(&mut self, block: usize, dest: &mut [u8; BLOCK_DEVICE_BLOCK_SIZE]) -> Result<(), ()> {
+            self.read_count += 1;
+            *dest = self.blocks[block];
+            Ok(())
+        }
+
+        fn write_block(&mut self, block: usize, src: &[u8; BLOCK_DEVICE_BLOCK_SIZE]) -> Result<(), ()> {
+            self.write_count += 1;
+            self.blocks[block] = *src;
+            Ok(())
+        }
+
+        fn block_count(&self) -> usize {
+            self.blocks.len()
+        }
+    }
+
+    #[test]
+    fn test_block_device_storage_module() {
+        let block_count = PERSISTENT_STORAGE_NORMAL_TEST_SIZE / BLOCK_DEVICE_BLOCK_SIZE;
+        test_persistent_storage_normal(BlockDeviceStorageModule::new(MockBlockDevice::new(block_count)));
+        test_persistent_storage_custom_type(BlockDeviceStorageModule::new(MockBlockDevice::new(1)));
+
+        let mut storage = BlockDeviceStorageModule::new(MockBlockDevice::new(4));
+
+        // only the partially written blocks have to be read first
+        storage.write(500, &[1u8; 1040]).unwrap();
+        assert_eq!(storage.get_device().read_count, 2);
+        assert_eq!(storage.get_device().write_count, 4);
+
+        let mut buffer = [0u8; 1044];
+        storage.read(498, &mut buffer).unwrap();
+        assert_eq!(&buffer[..2], &[0, 0]);
+        assert!(buffer[2..1042].iter().all(|x| *x == 1));
+        assert_eq!(&buffer[1042..], &[0, 0]);
+    }
+
+    #[test]
+    fn test_block_device_storage_module_region() {
+        let mut storage = BlockDeviceStorageModule::with_region(MockBlockDevice::new(8), 2, 4);
+        assert_eq!(storage.get_max_size(), 4 * BLOCK_DEVICE_BLOCK_SIZE);
+
+        storage.write(0, &[3u8; 10]).unwrap();
+        let device = storage.into_device();
+        assert_eq!(&device.blocks[2][..10], &[3u8; 10]);
+        assert!(device.blocks[1].iter().all(|x| *x == 0));
+    }
+
+    #[test]
+    fn test_sd_card_storage_module() {
+        let mut storage: SdCardStorageModule<_, 2> =
+            SdCardStorageModule::new(BlockDeviceStorageModule::new(MockBlockDevice::new(16)));
+
+        // small accesses to the same block only transfer it once
+        for i in 0..64 {
+            storage.write(i * 8, &[i as u8; 8]).unwrap();
+        }
+        let mut buffer = [0u8; 8];
+        for i in 0..64 {
+            storage.read(i * 8, &mut buffer).unwrap();
+            assert_eq!(buffer, [i as u8; 8]);
+        }
+        assert_eq!(storage.get_inner().get_device().read_count, 1);
+        assert_eq!(storage.get_inner().get_device().write_count, 0);
+
+        storage.flush().unwrap();
+        assert_eq!(storage.get_inner().get_device().write_count, 1);
+    }
+}
@@ -0,0 +1,466 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::PersistentStorageModule;
+
+#[derive(Clone, Copy, Default)]
+struct CacheEntry {
+    /// Index of the cached block in the underlying storage
+    block: usize,
+    valid: bool,
+    dirty: bool,
+    /// Value of the access counter when this entry was used the last time
+    last_used: u32,
+}
+
+/// Write-back cache that keeps up to `BLOCK_COUNT` recently accessed blocks of `BLOCK_SIZE` bytes in RAM.
+///
+/// Reads and writes of cached blocks do not access the underlying storage. Modified blocks are written back
+/// if they are evicted (least recently used first), if more than `max_dirty_blocks` blocks are dirty,
+/// on `flush` or if this module is dropped. Blocks that are completely covered by a `forget_region` hint
+/// are written back and evicted, so they do not take space away from blocks that are still used.
+///
+/// This avoids storage accesses if the same objects are evicted and loaded again shortly after.
+///
+/// **Note:** Dirty blocks are also written back while persisting the heap. As only whole blocks are written,
+/// a single dirty byte of an object can cost `BLOCK_SIZE` bytes, up to `max_dirty_blocks * BLOCK_SIZE` bytes in total.
+pub struct CachedStorageModule<const BLOCK_COUNT: usize, const BLOCK_SIZE: usize, S: PersistentStorageModule> {
+    inner: S,
+
+    entries: [CacheEntry; BLOCK_COUNT],
+    data: [[u8; BLOCK_SIZE]; BLOCK_COUNT],
+    access_counter: u32,
+
+    dirty_blocks: usize,
+    max_dirty_blocks: usize,
+
+    hit_count: usize,
+    miss_count: usize,
+    write_back_count: usize,
+}
+
+impl<const BLOCK_COUNT: usize, const BLOCK_SIZE: usize, S: PersistentStorageModule> CachedStorageModule<BLOCK_COUNT, BLOCK_SIZE, S> {
+    pub fn new(storage: S) -> Self {
+        assert!(BLOCK_COUNT > 0, "cache has to hold at least one block");
+        assert!(BLOCK_SIZE > 0, "block size has to be greater than zero");
+
+        Self {
+            inner: storage,
+            entries: [CacheEntry::default(); BLOCK_COUNT],
+            data: [[0u8; BLOCK_SIZE]; BLOCK_COUNT],
+            access_counter: 0,
+            dirty_blocks: 0,
+            max_dirty_blocks: BLOCK_COUNT,
+            hit_count: 0,
+            miss_count: 0,
+            write_back_count: 0,
+        }
+    }
+
+    pub fn get_max_dirty_blocks(&self) -> usize {
+        self.max_dirty_blocks
+    }
+
+    /// Limits how many blocks can be dirty at the same time (`BLOCK_COUNT` by default).
+    ///
+    /// Lower values reduce the amount of data that has to be written back while persisting.
+    /// If more blocks are dirty already, they are written back.
+    pub fn set_max_dirty_blocks(&mut self, max_dirty_blocks: usize) -> Result<(), ()> {
+        self.max_dirty_blocks = max_dirty_blocks.min(BLOCK_COUNT);
+        while self.dirty_blocks > self.max_dirty_blocks {
+            self.write_back_oldest_dirty()?;
+        }
+        Ok(())
+    }
+
+    /// Returns how many cached blocks are currently modified
+    pub fn get_dirty_blocks(&self) -> usize {
+        self.dirty_blocks
+    }
+
+    /// Returns how many block accesses were served from the cache
+    pub fn get_hit_count(&self) -> usize {
+        self.hit_count
+    }
+
+    /// Returns how many block accesses had to read the underlying storage
+    pub fn get_miss_count(&self) -> usize {
+        self.miss_count
+    }
+
+    /// Returns how many blocks were written to the underlying storage
+    pub fn get_write_back_count(&self) -> usize {
+        self.write_back_count
+    }
+
+    pub fn reset_counters(&mut self) {
+        self.hit_count = 0;
+        self.miss_count = 0;
+        self.write_back_count = 0;
+    }
+
+    pub fn get_inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the underlying storage.
+    ///
+    /// Be aware that accessing it directly bypasses the cache.
+    pub fn get_inner_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Writes back all dirty blocks and returns the underlying storage
+    pub fn into_inner(mut self) -> Result<S, ()> {
+        self.flush()?;
+
+        // all blocks are clean now, so skipping drop does not lose any data
+        let this = core::mem::ManuallyDrop::new(self);
+        Ok(unsafe { core::ptr::read(&this.inner) })
+    }
+
+    /// Returns the size of `block`, which is only smaller than `BLOCK_SIZE` for the last block of the storage
+    fn block_len(&self, block: usize) -> usize {
+        (self.inner.get_max_size() - block * BLOCK_SIZE).min(BLOCK_SIZE)
+    }
+
+    fn find_entry(&self, block: usize) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.valid && entry.block == block)
+    }
+
+    fn touch(&mut self, index: usize) {
+        self.access_counter = self.access_counter.wrapping_add(1);
+        self.entries[index].last_used = self.access_counter;
+    }
+
+    /// Age of an entry, robust against overflows of the access counter
+    fn age(&self, index: usize) -> u32 {
+        self.access_counter.wrapping_sub(self.entries[index].last_used)
+    }
+
+    fn write_back(&mut self, index: usize) -> Result<(), ()> {
+        let entry = self.entries[index];
+        if !entry.valid || !entry.dirty {
+            return Ok(());
+        }
+
+        let len = self.block_len(entry.block);
+        self.inner.write(entry.block * BLOCK_SIZE, &self.data[index][..len])?;
+        self.entries[index].dirty = false;
+        self.dirty_blocks -= 1;
+        self.write_back_count += 1;
+        Ok(())
+    }
+
+    fn write_back_oldest_dirty(&mut self) -> Result<(), ()> {
+        let oldest = (0..BLOCK_COUNT)
+            .filter(|index| self.entries[*index].valid && self.entries[*index].dirty)
+            .max_by_key(|index| self.age(*index));
+
+        match oldest {
+            Some(index) => self.write_back(index),
+            None => Ok(()),
+        }
+    }
+
+    /// Frees an entry (evicting the least recently used block if needed) and assigns it to `block`.
+    ///
+    /// If `load` is set, the block is read from the underlying storage.
+    fn insert(&mut self, block: usize, load: bool) -> Result<usize, ()> {
+        let index = match self.entries.iter().position(|entry| !entry.valid) {
+            Some(index) => index,
+            None => {
+                let index = (0..BLOCK_COUNT).max_by_key(|index| self.age(*index)).unwrap();
+                self.write_back(index)?;
+                self.entries[index].valid = false;
+                index
+            }
+        };
+
+        if load {
+            let len = self.block_len(block);
+            self.inner.read(block * BLOCK_SIZE, &mut self.data[index][..len])?;
+        }
+
+        self.entries[index] = CacheEntry {
+            block,
+            valid: true,
+            dirty: false,
+            last_used: 0,
+        };
+        self.touch(index);
+        Ok(index)
+    }
+
+    /// Calls `func` for every block that overlaps with `[offset, offset + len)`
+    /// with the block, the offset inside of the block and the range inside of the accessed region
+    fn for_each_block<F: FnMut(&mut Self, usize, usize, core::ops::Range<usize>) -> Result<(), ()>>(
+        &mut self,
+        offset: usize,
+        len: usize,
+        mut func: F,
+    ) -> Result<(), ()> {
+        let mut pos = 0;
+        while pos < len {
+            let block = (offset + pos) / BLOCK_SIZE;
+            let block_offset = (offset + pos) % BLOCK_SIZE;
+            let chunk_len = (BLOCK_SIZE - block_offset).min(len - pos);
+
+            func(self, block, block_offset, pos..pos + chunk_len)?;
+            pos += chunk_len;
+        }
+        Ok(())
+    }
+}
+
+impl<const BLOCK_COUNT: usize, const BLOCK_SIZE: usize, S: PersistentStorageModule> PersistentStorageModule for CachedStorageModule<BLOCK_COUNT, BLOCK_SIZE, S> {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        self.for_each_block(offset, dest.len(), |this, block, block_offset, range| {
+            let index = match this.find_entry(block) {
+                Some(index) => {
+                    this.hit_count += 1;
+                    this.touch(index);
+                    index
+                }
+                None => {
+                    this.miss_count += 1;
+                    this.insert(block, true)?
+                }
+            };
+
+            let len = range.len();
+            dest[range].copy_from_slice(&this.data[index][block_offset..block_offset + len]);
+            Ok(())
+        })
+    }
+
+    fn get_max_size(&self) -> usize {
+        self.inner.get_max_size()
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        self.for_each_block(offset, src.len(), |this, block, block_offset, range| {
+            let index = match this.find_entry(block) {
+                Some(index) => {
+                    this.hit_count += 1;
+                    this.touch(index);
+                    index
+                }
+                None => {
+                    // blocks that are overwritten completely do not have to be read first
+                    let overwritten = block_offset == 0 && range.len() == this.block_len(block);
+                    if !overwritten {
+                        this.miss_count += 1;
+                    }
+                    this.insert(block, !overwritten)?
+                }
+            };
+
+            let len = range.len();
+            this.data[index][block_offset..block_offset + len].copy_from_slice(&src[range]);
+            if !this.entries[index].dirty {
+                this.entries[index].dirty = true;
+                this.dirty_blocks += 1;
+            }
+
+            if this.dirty_blocks > this.max_dirty_blocks {
+                this.write_back_oldest_dirty()?;
+            }
+            Ok(())
+        })
+    }
+
+    fn forget_region(&mut self, offset: usize, size: usize) {
+        for index in 0..BLOCK_COUNT {
+            let entry = self.entries[index];
+            let start = entry.block * BLOCK_SIZE;
+            if !entry.valid || start < offset || start + self.block_len(entry.block) > offset + size {
+                continue;
+            }
+
+            // if the write back fails, the block stays cached, so no data is lost
+            if self.write_back(index).is_ok() {
+                self.entries[index].valid = false;
+            }
+        }
+
+        self.inner.forget_region(offset, size)
+    }
+
+    fn flush(&mut self) -> Result<(), ()> {
+        for index in 0..BLOCK_COUNT {
+            self.write_back(index)?;
+        }
+        self.inner.flush()
+    }
+}
+
+impl<const BLOCK_COUNT: usize, const BLOCK_SIZE: usize, S: PersistentStorageModule> Drop for CachedStorageModule<BLOCK_COUNT, BLOCK_SIZE, S> {
+    fn drop(&mut self) {
+        if self.flush().is_err() {
+            println!("could not write back cached blocks");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{rngs::SmallRng, RngCore, SeedableRng};
+
+    use crate::modules::persistent_storage::{
+        test::{
+            get_test_storage, test_persistent_storage_custom_type, test_persistent_storage_normal,
+            PERSISTENT_STORAGE_CUSTOM_TYPE_TEST_SIZE, PERSISTENT_STORAGE_NORMAL_TEST_SIZE,
+        },
+        PersistentStorageModule,
+    };
+
+    use super::CachedStorageModule;
+
+    #[test]
+    fn test_cached_storage_module_normal() {
+        let storage = get_test_storage("test_cached_storage_module_normal", PERSISTENT_STORAGE_NORMAL_TEST_SIZE);
+        test_persistent_storage_normal(CachedStorageModule::<4, 64, _>::new(storage));
+    }
+
+    #[test]
+    fn test_cached_storage_module_custom_type() {
+        let storage = get_test_storage("test_cached_storage_module_custom_type", PERSISTENT_STORAGE_CUSTOM_TYPE_TEST_SIZE);
+        test_persistent_storage_custom_type(CachedStorageModule::<4, 64, _>::new(storage));
+    }
+
+    #[test]
+    fn test_cached_storage_module_hits() {
+        let storage = get_test_storage("test_cached_storage_module_hits", 1024);
+        let mut storage = CachedStorageModule::<2, 64, _>::new(storage);
+
+        // write to two blocks, only the partially written one has to be read first
+        storage.write(60, &[1u8; 68]).unwrap();
+        assert_eq!(storage.get_miss_count(), 1);
+        assert_eq!(storage.get_dirty_blocks(), 2);
+
+        // not written to storage yet
+        let mut buffer = [0u8; 4];
+        storage.get_inner_mut().read(64, &mut buffer).unwrap();
+        assert_eq!(buffer, [0u8; 4]);
+
+        // reads are served from the cache
+        storage.read(60, &mut buffer).unwrap();
+        assert_eq!(buffer, [1u8; 4]);
+        assert_eq!(storage.get_hit_count(), 1);
+        assert_eq!(storage.get_miss_count(), 1);
+
+        // block 0 was used more recently, so block 1 is evicted and written back
+        storage.read(256, &mut buffer).unwrap();
+        assert_eq!(storage.get_miss_count(), 2);
+        assert_eq!(storage.get_write_back_count(), 1);
+        storage.get_inner_mut().read(64, &mut buffer).unwrap();
+        assert_eq!(buffer, [1u8; 4]);
+
+        storage.flush().unwrap();
+        assert_eq!(storage.get_dirty_blocks(), 0);
+        assert_eq!(storage.get_write_back_count(), 2);
+    }
+
+    #[test]
+    fn test_cached_storage_module_forget_region() {
+        let storage = get_test_storage("test_cached_storage_module_forget_region", 1024);
+        let mut storage = CachedStorageModule::<4, 64, _>::new(storage);
+
+        storage.write(0, &[2u8; 192]).unwrap();
+
+        // only block 1 is completely covered
+        storage.forget_region(32, 128);
+        assert_eq!(storage.get_write_back_count(), 1);
+        assert_eq!(storage.get_dirty_blocks(), 2);
+
+        let mut buffer = [0u8; 64];
+        storage.get_inner_mut().read(64, &mut buffer).unwrap();
+        assert_eq!(buffer, [2u8; 64]);
+
+        // block 1 has to be read again
+        storage.reset_counters();
+        storage.read(64, &mut buffer).unwrap();
+        assert_eq!(storage.get_miss_count(), 1);
+        assert_eq!(buffer, [2u8; 64]);
+    }
+
+    #[test]
+    fn test_cached_storage_module_max_dirty_blocks() {
+        let storage = get_test_storage("test_cached_storage_module_max_dirty_blocks", 1024);
+        let mut storage = CachedStorageModule::<4, 64, _>::new(storage);
+
+        storage.write(0, &[3u8; 256]).unwrap();
+        assert_eq!(storage.get_dirty_blocks(), 4);
+
+        storage.set_max_dirty_blocks(1).unwrap();
+        assert_eq!(storage.get_dirty_blocks(), 1);
+        assert_eq!(storage.get_write_back_count(), 3);
+
+        // the most recently written block is still dirty
+        let mut buffer = [0u8; 64];
+        storage.get_inner_mut().read(192, &mut buffer).unwrap();
+        assert_eq!(buffer, [0u8; 64]);
+
+        storage.write(0, &[4u8; 8]).unwrap();
+        assert_eq!(storage.get_dirty_blocks(), 1);
+        assert_eq!(storage.get_write_back_count(), 4);
+    }
+
+    #[test]
+    fn test_cached_storage_module_random() {
+        // the last block is only partially used
+        const SIZE: usize = 500;
+        const SEED: u64 = 7129384712398471233;
+
+        let storage = get_test_storage("test_cached_storage_module_random", SIZE);
+        let mut storage = CachedStorageModule::<3, 32, _>::new(storage);
+        storage.set_max_dirty_blocks(2).unwrap();
+        storage.write(0, &[0u8; SIZE]).unwrap();
+
+        let mut rand = SmallRng::seed_from_u64(SEED);
+        let mut expected = [0u8; SIZE];
+
+        for i in 0..5_000 {
+            let len = 1 + (rand.next_u32() as usize % 80);
+            let offset = rand.next_u32() as usize % (SIZE - len + 1);
+
+            match rand.next_u32() % 4 {
+                0 => {
+                    let mut buffer = [0u8; 80];
+                    storage.read(offset, &mut buffer[..len]).unwrap();
+                    assert_eq!(buffer[..len], expected[offset..offset + len]);
+                }
+                1 => storage.forget_region(offset, len),
+                _ => {
+                    let value = i as u8;
+                    storage.write(offset, &[value; 80][..len]).unwrap();
+                    expected[offset..offset + len].fill(value);
+                }
+            }
+
+            if i % 1000 == 0 {
+                storage.flush().unwrap();
+            }
+        }
+
+        let mut storage = storage.into_inner().unwrap();
+        let mut buffer = [0u8; SIZE];
+        storage.read(0, &mut buffer).unwrap();
+        assert_eq!(buffer, expected);
+    }
+}
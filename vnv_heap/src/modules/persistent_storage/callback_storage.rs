@@ -0,0 +1,84 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::ffi::c_void;
+
+use super::PersistentStorageModule;
+
+/// Storage access functions of C code (see `CallbackStorageModule`).
+///
+/// All functions get `context` as first argument and return `0` on success.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct VNVStorageCallbacks {
+    /// Passed to all functions, e.g. a pointer to a driver instance
+    pub context: *mut c_void,
+
+    /// Size of the storage in bytes
+    pub size: usize,
+
+    /// Reads `len` bytes at `offset` to `dest`
+    pub read: extern "C" fn(context: *mut c_void, offset: usize, dest: *mut u8, len: usize) -> i32,
+
+    /// Writes `len` bytes from `src` to `offset`
+    pub write: extern "C" fn(context: *mut c_void, offset: usize, src: *const u8, len: usize) -> i32,
+
+    /// Writes back deferred writes (optional, see `PersistentStorageModule::flush`)
+    pub flush: Option<extern "C" fn(context: *mut c_void) -> i32>,
+}
+
+/// Storage module whose accesses are implemented by C code (e.g. an existing driver of the application).
+pub struct CallbackStorageModule {
+    callbacks: VNVStorageCallbacks,
+}
+
+impl CallbackStorageModule {
+    /// ### Safety
+    ///
+    /// The functions of `callbacks` have to be safe to call with `callbacks.context` while this module exists
+    /// and access at most `len` bytes of their buffers.
+    pub unsafe fn new(callbacks: VNVStorageCallbacks) -> Self {
+        Self { callbacks }
+    }
+}
+
+impl PersistentStorageModule for CallbackStorageModule {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        match (self.callbacks.read)(self.callbacks.context, offset, dest.as_mut_ptr(), dest.len()) {
+            0 => Ok(()),
+            _ => Err(()),
+        }
+    }
+
+    fn get_max_size(&self) -> usize {
+        self.callbacks.size
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        match (self.callbacks.write)(self.callbacks.context, offset, src.as_ptr(), src.len()) {
+            0 => Ok(()),
+            _ => Err(()),
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), ()> {
+        match self.callbacks.flush.map_or(0, |flush| flush(self.callbacks.context)) {
+            0 => Ok(()),
+            _ => Err(()),
+        }
+    }
+}
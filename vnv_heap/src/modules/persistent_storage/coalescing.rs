@@ -0,0 +1,546 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::PersistentStorageModule;
+
+/// Size of the stack buffer that gaps are read into (see `CoalescingStorageModule::set_merge_window`)
+const GAP_FILL_CHUNK_SIZE: usize = 32;
+
+#[derive(Clone, Copy, Default)]
+struct QueuedWrite {
+    /// Offset of this write in the underlying storage
+    offset: usize,
+
+    /// Start of the queued data inside of the write buffer
+    buffer_offset: usize,
+
+    len: usize,
+}
+
+impl QueuedWrite {
+    #[inline]
+    fn end(&self) -> usize {
+        self.offset + self.len
+    }
+}
+
+/// Defers writes and writes them back in batches that are sorted by their offset.
+///
+/// Up to `MAX_WRITES` non-overlapping writes with a total of `BUFFER_SIZE` bytes are queued in RAM.
+/// Writes that directly follow each other are merged, so they are written back with a single call.
+/// The queue is written back if `flush` is called, if it is full or if this module is dropped.
+///
+/// This improves throughput on storage where sequential writes are a lot faster than scattered ones.
+///
+/// **Note:** Queued data is also written back while persisting the heap (see `flush`),
+/// so a persist writes up to `BUFFER_SIZE` bytes on top of the dirty objects.
+pub struct CoalescingStorageModule<const MAX_WRITES: usize, const BUFFER_SIZE: usize, S: PersistentStorageModule> {
+    inner: S,
+
+    /// Queued writes, sorted by their offset.
+    /// The data of these writes is stored in the same order in `buffer`.
+    queue: [QueuedWrite; MAX_WRITES],
+    queue_len: usize,
+
+    buffer: [u8; BUFFER_SIZE],
+    buffer_used: usize,
+
+    /// Queued writes with a gap of at most this many bytes are merged
+    merge_window: usize,
+
+    queued_write_count: usize,
+    flushed_write_count: usize,
+}
+
+impl<const MAX_WRITES: usize, const BUFFER_SIZE: usize, S: PersistentStorageModule> CoalescingStorageModule<MAX_WRITES, BUFFER_SIZE, S> {
+    pub fn new(storage: S) -> Self {
+        assert!(MAX_WRITES > 0, "queue has to hold at least one write");
+
+        Self {
+            inner: storage,
+            queue: [QueuedWrite::default(); MAX_WRITES],
+            queue_len: 0,
+            buffer: [0u8; BUFFER_SIZE],
+            buffer_used: 0,
+            merge_window: 0,
+            queued_write_count: 0,
+            flushed_write_count: 0,
+        }
+    }
+
+    pub fn get_merge_window(&self) -> usize {
+        self.merge_window
+    }
+
+    /// Merges queued writes that are at most `merge_window` bytes apart (0 by default).
+    ///
+    /// The gap between them is read from the underlying storage and queued as well, so both writes
+    /// are written back with a single call. This trades a read for a write transaction and some buffer space,
+    /// which pays off if every transaction has a high fixed cost (e.g. SPI command overhead).
+    pub fn set_merge_window(&mut self, merge_window: usize) {
+        self.merge_window = merge_window;
+    }
+
+    /// Returns how many bytes are currently queued
+    pub fn get_pending_bytes(&self) -> usize {
+        self.buffer_used
+    }
+
+    /// Returns how many (already merged) writes are currently queued
+    pub fn get_pending_writes(&self) -> usize {
+        self.queue_len
+    }
+
+    /// Returns how many writes were queued instead of being passed to the underlying storage directly
+    pub fn get_queued_write_count(&self) -> usize {
+        self.queued_write_count
+    }
+
+    /// Returns how many writes were passed to the underlying storage while flushing the queue
+    pub fn get_flushed_write_count(&self) -> usize {
+        self.flushed_write_count
+    }
+
+    pub fn reset_counters(&mut self) {
+        self.queued_write_count = 0;
+        self.flushed_write_count = 0;
+    }
+
+    pub fn get_inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the underlying storage.
+    ///
+    /// Be aware that accessing it directly bypasses the queue.
+    pub fn get_inner_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Flushes the queue and returns the underlying storage
+    pub fn into_inner(mut self) -> Result<S, ()> {
+        self.flush()?;
+
+        // the queue is empty now, so skipping drop does not lose any data
+        let this = core::mem::ManuallyDrop::new(self);
+        Ok(unsafe { core::ptr::read(&this.inner) })
+    }
+
+    /// Tries to queue the write `[offset, offset + src.len())`.
+    ///
+    /// Returns `false` if this is not possible without flushing the queue first.
+    fn try_enqueue(&mut self, offset: usize, src: &[u8]) -> bool {
+        let end = offset + src.len();
+
+        // index of the first write that ends after `offset`
+        let index = self.queue[..self.queue_len]
+            .iter()
+            .position(|item| item.end() > offset)
+            .unwrap_or(self.queue_len);
+
+        if let Some(item) = self.queue[..self.queue_len].get(index) {
+            if item.offset <= offset && end <= item.end() {
+                // this region is already queued, overwrite it
+                let start = item.buffer_offset + (offset - item.offset);
+                self.buffer[start..start + src.len()].copy_from_slice(src);
+                return true;
+            }
+
+            if item.offset < end {
+                // partially overlapping writes are not merged as this would change the order of writes
+                return false;
+            }
+        }
+
+        if self.buffer_used + src.len() > BUFFER_SIZE {
+            return false;
+        }
+
+        let extends_prev = index > 0 && self.queue[index - 1].end() == offset;
+        let extends_next = !extends_prev && self.queue[..self.queue_len].get(index).is_some_and(|next| next.offset == end);
+        if !extends_prev && !extends_next && self.queue_len == MAX_WRITES {
+            return false;
+        }
+
+        let buffer_offset = match self.queue[..index].last() {
+            Some(prev) => prev.buffer_offset + prev.len,
+            None => 0,
+        };
+
+        // make space in the buffer, so that the data stays in the same order as the queue
+        self.buffer
+            .copy_within(buffer_offset..self.buffer_used, buffer_offset + src.len());
+        self.buffer[buffer_offset..buffer_offset + src.len()].copy_from_slice(src);
+        self.buffer_used += src.len();
+
+        for item in self.queue[index..self.queue_len].iter_mut() {
+            item.buffer_offset += src.len();
+        }
+
+        let curr_index = if extends_prev {
+            self.queue[index - 1].len += src.len();
+            index - 1
+        } else if extends_next {
+            // e.g. a header that is written after the data behind it
+            let next = &mut self.queue[index];
+            next.offset = offset;
+            next.buffer_offset = buffer_offset;
+            next.len += src.len();
+            index
+        } else {
+            self.queue.copy_within(index..self.queue_len, index + 1);
+            self.queue[index] = QueuedWrite {
+                offset,
+                buffer_offset,
+                len: src.len(),
+            };
+            self.queue_len += 1;
+            index
+        };
+
+        // merge with the next write if there is no gap between them anymore
+        if curr_index + 1 < self.queue_len && self.queue[curr_index].end() == self.queue[curr_index + 1].offset {
+            self.queue[curr_index].len += self.queue[curr_index + 1].len;
+            self.queue.copy_within(curr_index + 2..self.queue_len, curr_index + 1);
+            self.queue_len -= 1;
+        }
+
+        true
+    }
+
+    /// Queues the gaps between writes that are at most `merge_window` bytes apart.
+    ///
+    /// Gaps are only filled if this is possible without flushing the queue.
+    fn fill_small_gaps(&mut self) {
+        let mut index = 0;
+        while index + 1 < self.queue_len {
+            let gap_start = self.queue[index].end();
+            let gap = self.queue[index + 1].offset - gap_start;
+            if gap > self.merge_window || self.buffer_used + gap > BUFFER_SIZE {
+                index += 1;
+                continue;
+            }
+
+            // the gap is not queued, so the underlying storage contains its current data
+            let mut chunk = [0u8; GAP_FILL_CHUNK_SIZE];
+            let mut filled = 0;
+            while filled < gap {
+                let len = (gap - filled).min(GAP_FILL_CHUNK_SIZE);
+                if self.inner.read(gap_start + filled, &mut chunk[..len]).is_err() {
+                    return;
+                }
+
+                let res = self.try_enqueue(gap_start + filled, &chunk[..len]);
+                debug_assert!(res, "there is enough space in the buffer and the chunk extends a queued write");
+                filled += len;
+            }
+
+            // the writes are merged now, so the next gap is at the same index
+        }
+    }
+}
+
+impl<const MAX_WRITES: usize, const BUFFER_SIZE: usize, S: PersistentStorageModule> PersistentStorageModule for CoalescingStorageModule<MAX_WRITES, BUFFER_SIZE, S> {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        self.inner.read(offset, dest)?;
+
+        // queued data is newer than the data in the underlying storage
+        let end = offset + dest.len();
+        for item in self.queue[..self.queue_len].iter() {
+            let start = item.offset.max(offset);
+            let stop = item.end().min(end);
+            if start >= stop {
+                continue;
+            }
+
+            let buffer_start = item.buffer_offset + (start - item.offset);
+            dest[start - offset..stop - offset]
+                .copy_from_slice(&self.buffer[buffer_start..buffer_start + (stop - start)]);
+        }
+
+        Ok(())
+    }
+
+    fn get_max_size(&self) -> usize {
+        self.inner.get_max_size()
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        if src.is_empty() {
+            return Ok(());
+        }
+
+        if src.len() > BUFFER_SIZE {
+            // would never fit into the queue
+            self.flush()?;
+            return self.inner.write(offset, src);
+        }
+
+        if !self.try_enqueue(offset, src) {
+            self.flush()?;
+
+            let res = self.try_enqueue(offset, src);
+            debug_assert!(res, "queue is empty, so this should always succeed");
+        }
+
+        if self.merge_window > 0 {
+            self.fill_small_gaps();
+        }
+
+        self.queued_write_count += 1;
+        Ok(())
+    }
+
+    fn forget_region(&mut self, offset: usize, size: usize) {
+        self.inner.forget_region(offset, size)
+    }
+
+    fn flush(&mut self) -> Result<(), ()> {
+        let mut flushed = 0;
+        let mut res = Ok(());
+        for item in self.queue[..self.queue_len].iter() {
+            res = self.inner.write(
+                item.offset,
+                &self.buffer[item.buffer_offset..item.buffer_offset + item.len],
+            );
+            if res.is_err() {
+                break;
+            }
+            flushed += 1;
+        }
+        self.flushed_write_count += flushed;
+
+        if flushed > 0 {
+            // remove written back data, so that the queue stays consistent if a write failed
+            let last = self.queue[flushed - 1];
+            let flushed_bytes = last.buffer_offset + last.len;
+
+            self.buffer.copy_within(flushed_bytes..self.buffer_used, 0);
+            self.buffer_used -= flushed_bytes;
+
+            self.queue.copy_within(flushed..self.queue_len, 0);
+            self.queue_len -= flushed;
+            for item in self.queue[..self.queue_len].iter_mut() {
+                item.buffer_offset -= flushed_bytes;
+            }
+        }
+
+        res?;
+        self.inner.flush()
+    }
+}
+
+impl<const MAX_WRITES: usize, const BUFFER_SIZE: usize, S: PersistentStorageModule> Drop for CoalescingStorageModule<MAX_WRITES, BUFFER_SIZE, S> {
+    fn drop(&mut self) {
+        if self.flush().is_err() {
+            println!("could not flush queued writes");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{rngs::SmallRng, RngCore, SeedableRng};
+
+    use crate::modules::persistent_storage::{
+        test::{
+            get_test_storage, test_persistent_storage_custom_type, test_persistent_storage_normal,
+            PERSISTENT_STORAGE_CUSTOM_TYPE_TEST_SIZE, PERSISTENT_STORAGE_NORMAL_TEST_SIZE,
+        },
+        PersistentStorageModule,
+    };
+
+    use super::CoalescingStorageModule;
+
+    #[test]
+    fn test_coalescing_storage_module_normal() {
+        let storage = get_test_storage("test_coalescing_storage_module_normal", PERSISTENT_STORAGE_NORMAL_TEST_SIZE);
+        test_persistent_storage_normal(CoalescingStorageModule::<8, 256, _>::new(storage));
+    }
+
+    #[test]
+    fn test_coalescing_storage_module_custom_type() {
+        let storage = get_test_storage("test_coalescing_storage_module_custom_type", PERSISTENT_STORAGE_CUSTOM_TYPE_TEST_SIZE);
+        test_persistent_storage_custom_type(CoalescingStorageModule::<8, 256, _>::new(storage));
+    }
+
+    #[test]
+    fn test_coalescing_storage_module_batches() {
+        let storage = get_test_storage("test_coalescing_storage_module_batches", 1024);
+        let mut storage = CoalescingStorageModule::<4, 128, _>::new(storage);
+
+        // written in reverse order, but without gaps
+        for i in (0..8).rev() {
+            storage.write(100 + i * 4, &[i as u8; 4]).unwrap();
+        }
+        storage.write(500, &[9u8; 8]).unwrap();
+        assert_eq!(storage.get_pending_writes(), 2);
+        assert_eq!(storage.get_pending_bytes(), 40);
+
+        // not written to storage yet
+        let mut buffer = [0u8; 4];
+        storage.get_inner_mut().read(128, &mut buffer).unwrap();
+        assert_eq!(buffer, [0u8; 4]);
+
+        // but reads already see the queued data
+        storage.read(126, &mut buffer).unwrap();
+        assert_eq!(buffer, [6, 6, 7, 7]);
+
+        // overwrite a region that is already queued
+        storage.write(102, &[42u8; 4]).unwrap();
+        assert_eq!(storage.get_pending_writes(), 2);
+
+        storage.flush().unwrap();
+        assert_eq!(storage.get_pending_writes(), 0);
+        assert_eq!(storage.get_pending_bytes(), 0);
+        assert_eq!(storage.get_queued_write_count(), 10);
+        assert_eq!(storage.get_flushed_write_count(), 2);
+
+        let mut buffer = [0u8; 8];
+        storage.get_inner_mut().read(100, &mut buffer).unwrap();
+        assert_eq!(buffer, [0, 0, 42, 42, 42, 42, 1, 1]);
+    }
+
+    #[test]
+    fn test_coalescing_storage_module_full() {
+        let storage = get_test_storage("test_coalescing_storage_module_full", 1024);
+        let mut storage = CoalescingStorageModule::<2, 16, _>::new(storage);
+
+        storage.write(0, &[1u8; 4]).unwrap();
+        storage.write(10, &[2u8; 4]).unwrap();
+        assert_eq!(storage.get_flushed_write_count(), 0);
+
+        // no free slot left
+        storage.write(20, &[3u8; 4]).unwrap();
+        assert_eq!(storage.get_flushed_write_count(), 2);
+        assert_eq!(storage.get_pending_writes(), 1);
+
+        // bigger than the buffer
+        storage.write(100, &[4u8; 32]).unwrap();
+        assert_eq!(storage.get_flushed_write_count(), 3);
+        assert_eq!(storage.get_pending_writes(), 0);
+
+        let mut buffer = [0u8; 4];
+        storage.get_inner_mut().read(20, &mut buffer).unwrap();
+        assert_eq!(buffer, [3u8; 4]);
+    }
+
+    #[test]
+    fn test_coalescing_storage_module_prepend() {
+        let storage = get_test_storage("test_coalescing_storage_module_prepend", 1024);
+        let mut storage = CoalescingStorageModule::<2, 64, _>::new(storage);
+
+        storage.write(4, &[1u8; 8]).unwrap();
+        storage.write(20, &[2u8; 8]).unwrap();
+
+        // extends the queued writes in front, so no free slot is needed
+        storage.write(0, &[3u8; 4]).unwrap();
+        storage.write(16, &[4u8; 4]).unwrap();
+        assert_eq!(storage.get_pending_writes(), 2);
+        assert_eq!(storage.get_flushed_write_count(), 0);
+
+        storage.flush().unwrap();
+        assert_eq!(storage.get_flushed_write_count(), 2);
+
+        let mut buffer = [0u8; 28];
+        storage.get_inner_mut().read(0, &mut buffer).unwrap();
+        assert_eq!(buffer[..4], [3u8; 4]);
+        assert_eq!(buffer[4..12], [1u8; 8]);
+        assert_eq!(buffer[16..20], [4u8; 4]);
+        assert_eq!(buffer[20..], [2u8; 8]);
+    }
+
+    #[test]
+    fn test_coalescing_storage_module_merge_window() {
+        let storage = get_test_storage("test_coalescing_storage_module_merge_window", 1024);
+        let mut storage = CoalescingStorageModule::<4, 128, _>::new(storage);
+        storage.get_inner_mut().write(100, &[7u8; 64]).unwrap();
+        storage.set_merge_window(8);
+
+        storage.write(100, &[1u8; 4]).unwrap();
+        storage.write(112, &[2u8; 4]).unwrap();
+        storage.write(130, &[3u8; 4]).unwrap();
+        assert_eq!(storage.get_pending_writes(), 2);
+        assert_eq!(storage.get_pending_bytes(), 20);
+
+        // fills the gap to both neighbours
+        storage.write(122, &[4u8; 2]).unwrap();
+        assert_eq!(storage.get_pending_writes(), 1);
+        assert_eq!(storage.get_pending_bytes(), 34);
+
+        storage.flush().unwrap();
+        assert_eq!(storage.get_flushed_write_count(), 1);
+
+        let mut buffer = [0u8; 36];
+        storage.get_inner_mut().read(100, &mut buffer).unwrap();
+        assert_eq!(buffer[..4], [1u8; 4]);
+        assert_eq!(buffer[4..12], [7u8; 8]);
+        assert_eq!(buffer[12..16], [2u8; 4]);
+        assert_eq!(buffer[16..22], [7u8; 6]);
+        assert_eq!(buffer[22..24], [4u8; 2]);
+        assert_eq!(buffer[24..30], [7u8; 6]);
+        assert_eq!(buffer[30..34], [3u8; 4]);
+        assert_eq!(buffer[34..], [7u8; 2]);
+    }
+
+    #[test]
+    fn test_coalescing_storage_module_random() {
+        test_random("test_coalescing_storage_module_random", 0);
+    }
+
+    #[test]
+    fn test_coalescing_storage_module_random_merge_window() {
+        test_random("test_coalescing_storage_module_random_merge_window", 20);
+    }
+
+    fn test_random(test_name: &str, merge_window: usize) {
+        const SIZE: usize = 512;
+        const SEED: u64 = 5446535461589659585;
+
+        let storage = get_test_storage(test_name, SIZE);
+        let mut storage = CoalescingStorageModule::<5, 64, _>::new(storage);
+        storage.set_merge_window(merge_window);
+        storage.write(0, &[0u8; SIZE]).unwrap();
+
+        let mut rand = SmallRng::seed_from_u64(SEED);
+        let mut expected = [0u8; SIZE];
+
+        for i in 0..5_000 {
+            let len = 1 + (rand.next_u32() as usize % 24);
+            let offset = rand.next_u32() as usize % (SIZE - len);
+
+            if rand.next_u32() % 3 == 0 {
+                let mut buffer = [0u8; 24];
+                storage.read(offset, &mut buffer[..len]).unwrap();
+                assert_eq!(buffer[..len], expected[offset..offset + len]);
+            } else {
+                let value = i as u8;
+                storage.write(offset, &[value; 24][..len]).unwrap();
+                expected[offset..offset + len].fill(value);
+            }
+
+            if i % 1000 == 0 {
+                storage.flush().unwrap();
+            }
+        }
+
+        let mut storage = storage.into_inner().unwrap();
+        let mut buffer = [0u8; SIZE];
+        storage.read(0, &mut buffer).unwrap();
+        assert_eq!(buffer, expected);
+    }
+}
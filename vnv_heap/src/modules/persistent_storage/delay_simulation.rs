@@ -0,0 +1,196 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::{Duration, Instant};
+
+use super::PersistentStorageModule;
+
+/// Latency model used by `DelaySimulationStorageModule`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DelaySimulationTiming {
+    /// Fixed latency of every read transaction (e.g. command, address and driver overhead)
+    pub read_latency: Duration,
+
+    /// Fixed latency of every write transaction (e.g. write enable, command, address and driver overhead)
+    pub write_latency: Duration,
+
+    /// Time to transfer a single byte
+    pub byte_time: Duration,
+
+    /// If set, each access blocks until the simulated time has passed.
+    /// Otherwise, the simulated time is only accumulated (see `get_simulated_time`).
+    pub busy_wait: bool,
+}
+
+impl DelaySimulationTiming {
+    /// Timing of a MB85RS4MT FRAM module on a SPI bus with `spi_clock_hz` (e.g. 8 MHz).
+    ///
+    /// Every access transfers a command byte and a 24 bit address, writes additionally need a write enable transaction.
+    pub fn mb85rs4mt(spi_clock_hz: u32, transaction_overhead: Duration) -> Self {
+        assert!(spi_clock_hz > 0, "spi clock has to be greater than zero");
+
+        let byte_time = Duration::from_nanos(8 * 1_000_000_000 / spi_clock_hz as u64);
+        Self {
+            read_latency: transaction_overhead + byte_time * 4,
+            write_latency: (transaction_overhead + byte_time) + (transaction_overhead + byte_time * 4),
+            byte_time,
+            busy_wait: true,
+        }
+    }
+
+    /// Returns the simulated duration of reading `len` bytes
+    pub fn read_time(&self, len: usize) -> Duration {
+        self.read_latency + self.byte_time * len as u32
+    }
+
+    /// Returns the simulated duration of writing `len` bytes
+    pub fn write_time(&self, len: usize) -> Duration {
+        self.write_latency + self.byte_time * len as u32
+    }
+}
+
+/// Wraps another storage module and simulates the latency of a slower storage device.
+///
+/// File or RAM storage on a desktop is a lot faster than e.g. FRAM on an SPI bus, so benchmark results on a desktop
+/// would not be representative. With `busy_wait` enabled, every access takes at least as long as the simulated device needs.
+pub struct DelaySimulationStorageModule<S: PersistentStorageModule> {
+    inner: S,
+    timing: DelaySimulationTiming,
+    simulated_time: Duration,
+    transaction_count: usize,
+}
+
+impl<S: PersistentStorageModule> DelaySimulationStorageModule<S> {
+    pub fn new(storage: S, timing: DelaySimulationTiming) -> Self {
+        Self {
+            inner: storage,
+            timing,
+            simulated_time: Duration::ZERO,
+            transaction_count: 0,
+        }
+    }
+
+    pub fn get_timing(&self) -> DelaySimulationTiming {
+        self.timing
+    }
+
+    /// Returns the total time the simulated device spent on reads and writes
+    pub fn get_simulated_time(&self) -> Duration {
+        self.simulated_time
+    }
+
+    /// Returns the total number of simulated reads and writes
+    pub fn get_transaction_count(&self) -> usize {
+        self.transaction_count
+    }
+
+    pub fn reset_statistics(&mut self) {
+        self.simulated_time = Duration::ZERO;
+        self.transaction_count = 0;
+    }
+
+    /// Returns the wrapped storage module
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Runs `access` and waits until `duration` has passed since the access started (if `busy_wait` is set)
+    fn simulate<R>(&mut self, duration: Duration, access: impl FnOnce(&mut S) -> R) -> R {
+        let start = Instant::now();
+        let res = access(&mut self.inner);
+
+        if self.timing.busy_wait {
+            while start.elapsed() < duration {}
+        }
+
+        self.simulated_time += duration;
+        self.transaction_count += 1;
+        res
+    }
+}
+
+impl<S: PersistentStorageModule> PersistentStorageModule for DelaySimulationStorageModule<S> {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        let duration = self.timing.read_time(dest.len());
+        self.simulate(duration, |inner| inner.read(offset, dest))
+    }
+
+    fn get_max_size(&self) -> usize {
+        self.inner.get_max_size()
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        let duration = self.timing.write_time(src.len());
+        self.simulate(duration, |inner| inner.write(offset, src))
+    }
+
+    fn flush(&mut self) -> Result<(), ()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use crate::modules::persistent_storage::{
+        test::{get_test_storage, test_persistent_storage_normal, PERSISTENT_STORAGE_NORMAL_TEST_SIZE},
+        PersistentStorageModule,
+    };
+
+    use super::{DelaySimulationStorageModule, DelaySimulationTiming};
+
+    fn get_timing(busy_wait: bool) -> DelaySimulationTiming {
+        DelaySimulationTiming {
+            busy_wait,
+            ..DelaySimulationTiming::mb85rs4mt(8_000_000, Duration::from_micros(2))
+        }
+    }
+
+    #[test]
+    fn test_delay_simulation_normal() {
+        let storage = get_test_storage("test_delay_simulation_normal", PERSISTENT_STORAGE_NORMAL_TEST_SIZE);
+        test_persistent_storage_normal(DelaySimulationStorageModule::new(storage, get_timing(false)));
+    }
+
+    #[test]
+    fn test_delay_simulation_timing() {
+        let storage = get_test_storage("test_delay_simulation_timing", 1024);
+        let mut storage = DelaySimulationStorageModule::new(storage, get_timing(false));
+
+        // same timing as `MB85RS4MTMockStorageModule`: 4 + 96 bytes with 1 byte per microsecond
+        storage.read(0, &mut [0u8; 96]).unwrap();
+        assert_eq!(storage.get_transaction_count(), 1);
+        assert_eq!(storage.get_simulated_time(), Duration::from_micros(2 + 100));
+
+        // write enable + write transaction
+        storage.reset_statistics();
+        storage.write(0, &[0u8; 96]).unwrap();
+        assert_eq!(storage.get_transaction_count(), 1);
+        assert_eq!(storage.get_simulated_time(), Duration::from_micros(2 + 1 + 2 + 100));
+    }
+
+    #[test]
+    fn test_delay_simulation_busy_wait() {
+        let storage = get_test_storage("test_delay_simulation_busy_wait", 4096);
+        let mut storage = DelaySimulationStorageModule::new(storage, get_timing(true));
+
+        let start = Instant::now();
+        storage.write(0, &[1u8; 2000]).unwrap();
+        assert!(start.elapsed() >= Duration::from_micros(2000));
+    }
+}
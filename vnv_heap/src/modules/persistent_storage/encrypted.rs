@@ -0,0 +1,171 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::PersistentStorageModule;
+
+/// Size of the stack buffer that data is encrypted in before it is written
+const ENCRYPTION_BUFFER_SIZE: usize = 64;
+
+/// Stream cipher that is used by `EncryptedStorageModule` (e.g. `XChaCha20StorageCipher`).
+///
+/// The storage is split into blocks of `BLOCK_SIZE` bytes and each block uses its own nonce,
+/// which is derived from its index.
+pub trait StorageCipher {
+    /// Size of the blocks that get their own nonce
+    const BLOCK_SIZE: usize;
+
+    /// XORs the key stream of block `block_index` (starting at byte `offset_in_block`) into `data`.
+    ///
+    /// `offset_in_block + data.len()` is at most `BLOCK_SIZE`.
+    fn apply_keystream(&self, block_index: u64, offset_in_block: usize, data: &mut [u8]);
+}
+
+/// Encrypts all data before it is written to the wrapped storage module and decrypts it after reading (data at rest encryption).
+///
+/// As the key stream of every byte only depends on its offset, writes that only cover a part of a block
+/// do not have to read the rest of the block first.
+///
+/// **Note:** The data is not authenticated, so modifications of the storage are not detected
+/// (use `VNVHeap::allocate_encrypted` for this). As a block is encrypted with the same key stream
+/// every time it is written, an attacker that can read the storage at different times can see
+/// which bytes changed (XOR of the old and new plain text).
+pub struct EncryptedStorageModule<S: PersistentStorageModule, C: StorageCipher> {
+    inner: S,
+    cipher: C,
+}
+
+impl<S: PersistentStorageModule, C: StorageCipher> EncryptedStorageModule<S, C> {
+    pub fn new(storage: S, cipher: C) -> Self {
+        assert!(C::BLOCK_SIZE > 0, "block size has to be greater than zero");
+
+        Self {
+            inner: storage,
+            cipher,
+        }
+    }
+
+    /// Returns the wrapped storage module
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Applies the key stream for data that is located at `offset` in storage
+    fn apply_keystream(&self, offset: usize, data: &mut [u8]) {
+        let mut pos = 0;
+        while pos < data.len() {
+            let block_index = (offset + pos) / C::BLOCK_SIZE;
+            let offset_in_block = (offset + pos) % C::BLOCK_SIZE;
+            let len = (C::BLOCK_SIZE - offset_in_block).min(data.len() - pos);
+
+            self.cipher
+                .apply_keystream(block_index as u64, offset_in_block, &mut data[pos..pos + len]);
+            pos += len;
+        }
+    }
+}
+
+impl<S: PersistentStorageModule, C: StorageCipher> PersistentStorageModule for EncryptedStorageModule<S, C> {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        self.inner.read(offset, dest)?;
+        self.apply_keystream(offset, dest);
+        Ok(())
+    }
+
+    fn get_max_size(&self) -> usize {
+        self.inner.get_max_size()
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        // src cannot be encrypted in place, so it is encrypted in chunks
+        let mut buffer = [0u8; ENCRYPTION_BUFFER_SIZE];
+        for (i, chunk) in src.chunks(ENCRYPTION_BUFFER_SIZE).enumerate() {
+            let chunk_offset = offset + i * ENCRYPTION_BUFFER_SIZE;
+            let encrypted = &mut buffer[..chunk.len()];
+            encrypted.copy_from_slice(chunk);
+            self.apply_keystream(chunk_offset, encrypted);
+            self.inner.write(chunk_offset, encrypted)?;
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), ()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::modules::persistent_storage::{
+        test::{
+            get_test_storage, test_persistent_storage_custom_type, test_persistent_storage_normal,
+            PERSISTENT_STORAGE_CUSTOM_TYPE_TEST_SIZE, PERSISTENT_STORAGE_NORMAL_TEST_SIZE,
+        },
+        PersistentStorageModule, XChaCha20StorageCipher,
+    };
+
+    use super::EncryptedStorageModule;
+
+    fn get_cipher() -> XChaCha20StorageCipher {
+        XChaCha20StorageCipher::new(&[7u8; 32], &[3u8; 16])
+    }
+
+    #[test]
+    fn test_encrypted_storage_module_normal() {
+        let storage = get_test_storage("test_encrypted_storage_module_normal", PERSISTENT_STORAGE_NORMAL_TEST_SIZE);
+        test_persistent_storage_normal(EncryptedStorageModule::new(storage, get_cipher()));
+    }
+
+    #[test]
+    fn test_encrypted_storage_module_custom_type() {
+        let storage = get_test_storage("test_encrypted_storage_module_custom_type", PERSISTENT_STORAGE_CUSTOM_TYPE_TEST_SIZE);
+        test_persistent_storage_custom_type(EncryptedStorageModule::new(storage, get_cipher()));
+    }
+
+    #[test]
+    fn test_encrypted_storage_module_partial_writes() {
+        let storage = get_test_storage("test_encrypted_storage_module_partial_writes", 1024);
+        let mut module = EncryptedStorageModule::new(storage, get_cipher());
+
+        let data: [u8; 300] = core::array::from_fn(|i| i as u8);
+        module.write(10, &data).unwrap();
+
+        // overwrite a few bytes that cross a block boundary
+        module.write(60, &[0xAA; 8]).unwrap();
+
+        let mut read = [0u8; 300];
+        module.read(10, &mut read).unwrap();
+        for (i, byte) in read.iter().enumerate() {
+            let offset = i + 10;
+            if (60..68).contains(&offset) {
+                assert_eq!(*byte, 0xAA);
+            } else {
+                assert_eq!(*byte, data[i]);
+            }
+        }
+
+        // reading a part of a block also works
+        let mut part = [0u8; 5];
+        module.read(100, &mut part).unwrap();
+        assert_eq!(part, data[90..95]);
+
+        // the underlying storage does not contain the plain text
+        let mut storage = module.into_inner();
+        storage.read(10, &mut read).unwrap();
+        assert_ne!(read[..50], data[..50]);
+    }
+}
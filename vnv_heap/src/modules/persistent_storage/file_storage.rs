@@ -22,7 +22,7 @@ use std::{
     path::Path,
 };
 
-use super::PersistentStorageModule;
+use super::{AsyncPersistentStorageModule, PersistentStorageModule};
 
 pub struct FilePersistentStorageModule {
     /// underlying file which will be mapped
@@ -95,6 +95,8 @@ impl PersistentStorageModule for FilePersistentStorageModule {
     }
 }
 
+impl AsyncPersistentStorageModule for FilePersistentStorageModule {}
+
 impl Drop for FilePersistentStorageModule {
     fn drop(&mut self) {
         // drop and close file before removing
@@ -111,15 +113,12 @@ impl Drop for FilePersistentStorageModule {
 
 #[cfg(test)]
 mod test {
-    use std::sync::atomic::AtomicBool;
-
-    use try_lock::TryLock;
-
     use crate::modules::persistent_storage::test::{
         test_persistent_storage_custom_type, PERSISTENT_STORAGE_CUSTOM_TYPE_TEST_SIZE,
         PERSISTENT_STORAGE_NORMAL_TEST_SIZE,
     };
     use crate::modules::persistent_storage::{PersistentStorageModule, SharedStorageReference};
+    use crate::persist_sync::{AtomicBool, TryLock};
     use crate::shared_persist_lock::SharedPersistLock;
 
     use super::super::test::test_persistent_storage_normal;
@@ -0,0 +1,480 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::mem::size_of;
+
+use embedded_storage::nor_flash::NorFlash;
+
+use crate::util::crc32;
+
+use super::PersistentStorageModule;
+
+/// Byte value of erased flash
+const ERASED_BYTE: u8 = 0xFF;
+
+/// Size of the content of a sector header: logical sector, sequence number and CRC-32
+const HEADER_CONTENT_SIZE: usize = 3 * size_of::<u32>();
+
+/// Makes flash that has to be erased before it can be written again usable as byte addressable `PersistentStorageModule`.
+///
+/// The flash is divided into `SECTORS` erase sectors, one of which is kept erased as spare.
+/// Logical sectors are mapped to physical sectors. Every physical sector starts with a small header
+/// that stores its logical sector, so `(SECTORS - 1) * (F::ERASE_SIZE - header size)` bytes can be used.
+/// If the written (aligned) region is still erased, the data is written in place.
+/// Otherwise, the sector is read into a RAM buffer, merged with the new data and written to the spare sector.
+/// Afterwards, the old sector is erased and becomes the new spare sector.
+///
+/// In contrast to `NorFlashStorageModule`, the old data of a sector stays intact until its new version is written completely
+/// and the wear of a frequently written sector is spread over two physical sectors
+/// (use `WearLevelingStorageModule` on top of this module to spread it over all sectors).
+///
+/// The mapping is kept in RAM and rebuilt from the sector headers by `new`.
+/// The header of a new version is written after its data and carries a higher sequence number than the old version,
+/// so a power failure during a write either keeps the old version or completes the new one.
+///
+/// `BUFFER_SIZE` has to be at least `F::ERASE_SIZE` bytes big.
+///
+/// **Note:** Erase counts are only tracked since this module was created.
+/// Also, overwriting data can erase two sectors (the spare sector and the old one) for each touched sector,
+/// so a persist of a few dirty bytes can take as long as two erases.
+pub struct FlashTranslationStorageModule<F: NorFlash, const SECTORS: usize, const BUFFER_SIZE: usize = 4096> {
+    flash: F,
+    buffer: [u8; BUFFER_SIZE],
+
+    /// Physical sector of each logical sector (the last entry is the spare sector)
+    mapping: [usize; SECTORS],
+
+    /// Whether the spare sector is known to be erased
+    spare_erased: bool,
+
+    /// Erase count of each physical sector
+    erase_counts: [u32; SECTORS],
+
+    /// Sequence number of the newest sector header
+    sequence: u32,
+}
+
+impl<F: NorFlash, const SECTORS: usize, const BUFFER_SIZE: usize> FlashTranslationStorageModule<F, SECTORS, BUFFER_SIZE> {
+    /// Size of the sector header, aligned to the read and write sizes of the flash
+    const HEADER_SIZE: usize = {
+        let mut size = F::WRITE_SIZE;
+        while size < HEADER_CONTENT_SIZE || size % F::READ_SIZE != 0 {
+            size += F::WRITE_SIZE;
+        }
+        size
+    };
+
+    /// Usable bytes of each sector
+    const DATA_SIZE: usize = F::ERASE_SIZE - Self::HEADER_SIZE;
+
+    /// Creates a new module and restores the mapping from the sector headers of `flash`.
+    ///
+    /// Sectors whose new version was not written completely (e.g. because of a power failure) are erased again.
+    pub fn new(flash: F) -> Result<Self, ()> {
+        assert!(SECTORS >= 2, "at least one sector and one spare sector are required");
+        assert!(SECTORS <= u32::MAX as usize, "too many sectors");
+        assert!(
+            F::ERASE_SIZE <= BUFFER_SIZE,
+            "buffer has to be at least as big as an erase sector"
+        );
+        assert!(
+            F::ERASE_SIZE % F::WRITE_SIZE == 0 && F::ERASE_SIZE % F::READ_SIZE == 0,
+            "erase sectors have to be aligned to read and write sizes"
+        );
+        assert!(Self::HEADER_SIZE < F::ERASE_SIZE, "erase sectors are too small");
+        assert!(
+            flash.capacity() >= SECTORS * F::ERASE_SIZE,
+            "flash is too small for the given sector count"
+        );
+
+        let mut module = Self {
+            flash,
+            buffer: [0u8; BUFFER_SIZE],
+            mapping: [0; SECTORS],
+            spare_erased: false,
+            erase_counts: [0; SECTORS],
+            sequence: 0,
+        };
+        module.restore_mapping()?;
+
+        Ok(module)
+    }
+
+    /// Returns how many sectors were erased so far
+    pub fn get_erase_count(&self) -> usize {
+        self.erase_counts.iter().map(|count| *count as usize).sum()
+    }
+
+    /// Returns the erase count of each physical sector
+    pub fn get_erase_counts(&self) -> &[u32; SECTORS] {
+        &self.erase_counts
+    }
+
+    pub fn get_inner(&self) -> &F {
+        &self.flash
+    }
+
+    pub fn into_inner(self) -> F {
+        self.flash
+    }
+
+    /// Reads the header of the given physical sector.
+    ///
+    /// Returns `None` if the header was never written or its write was interrupted.
+    fn read_header(&mut self, physical: usize) -> Result<Option<(usize, u32)>, ()> {
+        let header = &mut self.buffer[..Self::HEADER_SIZE];
+        self.flash
+            .read((physical * F::ERASE_SIZE) as u32, header)
+            .map_err(|_| ())?;
+
+        let logical = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+        let sequence = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        let checksum = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        if checksum != crc32(&header[..8]) || logical >= SECTORS - 1 {
+            return Ok(None);
+        }
+
+        Ok(Some((logical, sequence)))
+    }
+
+    /// Writes the header of `logical` to the first bytes of the sector buffer
+    fn fill_header(&mut self, logical: usize, sequence: u32) {
+        let header = &mut self.buffer[..Self::HEADER_SIZE];
+        header.fill(ERASED_BYTE);
+        header[0..4].copy_from_slice(&(logical as u32).to_le_bytes());
+        header[4..8].copy_from_slice(&sequence.to_le_bytes());
+        let checksum = crc32(&header[..8]);
+        header[8..12].copy_from_slice(&checksum.to_le_bytes());
+    }
+
+    /// Rebuilds the mapping from the sector headers
+    fn restore_mapping(&mut self) -> Result<(), ()> {
+        let mut found: [Option<(usize, u32)>; SECTORS] = [None; SECTORS];
+        let mut is_free = [true; SECTORS];
+
+        for physical in 0..SECTORS {
+            let (logical, sequence) = match self.read_header(physical)? {
+                Some(header) => header,
+                None => continue,
+            };
+
+            self.sequence = self.sequence.max(sequence);
+            match found[logical] {
+                Some((_, prev_sequence)) if prev_sequence > sequence => {
+                    // the power failed before this old version was erased
+                    self.erase_sector(physical)?;
+                }
+                Some((prev_physical, _)) => {
+                    self.erase_sector(prev_physical)?;
+                    is_free[prev_physical] = true;
+                    found[logical] = Some((physical, sequence));
+                    is_free[physical] = false;
+                }
+                None => {
+                    found[logical] = Some((physical, sequence));
+                    is_free[physical] = false;
+                }
+            }
+        }
+
+        // sectors without a header are either erased or a new version whose header was not written
+        for physical in 0..SECTORS {
+            if is_free[physical] && !self.is_sector_erased(physical)? {
+                self.erase_sector(physical)?;
+            }
+        }
+
+        let mut free = (0..SECTORS).filter(|physical| is_free[*physical]);
+        for logical in 0..(SECTORS - 1) {
+            self.mapping[logical] = match found[logical] {
+                Some((physical, _)) => physical,
+                None => free.next().unwrap(),
+            };
+        }
+        self.mapping[SECTORS - 1] = free.next().unwrap();
+        self.spare_erased = true;
+
+        Ok(())
+    }
+
+    fn is_sector_erased(&mut self, physical: usize) -> Result<bool, ()> {
+        let sector = &mut self.buffer[..F::ERASE_SIZE];
+        self.flash
+            .read((physical * F::ERASE_SIZE) as u32, sector)
+            .map_err(|_| ())?;
+
+        Ok(sector.iter().all(|byte| *byte == ERASED_BYTE))
+    }
+
+    fn erase_sector(&mut self, physical: usize) -> Result<(), ()> {
+        let start = physical * F::ERASE_SIZE;
+        self.flash
+            .erase(start as u32, (start + F::ERASE_SIZE) as u32)
+            .map_err(|_| ())?;
+        self.erase_counts[physical] += 1;
+
+        Ok(())
+    }
+
+    /// Writes `src` to `rel_offset` inside of the data of the given logical sector
+    fn write_sector(&mut self, logical: usize, rel_offset: usize, src: &[u8]) -> Result<(), ()> {
+        let physical = self.mapping[logical];
+        let sector = &mut self.buffer[..F::ERASE_SIZE];
+        self.flash
+            .read((physical * F::ERASE_SIZE) as u32, sector)
+            .map_err(|_| ())?;
+
+        let rel_offset = Self::HEADER_SIZE + rel_offset;
+        if sector[rel_offset..rel_offset + src.len()] == *src {
+            // nothing changes
+            return Ok(());
+        }
+
+        // region that has to be programmed, aligned to the write size
+        let write_start = rel_offset - (rel_offset % F::WRITE_SIZE);
+        let write_end = {
+            let end = rel_offset + src.len();
+            (end + F::WRITE_SIZE - 1) - ((end + F::WRITE_SIZE - 1) % F::WRITE_SIZE)
+        };
+
+        let is_erased = sector[write_start..write_end]
+            .iter()
+            .all(|byte| *byte == ERASED_BYTE);
+        let has_header = sector[..Self::HEADER_SIZE]
+            .iter()
+            .any(|byte| *byte != ERASED_BYTE);
+
+        sector[rel_offset..rel_offset + src.len()].copy_from_slice(src);
+
+        if is_erased {
+            if !has_header {
+                // first write to this sector: claim it for its logical sector before writing data
+                self.sequence += 1;
+                self.fill_header(logical, self.sequence);
+                self.flash
+                    .write((physical * F::ERASE_SIZE) as u32, &self.buffer[..Self::HEADER_SIZE])
+                    .map_err(|_| ())?;
+            }
+
+            // can be written without erasing the sector first
+            return self
+                .flash
+                .write((physical * F::ERASE_SIZE + write_start) as u32, &self.buffer[write_start..write_end])
+                .map_err(|_| ());
+        }
+
+        // write the new version of the sector to the spare sector
+        let spare = self.mapping[SECTORS - 1];
+        if !self.spare_erased {
+            self.erase_sector(spare)?;
+        }
+
+        self.spare_erased = false;
+        self.sequence += 1;
+        self.fill_header(logical, self.sequence);
+
+        // IMPORTANT: write the header last, so an incomplete version is not restored
+        self.flash
+            .write(
+                (spare * F::ERASE_SIZE + Self::HEADER_SIZE) as u32,
+                &self.buffer[Self::HEADER_SIZE..F::ERASE_SIZE],
+            )
+            .map_err(|_| ())?;
+        self.flash
+            .write((spare * F::ERASE_SIZE) as u32, &self.buffer[..Self::HEADER_SIZE])
+            .map_err(|_| ())?;
+        self.mapping[logical] = spare;
+        self.mapping[SECTORS - 1] = physical;
+
+        // the old version is not needed anymore
+        self.erase_sector(physical)?;
+        self.spare_erased = true;
+
+        Ok(())
+    }
+}
+
+impl<F: NorFlash, const SECTORS: usize, const BUFFER_SIZE: usize> PersistentStorageModule
+    for FlashTranslationStorageModule<F, SECTORS, BUFFER_SIZE>
+{
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        debug_assert!(offset + dest.len() <= self.get_max_size());
+
+        let mut rel_offset = 0;
+        while rel_offset < dest.len() {
+            let curr_offset = offset + rel_offset;
+            let start = Self::HEADER_SIZE + curr_offset % Self::DATA_SIZE;
+            let len = (F::ERASE_SIZE - start).min(dest.len() - rel_offset);
+            let physical_offset = self.mapping[curr_offset / Self::DATA_SIZE] * F::ERASE_SIZE;
+            let dest = &mut dest[rel_offset..rel_offset + len];
+
+            if start % F::READ_SIZE == 0 && len % F::READ_SIZE == 0 {
+                self.flash
+                    .read((physical_offset + start) as u32, dest)
+                    .map_err(|_| ())?;
+            } else {
+                // unaligned read: read the whole sector and copy the requested part
+                let sector = &mut self.buffer[..F::ERASE_SIZE];
+                self.flash
+                    .read(physical_offset as u32, sector)
+                    .map_err(|_| ())?;
+                dest.copy_from_slice(&sector[start..start + len]);
+            }
+
+            rel_offset += len;
+        }
+
+        Ok(())
+    }
+
+    fn get_max_size(&self) -> usize {
+        (SECTORS - 1) * Self::DATA_SIZE
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        debug_assert!(offset + src.len() <= self.get_max_size());
+
+        let mut rel_offset = 0;
+        while rel_offset < src.len() {
+            let curr_offset = offset + rel_offset;
+            let start = curr_offset % Self::DATA_SIZE;
+            let len = (Self::DATA_SIZE - start).min(src.len() - rel_offset);
+
+            self.write_sector(curr_offset / Self::DATA_SIZE, start, &src[rel_offset..rel_offset + len])?;
+
+            rel_offset += len;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use embedded_storage::nor_flash::NorFlash;
+
+    use crate::modules::persistent_storage::{
+        nor_flash::test::MockNorFlash,
+        test::{
+            test_persistent_storage_custom_type, test_persistent_storage_normal,
+            PERSISTENT_STORAGE_NORMAL_TEST_SIZE,
+        },
+        PersistentStorageModule,
+    };
+
+    use super::FlashTranslationStorageModule;
+
+    /// Erase size of `MockNorFlash`
+    const SECTOR_SIZE: usize = 256;
+
+    type TestModule = FlashTranslationStorageModule<MockNorFlash<1024>, 4, SECTOR_SIZE>;
+
+    #[test]
+    fn test_flash_translation_storage_module_normal() {
+        // every sector loses some bytes to its header
+        const SECTORS: usize = PERSISTENT_STORAGE_NORMAL_TEST_SIZE / SECTOR_SIZE + 2;
+
+        let storage = FlashTranslationStorageModule::<_, SECTORS, SECTOR_SIZE>::new(
+            MockNorFlash::<{ SECTORS * SECTOR_SIZE }>::new(),
+        )
+        .unwrap();
+        test_persistent_storage_normal(storage);
+    }
+
+    #[test]
+    fn test_flash_translation_storage_module_custom_type() {
+        let storage = FlashTranslationStorageModule::<_, 2, SECTOR_SIZE>::new(MockNorFlash::<512>::new()).unwrap();
+        test_persistent_storage_custom_type(storage);
+    }
+
+    /// Writes data to all logical sectors, so that some of them are moved
+    fn write_test_data(storage: &mut TestModule) {
+        // 12 bytes of each sector are used by its header
+        assert_eq!(storage.get_max_size(), 3 * 244);
+
+        // erased memory can be written directly
+        storage.write(3, &[1, 2, 3]).unwrap();
+        storage.write(100, &[4u8; 10]).unwrap();
+        assert_eq!(storage.get_erase_count(), 0);
+
+        // writing the same data again is skipped
+        storage.write(100, &[4u8; 10]).unwrap();
+        assert_eq!(storage.get_erase_count(), 0);
+
+        // overwriting moves the sector to the spare sector (which was erased by `new`)
+        storage.write(4, &[5]).unwrap();
+        assert_eq!(storage.get_erase_counts(), &[1, 0, 0, 0]);
+
+        // the old sector is the spare sector now and is already erased
+        storage.write(5, &[6]).unwrap();
+        assert_eq!(storage.get_erase_counts(), &[1, 0, 0, 1]);
+
+        // writes data to two sectors (in place), overwrites data of the second one afterwards
+        storage.write(250, &[7u8; 300]).unwrap();
+        storage.write(200, &[8u8; 100]).unwrap();
+        assert_eq!(storage.get_erase_counts(), &[1, 1, 0, 1]);
+    }
+
+    fn check_test_data(storage: &mut TestModule) {
+        let mut buffer = [0u8; 6];
+        storage.read(2, &mut buffer).unwrap();
+        assert_eq!(buffer, [0xFF, 1, 5, 6, 0xFF, 0xFF]);
+
+        let mut buffer = [0u8; 10];
+        storage.read(100, &mut buffer).unwrap();
+        assert_eq!(buffer, [4u8; 10]);
+
+        let mut buffer = [0u8; 352];
+        storage.read(199, &mut buffer).unwrap();
+        assert_eq!(buffer[0], 0xFF);
+        assert_eq!(buffer[1..101], [8u8; 100]);
+        assert_eq!(buffer[101..351], [7u8; 250]);
+        assert_eq!(buffer[351], 0xFF);
+    }
+
+    #[test]
+    fn test_flash_translation_storage_module_erase() {
+        let mut storage = TestModule::new(MockNorFlash::new()).unwrap();
+        write_test_data(&mut storage);
+        check_test_data(&mut storage);
+    }
+
+    #[test]
+    fn test_flash_translation_storage_module_restore() {
+        let mut storage = TestModule::new(MockNorFlash::new()).unwrap();
+        write_test_data(&mut storage);
+
+        // mapping is rebuilt from the sector headers
+        let mut storage = TestModule::new(storage.into_inner()).unwrap();
+        assert_eq!(storage.get_erase_count(), 0);
+        check_test_data(&mut storage);
+
+        // power failure while the new version of a sector was written to the (erased) spare sector 1
+        let mut flash = storage.into_inner();
+        flash.write((SECTOR_SIZE + 12) as u32, &[9u8; 8]).unwrap();
+
+        // the incomplete version is erased again and the old data stays intact
+        let mut storage = TestModule::new(flash).unwrap();
+        assert_eq!(storage.get_erase_counts(), &[0, 1, 0, 0]);
+        check_test_data(&mut storage);
+
+        storage.write(3, &[10]).unwrap();
+        let mut buffer = [0u8; 2];
+        storage.read(3, &mut buffer).unwrap();
+        assert_eq!(buffer, [10, 5]);
+    }
+}
@@ -0,0 +1,139 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::PersistentStorageModule;
+
+/// Region of a legacy storage layout, in which a struct is stored at a fixed offset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LegacyRegion {
+    pub offset: usize,
+    pub size: usize,
+}
+
+impl LegacyRegion {
+    pub const fn new(offset: usize, size: usize) -> Self {
+        Self { offset, size }
+    }
+
+    const fn end(&self) -> usize {
+        self.offset + self.size
+    }
+}
+
+/// Shares a storage device between a legacy layout and the heap while migrating to vNV-Heap.
+///
+/// The heap is placed behind all legacy regions, so legacy data is not overwritten
+/// until it is imported (see `VNVHeap::import_legacy`).
+/// Offsets passed to this module are relative to the start of the heap area.
+pub struct LegacyLayoutStorageModule<S: PersistentStorageModule> {
+    inner: S,
+    heap_offset: usize,
+}
+
+impl<S: PersistentStorageModule> LegacyLayoutStorageModule<S> {
+    /// Creates a new module for the given legacy regions.
+    ///
+    /// Panics if legacy regions overlap or if there is no space left for the heap.
+    pub fn new(storage: S, regions: &[LegacyRegion]) -> Self {
+        for (i, a) in regions.iter().enumerate() {
+            for b in regions[(i + 1)..].iter() {
+                assert!(
+                    a.end() <= b.offset || b.end() <= a.offset,
+                    "legacy regions {:?} and {:?} overlap",
+                    a,
+                    b
+                );
+            }
+        }
+
+        let heap_offset = regions.iter().map(LegacyRegion::end).max().unwrap_or(0);
+        assert!(heap_offset < storage.get_max_size(), "no space left for the heap");
+
+        Self {
+            inner: storage,
+            heap_offset,
+        }
+    }
+
+    /// Offset on the storage device at which the heap area starts
+    pub fn get_heap_offset(&self) -> usize {
+        self.heap_offset
+    }
+
+    /// Reads the raw data of a legacy region
+    pub fn read_legacy(&mut self, region: &LegacyRegion, dest: &mut [u8]) -> Result<(), ()> {
+        assert!(region.end() <= self.heap_offset, "region is not part of the legacy layout");
+        assert_eq!(dest.len(), region.size);
+
+        self.inner.read(region.offset, dest)
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: PersistentStorageModule> PersistentStorageModule for LegacyLayoutStorageModule<S> {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        self.inner.read(self.heap_offset + offset, dest)
+    }
+
+    fn get_max_size(&self) -> usize {
+        self.inner.get_max_size() - self.heap_offset
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        self.inner.write(self.heap_offset + offset, src)
+    }
+
+    fn forget_region(&mut self, offset: usize, size: usize) {
+        self.inner.forget_region(self.heap_offset + offset, size)
+    }
+
+    fn flush(&mut self) -> Result<(), ()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::modules::persistent_storage::{
+        test::{get_test_storage, test_persistent_storage_normal, PERSISTENT_STORAGE_NORMAL_TEST_SIZE},
+        LegacyLayoutStorageModule, LegacyRegion,
+    };
+
+    #[test]
+    fn test_legacy_layout_storage_module() {
+        let regions = [LegacyRegion::new(0, 16), LegacyRegion::new(32, 8)];
+        let storage = get_test_storage(
+            "test_legacy_layout_storage_module",
+            PERSISTENT_STORAGE_NORMAL_TEST_SIZE + 40,
+        );
+        let module = LegacyLayoutStorageModule::new(storage, &regions);
+        assert_eq!(module.get_heap_offset(), 40);
+
+        test_persistent_storage_normal(module);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_legacy_layout_storage_module_overlap() {
+        let regions = [LegacyRegion::new(0, 16), LegacyRegion::new(8, 8)];
+        let storage = get_test_storage("test_legacy_layout_storage_module_overlap", 64);
+        LegacyLayoutStorageModule::new(storage, &regions);
+    }
+}
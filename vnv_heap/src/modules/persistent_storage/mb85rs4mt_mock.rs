@@ -0,0 +1,180 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::{Duration, Instant};
+
+use super::PersistentStorageModule;
+
+/// Size of the MB85RS4MT FRAM (512KB)
+const MB85RS4MT_SIZE: usize = 524288;
+
+/// Command byte + 24 bit address
+const MB85RS4MT_ACCESS_HEADER_SIZE: usize = 4;
+
+/// Timing parameters used by `MB85RS4MTMockStorageModule` to emulate the latency of the SPI bus
+#[derive(Debug, Clone, Copy)]
+pub struct MB85RS4MTMockTiming {
+    /// Clock frequency of the emulated SPI bus in Hz
+    pub spi_clock_hz: u32,
+
+    /// Fixed cost of each SPI transaction (e.g. driver overhead, chip select)
+    pub transaction_overhead: Duration,
+
+    /// If set, each access blocks until the emulated time has passed.
+    /// Otherwise, the emulated time is only accumulated (see `get_emulated_time`).
+    pub busy_wait: bool,
+}
+
+impl Default for MB85RS4MTMockTiming {
+    /// Same SPI clock as `MB85RS4MTFramStorageModule` uses on the ESP32-C3
+    fn default() -> Self {
+        Self {
+            spi_clock_hz: 40_000_000,
+            transaction_overhead: Duration::from_micros(10),
+            busy_wait: false,
+        }
+    }
+}
+
+/// Desktop test double of `MB85RS4MTFramStorageModule`.
+///
+/// Stores its data in RAM and emulates the latency of the SPI transactions
+/// the real module would execute (a write consists of a write enable and a write transaction).
+pub struct MB85RS4MTMockStorageModule {
+    data: Vec<u8>,
+    timing: MB85RS4MTMockTiming,
+    emulated_time: Duration,
+    transaction_count: usize,
+}
+
+impl MB85RS4MTMockStorageModule {
+    /// Creates a new mock with default timing.
+    ///
+    /// This function is unsafe to keep the same interface as `MB85RS4MTFramStorageModule`.
+    /// Other than the real module, multiple instances of the mock can be created.
+    pub unsafe fn new() -> Result<Self, ()> {
+        Ok(Self::new_with_timing(MB85RS4MTMockTiming::default()))
+    }
+
+    pub fn new_with_timing(timing: MB85RS4MTMockTiming) -> Self {
+        assert!(timing.spi_clock_hz > 0, "spi clock has to be greater than zero");
+
+        Self {
+            data: vec![0u8; MB85RS4MT_SIZE],
+            timing,
+            emulated_time: Duration::ZERO,
+            transaction_count: 0,
+        }
+    }
+
+    /// Returns the total time that was spent on the emulated SPI bus
+    pub fn get_emulated_time(&self) -> Duration {
+        self.emulated_time
+    }
+
+    /// Returns the total number of emulated SPI transactions
+    pub fn get_transaction_count(&self) -> usize {
+        self.transaction_count
+    }
+
+    pub fn reset_statistics(&mut self) {
+        self.emulated_time = Duration::ZERO;
+        self.transaction_count = 0;
+    }
+
+    /// Emulates one SPI transaction that transfers `byte_count` bytes
+    fn transaction(&mut self, byte_count: usize) {
+        let transfer_ns = (byte_count as u64 * 8 * 1_000_000_000) / self.timing.spi_clock_hz as u64;
+        let duration = self.timing.transaction_overhead + Duration::from_nanos(transfer_ns);
+
+        if self.timing.busy_wait {
+            let start = Instant::now();
+            while start.elapsed() < duration {}
+        }
+
+        self.emulated_time += duration;
+        self.transaction_count += 1;
+    }
+}
+
+impl PersistentStorageModule for MB85RS4MTMockStorageModule {
+    fn read(&mut self, address: usize, buffer: &mut [u8]) -> Result<(), ()> {
+        debug_assert!(address + buffer.len() <= self.get_max_size());
+
+        self.transaction(MB85RS4MT_ACCESS_HEADER_SIZE + buffer.len());
+        buffer.copy_from_slice(&self.data[address..address + buffer.len()]);
+
+        Ok(())
+    }
+
+    fn write(&mut self, address: usize, buffer: &[u8]) -> Result<(), ()> {
+        debug_assert!(address + buffer.len() <= self.get_max_size());
+
+        // disable write protect
+        self.transaction(1);
+
+        self.transaction(MB85RS4MT_ACCESS_HEADER_SIZE + buffer.len());
+        self.data[address..address + buffer.len()].copy_from_slice(buffer);
+
+        Ok(())
+    }
+
+    fn get_max_size(&self) -> usize {
+        MB85RS4MT_SIZE
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::modules::persistent_storage::{
+        test::{test_persistent_storage_custom_type, test_persistent_storage_normal},
+        PersistentStorageModule, SlicedStorageModule,
+    };
+
+    use super::{MB85RS4MTMockStorageModule, MB85RS4MTMockTiming};
+
+    #[test]
+    fn test_mb85rs4mt_mock_normal() {
+        test_persistent_storage_normal(unsafe { MB85RS4MTMockStorageModule::new() }.unwrap());
+        test_persistent_storage_custom_type(unsafe { MB85RS4MTMockStorageModule::new() }.unwrap());
+        test_persistent_storage_normal(SlicedStorageModule::<64, _>::new(
+            unsafe { MB85RS4MTMockStorageModule::new() }.unwrap(),
+        ));
+    }
+
+    #[test]
+    fn test_mb85rs4mt_mock_timing() {
+        let mut storage = MB85RS4MTMockStorageModule::new_with_timing(MB85RS4MTMockTiming {
+            spi_clock_hz: 8_000_000,
+            transaction_overhead: Duration::from_micros(2),
+            busy_wait: false,
+        });
+
+        // 4 + 96 bytes with 1 byte per microsecond
+        storage.read(0, &mut [0u8; 96]).unwrap();
+        assert_eq!(storage.get_transaction_count(), 1);
+        assert_eq!(storage.get_emulated_time(), Duration::from_micros(2 + 100));
+
+        // write enable + write transaction
+        storage.reset_statistics();
+        storage.write(0, &[0u8; 96]).unwrap();
+        assert_eq!(storage.get_transaction_count(), 2);
+        assert_eq!(storage.get_emulated_time(), Duration::from_micros(2 + 1 + 2 + 100));
+    }
+}
@@ -0,0 +1,32 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::PersistentStorageModule;
+
+/// A storage module whose data is directly addressable (e.g. MRAM, FRAM on a parallel bus or a memory mapped file).
+///
+/// `VNVObject::get_mapped` uses this to read objects that are not resident directly from storage
+/// instead of copying them into the resident buffer.
+///
+/// ### Safety
+///
+/// The returned pointer has to stay valid as long as this module exists. Reading through it has to return
+/// the same data as `read`, and writes to other regions must not change the data it points to.
+pub unsafe trait MemoryMappedStorageModule: PersistentStorageModule {
+    /// Returns a pointer to the region `[offset, offset + len)` or `None` if this region is not mapped.
+    fn get_mapped_ptr(&self, offset: usize, len: usize) -> Option<*const u8>;
+}
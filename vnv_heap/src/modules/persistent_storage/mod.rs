@@ -18,12 +18,30 @@
 mod access_distribution;
 pub(crate) use access_distribution::*;
 
+mod async_storage;
+pub use async_storage::*;
+
+mod memory_mapped;
+pub use memory_mapped::*;
+
 #[cfg(not(no_std))]
 mod file_storage;
 
 #[cfg(not(no_std))]
 pub use file_storage::FilePersistentStorageModule;
 
+#[cfg(not(no_std))]
+mod mb85rs4mt_mock;
+
+#[cfg(not(no_std))]
+pub use mb85rs4mt_mock::{MB85RS4MTMockStorageModule, MB85RS4MTMockTiming};
+
+#[cfg(not(no_std))]
+mod delay_simulation;
+
+#[cfg(not(no_std))]
+pub use delay_simulation::{DelaySimulationStorageModule, DelaySimulationTiming};
+
 mod truncated;
 pub use truncated::*;
 
@@ -33,6 +51,54 @@ pub use sliced::*;
 mod dummy;
 pub use dummy::*;
 
+mod ram_storage;
+pub use ram_storage::RamStorageModule;
+
+mod verifying;
+pub use verifying::*;
+
+mod encrypted;
+pub use encrypted::*;
+
+mod xchacha20;
+pub use xchacha20::*;
+
+mod coalescing;
+pub use coalescing::*;
+
+mod cached;
+pub use cached::*;
+
+mod legacy_layout;
+pub use legacy_layout::*;
+
+mod partitioned;
+pub use partitioned::*;
+
+mod wear_leveling;
+pub use wear_leveling::*;
+
+mod block_device;
+pub use block_device::*;
+
+#[cfg(feature = "capi")]
+mod callback_storage;
+
+#[cfg(feature = "capi")]
+pub use callback_storage::{CallbackStorageModule, VNVStorageCallbacks};
+
+#[cfg(feature = "embedded_storage")]
+mod nor_flash;
+
+#[cfg(feature = "embedded_storage")]
+pub use nor_flash::NorFlashStorageModule;
+
+#[cfg(feature = "embedded_storage")]
+mod flash_translation;
+
+#[cfg(feature = "embedded_storage")]
+pub use flash_translation::FlashTranslationStorageModule;
+
 pub trait PersistentStorageModule {
     /// Reads a region `[offset, offset + dest.len())` to a storage location `dest` that is at least `dest.len()` bytes big.
     ///
@@ -52,6 +118,38 @@ pub trait PersistentStorageModule {
     ///
     /// (So you probably only want to overwrite this function if you are defining a cache)
     fn forget_region(&mut self, _offset: usize, _size: usize) {}
+
+    /// Writes back all data that was written to this module but not to the underlying storage yet.
+    ///
+    /// This is called while persisting the heap and can also be triggered with `VNVHeap::flush_storage`.
+    ///
+    /// (So you probably only want to overwrite this function if your module defers writes)
+    fn flush(&mut self) -> Result<(), ()> {
+        Ok(())
+    }
+}
+
+/// Allows wrapping a storage module without taking ownership of it (e.g. `CoalescingStorageModule::new(&mut storage)`)
+impl<S: PersistentStorageModule + ?Sized> PersistentStorageModule for &mut S {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        (**self).read(offset, dest)
+    }
+
+    fn get_max_size(&self) -> usize {
+        (**self).get_max_size()
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        (**self).write(offset, src)
+    }
+
+    fn forget_region(&mut self, offset: usize, size: usize) {
+        (**self).forget_region(offset, size)
+    }
+
+    fn flush(&mut self) -> Result<(), ()> {
+        (**self).flush()
+    }
 }
 
 pub(crate) mod persistent_storage_util {
@@ -82,6 +180,20 @@ pub(crate) mod persistent_storage_util {
 
         Ok(res.assume_init())
     }
+
+    /// Overwrites `len` bytes starting at `offset` with zeros
+    #[cfg(feature = "deterministic_layout")]
+    pub(crate) fn write_zeros<P: PersistentStorageModule>(storage: &mut P, offset: usize, len: usize) -> Result<(), ()> {
+        let buffer = [0u8; 256];
+        let mut pos = 0;
+        while pos < len {
+            let chunk = core::cmp::min(buffer.len(), len - pos);
+            storage.write(offset + pos, &buffer[..chunk])?;
+            pos += chunk;
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,290 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use embedded_storage::nor_flash::NorFlash;
+
+use super::PersistentStorageModule;
+
+/// Byte value of erased NOR flash
+const ERASED_BYTE: u8 = 0xFF;
+
+/// Makes any NOR flash driver that implements the `embedded-storage` traits usable as `PersistentStorageModule`.
+///
+/// NOR flash can only be written once after it was erased. This is why writes are handled as follows:
+/// If the (aligned) region is still erased, the data is written directly.
+/// Otherwise, every affected erase sector is read into a RAM buffer, merged with the new data, erased and written back.
+/// Sectors where nothing changes are skipped to reduce wear.
+///
+/// `BUFFER_SIZE` has to be at least `F::ERASE_SIZE` bytes big.
+///
+/// **Note:** Erasing is not atomic. A power failure during a write can lose data of the whole sector.
+/// Also, a single dirty byte can cost a whole sector erase while persisting,
+/// so derive `max_dirty_bytes` from the erase time of your flash rather than from its write throughput.
+pub struct NorFlashStorageModule<F: NorFlash, const BUFFER_SIZE: usize = 4096> {
+    flash: F,
+    buffer: [u8; BUFFER_SIZE],
+    erase_count: usize,
+}
+
+impl<F: NorFlash, const BUFFER_SIZE: usize> NorFlashStorageModule<F, BUFFER_SIZE> {
+    pub fn new(flash: F) -> Self {
+        assert!(
+            F::ERASE_SIZE <= BUFFER_SIZE,
+            "buffer has to be at least as big as an erase sector"
+        );
+        assert!(
+            F::ERASE_SIZE % F::WRITE_SIZE == 0 && F::ERASE_SIZE % F::READ_SIZE == 0,
+            "erase sectors have to be aligned to read and write sizes"
+        );
+
+        Self {
+            flash,
+            buffer: [0u8; BUFFER_SIZE],
+            erase_count: 0,
+        }
+    }
+
+    /// Returns how many sectors were erased so far
+    pub fn get_erase_count(&self) -> usize {
+        self.erase_count
+    }
+
+    pub fn get_inner(&self) -> &F {
+        &self.flash
+    }
+
+    pub fn into_inner(self) -> F {
+        self.flash
+    }
+
+    /// Writes `src` to `offset`, which has to be inside of the erase sector that starts at `sector_start`
+    fn write_sector(&mut self, sector_start: usize, offset: usize, src: &[u8]) -> Result<(), ()> {
+        let sector = &mut self.buffer[..F::ERASE_SIZE];
+        self.flash
+            .read(sector_start as u32, sector)
+            .map_err(|_| ())?;
+
+        let rel_offset = offset - sector_start;
+        if sector[rel_offset..rel_offset + src.len()] == *src {
+            // nothing changes
+            return Ok(());
+        }
+
+        // region that has to be programmed, aligned to the write size
+        let write_start = rel_offset - (rel_offset % F::WRITE_SIZE);
+        let write_end = {
+            let end = rel_offset + src.len();
+            (end + F::WRITE_SIZE - 1) - ((end + F::WRITE_SIZE - 1) % F::WRITE_SIZE)
+        };
+
+        let is_erased = sector[write_start..write_end]
+            .iter()
+            .all(|byte| *byte == ERASED_BYTE);
+
+        sector[rel_offset..rel_offset + src.len()].copy_from_slice(src);
+
+        if is_erased {
+            // can be written without erasing the sector first
+            return self
+                .flash
+                .write((sector_start + write_start) as u32, &sector[write_start..write_end])
+                .map_err(|_| ());
+        }
+
+        self.flash
+            .erase(sector_start as u32, (sector_start + F::ERASE_SIZE) as u32)
+            .map_err(|_| ())?;
+        self.erase_count += 1;
+
+        self.flash
+            .write(sector_start as u32, sector)
+            .map_err(|_| ())
+    }
+}
+
+impl<F: NorFlash> From<F> for NorFlashStorageModule<F> {
+    fn from(flash: F) -> Self {
+        Self::new(flash)
+    }
+}
+
+impl<F: NorFlash, const BUFFER_SIZE: usize> PersistentStorageModule for NorFlashStorageModule<F, BUFFER_SIZE> {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        if offset % F::READ_SIZE == 0 && dest.len() % F::READ_SIZE == 0 {
+            return self.flash.read(offset as u32, dest).map_err(|_| ());
+        }
+
+        // unaligned read: read whole sectors and copy the requested part
+        let mut rel_offset = 0;
+        while rel_offset < dest.len() {
+            let curr_offset = offset + rel_offset;
+            let sector_start = curr_offset - (curr_offset % F::ERASE_SIZE);
+            let start = curr_offset - sector_start;
+            let len = (F::ERASE_SIZE - start).min(dest.len() - rel_offset);
+
+            let sector = &mut self.buffer[..F::ERASE_SIZE];
+            self.flash
+                .read(sector_start as u32, sector)
+                .map_err(|_| ())?;
+            dest[rel_offset..rel_offset + len].copy_from_slice(&sector[start..start + len]);
+
+            rel_offset += len;
+        }
+
+        Ok(())
+    }
+
+    fn get_max_size(&self) -> usize {
+        self.flash.capacity()
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        let mut rel_offset = 0;
+        while rel_offset < src.len() {
+            let curr_offset = offset + rel_offset;
+            let sector_start = curr_offset - (curr_offset % F::ERASE_SIZE);
+            let len = (F::ERASE_SIZE - (curr_offset - sector_start)).min(src.len() - rel_offset);
+
+            self.write_sector(sector_start, curr_offset, &src[rel_offset..rel_offset + len])?;
+
+            rel_offset += len;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(super) mod test {
+    use embedded_storage::nor_flash::{ErrorType, NorFlash, NorFlashErrorKind, ReadNorFlash};
+
+    use crate::modules::persistent_storage::{
+        test::{
+            test_persistent_storage_custom_type, test_persistent_storage_normal,
+            PERSISTENT_STORAGE_NORMAL_TEST_SIZE,
+        },
+        PersistentStorageModule,
+    };
+
+    use super::NorFlashStorageModule;
+
+    /// NOR flash in RAM that only allows writing to erased words
+    pub(crate) struct MockNorFlash<const SIZE: usize> {
+        data: Vec<u8>,
+    }
+
+    impl<const SIZE: usize> MockNorFlash<SIZE> {
+        pub(crate) fn new() -> Self {
+            Self {
+                data: vec![0xFF; SIZE],
+            }
+        }
+    }
+
+    impl<const SIZE: usize> ErrorType for MockNorFlash<SIZE> {
+        type Error = NorFlashErrorKind;
+    }
+
+    impl<const SIZE: usize> ReadNorFlash for MockNorFlash<SIZE> {
+        const READ_SIZE: usize = 1;
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.data[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            SIZE
+        }
+    }
+
+    impl<const SIZE: usize> NorFlash for MockNorFlash<SIZE> {
+        const WRITE_SIZE: usize = 4;
+        const ERASE_SIZE: usize = 256;
+
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+            embedded_storage::nor_flash::check_erase(self, from, to)?;
+            self.data[from as usize..to as usize].fill(0xFF);
+            Ok(())
+        }
+
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+            embedded_storage::nor_flash::check_write(self, offset, bytes.len())?;
+
+            let offset = offset as usize;
+            for (i, byte) in bytes.iter().enumerate() {
+                assert_eq!(self.data[offset + i], 0xFF, "writing to a region that was not erased");
+                self.data[offset + i] = *byte;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_nor_flash_storage_module_normal() {
+        let storage: NorFlashStorageModule<_> = MockNorFlash::<PERSISTENT_STORAGE_NORMAL_TEST_SIZE>::new().into();
+        test_persistent_storage_normal(storage);
+    }
+
+    #[test]
+    fn test_nor_flash_storage_module_custom_type() {
+        let storage: NorFlashStorageModule<_> = MockNorFlash::<256>::new().into();
+        test_persistent_storage_custom_type(storage);
+    }
+
+    #[test]
+    fn test_nor_flash_storage_module_erase() {
+        let mut storage = NorFlashStorageModule::<_, 256>::new(MockNorFlash::<1024>::new());
+
+        // erased memory can be written directly
+        storage.write(3, &[1, 2, 3]).unwrap();
+        storage.write(100, &[4u8; 10]).unwrap();
+        assert_eq!(storage.get_erase_count(), 0);
+
+        // writing the same data again is skipped
+        storage.write(100, &[4u8; 10]).unwrap();
+        assert_eq!(storage.get_erase_count(), 0);
+
+        // overwriting needs an erase
+        storage.write(4, &[5]).unwrap();
+        assert_eq!(storage.get_erase_count(), 1);
+
+        // spans multiple sectors that are still erased in that region
+        storage.write(250, &[6u8; 300]).unwrap();
+        assert_eq!(storage.get_erase_count(), 1);
+
+        // overwrites data in two sectors
+        storage.write(200, &[7u8; 100]).unwrap();
+        assert_eq!(storage.get_erase_count(), 3);
+
+        let mut buffer = [0u8; 6];
+        storage.read(2, &mut buffer).unwrap();
+        assert_eq!(buffer, [0xFF, 1, 5, 3, 0xFF, 0xFF]);
+
+        let mut buffer = [0u8; 10];
+        storage.read(100, &mut buffer).unwrap();
+        assert_eq!(buffer, [4u8; 10]);
+
+        let mut buffer = [0u8; 352];
+        storage.read(199, &mut buffer).unwrap();
+        assert_eq!(buffer[0], 0xFF);
+        assert_eq!(buffer[1..101], [7u8; 100]);
+        assert_eq!(buffer[101..351], [6u8; 250]);
+        assert_eq!(buffer[351], 0xFF);
+    }
+}
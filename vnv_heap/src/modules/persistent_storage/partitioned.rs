@@ -0,0 +1,153 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::ops::Range;
+
+use super::PersistentStorageModule;
+
+/// Lets the heap manage only the region `[start, end)` of a storage device.
+///
+/// The remaining regions are reserved for the application (e.g. for firmware update slots)
+/// and can be accessed with `VNVHeap::storage` while the heap is in use.
+/// Offsets passed to the `PersistentStorageModule` functions are relative to `start`.
+pub struct PartitionedStorageModule<S: PersistentStorageModule> {
+    inner: S,
+    heap_region: Range<usize>,
+}
+
+impl<S: PersistentStorageModule> PartitionedStorageModule<S> {
+    pub fn new(storage: S, heap_region: Range<usize>) -> Self {
+        assert!(heap_region.start < heap_region.end, "heap region is empty");
+        assert!(
+            heap_region.end <= storage.get_max_size(),
+            "heap region exceeds the storage"
+        );
+
+        Self {
+            inner: storage,
+            heap_region,
+        }
+    }
+
+    /// Region of the storage device that is managed by the heap
+    pub fn get_heap_region(&self) -> Range<usize> {
+        self.heap_region.clone()
+    }
+
+    /// Returns whether `[offset, offset + len)` is part of the storage device, but not of the heap region
+    pub fn is_reserved(&self, offset: usize, len: usize) -> bool {
+        let end = match offset.checked_add(len) {
+            Some(end) => end,
+            None => return false,
+        };
+
+        end <= self.inner.get_max_size() && (end <= self.heap_region.start || offset >= self.heap_region.end)
+    }
+
+    /// Reads from a reserved region. `offset` is the offset on the storage device.
+    ///
+    /// Panics if the region is not reserved.
+    pub fn read_reserved(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        assert!(self.is_reserved(offset, dest.len()), "region is not reserved");
+        self.inner.read(offset, dest)
+    }
+
+    /// Writes to a reserved region. `offset` is the offset on the storage device.
+    ///
+    /// Panics if the region is not reserved.
+    pub fn write_reserved(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        assert!(self.is_reserved(offset, src.len()), "region is not reserved");
+        self.inner.write(offset, src)
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: PersistentStorageModule> PersistentStorageModule for PartitionedStorageModule<S> {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        debug_assert!(offset + dest.len() <= self.get_max_size());
+        self.inner.read(self.heap_region.start + offset, dest)
+    }
+
+    fn get_max_size(&self) -> usize {
+        self.heap_region.end - self.heap_region.start
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        debug_assert!(offset + src.len() <= self.get_max_size());
+        self.inner.write(self.heap_region.start + offset, src)
+    }
+
+    fn forget_region(&mut self, offset: usize, size: usize) {
+        self.inner.forget_region(self.heap_region.start + offset, size)
+    }
+
+    fn flush(&mut self) -> Result<(), ()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::modules::persistent_storage::{
+        test::{get_test_storage, test_persistent_storage_normal, PERSISTENT_STORAGE_NORMAL_TEST_SIZE},
+        PartitionedStorageModule, PersistentStorageModule,
+    };
+
+    #[test]
+    fn test_partitioned_storage_module() {
+        let storage = get_test_storage(
+            "test_partitioned_storage_module",
+            PERSISTENT_STORAGE_NORMAL_TEST_SIZE + 200,
+        );
+        test_persistent_storage_normal(PartitionedStorageModule::new(
+            storage,
+            100..(PERSISTENT_STORAGE_NORMAL_TEST_SIZE + 100),
+        ));
+    }
+
+    #[test]
+    fn test_partitioned_storage_module_reserved() {
+        let storage = get_test_storage("test_partitioned_storage_module_reserved", 300);
+        let mut module = PartitionedStorageModule::new(storage, 100..200);
+        assert_eq!(module.get_max_size(), 100);
+
+        assert!(module.is_reserved(0, 100));
+        assert!(module.is_reserved(200, 100));
+        assert!(!module.is_reserved(50, 51));
+        assert!(!module.is_reserved(150, 51));
+        assert!(!module.is_reserved(250, 51));
+
+        module.write_reserved(0, &[1u8; 100]).unwrap();
+        module.write(0, &[2u8; 100]).unwrap();
+        module.write_reserved(200, &[3u8; 100]).unwrap();
+
+        let mut buffer = [0u8; 100];
+        module.read_reserved(0, &mut buffer).unwrap();
+        assert_eq!(buffer, [1u8; 100]);
+        module.read(0, &mut buffer).unwrap();
+        assert_eq!(buffer, [2u8; 100]);
+        module.read_reserved(200, &mut buffer).unwrap();
+        assert_eq!(buffer, [3u8; 100]);
+
+        let mut storage = module.into_inner();
+        storage.read(100, &mut buffer).unwrap();
+        assert_eq!(buffer, [2u8; 100]);
+    }
+}
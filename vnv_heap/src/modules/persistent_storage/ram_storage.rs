@@ -0,0 +1,140 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::{AsyncPersistentStorageModule, MemoryMappedStorageModule, PersistentStorageModule};
+
+/// Storage module that keeps all data in a RAM buffer.
+///
+/// Data is lost on power failure, so this is only useful for testing (e.g. unit tests, fuzzing, self-tests on embedded devices
+/// or under Miri where no file system is available).
+///
+/// The buffer can be a `Vec<u8>` (see `new`) or, without an allocator, a borrowed slice or an array (see `from_buffer`).
+#[cfg(not(no_std))]
+pub struct RamStorageModule<B: AsRef<[u8]> + AsMut<[u8]> = Vec<u8>> {
+    buffer: B,
+}
+
+#[cfg(no_std)]
+pub struct RamStorageModule<B: AsRef<[u8]> + AsMut<[u8]>> {
+    buffer: B,
+}
+
+#[cfg(not(no_std))]
+impl RamStorageModule {
+    /// Creates a new zero initialized storage of `size` bytes
+    pub fn new(size: usize) -> Self {
+        Self {
+            buffer: vec![0u8; size],
+        }
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> RamStorageModule<B> {
+    /// Uses `buffer` as storage (e.g. a `&'static mut [u8]`), its current content is kept
+    pub fn from_buffer(buffer: B) -> Self {
+        Self { buffer }
+    }
+
+    /// Returns the current content of the storage
+    pub fn get_buffer(&self) -> &[u8] {
+        self.buffer.as_ref()
+    }
+
+    /// Returns the buffer that is used as storage
+    pub fn into_inner(self) -> B {
+        self.buffer
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> PersistentStorageModule for RamStorageModule<B> {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        let src = self.buffer.as_ref().get(offset..offset + dest.len()).ok_or(())?;
+        dest.copy_from_slice(src);
+        Ok(())
+    }
+
+    fn get_max_size(&self) -> usize {
+        self.buffer.as_ref().len()
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        let dest = self.buffer.as_mut().get_mut(offset..offset + src.len()).ok_or(())?;
+        dest.copy_from_slice(src);
+        Ok(())
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> AsyncPersistentStorageModule for RamStorageModule<B> {}
+
+// the buffer is never reallocated
+#[cfg(not(no_std))]
+unsafe impl MemoryMappedStorageModule for RamStorageModule<Vec<u8>> {
+    fn get_mapped_ptr(&self, offset: usize, len: usize) -> Option<*const u8> {
+        self.buffer.get(offset..offset.checked_add(len)?).map(|region| region.as_ptr())
+    }
+}
+
+// the borrowed buffer cannot move
+unsafe impl MemoryMappedStorageModule for RamStorageModule<&mut [u8]> {
+    fn get_mapped_ptr(&self, offset: usize, len: usize) -> Option<*const u8> {
+        self.buffer.get(offset..offset.checked_add(len)?).map(|region| region.as_ptr())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::modules::persistent_storage::{
+        test::{test_persistent_storage_normal, PERSISTENT_STORAGE_NORMAL_TEST_SIZE},
+        PersistentStorageModule,
+    };
+
+    use super::RamStorageModule;
+
+    #[test]
+    fn test_ram_storage_module_normal() {
+        test_persistent_storage_normal(RamStorageModule::new(PERSISTENT_STORAGE_NORMAL_TEST_SIZE));
+    }
+
+    #[test]
+    fn test_ram_storage_module_out_of_bounds() {
+        let mut storage = RamStorageModule::new(16);
+
+        assert!(storage.write(10, &[1u8; 10]).is_err());
+        assert!(storage.read(16, &mut [0u8; 1]).is_err());
+        assert!(storage.write(6, &[1u8; 10]).is_ok());
+    }
+
+    #[test]
+    fn test_ram_storage_module_borrowed_buffer() {
+        let mut buffer = [0u8; PERSISTENT_STORAGE_NORMAL_TEST_SIZE];
+        test_persistent_storage_normal(RamStorageModule::from_buffer(&mut buffer[..]));
+
+        // the data stays in the buffer
+        assert!(buffer.iter().any(|byte| *byte != 0));
+    }
+
+    #[test]
+    fn test_ram_storage_module_array() {
+        let mut storage = RamStorageModule::from_buffer([0u8; 16]);
+        assert_eq!(storage.get_max_size(), 16);
+
+        storage.write(4, &[1, 2, 3]).unwrap();
+        assert!(storage.write(14, &[1, 2, 3]).is_err());
+        assert_eq!(storage.get_buffer()[3..8], [0, 1, 2, 3, 0]);
+        assert_eq!(storage.into_inner()[4], 1);
+    }
+}
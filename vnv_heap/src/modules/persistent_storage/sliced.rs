@@ -17,6 +17,9 @@
 
 use super::PersistentStorageModule;
 
+/// Splits every read and write into accesses of at most `SLICE_SIZE` bytes.
+///
+/// If the slice size should be chosen at runtime, use `RuntimeSlicedStorageModule` instead.
 pub struct SlicedStorageModule<const SLICE_SIZE: usize, S: PersistentStorageModule> {
     inner: S,
 }
@@ -31,15 +34,53 @@ impl<const SLICE_SIZE: usize, S: PersistentStorageModule> SlicedStorageModule<SL
 
 impl<const SLICE_SIZE: usize, S: PersistentStorageModule> PersistentStorageModule for SlicedStorageModule<SLICE_SIZE, S> {
     fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
-        let mut rel_offset = 0;
-        while rel_offset < dest.len() {
-            let end_read = (rel_offset + SLICE_SIZE).min(dest.len());
-            self.inner.read(offset + rel_offset, &mut dest[rel_offset..end_read])?;
+        sliced_read(&mut self.inner, SLICE_SIZE, offset, dest)
+    }
+
+    fn get_max_size(&self) -> usize {
+        self.inner.get_max_size()
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        sliced_write(&mut self.inner, SLICE_SIZE, offset, src)
+    }
+
+    fn flush(&mut self) -> Result<(), ()> {
+        self.inner.flush()
+    }
+}
+
+/// Same as `SlicedStorageModule`, but the slice size is chosen at runtime.
+///
+/// This is useful to explore different slice sizes without compiling one binary per slice size.
+pub struct RuntimeSlicedStorageModule<S: PersistentStorageModule> {
+    inner: S,
+    slice_size: usize,
+}
+
+impl<S: PersistentStorageModule> RuntimeSlicedStorageModule<S> {
+    pub fn new(storage: S, slice_size: usize) -> Self {
+        assert!(slice_size > 0, "slice size has to be greater than zero");
 
-            rel_offset += SLICE_SIZE;
+        Self {
+            inner: storage,
+            slice_size,
         }
-        
-        Ok(())
+    }
+
+    pub fn get_slice_size(&self) -> usize {
+        self.slice_size
+    }
+
+    pub fn set_slice_size(&mut self, slice_size: usize) {
+        assert!(slice_size > 0, "slice size has to be greater than zero");
+        self.slice_size = slice_size;
+    }
+}
+
+impl<S: PersistentStorageModule> PersistentStorageModule for RuntimeSlicedStorageModule<S> {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        sliced_read(&mut self.inner, self.slice_size, offset, dest)
     }
 
     fn get_max_size(&self) -> usize {
@@ -47,13 +88,57 @@ impl<const SLICE_SIZE: usize, S: PersistentStorageModule> PersistentStorageModul
     }
 
     fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
-        let mut rel_offset = 0;
-        while rel_offset < src.len() {
-            let end_read = (rel_offset + SLICE_SIZE).min(src.len());
-            self.inner.write(offset + rel_offset, &src[rel_offset..end_read])?;
+        sliced_write(&mut self.inner, self.slice_size, offset, src)
+    }
+
+    fn flush(&mut self) -> Result<(), ()> {
+        self.inner.flush()
+    }
+}
+
+#[inline]
+fn sliced_read<S: PersistentStorageModule>(inner: &mut S, slice_size: usize, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+    let mut rel_offset = 0;
+    while rel_offset < dest.len() {
+        let end_read = (rel_offset + slice_size).min(dest.len());
+        inner.read(offset + rel_offset, &mut dest[rel_offset..end_read])?;
+
+        rel_offset += slice_size;
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn sliced_write<S: PersistentStorageModule>(inner: &mut S, slice_size: usize, offset: usize, src: &[u8]) -> Result<(), ()> {
+    let mut rel_offset = 0;
+    while rel_offset < src.len() {
+        let end_read = (rel_offset + slice_size).min(src.len());
+        inner.write(offset + rel_offset, &src[rel_offset..end_read])?;
+
+        rel_offset += slice_size;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::modules::persistent_storage::{
+        test::{get_test_storage, test_persistent_storage_normal, PERSISTENT_STORAGE_NORMAL_TEST_SIZE},
+        RuntimeSlicedStorageModule, SlicedStorageModule,
+    };
+
+    #[test]
+    fn test_sliced_storage_module() {
+        let storage = get_test_storage("test_sliced_storage_module", PERSISTENT_STORAGE_NORMAL_TEST_SIZE);
+        test_persistent_storage_normal(SlicedStorageModule::<7, _>::new(storage));
+    }
 
-            rel_offset += SLICE_SIZE;
+    #[test]
+    fn test_runtime_sliced_storage_module() {
+        for slice_size in [1, 7, 64, PERSISTENT_STORAGE_NORMAL_TEST_SIZE] {
+            let storage = get_test_storage("test_runtime_sliced_storage_module", PERSISTENT_STORAGE_NORMAL_TEST_SIZE);
+            test_persistent_storage_normal(RuntimeSlicedStorageModule::new(storage, slice_size));
         }
-        Ok(())
     }
 }
@@ -45,4 +45,8 @@ impl<const SIZE: usize, S: PersistentStorageModule> PersistentStorageModule for
         debug_assert!(offset + src.len() <= SIZE);
         self.inner.write(offset, src)
     }
+
+    fn flush(&mut self) -> Result<(), ()> {
+        self.inner.flush()
+    }
 }
@@ -0,0 +1,177 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::PersistentStorageModule;
+
+/// Size of the stack buffer that is used to read back written data
+const VERIFY_BUFFER_SIZE: usize = 64;
+
+/// Reads back every written region and compares it with the data that should have been written.
+///
+/// If the data does not match, `write` returns an error and the mismatch counter is increased.
+/// Note that this doubles the amount of storage accesses.
+pub struct VerifyingStorageModule<S: PersistentStorageModule> {
+    inner: S,
+    verified_write_count: usize,
+    mismatch_count: usize,
+}
+
+impl<S: PersistentStorageModule> VerifyingStorageModule<S> {
+    pub fn new(storage: S) -> Self {
+        Self {
+            inner: storage,
+            verified_write_count: 0,
+            mismatch_count: 0,
+        }
+    }
+
+    /// Returns how many writes were successfully verified
+    pub fn get_verified_write_count(&self) -> usize {
+        self.verified_write_count
+    }
+
+    /// Returns how many writes did not match the data that was read back
+    pub fn get_mismatch_count(&self) -> usize {
+        self.mismatch_count
+    }
+
+    pub fn reset_counters(&mut self) {
+        self.verified_write_count = 0;
+        self.mismatch_count = 0;
+    }
+
+    pub fn get_inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn verify(&mut self, offset: usize, src: &[u8]) -> Result<bool, ()> {
+        let mut buffer = [0u8; VERIFY_BUFFER_SIZE];
+
+        let mut rel_offset = 0;
+        while rel_offset < src.len() {
+            let len = VERIFY_BUFFER_SIZE.min(src.len() - rel_offset);
+            self.inner.read(offset + rel_offset, &mut buffer[..len])?;
+
+            if buffer[..len] != src[rel_offset..rel_offset + len] {
+                return Ok(false);
+            }
+
+            rel_offset += len;
+        }
+
+        Ok(true)
+    }
+}
+
+impl<S: PersistentStorageModule> PersistentStorageModule for VerifyingStorageModule<S> {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        self.inner.read(offset, dest)
+    }
+
+    fn get_max_size(&self) -> usize {
+        self.inner.get_max_size()
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        self.inner.write(offset, src)?;
+
+        if !self.verify(offset, src)? {
+            self.mismatch_count += 1;
+            return Err(());
+        }
+
+        self.verified_write_count += 1;
+        Ok(())
+    }
+
+    fn forget_region(&mut self, offset: usize, size: usize) {
+        self.inner.forget_region(offset, size)
+    }
+
+    fn flush(&mut self) -> Result<(), ()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::modules::persistent_storage::{
+        test::{get_test_storage, test_persistent_storage_normal, PERSISTENT_STORAGE_NORMAL_TEST_SIZE},
+        FilePersistentStorageModule, PersistentStorageModule,
+    };
+
+    use super::VerifyingStorageModule;
+
+    /// Flips one bit of every write that covers `broken_offset`
+    struct BrokenStorageModule {
+        inner: FilePersistentStorageModule,
+        broken_offset: usize,
+    }
+
+    impl PersistentStorageModule for BrokenStorageModule {
+        fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+            self.inner.read(offset, dest)
+        }
+
+        fn get_max_size(&self) -> usize {
+            self.inner.get_max_size()
+        }
+
+        fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+            self.inner.write(offset, src)?;
+
+            if offset <= self.broken_offset && self.broken_offset < offset + src.len() {
+                let mut byte = [0u8];
+                self.inner.read(self.broken_offset, &mut byte)?;
+                byte[0] ^= 1;
+                self.inner.write(self.broken_offset, &byte)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_verifying_storage_module_normal() {
+        let storage = get_test_storage("test_verifying_storage_module_normal", PERSISTENT_STORAGE_NORMAL_TEST_SIZE);
+        test_persistent_storage_normal(VerifyingStorageModule::new(storage));
+    }
+
+    #[test]
+    fn test_verifying_storage_module_mismatch() {
+        let mut storage = VerifyingStorageModule::new(BrokenStorageModule {
+            inner: get_test_storage("test_verifying_storage_module_mismatch", 1024),
+            broken_offset: 300,
+        });
+
+        storage.write(0, &[1u8; 200]).unwrap();
+        assert_eq!(storage.get_verified_write_count(), 1);
+        assert_eq!(storage.get_mismatch_count(), 0);
+
+        assert!(storage.write(200, &[2u8; 200]).is_err());
+        assert_eq!(storage.get_verified_write_count(), 1);
+        assert_eq!(storage.get_mismatch_count(), 1);
+
+        storage.write(400, &[3u8; 200]).unwrap();
+        assert_eq!(storage.get_verified_write_count(), 2);
+        assert_eq!(storage.get_mismatch_count(), 1);
+    }
+}
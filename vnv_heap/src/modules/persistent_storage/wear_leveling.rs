@@ -0,0 +1,488 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::mem::size_of;
+
+use crate::util::crc32_update;
+
+use super::PersistentStorageModule;
+
+/// Size of the buffer that is used to copy blocks
+const COPY_BUFFER_SIZE: usize = 64;
+
+/// Number of metadata slots, which are written alternately so that one valid copy always remains
+const METADATA_SLOT_COUNT: usize = 2;
+
+/// Spreads the wear of writes over all blocks of the underlying storage (e.g. NOR flash).
+///
+/// The storage is divided into `BLOCK_COUNT` physical blocks of `BLOCK_SIZE` bytes (this should be the erase sector size).
+/// One of these blocks is kept as spare, so `(BLOCK_COUNT - 1) * BLOCK_SIZE` bytes can be used.
+/// Logical blocks are mapped to physical blocks and every write increases the erase count of its physical block
+/// (as every write could require an erase in the worst case).
+///
+/// If a block was erased `threshold` times more often than the least worn block, their data is swapped (using the spare block).
+/// The worn block becomes the new spare block, so hot regions (e.g. metadata of the nonresident allocator)
+/// are rotated through all blocks instead of burning out a single sector.
+///
+/// The mapping and the erase counts are stored behind the blocks (see `required_storage_size`) and are loaded again by `new`.
+/// They are written alternately to two slots with a sequence number and a CRC-32, so an interrupted write falls back
+/// to the previous version. Every step of a relocation is stored before the old block is overwritten,
+/// so the data stays intact if the power fails in between. Erase counts that changed without a relocation
+/// are stored on `flush` and are lost (i.e. counted too low) if the power fails before that.
+///
+/// **Note:** A write that triggers a relocation copies up to two blocks and stores the mapping twice.
+/// So a single small write during persisting can take as long as writing `2 * BLOCK_SIZE` bytes.
+pub struct WearLevelingStorageModule<const BLOCK_SIZE: usize, const BLOCK_COUNT: usize, S: PersistentStorageModule> {
+    inner: S,
+
+    /// Physical block of each logical block (the last entry is the spare block)
+    mapping: [usize; BLOCK_COUNT],
+
+    /// Erase count of each physical block
+    erase_counts: [u32; BLOCK_COUNT],
+
+    /// Whether `erase_counts` changed since the metadata was stored the last time
+    erase_counts_dirty: bool,
+
+    /// Sequence number of the last stored metadata
+    sequence: u64,
+
+    threshold: u32,
+    relocation_count: usize,
+}
+
+impl<const BLOCK_SIZE: usize, const BLOCK_COUNT: usize, S: PersistentStorageModule>
+    WearLevelingStorageModule<BLOCK_SIZE, BLOCK_COUNT, S>
+{
+    /// Size of one metadata slot: sequence number, mapping, erase counts and CRC-32
+    const METADATA_SIZE: usize = size_of::<u64>() + BLOCK_COUNT * 2 * size_of::<u32>() + size_of::<u32>();
+
+    /// Minimum size of the underlying storage: all blocks followed by the metadata slots
+    pub const fn required_storage_size() -> usize {
+        BLOCK_SIZE * BLOCK_COUNT + METADATA_SLOT_COUNT * Self::METADATA_SIZE
+    }
+
+    /// Creates a new module and loads the mapping and erase counts that were stored on `storage` before.
+    ///
+    /// If no valid metadata is found (e.g. on first use), every logical block is mapped to the physical block with the same index.
+    pub fn new(storage: S, threshold: u32) -> Result<Self, ()> {
+        assert!(BLOCK_SIZE > 0, "blocks have to be at least one byte big");
+        assert!(BLOCK_COUNT >= 2, "at least one block and one spare block are required");
+        assert!(threshold > 0, "threshold has to be greater than zero");
+        assert!(
+            storage.get_max_size() >= Self::required_storage_size(),
+            "storage is too small for the given blocks"
+        );
+
+        let mut module = Self {
+            inner: storage,
+            mapping: core::array::from_fn(|i| i),
+            erase_counts: [0; BLOCK_COUNT],
+            erase_counts_dirty: false,
+            sequence: 0,
+            threshold,
+            relocation_count: 0,
+        };
+
+        for slot in 0..METADATA_SLOT_COUNT {
+            if let Some((sequence, mapping, erase_counts)) = module.read_metadata(slot)? {
+                if sequence > module.sequence {
+                    module.sequence = sequence;
+                    module.mapping = mapping;
+                    module.erase_counts = erase_counts;
+                }
+            }
+        }
+
+        Ok(module)
+    }
+
+    /// Returns the erase count of each physical block
+    pub fn get_erase_counts(&self) -> &[u32; BLOCK_COUNT] {
+        &self.erase_counts
+    }
+
+    /// Returns how often blocks were relocated so far (since this module was created)
+    pub fn get_relocation_count(&self) -> usize {
+        self.relocation_count
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    #[inline]
+    fn spare_block(&self) -> usize {
+        self.mapping[BLOCK_COUNT - 1]
+    }
+
+    #[inline]
+    fn metadata_offset(slot: usize) -> usize {
+        BLOCK_SIZE * BLOCK_COUNT + slot * Self::METADATA_SIZE
+    }
+
+    /// Reads the metadata of the given slot.
+    ///
+    /// Returns `None` if the slot was never written, its write was interrupted or its mapping is not valid.
+    fn read_metadata(&mut self, slot: usize) -> Result<Option<(u64, [usize; BLOCK_COUNT], [u32; BLOCK_COUNT])>, ()> {
+        let mut offset = Self::metadata_offset(slot);
+        let mut crc = !0u32;
+
+        let mut sequence = [0u8; size_of::<u64>()];
+        self.inner.read(offset, &mut sequence)?;
+        crc = crc32_update(crc, &sequence);
+        offset += sequence.len();
+
+        let mut mapping = [0usize; BLOCK_COUNT];
+        let mut erase_counts = [0u32; BLOCK_COUNT];
+        for value in mapping.iter_mut() {
+            let mut bytes = [0u8; size_of::<u32>()];
+            self.inner.read(offset, &mut bytes)?;
+            crc = crc32_update(crc, &bytes);
+            offset += bytes.len();
+            *value = u32::from_le_bytes(bytes) as usize;
+        }
+        for value in erase_counts.iter_mut() {
+            let mut bytes = [0u8; size_of::<u32>()];
+            self.inner.read(offset, &mut bytes)?;
+            crc = crc32_update(crc, &bytes);
+            offset += bytes.len();
+            *value = u32::from_le_bytes(bytes);
+        }
+
+        let mut checksum = [0u8; size_of::<u32>()];
+        self.inner.read(offset, &mut checksum)?;
+
+        let sequence = u64::from_le_bytes(sequence);
+        if !crc != u32::from_le_bytes(checksum) || sequence == 0 {
+            return Ok(None);
+        }
+
+        // every physical block has to be used exactly once
+        let mut used = [false; BLOCK_COUNT];
+        for physical in mapping {
+            if physical >= BLOCK_COUNT || used[physical] {
+                return Ok(None);
+            }
+            used[physical] = true;
+        }
+
+        Ok(Some((sequence, mapping, erase_counts)))
+    }
+
+    /// Stores the current mapping and erase counts to the slot that does not hold the latest version
+    fn store_metadata(&mut self) -> Result<(), ()> {
+        let sequence = self.sequence + 1;
+        let mut writer = MetadataWriter {
+            storage: &mut self.inner,
+            offset: Self::metadata_offset(sequence as usize % METADATA_SLOT_COUNT),
+            buffer: [0u8; COPY_BUFFER_SIZE],
+            len: 0,
+            crc: !0u32,
+        };
+
+        writer.push(&sequence.to_le_bytes())?;
+        for physical in self.mapping {
+            writer.push(&(physical as u32).to_le_bytes())?;
+        }
+        for erase_count in self.erase_counts {
+            writer.push(&erase_count.to_le_bytes())?;
+        }
+        writer.finish()?;
+        self.inner.flush()?;
+
+        self.sequence = sequence;
+        self.erase_counts_dirty = false;
+        Ok(())
+    }
+
+    /// Copies the data of physical block `src` to physical block `dest`
+    fn copy_block(&mut self, src: usize, dest: usize) -> Result<(), ()> {
+        let mut buffer = [0u8; COPY_BUFFER_SIZE];
+        let mut rel_offset = 0;
+        while rel_offset < BLOCK_SIZE {
+            let len = COPY_BUFFER_SIZE.min(BLOCK_SIZE - rel_offset);
+            self.inner.read(src * BLOCK_SIZE + rel_offset, &mut buffer[..len])?;
+            self.inner.write(dest * BLOCK_SIZE + rel_offset, &buffer[..len])?;
+
+            rel_offset += len;
+        }
+
+        self.erase_counts[dest] += 1;
+        Ok(())
+    }
+
+    /// Moves the given logical block to the least worn physical block, if its current block is worn too much
+    fn level_wear(&mut self, logical: usize) -> Result<(), ()> {
+        let hot = self.mapping[logical];
+        let (cold_logical, cold) = self
+            .mapping
+            .iter()
+            .copied()
+            .enumerate()
+            .min_by_key(|(_, physical)| self.erase_counts[*physical])
+            .unwrap();
+
+        if self.erase_counts[hot] < self.erase_counts[cold] + self.threshold {
+            return Ok(());
+        }
+
+        let spare = self.spare_block();
+        if cold != spare {
+            // move the cold data to the spare block, so the least worn block is free
+            self.copy_block(cold, spare)?;
+            self.mapping[cold_logical] = spare;
+            self.mapping[BLOCK_COUNT - 1] = cold;
+
+            // IMPORTANT: store the new mapping before the cold block is overwritten
+            self.store_metadata()?;
+        }
+
+        self.copy_block(hot, cold)?;
+        self.mapping[logical] = cold;
+
+        // the worn block can rest until it is the least worn one
+        self.mapping[BLOCK_COUNT - 1] = hot;
+        self.store_metadata()?;
+        self.relocation_count += 1;
+
+        Ok(())
+    }
+}
+
+/// Collects the metadata in a small buffer, so it is not written to the storage field by field
+struct MetadataWriter<'a, S: PersistentStorageModule> {
+    storage: &'a mut S,
+    offset: usize,
+    buffer: [u8; COPY_BUFFER_SIZE],
+    len: usize,
+    crc: u32,
+}
+
+impl<S: PersistentStorageModule> MetadataWriter<'_, S> {
+    fn push(&mut self, bytes: &[u8]) -> Result<(), ()> {
+        self.crc = crc32_update(self.crc, bytes);
+        for byte in bytes {
+            if self.len == self.buffer.len() {
+                self.write_buffer()?;
+            }
+            self.buffer[self.len] = *byte;
+            self.len += 1;
+        }
+
+        Ok(())
+    }
+
+    fn write_buffer(&mut self) -> Result<(), ()> {
+        self.storage.write(self.offset, &self.buffer[..self.len])?;
+        self.offset += self.len;
+        self.len = 0;
+        Ok(())
+    }
+
+    /// Appends the CRC-32 of all pushed bytes and writes the rest of the buffer
+    fn finish(mut self) -> Result<(), ()> {
+        let crc = !self.crc;
+        self.push(&crc.to_le_bytes())?;
+        self.write_buffer()
+    }
+}
+
+impl<const BLOCK_SIZE: usize, const BLOCK_COUNT: usize, S: PersistentStorageModule> PersistentStorageModule
+    for WearLevelingStorageModule<BLOCK_SIZE, BLOCK_COUNT, S>
+{
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        debug_assert!(offset + dest.len() <= self.get_max_size());
+
+        let mut rel_offset = 0;
+        while rel_offset < dest.len() {
+            let curr_offset = offset + rel_offset;
+            let block_offset = curr_offset % BLOCK_SIZE;
+            let len = (BLOCK_SIZE - block_offset).min(dest.len() - rel_offset);
+
+            let physical = self.mapping[curr_offset / BLOCK_SIZE];
+            self.inner
+                .read(physical * BLOCK_SIZE + block_offset, &mut dest[rel_offset..rel_offset + len])?;
+
+            rel_offset += len;
+        }
+
+        Ok(())
+    }
+
+    fn get_max_size(&self) -> usize {
+        (BLOCK_COUNT - 1) * BLOCK_SIZE
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        debug_assert!(offset + src.len() <= self.get_max_size());
+
+        let mut rel_offset = 0;
+        while rel_offset < src.len() {
+            let curr_offset = offset + rel_offset;
+            let block_offset = curr_offset % BLOCK_SIZE;
+            let len = (BLOCK_SIZE - block_offset).min(src.len() - rel_offset);
+
+            let logical = curr_offset / BLOCK_SIZE;
+            let physical = self.mapping[logical];
+            self.inner
+                .write(physical * BLOCK_SIZE + block_offset, &src[rel_offset..rel_offset + len])?;
+            self.erase_counts[physical] += 1;
+            self.erase_counts_dirty = true;
+
+            self.level_wear(logical)?;
+
+            rel_offset += len;
+        }
+
+        Ok(())
+    }
+
+    fn forget_region(&mut self, offset: usize, size: usize) {
+        let mut rel_offset = 0;
+        while rel_offset < size {
+            let curr_offset = offset + rel_offset;
+            let block_offset = curr_offset % BLOCK_SIZE;
+            let len = (BLOCK_SIZE - block_offset).min(size - rel_offset);
+
+            let physical = self.mapping[curr_offset / BLOCK_SIZE];
+            self.inner.forget_region(physical * BLOCK_SIZE + block_offset, len);
+
+            rel_offset += len;
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), ()> {
+        if self.erase_counts_dirty {
+            self.store_metadata()?;
+        }
+
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::modules::persistent_storage::{
+        test::{
+            get_test_storage, test_persistent_storage_custom_type, test_persistent_storage_normal,
+            PERSISTENT_STORAGE_NORMAL_TEST_SIZE,
+        },
+        FilePersistentStorageModule, PersistentStorageModule, WearLevelingStorageModule,
+    };
+
+    const BLOCK_SIZE: usize = 256;
+    const BLOCK_COUNT: usize = PERSISTENT_STORAGE_NORMAL_TEST_SIZE / BLOCK_SIZE + 1;
+
+    type TestModule<S> = WearLevelingStorageModule<BLOCK_SIZE, BLOCK_COUNT, S>;
+
+    const STORAGE_SIZE: usize = TestModule::<FilePersistentStorageModule>::required_storage_size();
+
+    #[test]
+    fn test_wear_leveling_storage_module() {
+        for threshold in [1, 4, 1000] {
+            let storage = get_test_storage("test_wear_leveling_storage_module", STORAGE_SIZE);
+            test_persistent_storage_normal(TestModule::new(storage, threshold).unwrap());
+
+            let storage = get_test_storage("test_wear_leveling_storage_module", STORAGE_SIZE);
+            test_persistent_storage_custom_type(TestModule::new(storage, threshold).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_wear_leveling_hot_block() {
+        const THRESHOLD: u32 = 8;
+
+        let storage = get_test_storage("test_wear_leveling_hot_block", STORAGE_SIZE);
+        let mut module = TestModule::new(storage, THRESHOLD).unwrap();
+
+        // fill every logical block with its index
+        for i in 0..(BLOCK_COUNT - 1) {
+            module.write(i * BLOCK_SIZE, &[i as u8; BLOCK_SIZE]).unwrap();
+        }
+
+        // always write the same (hot) region
+        for i in 0..10_000usize {
+            module.write(8, &i.to_le_bytes()).unwrap();
+        }
+
+        let erase_counts = module.get_erase_counts();
+        let max = *erase_counts.iter().max().unwrap();
+        let min = *erase_counts.iter().min().unwrap();
+        assert!(module.get_relocation_count() > 0);
+        assert!(max - min <= 2 * THRESHOLD, "erase counts are not leveled: {:?}", erase_counts);
+
+        // data of other blocks is preserved
+        let mut buffer = [0u8; BLOCK_SIZE];
+        for i in 1..(BLOCK_COUNT - 1) {
+            module.read(i * BLOCK_SIZE, &mut buffer).unwrap();
+            assert_eq!(buffer, [i as u8; BLOCK_SIZE]);
+        }
+
+        module.read(0, &mut buffer).unwrap();
+        assert_eq!(buffer[..8], [0u8; 8]);
+        assert_eq!(buffer[8..16], 9_999usize.to_le_bytes());
+        assert_eq!(buffer[16..], [0u8; BLOCK_SIZE - 16]);
+    }
+
+    #[test]
+    fn test_wear_leveling_reopen() {
+        const THRESHOLD: u32 = 4;
+
+        let storage = get_test_storage("test_wear_leveling_reopen", STORAGE_SIZE);
+        let mut module = TestModule::new(storage, THRESHOLD).unwrap();
+        for i in 0..(BLOCK_COUNT - 1) {
+            module.write(i * BLOCK_SIZE, &[i as u8; BLOCK_SIZE]).unwrap();
+        }
+        for i in 0..1000usize {
+            module.write(8, &i.to_le_bytes()).unwrap();
+        }
+        module.flush().unwrap();
+        assert!(module.get_relocation_count() > 0);
+
+        let erase_counts = *module.get_erase_counts();
+        let mut module = TestModule::new(module.into_inner(), THRESHOLD).unwrap();
+
+        // mapping and erase counts are restored
+        assert_eq!(module.get_erase_counts(), &erase_counts);
+        let mut buffer = [0u8; BLOCK_SIZE];
+        for i in 1..(BLOCK_COUNT - 1) {
+            module.read(i * BLOCK_SIZE, &mut buffer).unwrap();
+            assert_eq!(buffer, [i as u8; BLOCK_SIZE]);
+        }
+        module.read(0, &mut buffer).unwrap();
+        assert_eq!(buffer[8..16], 999usize.to_le_bytes());
+
+        // an interrupted metadata write falls back to the previous version
+        let mut storage = module.into_inner();
+        let latest_slot = (0..2)
+            .map(|slot| BLOCK_SIZE * BLOCK_COUNT + slot * TestModule::<FilePersistentStorageModule>::METADATA_SIZE)
+            .max_by_key(|offset| {
+                let mut sequence = [0u8; 8];
+                storage.read(*offset, &mut sequence).unwrap();
+                u64::from_le_bytes(sequence)
+            })
+            .unwrap();
+        storage.write(latest_slot + 8, &[0xAB; 4]).unwrap();
+
+        let mut module = TestModule::new(storage, THRESHOLD).unwrap();
+        for i in 1..(BLOCK_COUNT - 1) {
+            module.read(i * BLOCK_SIZE, &mut buffer).unwrap();
+            assert_eq!(buffer, [i as u8; BLOCK_SIZE]);
+        }
+    }
+}
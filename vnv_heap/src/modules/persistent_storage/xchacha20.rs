@@ -0,0 +1,152 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::encrypted::StorageCipher;
+
+const CHACHA_CONSTANTS: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+/// XChaCha20 key stream for `EncryptedStorageModule`.
+///
+/// Every block of 64 bytes is encrypted with the 24 byte nonce `nonce || block_index` (block index in little endian).
+/// `nonce` does not have to be secret, but should be unique per device, so that two devices
+/// with the same key do not use the same key stream.
+///
+/// Implemented in software, without any dependencies or lookup tables.
+#[derive(Clone)]
+pub struct XChaCha20StorageCipher {
+    /// Key derived from the key and nonce with HChaCha20
+    subkey: [u32; 8],
+}
+
+impl XChaCha20StorageCipher {
+    pub fn new(key: &[u8; 32], nonce: &[u8; 16]) -> Self {
+        let mut state = [0u32; 16];
+        state[0..4].copy_from_slice(&CHACHA_CONSTANTS);
+        state[4..12].copy_from_slice(&load_words::<8>(key));
+        state[12..16].copy_from_slice(&load_words::<4>(nonce));
+        chacha_rounds(&mut state);
+
+        let mut subkey = [0u32; 8];
+        subkey[0..4].copy_from_slice(&state[0..4]);
+        subkey[4..8].copy_from_slice(&state[12..16]);
+        Self { subkey }
+    }
+}
+
+impl StorageCipher for XChaCha20StorageCipher {
+    const BLOCK_SIZE: usize = 64;
+
+    fn apply_keystream(&self, block_index: u64, offset_in_block: usize, data: &mut [u8]) {
+        let mut input = [0u32; 16];
+        input[0..4].copy_from_slice(&CHACHA_CONSTANTS);
+        input[4..12].copy_from_slice(&self.subkey);
+        // word 12 is the counter and word 13 the zero prefix of the nonce
+        input[14] = block_index as u32;
+        input[15] = (block_index >> 32) as u32;
+
+        let mut state = input;
+        chacha_rounds(&mut state);
+
+        let mut keystream = [0u8; 64];
+        for (i, chunk) in keystream.chunks_exact_mut(4).enumerate() {
+            chunk.copy_from_slice(&state[i].wrapping_add(input[i]).to_le_bytes());
+        }
+
+        for (byte, key) in data.iter_mut().zip(&keystream[offset_in_block..]) {
+            *byte ^= key;
+        }
+    }
+}
+
+fn load_words<const N: usize>(bytes: &[u8]) -> [u32; N] {
+    let mut words = [0u32; N];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *word = u32::from_le_bytes(chunk.try_into().unwrap());
+    }
+    words
+}
+
+#[inline]
+fn quarter_round(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(12);
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(7);
+}
+
+/// The 20 rounds of ChaCha20 (without adding the input afterwards)
+fn chacha_rounds(state: &mut [u32; 16]) {
+    for _ in 0..10 {
+        quarter_round(state, 0, 4, 8, 12);
+        quarter_round(state, 1, 5, 9, 13);
+        quarter_round(state, 2, 6, 10, 14);
+        quarter_round(state, 3, 7, 11, 15);
+        quarter_round(state, 0, 5, 10, 15);
+        quarter_round(state, 1, 6, 11, 12);
+        quarter_round(state, 2, 7, 8, 13);
+        quarter_round(state, 3, 4, 9, 14);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{StorageCipher, XChaCha20StorageCipher};
+
+    fn from_hex<const N: usize>(hex: &str) -> [u8; N] {
+        let mut bytes = [0u8; N];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_hchacha20() {
+        // test vector of draft-irtf-cfrg-xchacha
+        let key: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let cipher = XChaCha20StorageCipher::new(&key, &from_hex("000000090000004a0000000031415927"));
+        let subkey: [u8; 32] = from_hex("82413b4227b27bfed30e42508a877d73a0f9e4d58a74a853c12ec41326d3ecdc");
+
+        for (i, word) in cipher.subkey.iter().enumerate() {
+            assert_eq!(word.to_le_bytes(), subkey[4 * i..4 * i + 4]);
+        }
+    }
+
+    #[test]
+    fn test_xchacha20_keystream() {
+        let key: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let nonce: [u8; 16] = core::array::from_fn(|i| 0x40 + i as u8);
+        let cipher = XChaCha20StorageCipher::new(&key, &nonce);
+
+        let mut block = [0u8; 64];
+        cipher.apply_keystream(3, 0, &mut block);
+        assert_eq!(
+            block,
+            from_hex::<64>("bdfc636660f6769762c5dfffc4c5425f853bccfdf3426816fb4a63518340755b5494e1874499ebd3fee0cf50daec6e121c39ed9bb55e60d716ce7efd264333d7")
+        );
+
+        // starting in the middle of a block uses the rest of its key stream
+        let mut partial = [0u8; 10];
+        cipher.apply_keystream(0, 20, &mut partial);
+        let expected: [u8; 64] = from_hex("592336afe0bb14b138985651fc1190821841ca72dffabfa58a569255cdf6e8aa1abe034562c8df6820770d63bed828cf40047a3018d9d99a2af3ed2c49e68ad4");
+        assert_eq!(partial, expected[20..30]);
+    }
+}
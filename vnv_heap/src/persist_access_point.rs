@@ -17,17 +17,42 @@
 
 use core::{
     mem::transmute,
-    sync::atomic::{AtomicBool, Ordering, AtomicPtr},
+    sync::atomic::{AtomicU8, Ordering},
 };
+#[cfg(debug_assertions)]
+use core::sync::atomic::AtomicPtr;
 use try_lock::TryLock;
 
+#[cfg(feature = "emergency_region")]
+use crate::resident_object_manager::emergency_reserve::EmergencyReserve;
+#[cfg(feature = "recovery")]
+use crate::heap_recovery::{CommitCursor, COMMIT_RECORD_COUNT};
 use crate::{
     modules::{allocator::AllocatorModule, persistent_storage::SharedStorageReference},
+    persist_sync::{self, AtomicBool},
     resident_object_manager::{
-        persist, resident_list::SharedResidentListRef, restore,
+        persist, persist_budget, resident_list::SharedResidentListRef, restore,
     },
+    vnv_heap::{PartialPersistResult, PersistStatus, PersistSummary},
 };
 
+/// Current `PersistStatus` (stored as `u8`, so it can be read from any context)
+static PERSIST_STATUS: AtomicU8 = AtomicU8::new(PersistStatus::Idle as u8);
+
+pub(crate) fn get_persist_status() -> PersistStatus {
+    match PERSIST_STATUS.load(Ordering::SeqCst) {
+        1 => PersistStatus::Queued,
+        2 => PersistStatus::Persisting,
+        3 => PersistStatus::Persisted,
+        4 => PersistStatus::Restoring,
+        _ => PersistStatus::Idle,
+    }
+}
+
+fn set_persist_status(status: PersistStatus) {
+    PERSIST_STATUS.store(status as u8, Ordering::SeqCst);
+}
+
 /// An object containing all necessary data
 /// 
 /// We need this as the VNVHeap object can be moved (if it is not borrowed/there exist no objects)
@@ -55,7 +80,7 @@ impl PersistAccessPoint {
         resident_list: SharedResidentListRef<'a>,
         storage: SharedStorageReference<'a, 'b>,
         handler: fn(*mut u8, usize) -> (),
-        heap_lock: &TryLock<()>,
+        heap_lock: &persist_sync::TryLock<()>,
         persist_queued: &AtomicBool,
         heap: *mut dyn AllocatorModule,
     ) -> Result<(), ()> {
@@ -79,7 +104,13 @@ impl PersistAccessPoint {
             heap_lock: transmute(heap_lock),
             persist_queued: transmute(persist_queued),
             handler,
+            post_persist_hook: None,
             heap,
+            #[cfg(feature = "emergency_region")]
+            emergency_reserve: core::ptr::null(),
+            #[cfg(feature = "recovery")]
+            // the slice slots are only known to the heap, which sets the cursor right after this
+            commit_cursor: CommitCursor::new([0; COMMIT_RECORD_COUNT]),
         });
 
         drop(lock_guard);
@@ -94,6 +125,42 @@ impl PersistAccessPoint {
         let mut lock_guard = self.inner.try_lock().ok_or(())?;
         *lock_guard = None;
 
+        // a queued persist is not executed anymore
+        set_persist_status(PersistStatus::Idle);
+
+        Ok(())
+    }
+
+    /// Sets the hook that is called after the state was persisted (see `VNVHeap::set_post_persist_hook`)
+    pub(crate) fn set_post_persist_hook(&self, hook: Option<PostPersistHook>) -> Result<(), ()> {
+        let mut lock_guard = self.inner.try_lock().ok_or(())?;
+        let inner = lock_guard.as_mut().ok_or(())?;
+        inner.post_persist_hook = hook;
+
+        Ok(())
+    }
+
+    /// Sets the emergency region that is restored after the state was persisted
+    ///
+    /// ### Safety
+    ///
+    /// `emergency_reserve` has to remain valid until `unset` is called and may only be modified while the heap lock is held
+    #[cfg(feature = "emergency_region")]
+    pub(crate) unsafe fn set_emergency_reserve(&self, emergency_reserve: *const EmergencyReserve) -> Result<(), ()> {
+        let mut lock_guard = self.inner.try_lock().ok_or(())?;
+        let inner = lock_guard.as_mut().ok_or(())?;
+        inner.emergency_reserve = emergency_reserve;
+
+        Ok(())
+    }
+
+    /// Sets where the next commit record is written (see `commit_persisted_slice`)
+    #[cfg(feature = "recovery")]
+    pub(crate) fn set_commit_cursor(&self, commit_cursor: CommitCursor) -> Result<(), ()> {
+        let mut lock_guard = self.inner.try_lock().ok_or(())?;
+        let inner = lock_guard.as_mut().ok_or(())?;
+        inner.commit_cursor = commit_cursor;
+
         Ok(())
     }
 
@@ -115,15 +182,8 @@ impl PersistAccessPoint {
 
             // ###### TRY TO GET ALL NECESSARY LOCKS ######
 
-            {
-                // there wont be any race conditions here as its guaranteed that no other threads
-                // run during this handler
-                if inner.heap_lock.try_lock().is_none() || inner.storage.is_locked() {
-                    print_persist_debug("cannot acquire lock. persist queued...\n");
-
-                    inner.persist_queued.store(true, Ordering::SeqCst);
-                    return;
-                }
+            if !is_unlocked_or_queue(inner.heap_lock, &inner.storage, inner.persist_queued) {
+                return;
             }
 
             #[cfg(debug_assertions)]
@@ -135,21 +195,56 @@ impl PersistAccessPoint {
             };
 
             // ###### START PERSISTING STATE ######
-            persist(&inner.resident_list, &mut inner.storage);
+            set_persist_status(PersistStatus::Persisting);
+            let start = inner.post_persist_hook.as_ref().and_then(|hook| hook.now());
+
+            // the slice of the last committed persist is kept until this persist is committed
+            #[cfg(feature = "recovery")]
+            let slice_offset = inner.commit_cursor.get_slice_offset();
+            #[cfg(not(feature = "recovery"))]
+            let slice_offset = 0;
+
+            let summary = persist(
+                &inner.resident_list,
+                &mut inner.storage,
+                inner.resident_buf_base_ptr,
+                slice_offset,
+                #[cfg(feature = "recovery")]
+                &mut inner.commit_cursor,
+            );
+
+            #[cfg(feature = "recovery")]
+            if !summary.committed {
+                print_persist_debug("commit of the persisted state failed\n");
+            }
 
             // ###### FINISHED PERSISTING STATE: EXECUTING HANDLER NOW ######
+            set_persist_status(PersistStatus::Persisted);
+            if let Some(hook) = inner.post_persist_hook.as_ref() {
+                hook.call(summary, start);
+            }
             (inner.handler)(inner.resident_buf_base_ptr, inner.resident_buf_size);
 
             // ###### HANDLER RETURNED: RESTORING STATE NOW ######
+            set_persist_status(PersistStatus::Restoring);
             restore(
                 &mut inner.storage,
 
                 // this is safe as we could access the heap_lock
                 unsafe { inner.heap.as_mut().unwrap() },
                 inner.resident_buf_base_ptr,
-                inner.resident_buf_size
+                inner.resident_buf_size,
+                slice_offset,
             );
 
+            // the emergency region is not part of the resident list, so it has to be restored separately
+            #[cfg(feature = "emergency_region")]
+            unsafe {
+                if let Some(emergency_reserve) = inner.emergency_reserve.as_ref() {
+                    emergency_reserve.restore(inner.heap.as_mut().unwrap());
+                }
+            }
+
             #[cfg(debug_assertions)]
             unsafe {
                 let heap_dump_new = inner.heap.as_mut().unwrap().dump();
@@ -160,11 +255,54 @@ impl PersistAccessPoint {
             check_metadata(&inner.resident_list, metadata_backup);
 
 
+            set_persist_status(PersistStatus::Idle);
             print_persist_debug("restore finished\n");
         }
     }
+
+    /// Writes back dirty objects until `budget_bytes` are used up (see `vnv_persist_budget`).
+    ///
+    /// Returns `None` if there is no heap or if it is locked right now.
+    pub(crate) fn persist_budget_if_not_empty(&self, budget_bytes: usize) -> Option<PartialPersistResult> {
+        let mut lock_guard = self.inner.try_lock()?;
+        let inner = lock_guard.as_mut()?;
+
+        if !is_unlocked(inner.heap_lock, &inner.storage) {
+            // unlike `persist_if_not_empty`, this is not queued, as the budget would not be available anymore
+            print_persist_debug("cannot acquire lock. partial persist skipped...\n");
+            return None;
+        }
+
+        print_persist_debug("partial persist was triggered\n");
+        Some(persist_budget(&inner.resident_list, &mut inner.storage, budget_bytes))
+    }
+}
+
+
+/// Returns `true` if neither the heap nor its storage is locked, i.e. if the heap can be persisted right now
+fn is_unlocked(heap_lock: &persist_sync::TryLock<()>, storage: &SharedStorageReference) -> bool {
+    // there wont be any race conditions here as its guaranteed that no other threads
+    // run during this handler
+    heap_lock.try_lock().is_some() && !storage.is_locked()
 }
 
+/// Like `is_unlocked`, but queues the persist if the heap or its storage is locked.
+/// It is executed as soon as the lock is released (see `SharedPersistGuard::drop`).
+pub(crate) fn is_unlocked_or_queue(
+    heap_lock: &persist_sync::TryLock<()>,
+    storage: &SharedStorageReference,
+    persist_queued: &AtomicBool,
+) -> bool {
+    if is_unlocked(heap_lock, storage) {
+        return true;
+    }
+
+    print_persist_debug("cannot acquire lock. persist queued...\n");
+
+    persist_queued.store(true, Ordering::SeqCst);
+    set_persist_status(PersistStatus::Queued);
+    false
+}
 
 #[cfg(debug_assertions)]
 use crate::resident_object_manager::resident_object_metadata::ResidentObjectMetadata;
@@ -229,15 +367,38 @@ pub(crate) fn print_persist_debug(text: &str) {
     print!("{}", text);
 }
 
+/// Hook that is called with a `PersistSummary` after each persist (see `VNVHeap::set_post_persist_hook`)
+#[derive(Clone, Copy)]
+pub(crate) struct PostPersistHook {
+    pub(crate) handler: fn(&PersistSummary),
+    pub(crate) clock: Option<fn() -> u64>,
+}
+
+impl PostPersistHook {
+    fn now(&self) -> Option<u64> {
+        self.clock.map(|clock| clock())
+    }
+
+    fn call(&self, mut summary: PersistSummary, start: Option<u64>) {
+        summary.duration_ticks = start.zip(self.now()).map(|(start, end)| end.wrapping_sub(start));
+        (self.handler)(&summary)
+    }
+}
+
 struct PersistAccessPointInner {
     resident_buf_base_ptr: *mut u8,
     resident_buf_size: usize,
     resident_list: SharedResidentListRef<'static>,
     storage: SharedStorageReference<'static, 'static>,
     handler: fn(*mut u8, usize) -> (),
-    heap_lock: &'static TryLock<()>,
+    post_persist_hook: Option<PostPersistHook>,
+    heap_lock: &'static persist_sync::TryLock<()>,
     persist_queued: &'static AtomicBool,
     heap: *mut dyn AllocatorModule,
+    #[cfg(feature = "emergency_region")]
+    emergency_reserve: *const EmergencyReserve,
+    #[cfg(feature = "recovery")]
+    commit_cursor: CommitCursor,
 }
 
 unsafe impl Send for PersistAccessPoint {}
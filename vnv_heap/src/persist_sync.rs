@@ -0,0 +1,165 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Primitives of the protocol between the locks of a heap and `vnv_persist_all`.
+//!
+//! In tests with `--cfg loom`, they are replaced by implementations that loom can model check,
+//! so the model checking runs the real lock protocol (see `test::persist_lock_loom`).
+
+#[cfg(not(all(loom, test)))]
+pub(crate) use core::sync::atomic::AtomicBool;
+#[cfg(not(all(loom, test)))]
+pub(crate) use try_lock::{Locked, TryLock};
+
+#[cfg(all(loom, test))]
+pub(crate) use model::{interrupt, set_queued_persist_handler, step, AtomicBool, Locked, TryLock};
+
+/// Executes a persist that was queued while one of the locks was held (see `SharedPersistGuard::drop`)
+#[cfg(not(all(loom, test)))]
+pub(crate) fn run_queued_persist() {
+    // as currently only one heap can be created and this heap is only active in one thread
+    // its safe to assume that no other threads should be running
+    // (before calling vnv_persist_all the first time, it has to be made sure that all other threads stop)
+    unsafe { crate::vnv_persist_all() }
+}
+
+#[cfg(all(loom, test))]
+pub(crate) use model::run_queued_persist;
+
+/// The persist routine is executed in an interrupt handler, so it runs to completion and cannot be
+/// preempted by the main program. This is modelled with the `CPU` mutex: the main program locks it
+/// for every single atomic operation, while the interrupt locks it for its whole duration.
+/// Thus, loom explores every point at which the interrupt can occur.
+#[cfg(all(loom, test))]
+mod model {
+    use core::{
+        cell::{Cell, UnsafeCell},
+        ops::{Deref, DerefMut},
+        sync::atomic::Ordering,
+    };
+    use std::sync::Arc;
+
+    use loom::sync::Mutex;
+
+    loom::lazy_static! {
+        static ref CPU: Mutex<()> = Mutex::new(());
+        static ref QUEUED_PERSIST_HANDLER: Mutex<Option<Arc<dyn Fn() + Send + Sync>>> = Mutex::new(None);
+    }
+
+    loom::thread_local! {
+        static IN_INTERRUPT: Cell<bool> = Cell::new(false);
+    }
+
+    /// Executes `f` as one step of the current context (the interrupt can only occur between steps)
+    pub(crate) fn step<R>(f: impl FnOnce() -> R) -> R {
+        if IN_INTERRUPT.with(|in_interrupt| in_interrupt.get()) {
+            return f();
+        }
+
+        let _guard = CPU.lock().unwrap();
+        f()
+    }
+
+    /// Executes `f` as an interrupt handler, i.e. without being preempted by the main program
+    pub(crate) fn interrupt<R>(f: impl FnOnce() -> R) -> R {
+        let _guard = CPU.lock().unwrap();
+        IN_INTERRUPT.with(|in_interrupt| in_interrupt.set(true));
+        let res = f();
+        IN_INTERRUPT.with(|in_interrupt| in_interrupt.set(false));
+        res
+    }
+
+    /// Sets the function that is called instead of `vnv_persist_all` if a queued persist is executed
+    pub(crate) fn set_queued_persist_handler(handler: Arc<dyn Fn() + Send + Sync>) {
+        *QUEUED_PERSIST_HANDLER.lock().unwrap() = Some(handler);
+    }
+
+    pub(crate) fn run_queued_persist() {
+        let handler = QUEUED_PERSIST_HANDLER.lock().unwrap().clone();
+        if let Some(handler) = handler {
+            handler();
+        }
+    }
+
+    pub(crate) struct AtomicBool(loom::sync::atomic::AtomicBool);
+
+    impl AtomicBool {
+        pub(crate) fn new(value: bool) -> Self {
+            Self(loom::sync::atomic::AtomicBool::new(value))
+        }
+
+        pub(crate) fn load(&self, order: Ordering) -> bool {
+            step(|| self.0.load(order))
+        }
+
+        pub(crate) fn store(&self, value: bool, order: Ordering) {
+            step(|| self.0.store(value, order))
+        }
+
+        pub(crate) fn swap(&self, value: bool, order: Ordering) -> bool {
+            step(|| self.0.swap(value, order))
+        }
+    }
+
+    pub(crate) struct TryLock<T> {
+        locked: AtomicBool,
+        value: UnsafeCell<T>,
+    }
+
+    unsafe impl<T: Send> Sync for TryLock<T> {}
+
+    impl<T> TryLock<T> {
+        pub(crate) fn new(value: T) -> Self {
+            Self {
+                locked: AtomicBool::new(false),
+                value: UnsafeCell::new(value),
+            }
+        }
+
+        pub(crate) fn try_lock(&self) -> Option<Locked<'_, T>> {
+            if self.locked.swap(true, Ordering::SeqCst) {
+                None
+            } else {
+                Some(Locked { lock: self })
+            }
+        }
+    }
+
+    pub(crate) struct Locked<'a, T> {
+        lock: &'a TryLock<T>,
+    }
+
+    impl<T> Deref for Locked<'_, T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            unsafe { &*self.lock.value.get() }
+        }
+    }
+
+    impl<T> DerefMut for Locked<'_, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            unsafe { &mut *self.lock.value.get() }
+        }
+    }
+
+    impl<T> Drop for Locked<'_, T> {
+        fn drop(&mut self) {
+            self.lock.locked.store(false, Ordering::SeqCst);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::{sync_dirty_data, ResidentObjectManager};
+use crate::{
+    modules::{
+        allocator::AllocatorModule, object_management::ObjectManagementModule,
+        persistent_storage::PersistentStorageModule,
+    },
+    vnv_error::VNVError,
+};
+
+impl<'a, 'b, A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'a, 'b, A, M> {
+    /// Limits how many bytes can be dirty at the same time to `limit` (at most the dirty budget the heap was created with).
+    ///
+    /// If more bytes are dirty, objects are synced (or unloaded) by the object management module.
+    pub(crate) fn set_dirty_limit<S: PersistentStorageModule>(
+        &mut self,
+        limit: usize,
+        storage: &mut S,
+    ) -> Result<(), VNVError> {
+        let withheld = self.withheld_dirty_size;
+        let capacity = self.get_dirty_budget() + withheld;
+        let new_withheld = capacity - limit.min(capacity);
+
+        if new_withheld > withheld {
+            let required_bytes = new_withheld - withheld;
+            if self.remaining_dirty_size < required_bytes {
+                let missing_bytes = required_bytes - self.remaining_dirty_size;
+                unsafe {
+                    sync_dirty_data::<A, S, M>(
+                        &mut self.remaining_dirty_size,
+                        self.resident_list,
+                        &mut self.object_manager,
+                        missing_bytes,
+                        storage,
+                        &self.heap,
+                        &mut self.stats,
+                    )
+                }?;
+            }
+
+            self.remaining_dirty_size -= required_bytes;
+            #[cfg(debug_assertions)]
+            {
+                self._initial_dirty_size -= required_bytes;
+            }
+        } else {
+            let released_bytes = withheld - new_withheld;
+            self.remaining_dirty_size += released_bytes;
+            #[cfg(debug_assertions)]
+            {
+                self._initial_dirty_size += released_bytes;
+            }
+        }
+
+        self.withheld_dirty_size = new_withheld;
+
+        #[cfg(feature = "watermarks")]
+        self.update_dirty_budget_watermark();
+
+        self.check_integrity();
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::mem::size_of;
+
+use super::ResidentObjectManager;
+use crate::{
+    allocation_identifier::AllocationIdentifier,
+    modules::{
+        allocator::AllocatorModule, object_management::ObjectManagementModule,
+        persistent_storage::PersistentStorageModule,
+    },
+    vnv_error::VNVError,
+    vnv_heap::DirtyPoolUsage,
+};
+
+impl<'a, 'b, A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'a, 'b, A, M> {
+    pub(crate) fn get_dirty_pool_usage(&self) -> DirtyPoolUsage {
+        let limit = self.background_dirty_limit;
+        let mut usage = DirtyPoolUsage {
+            foreground_bytes: 0,
+            background_bytes: 0,
+            background_limit: if limit == usize::MAX { None } else { Some(limit) },
+        };
+
+        for item in self.resident_list.iter() {
+            if !item.inner.status.is_data_dirty() {
+                continue;
+            }
+
+            if item.inner.status.is_background() {
+                usage.background_bytes += item.inner.layout.size();
+            } else {
+                usage.foreground_bytes += item.inner.layout.size();
+            }
+        }
+
+        usage
+    }
+
+    /// Changes the limit of the background pool and syncs background objects if it is exceeded now
+    pub(crate) fn set_background_dirty_limit<S: PersistentStorageModule>(
+        &mut self,
+        limit: Option<usize>,
+        storage: &mut S,
+    ) -> Result<(), VNVError> {
+        let limit = limit.unwrap_or(usize::MAX);
+        unsafe { self.shrink_background_pool(limit, storage) }?;
+        self.background_dirty_limit = limit;
+
+        Ok(())
+    }
+
+    /// Same as `get_mut`, but the dirty user data of the object is accounted to the background pool
+    pub(crate) unsafe fn get_mut_background<T: Sized, S: PersistentStorageModule>(
+        &mut self,
+        identifier: &AllocationIdentifier<T>,
+        storage: &mut S,
+    ) -> Result<*mut T, VNVError> {
+        let limit = self.background_dirty_limit;
+        if size_of::<T>() > limit {
+            return Err(VNVError::DirtyPoolExhausted);
+        }
+
+        let in_pool = match self.find_element_mut(identifier) {
+            Some(ptr) => {
+                let status = &ptr.as_ref().unwrap().inner.status;
+                status.is_data_dirty() && status.is_background()
+            }
+            None => false,
+        };
+        if !in_pool {
+            // make space for this object in the background pool
+            self.shrink_background_pool(limit - size_of::<T>(), storage)?;
+        }
+
+        let ptr = self.get_mut(identifier, false, None, storage)?;
+
+        // the object is resident now
+        let meta_ptr = self.find_element_mut(identifier).unwrap();
+        meta_ptr.as_mut().unwrap().inner.status.set_background(true);
+
+        self.check_integrity();
+        Ok(ptr)
+    }
+
+    /// Adds a newly allocated object to the background pool.
+    ///
+    /// If there is no space left in the background pool, the object is synced instead.
+    pub(crate) unsafe fn add_to_background_pool<T: Sized, S: PersistentStorageModule>(
+        &mut self,
+        identifier: &AllocationIdentifier<T>,
+        storage: &mut S,
+    ) -> Result<(), VNVError> {
+        let background_bytes = self.get_dirty_pool_usage().background_bytes;
+
+        if let Some(meta_ptr) = self.find_element_mut(identifier) {
+            let meta = meta_ptr.as_mut().unwrap();
+            meta.inner.status.set_background(true);
+
+            if meta.inner.status.is_data_dirty() && background_bytes + size_of::<T>() > self.background_dirty_limit {
+                self.remaining_dirty_size += meta.persist_user_data_dynamic(storage, &mut self.stats)?;
+            }
+        }
+
+        self.check_integrity();
+        Ok(())
+    }
+
+    /// Syncs background objects that are not in use until the background pool has at most `limit` dirty bytes
+    unsafe fn shrink_background_pool<S: PersistentStorageModule>(
+        &mut self,
+        limit: usize,
+        storage: &mut S,
+    ) -> Result<(), VNVError> {
+        let mut background_bytes = self.get_dirty_pool_usage().background_bytes;
+
+        let mut iter = self.resident_list.iter_mut();
+        while background_bytes > limit {
+            let mut item = match iter.next() {
+                Some(item) => item,
+                None => return Err(VNVError::DirtyPoolExhausted),
+            };
+
+            let meta = item.get_element();
+            let status = meta.inner.status;
+            if !status.is_background() || !status.is_data_dirty() || status.is_in_use() {
+                continue;
+            }
+
+            self.remaining_dirty_size += meta.persist_user_data_dynamic(storage, &mut self.stats)?;
+            background_bytes -= meta.inner.layout.size();
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{alloc::Layout, ptr::NonNull};
+
+use crate::modules::allocator::AllocatorModule;
+
+/// Region of the resident buffer that is set aside to make an object resident
+/// if all other resident objects are in use (and thus cannot be unloaded).
+///
+/// It is stored in the cutoff of the resident buffer (see `ResidentBufPersistentStorage`) and is shared
+/// by the `ResidentObjectManager` and `vnv_persist_all`. It is only accessed while the lock of the resident heap is held.
+pub(crate) struct EmergencyReserve {
+    /// Reserved region or `None` if no region is reserved
+    region: Option<(NonNull<u8>, Layout)>,
+
+    /// `true` if the region is allocated for this reserve, `false` if it was handed out to an object
+    held: bool,
+}
+
+impl EmergencyReserve {
+    pub(crate) const fn new() -> Self {
+        Self {
+            region: None,
+            held: false,
+        }
+    }
+
+    /// Returns `true` if the region can currently be used in an emergency
+    pub(crate) fn is_available(&self) -> bool {
+        self.region.is_some() && self.held
+    }
+
+    /// Stores a region that was just allocated in `heap`
+    pub(crate) fn set(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        debug_assert!(self.region.is_none());
+
+        self.region = Some((ptr, layout));
+        self.held = true;
+    }
+
+    /// Removes the reserved region and deallocates it (if it is not used by an object)
+    pub(crate) unsafe fn clear<A: AllocatorModule + ?Sized>(&mut self, heap: &mut A) {
+        self.release(heap);
+        self.region = None;
+    }
+
+    /// Deallocates the region, so that it can be used to make an object resident.
+    ///
+    /// Returns `false` if there is no region available.
+    pub(crate) unsafe fn release<A: AllocatorModule + ?Sized>(&mut self, heap: &mut A) -> bool {
+        if let (Some((ptr, layout)), true) = (self.region, self.held) {
+            heap.deallocate(ptr, layout);
+            self.held = false;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Allocates the region again, if it is not used by an object anymore
+    pub(crate) unsafe fn reacquire<A: AllocatorModule + ?Sized>(&mut self, heap: &mut A) {
+        if let (Some((ptr, layout)), false) = (self.region, self.held) {
+            self.held = heap.allocate_at(layout, ptr.as_ptr()).is_ok();
+        }
+    }
+
+    /// Allocates the region again after the resident heap was reset (see `restore`)
+    pub(crate) unsafe fn restore<A: AllocatorModule + ?Sized>(&self, heap: &mut A) {
+        if let (Some((ptr, layout)), true) = (self.region, self.held) {
+            heap.allocate_at(layout, ptr.as_ptr())
+                .expect("emergency region should not overlap with restored objects");
+        }
+    }
+}
@@ -0,0 +1,70 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::cell::Cell;
+
+/// Counters of a heap that are updated by its resident object manager
+#[derive(Clone, Copy)]
+pub(crate) struct MetricCounters {
+    pub(crate) evictions: usize,
+    pub(crate) allocation_failures: usize,
+}
+
+impl MetricCounters {
+    pub(crate) const fn new() -> Self {
+        Self {
+            evictions: 0,
+            allocation_failures: 0,
+        }
+    }
+}
+
+/// Storage accesses of a heap.
+///
+/// `vnv_persist_all` accesses the storage as well, so these are stored in the cutoff of the resident buffer
+/// (see `ResidentBufPersistentStorage`). They are only updated while the storage lock is held.
+pub(crate) struct StorageCounters {
+    reads: Cell<usize>,
+    writes: Cell<usize>,
+}
+
+impl StorageCounters {
+    pub(crate) const fn new() -> Self {
+        Self {
+            reads: Cell::new(0),
+            writes: Cell::new(0),
+        }
+    }
+
+    /// Has to be called after the heap read from storage
+    pub(crate) fn storage_read(&self) {
+        self.reads.set(self.reads.get() + 1);
+    }
+
+    /// Has to be called after the heap wrote to storage
+    pub(crate) fn storage_written(&self) {
+        self.writes.set(self.writes.get() + 1);
+    }
+
+    pub(crate) fn get_reads(&self) -> usize {
+        self.reads.get()
+    }
+
+    pub(crate) fn get_writes(&self) -> usize {
+        self.writes.get()
+    }
+}
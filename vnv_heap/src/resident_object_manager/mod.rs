@@ -15,37 +15,108 @@
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use core::ptr::slice_from_raw_parts_mut;
+use core::alloc::Layout;
+use core::cell::Cell;
+use core::ptr::NonNull;
 use core::{marker::PhantomData, mem::size_of};
 
 use log::{debug, trace, warn};
-use memoffset::offset_of;
+#[cfg(feature = "emergency_region")]
+use emergency_reserve::EmergencyReserve;
+use residency_token::ResidencyToken;
 use resident_list::ResidentList;
 use resident_object_metadata::ResidentObjectMetadata;
+#[cfg(feature = "watermarks")]
+use watermarks::WatermarkState;
+#[cfg(feature = "persist_priority")]
+use persist_priority::{PersistPriority, PersistPriorityTable};
+#[cfg(feature = "object_stats")]
+use object_stats::ObjectStatsTable;
+#[cfg(feature = "metrics")]
+use metrics::MetricCounters;
+#[cfg(feature = "global_dirty_budget")]
+use crate::dirty_budget_pool::DirtyBudgetReservation;
 
 use crate::modules::object_management::{
     ObjectManagementList, ObjectManagementListArguments, ObjectManagementModule, ObjectStatusWrapper
 };
 use crate::shared_persist_lock::SharedPersistLock;
+use crate::vnv_error::VNVError;
+use crate::vnv_heap::{DropOutcome, DropPolicy, ResidentExhaustionReason, ResidentUsage};
+use crate::vnv_config::{PersistLatencyBudget, WriteBack};
 use crate::{
     allocation_identifier::AllocationIdentifier,
     modules::{allocator::AllocatorModule, persistent_storage::PersistentStorageModule},
 };
 
+pub(crate) mod dirty_limit;
+#[cfg(feature = "dirty_pools")]
+pub(crate) mod dirty_pools;
+#[cfg(feature = "emergency_region")]
+pub(crate) mod emergency_reserve;
+#[cfg(feature = "metrics")]
+pub(crate) mod metrics;
 pub(crate) mod partial_dirtiness_tracking;
 mod persist;
+#[cfg(feature = "persist_priority")]
+pub(crate) mod persist_priority;
+pub(crate) mod residency_token;
 pub(crate) mod resident_list;
 pub(crate) mod resident_object;
 pub(crate) mod resident_object_backup;
 pub(crate) mod resident_object_metadata;
 mod resident_object_status;
+#[cfg(feature = "object_stats")]
+pub(crate) mod object_stats;
+#[cfg(feature = "watermarks")]
+pub(crate) mod watermarks;
 
 pub(crate) use persist::*;
 use resident_object::*;
 use resident_object_backup::*;
 
 #[cfg(test)]
-mod test;
+pub(crate) mod test;
+
+/// Statistics of a heap that are updated while its objects are loaded, written back and unloaded
+/// (only used with the `object_stats` and `metrics` features)
+pub(crate) struct HeapStats {
+    #[cfg(feature = "object_stats")]
+    pub(crate) objects: ObjectStatsTable,
+
+    #[cfg(feature = "metrics")]
+    pub(crate) metrics: MetricCounters,
+}
+
+impl HeapStats {
+    pub(crate) const fn new() -> Self {
+        Self {
+            #[cfg(feature = "object_stats")]
+            objects: ObjectStatsTable::new(),
+            #[cfg(feature = "metrics")]
+            metrics: MetricCounters::new(),
+        }
+    }
+
+    /// Has to be called before an object is removed from the resident buffer
+    #[allow(unused_variables)]
+    pub(crate) fn object_unloaded(&mut self, metadata: &ResidentObjectMetadata) {
+        #[cfg(feature = "object_stats")]
+        self.objects.object_unloaded(metadata);
+
+        #[cfg(feature = "metrics")]
+        {
+            self.metrics.evictions += 1;
+        }
+    }
+
+    /// Has to be called after `bytes` of dirty data of an object were written back to storage
+    #[allow(unused_variables)]
+    pub(crate) fn object_written_back(&mut self, metadata: &ResidentObjectMetadata, bytes: usize) {
+        #[cfg(feature = "object_stats")]
+        self.objects.object_written_back(metadata, bytes);
+    }
+}
 
 pub(crate) struct ResidentObjectManager<'a: 'b, 'b, A: AllocatorModule, M: ObjectManagementModule> {
     /// In memory heap for resident objects and their metadata
@@ -61,6 +132,48 @@ pub(crate) struct ResidentObjectManager<'a: 'b, 'b, A: AllocatorModule, M: Objec
     /// Object management module
     pub(crate) object_manager: M,
 
+    /// Reason why the last attempt to make an object resident failed
+    pub(crate) last_exhaustion: Option<ResidentExhaustionReason>,
+
+    /// What happens if an object cannot be made resident while it is dropped
+    pub(crate) drop_policy: DropPolicy,
+
+    /// When dirty objects are written back to storage
+    pub(crate) write_back: WriteBack,
+
+    /// Dirty bytes that cannot be used because of the current dirty limit (see `set_dirty_limit`)
+    pub(crate) withheld_dirty_size: usize,
+
+    /// Maximum dirty bytes that were set at runtime (see `VNVHeap::set_max_dirty_bytes`)
+    pub(crate) max_dirty_bytes: usize,
+
+    /// See `VNVConfig::persist_latency_budget`
+    pub(crate) persist_latency_budget: Option<PersistLatencyBudget>,
+
+    /// Bytes drawn from the system-wide dirty budget (see `VNVHeap::set_dirty_budget_pool`)
+    #[cfg(feature = "global_dirty_budget")]
+    pub(crate) dirty_budget_reservation: Option<DirtyBudgetReservation>,
+
+    /// Maximum amount of dirty user data of background objects (`usize::MAX` if unlimited)
+    #[cfg(feature = "dirty_pools")]
+    pub(crate) background_dirty_limit: usize,
+
+    /// Emergency region of the heap (null if this manager does not belong to a heap).
+    /// Shared with `vnv_persist_all`, so it is only accessed while the lock of `heap` is held.
+    #[cfg(feature = "emergency_region")]
+    pub(crate) emergency_reserve: *mut EmergencyReserve,
+
+    /// Persist priorities of the objects (see `VNVObject::set_persist_priority`)
+    #[cfg(feature = "persist_priority")]
+    persist_priorities: PersistPriorityTable,
+
+    /// High-water marks of the heap
+    #[cfg(feature = "watermarks")]
+    pub(crate) watermarks: WatermarkState,
+
+    /// Statistics of this heap
+    pub(crate) stats: HeapStats,
+
     /// Phantom data to resident buffer, to bind its lifetime to `ResidentObjectManager`
     _resident_buffer: PhantomData<&'a mut [u8]>,
 
@@ -80,6 +193,7 @@ impl<'a, 'b, A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManage
     pub(crate) fn new(
         resident_buffer: &'a mut [u8],
         max_dirty_size: usize,
+        write_back: WriteBack,
         resident_list: &'b mut ResidentList,
         heap: SharedPersistLock<'b, *mut A>,
     ) -> Result<Self, ()> {
@@ -102,6 +216,23 @@ impl<'a, 'b, A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManage
             heap,
             remaining_dirty_size: max_dirty_size,
             object_manager: M::new(),
+            last_exhaustion: None,
+            drop_policy: DropPolicy::default(),
+            write_back,
+            withheld_dirty_size: 0,
+            max_dirty_bytes: usize::MAX,
+            persist_latency_budget: None,
+            #[cfg(feature = "global_dirty_budget")]
+            dirty_budget_reservation: None,
+            #[cfg(feature = "dirty_pools")]
+            background_dirty_limit: usize::MAX,
+            #[cfg(feature = "emergency_region")]
+            emergency_reserve: core::ptr::null_mut(),
+            #[cfg(feature = "persist_priority")]
+            persist_priorities: PersistPriorityTable::new(),
+            #[cfg(feature = "watermarks")]
+            watermarks: WatermarkState::new(max_dirty_size),
+            stats: HeapStats::new(),
             _resident_buffer: PhantomData,
 
             #[cfg(debug_assertions)]
@@ -119,17 +250,283 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
         alloc_id: &AllocationIdentifier<T>,
         enable_partial_dirtiness_tracking: bool,
         storage: &mut S,
-    ) -> Result<&mut ResidentObject<T>, ()> {
-        if let Some(metadata) = self.find_element_mut(&alloc_id) {
+    ) -> Result<&mut ResidentObject<T>, VNVError> {
+        let meta_ptr = self.require_resident_dynamic(
+            alloc_id.offset,
+            Layout::new::<T>(),
+            enable_partial_dirtiness_tracking,
+            storage,
+        )?;
+
+        let obj_ref = ResidentObjectMetadata::ptr_to_resident_obj_ptr::<T>(meta_ptr)
+            .as_mut()
+            .unwrap();
+
+        Ok(obj_ref)
+    }
+
+    /// Same as `require_resident`, but for objects whose data layout is only known at runtime
+    unsafe fn require_resident_dynamic<S: PersistentStorageModule>(
+        &mut self,
+        offset: usize,
+        layout: Layout,
+        enable_partial_dirtiness_tracking: bool,
+        storage: &mut S,
+    ) -> Result<*mut ResidentObjectMetadata, VNVError> {
+        if let Some(metadata) = self.find_element_by_offset(offset) {
+            // already resident
+            return Ok(metadata);
+        }
+
+        let meta_ptr = self.allocate_resident_dynamic(offset, layout, enable_partial_dirtiness_tracking, storage)?;
+
+        // read object data
+        let res = meta_ptr.as_mut().unwrap().load_user_data(storage).map_err(VNVError::from);
+
+        #[cfg(feature = "object_checksums")]
+        let res = res.and_then(|()| self.verify_checksum(meta_ptr, storage));
+
+        #[cfg(feature = "access_counters")]
+        let res = res.and_then(|()| Ok(meta_ptr.as_mut().unwrap().load_access_count(storage)?));
+
+        if let Err(err) = res {
+            // error: deallocate again
+            self.abort_resident(meta_ptr, enable_partial_dirtiness_tracking);
+            return Err(err);
+        }
+
+        #[cfg(feature = "object_stats")]
+        self.stats.objects.object_loaded(meta_ptr.as_ref().unwrap());
+
+        Ok(meta_ptr)
+    }
+
+    /// Same as `require_resident_dynamic`, but skips searching the resident list if `token` is still valid.
+    ///
+    /// Otherwise, `token` is updated so that the next call can skip it.
+    unsafe fn require_resident_cached<S: PersistentStorageModule>(
+        &mut self,
+        offset: usize,
+        layout: Layout,
+        enable_partial_dirtiness_tracking: bool,
+        token: Option<&Cell<ResidencyToken>>,
+        storage: &mut S,
+    ) -> Result<*mut ResidentObjectMetadata, VNVError> {
+        let token = match token {
+            Some(token) => token,
+            None => return self.require_resident_dynamic(offset, layout, enable_partial_dirtiness_tracking, storage),
+        };
+
+        if let Some(metadata) = token.get().resolve(offset) {
+            return Ok(metadata);
+        }
+
+        let meta_ptr = self.require_resident_dynamic(offset, layout, enable_partial_dirtiness_tracking, storage)?;
+        token.set(ResidencyToken::new(meta_ptr));
+        Ok(meta_ptr)
+    }
+
+    /// Makes the object resident without creating a reference (see `VNVObject::prefetch`)
+    pub(crate) unsafe fn prefetch<T: Sized, S: PersistentStorageModule>(
+        &mut self,
+        alloc_id: &AllocationIdentifier<T>,
+        token: Option<&Cell<ResidencyToken>>,
+        storage: &mut S,
+    ) -> Result<(), VNVError> {
+        self.check_integrity();
+
+        let meta_ptr = self.require_resident_cached(alloc_id.offset, Layout::new::<T>(), false, token, storage)?;
+
+        // counts as recently used, so the object is not the first one that is unloaded again
+        self.object_manager.access_object(ObjectStatusWrapper {
+            metadata: meta_ptr.as_mut().unwrap()
+        });
+
+        self.check_integrity();
+        Ok(())
+    }
+
+    /// First half of loading an object without blocking on the storage read (see `VNVHeap::load_async`).
+    ///
+    /// Returns `None` if the object is already resident. Otherwise the object is allocated in the resident buffer and
+    /// marked as in use (so that it is not unloaded while its data is read). The caller has to read the user data into
+    /// the returned object and finish the load with `finish_load` afterwards.
+    pub(crate) unsafe fn prepare_load<T: Sized, S: PersistentStorageModule>(
+        &mut self,
+        alloc_id: &AllocationIdentifier<T>,
+        enable_partial_dirtiness_tracking: bool,
+        storage: &mut S,
+    ) -> Result<Option<*mut ResidentObjectMetadata>, VNVError> {
+        self.check_integrity();
+
+        if self.find_element_mut(alloc_id).is_some() {
             // already resident
-            let res_object_ptr = ResidentObjectMetadata::ptr_to_resident_obj_ptr(metadata);
-            return Ok(res_object_ptr.as_mut().unwrap());
+            return Ok(None);
+        }
+
+        let meta_ptr = self.allocate_resident(alloc_id, enable_partial_dirtiness_tracking, storage)?;
+        meta_ptr.as_mut().unwrap().inner.status.set_is_in_use(true);
+
+        Ok(Some(meta_ptr))
+    }
+
+    /// Second half of loading an object (see `prepare_load`).
+    ///
+    /// `res` is the result of reading the user data. If it failed, the object is removed from the resident buffer again.
+    pub(crate) unsafe fn finish_load<T: Sized, S: PersistentStorageModule>(
+        &mut self,
+        meta_ptr: *mut ResidentObjectMetadata,
+        enable_partial_dirtiness_tracking: bool,
+        res: Result<(), VNVError>,
+        #[allow(unused_variables)] storage: &mut S,
+    ) -> Result<(), VNVError> {
+        meta_ptr.as_mut().unwrap().inner.status.set_is_in_use(false);
+
+        #[cfg(feature = "object_checksums")]
+        let res = res.and_then(|()| self.verify_checksum(meta_ptr, storage));
+
+        #[cfg(feature = "access_counters")]
+        let res = res.and_then(|()| Ok(meta_ptr.as_mut().unwrap().load_access_count(storage)?));
+
+        if let Err(err) = res {
+            self.abort_resident(meta_ptr, enable_partial_dirtiness_tracking);
+            return Err(err);
         }
 
-        trace!("Make object resident (offset: {})", alloc_id.offset);
+        #[cfg(feature = "object_stats")]
+        self.stats.objects.object_loaded(meta_ptr.as_ref().unwrap());
+
+        self.check_integrity();
+        Ok(())
+    }
+
+    /// Makes the objects `get_id(0..count)` resident (if not already).
+    ///
+    /// Objects that follow each other in storage (in ascending order) are read with a single storage read
+    /// into a temporary buffer (of `BATCH_READ_BUFFER_SIZE` bytes) and copied into their resident slots from there.
+    /// Objects that are too big for this buffer are loaded one by one.
+    pub(crate) unsafe fn load_many<T: Sized, S: PersistentStorageModule>(
+        &mut self,
+        count: usize,
+        get_id: impl Fn(usize) -> AllocationIdentifier<T>,
+        storage: &mut S,
+    ) -> Result<(), VNVError> {
+        let obj_size = calc_backup_obj_layout_static::<T>().size();
+        let mut buffer = [0u8; BATCH_READ_BUFFER_SIZE];
+        let mut res = Ok(());
+
+        let mut start = 0;
+        while start < count {
+            // find the run of objects [start, end) that can be read at once
+            let first_offset = get_id(start).offset;
+            let mut end = start + 1;
+            while end < count && end - start < BATCH_READ_MAX_OBJECTS {
+                let prev_offset = get_id(end - 1).offset;
+                let offset = get_id(end).offset;
+                if offset < prev_offset + obj_size || offset + obj_size - first_offset > buffer.len() {
+                    break;
+                }
+                end += 1;
+            }
+
+            if end - start == 1 {
+                // nothing to coalesce
+                if let Err(err) = self.require_resident(&get_id(start), false, storage) {
+                    res = Err(err);
+                }
+                start = end;
+                continue;
+            }
+
+            // allocate all resident slots first, they are marked as in use until the data is loaded
+            let mut prepared: u64 = 0;
+            let mut read_res = Ok(());
+            for i in start..end {
+                match self.prepare_load(&get_id(i), false, storage) {
+                    Ok(Some(_)) => prepared |= 1 << (i - start),
+                    Ok(None) => {}
+                    Err(err) => {
+                        read_res = Err(err);
+                        break;
+                    }
+                }
+            }
+            if read_res.is_err() {
+                res = read_res;
+            }
+
+            if prepared != 0 {
+                let span = get_id(end - 1).offset + obj_size - first_offset;
+                read_res = storage.read(first_offset, &mut buffer[..span]).map_err(VNVError::from);
+            }
+
+            for i in (start..end).filter(|i| prepared & (1 << (i - start)) != 0) {
+                let alloc_id = get_id(i);
+                let meta_ptr = self.find_element_mut(&alloc_id).unwrap();
+
+                let load_res = read_res.map(|()| {
+                    let buffer_offset = alloc_id.offset - first_offset + calc_backup_obj_user_data_offset();
+                    let data = meta_ptr.as_mut().unwrap().dynamic_metadata_to_data_range_mut();
+                    data.copy_from_slice(&buffer[buffer_offset..buffer_offset + data.len()]);
+                });
+
+                if let Err(err) = self.finish_load::<T, S>(meta_ptr, false, load_res, storage) {
+                    res = Err(err);
+                }
+            }
+
+            start = end;
+        }
+
+        res
+    }
+
+    /// Compares the checksum stored with the object to the checksum of its (just loaded) resident data
+    #[cfg(feature = "object_checksums")]
+    unsafe fn verify_checksum<S: PersistentStorageModule>(
+        &self,
+        meta_ptr: *mut ResidentObjectMetadata,
+        storage: &mut S,
+    ) -> Result<(), VNVError> {
+        let meta_ref = meta_ptr.as_ref().unwrap();
+        let expected = meta_ref.load_checksum(storage)?;
+        let actual = meta_ref.calc_checksum();
+
+        if expected != actual {
+            warn!(
+                "Checksum mismatch of object (offset: {}, expected: {:#x}, actual: {:#x})",
+                meta_ref.inner.offset, expected, actual
+            );
+            return Err(VNVError::CorruptedData);
+        }
+
+        Ok(())
+    }
+
+    /// Allocates space for the given (non resident) object and appends it to the resident list.
+    ///
+    /// The user data is **not** loaded yet.
+    unsafe fn allocate_resident<T: Sized, S: PersistentStorageModule>(
+        &mut self,
+        alloc_id: &AllocationIdentifier<T>,
+        enable_partial_dirtiness_tracking: bool,
+        storage: &mut S,
+    ) -> Result<*mut ResidentObjectMetadata, VNVError> {
+        self.allocate_resident_dynamic(alloc_id.offset, Layout::new::<T>(), enable_partial_dirtiness_tracking, storage)
+    }
+
+    /// Same as `allocate_resident`, but for objects whose data layout is only known at runtime
+    unsafe fn allocate_resident_dynamic<S: PersistentStorageModule>(
+        &mut self,
+        offset: usize,
+        layout: Layout,
+        enable_partial_dirtiness_tracking: bool,
+        storage: &mut S,
+    ) -> Result<*mut ResidentObjectMetadata, VNVError> {
+        trace!("Make object resident (offset: {})", offset);
 
         let (total_layout, res_obj_offset) =
-            calc_resident_obj_layout_static::<T>(enable_partial_dirtiness_tracking);
+            calc_resident_obj_layout_dynamic(&layout, enable_partial_dirtiness_tracking);
 
         let (mut obj_ptr, mut guard) = {
             // try to allocate
@@ -139,6 +536,12 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
             // during its execution, it is fine
             let guard = self.heap.try_lock().unwrap(); // (WCET analysis: resident_object_manager1, resident_object_manager2)
 
+            // reserve the emergency region again, if it is not used anymore
+            #[cfg(feature = "emergency_region")]
+            if let Some(emergency_reserve) = self.get_emergency_reserve() {
+                emergency_reserve.reacquire(guard.as_mut().unwrap());
+            }
+
             match guard.as_mut().unwrap().allocate(total_layout) {
                 Ok(res) => (res, guard),
                 Err(_) => {
@@ -153,11 +556,13 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
                         allocator: &self.heap,
                         remaining_dirty_size: &mut self.remaining_dirty_size,
                         storage,
+                        stats: &mut self.stats,
                     };
 
                     let list = ObjectManagementList::<A, S> {
                         arguments: &mut args,
                         resident_list: &mut self.resident_list,
+                        filter: None,
                     };
 
                     if let Ok(()) = self.object_manager.unload_objects::<A, S>(&total_layout, list) {
@@ -179,19 +584,39 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
                             )
                         }
                     } else {
-                        warn!(
-                            "-> Could not allocate an object with size {} in RAM",
-                            total_layout.size()
-                        );
-
-                        return Err(());
+                        // unwrap is okay here (see above)
+                        let guard = self.heap.try_lock().unwrap();
+
+                        // if all resident objects are in use, unloading objects cannot make any progress
+                        let all_in_use = self.are_all_resident_objects_in_use();
+                        #[cfg(feature = "emergency_region")]
+                        let emergency_res = if all_in_use {
+                            self.allocate_from_emergency_reserve(guard.as_mut().unwrap(), total_layout)
+                        } else {
+                            None
+                        };
+                        #[cfg(not(feature = "emergency_region"))]
+                        let emergency_res: Option<NonNull<u8>> = None;
+
+                        if let Some(res) = emergency_res {
+                            warn!(
+                                "-> All resident objects are in use, allocated {} bytes from the emergency region",
+                                total_layout.size()
+                            );
+                            (res, guard)
+                        } else {
+                            drop(guard);
+                            return Err(self.resident_exhausted(total_layout, all_in_use));
+                        }
                     }
                 }
             }
         };
 
-        let dirty_size =
-            ResidentObjectMetadata::fresh_object_dirty_size::<T>(enable_partial_dirtiness_tracking);
+        let dirty_size = ResidentObjectMetadata::fresh_object_dirty_size_dynamic(
+            enable_partial_dirtiness_tracking,
+            layout.size(),
+        );
 
         // metadata will be regarded dirty the moment the object is made persistently
         if self.remaining_dirty_size < dirty_size {
@@ -213,6 +638,7 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
                 required_bytes,
                 storage,
                 &self.heap,
+                &mut self.stats,
             )?;
 
             // reallocate
@@ -222,17 +648,24 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
         }
 
         self.remaining_dirty_size -= dirty_size;
+        #[cfg(feature = "watermarks")]
+        self.watermarks.update_dirty_watermark(self.remaining_dirty_size);
 
         // read data now and store it to the allocated region in memory
         let resident_obj_ptr = obj_ptr.as_ptr().add(res_obj_offset);
 
-        let meta_ptr = resident_obj_ptr.add(offset_of!(ResidentObject<T>, metadata))
-            as *mut ResidentObjectMetadata;
-        meta_ptr.write(ResidentObjectMetadata::new::<T>(
-            alloc_id.offset,
+        // the metadata is the first field of `ResidentObject`
+        let meta_ptr = resident_obj_ptr as *mut ResidentObjectMetadata;
+        meta_ptr.write(ResidentObjectMetadata::new_dynamic(
+            offset,
+            layout,
             enable_partial_dirtiness_tracking,
         ));
 
+        // vnv_persist_all reads the priority from the status
+        #[cfg(feature = "persist_priority")]
+        (*meta_ptr).inner.status.set_persist_priority(self.persist_priorities.get(offset));
+
         {
             // some checks and append to resident list
             let meta_ref = meta_ptr.as_mut().unwrap();
@@ -256,47 +689,214 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
         // FINISHED WITH CRITICAL ALLOCATE SECTION!
         drop(guard); // (WCET analysis: resident_object_manager2)
 
-        {
-            // read object data T
-            let obj_data_ptr = resident_obj_ptr.add(offset_of!(ResidentObject<T>, data));
-            let data_slice = slice_from_raw_parts_mut(obj_data_ptr, size_of::<T>())
-                .as_mut()
-                .unwrap();
-
-            match storage.read(
-                alloc_id.offset + calc_backup_obj_user_data_offset(),
-                data_slice,
-            ) {
-                Ok(()) => {
-                    // success
-                }
-                Err(()) => {
-                    // error: deallocate again
+        #[cfg(feature = "watermarks")]
+        self.update_resident_watermark();
+
+        Ok(meta_ptr)
+    }
+
+    /// Reverts `allocate_resident` (e.g. if the user data could not be loaded)
+    unsafe fn abort_resident(
+        &mut self,
+        meta_ptr: *mut ResidentObjectMetadata,
+        enable_partial_dirtiness_tracking: bool,
+    ) {
+        let layout = meta_ptr.as_ref().unwrap().inner.layout;
+        let (total_layout, res_obj_offset) =
+            calc_resident_obj_layout_dynamic(&layout, enable_partial_dirtiness_tracking);
+        let dirty_size = ResidentObjectMetadata::fresh_object_dirty_size_dynamic(
+            enable_partial_dirtiness_tracking,
+            layout.size(),
+        );
 
-                    // unwrap is okay here because there are no other threads concurrently accessing it
-                    // except from vnv_persist_all, but as it is guaranteed that no other threads run
-                    // during its execution, it is fine
-                    let guard = self.heap.try_lock().unwrap(); // (WCET analysis: resident_object_manager3)
+        let resident_obj_ptr = meta_ptr as *mut u8;
+        let obj_ptr = NonNull::new(resident_obj_ptr.sub(res_obj_offset)).unwrap();
 
-                    // remove previously created resident object
-                    let _ = self.resident_list.remove(meta_ptr);
+        // unwrap is okay here because there are no other threads concurrently accessing it
+        // except from vnv_persist_all, but as it is guaranteed that no other threads run
+        // during its execution, it is fine
+        let guard = self.heap.try_lock().unwrap(); // (WCET analysis: resident_object_manager3)
 
-                    guard.as_mut().unwrap().deallocate(obj_ptr, total_layout);
-                    drop(guard); // (WCET analysis: resident_object_manager3)
+        // remove previously created resident object
+        let _ = self.resident_list.remove(meta_ptr);
 
-                    self.remaining_dirty_size += dirty_size;
+        guard.as_mut().unwrap().deallocate(obj_ptr, total_layout);
+        drop(guard); // (WCET analysis: resident_object_manager3)
 
-                    self.check_integrity();
-                    return Err(());
-                }
+        self.remaining_dirty_size += dirty_size;
+
+        self.check_integrity();
+    }
+
+    /// Returns `true` if there are resident objects and all of them are in use (or pinned), so none of them can be unloaded
+    fn are_all_resident_objects_in_use(&self) -> bool {
+        let mut iter = self.resident_list.iter().peekable();
+        iter.peek().is_some() && iter.all(|item| item.inner.status.is_in_use() || item.inner.status.is_pinned())
+    }
+
+    /// Returns the emergency region of the heap or `None` if this manager does not belong to a heap
+    ///
+    /// ### Safety
+    ///
+    /// The lock of `heap` has to be held while the result is accessed
+    #[cfg(feature = "emergency_region")]
+    unsafe fn get_emergency_reserve(&self) -> Option<&mut EmergencyReserve> {
+        self.emergency_reserve.as_mut()
+    }
+
+    /// Sets the persist priority of the object at `offset`, fails if `PERSIST_PRIORITY_CAPACITY` objects already have a priority.
+    ///
+    /// The priority of a resident object is stored in its status as well. Both are updated while the lock of `heap` is held,
+    /// so `vnv_persist_all` does not run in between.
+    #[cfg(feature = "persist_priority")]
+    pub(crate) fn set_persist_priority(&mut self, offset: usize, priority: PersistPriority) -> Result<(), ()> {
+        let _guard = self.heap.try_lock().unwrap();
+        self.persist_priorities.set(offset, priority)?;
+
+        let mut iter = self.resident_list.iter_mut();
+        while let Some(mut item) = iter.next() {
+            let item_ref = item.get_element();
+            if item_ref.inner.offset == offset {
+                item_ref.inner.status.set_persist_priority(priority);
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the persist priority of the object at `offset`
+    #[cfg(feature = "persist_priority")]
+    pub(crate) fn get_persist_priority(&self, offset: usize) -> PersistPriority {
+        self.persist_priorities.get(offset)
+    }
+
+    /// Has to be called after the non-resident object at `offset` was moved to `new_offset`
+    #[cfg(feature = "persist_priority")]
+    pub(crate) fn persist_priority_object_relocated(&mut self, offset: usize, new_offset: usize) {
+        self.persist_priorities.object_relocated(offset, new_offset);
+    }
+
+    /// Has to be called after the non-resident object at `offset` was moved to another offset
+    pub(crate) fn object_relocated(&mut self, offset: usize) {
+        self.object_manager.object_deallocated(offset);
+    }
+
+    /// Has to be called after the object at `offset` was deallocated
+    #[cfg(feature = "persist_priority")]
+    pub(crate) fn persist_priority_object_deallocated(&mut self, offset: usize) {
+        self.persist_priorities.object_deallocated(offset);
+    }
+
+    /// Releases the emergency region (if available) and tries to allocate `layout` in it
+    #[cfg(feature = "emergency_region")]
+    unsafe fn allocate_from_emergency_reserve(&self, allocator: &mut A, layout: Layout) -> Option<NonNull<u8>> {
+        let emergency_reserve = self.get_emergency_reserve()?;
+        if !emergency_reserve.release(allocator) {
+            return None;
+        }
+
+        match allocator.allocate(layout) {
+            Ok(ptr) => Some(ptr),
+            Err(()) => {
+                // object does not fit, keep the region reserved
+                emergency_reserve.reacquire(allocator);
+                None
+            }
+        }
+    }
+
+    /// Records why an object with `layout` could not be made resident and returns the matching error
+    fn resident_exhausted(&mut self, layout: Layout, all_in_use: bool) -> VNVError {
+        let usage = self.get_resident_usage();
+        let reason = if usage.is_metadata_dominated() {
+            ResidentExhaustionReason::MetadataOverhead
+        } else {
+            ResidentExhaustionReason::InsufficientSpace
+        };
+        self.last_exhaustion = Some(reason);
+
+        warn!(
+            "-> Could not allocate an object with size {} in RAM ({:?}, user bytes: {}, metadata bytes: {}, all objects in use: {})",
+            layout.size(),
+            reason,
+            usage.user_bytes,
+            usage.metadata_bytes,
+            all_in_use
+        );
+
+        if all_in_use {
+            VNVError::ResidentObjectsInUse
+        } else {
+            VNVError::ResidentBufferExhausted
+        }
+    }
+
+    /// Reserves a region with `layout` in the resident buffer that is used
+    /// if all resident objects are in use. Objects that are not in use are unloaded to make space.
+    ///
+    /// A previously reserved region is removed. If `layout` is `None`, no new region is reserved.
+    #[cfg(feature = "emergency_region")]
+    pub(crate) fn set_emergency_region<S: PersistentStorageModule>(
+        &mut self,
+        layout: Option<Layout>,
+        storage: &mut S,
+    ) -> Result<(), VNVError> {
+        {
+            // unwrap is okay here because there are no other threads concurrently accessing it
+            // except from vnv_persist_all, but as it is guaranteed that no other threads run
+            // during its execution, it is fine
+            let guard = self.heap.try_lock().unwrap();
+            match unsafe { self.get_emergency_reserve() } {
+                Some(emergency_reserve) => unsafe { emergency_reserve.clear(guard.as_mut().unwrap()) },
+                None => return Err(VNVError::Unsupported),
+            }
+        }
+
+        let layout = match layout {
+            Some(layout) => layout,
+            None => return Ok(()),
+        };
+
+        let mut guard = self.heap.try_lock().unwrap();
+        let mut res = unsafe { guard.as_mut().unwrap().allocate(layout) };
+        if res.is_err() {
+            drop(guard);
+
+            let mut args = ObjectManagementListArguments {
+                allocator: &self.heap,
+                remaining_dirty_size: &mut self.remaining_dirty_size,
+                storage,
+                stats: &mut self.stats,
+            };
+
+            let list = ObjectManagementList::<A, S> {
+                arguments: &mut args,
+                resident_list: self.resident_list,
+                filter: None,
             };
+
+            let unload_res = self.object_manager.unload_objects::<A, S>(&layout, list);
+            self.check_integrity();
+            if unload_res.is_err() {
+                return Err(VNVError::ResidentBufferExhausted);
+            }
+
+            guard = self.heap.try_lock().unwrap();
+            res = unsafe { guard.as_mut().unwrap().allocate(layout) };
         }
 
-        let obj_ref = (resident_obj_ptr as *mut ResidentObject<T>)
-            .as_mut()
-            .unwrap();
+        // set region while the lock is still held, so vnv_persist_all restores it as well
+        let ptr = res.map_err(|()| VNVError::ResidentBufferExhausted)?;
+        unsafe { self.get_emergency_reserve().unwrap().set(ptr, layout) };
+        drop(guard);
 
-        Ok(obj_ref)
+        Ok(())
+    }
+
+    #[cfg(feature = "emergency_region")]
+    pub(crate) fn is_emergency_region_available(&self) -> bool {
+        let _guard = self.heap.try_lock().unwrap();
+        unsafe { self.get_emergency_reserve() }.is_some_and(|emergency_reserve| emergency_reserve.is_available())
     }
 
     pub(crate) fn unload_object<T: Sized, S: PersistentStorageModule>(
@@ -304,7 +904,7 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
         alloc_id: &AllocationIdentifier<T>,
         storage: &mut S,
         user_partial_dirtiness_tracking: bool,
-    ) -> Result<(), ()> {
+    ) -> Result<(), VNVError> {
         self.check_integrity();
 
         let mut iter = self.resident_list.iter_mut();
@@ -313,21 +913,23 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
                 // element found
                 {
                     let element_ref = element.get_element();
-                    if element_ref.inner.status.is_in_use() {
-                        return Err(());
+                    if element_ref.inner.status.is_in_use() || element_ref.inner.status.is_pinned() {
+                        return Err(VNVError::ObjectInUse);
                     }
                 }
 
+                // fails without changes if the storage is locked (e.g. by a pending async load)
                 unsafe {
                     ResidentObject::<T>::unload_resident_object(
                         element,
                         storage,
                         &mut self.heap,
                         &mut self.remaining_dirty_size,
+                        &mut self.stats,
                         false,
                         user_partial_dirtiness_tracking,
                     )
-                    .expect("unloading should succeed")
+                    .map_err(|_| VNVError::StorageError)?
                 };
 
                 break;
@@ -336,29 +938,226 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
 
         self.check_integrity();
 
-        return Ok(());
+        return Ok(());
+    }
+
+    /// Same as `unload_object`, but for objects whose layout is only known at runtime
+    pub(crate) fn unload_object_dynamic<S: PersistentStorageModule>(
+        &mut self,
+        offset: usize,
+        storage: &mut S,
+    ) -> Result<(), VNVError> {
+        self.check_integrity();
+
+        let mut iter = self.resident_list.iter_mut();
+        while let Some(mut element) = iter.next() {
+            if element.get_element().inner.offset == offset {
+                let status = element.get_element().inner.status;
+                if status.is_in_use() || status.is_pinned() {
+                    return Err(VNVError::ObjectInUse);
+                }
+
+                unsafe {
+                    ResidentObjectMetadata::unload_resident_object_dynamic(
+                        element,
+                        storage,
+                        &self.heap,
+                        &mut self.remaining_dirty_size,
+                        &mut self.stats,
+                    )
+                }?;
+
+                break;
+            }
+        }
+
+        self.check_integrity();
+        Ok(())
+    }
+
+    /// Makes the object resident and prevents it from being unloaded until `unpin` is called.
+    ///
+    /// Fails with `PinnedBytesExhausted` if only pinned objects are left and this one does not fit anymore.
+    pub(crate) fn pin<T: Sized, S: PersistentStorageModule>(
+        &mut self,
+        alloc_id: &AllocationIdentifier<T>,
+        storage: &mut S,
+    ) -> Result<(), VNVError> {
+        self.check_integrity();
+
+        if let Some(ptr) = unsafe { self.find_element_mut(alloc_id) } {
+            if unsafe { ptr.as_ref().unwrap() }.inner.status.is_pinned() {
+                // already pinned
+                return Ok(());
+            }
+        }
+
+        let res: Result<*mut ResidentObject<T>, VNVError> = unsafe { self.require_resident(alloc_id, false, storage) }
+            .map(|obj| obj as *mut ResidentObject<T>);
+        let obj_ptr = match res {
+            Ok(ptr) => ptr,
+            Err(VNVError::ResidentObjectsInUse | VNVError::ResidentBufferExhausted)
+                if self.resident_list.iter().all(|item| item.inner.status.is_pinned()) =>
+            {
+                // only pinned objects are left, so there is no space for this one
+                return Err(VNVError::PinnedBytesExhausted);
+            }
+            Err(err) => return Err(err),
+        };
+        unsafe { obj_ptr.as_mut().unwrap() }.metadata.inner.status.set_pinned(true);
+
+        self.check_integrity();
+        Ok(())
+    }
+
+    /// Allows the object to be unloaded again (see `pin`)
+    pub(crate) fn unpin<T: Sized>(&mut self, alloc_id: &AllocationIdentifier<T>) {
+        if let Some(ptr) = unsafe { self.find_element_mut(alloc_id) } {
+            unsafe { ptr.as_mut().unwrap() }.inner.status.set_pinned(false);
+        }
+    }
+
+    pub(crate) fn is_pinned<T: Sized>(&mut self, alloc_id: &AllocationIdentifier<T>) -> bool {
+        match unsafe { self.find_element_mut(alloc_id) } {
+            Some(ptr) => unsafe { ptr.as_ref().unwrap() }.inner.status.is_pinned(),
+            None => false,
+        }
+    }
+
+    /// Writes the dirty data of the object back to storage and returns how many dirty bytes are available again
+    pub(crate) fn flush_object<T: Sized, S: PersistentStorageModule>(
+        &mut self,
+        alloc_id: &AllocationIdentifier<T>,
+        storage: &mut S,
+    ) -> Result<usize, VNVError> {
+        self.check_integrity();
+
+        let mut cleaned_bytes = 0;
+        if let Some(ptr) = unsafe { self.find_element_mut(alloc_id) } {
+            // object is resident
+            let ptr: *mut ResidentObject<T> = unsafe { ResidentObjectMetadata::ptr_to_resident_obj_ptr(ptr) };
+            let data = unsafe { ptr.as_mut().unwrap() };
+            
+            if data.metadata.inner.status.is_data_dirty() {
+                cleaned_bytes = unsafe { data.persist_user_data(storage, &mut self.stats) }?;
+                self.remaining_dirty_size += cleaned_bytes;
+            }
+
+            self.check_integrity();
+        }
+
+        return Ok(cleaned_bytes);
+    }
+
+    /// Writes the dirty data of all resident objects back to storage and returns how many dirty bytes are available again.
+    ///
+    /// Fails with `VNVError::ObjectInUse` if an object is still mutably borrowed.
+    pub(crate) fn flush_all<S: PersistentStorageModule>(&mut self, storage: &mut S) -> Result<usize, VNVError> {
+        self.check_integrity();
+
+        let mut cleaned_bytes = 0;
+        let mut iter = self.resident_list.iter_mut();
+        while let Some(mut item) = iter.next() {
+            let meta_ref = item.get_element();
+            if !meta_ref.inner.status.is_data_dirty() {
+                continue;
+            }
+            if meta_ref.inner.status.is_mutable_ref_active() {
+                return Err(VNVError::ObjectInUse);
+            }
+
+            let size = unsafe { meta_ref.persist_user_data_dynamic(storage, &mut self.stats) }?;
+            self.remaining_dirty_size += size;
+            cleaned_bytes += size;
+        }
+
+        self.check_integrity();
+        Ok(cleaned_bytes)
+    }
+
+    /// Lets the object manager write back up to `budget_bytes` of dirty data (see `VNVHeap::clean`).
+    ///
+    /// Returns how many dirty bytes are available again.
+    pub(crate) fn clean<S: PersistentStorageModule>(&mut self, budget_bytes: usize, storage: &mut S) -> usize {
+        self.check_integrity();
+
+        let prev_dirty_size = self.remaining_dirty_size;
+        let mut args = ObjectManagementListArguments {
+            remaining_dirty_size: &mut self.remaining_dirty_size,
+            storage,
+            allocator: &self.heap,
+            stats: &mut self.stats,
+        };
+
+        let list = ObjectManagementList::<A, S> {
+            arguments: &mut args,
+            resident_list: self.resident_list,
+            filter: None,
+        };
+        self.object_manager.clean_dirty_data::<A, S>(budget_bytes, list);
+
+        self.check_integrity();
+        self.remaining_dirty_size - prev_dirty_size
     }
 
-    pub(crate) fn flush_object<T: Sized, S: PersistentStorageModule>(
+    /// Returns how many bytes can be dirty in total (i.e. `max_dirty_bytes` of the heap)
+    pub(crate) fn get_dirty_budget(&self) -> usize {
+        let mut dirty_size = 0;
+
+        let mut iter = self.resident_list.iter();
+        while let Some(item) = iter.next() {
+            dirty_size += item.dirty_size();
+        }
+
+        dirty_size + self.remaining_dirty_size
+    }
+
+    /// Throws away all unsynchronized changes of this object and reloads its last synchronized state.
+    ///
+    /// The dirty bytes of the object are available again afterwards.
+    /// This is only supported for types that do not need to be dropped and for objects
+    /// whose data was written to storage at least once.
+    pub(crate) fn discard_changes<T: Sized, S: PersistentStorageModule>(
         &mut self,
         alloc_id: &AllocationIdentifier<T>,
         storage: &mut S,
-    ) -> Result<(), ()> {
+    ) -> Result<(), VNVError> {
+        if core::mem::needs_drop::<T>() {
+            // the discarded value would never be dropped
+            return Err(VNVError::Unsupported);
+        }
+
         self.check_integrity();
 
-        if let Some(ptr) = unsafe { self.find_element_mut(alloc_id) } {
-            // object is resident
-            let ptr: *mut ResidentObject<T> = unsafe { ResidentObjectMetadata::ptr_to_resident_obj_ptr(ptr) };
-            let data = unsafe { ptr.as_mut().unwrap() };
-            
-            if data.metadata.inner.status.is_data_dirty() {
-                self.remaining_dirty_size += unsafe { data.persist_user_data(storage) }?;
+        let meta_ref = match unsafe { self.find_element_mut(alloc_id) } {
+            Some(ptr) => unsafe { ptr.as_mut().unwrap() },
+            None => {
+                // object is not resident, so nothing can be dirty
+                return Ok(());
             }
+        };
 
-            self.check_integrity();
+        if meta_ref.inner.status.is_in_use() {
+            return Err(VNVError::ObjectInUse);
         }
 
-        return Ok(());
+        let prev_dirty_size = meta_ref.dirty_size();
+        unsafe { meta_ref.discard_user_data_dynamic(storage) }?;
+        self.remaining_dirty_size += prev_dirty_size - meta_ref.dirty_size();
+
+        #[cfg(feature = "object_checksums")]
+        {
+            let meta_ptr = meta_ref as *mut ResidentObjectMetadata;
+            if let Err(err) = unsafe { self.verify_checksum(meta_ptr, storage) } {
+                // the reloaded data is corrupted, so it must not stay resident
+                let partial = meta_ref.inner.status.is_partial_dirtiness_tracking_enabled();
+                unsafe { self.abort_resident(meta_ptr, partial) };
+                return Err(err);
+            }
+        }
+
+        self.check_integrity();
+        Ok(())
     }
 
     pub(crate) fn try_to_allocate<T>(
@@ -389,6 +1188,8 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
         let res_ptr = unsafe { res_ptr.as_ptr().add(resident_metadata_rel_offset) };
 
         self.remaining_dirty_size -= dirty_size;
+        #[cfg(feature = "watermarks")]
+        self.watermarks.update_dirty_watermark(self.remaining_dirty_size);
 
         // read data now and store it to the allocated region in memory
         let ptr = res_ptr as *mut ResidentObjectMetadata;
@@ -398,6 +1199,12 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
             use_partial_dirtiness_tracking,
         );
         metadata.inner.status.set_data_dirty(true);
+        metadata.inner.status.set_backup_missing(true);
+
+        // the access counter was not written to storage yet
+        #[cfg(feature = "access_counters")]
+        metadata.inner.status.set_access_count_dirty(true);
+
         unsafe { ptr.write(metadata) };
 
         {
@@ -420,13 +1227,20 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
             );
 
             unsafe { self.resident_list.insert(obj_ref) };
+
+            #[cfg(feature = "object_stats")]
+            self.stats.objects.object_created(obj_ref);
         }
 
         drop(guard); // (WCET analysis: resident_object_manager4)
 
+        #[cfg(feature = "watermarks")]
+        self.update_resident_watermark();
+
         // now fill the object with data
         let object_ref = unsafe { (ptr as *mut ResidentObject<T>).as_mut().unwrap() };
-        object_ref.data = data;
+        // do not use an assignment here, as it would drop the uninitialized previous value
+        unsafe { core::ptr::write(&mut object_ref.data, data) };
 
         // finished successfully
         // mark object as modified and accessed
@@ -448,13 +1262,36 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
         alloc_id: &AllocationIdentifier<T>,
         use_partial_dirtiness_tracking: bool,
         storage: &mut S,
-    ) -> Result<(), ()> {
+    ) -> Result<DropOutcome, VNVError> {
         self.check_integrity();
+        self.object_manager.object_deallocated(alloc_id.offset);
+
         if core::mem::needs_drop::<T>() {
             // require resident to drop object in memory
-            let _ = unsafe {
+            let mut res = unsafe {
                 self.require_resident(alloc_id, use_partial_dirtiness_tracking, storage)
-            }?;
+            }
+            .map(|_| ());
+
+            if res.is_err() && self.drop_policy == DropPolicy::ForceEvictOthers {
+                debug!("Could not make object resident to drop it, unload all other objects...");
+                unsafe { self.unload_all_unused(storage) }?;
+                res = unsafe {
+                    self.require_resident(alloc_id, use_partial_dirtiness_tracking, storage)
+                }
+                .map(|_| ());
+            }
+
+            if let Err(err) = res {
+                if self.drop_policy == DropPolicy::LeakWithoutDrop {
+                    warn!("Could not make object resident to drop it, deallocate it without dropping (offset: {})", alloc_id.offset);
+                    self.check_integrity();
+                    return Ok(DropOutcome::LeakedWithoutDrop);
+                }
+
+                warn!("Could not make object resident to drop it, keep it alive (offset: {})", alloc_id.offset);
+                return Err(err);
+            }
         }
 
         let mut iter_mut = self.resident_list.iter_mut();
@@ -467,18 +1304,25 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
 
             if found {
                 unsafe {
+                    if core::mem::needs_drop::<T>() {
+                        // the data is not synced anymore, so it can be dropped in place
+                        let resident_obj_ptr = ResidentObjectMetadata::ptr_to_resident_obj_ptr::<T>(curr.get_element());
+                        core::ptr::drop_in_place(&mut (*resident_obj_ptr).data);
+                    }
+
                     ResidentObject::<T>::unload_resident_object(
                         curr,
                         storage,
                         &mut self.heap,
                         &mut self.remaining_dirty_size,
+                        &mut self.stats,
                         true,
                         use_partial_dirtiness_tracking,
                     )?
                 }
 
                 self.check_integrity();
-                return Ok(());
+                return Ok(DropOutcome::Dropped);
             }
         }
 
@@ -488,14 +1332,90 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
         if core::mem::needs_drop::<T>() {
             // should not happen: object should be made resident and dropped in RAM
             debug_assert!(false, "Should not happen");
-            Err(())
+            Err(VNVError::ResidentBufferExhausted)
         } else {
             self.check_integrity();
 
             // everything is fine, object was not resident
             // but does not need to be dropped (because T does not require so)
-            Ok(())
+            Ok(DropOutcome::Dropped)
+        }
+    }
+
+    /// Same as `drop`, but for objects whose data does not have to be dropped and whose layout is only known at runtime.
+    ///
+    /// Unsynchronized changes are thrown away.
+    pub(crate) fn drop_dynamic(&mut self, offset: usize) {
+        self.check_integrity();
+        self.object_manager.object_deallocated(offset);
+
+        let mut iter_mut = self.resident_list.iter_mut();
+        while let Some(mut curr) = iter_mut.next() {
+            if curr.get_element().inner.offset == offset {
+                let prev_dirty_size = curr.get_element().dirty_size();
+                unsafe { ResidentObjectMetadata::remove_resident_object_dynamic(curr, &self.heap) };
+                self.remaining_dirty_size += prev_dirty_size;
+                break;
+            }
+        }
+
+        self.check_integrity();
+    }
+
+    /// Returns `true` if the object at `offset` is resident and in use or pinned
+    #[cfg(feature = "gc")]
+    pub(crate) fn is_in_use_dynamic(&mut self, offset: usize) -> bool {
+        match unsafe { self.find_element_by_offset(offset) } {
+            Some(ptr) => {
+                let status = &unsafe { ptr.as_ref().unwrap() }.inner.status;
+                status.is_in_use() || status.is_pinned()
+            }
+            None => false,
+        }
+    }
+
+    /// Copies the data of the object at `offset` (starting at byte `start`) to `data` if it is resident
+    /// and returns whether it is resident.
+    ///
+    /// Fails with `ObjectInUse` if the object is mutably borrowed, as its data could be modified right now.
+    pub(crate) fn read_resident_data(&mut self, offset: usize, start: usize, data: &mut [u8]) -> Result<bool, VNVError> {
+        let meta = match unsafe { self.find_element_by_offset(offset) } {
+            Some(ptr) => unsafe { ptr.as_ref().unwrap() },
+            None => return Ok(false),
+        };
+        if meta.inner.status.is_mutable_ref_active() {
+            return Err(VNVError::ObjectInUse);
+        }
+
+        let range = unsafe { meta.dynamic_metadata_to_data_range() };
+        let range = match range.get(start..start + data.len()) {
+            Some(range) => range,
+            None => return Err(VNVError::Unsupported),
+        };
+        data.copy_from_slice(range);
+        Ok(true)
+    }
+
+    /// Checks that accessing the object at `offset` does not read from or write to storage.
+    ///
+    /// Fails with `NotResident` if the object is not resident. If `mutable` is set and the object is not dirty yet,
+    /// this fails with `DirtyBudgetExhausted` if other objects would have to be written back to make it dirty.
+    pub(crate) fn check_resident_access(
+        &mut self,
+        offset: usize,
+        mutable: bool,
+        token: Option<&Cell<ResidencyToken>>,
+    ) -> Result<(), VNVError> {
+        let meta_ptr = match token.and_then(|token| token.get().resolve(offset)) {
+            Some(meta_ptr) => meta_ptr,
+            None => unsafe { self.find_element_by_offset(offset) }.ok_or(VNVError::NotResident)?,
+        };
+
+        let meta_ref = unsafe { meta_ptr.as_ref().unwrap() };
+        if mutable && !meta_ref.inner.status.is_data_dirty() && self.remaining_dirty_size < meta_ref.inner.layout.size() {
+            return Err(VNVError::DirtyBudgetExhausted);
         }
+        Ok(())
     }
 
     pub(crate) fn is_resident<T>(&mut self, identifier: &AllocationIdentifier<T>) -> bool {
@@ -515,16 +1435,41 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
         &mut self,
         identifier: &AllocationIdentifier<T>,
         use_partial_dirtiness_tracking: bool,
+        token: Option<&Cell<ResidencyToken>>,
+        storage: &mut S,
+    ) -> Result<*mut T, VNVError> {
+        let meta_ptr = self.get_mut_dynamic(
+            identifier.offset,
+            Layout::new::<T>(),
+            use_partial_dirtiness_tracking,
+            token,
+            storage,
+        )?;
+
+        let obj_ref = ResidentObjectMetadata::ptr_to_resident_obj_ptr::<T>(meta_ptr)
+            .as_mut()
+            .unwrap();
+        Ok(&mut obj_ref.data)
+    }
+
+    /// Same as `get_mut`, but for objects whose data layout is only known at runtime.
+    ///
+    /// Returns the metadata of the object, use `dynamic_metadata_to_data_range_mut` to access its data.
+    pub(crate) unsafe fn get_mut_dynamic<S: PersistentStorageModule>(
+        &mut self,
+        offset: usize,
+        layout: Layout,
+        use_partial_dirtiness_tracking: bool,
+        token: Option<&Cell<ResidencyToken>>,
         storage: &mut S,
-    ) -> Result<*mut T, ()> {
+    ) -> Result<*mut ResidentObjectMetadata, VNVError> {
         self.check_integrity();
-        trace!("Get mutable reference (offset={})", identifier.offset);
+        trace!("Get mutable reference (offset={})", offset);
 
-        let obj_ref: *mut ResidentObject<T> =
-            self.require_resident(identifier, use_partial_dirtiness_tracking, storage)?;
+        let meta_ptr = self.require_resident_cached(offset, layout, use_partial_dirtiness_tracking, token, storage)?;
 
-        let bytes_to_sync = {
-            let meta_ref = &mut obj_ref.as_mut().unwrap().metadata;
+        {
+            let meta_ref = meta_ptr.as_mut().unwrap();
 
             // should be ensured by the rust compiler
             debug_assert!(
@@ -535,7 +1480,46 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
                 !meta_ref.inner.status.is_mutable_ref_active(),
                 "This object should not have any mutable references!"
             );
+        }
+
+        self.make_data_dirty(meta_ptr, storage)?;
+
+        let meta_ref = meta_ptr.as_mut().unwrap();
+        meta_ref.inner.status.set_is_in_use(true);
+        meta_ref.inner.status.set_is_mutable_ref_active(true);
+
+        // the data has to be written back again by `vnv_persist_budget`
+        meta_ref.inner.status.set_written_back(false);
+
+        #[cfg(feature = "access_counters")]
+        meta_ref.record_access();
+
+        self.check_integrity();
+
+        // finished successfully
+        // mark object as modified and accessed
+        self.object_manager.access_object(ObjectStatusWrapper {
+            metadata: meta_ref
+        });
+        self.object_manager.modify_object(ObjectStatusWrapper {
+            metadata: meta_ref
+        });
+
+        self.prefetch_predicted(storage);
+
+        Ok(meta_ptr)
+    }
 
+    /// Makes the whole data of the resident object dirty (if it is not dirty yet).
+    ///
+    /// If not enough dirty bytes are left, other objects are synced first.
+    pub(crate) unsafe fn make_data_dirty<S: PersistentStorageModule>(
+        &mut self,
+        meta_ptr: *mut ResidentObjectMetadata,
+        storage: &mut S,
+    ) -> Result<(), VNVError> {
+        let bytes_to_sync = {
+            let meta_ref = meta_ptr.as_mut().unwrap();
             if !meta_ref.inner.status.is_data_dirty()
                 && self.remaining_dirty_size < meta_ref.inner.layout.size()
             {
@@ -549,59 +1533,77 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
 
         // its IMPORTANT here that we don't have any open reference to a ResidentObject/ResidentObjectMetadata anymore
         if bytes_to_sync != 0 {
+            // mark as in use for now, so that this object won't get unloaded while making space
+            let was_in_use = meta_ptr.as_ref().unwrap().inner.status.is_in_use();
+            meta_ptr.as_mut().unwrap().inner.status.set_is_in_use(true);
+
             // sync data now
-            sync_dirty_data::<A, S, M>(
+            let res = sync_dirty_data::<A, S, M>(
                 &mut self.remaining_dirty_size,
                 self.resident_list,
                 &mut self.object_manager,
                 bytes_to_sync,
                 storage,
                 &self.heap,
-            )?;
+                &mut self.stats,
+            );
+
+            meta_ptr.as_mut().unwrap().inner.status.set_is_in_use(was_in_use);
+            res?;
         }
 
-        let obj_ref = obj_ref.as_mut().unwrap();
-        let meta_ref = &mut obj_ref.metadata;
+        let meta_ref = meta_ptr.as_mut().unwrap();
 
         if !meta_ref.inner.status.is_data_dirty() {
             assert!(self.remaining_dirty_size >= meta_ref.inner.layout.size());
 
             // make dirty
             self.remaining_dirty_size -= meta_ref.inner.layout.size();
+            #[cfg(feature = "watermarks")]
+            self.watermarks.update_dirty_watermark(self.remaining_dirty_size);
             meta_ref.inner.status.set_data_dirty(true);
-        }
-
-        meta_ref.inner.status.set_is_in_use(true);
-        meta_ref.inner.status.set_is_mutable_ref_active(true);
-        self.check_integrity();
 
-        // finished successfully
-        // mark object as modified and accessed
-        self.object_manager.access_object(ObjectStatusWrapper {
-            metadata: meta_ref
-        });
-        self.object_manager.modify_object(ObjectStatusWrapper {
-            metadata: meta_ref
-        });
+            // the dirty data belongs to the foreground pool, unless `get_mut_background` moves it
+            #[cfg(feature = "dirty_pools")]
+            meta_ref.inner.status.set_background(false);
+        }
 
-        Ok(&mut obj_ref.data)
+        Ok(())
     }
 
     pub(crate) unsafe fn get_partial_mut<T: Sized, S: PersistentStorageModule>(
         &mut self,
         identifier: &AllocationIdentifier<T>,
         storage: &mut S,
-    ) -> Result<(*mut ResidentObjectMetadata, *mut T), ()> {
+    ) -> Result<(*mut ResidentObjectMetadata, *mut T), VNVError> {
+        let meta_ptr = self.get_clean_mut_dynamic(identifier.offset, Layout::new::<T>(), true, storage)?;
+
+        let obj_ref = ResidentObjectMetadata::ptr_to_resident_obj_ptr::<T>(meta_ptr)
+            .as_mut()
+            .unwrap();
+        Ok((&mut obj_ref.metadata, &mut obj_ref.data))
+    }
+
+    /// Same as `get_partial_mut`, but for objects whose data layout is only known at runtime.
+    ///
+    /// The object is not made dirty, use `partial_mut_make_range_dirty` (or `make_data_dirty` without
+    /// partial dirtiness tracking) before modifying it.
+    /// Returns the metadata of the object, use `dynamic_metadata_to_data_range_mut` to access its data.
+    pub(crate) unsafe fn get_clean_mut_dynamic<S: PersistentStorageModule>(
+        &mut self,
+        offset: usize,
+        layout: Layout,
+        use_partial_dirtiness_tracking: bool,
+        storage: &mut S,
+    ) -> Result<*mut ResidentObjectMetadata, VNVError> {
         self.check_integrity();
         trace!(
             "Get partial mutable reference (offset={})",
-            identifier.offset
+            offset
         );
 
-        let obj_ref: *mut ResidentObject<T> = self.require_resident(identifier, true, storage)?;
-
-        let obj_ref = obj_ref.as_mut().unwrap();
-        let meta_ref = &mut obj_ref.metadata;
+        let meta_ptr = self.require_resident_dynamic(offset, layout, use_partial_dirtiness_tracking, storage)?;
+        let meta_ref = meta_ptr.as_mut().unwrap();
 
         // Should be enforced by the rust compiler (as long the VNVList is implemented correctly)
         debug_assert!(!meta_ref.inner.status.is_in_use(), "Should not be in use!");
@@ -612,6 +1614,13 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
 
         meta_ref.inner.status.set_is_in_use(true);
         meta_ref.inner.status.set_is_mutable_ref_active(true);
+
+        // the data has to be written back again by `vnv_persist_budget`
+        meta_ref.inner.status.set_written_back(false);
+
+        #[cfg(feature = "access_counters")]
+        meta_ref.record_access();
+
         self.check_integrity();
 
         // finished successfully
@@ -623,7 +1632,7 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
             metadata: meta_ref
         });
 
-        Ok((&mut obj_ref.metadata, &mut obj_ref.data))
+        Ok(meta_ptr)
     }
 
     pub(crate) fn partial_mut_make_range_dirty<S: PersistentStorageModule>(
@@ -632,7 +1641,7 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
         addr_offset: usize,
         size: usize,
         storage: &mut S,
-    ) -> Result<(), ()> {
+    ) -> Result<(), VNVError> {
         self.check_integrity();
 
         // some important checks
@@ -664,6 +1673,7 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
                     bytes_to_sync,
                     storage,
                     &self.heap,
+                    &mut self.stats,
                 )?;
             }
         }
@@ -675,6 +1685,8 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
             dirty_size
         );
         self.remaining_dirty_size -= dirty_size;
+        #[cfg(feature = "watermarks")]
+        self.watermarks.update_dirty_watermark(self.remaining_dirty_size);
 
         // yay, we can make all bytes in the range dirty
         wrapper.set_range_dirty(addr_offset, size);
@@ -694,9 +1706,10 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
         Ok(())
     }
 
-    pub(crate) unsafe fn release_partial_mut<T: Sized>(
+    pub(crate) unsafe fn release_partial_mut<T: Sized, S: PersistentStorageModule>(
         &mut self,
         meta_ptr: *mut ResidentObjectMetadata,
+        storage: &mut S,
     ) {
         self.check_integrity();
         let meta_ref = meta_ptr.as_mut().unwrap();
@@ -710,6 +1723,7 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
 
         meta_ref.status.set_is_in_use(false);
         meta_ref.status.set_is_mutable_ref_active(false);
+        self.write_back_released(meta_ptr, storage);
         self.check_integrity();
     }
 
@@ -717,14 +1731,40 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
         &mut self,
         identifier: &AllocationIdentifier<T>,
         use_partial_dirtiness_tracking: bool,
+        token: Option<&Cell<ResidencyToken>>,
+        storage: &mut S,
+    ) -> Result<*const T, VNVError> {
+        let meta_ptr = self.get_ref_dynamic(
+            identifier.offset,
+            Layout::new::<T>(),
+            use_partial_dirtiness_tracking,
+            token,
+            storage,
+        )?;
+
+        let obj_ref = ResidentObjectMetadata::ptr_to_resident_obj_ptr::<T>(meta_ptr)
+            .as_ref()
+            .unwrap();
+        Ok(&obj_ref.data)
+    }
+
+    /// Same as `get_ref`, but for objects whose data layout is only known at runtime.
+    ///
+    /// Returns the metadata of the object, use `dynamic_metadata_to_data_range` to access its data.
+    pub(crate) unsafe fn get_ref_dynamic<S: PersistentStorageModule>(
+        &mut self,
+        offset: usize,
+        layout: Layout,
+        use_partial_dirtiness_tracking: bool,
+        token: Option<&Cell<ResidencyToken>>,
         storage: &mut S,
-    ) -> Result<*const T, ()> {
+    ) -> Result<*mut ResidentObjectMetadata, VNVError> {
         self.check_integrity();
-        trace!("Get mutable reference (offset={})", identifier.offset);
+        trace!("Get mutable reference (offset={})", offset);
 
-        let obj_ref: *mut ResidentObject<T> = self.require_resident(identifier, use_partial_dirtiness_tracking, storage)?;
-        let obj_ref = obj_ref.as_mut().unwrap();
-        let meta_ref = &mut obj_ref.metadata.inner;
+        let meta_ptr = self.require_resident_cached(offset, layout, use_partial_dirtiness_tracking, token, storage)?;
+        let meta = meta_ptr.as_mut().unwrap();
+        let meta_ref = &mut meta.inner;
 
         debug_assert!(
             !meta_ref.status.is_in_use(),
@@ -737,16 +1777,43 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
 
         meta_ref.status.set_is_in_use(true);
 
+        #[cfg(feature = "access_counters")]
+        meta.record_access();
+
         // finished successfully
         // mark object as accessed
         self.object_manager.access_object(ObjectStatusWrapper {
-            metadata: &mut obj_ref.metadata
+            metadata: meta
         });
 
-        Ok(&obj_ref.data)
+        self.prefetch_predicted(storage);
+
+        Ok(meta_ptr)
+    }
+
+    /// Makes the object that the object management module expects to be accessed next resident (if any).
+    ///
+    /// This is only an optimization, so errors are ignored.
+    unsafe fn prefetch_predicted<S: PersistentStorageModule>(&mut self, storage: &mut S) {
+        if let Some(location) = self.object_manager.predict_next_access() {
+            let res = self.require_resident_dynamic(
+                location.offset,
+                location.layout,
+                location.partial_dirtiness_tracking,
+                storage,
+            );
+            if let Err(err) = res {
+                debug!("Could not prefetch object (offset: {}): {:?}", location.offset, err);
+            }
+            self.check_integrity();
+        }
     }
 
-    pub(crate) unsafe fn release_mut<T: Sized>(&mut self, identifier: &AllocationIdentifier<T>) {
+    pub(crate) unsafe fn release_mut<T: Sized, S: PersistentStorageModule>(
+        &mut self,
+        identifier: &AllocationIdentifier<T>,
+        storage: &mut S,
+    ) {
         self.check_integrity();
         trace!("Release mutable reference (offset={})", identifier.offset);
         if let Some(meta_ptr) = self.find_element_mut(identifier) {
@@ -757,6 +1824,7 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
 
             meta_ref.status.set_is_in_use(false);
             meta_ref.status.set_is_mutable_ref_active(false);
+            self.write_back_released(meta_ptr, storage);
         } else {
             // nothing to do, as references are not tracked for nonresident objects
             // should not happen anyway...
@@ -769,6 +1837,33 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
         self.check_integrity();
     }
 
+    /// Writes the object back to storage after its mutable reference was released (see `WriteBack`).
+    ///
+    /// As this is called while dropping the reference, errors cannot be returned.
+    /// If writing back fails, the object just stays dirty and is written back lazily.
+    unsafe fn write_back_released<S: PersistentStorageModule>(
+        &mut self,
+        meta_ptr: *mut ResidentObjectMetadata,
+        storage: &mut S,
+    ) {
+        if self.write_back == WriteBack::Lazy {
+            return;
+        }
+
+        let meta_ref = meta_ptr.as_mut().unwrap();
+        match meta_ref.persist_user_data_dynamic(storage, &mut self.stats) {
+            Ok(size_persisted) => self.remaining_dirty_size += size_persisted,
+            Err(()) => {
+                warn!("Could not write back object (offset={})", meta_ref.inner.offset);
+                return;
+            }
+        }
+
+        if self.write_back == WriteBack::Immediate && storage.flush().is_err() {
+            warn!("Could not flush storage after writing back object (offset={})", meta_ref.inner.offset);
+        }
+    }
+
     pub(crate) unsafe fn release_ref<T: Sized>(&mut self, identifier: &AllocationIdentifier<T>) {
         self.check_integrity();
         trace!("Release immutable reference (offset={})", identifier.offset);
@@ -791,10 +1886,100 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
         self.check_integrity();
     }
 
+    /// Clears the in use flags of all resident objects.
+    ///
+    /// Returns the number of objects that were marked as in use.
+    ///
+    /// ### Safety
+    ///
+    /// There must not be any open references to resident objects.
+    /// Unloads all resident objects that are not in use, without asking the object management module
+    unsafe fn unload_all_unused<S: PersistentStorageModule>(&mut self, storage: &mut S) -> Result<(), ()> {
+        let mut iter = self.resident_list.iter_mut();
+        while let Some(mut item) = iter.next() {
+            let status = item.get_element().inner.status;
+            if !status.is_in_use() && !status.is_pinned() {
+                ResidentObjectMetadata::unload_resident_object_dynamic(
+                    item,
+                    storage,
+                    &self.heap,
+                    &mut self.remaining_dirty_size,
+                    &mut self.stats,
+                )?;
+            }
+        }
+
+        self.check_integrity();
+        Ok(())
+    }
+
+    pub(crate) unsafe fn force_release_all(&mut self) -> usize {
+        let mut released = 0;
+
+        let mut iter = self.resident_list.iter_mut();
+        while let Some(mut item) = iter.next() {
+            let status = &mut item.get_element().inner.status;
+            if status.is_in_use() {
+                warn!("Force releasing object that is still in use");
+                status.set_is_in_use(false);
+                status.set_is_mutable_ref_active(false);
+                released += 1;
+            }
+        }
+
+        self.check_integrity();
+        released
+    }
+
+    /// Returns how often this object was accessed (also counting accesses that were not synced yet)
+    #[cfg(feature = "access_counters")]
+    pub(crate) fn get_access_count<T: Sized, S: PersistentStorageModule>(
+        &mut self,
+        alloc_id: &AllocationIdentifier<T>,
+        storage: &mut S,
+    ) -> Result<ObjectAccessCount, VNVError> {
+        if let Some(meta_ptr) = unsafe { self.find_element_mut(alloc_id) } {
+            return Ok(unsafe { meta_ptr.as_ref().unwrap() }.inner.access_count);
+        }
+
+        let count = unsafe {
+            crate::modules::persistent_storage::persistent_storage_util::read_storage_data(
+                storage,
+                alloc_id.offset + calc_backup_obj_access_count_offset(),
+            )
+        }?;
+        Ok(count)
+    }
+
     pub(crate) fn count_resident_objects(&self) -> usize {
         self.resident_list.iter().count() 
     }
 
+    pub(crate) fn get_resident_usage(&self) -> ResidentUsage {
+        let mut usage = ResidentUsage {
+            resident_objects: 0,
+            user_bytes: 0,
+            metadata_bytes: 0,
+            pinned_bytes: 0,
+        };
+
+        for item in self.resident_list.iter() {
+            let (total_layout, _) = calc_resident_obj_layout_dynamic(
+                &item.inner.layout,
+                item.inner.status.is_partial_dirtiness_tracking_enabled(),
+            );
+
+            usage.resident_objects += 1;
+            usage.user_bytes += item.inner.layout.size();
+            usage.metadata_bytes += total_layout.size() - item.inner.layout.size();
+            if item.inner.status.is_pinned() {
+                usage.pinned_bytes += total_layout.size();
+            }
+        }
+
+        usage
+    }
+
     #[cfg(feature = "benchmarks")]
     #[allow(unused)]
     pub(crate) fn get_resident_list(&self) -> &ResidentList {
@@ -812,10 +1997,14 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
         &mut self,
         alloc_id: &AllocationIdentifier<T>,
     ) -> Option<*mut ResidentObjectMetadata> {
+        self.find_element_by_offset(alloc_id.offset)
+    }
+
+    unsafe fn find_element_by_offset(&mut self, offset: usize) -> Option<*mut ResidentObjectMetadata> {
         let mut iter = self.resident_list.iter_mut();
         while let Some(mut item) = iter.next() {
             let item_ref = item.get_element();
-            if item_ref.inner.offset == alloc_id.offset {
+            if item_ref.inner.offset == offset {
                 return Some(item_ref);
             }
         }
@@ -856,7 +2045,12 @@ impl<A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'_, '_
     }
 }
 
-#[allow(dead_code)]
+/// Size of the buffer that `load_many` reads neighboring objects into
+const BATCH_READ_BUFFER_SIZE: usize = 256;
+
+/// Maximum number of objects that `load_many` reads at once
+const BATCH_READ_MAX_OBJECTS: usize = u64::BITS as usize;
+
 pub(crate) const fn get_total_resident_size<T: Sized>() -> usize {
     size_of::<ResidentObject<T>>()
 }
@@ -880,7 +2074,8 @@ unsafe fn sync_dirty_data<
     required_bytes: usize,
     storage: &'a mut S,
     allocator: &'a SharedPersistLock<'b, *mut A>,
-) -> Result<(), ()> {
+    stats: &'a mut HeapStats,
+) -> Result<(), VNVError> {
     if required_bytes == 0 {
         return Ok(());
     }
@@ -891,14 +2086,18 @@ unsafe fn sync_dirty_data<
         remaining_dirty_size,
         storage: storage,
         allocator,
+        stats,
     };
 
     let list = ObjectManagementList::<A, S> {
         arguments: &mut args,
         resident_list,
+        filter: None,
     };
 
-    object_manager.sync_dirty_data::<A, S>(required_bytes, list)?;
+    object_manager
+        .sync_dirty_data::<A, S>(required_bytes, list)
+        .map_err(|()| VNVError::DirtyBudgetExhausted)?;
     assert!(
         *remaining_dirty_size >= prev_dirty_size + required_bytes,
         "should have made enough space"
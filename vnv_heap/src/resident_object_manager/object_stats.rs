@@ -0,0 +1,156 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::resident_object_metadata::ResidentObjectMetadata;
+use crate::vnv_heap::{ObjectStats, ObjectStatsEntry};
+
+/// How many objects can be tracked at the same time. Objects that are accessed after the table is full are not tracked.
+pub const OBJECT_STATS_CAPACITY: usize = 64;
+
+#[derive(Clone, Copy)]
+struct TrackedObject {
+    offset: usize,
+    size: usize,
+    stats: ObjectStats,
+
+    /// Time at which the object was made resident (if it is resident)
+    resident_since: Option<u64>,
+}
+
+/// Statistics of the objects of a heap
+pub(crate) struct ObjectStatsTable {
+    objects: [Option<TrackedObject>; OBJECT_STATS_CAPACITY],
+
+    /// Total number of faults, used as time if no clock is set
+    total_faults: u64,
+    clock: Option<fn() -> u64>,
+}
+
+impl ObjectStatsTable {
+    pub(crate) const fn new() -> Self {
+        Self {
+            objects: [None; OBJECT_STATS_CAPACITY],
+            total_faults: 0,
+            clock: None,
+        }
+    }
+
+    fn now(&self) -> u64 {
+        match self.clock {
+            Some(clock) => clock(),
+            None => self.total_faults,
+        }
+    }
+
+    fn find(&self, offset: usize) -> Option<&TrackedObject> {
+        self.objects.iter().flatten().find(|obj| obj.offset == offset)
+    }
+
+    fn find_or_insert(&mut self, offset: usize, size: usize) -> Option<&mut TrackedObject> {
+        let index = match self.objects.iter().position(|obj| obj.map_or(false, |obj| obj.offset == offset)) {
+            Some(index) => index,
+            None => {
+                let index = self.objects.iter().position(|obj| obj.is_none())?;
+                self.objects[index] = Some(TrackedObject {
+                    offset,
+                    size,
+                    stats: ObjectStats::default(),
+                    resident_since: None,
+                });
+                index
+            }
+        };
+
+        self.objects[index].as_mut()
+    }
+
+    pub(crate) fn set_clock(&mut self, clock: Option<fn() -> u64>) {
+        self.clock = clock;
+    }
+
+    /// Has to be called after an object was loaded from storage
+    pub(crate) fn object_loaded(&mut self, metadata: &ResidentObjectMetadata) {
+        self.total_faults += 1;
+        let now = self.now();
+        if let Some(obj) = self.find_or_insert(metadata.inner.offset, metadata.inner.layout.size()) {
+            obj.stats.faults += 1;
+            obj.resident_since = Some(now);
+        }
+    }
+
+    /// Has to be called after a new object was created in the resident buffer (without loading it)
+    pub(crate) fn object_created(&mut self, metadata: &ResidentObjectMetadata) {
+        let now = self.now();
+        if let Some(obj) = self.find_or_insert(metadata.inner.offset, metadata.inner.layout.size()) {
+            obj.resident_since = Some(now);
+        }
+    }
+
+    /// Has to be called before an object is removed from the resident buffer
+    pub(crate) fn object_unloaded(&mut self, metadata: &ResidentObjectMetadata) {
+        let now = self.now();
+        if let Some(obj) = self.find_or_insert(metadata.inner.offset, metadata.inner.layout.size()) {
+            obj.stats.evictions += 1;
+            if let Some(since) = obj.resident_since.take() {
+                obj.stats.resident_time += now.saturating_sub(since);
+            }
+        }
+    }
+
+    /// Has to be called after `bytes` of dirty data of an object were written back to storage
+    pub(crate) fn object_written_back(&mut self, metadata: &ResidentObjectMetadata, bytes: usize) {
+        if let Some(obj) = self.find_or_insert(metadata.inner.offset, metadata.inner.layout.size()) {
+            obj.stats.write_backs += 1;
+            obj.stats.write_back_bytes += bytes;
+        }
+    }
+
+    /// Has to be called after the object at `offset` was deallocated
+    pub(crate) fn object_deallocated(&mut self, offset: usize) {
+        for obj in self.objects.iter_mut() {
+            if obj.map_or(false, |obj| obj.offset == offset) {
+                *obj = None;
+            }
+        }
+    }
+
+    /// Returns the statistics of the object at `offset` or `None` if it is not tracked
+    pub(crate) fn get_stats(&self, offset: usize) -> Option<ObjectStats> {
+        let now = self.now();
+        self.find(offset).map(|obj| current_stats(obj, now))
+    }
+
+    /// Returns the `index`-th slot of the table (`None` if it is empty), or `Err` if `index` is out of bounds
+    pub(crate) fn get_entry(&self, index: usize) -> Result<Option<ObjectStatsEntry>, ()> {
+        let now = self.now();
+        let obj = self.objects.get(index).ok_or(())?;
+        Ok(obj.map(|obj| ObjectStatsEntry {
+            offset: obj.offset,
+            size: obj.size,
+            stats: current_stats(&obj, now),
+        }))
+    }
+}
+
+/// Includes the time since the object was made resident (if it is resident right now)
+fn current_stats(obj: &TrackedObject, now: u64) -> ObjectStats {
+    let mut stats = obj.stats;
+    if let Some(since) = obj.resident_since {
+        stats.resident_time += now.saturating_sub(since);
+    }
+    stats
+}
@@ -72,11 +72,6 @@ impl PartialDirtinessTrackingInfo {
         }
     }
 
-    pub(crate) fn new_used<T>() -> Self {
-        let layout = Layout::new::<T>();
-        Self::new_used_dynamic(&layout)
-    }
-
     pub(crate) const fn calc_bit_and_byte_count(data_size: usize) -> (usize, usize) {
         debug_assert!(
             data_size <= MAX_SUPPORTED_PARTIAL_DIRTY_OBJ_SIZE,
@@ -119,9 +114,10 @@ impl PartialDirtinessTrackingInfo {
         &'a self,
         base_ptr: *const ResidentObjectMetadata,
     ) -> &'a mut [u8] {
-        let base_ptr = (base_ptr as *const u8) as usize - (self.byte_count as usize);
+        // the dirty buffer is located right in front of the metadata
+        let base_ptr = unsafe { (base_ptr as *mut u8).sub(self.byte_count as usize) };
 
-        let slice_ptr = slice_from_raw_parts_mut(base_ptr as *mut u8, self.byte_count as usize);
+        let slice_ptr = slice_from_raw_parts_mut(base_ptr, self.byte_count as usize);
         let slice = unsafe { slice_ptr.as_mut().unwrap() };
 
         slice
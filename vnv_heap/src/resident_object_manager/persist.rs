@@ -15,24 +15,37 @@
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+#[cfg(feature = "recovery")]
+use core::alloc::Layout;
+#[cfg(feature = "recovery")]
+use crate::heap_recovery::{commit_persisted_slice, CommitCursor};
 use std::{
-    mem::size_of,
-    ptr::{copy, null_mut, slice_from_raw_parts},
+    mem::{size_of, MaybeUninit},
+    ptr::{copy, null_mut, slice_from_raw_parts, slice_from_raw_parts_mut},
 };
 
+#[cfg(feature = "persist_priority")]
+use super::persist_priority::PersistPriority;
 use super::{calc_resident_obj_layout_dynamic, resident_list::SharedResidentListRef, ResidentObjectMetadata, ResidentObjectMetadataBackup};
-use crate::modules::{
+use crate::{vnv_heap::{PartialPersistResult, PersistSummary}, modules::{
     allocator::AllocatorModule,
     persistent_storage::{
-        persistent_storage_util::{read_storage_data, write_storage_data}, PersistentStorageModule, SharedStorageReference
+        persistent_storage_util::{read_storage_data, write_storage_data}, CoalescingStorageModule, PersistentStorageModule,
+        SharedStorageReference,
     },
-};
+}};
 
+/// Writes the state of all resident objects to the slice at `slice_offset`.
+///
+/// With the `recovery` feature, `slice_offset` has to be the slot of `commit_cursor` (see `CommitCursor::get_slice_offset`).
 // TODO does currently not work for partial dirtiness tracking
 pub(crate) fn persist(
     resident_list: &SharedResidentListRef,
     storage_ref: &mut SharedStorageReference,
-) {
+    resident_buf_base_ptr: *mut u8,
+    slice_offset: usize,
+    #[cfg(feature = "recovery")] commit_cursor: &mut CommitCursor,
+) -> PersistSummary {
     // step 1: get first item of list
     let head = resident_list.get_head();
     let head = unsafe { head.as_ref().unwrap() };
@@ -43,12 +56,28 @@ pub(crate) fn persist(
     if curr.is_null() {
         // no objects to be persisted
         let slice_size = size_of::<usize>() as usize;
-        write_storage_data(storage_ref, 0, &slice_size).unwrap();
-        return;
+        write_storage_data(storage_ref, slice_offset, &slice_size).unwrap();
+        storage_ref.flush().unwrap();
+        return PersistSummary {
+            persisted_bytes: slice_size,
+            persisted_objects: 0,
+            dirty_objects: 0,
+            duration_ticks: None,
+            // there is nothing to recover
+            #[cfg(feature = "recovery")]
+            committed: true,
+        };
     }
 
+    // the dirty data of prioritized objects is written to its storage location first,
+    // so it is safe even if the power fails before the persisted state is committed
+    #[cfg(feature = "persist_priority")]
+    write_back_prioritized(curr, storage_ref);
+
     let mut slice_end_ptr = (curr as *mut ResidentObjectMetadataBackup) as *mut u8;
     let slice_base_ptr: *mut u8 = unsafe { slice_end_ptr.sub(size_of::<usize>()) };
+    let mut persisted_objects = 0;
+    let mut dirty_objects = 0;
     while !curr.is_null() {
         let (next, is_data_dirty, backup_obj, data_range, data_range_len) =
             if let Some(item) = unsafe { curr.as_ref() } {
@@ -59,7 +88,7 @@ pub(crate) fn persist(
 
                 let next = unsafe { item.next_resident_object.as_ptr().read() };
 
-                let backup = ResidentObjectMetadataBackup::from_metadata(item);
+                let backup = ResidentObjectMetadataBackup::from_metadata(item, resident_buf_base_ptr);
                 let is_data_dirty = item.inner.status.is_data_dirty();
                 let data_range = unsafe { item.dynamic_metadata_to_data_range() };
 
@@ -79,8 +108,10 @@ pub(crate) fn persist(
         unsafe { (slice_end_ptr as *mut ResidentObjectMetadataBackup).write(backup_obj) };
 
         slice_end_ptr = unsafe { slice_end_ptr.add(size_of::<ResidentObjectMetadataBackup>()) };
+        persisted_objects += 1;
 
         if is_data_dirty {
+            dirty_objects += 1;
             unsafe {
                 // data may be overlapping
                 copy(data_range, slice_end_ptr, data_range_len);
@@ -104,14 +135,115 @@ pub(crate) fn persist(
         tmp.as_ref().unwrap()
     };
 
-    storage_ref.write(0, &slice).unwrap();
+    storage_ref.write(slice_offset, &slice).unwrap();
+
+    // step 5: write back everything that is still buffered by the storage module
+    storage_ref.flush().unwrap();
+
+    // step 6: the slice is only recovered after a reboot if it was committed completely (see `VNVHeap::recover`).
+    // If this fails, the state can still be restored, it is just not recovered if the power fails now
+    #[cfg(feature = "recovery")]
+    let committed = commit_persisted_slice(storage_ref, slice, commit_cursor).is_ok();
+
+    PersistSummary {
+        persisted_bytes: slice_len,
+        persisted_objects,
+        dirty_objects,
+        duration_ticks: None,
+        #[cfg(feature = "recovery")]
+        committed,
+    }
+}
+
+/// Writes the dirty data of all objects with a priority other than `PersistPriority::Normal` back to storage,
+/// starting with the highest priority (see `VNVObject::set_persist_priority`).
+///
+/// The objects stay dirty, so they are part of the persisted state as well.
+#[cfg(feature = "persist_priority")]
+fn write_back_prioritized(head: *mut ResidentObjectMetadata, storage_ref: &mut SharedStorageReference) {
+    let mut writer = StagedWriter::new(storage_ref);
+    for priority in [PersistPriority::Critical, PersistPriority::High] {
+        let mut curr = head;
+        while let Some(item) = unsafe { curr.as_ref() } {
+            if item.inner.status.is_data_dirty() && item.inner.status.get_persist_priority() == priority {
+                unsafe { item.write_user_data_dynamic(&mut writer) }.unwrap();
+            }
+            curr = unsafe { item.next_resident_object.as_ptr().read() };
+        }
+
+        // critical objects have to be written completely before the first high priority object
+        writer.flush().unwrap();
+    }
+}
+
+/// Order in which `persist_budget` writes back the dirty objects
+#[cfg(feature = "persist_priority")]
+const PERSIST_BUDGET_ORDER: [PersistPriority; 3] = [PersistPriority::Critical, PersistPriority::High, PersistPriority::Normal];
+#[cfg(not(feature = "persist_priority"))]
+const PERSIST_BUDGET_ORDER: [(); 1] = [()];
+
+#[cfg(feature = "persist_priority")]
+fn has_priority(item: &ResidentObjectMetadata, priority: PersistPriority) -> bool {
+    item.inner.status.get_persist_priority() == priority
+}
+
+#[cfg(not(feature = "persist_priority"))]
+fn has_priority(_item: &ResidentObjectMetadata, _priority: ()) -> bool {
+    true
+}
+
+/// Writes the dirty data of resident objects to their storage location until no more objects fit into `budget_bytes`,
+/// objects with a higher priority first (see `vnv_persist_budget`).
+///
+/// Written objects are marked as written back and skipped by the next call unless they are modified in the meantime.
+/// They stay dirty, so they are part of the state that is written by `persist` as well.
+pub(crate) fn persist_budget(
+    resident_list: &SharedResidentListRef,
+    storage_ref: &mut SharedStorageReference,
+    budget_bytes: usize,
+) -> PartialPersistResult {
+    let head = resident_list.get_head();
+    let head = unsafe { head.as_ref().unwrap().as_ptr().read() };
+
+    let mut result = PartialPersistResult {
+        written_bytes: 0,
+        remaining_bytes: 0,
+    };
+    let mut writer = StagedWriter::new(storage_ref);
+    for priority in PERSIST_BUDGET_ORDER {
+        let mut curr = head;
+        while let Some(item) = unsafe { curr.as_mut() } {
+            let status = item.inner.status;
+            if status.is_data_dirty() && !status.is_written_back() && has_priority(item, priority) {
+                let size = item.inner.layout.size();
+                if result.written_bytes + size <= budget_bytes {
+                    unsafe { item.write_user_data_dynamic(&mut writer) }.unwrap();
+                    result.written_bytes += size;
+
+                    // the object could still be modified through its mutable reference, so it is written again next time
+                    if !status.is_mutable_ref_active() {
+                        item.inner.status.set_written_back(true);
+                    }
+                } else {
+                    result.remaining_bytes += size;
+                }
+            }
+
+            curr = unsafe { item.next_resident_object.as_ptr().read() };
+        }
+    }
+
+    writer.flush().unwrap();
+    result
 }
 
+/// Restores the state that was written to the slice at `slice_offset` by `persist`
 pub(crate) fn restore(
     storage_ref: &mut SharedStorageReference,
     heap: &mut dyn AllocatorModule,
     resident_buf_base_ptr: *mut u8,
     resident_buf_size: usize,
+    slice_offset: usize,
 ) {
     // step 1: reset resident heaps state
     unsafe {
@@ -120,19 +252,21 @@ pub(crate) fn restore(
     };
 
     // step 2: read slice size    
-    let slice_size: usize = unsafe { read_storage_data(storage_ref, 0).unwrap() };
+    let slice_size: usize = unsafe { read_storage_data(storage_ref, slice_offset).unwrap() };
 
     if slice_size == size_of::<usize>() {
         // no object were resident
         return;
     }
 
+    let mut reader = StagedSliceReader::new(slice_offset, slice_size);
+
     // step 3: read one metadata backup at a time to restore the heap without overwriting any data
     // this is inefficient and could probably be fixed by using a deterministic heap...
     {
         let mut curr_offset = size_of::<usize>();
         while curr_offset < slice_size {
-            let backup: ResidentObjectMetadataBackup = unsafe { read_storage_data(storage_ref, curr_offset).unwrap() };
+            let backup = unsafe { reader.read_backup(storage_ref, curr_offset).unwrap() };
             let ram_offset = backup.ram_offset;
 
             let metadata = backup.to_metadata(null_mut());
@@ -141,10 +275,9 @@ pub(crate) fn restore(
                 &metadata.inner.layout,
                 metadata.inner.status.is_partial_dirtiness_tracking_enabled(),
             );
-            debug_assert!(ram_offset >= resident_buf_base_ptr as usize);
-            debug_assert!(ram_offset + total_layout.size() < (resident_buf_base_ptr as usize) + resident_buf_size);
+            debug_assert!(ram_offset + total_layout.size() < resident_buf_size);
             unsafe {
-                heap.allocate_at(total_layout, ram_offset as *mut u8).unwrap();
+                heap.allocate_at(total_layout, ram_offset_to_ptr(resident_buf_base_ptr, ram_offset)).unwrap();
             }
             curr_offset += size_of::<ResidentObjectMetadataBackup>();
             if metadata.inner.status.is_data_dirty() {
@@ -157,11 +290,11 @@ pub(crate) fn restore(
     let mut curr_offset = size_of::<usize>();
     let mut prev: *mut ResidentObjectMetadata = null_mut();
     while curr_offset < slice_size {
-        let backup: ResidentObjectMetadataBackup = unsafe { read_storage_data(storage_ref, curr_offset).unwrap() };
+        let backup = unsafe { reader.read_backup(storage_ref, curr_offset).unwrap() };
         curr_offset += size_of::<ResidentObjectMetadataBackup>();
 
         let ram_offset = backup.ram_offset;
-        let ram_ptr = (ram_offset as *mut u8) as *mut ResidentObjectMetadata;
+        let ram_ptr = ram_offset_to_ptr(resident_buf_base_ptr, ram_offset) as *mut ResidentObjectMetadata;
 
         let metadata = backup.to_metadata(null_mut());
         let data_status = metadata.inner.status;
@@ -187,13 +320,175 @@ pub(crate) fn restore(
             let data_dest = unsafe { mut_ref.dynamic_metadata_to_data_range_mut() };
 
             // slices could overlap
-            storage_ref.read(curr_offset, data_dest).unwrap();
+            reader.read(storage_ref, curr_offset, data_dest).unwrap();
 
             curr_offset += data_layout.size();
         } else {
             // data is not dirty and is stored at its default storage location
             unsafe { mut_ref.load_user_data(storage_ref).unwrap() };
+
+            #[cfg(feature = "object_checksums")]
+            assert_eq!(
+                mut_ref.load_checksum(storage_ref).unwrap(),
+                unsafe { mut_ref.calc_checksum() },
+                "restored object is corrupted (offset: {})",
+                mut_ref.inner.offset
+            );
         }
     }
 
+    // the state is resident again, so it must not be recovered after a reboot (see `VNVHeap::recover`)
+    #[cfg(feature = "recovery")]
+    {
+        write_storage_data(storage_ref, slice_offset, &size_of::<usize>()).unwrap();
+        storage_ref.flush().unwrap();
+    }
+}
+
+/// An object that was resident when the heap was persisted
+#[cfg(feature = "recovery")]
+pub(crate) struct PersistedObject {
+    pub(crate) offset: usize,
+    pub(crate) layout: Layout,
+
+    /// Offset of the persisted user data in storage (only available if the data was dirty)
+    pub(crate) dirty_data_offset: Option<usize>,
+}
+
+/// Iterates over the objects of the slice that was written by `persist`,
+/// e.g. to recover the heap after a reboot (see `VNVHeap::recover`)
+#[cfg(feature = "recovery")]
+pub(crate) struct PersistedObjectIter {
+    reader: StagedSliceReader,
+    curr_offset: usize,
+}
+
+#[cfg(feature = "recovery")]
+impl PersistedObjectIter {
+    /// Iterates over the slice at `slice_offset`, fails if it is larger than `max_slice_size`
+    pub(crate) fn new<S: PersistentStorageModule>(storage: &mut S, slice_offset: usize, max_slice_size: usize) -> Result<Self, ()> {
+        let slice_size: usize = unsafe { read_storage_data(storage, slice_offset) }?;
+        if slice_size < size_of::<usize>() || slice_size > max_slice_size {
+            return Err(());
+        }
+
+        Ok(Self {
+            reader: StagedSliceReader::new(slice_offset, slice_size),
+            curr_offset: size_of::<usize>(),
+        })
+    }
+
+    pub(crate) fn next<S: PersistentStorageModule>(&mut self, storage: &mut S) -> Result<Option<PersistedObject>, ()> {
+        let slice_size = self.reader.slice_size;
+        if self.curr_offset == slice_size {
+            return Ok(None);
+        }
+        if self.curr_offset + size_of::<ResidentObjectMetadataBackup>() > slice_size {
+            return Err(());
+        }
+
+        let backup = unsafe { self.reader.read_backup(storage, self.curr_offset) }?;
+        self.curr_offset += size_of::<ResidentObjectMetadataBackup>();
+
+        let layout = backup.layout;
+        let status = backup.status;
+        let dirty_data_offset = if status.is_data_dirty() {
+            if self.curr_offset + layout.size() > slice_size {
+                return Err(());
+            }
+
+            let data_offset = self.reader.slice_offset + self.curr_offset;
+            self.curr_offset += layout.size();
+            Some(data_offset)
+        } else {
+            None
+        };
+
+        Ok(Some(PersistedObject {
+            offset: backup.storage_offset,
+            layout,
+            dirty_data_offset,
+        }))
+    }
+}
+
+/// Size of the buffers that persisted data is read through and written back through (see `StagedSliceReader` and `StagedWriter`)
+pub(crate) const STAGING_BUFFER_SIZE: usize = 256;
+
+/// How many separate (not adjacent) regions `StagedWriter` queues before writing them back
+pub(crate) const STAGING_WRITE_COUNT: usize = 8;
+
+/// Queues the writes of `write_back_prioritized` and `persist_budget`, so that the header of an object
+/// (access counter and checksum) is written together with its data and adjacent objects are written with a single call
+pub(crate) type StagedWriter<S> = CoalescingStorageModule<STAGING_WRITE_COUNT, STAGING_BUFFER_SIZE, S>;
+
+/// Reads the persisted slice sequentially through a small buffer,
+/// so that the metadata (and dirty data) of small objects is not read with one storage access each
+struct StagedSliceReader {
+    buffer: [u8; STAGING_BUFFER_SIZE],
+
+    /// Offset of `buffer[0]` in the slice
+    buffer_offset: usize,
+    buffer_len: usize,
+
+    /// Offset of the slice in storage
+    slice_offset: usize,
+    slice_size: usize,
+}
+
+impl StagedSliceReader {
+    fn new(slice_offset: usize, slice_size: usize) -> Self {
+        Self {
+            buffer: [0u8; STAGING_BUFFER_SIZE],
+            buffer_offset: 0,
+            buffer_len: 0,
+            slice_offset,
+            slice_size,
+        }
+    }
+
+    /// Reads `[offset, offset + dest.len())` of the persisted slice
+    fn read<S: PersistentStorageModule>(&mut self, storage: &mut S, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        debug_assert!(offset + dest.len() <= self.slice_size);
+
+        if dest.len() > STAGING_BUFFER_SIZE {
+            return storage.read(self.slice_offset + offset, dest);
+        }
+
+        let is_buffered = offset >= self.buffer_offset
+            && offset + dest.len() <= self.buffer_offset + self.buffer_len;
+        if !is_buffered {
+            let len = STAGING_BUFFER_SIZE.min(self.slice_size - offset);
+            self.buffer_len = 0;
+            storage.read(self.slice_offset + offset, &mut self.buffer[..len])?;
+            self.buffer_offset = offset;
+            self.buffer_len = len;
+        }
+
+        let start = offset - self.buffer_offset;
+        dest.copy_from_slice(&self.buffer[start..start + dest.len()]);
+        Ok(())
+    }
+
+    unsafe fn read_backup<S: PersistentStorageModule>(
+        &mut self,
+        storage: &mut S,
+        offset: usize,
+    ) -> Result<ResidentObjectMetadataBackup, ()> {
+        let mut backup = MaybeUninit::<ResidentObjectMetadataBackup>::uninit();
+        let dest = slice_from_raw_parts_mut(
+            backup.as_mut_ptr() as *mut u8,
+            size_of::<ResidentObjectMetadataBackup>(),
+        );
+        self.read(storage, offset, dest.as_mut().unwrap())?;
+
+        Ok(backup.assume_init())
+    }
+}
+
+/// Converts the offset stored in `ResidentObjectMetadataBackup::ram_offset` back to a pointer.
+///
+/// The pointer is derived from the resident buffer, so that it keeps the provenance of the resident buffer.
+fn ram_offset_to_ptr(resident_buf_base_ptr: *mut u8, ram_offset: usize) -> *mut u8 {
+    resident_buf_base_ptr.wrapping_add(ram_offset)
 }
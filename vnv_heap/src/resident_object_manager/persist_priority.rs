@@ -0,0 +1,95 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+/// How many objects can have a priority other than `PersistPriority::Normal` at the same time
+pub const PERSIST_PRIORITY_CAPACITY: usize = 16;
+
+/// Determines which dirty objects are written back first by `vnv_persist_all` (see `VNVObject::set_persist_priority`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum PersistPriority {
+    /// The object is only part of the persisted state
+    #[default]
+    Normal,
+
+    /// The object is written back before the persisted state, but after all `Critical` objects
+    High,
+
+    /// The object is written back before all other objects
+    Critical,
+}
+
+/// Priorities of the objects of a heap (objects that are not listed have `PersistPriority::Normal`).
+///
+/// The table is kept in the resident object manager. Resident objects also store their priority
+/// in their status, so `vnv_persist_all` does not need to access the table.
+pub(crate) struct PersistPriorityTable {
+    priorities: [Option<(usize, PersistPriority)>; PERSIST_PRIORITY_CAPACITY],
+}
+
+impl PersistPriorityTable {
+    pub(crate) const fn new() -> Self {
+        Self {
+            priorities: [None; PERSIST_PRIORITY_CAPACITY],
+        }
+    }
+
+    /// Returns the priority of the object at `offset`
+    pub(crate) fn get(&self, offset: usize) -> PersistPriority {
+        self.priorities
+            .iter()
+            .flatten()
+            .find(|(obj_offset, _)| *obj_offset == offset)
+            .map_or(PersistPriority::Normal, |(_, priority)| *priority)
+    }
+
+    /// Sets the priority of the object at `offset`, fails if `PERSIST_PRIORITY_CAPACITY` objects already have a priority
+    pub(crate) fn set(&mut self, offset: usize, priority: PersistPriority) -> Result<(), ()> {
+        let existing = self
+            .priorities
+            .iter()
+            .position(|obj| obj.map_or(false, |(obj_offset, _)| obj_offset == offset));
+
+        if priority == PersistPriority::Normal {
+            if let Some(index) = existing {
+                self.priorities[index] = None;
+            }
+            return Ok(());
+        }
+
+        let index = match existing {
+            Some(index) => index,
+            None => self.priorities.iter().position(|obj| obj.is_none()).ok_or(())?,
+        };
+        self.priorities[index] = Some((offset, priority));
+        Ok(())
+    }
+
+    /// Has to be called after the object at `offset` was moved to `new_offset`
+    pub(crate) fn object_relocated(&mut self, offset: usize, new_offset: usize) {
+        for (obj_offset, _) in self.priorities.iter_mut().flatten() {
+            if *obj_offset == offset {
+                *obj_offset = new_offset;
+            }
+        }
+    }
+
+    /// Has to be called after the object at `offset` was deallocated
+    pub(crate) fn object_deallocated(&mut self, offset: usize) {
+        // `set` cannot fail for `Normal`
+        let _ = self.set(offset, PersistPriority::Normal);
+    }
+}
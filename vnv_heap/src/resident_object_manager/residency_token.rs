@@ -0,0 +1,75 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{
+    ptr::null_mut,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use super::resident_object_metadata::ResidentObjectMetadata;
+
+/// Incremented every time an object is removed from a resident list.
+///
+/// Afterwards, its metadata could be overwritten, so all tokens that were issued before are invalid.
+static RESIDENT_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// Has to be called after an object was removed from a resident list
+#[inline]
+pub(crate) fn invalidate_residency_tokens() {
+    RESIDENT_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Remembers where the metadata of a resident object is located.
+///
+/// Objects that are accessed over and over again can be found without searching the resident list,
+/// as long as no object was unloaded since then.
+#[derive(Clone, Copy)]
+pub(crate) struct ResidencyToken {
+    metadata: *mut ResidentObjectMetadata,
+    generation: usize,
+}
+
+impl ResidencyToken {
+    pub(crate) const fn invalid() -> Self {
+        Self {
+            metadata: null_mut(),
+            generation: usize::MAX,
+        }
+    }
+
+    pub(crate) fn new(metadata: *mut ResidentObjectMetadata) -> Self {
+        Self {
+            metadata,
+            generation: RESIDENT_GENERATION.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Returns the metadata of the object at `offset` if this token is still valid
+    #[inline]
+    pub(crate) fn resolve(&self, offset: usize) -> Option<*mut ResidentObjectMetadata> {
+        if self.metadata.is_null() || self.generation != RESIDENT_GENERATION.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        debug_assert_eq!(
+            unsafe { self.metadata.as_ref().unwrap().inner.offset },
+            offset,
+            "residency token points to another object"
+        );
+        Some(self.metadata)
+    }
+}
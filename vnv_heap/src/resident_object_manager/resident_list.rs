@@ -21,7 +21,7 @@ use core::{
     sync::atomic::{AtomicPtr, Ordering},
 };
 
-use super::resident_object_metadata::ResidentObjectMetadata;
+use super::{residency_token::invalidate_residency_tokens, resident_object_metadata::ResidentObjectMetadata};
 
 pub(crate) struct ResidentList {
     head: AtomicPtr<ResidentObjectMetadata>,
@@ -176,6 +176,7 @@ impl DeleteHandle<'_, '_> {
         let next_ptr = curr_ref.next_resident_object.load(Ordering::SeqCst);
 
         self.inner.prev.store(next_ptr, Ordering::SeqCst);
+        invalidate_residency_tokens();
 
         curr_ref
     }
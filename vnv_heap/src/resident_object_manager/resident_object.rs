@@ -27,7 +27,7 @@ use crate::{
 };
 
 use super::{
-    partial_dirtiness_tracking::PartialDirtinessTrackingInfo, resident_list::DeleteHandle, resident_object_metadata::ResidentObjectMetadata
+    partial_dirtiness_tracking::PartialDirtinessTrackingInfo, resident_list::DeleteHandle, resident_object_metadata::ResidentObjectMetadata, HeapStats
 };
 
 /// An object that is currently stored in RAM
@@ -54,6 +54,7 @@ impl<T: Sized> ResidentObject<T> {
         storage: &mut S,
         allocator_module: &mut SharedPersistLock<*mut A>,
         dirty_size: &mut usize,
+        stats: &mut HeapStats,
         unsafe_no_sync: bool,
         use_partial_dirtiness_tracking: bool
     ) -> Result<(), ()> {
@@ -72,7 +73,12 @@ impl<T: Sized> ResidentObject<T> {
 
             if !unsafe_no_sync {
                 // sync unsynced changes
-                resident_obj.persist_user_data(storage)?;
+                resident_obj.persist_user_data(storage, stats)?;
+
+                #[cfg(feature = "access_counters")]
+                resident_obj.metadata.sync_access_count(storage)?;
+
+                stats.object_unloaded(&resident_obj.metadata);
             }
 
             prev_dirty_size
@@ -122,12 +128,13 @@ impl<T: Sized> ResidentObject<T> {
     pub(crate) unsafe fn persist_user_data<S: PersistentStorageModule>(
         &mut self,
         storage: &mut S,
+        stats: &mut HeapStats,
     ) -> Result<usize, ()> {
         if !self.metadata.inner.status.is_data_dirty() {
             return Ok(0);
         }
 
-        self.metadata.persist_user_data_dynamic(storage)
+        self.metadata.persist_user_data_dynamic(storage, stats)
     }
 }
 
@@ -32,15 +32,40 @@ pub(crate) const TOTAL_METADATA_BACKUP_SIZE: usize = {
     size_of::<ResidentObjectMetadataBackup>()
 };
 
+/// How often an object was accessed.
+///
+/// With the `access_counters` feature, this is stored in front of the user data of each object.
+pub type ObjectAccessCount = u32;
+
+#[cfg(feature = "access_counters")]
+const ACCESS_COUNTER_HEADER_SIZE: usize = size_of::<ObjectAccessCount>();
+
+#[cfg(not(feature = "access_counters"))]
+const ACCESS_COUNTER_HEADER_SIZE: usize = 0;
+
+/// Checksum of the user data of an object.
+///
+/// With the `object_checksums` feature, this is stored in front of the user data of each object
+/// (after the access counter) and verified every time the object is loaded.
+pub type ObjectChecksum = u32;
+
+#[cfg(feature = "object_checksums")]
+const CHECKSUM_HEADER_SIZE: usize = size_of::<ObjectChecksum>();
+
+#[cfg(not(feature = "object_checksums"))]
+const CHECKSUM_HEADER_SIZE: usize = 0;
+
+const HEADER_SIZE: usize = ACCESS_COUNTER_HEADER_SIZE + CHECKSUM_HEADER_SIZE;
+
 pub(crate) const fn calc_backup_obj_layout_static<T>() -> Layout {
     assert!(Layout::from_size_align(
-        size_of::<T>(),
+        size_of::<T>() + HEADER_SIZE,
         1
     )
     .is_ok());
     let layout = unsafe {
         Layout::from_size_align_unchecked(
-            size_of::<T>(),
+            size_of::<T>() + HEADER_SIZE,
             1,
         )
     };
@@ -48,11 +73,33 @@ pub(crate) const fn calc_backup_obj_layout_static<T>() -> Layout {
     layout
 }
 
+/// Same as `calc_backup_obj_layout_static` for user data of `data_size` bytes (see `VNVBytes`)
+pub(crate) fn calc_backup_obj_layout_dynamic(data_size: usize) -> Layout {
+    data_size
+        .checked_add(HEADER_SIZE)
+        .and_then(|size| Layout::from_size_align(size, 1).ok())
+        .expect("object is too big")
+}
+
+/// Offset of the access counter of an object (only available with the `access_counters` feature)
+#[cfg(feature = "access_counters")]
 #[inline]
-pub(crate) const fn calc_backup_obj_user_data_offset() -> usize {
+pub(crate) const fn calc_backup_obj_access_count_offset() -> usize {
     0
 }
 
+/// Offset of the checksum of an object (only available with the `object_checksums` feature)
+#[cfg(feature = "object_checksums")]
+#[inline]
+pub(crate) const fn calc_backup_obj_checksum_offset() -> usize {
+    ACCESS_COUNTER_HEADER_SIZE
+}
+
+#[inline]
+pub(crate) const fn calc_backup_obj_user_data_offset() -> usize {
+    HEADER_SIZE
+}
+
 /// Metadata of resident objects that will be saved
 /// to non volatile storage, so that program can recover
 /// after a power failure
@@ -64,16 +111,20 @@ pub(crate) struct ResidentObjectMetadataBackup {
     /// What status is the resident object in?
     pub(crate) status: ResidentObjectStatus,
 
-    /// Points to the location in RAM where this metadata object is stored
+    /// Location of this metadata object relative to the start of the resident buffer
+    /// (so the persisted state does not depend on where the resident buffer is located)
     pub(crate) ram_offset: usize,
 
     pub(crate) storage_offset: usize,
 
     pub(crate) layout: Layout,
+
+    #[cfg(feature = "access_counters")]
+    pub(crate) access_count: ObjectAccessCount,
 }
 
 impl ResidentObjectMetadataBackup {
-    pub(crate) fn from_metadata(value: &ResidentObjectMetadata) -> Self {
+    pub(crate) fn from_metadata(value: &ResidentObjectMetadata, resident_buf_base_ptr: *const u8) -> Self {
         let ResidentObjectMetadataInner {
             status,
             layout,
@@ -82,13 +133,19 @@ impl ResidentObjectMetadataBackup {
 
             #[cfg(debug_assertions)]
             data_offset: _data_offset,
+
+            #[cfg(feature = "access_counters")]
+            access_count,
         } = value.inner;
 
         Self {
             status: status.clone(),
             layout: layout.clone(),
-            ram_offset: (value as *const ResidentObjectMetadata) as usize,
-            storage_offset: storage_offset
+            ram_offset: (value as *const ResidentObjectMetadata) as usize - resident_buf_base_ptr as usize,
+            storage_offset: storage_offset,
+
+            #[cfg(feature = "access_counters")]
+            access_count,
         }
     }
 
@@ -97,7 +154,10 @@ impl ResidentObjectMetadataBackup {
             status,
             layout,
             ram_offset: _offset,
-            storage_offset
+            storage_offset,
+
+            #[cfg(feature = "access_counters")]
+            access_count,
         } = self;
 
         let partial_dirtiness_tracking_info = if status.is_partial_dirtiness_tracking_enabled() {
@@ -114,6 +174,9 @@ impl ResidentObjectMetadataBackup {
 
             #[cfg(debug_assertions)]
             data_offset: usize::MAX,
+
+            #[cfg(feature = "access_counters")]
+            access_count,
         };
 
         ResidentObjectMetadata {
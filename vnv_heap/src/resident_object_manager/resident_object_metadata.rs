@@ -26,9 +26,19 @@ use memoffset::offset_of;
 
 use crate::{
     modules::{allocator::AllocatorModule, persistent_storage::PersistentStorageModule},
-    resident_object_manager::calc_resident_obj_layout_dynamic, util::round_up_to_nearest,
+    resident_object_manager::{calc_resident_obj_layout_dynamic, HeapStats}, util::round_up_to_nearest,
+    vnv_error::VNVError,
 };
 
+#[cfg(any(feature = "access_counters", feature = "object_checksums"))]
+use crate::modules::persistent_storage::persistent_storage_util::{read_storage_data, write_storage_data};
+
+#[cfg(feature = "access_counters")]
+use super::{calc_backup_obj_access_count_offset, ObjectAccessCount};
+
+#[cfg(feature = "object_checksums")]
+use super::{calc_backup_obj_checksum_offset, ObjectChecksum};
+
 use super::{
     calc_backup_obj_user_data_offset, partial_dirtiness_tracking::PartialDirtinessTrackingInfo,
     resident_list::DeleteHandle,
@@ -99,24 +109,41 @@ pub(crate) struct ResidentObjectMetadataInner {
     /// belongs to which metadata.
     #[cfg(debug_assertions)]
     pub(super) data_offset: usize,
+
+    /// Total number of accesses of this object (including accesses before it was made resident)
+    #[cfg(feature = "access_counters")]
+    pub(crate) access_count: ObjectAccessCount,
 }
 
 impl ResidentObjectMetadataInner {
     pub(super) fn new<T: Sized>(offset: usize, partial_dirtiness_tracking: bool) -> Self {
+        let inner = Self::new_dynamic(offset, Layout::new::<T>(), partial_dirtiness_tracking);
+
+        #[cfg(debug_assertions)]
+        debug_assert_eq!(inner.data_offset, offset_of!(ResidentObject<T>, data));
+
+        inner
+    }
+
+    /// Same as `new`, but for objects whose data layout is only known at runtime (see `VNVBytes`)
+    pub(super) fn new_dynamic(offset: usize, layout: Layout, partial_dirtiness_tracking: bool) -> Self {
         let partial_dirtiness_tracking_info = if partial_dirtiness_tracking {
-            PartialDirtinessTrackingInfo::new_used::<T>()
+            PartialDirtinessTrackingInfo::new_used_dynamic(&layout)
         } else {
             PartialDirtinessTrackingInfo::new_unused()
         };
 
         ResidentObjectMetadataInner {
             status: ResidentObjectStatus::new_metadata_dirty(partial_dirtiness_tracking),
-            layout: Layout::new::<T>(),
+            layout,
             offset,
             partial_dirtiness_tracking_info,
 
             #[cfg(debug_assertions)]
-            data_offset: offset_of!(ResidentObject<T>, data),
+            data_offset: Layout::new::<ResidentObjectMetadata>().extend(layout).unwrap().1,
+
+            #[cfg(feature = "access_counters")]
+            access_count: 0,
         }
     }
 }
@@ -131,6 +158,9 @@ impl Default for ResidentObjectMetadataInner {
 
             #[cfg(debug_assertions)]
             data_offset: usize::MAX,
+
+            #[cfg(feature = "access_counters")]
+            access_count: 0,
         }
     }
 }
@@ -143,10 +173,24 @@ impl ResidentObjectMetadata {
         }
     }
 
+    pub(crate) fn new_dynamic(offset: usize, layout: Layout, partial_dirtiness_tracking: bool) -> Self {
+        ResidentObjectMetadata {
+            next_resident_object: AtomicPtr::new(null_mut()),
+            inner: ResidentObjectMetadataInner::new_dynamic(offset, layout, partial_dirtiness_tracking),
+        }
+    }
+
     pub(crate) const fn fresh_object_dirty_size<T>(
         enable_partial_dirtiness_tracking: bool,
     ) -> usize {
-        calc_dirty_metadata_dirty_byte_cnt(enable_partial_dirtiness_tracking, size_of::<T>())
+        Self::fresh_object_dirty_size_dynamic(enable_partial_dirtiness_tracking, size_of::<T>())
+    }
+
+    pub(crate) const fn fresh_object_dirty_size_dynamic(
+        enable_partial_dirtiness_tracking: bool,
+        data_size: usize,
+    ) -> usize {
+        calc_dirty_metadata_dirty_byte_cnt(enable_partial_dirtiness_tracking, data_size)
     }
 
     #[inline]
@@ -187,11 +231,11 @@ impl ResidentObjectMetadata {
             .add(size_of::<ResidentObjectMetadata>());
 
         // align base pointer (add alignment, because T could be aligned)
-        let base_ptr = ((meta_ptr as usize) + (self.inner.layout.align() - 1))
+        let aligned_addr = ((meta_ptr as usize) + (self.inner.layout.align() - 1))
             & !(self.inner.layout.align() - 1);
 
-        // convert back to pointer
-        let base_ptr = base_ptr as *const u8;
+        // derive from meta_ptr instead of casting the address back to keep its provenance
+        let base_ptr = meta_ptr.add(aligned_addr - (meta_ptr as usize));
 
         // test if the right offset was applied
         #[cfg(debug_assertions)]
@@ -249,6 +293,61 @@ impl ResidentObjectMetadata {
         storage.read(offset, range)
     }
 
+    /// Calculates the checksum of the resident user data
+    ///
+    /// ### Safety
+    ///
+    /// This call is only safe to call if this ResidentObjectMetadataInner lives inside a ResidentObjectMetadata and a ResidentObject instance.
+    #[cfg(feature = "object_checksums")]
+    pub(crate) unsafe fn calc_checksum(&self) -> ObjectChecksum {
+        crate::util::crc32(self.dynamic_metadata_to_data_range())
+    }
+
+    /// Reads the checksum that was stored together with the user data of this object
+    #[cfg(feature = "object_checksums")]
+    pub(crate) fn load_checksum<S: PersistentStorageModule>(
+        &self,
+        storage: &mut S,
+    ) -> Result<ObjectChecksum, ()> {
+        let offset = self.inner.offset + calc_backup_obj_checksum_offset();
+        unsafe { read_storage_data(storage, offset) }
+    }
+
+    /// Reads the access counter of this object from storage
+    #[cfg(feature = "access_counters")]
+    pub(crate) fn load_access_count<S: PersistentStorageModule>(
+        &mut self,
+        storage: &mut S,
+    ) -> Result<(), ()> {
+        let offset = self.inner.offset + calc_backup_obj_access_count_offset();
+        self.inner.access_count = unsafe { read_storage_data(storage, offset) }?;
+        self.inner.status.set_access_count_dirty(false);
+        Ok(())
+    }
+
+    /// Increments the access counter (this is only written to storage once this object is synced or unloaded)
+    #[cfg(feature = "access_counters")]
+    pub(crate) fn record_access(&mut self) {
+        self.inner.access_count = self.inner.access_count.saturating_add(1);
+        self.inner.status.set_access_count_dirty(true);
+    }
+
+    /// Writes the access counter back to storage if it changed
+    #[cfg(feature = "access_counters")]
+    pub(crate) fn sync_access_count<S: PersistentStorageModule>(
+        &mut self,
+        storage: &mut S,
+    ) -> Result<(), ()> {
+        if !self.inner.status.is_access_count_dirty() {
+            return Ok(());
+        }
+
+        let offset = self.inner.offset + calc_backup_obj_access_count_offset();
+        write_storage_data(storage, offset, &self.inner.access_count)?;
+        self.inner.status.set_access_count_dirty(false);
+        Ok(())
+    }
+
     /// Unloads this resident object dynamically by indirectly calculating the layout of that object.
     /// This is a good option to do if you don't know `T` of this resident object.
     ///
@@ -266,6 +365,7 @@ impl ResidentObjectMetadata {
         storage: &mut S,
         allocator_module: &SharedPersistLock<*mut A>,
         dirty_size: &mut usize,
+        stats: &mut HeapStats,
     ) -> Result<(), ()> {
         debug_assert!(
             !delete_handle.get_element().inner.status.is_in_use(),
@@ -277,8 +377,31 @@ impl ResidentObjectMetadata {
         // sync unsynced changes
         let _ = delete_handle
             .get_element()
-            .persist_user_data_dynamic(storage)?;
+            .persist_user_data_dynamic(storage, stats)?;
+
+        #[cfg(feature = "access_counters")]
+        delete_handle.get_element().sync_access_count(storage)?;
 
+        stats.object_unloaded(delete_handle.get_element());
+
+        Self::remove_resident_object_dynamic(delete_handle, allocator_module);
+
+        *dirty_size += prev_dirty_size;
+
+        Ok(())
+    }
+
+    /// Removes this resident object from the resident list and deallocates it **without** syncing it.
+    ///
+    /// The caller has to give the dirty bytes of this object back (see `dirty_size`).
+    ///
+    /// ### Safety
+    ///
+    /// Same as `unload_resident_object_dynamic`
+    pub(crate) unsafe fn remove_resident_object_dynamic<A: AllocatorModule>(
+        delete_handle: DeleteHandle,
+        allocator_module: &SharedPersistLock<*mut A>,
+    ) {
         {
             // IMPORTANT: lock the shared persist lock for this modify block
             // because there are race conditions between this and vnv_persist_all (deallocate is not atomar)
@@ -309,10 +432,6 @@ impl ResidentObjectMetadata {
 
             drop(guard);
         }
-
-        *dirty_size += prev_dirty_size;
-
-        Ok(())
     }
 
     /// Persists the user data of this resident object if you don't know the type `T` of the inner data.
@@ -326,6 +445,7 @@ impl ResidentObjectMetadata {
     pub(crate) unsafe fn persist_user_data_dynamic<S: PersistentStorageModule>(
         &mut self,
         storage: &mut S,
+        stats: &mut HeapStats,
     ) -> Result<usize, ()> {
         if !self.inner.status.is_data_dirty() {
             return Ok(0);
@@ -333,8 +453,15 @@ impl ResidentObjectMetadata {
 
         let size_persisted = self.write_user_data_dynamic(storage)?;
 
+        stats.object_written_back(self, size_persisted);
+
+        // piggyback on this sync
+        #[cfg(feature = "access_counters")]
+        self.sync_access_count(storage)?;
+
         // everything is persisted, not dirty anymore
         self.inner.status.set_data_dirty(false);
+        self.inner.status.set_backup_missing(false);
 
         // does nothing if partial dirtiness tracking is not enabled
         self.inner
@@ -345,6 +472,56 @@ impl ResidentObjectMetadata {
         Ok(size_persisted)
     }
 
+    /// Discards all changes of the user data by reloading the dirty parts from storage.
+    /// Afterwards, the user data is not dirty anymore.
+    ///
+    /// Returns an error if the user data was never written to storage.
+    ///
+    /// ### Safety
+    ///
+    /// This call is only safe to call if this ResidentObjectMetadataInner lives inside a ResidentObjectMetadata and a ResidentObject instance.
+    pub(crate) unsafe fn discard_user_data_dynamic<S: PersistentStorageModule>(
+        &mut self,
+        storage: &mut S,
+    ) -> Result<(), VNVError> {
+        if !self.inner.status.is_data_dirty() {
+            return Ok(());
+        }
+
+        if self.inner.status.is_backup_missing() {
+            // there is no clean version of this object we could restore
+            return Err(VNVError::Unsupported);
+        }
+
+        let offset = self.inner.offset + calc_backup_obj_user_data_offset();
+
+        if !self.inner.status.is_partial_dirtiness_tracking_enabled() {
+            // reload whole object
+            storage.read(offset, self.dynamic_metadata_to_data_range_mut())?;
+        } else {
+            // only reload the dirty parts, all other parts are already in sync with storage
+            let base_ptr = self.dynamic_metadata_to_data_range_internal() as *mut u8;
+
+            let mut wrapper = self.inner.partial_dirtiness_tracking_info.get_wrapper(self);
+            let mut iter = wrapper.dirty_iter();
+
+            while let Some(range) = iter.next() {
+                let slice = slice_from_raw_parts_mut(base_ptr.add(range.start), range.len());
+                storage.read(offset + range.start, slice.as_mut().unwrap())?;
+            }
+        }
+
+        self.inner.status.set_data_dirty(false);
+
+        // does nothing if partial dirtiness tracking is not enabled
+        self.inner
+            .partial_dirtiness_tracking_info
+            .get_wrapper(self)
+            .set_all_blocks_synced();
+
+        Ok(())
+    }
+
     /// Writes the user data of this resident object if you don't know the type `T` of the inner data.
     /// This function differs from `persist_user_data_dynamic` that is does not update the dirty state of this object.
     ///
@@ -359,13 +536,13 @@ impl ResidentObjectMetadata {
 
         let offset = self.inner.offset + calc_backup_obj_user_data_offset();
 
-        if !self.inner.status.is_partial_dirtiness_tracking_enabled() {
+        let synced_byte_count = if !self.inner.status.is_partial_dirtiness_tracking_enabled() {
             // sync whole object
             let data_range = self.dynamic_metadata_to_data_range();
             storage.write(offset, data_range)?;
 
             debug_assert_eq!(data_range.len(), self.inner.layout.size());
-            Ok(data_range.len())
+            data_range.len()
         } else {
             // sync object partially
             let mut wrapper = self.inner.partial_dirtiness_tracking_info.get_wrapper(self);
@@ -387,8 +564,18 @@ impl ResidentObjectMetadata {
 
                 synced_byte_count += slice.len()
             }
-            Ok(synced_byte_count)
-        }
+            synced_byte_count
+        };
+
+        // the checksum is written after the data, so a torn data write is detected on the next load
+        #[cfg(feature = "object_checksums")]
+        write_storage_data(
+            storage,
+            self.inner.offset + calc_backup_obj_checksum_offset(),
+            &self.calc_checksum(),
+        )?;
+
+        Ok(synced_byte_count)
     }
 
 }
@@ -15,12 +15,26 @@
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-const IS_IN_USE: u8 = 1 << 0;
-const IS_MUTABLE_REF_ACTIVE: u8 = 1 << 1;
-const ENABLE_PARTIAL_DIRTINESS_TRACKING: u8 = 1 << 2;
-const DATA_DIRTY: u8 = 1 << 3;
-const CLOCK_ACCESSED: u8 = 1 << 4;
-const CLOCK_MODIFIED: u8 = 1 << 5;
+#[cfg(feature = "persist_priority")]
+use super::persist_priority::PersistPriority;
+
+const IS_IN_USE: u16 = 1 << 0;
+const IS_MUTABLE_REF_ACTIVE: u16 = 1 << 1;
+const ENABLE_PARTIAL_DIRTINESS_TRACKING: u16 = 1 << 2;
+const DATA_DIRTY: u16 = 1 << 3;
+const CLOCK_ACCESSED: u16 = 1 << 4;
+const CLOCK_MODIFIED: u16 = 1 << 5;
+const BACKUP_MISSING: u16 = 1 << 6;
+#[cfg(feature = "access_counters")]
+const ACCESS_COUNT_DIRTY: u16 = 1 << 7;
+const IS_PINNED: u16 = 1 << 8;
+#[cfg(feature = "dirty_pools")]
+const IS_BACKGROUND: u16 = 1 << 9;
+const WRITTEN_BACK: u16 = 1 << 10;
+#[cfg(feature = "persist_priority")]
+const PRIORITY_HIGH: u16 = 1 << 11;
+#[cfg(feature = "persist_priority")]
+const PRIORITY_CRITICAL: u16 = 1 << 12;
 
 /*
 The bit usage is as follows:
@@ -31,13 +45,18 @@ The bit usage is as follows:
 3    Is Data Dirty (also used as a cache if partial dirtiness tracking is enabled)
 4    Clock status bit: was accessed (for more information look into ClockObjectManagementModule)
 5    Clock status bit: was modified (for more information look into ClockObjectManagementModule)
-6    [Unused]
-7    [Unused]
+6    Is Backup Missing (the user data was never written to its storage location, e.g. for newly allocated resident objects)
+7    Is Access Count Dirty (only used with the `access_counters` feature)
+8    Is Pinned (the object must not be unloaded, see `VNVObject::pin`)
+9    Is Background (the dirty user data belongs to the background pool, only used with the `dirty_pools` feature)
+10   Is Written Back (the dirty user data was written to its storage location by `vnv_persist_budget` and not modified since then)
+11   Has Priority High (see `PersistPriority`, only used with the `persist_priority` feature)
+12   Has Priority Critical (see `PersistPriority`, only used with the `persist_priority` feature)
 */
 
 #[derive(Clone, Copy, PartialEq)]
 pub(crate) struct ResidentObjectStatus {
-    bit_list: u8,
+    bit_list: u16,
 }
 
 macro_rules! generate_functions {
@@ -72,12 +91,12 @@ impl ResidentObjectStatus {
     }
 
     #[inline]
-    fn is_set(&self, bitmask: u8) -> bool {
+    fn is_set(&self, bitmask: u16) -> bool {
         (self.bit_list & bitmask) != 0
     }
 
     #[inline]
-    fn set(&mut self, bitmask: u8, state: bool) {
+    fn set(&mut self, bitmask: u16, state: bool) {
         if state {
             // set
             self.bit_list |= bitmask;
@@ -110,6 +129,30 @@ impl ResidentObjectStatus {
         is_clock_modified_bit_set,
         set_clock_modified_bit
     );
+    generate_functions!(BACKUP_MISSING, is_backup_missing, set_backup_missing);
+    generate_functions!(IS_PINNED, is_pinned, set_pinned);
+    generate_functions!(WRITTEN_BACK, is_written_back, set_written_back);
+    #[cfg(feature = "access_counters")]
+    generate_functions!(ACCESS_COUNT_DIRTY, is_access_count_dirty, set_access_count_dirty);
+    #[cfg(feature = "dirty_pools")]
+    generate_functions!(IS_BACKGROUND, is_background, set_background);
+
+    #[cfg(feature = "persist_priority")]
+    pub(crate) fn get_persist_priority(&self) -> PersistPriority {
+        if self.is_set(PRIORITY_CRITICAL) {
+            PersistPriority::Critical
+        } else if self.is_set(PRIORITY_HIGH) {
+            PersistPriority::High
+        } else {
+            PersistPriority::Normal
+        }
+    }
+
+    #[cfg(feature = "persist_priority")]
+    pub(crate) fn set_persist_priority(&mut self, priority: PersistPriority) {
+        self.set(PRIORITY_HIGH, priority == PersistPriority::High);
+        self.set(PRIORITY_CRITICAL, priority == PersistPriority::Critical);
+    }
 }
 
 impl Default for ResidentObjectStatus {
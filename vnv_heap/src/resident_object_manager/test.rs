@@ -19,11 +19,8 @@ use core::{
     array,
     ptr::{null, null_mut},
 };
-use core::sync::atomic::AtomicBool;
 use std::mem::size_of;
 
-use try_lock::TryLock;
-
 use crate::{
     allocation_identifier::AllocationIdentifier,
     modules::{
@@ -32,10 +29,26 @@ use crate::{
         object_management::DefaultObjectManagementModule,
         persistent_storage::{test::get_test_storage, PersistentStorageModule},
     },
+    persist_sync::{AtomicBool, TryLock},
     resident_object_manager::{calc_backup_obj_layout_static, calc_backup_obj_user_data_offset, resident_list::ResidentList}, shared_persist_lock::SharedPersistLock,
 };
 
 use super::ResidentObjectManager;
+use crate::WriteBack;
+
+/// Writes the user data of an object that was allocated directly in `storage`
+/// (together with its checksum, if `object_checksums` is enabled)
+pub(crate) fn write_test_obj_data<S: PersistentStorageModule>(storage: &mut S, offset: usize, data: &[u8]) {
+    storage.write(offset + calc_backup_obj_user_data_offset(), data).unwrap();
+
+    #[cfg(feature = "object_checksums")]
+    crate::modules::persistent_storage::persistent_storage_util::write_storage_data(
+        storage,
+        offset + super::resident_object_backup::calc_backup_obj_checksum_offset(),
+        &crate::util::crc32(data),
+    )
+    .unwrap();
+}
 
 // test that dirty size will return to
 // its initial value once no objects are resident anymore
@@ -63,6 +76,7 @@ fn test_release_dirty_size() {
         ResidentObjectManager::<LinkedListAllocatorModule, DefaultObjectManagementModule>::new(
             &mut buffer,
             INITIAL_DIRTY_SIZE,
+            WriteBack::Lazy,
             &mut resident_list,
             shared_heap_lock
         )
@@ -80,7 +94,7 @@ fn test_release_dirty_size() {
             .unwrap();
 
         // zero out space
-        storage.write(offset + calc_backup_obj_user_data_offset(), &initial_data).unwrap();
+        write_test_obj_data(&mut storage, offset, &initial_data);
 
         offset
     });
@@ -92,6 +106,7 @@ fn test_release_dirty_size() {
                     .get_ref(
                         &AllocationIdentifier::<TestObj>::from_offset(*offset),
                         false,
+                        None,
                         &mut storage,
                     )
                     .unwrap();
@@ -114,12 +129,14 @@ fn test_release_dirty_size() {
                     .get_mut(
                         &AllocationIdentifier::<TestObj>::from_offset(*offset),
                         false,
+                        None,
                         &mut storage,
                     )
                     .unwrap();
 
                 manager.release_mut(
                     &AllocationIdentifier::<TestObj>::from_offset(*offset),
+                    &mut storage,
                 );
             }
         }
@@ -164,6 +181,7 @@ fn test_remain_resident() {
         ResidentObjectManager::<BuddyAllocatorModule<16>, DefaultObjectManagementModule>::new(
             &mut buffer,
             INITIAL_DIRTY_SIZE,
+            WriteBack::Lazy,
             &mut resident_list,
             shared_heap_lock,
 
@@ -182,7 +200,7 @@ fn test_remain_resident() {
             .unwrap();
 
         // zero out space
-        storage.write(offset + calc_backup_obj_user_data_offset(), &initial_data).unwrap();
+        write_test_obj_data(&mut storage, offset, &initial_data);
 
         offset
     });
@@ -225,6 +243,7 @@ fn test_remain_resident() {
                     .get_ref(
                         &AllocationIdentifier::<TestObj>::from_offset(*offset),
                         false,
+                        None,
                         &mut storage,
                     )
                     .unwrap();
@@ -240,6 +259,7 @@ fn test_remain_resident() {
                     .get_mut(
                         &AllocationIdentifier::<TestObj>::from_offset(*offset),
                         false,
+                        None,
                         &mut storage,
                     )
                     .unwrap();
@@ -274,7 +294,8 @@ fn test_remain_resident() {
     for i in 0..mut_offsets.len() {
         unsafe {
             manager.release_mut(
-                &AllocationIdentifier::<TestObj>::from_offset(mut_offsets[i])
+                &AllocationIdentifier::<TestObj>::from_offset(mut_offsets[i]),
+                &mut storage,
             );
         }
     }
@@ -293,3 +314,77 @@ fn test_remain_resident() {
     assert_eq!(manager.count_resident_objects(), 0);
     assert!(manager.resident_list.is_empty());
 }
+
+// test that discarding changes of a partially tracked object
+// only restores the dirty blocks and releases their dirty size
+#[test]
+fn test_discard_changes_partial_tracking() {
+    const INITIAL_DIRTY_SIZE: usize = 1000;
+    const STORAGE_SIZE: usize = 4096;
+    type TestObj = [u8; 300];
+
+    let mut buffer = [0u8; 1000];
+    let mut storage = get_test_storage("rom_test_discard_changes_partial_tracking", STORAGE_SIZE);
+    let mut non_resident_alloc = NonResidentBuddyAllocatorModule::<16>::new();
+
+    let mut resident_list = ResidentList::new();
+
+    let mut heap = LinkedListAllocatorModule::new();
+
+    let lock = TryLock::new(());
+    let persist_queued = AtomicBool::new(false);
+    let shared_heap_lock: SharedPersistLock<*mut LinkedListAllocatorModule> =
+        SharedPersistLock::new(&mut heap, &persist_queued, &lock);
+
+    let mut manager =
+        ResidentObjectManager::<LinkedListAllocatorModule, DefaultObjectManagementModule>::new(
+            &mut buffer,
+            INITIAL_DIRTY_SIZE,
+            WriteBack::Lazy,
+            &mut resident_list,
+            shared_heap_lock
+        )
+        .unwrap();
+
+    non_resident_alloc
+        .init(0, STORAGE_SIZE, &mut storage)
+        .unwrap();
+
+    let initial_data: TestObj = [5u8; 300];
+    let offset = non_resident_alloc
+        .allocate(calc_backup_obj_layout_static::<TestObj>(), &mut storage)
+        .unwrap();
+    write_test_obj_data(&mut storage, offset, &initial_data);
+
+    let alloc_id = AllocationIdentifier::<TestObj>::from_offset(offset);
+
+    unsafe {
+        manager.require_resident(&alloc_id, true, &mut storage).unwrap();
+    }
+    let clean_dirty_size = manager.remaining_dirty_size;
+
+    unsafe {
+        let (meta_ptr, data_ptr) = manager.get_partial_mut(&alloc_id, &mut storage).unwrap();
+        let meta_ref = meta_ptr.as_mut().unwrap();
+
+        manager.partial_mut_make_range_dirty(meta_ref, 10, 1, &mut storage).unwrap();
+        (*data_ptr)[10] = 1;
+        manager.partial_mut_make_range_dirty(meta_ref, 250, 1, &mut storage).unwrap();
+        (*data_ptr)[250] = 2;
+
+        manager.release_partial_mut::<TestObj, _>(meta_ptr, &mut storage);
+    }
+    assert!(manager.remaining_dirty_size < clean_dirty_size);
+
+    manager.discard_changes(&alloc_id, &mut storage).unwrap();
+    assert_eq!(manager.remaining_dirty_size, clean_dirty_size);
+
+    unsafe {
+        let data_ptr = manager.get_ref(&alloc_id, true, None, &mut storage).unwrap();
+        assert_eq!(*data_ptr, initial_data);
+        manager.release_ref(&alloc_id);
+    }
+
+    manager.drop(&alloc_id, true, &mut storage).unwrap();
+    assert_eq!(manager.remaining_dirty_size, INITIAL_DIRTY_SIZE);
+}
@@ -0,0 +1,90 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::cmp::max;
+
+use super::ResidentObjectManager;
+use crate::{
+    modules::{allocator::AllocatorModule, object_management::ObjectManagementModule},
+    vnv_heap::Watermarks,
+};
+
+/// High-water marks of a heap
+pub(crate) struct WatermarkState {
+    /// Bytes that are currently allocated in storage
+    storage_bytes: usize,
+
+    /// How many bytes can be dirty in total
+    dirty_budget: usize,
+
+    marks: Watermarks,
+}
+
+impl WatermarkState {
+    pub(crate) const fn new(dirty_budget: usize) -> Self {
+        Self {
+            storage_bytes: 0,
+            dirty_budget,
+            marks: Watermarks {
+                max_storage_bytes: 0,
+                max_resident_bytes: 0,
+                max_dirty_bytes: 0,
+            },
+        }
+    }
+
+    /// Has to be called after `size` bytes were allocated in storage
+    pub(crate) fn storage_allocated(&mut self, size: usize) {
+        self.storage_bytes += size;
+        self.marks.max_storage_bytes = max(self.marks.max_storage_bytes, self.storage_bytes);
+    }
+
+    /// Has to be called after `size` bytes were deallocated in storage
+    pub(crate) fn storage_deallocated(&mut self, size: usize) {
+        self.storage_bytes = self.storage_bytes.saturating_sub(size);
+    }
+
+    /// Has to be called after `remaining_dirty_size` of the `ResidentObjectManager` was decreased
+    pub(crate) fn update_dirty_watermark(&mut self, remaining_dirty_size: usize) {
+        self.marks.max_dirty_bytes = max(
+            self.marks.max_dirty_bytes,
+            self.dirty_budget.saturating_sub(remaining_dirty_size),
+        );
+    }
+
+    /// Returns the high-water marks of the heap
+    pub(crate) fn get_watermarks(&self) -> Watermarks {
+        self.marks
+    }
+}
+
+impl<'a, 'b, A: AllocatorModule, M: ObjectManagementModule> ResidentObjectManager<'a, 'b, A, M> {
+    /// Has to be called after an object was made resident
+    pub(crate) fn update_resident_watermark(&mut self) {
+        let usage = self.get_resident_usage();
+        let watermarks = &mut self.watermarks;
+        watermarks.marks.max_resident_bytes = max(
+            watermarks.marks.max_resident_bytes,
+            usage.user_bytes + usage.metadata_bytes,
+        );
+    }
+
+    /// Has to be called after the dirty budget was changed (see `set_dirty_limit`)
+    pub(crate) fn update_dirty_budget_watermark(&mut self) {
+        self.watermarks.dirty_budget = self.get_dirty_budget();
+    }
+}
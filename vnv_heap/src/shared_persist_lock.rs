@@ -17,12 +17,14 @@
 
 use core::{
     ops::{Deref, DerefMut},
-    sync::atomic::{AtomicBool, Ordering},
+    sync::atomic::Ordering,
     cell::UnsafeCell, mem::ManuallyDrop
 };
-use try_lock::{Locked, TryLock};
 
-use crate::{persist_access_point::print_persist_debug, vnv_persist_all};
+use crate::{
+    persist_access_point::print_persist_debug,
+    persist_sync::{run_queued_persist, AtomicBool, Locked, TryLock},
+};
 
 pub(crate) struct SharedPersistLock<'a, T> {
     persist_queued: &'a AtomicBool,
@@ -39,6 +41,11 @@ impl<'a, T> SharedPersistLock<'a, T> {
         }
     }
 
+    /// Returns the value of this instance without locking (clones of this lock have their own value)
+    pub(crate) fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+
     pub(crate) fn try_lock<'b>(&'b self) -> Option<SharedPersistGuard<'a, 'b, T>> {
         self.lock.try_lock().map(|lock| SharedPersistGuard {
             persist_queued: self.persist_queued,
@@ -83,13 +90,7 @@ impl<T> Drop for SharedPersistGuard<'_, '_, T> {
 
             // persist was called during this lock call
             // call persist again, as now the lock is available again
-
-            // as currently only one heap can be created and this heap is only active in one thread
-            // its safe to assume that no other threads should be running
-            // (before calling vnv_persist_all the first time, it has to be made sure that all other threads stop)
-            unsafe {
-                vnv_persist_all();
-            }
+            run_queued_persist();
         }
     }
 }
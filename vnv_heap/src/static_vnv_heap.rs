@@ -0,0 +1,177 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use crate::{
+    modules::{
+        allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
+        object_management::ObjectManagementModule, persistent_storage::PersistentStorageModule,
+    },
+    VNVConfig, VNVError, VNVHeap,
+};
+
+const STATE_UNINIT: u8 = 0;
+const STATE_INITIALIZING: u8 = 1;
+const STATE_READY: u8 = 2;
+
+/// Heap that owns its resident buffer of `SIZE` bytes, so it can be stored in a `static` (see `static_vnv_heap!`).
+///
+/// Like every other heap, it is registered for `vnv_persist_all` once it is initialized.
+///
+/// The heap itself is not thread safe, so `init` and `get` are `unsafe`: it may only be used from one thread
+/// (which can be interrupted by `vnv_persist_all`). Use `SyncVNVHeap` to share a heap between threads.
+pub struct StaticVNVHeap<
+    A: AllocatorModule + 'static,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+    S: PersistentStorageModule + 'static,
+    const SIZE: usize,
+> {
+    state: AtomicU8,
+    buffer: UnsafeCell<[u8; SIZE]>,
+    heap: UnsafeCell<MaybeUninit<VNVHeap<'static, A, N, M, S>>>,
+}
+
+// `Sync` is needed to store the heap in a static. This is sound because the heap can only be reached
+// through `init` and `get`, which require that it is only used by one thread (see their safety sections)
+unsafe impl<
+        A: AllocatorModule + 'static,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+        S: PersistentStorageModule + 'static,
+        const SIZE: usize,
+    > Sync for StaticVNVHeap<A, N, M, S, SIZE>
+{
+}
+
+impl<
+        A: AllocatorModule + 'static,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+        S: PersistentStorageModule + 'static,
+        const SIZE: usize,
+    > StaticVNVHeap<A, N, M, S, SIZE>
+{
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(STATE_UNINIT),
+            buffer: UnsafeCell::new([0u8; SIZE]),
+            heap: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Creates the heap (see `VNVHeap::new`) with the resident buffer of this static.
+    ///
+    /// Fails with `VNVError::Unsupported` if the heap was already initialized.
+    ///
+    /// ### Safety
+    ///
+    /// The heap may only be used by one thread: `init`, `get` and the returned references must not be used
+    /// by any other thread than the one that initialized the heap (interrupts by `vnv_persist_all` are fine).
+    pub unsafe fn init(
+        &'static self,
+        storage_module: S,
+        heap: A,
+        config: VNVConfig,
+        persist_handler: fn(*mut u8, usize) -> (),
+    ) -> Result<&'static VNVHeap<'static, A, N, M, S>, VNVError> {
+        if self
+            .state
+            .compare_exchange(STATE_UNINIT, STATE_INITIALIZING, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return Err(VNVError::Unsupported);
+        }
+
+        // the buffer is only borrowed by the heap, which is created at most once at a time
+        let resident_buffer = &mut *self.buffer.get();
+        match VNVHeap::new(resident_buffer, storage_module, heap, config, persist_handler) {
+            Ok(vnv_heap) => {
+                let vnv_heap = (*self.heap.get()).write(vnv_heap);
+                self.state.store(STATE_READY, Ordering::SeqCst);
+                Ok(vnv_heap)
+            }
+            Err(err) => {
+                self.state.store(STATE_UNINIT, Ordering::SeqCst);
+                Err(err)
+            }
+        }
+    }
+
+    /// Returns the heap, or `None` if `init` was not called yet
+    ///
+    /// ### Safety
+    ///
+    /// Has to be called from the thread that initialized the heap (see `init`).
+    pub unsafe fn get(&'static self) -> Option<&'static VNVHeap<'static, A, N, M, S>> {
+        if self.state.load(Ordering::SeqCst) != STATE_READY {
+            return None;
+        }
+
+        Some((*self.heap.get()).assume_init_ref())
+    }
+
+    /// Drops the heap, so `init` can be called again.
+    ///
+    /// ### Safety
+    ///
+    /// All references that were returned by `init` and `get` (and all objects of this heap) must not be used anymore.
+    pub unsafe fn deinit(&'static self) {
+        if self
+            .state
+            .compare_exchange(STATE_READY, STATE_INITIALIZING, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            (*self.heap.get()).assume_init_drop();
+            self.state.store(STATE_UNINIT, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Defines a `static` heap with a resident buffer of `SIZE` bytes, so no buffer has to be passed around.
+///
+/// The allocator modules default to `LinkedListAllocatorModule`, `NonResidentBuddyAllocatorModule<16>`
+/// and `DefaultObjectManagementModule`, but can also be specified after the storage module:
+///
+/// ```ignore
+/// static_vnv_heap!(static HEAP: 4096, MyStorageModule);
+///
+/// // the heap is only used by this thread
+/// let heap = unsafe { HEAP.init(storage, LinkedListAllocatorModule::new(), config, persist_handler)? };
+/// let counter = heap.allocate(0u32)?;
+/// ```
+#[macro_export]
+macro_rules! static_vnv_heap {
+    ($vis:vis static $name:ident: $size:expr, $storage:ty) => {
+        $crate::static_vnv_heap!(
+            $vis static $name: $size,
+            $storage,
+            $crate::modules::allocator::LinkedListAllocatorModule,
+            $crate::modules::nonresident_allocator::NonResidentBuddyAllocatorModule<16>,
+            $crate::modules::object_management::DefaultObjectManagementModule
+        );
+    };
+    ($vis:vis static $name:ident: $size:expr, $storage:ty, $allocator:ty, $non_resident_allocator:ty, $object_management:ty) => {
+        $vis static $name: $crate::StaticVNVHeap<$allocator, $non_resident_allocator, $object_management, $storage, { $size }> =
+            $crate::StaticVNVHeap::new();
+    };
+}
@@ -0,0 +1,81 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::vnv_config::StorageThroughput;
+
+/// Sizes of the transfers that are measured during calibration
+pub(crate) const CALIBRATION_TRANSFER_SIZES: [usize; 4] = [16, 64, 128, 256];
+
+/// Size of the storage region (and stack buffer) that is used for calibration
+pub(crate) const CALIBRATION_BUFFER_SIZE: usize = 256;
+
+/// Least squares fit of measured transfer times
+pub(crate) struct LinearFit {
+    count: i128,
+    sum_bytes: i128,
+    sum_time: i128,
+    sum_bytes_squared: i128,
+    sum_bytes_time: i128,
+}
+
+impl LinearFit {
+    pub(crate) const fn new() -> Self {
+        Self {
+            count: 0,
+            sum_bytes: 0,
+            sum_time: 0,
+            sum_bytes_squared: 0,
+            sum_bytes_time: 0,
+        }
+    }
+
+    /// Adds one measurement of transferring `bytes` that took `time_us`
+    pub(crate) fn add(&mut self, bytes: usize, time_us: u64) {
+        let (bytes, time_us) = (bytes as i128, time_us as i128);
+        self.count += 1;
+        self.sum_bytes += bytes;
+        self.sum_time += time_us;
+        self.sum_bytes_squared += bytes * bytes;
+        self.sum_bytes_time += bytes * time_us;
+    }
+
+    /// Returns the throughput that matches the measurements best.
+    ///
+    /// If the time does not grow with the transfer size (e.g. because the clock is too coarse),
+    /// `bytes_per_ms` is `u32::MAX`.
+    pub(crate) fn fit(&self) -> StorageThroughput {
+        // slope (µs per byte) = slope_num / slope_den
+        let slope_num = self.count * self.sum_bytes_time - self.sum_bytes * self.sum_time;
+        let slope_den = self.count * self.sum_bytes_squared - self.sum_bytes * self.sum_bytes;
+        if slope_num <= 0 || slope_den <= 0 {
+            return StorageThroughput {
+                fixed_latency_us: clamp_u32(self.sum_time / self.count.max(1)),
+                bytes_per_ms: u32::MAX,
+            };
+        }
+
+        let intercept = (self.sum_time * slope_den - slope_num * self.sum_bytes) / (self.count * slope_den);
+        StorageThroughput {
+            fixed_latency_us: clamp_u32(intercept.max(0)),
+            bytes_per_ms: clamp_u32((1000 * slope_den / slope_num).max(1)),
+        }
+    }
+}
+
+fn clamp_u32(value: i128) -> u32 {
+    value.clamp(0, u32::MAX as i128) as u32
+}
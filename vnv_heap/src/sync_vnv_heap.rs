@@ -0,0 +1,100 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::{
+    modules::{
+        allocator::AllocatorModule, lock::LockModule, nonresident_allocator::NonResidentAllocatorModule,
+        object_management::ObjectManagementModule, persistent_storage::PersistentStorageModule,
+    },
+    VNVHeap,
+};
+
+/// Heap that can be shared between threads.
+///
+/// All accesses to the heap happen inside `with`, which holds the lock of `L` while it runs.
+/// Objects cannot leave this closure, use links (see `VNVHeap::with_link`) to access them again later.
+///
+/// The lock does not protect against `vnv_persist_all`: like before, it has to be called
+/// while all other threads are stopped (e.g. from an interrupt handler).
+/// If a thread is inside of `with` at that time, persisting is delayed until that thread finished
+/// its current operation on the heap.
+pub struct SyncVNVHeap<
+    'a,
+    L: LockModule,
+    A: AllocatorModule + 'static,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+    S: PersistentStorageModule + 'static,
+> {
+    lock: L,
+    heap: VNVHeap<'a, A, N, M, S>,
+}
+
+// all accesses to the heap are serialized by the lock, so the modules are only used by one thread at a time
+// (but they have to be `Send`, as this can be a different thread every time)
+unsafe impl<
+        L: LockModule + Sync,
+        A: AllocatorModule + Send + 'static,
+        N: NonResidentAllocatorModule + Send,
+        M: ObjectManagementModule + Send,
+        S: PersistentStorageModule + Send + 'static,
+    > Sync for SyncVNVHeap<'_, L, A, N, M, S>
+{
+}
+
+impl<
+        'a,
+        L: LockModule,
+        A: AllocatorModule + 'static,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+        S: PersistentStorageModule + 'static,
+    > SyncVNVHeap<'a, L, A, N, M, S>
+{
+    pub fn new(heap: VNVHeap<'a, A, N, M, S>, lock: L) -> Self {
+        Self { lock, heap }
+    }
+
+    /// Acquires the lock and calls `f` with the heap.
+    pub fn with<R>(&self, f: impl FnOnce(&VNVHeap<'a, A, N, M, S>) -> R) -> R {
+        let _guard = LockGuard::new(&self.lock);
+        f(&self.heap)
+    }
+
+    /// Returns the heap, which can only be used by one thread again
+    pub fn into_inner(self) -> VNVHeap<'a, A, N, M, S> {
+        self.heap
+    }
+}
+
+/// Releases the lock again, even if the closure panics
+struct LockGuard<'a, L: LockModule> {
+    lock: &'a L,
+}
+
+impl<'a, L: LockModule> LockGuard<'a, L> {
+    fn new(lock: &'a L) -> Self {
+        lock.lock();
+        Self { lock }
+    }
+}
+
+impl<L: LockModule> Drop for LockGuard<'_, L> {
+    fn drop(&mut self) {
+        self.lock.unlock();
+    }
+}
@@ -0,0 +1,131 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::ptr::slice_from_raw_parts_mut;
+
+use crate::{
+    modules::persistent_storage::persistent_storage_util::read_storage_data,
+    resident_object_manager::resident_object_backup::calc_backup_obj_access_count_offset,
+    vnv_persist_all, ObjectAccessCount,
+};
+
+use super::get_test_heap;
+
+#[test]
+fn test_access_counters() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_access_counters", 4 * 4096, &mut buffer, 2000, |_, _| {});
+
+    let mut obj = heap.allocate([1u32; 20]).unwrap();
+    assert_eq!(obj.get_access_count().unwrap(), 0);
+
+    for _ in 0..3 {
+        let _obj_ref = obj.get().unwrap();
+    }
+    {
+        let mut mut_ref = obj.get_mut().unwrap();
+        mut_ref[0] = 2;
+    }
+    assert_eq!(obj.get_access_count().unwrap(), 4);
+
+    // counter is written back together with the object
+    obj.unload().unwrap();
+    assert!(!obj.is_resident());
+    assert_eq!(obj.get_access_count().unwrap(), 4);
+
+    let offset = obj.get_alloc_id().offset;
+    let stored: ObjectAccessCount = unsafe {
+        read_storage_data(
+            heap.get_inner().borrow_mut().get_storage_module(),
+            offset + calc_backup_obj_access_count_offset(),
+        )
+        .unwrap()
+    };
+    assert_eq!(stored, 4);
+
+    // loading the object again continues counting
+    assert_eq!(*obj.get().unwrap(), {
+        let mut expected = [1u32; 20];
+        expected[0] = 2;
+        expected
+    });
+    assert_eq!(obj.get_access_count().unwrap(), 5);
+
+    // flushing also synchronizes the counter
+    {
+        let mut mut_ref = obj.get_mut().unwrap();
+        mut_ref[1] = 3;
+    }
+    obj.flush().unwrap();
+    let stored: ObjectAccessCount = unsafe {
+        read_storage_data(
+            heap.get_inner().borrow_mut().get_storage_module(),
+            offset + calc_backup_obj_access_count_offset(),
+        )
+        .unwrap()
+    };
+    assert_eq!(stored, 6);
+}
+
+#[test]
+fn test_access_counters_independent_objects() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_access_counters_independent_objects", 4 * 4096, &mut buffer, 2000, |_, _| {});
+
+    let mut a = heap.allocate(0u64).unwrap();
+    let mut b = heap.allocate(0u64).unwrap();
+
+    for i in 0..10 {
+        *a.get_mut().unwrap() += 1;
+        if i % 2 == 0 {
+            let _ = b.get().unwrap();
+            b.unload().unwrap();
+        }
+    }
+
+    assert_eq!(a.get_access_count().unwrap(), 10);
+    assert_eq!(b.get_access_count().unwrap(), 5);
+    assert_eq!(*a.get().unwrap(), 10);
+}
+
+#[test]
+fn test_access_counters_persist() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap(
+        "test_access_counters_persist",
+        4 * 4096,
+        &mut buffer,
+        2000,
+        |base_ptr, size| {
+            // simulate power loss
+            let buffer = unsafe { slice_from_raw_parts_mut(base_ptr, size).as_mut() }.unwrap();
+            buffer.fill(0);
+        },
+    );
+
+    let mut obj = heap.allocate([1u32; 20]).unwrap();
+    for _ in 0..3 {
+        let _obj_ref = obj.get().unwrap();
+    }
+
+    // unsynced accesses are part of the persisted state
+    unsafe { vnv_persist_all() };
+    assert!(obj.is_resident());
+    assert_eq!(obj.get_access_count().unwrap(), 3);
+
+    obj.unload().unwrap();
+    assert_eq!(obj.get_access_count().unwrap(), 3);
+}
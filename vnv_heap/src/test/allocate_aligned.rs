@@ -0,0 +1,58 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::VNVAligned;
+
+use super::get_test_heap;
+
+#[test]
+fn test_allocate_aligned() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_allocate_aligned", 4 * 4096, &mut buffer, 2000, |_, _| {});
+
+    // the following objects would not be aligned by chance
+    let _small1 = heap.allocate(1u8).unwrap();
+    let _small2 = heap.allocate(2u8).unwrap();
+
+    #[cfg(not(any(feature = "access_counters", feature = "object_checksums")))]
+    {
+        let mut obj = heap.allocate_aligned::<[u8; 40], 32>([3u8; 40]).unwrap();
+        assert_eq!(obj.get_alloc_id().offset % 32, 0);
+        for _ in 0..2 {
+            {
+                let data = obj.get().unwrap();
+                assert_eq!(**data, [3u8; 40]);
+                assert_eq!(&*data as *const VNVAligned<[u8; 40], 32> as usize % 32, 0);
+            }
+
+            // the object is aligned again after it is loaded from storage
+            obj.unload().unwrap();
+        }
+    }
+
+    // the object header breaks the alignment in storage
+    #[cfg(any(feature = "access_counters", feature = "object_checksums"))]
+    assert!(matches!(
+        heap.allocate_aligned::<[u8; 40], 32>([3u8; 40]),
+        Err(crate::VNVError::Unsupported)
+    ));
+
+    // smaller alignments are still possible with object headers
+    let mut obj = heap.allocate_aligned::<u32, 4>(4).unwrap();
+    **obj.get_mut().unwrap() += 1;
+    obj.unload().unwrap();
+    assert_eq!(**obj.get().unwrap(), 5);
+}
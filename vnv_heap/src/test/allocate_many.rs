@@ -0,0 +1,77 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::test::{get_instrumented_test_heap, get_test_heap, StorageOperation};
+
+#[test]
+fn test_allocate_many() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_allocate_many", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let mut objs = heap.allocate_many::<[u32; 3], 10>(|i| [i as u32; 3]).unwrap();
+    for obj in objs.iter() {
+        assert!(!obj.is_resident());
+    }
+
+    for (i, obj) in objs.iter_mut().enumerate() {
+        assert_eq!(*obj.get().unwrap(), [i as u32; 3]);
+        obj.get_mut().unwrap()[0] = 100;
+    }
+    for (i, obj) in objs.iter_mut().enumerate() {
+        obj.unload().unwrap();
+        assert_eq!(*obj.get().unwrap(), [100, i as u32, i as u32]);
+    }
+    drop(objs);
+
+    // objects that do not fit into the stack buffer are written one by one
+    let mut objs = heap.allocate_many::<[u8; 300], 3>(|i| [i as u8; 300]).unwrap();
+    for (i, obj) in objs.iter_mut().enumerate() {
+        assert_eq!(*obj.get().unwrap(), [i as u8; 300]);
+    }
+}
+
+#[test]
+fn test_allocate_many_placed_together() {
+    static READS: AtomicUsize = AtomicUsize::new(0);
+    fn count_reads(op: StorageOperation) {
+        if matches!(op, StorageOperation::Read { .. }) {
+            READS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let mut buffer = [0u8; 2000];
+    let heap = get_instrumented_test_heap("test_allocate_many_placed_together", &mut buffer, count_reads);
+
+    let mut objs = heap.allocate_many::<[u32; 4], 6>(|i| [i as u32; 4]).unwrap();
+    for pair in objs.windows(2) {
+        assert!(pair[0].get_alloc_id().offset < pair[1].get_alloc_id().offset);
+    }
+
+    READS.store(0, Ordering::SeqCst);
+    {
+        let [a, b, c, d, e, f] = &mut objs;
+        heap.get_many(&mut [a, b, c, d, e, f]).unwrap();
+    }
+    #[cfg(not(any(feature = "access_counters", feature = "object_checksums")))]
+    assert_eq!(READS.load(Ordering::SeqCst), 1);
+
+    for (i, obj) in objs.iter_mut().enumerate() {
+        assert_eq!(*obj.get().unwrap(), [i as u32; 4]);
+    }
+}
@@ -0,0 +1,268 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    future::Future,
+    pin::{pin, Pin},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Wake, Waker},
+};
+
+use crate::{
+    modules::{
+        allocator::LinkedListAllocatorModule,
+        nonresident_allocator::NonResidentBuddyAllocatorModule,
+        object_management::DefaultObjectManagementModule,
+        persistent_storage::{
+            test::get_test_storage, AsyncPersistentStorageModule, FilePersistentStorageModule,
+            PersistentStorageModule,
+        },
+    },
+    VNVConfig, VNVHeap, WriteBack,
+};
+
+/// Wraps a storage module and yields once before each async read
+struct YieldingStorageModule {
+    inner: FilePersistentStorageModule,
+    yields: &'static AtomicUsize,
+    fail_reads: &'static AtomicBool,
+}
+
+impl PersistentStorageModule for YieldingStorageModule {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        self.inner.read(offset, dest)
+    }
+
+    fn get_max_size(&self) -> usize {
+        self.inner.get_max_size()
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        self.inner.write(offset, src)
+    }
+}
+
+impl AsyncPersistentStorageModule for YieldingStorageModule {
+    async fn read_async(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        YieldOnce(false).await;
+        self.yields.fetch_add(1, Ordering::SeqCst);
+
+        if self.fail_reads.load(Ordering::SeqCst) {
+            return Err(());
+        }
+        self.inner.read(offset, dest)
+    }
+}
+
+struct YieldOnce(bool);
+
+impl Future for YieldOnce {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+/// Minimal executor that polls `future` until it is ready
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(NoopWaker));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+
+    loop {
+        if let Poll::Ready(res) = future.as_mut().poll(&mut cx) {
+            return res;
+        }
+    }
+}
+
+type AsyncTestHeap<'a> = VNVHeap<
+    'a,
+    LinkedListAllocatorModule,
+    NonResidentBuddyAllocatorModule<16>,
+    DefaultObjectManagementModule,
+    YieldingStorageModule,
+>;
+
+fn get_async_test_heap<'a>(
+    test_name: &str,
+    resident_buffer: &'a mut [u8],
+    yields: &'static AtomicUsize,
+    fail_reads: &'static AtomicBool,
+) -> AsyncTestHeap<'a> {
+    let storage = YieldingStorageModule {
+        inner: get_test_storage(test_name, 4 * 4096),
+        yields,
+        fail_reads,
+    };
+
+    VNVHeap::new(
+        resident_buffer,
+        storage,
+        LinkedListAllocatorModule::new(),
+        VNVConfig {
+            max_dirty_bytes: 1000,
+            write_back: WriteBack::Lazy,
+            persist_latency_budget: None,
+        },
+        |_, _| {},
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_async_get() {
+    static YIELDS: AtomicUsize = AtomicUsize::new(0);
+    static FAIL_READS: AtomicBool = AtomicBool::new(false);
+
+    let mut buffer = [0u8; 1000];
+    let heap = get_async_test_heap("test_async_get", &mut buffer, &YIELDS, &FAIL_READS);
+
+    let mut obj = heap.allocate([1u32; 20]).unwrap();
+    obj.unload().unwrap();
+    assert!(!obj.is_resident());
+
+    block_on(async {
+        let obj_ref = obj.get_async(&heap).await.unwrap();
+        assert_eq!(*obj_ref, [1u32; 20]);
+    });
+    assert!(obj.is_resident());
+    assert_eq!(YIELDS.load(Ordering::SeqCst), 1);
+
+    // already resident: no storage access required
+    block_on(async {
+        let mut obj_ref = obj.get_mut_async(&heap).await.unwrap();
+        obj_ref[3] = 4;
+    });
+    assert_eq!(YIELDS.load(Ordering::SeqCst), 1);
+
+    obj.unload().unwrap();
+    block_on(async {
+        let mut obj_ref = obj.get_mut_async(&heap).await.unwrap();
+        assert_eq!(obj_ref[3], 4);
+        obj_ref[4] = 5;
+    });
+    assert_eq!(YIELDS.load(Ordering::SeqCst), 2);
+
+    obj.unload().unwrap();
+    let obj_ref = obj.get().unwrap();
+    assert_eq!(obj_ref[3], 4);
+    assert_eq!(obj_ref[4], 5);
+}
+
+#[test]
+fn test_async_get_read_error() {
+    static YIELDS: AtomicUsize = AtomicUsize::new(0);
+    static FAIL_READS: AtomicBool = AtomicBool::new(false);
+
+    let mut buffer = [0u8; 1000];
+    let heap = get_async_test_heap("test_async_get_read_error", &mut buffer, &YIELDS, &FAIL_READS);
+
+    let mut obj = heap.allocate(123usize).unwrap();
+    obj.unload().unwrap();
+    let dirty_size = heap.get_inner().borrow().get_remaining_dirty_size();
+
+    FAIL_READS.store(true, Ordering::SeqCst);
+    assert!(block_on(obj.get_async(&heap)).is_err());
+
+    // failed load should not leave anything behind
+    assert!(!obj.is_resident());
+    assert_eq!(heap.get_resident_usage().resident_objects, 0);
+    assert_eq!(heap.get_inner().borrow().get_remaining_dirty_size(), dirty_size);
+
+    FAIL_READS.store(false, Ordering::SeqCst);
+    assert_eq!(*block_on(obj.get_async(&heap)).unwrap(), 123);
+}
+
+#[test]
+fn test_async_get_cancelled() {
+    static YIELDS: AtomicUsize = AtomicUsize::new(0);
+    static FAIL_READS: AtomicBool = AtomicBool::new(false);
+
+    let mut buffer = [0u8; 1000];
+    let heap = get_async_test_heap("test_async_get_cancelled", &mut buffer, &YIELDS, &FAIL_READS);
+
+    let mut obj = heap.allocate(123usize).unwrap();
+    obj.unload().unwrap();
+    let dirty_size = heap.get_inner().borrow().get_remaining_dirty_size();
+
+    {
+        // poll once (storage read is pending) and drop the future afterwards
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(obj.get_async(&heap));
+        assert!(future.as_mut().poll(&mut cx).is_pending());
+    }
+
+    // partially loaded object should have been removed again
+    assert!(!obj.is_resident());
+    assert_eq!(heap.get_resident_usage().resident_objects, 0);
+    assert_eq!(heap.get_inner().borrow().get_remaining_dirty_size(), dirty_size);
+
+    assert_eq!(*block_on(obj.get_async(&heap)).unwrap(), 123);
+}
+
+#[test]
+fn test_async_get_concurrent_access() {
+    static YIELDS: AtomicUsize = AtomicUsize::new(0);
+    static FAIL_READS: AtomicBool = AtomicBool::new(false);
+
+    let mut buffer = [0u8; 1000];
+    let heap = get_async_test_heap("test_async_get_concurrent_access", &mut buffer, &YIELDS, &FAIL_READS);
+
+    let mut obj1 = heap.allocate(123usize).unwrap();
+    let mut obj2 = heap.allocate(456usize).unwrap();
+    obj1.unload().unwrap();
+
+    {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(obj1.get_async(&heap));
+        assert!(future.as_mut().poll(&mut cx).is_pending());
+
+        // heap is not borrowed while the read is pending
+        assert_eq!(*obj2.get().unwrap(), 456);
+
+        // but the storage is still locked
+        assert!(obj2.unload().is_err());
+        assert!(heap.allocate(789usize).is_err());
+
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(obj_ref) => assert_eq!(*obj_ref.unwrap(), 123),
+            Poll::Pending => panic!("read should have finished"),
+        }
+    }
+
+    obj2.unload().unwrap();
+    assert_eq!(*obj2.get().unwrap(), 456);
+}
@@ -18,7 +18,7 @@
 use std::{thread, time::Instant};
 
 use crate::{
-    benchmarks::{run_all_benchmarks, BenchmarkRunOptions, DummyPersistTrigger, RunAllBenchmarkOptions, Timer},
+    benchmarks::{run_all_benchmarks, Benchmark, BenchmarkRunOptions, BenchmarkSuite, BenchmarkSuiteOptions, DummyPersistTrigger, RunAllBenchmarkOptions, Timer},
     modules::persistent_storage::FilePersistentStorageModule,
 };
 
@@ -37,9 +37,13 @@ fn test_benchmarks() {
         >(
             BenchmarkRunOptions {
                 cold_start: 0,
+                cold_start_buffer: &mut [],
                 machine_name: "desktop",
                 repetitions: 10,
                 result_buffer: &mut [0; 10],
+                comparison: None,
+                sizes: None,
+                suites: BenchmarkSuiteOptions::new(),
             },
             RunAllBenchmarkOptions::microbenchmarks(),
             get_storage,
@@ -53,6 +57,106 @@ fn test_benchmarks() {
 
 }
 
+/// Returns the number of the current run as its latency
+struct CountingBenchmark {
+    runs: u32,
+}
+
+impl Benchmark<()> for CountingBenchmark {
+    fn get_name(&self) -> &'static str {
+        "counting"
+    }
+
+    fn get_bench_options(&self) {}
+
+    fn execute<T: Timer>(&mut self) -> u32 {
+        self.runs += 1;
+        self.runs
+    }
+}
+
+#[test]
+fn test_benchmark_cold_start() {
+    let mut cold_start_buffer = [0; 3];
+    let mut result_buffer = [0; 4];
+
+    let res = CountingBenchmark { runs: 0 }.run_benchmark::<DesktopTimer>(&mut BenchmarkRunOptions {
+        cold_start: 3,
+        cold_start_buffer: &mut cold_start_buffer,
+        machine_name: "desktop",
+        repetitions: 4,
+        result_buffer: &mut result_buffer,
+        comparison: None,
+        sizes: None,
+        suites: BenchmarkSuiteOptions::new(),
+    });
+
+    // cold start runs are recorded separately and are not part of the results
+    assert_eq!(cold_start_buffer, [1, 2, 3]);
+    assert_eq!(result_buffer, [4, 5, 6, 7]);
+    assert_eq!(res.min_latency, 4);
+}
+
+#[test]
+fn test_benchmark_suite_options() {
+    let mut cold_start_buffer = [0; 3];
+    let mut result_buffer = [0; 10];
+    let mut run_options = BenchmarkRunOptions {
+        cold_start: 3,
+        cold_start_buffer: &mut cold_start_buffer,
+        machine_name: "desktop",
+        repetitions: 10,
+        result_buffer: &mut result_buffer,
+        comparison: None,
+        sizes: None,
+        suites: BenchmarkSuiteOptions::new()
+            .repetitions(BenchmarkSuite::LongPersistentStorage, 2)
+            .cold_start(BenchmarkSuite::LongPersistentStorage, 0)
+            .sizes(BenchmarkSuite::Implementation, &[16, 256]),
+    };
+
+    {
+        let suite_options = run_options.for_suite(BenchmarkSuite::LongPersistentStorage);
+        assert_eq!(suite_options.repetitions, 2);
+        assert_eq!(suite_options.result_buffer.len(), 2);
+        assert_eq!(suite_options.cold_start, 0);
+        assert!(suite_options.cold_start_buffer.is_empty());
+        assert!(suite_options.runs_size(8));
+    }
+    {
+        let suite_options = run_options.for_suite(BenchmarkSuite::Implementation);
+        assert_eq!(suite_options.repetitions, 10);
+        assert_eq!(suite_options.cold_start_buffer.len(), 3);
+        assert!(suite_options.runs_size(256));
+        assert!(!suite_options.runs_size(8));
+    }
+
+    // the results of each suite are written to the shared buffers
+    let res = CountingBenchmark { runs: 0 }
+        .run_benchmark::<DesktopTimer>(&mut run_options.for_suite(BenchmarkSuite::LongPersistentStorage));
+    assert_eq!(res.min_latency, 1);
+    assert_eq!(res.max_latency, 2);
+    assert_eq!(result_buffer[..3], [1, 2, 0]);
+}
+
+#[test]
+#[should_panic]
+fn test_benchmark_suite_options_buffer_too_small() {
+    let mut result_buffer = [0; 2];
+    let mut run_options = BenchmarkRunOptions {
+        cold_start: 0,
+        cold_start_buffer: &mut [],
+        machine_name: "desktop",
+        repetitions: 2,
+        result_buffer: &mut result_buffer,
+        comparison: None,
+        sizes: None,
+        suites: BenchmarkSuiteOptions::new().repetitions(BenchmarkSuite::Queue, 5),
+    };
+
+    run_options.for_suite(BenchmarkSuite::Queue);
+}
+
 fn get_storage() -> FilePersistentStorageModule {
     get_test_storage("test.data", 4096 * 8)
 }
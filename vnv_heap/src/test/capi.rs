@@ -0,0 +1,92 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use core::{ffi::c_void, ptr::null_mut};
+
+use crate::{
+    capi::{
+        vnv_alloc, vnv_free, vnv_get, vnv_heap_deinit, vnv_heap_init, vnv_mark_dirty, vnv_persist, vnv_release,
+        VNVHandle, VNVStatus,
+    },
+    modules::persistent_storage::VNVStorageCallbacks,
+};
+
+extern "C" fn read(context: *mut c_void, offset: usize, dest: *mut u8, len: usize) -> i32 {
+    let storage = unsafe { &*(context as *const Vec<u8>) };
+    match storage.get(offset..offset + len) {
+        Some(src) => {
+            unsafe { dest.copy_from_nonoverlapping(src.as_ptr(), len) };
+            0
+        }
+        None => -1,
+    }
+}
+
+extern "C" fn write(context: *mut c_void, offset: usize, src: *const u8, len: usize) -> i32 {
+    let storage = unsafe { &mut *(context as *mut Vec<u8>) };
+    match storage.get_mut(offset..offset + len) {
+        Some(dest) => {
+            unsafe { dest.as_mut_ptr().copy_from_nonoverlapping(src, len) };
+            0
+        }
+        None => -1,
+    }
+}
+
+#[test]
+fn test_capi() {
+    let mut storage = vec![0u8; 4 * 4096];
+    let callbacks = VNVStorageCallbacks {
+        context: &mut storage as *mut Vec<u8> as *mut c_void,
+        size: storage.len(),
+        read,
+        write,
+        flush: None,
+    };
+    let mut buffer = [0u8; 2000];
+
+    let mut handle = VNVHandle::default();
+    assert_eq!(unsafe { vnv_alloc(16, 4, &mut handle) }, VNVStatus::NotInitialized);
+
+    assert_eq!(unsafe { vnv_heap_init(buffer.as_mut_ptr(), buffer.len(), callbacks, 1000) }, VNVStatus::Ok);
+    assert_eq!(unsafe { vnv_heap_init(buffer.as_mut_ptr(), buffer.len(), callbacks, 1000) }, VNVStatus::Unsupported);
+
+    unsafe {
+        assert_eq!(vnv_alloc(16, 3, &mut handle), VNVStatus::InvalidArgument);
+        assert_eq!(vnv_alloc(100, 4, &mut handle), VNVStatus::Ok);
+
+        // dirty bytes can only be marked while the object is accessed
+        assert_eq!(vnv_mark_dirty(handle, 0, 4), VNVStatus::NotResident);
+
+        let mut ptr = null_mut();
+        assert_eq!(vnv_get(handle, &mut ptr), VNVStatus::Ok);
+        assert_eq!(vnv_mark_dirty(handle, 0, 4), VNVStatus::Ok);
+        assert_eq!(vnv_mark_dirty(handle, 98, 4), VNVStatus::InvalidArgument);
+        (ptr as *mut u32).write(42);
+        assert_eq!(vnv_release(handle), VNVStatus::Ok);
+
+        vnv_persist();
+
+        assert_eq!(vnv_get(handle, &mut ptr), VNVStatus::Ok);
+        assert_eq!((ptr as *const u32).read(), 42);
+        assert_eq!(vnv_release(handle), VNVStatus::Ok);
+
+        assert_eq!(vnv_free(handle), VNVStatus::Ok);
+        assert_eq!(vnv_get(handle, &mut ptr), VNVStatus::InvalidHandle);
+
+        assert_eq!(vnv_heap_deinit(), VNVStatus::Ok);
+    }
+}
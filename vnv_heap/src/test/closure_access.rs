@@ -0,0 +1,50 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::get_test_heap;
+
+#[test]
+fn test_closure_access() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_closure_access", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let mut obj = heap.allocate([0u32; 16]).unwrap();
+    obj.unload().unwrap();
+
+    let sum = obj.with(|data| data.iter().sum::<u32>()).unwrap();
+    assert_eq!(sum, 0);
+    assert!(obj.is_resident());
+    assert!(!obj.is_data_dirty());
+
+    // the object is not in use anymore, so it can be unloaded right away
+    obj.unload().unwrap();
+
+    obj.with_mut(|data| {
+        for (i, x) in data.iter_mut().enumerate() {
+            *x = i as u32;
+        }
+    })
+    .unwrap();
+    assert!(obj.is_data_dirty());
+    obj.unload().unwrap();
+
+    let sum = obj.with(|data| data.iter().sum::<u32>()).unwrap();
+    assert_eq!(sum, (0..16).sum::<u32>());
+
+    obj.with_mut(|data| data[0] = 42).unwrap();
+    assert_eq!(obj.with(|data| data[0]).unwrap(), 42);
+}
@@ -0,0 +1,82 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::modules::compression::Lz4CompressionModule;
+
+use super::get_test_heap;
+
+fn sparse_data(seed: u32) -> [u32; 256] {
+    let mut data = [0u32; 256];
+    for i in (0..256).step_by(64) {
+        data[i] = seed + i as u32;
+    }
+    data
+}
+
+fn random_data() -> [u32; 256] {
+    core::array::from_fn(|i| (i as u32).wrapping_mul(2654435761).rotate_left(13))
+}
+
+#[test]
+fn test_compressed_object() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_compressed_object", 2 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let mut obj = heap.allocate_compressed(sparse_data(1), Lz4CompressionModule::new()).unwrap();
+    assert_eq!(obj.get().unwrap(), sparse_data(1));
+    assert!(obj.is_compressed());
+    assert!(obj.get_stored_size() < 100);
+    assert_eq!(heap.count_resident_objects::<[u32; 256]>(), 0);
+
+    obj.update(|data| data[100] = 7).unwrap();
+    let mut expected = sparse_data(1);
+    expected[100] = 7;
+    assert_eq!(obj.get().unwrap(), expected);
+
+    // incompressible data is stored uncompressed and needs a bigger extent
+    obj.set(random_data()).unwrap();
+    assert!(!obj.is_compressed());
+    assert_eq!(obj.get_stored_size(), 1024);
+    assert!(obj.get_allocated_size() >= 1024);
+    assert_eq!(obj.get().unwrap(), random_data());
+
+    // the extent shrinks again once the data is compressible
+    obj.set(sparse_data(2)).unwrap();
+    assert!(obj.is_compressed());
+    assert!(obj.get_allocated_size() < 1024);
+    assert_eq!(obj.get().unwrap(), sparse_data(2));
+}
+
+#[test]
+fn test_compressed_object_saves_storage() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_compressed_object_saves_storage", 2 * 4096, &mut buffer, 1000, |_, _| {});
+
+    // uncompressed, these objects would need twice the size of the storage
+    let objects: Vec<_> = (0..16)
+        .map(|i| heap.allocate_compressed(sparse_data(i), Lz4CompressionModule::new()).unwrap())
+        .collect();
+
+    for (i, obj) in objects.iter().enumerate() {
+        assert_eq!(obj.get().unwrap(), sparse_data(i as u32));
+    }
+
+    // storage is freed again if the objects are dropped
+    drop(objects);
+    let obj = heap.allocate_compressed(random_data(), Lz4CompressionModule::new()).unwrap();
+    assert_eq!(obj.get().unwrap(), random_data());
+}
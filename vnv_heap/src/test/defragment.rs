@@ -0,0 +1,131 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::cell::Cell;
+
+use crate::{vnv_persist_all, VNVLink};
+
+use super::get_test_heap;
+
+/// Clock that does not advance, so only the budget estimated from the storage timing is used
+fn frozen_clock() -> u64 {
+    0
+}
+
+/// Clock that advances by 100 µs every time it is read
+fn slow_clock() -> u64 {
+    thread_local! {
+        static NOW: Cell<u64> = const { Cell::new(0) };
+    }
+    NOW.with(|now| {
+        now.set(now.get() + 100);
+        now.get()
+    })
+}
+
+#[test]
+fn test_defragment() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_defragment", 8192, &mut buffer, 1200, |_, _| {});
+
+    // fill the storage, then free every other object so that no buddies can be merged
+    let mut objs = vec![];
+    while let Ok(obj) = heap.allocate([objs.len() as u8; 50]) {
+        objs.push(obj);
+    }
+    objs.sort_by_key(|obj| obj.get_alloc_id().offset);
+    let mut objs: Vec<_> = objs.into_iter().skip(1).step_by(2).collect();
+    assert!(heap.allocate([0u8; 500]).is_err());
+
+    let expected: Vec<u8> = objs.iter_mut().map(|obj| obj.get().unwrap()[0]).collect();
+
+    // one object is moved even if the budget is smaller than the time it takes to move it
+    {
+        let mut refs: Vec<_> = objs.iter_mut().collect();
+        assert_eq!(heap.defragment(&mut refs, 0, slow_clock, |_, _| {}).unwrap(), 1);
+        assert_eq!(heap.defragment(&mut refs[1..], 150, slow_clock, |_, _| {}).unwrap(), 2);
+    }
+
+    loop {
+        let mut refs: Vec<_> = objs.iter_mut().collect();
+        let moved = heap.defragment(&mut refs, 200, slow_clock, |_, _| {}).unwrap();
+
+        // with the `recovery` feature, only a few objects can be moved between two persists
+        unsafe { vnv_persist_all() };
+        if moved == 0 {
+            break;
+        }
+    }
+
+    let big = heap.allocate([0u8; 500]).unwrap();
+    drop(big);
+
+    for (obj, expected) in objs.iter_mut().zip(expected) {
+        assert!(obj.get().unwrap().iter().all(|x| *x == expected));
+    }
+}
+
+#[test]
+fn test_defragment_skips_pinned() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_defragment_skips_pinned", 8192, &mut buffer, 1200, |_, _| {});
+
+    let a = heap.allocate([1u8; 50]).unwrap();
+    let b = heap.allocate([1u8; 50]).unwrap();
+    let (low, mut high) = if a.get_alloc_id().offset < b.get_alloc_id().offset { (a, b) } else { (b, a) };
+    let expected = high.get_alloc_id().offset;
+    drop(low);
+
+    high.pin().unwrap();
+    assert_eq!(heap.defragment(&mut [&mut high], 1000, frozen_clock, |_, _| {}).unwrap(), 0);
+    assert_eq!(high.get_alloc_id().offset, expected);
+
+    high.unpin();
+    assert_eq!(heap.defragment(&mut [&mut high], 1000, frozen_clock, |_, _| {}).unwrap(), 1);
+    assert!(high.get_alloc_id().offset < expected);
+    assert!(high.get().unwrap().iter().all(|x| *x == 1));
+}
+
+#[test]
+fn test_defragment_relocated_links() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_defragment_relocated_links", 8192, &mut buffer, 1200, |_, _| {});
+
+    let a = heap.allocate([1u8; 50]).unwrap();
+    let b = heap.allocate([2u8; 50]).unwrap();
+    let (low, mut high) = if a.get_alloc_id().offset < b.get_alloc_id().offset { (a, b) } else { (b, a) };
+    let expected = high.get().unwrap()[0];
+    let mut holder = heap.allocate(Some(high.link())).unwrap();
+    drop(low);
+
+    // the link stored in `holder` is updated, it can be accessed as the heap is not borrowed
+    let moved = heap
+        .defragment(&mut [&mut high], 1000, frozen_clock, |old: VNVLink<[u8; 50]>, new| {
+            assert_ne!(old, new);
+            let mut stored = holder.get_mut().unwrap();
+            if *stored == Some(old) {
+                *stored = Some(new);
+            }
+        })
+        .unwrap();
+    assert_eq!(moved, 1);
+
+    let link = holder.get().unwrap().unwrap();
+    assert!(high.is_linked_by(&link));
+    let value = unsafe { heap.with_link(&link, |obj| obj.get().unwrap()[0]) };
+    assert_eq!(value, expected);
+}
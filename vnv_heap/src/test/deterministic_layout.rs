@@ -0,0 +1,79 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::{
+    modules::{
+        allocator::LinkedListAllocatorModule,
+        persistent_storage::{test::get_test_storage, PersistentStorageModule},
+    },
+    vnv_persist_all, VNVConfig, VNVHeap, WriteBack,
+};
+
+use super::TestHeap;
+
+const STORAGE_SIZE: usize = 4 * 4096;
+
+/// Runs the same operations on a heap and returns the resulting storage image.
+///
+/// `garbage` is written to the storage before and `buffer_offset` moves the resident buffer,
+/// both must not have an influence on the image.
+fn run_operations(test_name: &str, garbage: u8, buffer_offset: usize) -> Vec<u8> {
+    let mut storage = get_test_storage(test_name, STORAGE_SIZE);
+    storage.write(0, &[garbage; STORAGE_SIZE]).unwrap();
+
+    let mut buffer = vec![0u8; 1200 + buffer_offset];
+    let heap: TestHeap = VNVHeap::new(
+        &mut buffer[buffer_offset..],
+        storage,
+        LinkedListAllocatorModule::new(),
+        VNVConfig { max_dirty_bytes: 1200, write_back: WriteBack::Lazy, persist_latency_budget: None },
+        |_, _| {},
+    )
+    .unwrap();
+
+    let mut a = heap.allocate([1u32; 10]).unwrap();
+    let b = heap.allocate([2u8; 100]).unwrap();
+    let mut c = heap.allocate(5u64).unwrap();
+    let d = heap.allocate([3u8; 30]).unwrap();
+
+    // freed blocks are reused in a different order than they were freed
+    drop(d);
+    drop(b);
+    let mut e = heap.allocate([4u8; 20]).unwrap();
+    let mut f = heap.allocate([5u8; 90]).unwrap();
+
+    a.get_mut().unwrap()[3] = 42;
+    unsafe { vnv_persist_all() };
+
+    let _slice = heap.allocate_from_iter(50, 0..50u16).unwrap();
+
+    a.unload().unwrap();
+    c.unload().unwrap();
+    e.unload().unwrap();
+    f.unload().unwrap();
+
+    std::fs::read(format!("/tmp/{}.tmp", test_name)).unwrap()
+}
+
+#[test]
+fn test_deterministic_layout() {
+    let first = run_operations("test_deterministic_layout_1", 0xAB, 0);
+    let second = run_operations("test_deterministic_layout_2", 0x17, 8);
+
+    assert_eq!(first.len(), STORAGE_SIZE);
+    assert!(first == second, "storage images differ");
+}
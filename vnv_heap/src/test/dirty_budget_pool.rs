@@ -0,0 +1,80 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::{DirtyBudgetPool, VNVError};
+
+use super::get_test_heap;
+
+#[test]
+fn test_dirty_budget_pool() {
+    static POOL: DirtyBudgetPool = DirtyBudgetPool::new(2000);
+
+    {
+        let mut buffer = [0u8; 2000];
+        let heap = get_test_heap("test_dirty_budget_pool", 4 * 4096, &mut buffer, 1500, |_, _| {});
+        heap.set_dirty_budget_pool(Some(&POOL)).unwrap();
+        assert_eq!(POOL.get_available_bytes(), 500);
+
+        // registering again does not draw the bytes twice
+        heap.set_dirty_budget_pool(Some(&POOL)).unwrap();
+        assert_eq!(POOL.get_available_bytes(), 500);
+
+        // shrinking returns bytes to the pool
+        heap.set_max_dirty_bytes(1000).unwrap();
+        assert_eq!(POOL.get_available_bytes(), 1000);
+
+        // e.g. application state that is saved in the same power-fail window
+        assert!(POOL.try_reserve(600));
+        assert!(!POOL.try_reserve(600));
+
+        // growing only succeeds if the pool has enough bytes left
+        assert_eq!(heap.set_max_dirty_bytes(1500), Err(VNVError::DirtyBudgetExhausted));
+        assert_eq!(heap.get_max_dirty_bytes(), 1000);
+        assert_eq!(POOL.get_available_bytes(), 400);
+
+        POOL.release(600);
+        heap.set_max_dirty_bytes(1500).unwrap();
+        assert_eq!(POOL.get_available_bytes(), 500);
+
+        heap.set_dirty_budget_pool(None).unwrap();
+        assert_eq!(POOL.get_available_bytes(), 2000);
+        heap.set_dirty_budget_pool(Some(&POOL)).unwrap();
+        assert_eq!(POOL.get_available_bytes(), 500);
+    }
+
+    // dropping the heap returns its bytes
+    assert_eq!(POOL.get_available_bytes(), 2000);
+}
+
+#[test]
+fn test_dirty_budget_pool_exhausted() {
+    static POOL: DirtyBudgetPool = DirtyBudgetPool::new(1000);
+
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_dirty_budget_pool_exhausted", 4 * 4096, &mut buffer, 1500, |_, _| {});
+
+    // registers with a lower limit instead
+    assert_eq!(heap.set_dirty_budget_pool(Some(&POOL)), Err(VNVError::DirtyBudgetExhausted));
+    assert_eq!(POOL.get_available_bytes(), 1000);
+    heap.set_max_dirty_bytes(1000).unwrap();
+    heap.set_dirty_budget_pool(Some(&POOL)).unwrap();
+    assert_eq!(POOL.get_available_bytes(), 0);
+
+    // dirty objects are synced to stay within the pool
+    let objects: Vec<_> = (0..20).map(|i| heap.allocate([i as u8; 64]).unwrap()).collect();
+    assert!(objects.iter().filter(|obj| obj.is_data_dirty()).count() < 20);
+    assert_eq!(heap.set_max_dirty_bytes(1001), Err(VNVError::DirtyBudgetExhausted));
+}
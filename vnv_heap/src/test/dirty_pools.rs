@@ -0,0 +1,90 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::VNVError;
+
+use super::get_test_heap;
+
+#[test]
+fn test_background_dirty_limit() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_background_dirty_limit", 4 * 4096, &mut buffer, 2000, |_, _| {});
+    heap.set_background_dirty_limit(Some(250)).unwrap();
+
+    let mut control = heap.allocate([0u8; 100]).unwrap();
+    let mut logs = vec![];
+    for i in 0..6u8 {
+        logs.push(heap.allocate_background([i; 100]).unwrap());
+        assert!(heap.get_dirty_pool_usage().background_bytes <= 250);
+    }
+
+    for (i, log) in logs.iter_mut().enumerate() {
+        log.get_mut().unwrap()[0] = 100 + i as u8;
+
+        let usage = heap.get_dirty_pool_usage();
+        assert!(usage.background_bytes <= 250);
+        assert!(usage.background_bytes >= 100);
+        assert_eq!(usage.background_limit, Some(250));
+    }
+
+    control.get_mut().unwrap()[0] = 1;
+    assert_eq!(heap.get_dirty_pool_usage().foreground_bytes, 100);
+
+    // foreground access is not limited by the background pool
+    assert_eq!(control.get().unwrap()[0], 1);
+
+    for (i, log) in logs.iter_mut().enumerate() {
+        log.unload().unwrap();
+        let data = log.get().unwrap();
+        assert_eq!(data[0], 100 + i as u8);
+        assert_eq!(data[1], i as u8);
+    }
+}
+
+#[test]
+fn test_background_dirty_limit_rebalance() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_background_dirty_limit_rebalance", 4 * 4096, &mut buffer, 2000, |_, _| {});
+
+    let mut log1 = heap.allocate_background([1u8; 100]).unwrap();
+    let mut log2 = heap.allocate_background([2u8; 100]).unwrap();
+    log1.get_mut().unwrap()[0] = 10;
+    log2.get_mut().unwrap()[0] = 20;
+    assert_eq!(heap.get_dirty_pool_usage().background_bytes, 200);
+    assert_eq!(heap.get_dirty_pool_usage().background_limit, None);
+
+    {
+        // objects that are in use cannot be synced
+        let _ref1 = log1.get_mut().unwrap();
+        assert_eq!(heap.set_background_dirty_limit(Some(50)), Err(VNVError::DirtyPoolExhausted));
+        assert_eq!(heap.get_dirty_pool_usage().background_limit, None);
+    }
+
+    heap.set_background_dirty_limit(Some(100)).unwrap();
+    assert_eq!(heap.get_dirty_pool_usage().background_bytes, 100);
+
+    heap.set_background_dirty_limit(Some(50)).unwrap();
+    assert_eq!(heap.get_dirty_pool_usage().background_bytes, 0);
+
+    // objects that are bigger than the limit cannot be modified in the background
+    assert_eq!(log1.get_mut().err(), Some(VNVError::DirtyPoolExhausted));
+    assert!(!log1.is_data_dirty());
+
+    heap.set_background_dirty_limit(None).unwrap();
+    assert_eq!(log1.get_mut().unwrap()[0], 10);
+    assert_eq!(log2.get().unwrap()[0], 20);
+}
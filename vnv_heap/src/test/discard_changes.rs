@@ -0,0 +1,92 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::VNVError;
+
+use super::get_test_heap;
+
+fn remaining_dirty_size(heap: &super::TestHeap) -> usize {
+    heap.get_inner().borrow_mut().get_resident_object_manager().remaining_dirty_size
+}
+
+#[test]
+fn test_discard_changes() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_discard_changes", 4 * 4096, &mut buffer, 2000, |_, _| {});
+
+    let mut obj = heap.allocate([1u32; 20]).unwrap();
+
+    // never synchronized, there is nothing to restore
+    assert_eq!(obj.discard_changes(), Err(VNVError::Unsupported));
+
+    obj.unload().unwrap();
+    let clean_dirty_size = remaining_dirty_size(&heap);
+
+    {
+        let mut mut_ref = obj.get_mut().unwrap();
+        mut_ref[3] = 42;
+    }
+    assert!(obj.is_data_dirty());
+    assert!(remaining_dirty_size(&heap) < clean_dirty_size);
+
+    {
+        // not allowed while the object is in use
+        let alloc_id = obj.get_alloc_id().clone();
+        let _obj_ref = obj.get().unwrap();
+        assert_eq!(
+            heap.get_inner().borrow_mut().discard_changes(&alloc_id),
+            Err(VNVError::ObjectInUse)
+        );
+    }
+
+    obj.discard_changes().unwrap();
+    assert!(obj.is_resident());
+    assert!(!obj.is_data_dirty());
+    assert_eq!(*obj.get().unwrap(), [1u32; 20]);
+
+    obj.unload().unwrap();
+    assert_eq!(remaining_dirty_size(&heap), clean_dirty_size);
+    assert_eq!(*obj.get().unwrap(), [1u32; 20]);
+
+    // flushed changes are kept
+    {
+        let mut mut_ref = obj.get_mut().unwrap();
+        mut_ref[0] = 7;
+    }
+    obj.flush().unwrap();
+    {
+        let mut mut_ref = obj.get_mut().unwrap();
+        mut_ref[0] = 8;
+    }
+    obj.discard_changes().unwrap();
+    assert_eq!(obj.get().unwrap()[0], 7);
+
+    // discarding clean or non resident objects does nothing
+    obj.discard_changes().unwrap();
+    obj.unload().unwrap();
+    obj.discard_changes().unwrap();
+    assert!(!obj.is_resident());
+}
+
+#[test]
+fn test_discard_changes_needs_drop() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_discard_changes_needs_drop", 4 * 4096, &mut buffer, 2000, |_, _| {});
+
+    let mut obj = heap.allocate(vec![1u8, 2, 3]).unwrap();
+    obj.unload().unwrap();
+    assert_eq!(obj.discard_changes(), Err(VNVError::Unsupported));
+}
@@ -0,0 +1,168 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::alloc::Layout;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{
+    modules::{
+        allocator::{AllocatorModule, LinkedListAllocatorModule},
+        nonresident_allocator::NonResidentBuddyAllocatorModule,
+        object_management::{ObjectManagementList, ObjectManagementModule},
+        persistent_storage::{test::get_test_storage, FilePersistentStorageModule, PersistentStorageModule},
+    },
+    DropOutcome, DropPolicy, VNVConfig, VNVHeap, VNVObject, WriteBack,
+};
+
+/// Never unloads or syncs any object, so the resident buffer stays full
+struct NeverUnloadObjectManagementModule;
+
+impl ObjectManagementModule for NeverUnloadObjectManagementModule {
+    fn new() -> Self {
+        Self
+    }
+
+    fn sync_dirty_data<A: AllocatorModule, S: PersistentStorageModule>(
+        &mut self,
+        _required_bytes: usize,
+        _dirty_item_list: ObjectManagementList<'_, '_, '_, '_, A, S>,
+    ) -> Result<(), ()> {
+        Err(())
+    }
+
+    fn unload_objects<A: AllocatorModule, S: PersistentStorageModule>(
+        &mut self,
+        _layout: &Layout,
+        _resident_item_list: ObjectManagementList<'_, '_, '_, '_, A, S>,
+    ) -> Result<(), ()> {
+        Err(())
+    }
+}
+
+const RESIDENT_BUFFER_SIZE: usize = 600;
+
+type DropTestHeap<'a> = VNVHeap<
+    'a,
+    LinkedListAllocatorModule,
+    NonResidentBuddyAllocatorModule<16>,
+    NeverUnloadObjectManagementModule,
+    FilePersistentStorageModule,
+>;
+
+type DropTestObject<'a, 'b, T> = VNVObject<
+    'a,
+    'b,
+    T,
+    LinkedListAllocatorModule,
+    NonResidentBuddyAllocatorModule<16>,
+    NeverUnloadObjectManagementModule,
+>;
+
+fn get_drop_test_heap<'a>(test_name: &str, resident_buffer: &'a mut [u8]) -> DropTestHeap<'a> {
+    VNVHeap::new(
+        resident_buffer,
+        get_test_storage(test_name, 4 * 4096),
+        LinkedListAllocatorModule::new(),
+        VNVConfig {
+            max_dirty_bytes: RESIDENT_BUFFER_SIZE,
+            write_back: WriteBack::Lazy,
+            persist_latency_budget: None,
+        },
+        |_, _| {},
+    )
+    .unwrap()
+}
+
+struct DropCounter<const ID: usize>([u8; 64]);
+
+static DROP_COUNTERS: [AtomicUsize; 3] = [AtomicUsize::new(0), AtomicUsize::new(0), AtomicUsize::new(0)];
+
+impl<const ID: usize> Drop for DropCounter<ID> {
+    fn drop(&mut self) {
+        DROP_COUNTERS[ID].fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Fills the resident buffer with objects, so that `obj` cannot be made resident anymore
+fn fill_resident_buffer<'a, 'b, T>(
+    heap: &'a DropTestHeap<'b>,
+    obj: &mut DropTestObject<'a, 'b, T>,
+) -> Vec<DropTestObject<'a, 'b, [u8; 64]>> {
+    obj.unload().unwrap();
+
+    let mut fillers = vec![];
+    loop {
+        let mut filler = heap.allocate([0u8; 64]).unwrap();
+        let is_resident = filler.get().is_ok();
+        fillers.push(filler);
+        if !is_resident {
+            break;
+        }
+    }
+
+    assert!(obj.get().is_err());
+    fillers
+}
+
+#[test]
+fn test_drop_policy_keep_alive() {
+    let mut buffer = [0u8; RESIDENT_BUFFER_SIZE];
+    let heap = get_drop_test_heap("test_drop_policy_keep_alive", &mut buffer);
+    assert_eq!(heap.get_drop_policy(), DropPolicy::KeepAlive);
+
+    let mut obj = heap.allocate(DropCounter::<0>([1; 64])).unwrap();
+    let mut fillers = fill_resident_buffer(&heap, &mut obj);
+
+    // object is returned and not dropped
+    let mut obj = obj.try_drop().unwrap_err();
+    assert_eq!(DROP_COUNTERS[0].load(Ordering::SeqCst), 0);
+
+    // after making space, the object can be dropped
+    for filler in fillers.iter_mut() {
+        filler.unload().unwrap();
+    }
+    assert_eq!(obj.get().unwrap().0, [1; 64]);
+    assert_eq!(obj.try_drop().ok(), Some(DropOutcome::Dropped));
+    assert_eq!(DROP_COUNTERS[0].load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_drop_policy_force_evict_others() {
+    let mut buffer = [0u8; RESIDENT_BUFFER_SIZE];
+    let heap = get_drop_test_heap("test_drop_policy_force_evict_others", &mut buffer);
+    heap.set_drop_policy(DropPolicy::ForceEvictOthers);
+
+    let mut obj = heap.allocate(DropCounter::<1>([1; 64])).unwrap();
+    let fillers = fill_resident_buffer(&heap, &mut obj);
+
+    assert_eq!(obj.try_drop().ok(), Some(DropOutcome::Dropped));
+    assert_eq!(DROP_COUNTERS[1].load(Ordering::SeqCst), 1);
+    assert!(fillers.iter().all(|filler| !filler.is_resident()));
+}
+
+#[test]
+fn test_drop_policy_leak_without_drop() {
+    let mut buffer = [0u8; RESIDENT_BUFFER_SIZE];
+    let heap = get_drop_test_heap("test_drop_policy_leak_without_drop", &mut buffer);
+    heap.set_drop_policy(DropPolicy::LeakWithoutDrop);
+
+    let mut obj = heap.allocate(DropCounter::<2>([1; 64])).unwrap();
+    let _fillers = fill_resident_buffer(&heap, &mut obj);
+
+    assert_eq!(obj.try_drop().ok(), Some(DropOutcome::LeakedWithoutDrop));
+    assert_eq!(DROP_COUNTERS[2].load(Ordering::SeqCst), 0);
+}
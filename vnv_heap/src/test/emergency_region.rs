@@ -0,0 +1,78 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::ptr::slice_from_raw_parts_mut;
+
+use crate::{vnv_persist_all, VNVError};
+
+use super::get_test_heap;
+
+#[test]
+fn test_emergency_region() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_emergency_region", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    assert!(!heap.is_emergency_region_available());
+    heap.reserve_emergency_region::<[u8; 200]>().unwrap();
+    assert!(heap.is_emergency_region_available());
+
+    let mut obj1 = heap.allocate([1u8; 200]).unwrap();
+    let mut obj2 = heap.allocate([2u8; 200]).unwrap();
+    let mut obj3 = heap.allocate([3u8; 200]).unwrap();
+    let mut obj4 = heap.allocate([4u8; 200]).unwrap();
+
+    {
+        let _ref1 = obj1.get().unwrap();
+        let _ref2 = obj2.get().unwrap();
+
+        // all other objects are in use, so the emergency region is used
+        let ref3 = obj3.get().unwrap();
+        assert_eq!(*ref3, [3u8; 200]);
+        assert!(!heap.is_emergency_region_available());
+
+        // the emergency region is only sized for one object
+        assert_eq!(obj4.get().err(), Some(VNVError::ResidentObjectsInUse));
+    }
+
+    // the region is reserved again once it is free
+    obj3.unload().unwrap();
+    assert_eq!(*obj4.get().unwrap(), [4u8; 200]);
+    assert!(heap.is_emergency_region_available());
+
+    heap.release_emergency_region();
+    assert!(!heap.is_emergency_region_available());
+}
+
+#[test]
+fn test_emergency_region_persist() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_emergency_region_persist", 4 * 4096, &mut buffer, 1000, |base_ptr, size| {
+        let buffer = unsafe { slice_from_raw_parts_mut(base_ptr, size).as_mut() }.unwrap();
+        buffer.fill(0);
+    });
+
+    let mut obj1 = heap.allocate([1u8; 200]).unwrap();
+    heap.reserve_emergency_region::<[u8; 200]>().unwrap();
+    let mut obj2 = heap.allocate([2u8; 200]).unwrap();
+
+    // restoring checks that the resident heap is in the same state as before (including the emergency region)
+    unsafe { vnv_persist_all() };
+
+    assert!(heap.is_emergency_region_available());
+    assert_eq!(*obj1.get().unwrap(), [1u8; 200]);
+    assert_eq!(*obj2.get().unwrap(), [2u8; 200]);
+}
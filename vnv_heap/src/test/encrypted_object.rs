@@ -0,0 +1,115 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::{
+    modules::{object_encryption::Ascon128EncryptionModule, persistent_storage::PersistentStorageModule},
+    resident_object_manager::resident_object_backup::calc_backup_obj_user_data_offset,
+};
+
+use super::get_test_heap;
+
+const SECRET: [u8; 24] = *b"very secret credentials!";
+const KEY: [u8; 16] = [0x42; 16];
+
+#[test]
+fn test_encrypted_object() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_encrypted_object", 8192, &mut buffer, 1000, |_, _| {});
+
+    let mut obj = heap.allocate_encrypted(SECRET, KEY, Ascon128EncryptionModule::new()).unwrap();
+    let mut other = heap.allocate_encrypted(5u32, [0x13; 16], Ascon128EncryptionModule::new()).unwrap();
+
+    assert_eq!(obj.get().unwrap(), SECRET);
+    assert_eq!(other.get().unwrap(), 5);
+
+    obj.unload().unwrap();
+    other.unload().unwrap();
+    assert_eq!(obj.get().unwrap(), SECRET);
+    assert_eq!(other.get().unwrap(), 5);
+
+    obj.update(|data| data[0] = b'V').unwrap();
+    other.set(6).unwrap();
+    obj.flush().unwrap();
+    other.unload().unwrap();
+
+    let mut expected = SECRET;
+    expected[0] = b'V';
+    assert_eq!(obj.get().unwrap(), expected);
+    assert_eq!(other.get().unwrap(), 6);
+
+    // the plaintext is not stored anywhere
+    obj.unload().unwrap();
+    let mut storage_data = [0u8; 4096];
+    heap.get_inner().borrow_mut().get_storage_module().read(0, &mut storage_data).unwrap();
+
+    assert!(!storage_data.windows(SECRET.len() - 1).any(|window| window == &SECRET[1..]));
+}
+
+#[test]
+fn test_encrypted_object_tampered() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_encrypted_object_tampered", 8192, &mut buffer, 1000, |_, _| {});
+
+    let mut obj = heap.allocate_encrypted(SECRET, KEY, Ascon128EncryptionModule::new()).unwrap();
+    obj.unload().unwrap();
+
+    let offset = obj.get_alloc_id().offset + calc_backup_obj_user_data_offset();
+    {
+        let mut inner = heap.get_inner().borrow_mut();
+        let storage = inner.get_storage_module();
+
+        // flip one bit of the ciphertext (which is stored after the nonce counter and the tag)
+        let mut byte = [0u8];
+        let byte_offset = offset + 8 + 16 + 3;
+        storage.read(byte_offset, &mut byte).unwrap();
+        byte[0] ^= 1;
+        storage.write(byte_offset, &byte).unwrap();
+    }
+
+    assert!(obj.get().is_err());
+
+    // a new value can still be written
+    // (with object checksums, the corrupted object cannot be loaded anymore)
+    #[cfg(not(feature = "object_checksums"))]
+    {
+        obj.set(SECRET).unwrap();
+        assert_eq!(obj.get().unwrap(), SECRET);
+    }
+}
+
+// with object checksums, the modified object cannot be loaded anymore
+#[cfg(not(feature = "object_checksums"))]
+#[test]
+fn test_encrypted_object_nonce_after_reboot() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_encrypted_object_nonce_after_reboot", 8192, &mut buffer, 1000, |_, _| {});
+
+    let mut obj = heap.allocate_encrypted(SECRET, KEY, Ascon128EncryptionModule::new()).unwrap();
+    obj.unload().unwrap();
+
+    // the object was encrypted with a high counter before the last reboot (which reset the counter in RAM)
+    let offset = obj.get_alloc_id().offset + calc_backup_obj_user_data_offset();
+    let stored_counter = u64::MAX / 2;
+    heap.get_inner().borrow_mut().get_storage_module().write(offset, &stored_counter.to_le_bytes()).unwrap();
+
+    obj.set(SECRET).unwrap();
+    obj.unload().unwrap();
+
+    let mut counter = [0u8; 8];
+    heap.get_inner().borrow_mut().get_storage_module().read(offset, &mut counter).unwrap();
+    assert_eq!(u64::from_le_bytes(counter), stored_counter + 1);
+    assert_eq!(obj.get().unwrap(), SECRET);
+}
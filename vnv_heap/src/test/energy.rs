@@ -0,0 +1,84 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::{EnergyConfig, EnergyMonitor, StorageThroughput};
+
+use super::get_test_heap;
+
+const CONFIG: EnergyConfig = EnergyConfig {
+    capacitance_uf: 1,
+    min_voltage_mv: 1800,
+    persist_threshold_mv: 3000,
+    persist_power_mw: 10,
+    write_throughput: StorageThroughput {
+        fixed_latency_us: 100,
+        bytes_per_ms: 1000,
+    },
+    safety_margin_percent: 0,
+};
+
+#[test]
+fn test_energy_config() {
+    // 1µF * (3V² - 1.8V²) / 2 = 2.88µJ, which lasts 288µs at 10mW
+    assert_eq!(CONFIG.available_energy_nj(3000), 2880);
+    assert_eq!(CONFIG.persist_time_us(3000), 288);
+    assert_eq!(CONFIG.safe_dirty_bytes(3000), 188);
+
+    // persisting starts at the threshold at the latest
+    assert_eq!(CONFIG.safe_dirty_bytes(3300), 188);
+    assert_eq!(CONFIG.safe_dirty_bytes(2500), 50);
+    assert_eq!(CONFIG.safe_dirty_bytes(1700), 0);
+
+    let config = EnergyConfig {
+        safety_margin_percent: 50,
+        ..CONFIG
+    };
+    assert_eq!(config.persist_time_us(3000), 144);
+    assert_eq!(config.safe_dirty_bytes(3000), 44);
+}
+
+static VOLTAGE_MV: AtomicU32 = AtomicU32::new(3300);
+
+#[test]
+fn test_energy_monitor() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_energy_monitor", 4 * 4096, &mut buffer, 1500, |_, _| {});
+    let monitor = EnergyMonitor::new(
+        || VOLTAGE_MV.load(Ordering::SeqCst),
+        EnergyConfig {
+            capacitance_uf: 5,
+            ..CONFIG
+        },
+    );
+
+    let objects: Vec<_> = (0..10).map(|i| heap.allocate([i as u8; 64]).unwrap()).collect();
+    let count_dirty = || objects.iter().filter(|obj| obj.is_data_dirty()).count();
+
+    VOLTAGE_MV.store(3300, Ordering::SeqCst);
+    assert!(!monitor.should_persist());
+    assert_eq!(monitor.update_dirty_limit(&heap), Ok(1340));
+    assert_eq!(heap.get_max_dirty_bytes(), 1340);
+    let dirty = count_dirty();
+
+    // less energy is left, so less bytes can be dirty
+    VOLTAGE_MV.store(2500, Ordering::SeqCst);
+    assert!(monitor.should_persist());
+    assert_eq!(monitor.update_dirty_limit(&heap), Ok(652));
+    assert_eq!(heap.get_max_dirty_bytes(), 652);
+    assert!(count_dirty() < dirty);
+}
@@ -0,0 +1,155 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{fs, mem::forget};
+
+use crate::{
+    modules::{
+        allocator::LinkedListAllocatorModule,
+        persistent_storage::{test::get_test_storage, PersistentStorageModule},
+    },
+    vnv_persist_all, GcMarker, VNVConfig, VNVHeap, VNVLink, VNVTrace, WriteBack,
+};
+
+use super::{get_test_heap, TestHeap};
+
+const STORAGE_SIZE: usize = 4 * 4096;
+
+/// Copy of the storage at the time of the simulated power failure
+const POWER_FAILURE_IMAGE: &str = "/tmp/test_gc_power_failure.image";
+
+struct Node {
+    value: u32,
+    next: Option<VNVLink<Node>>,
+}
+
+impl VNVTrace for Node {
+    fn trace(&self, marker: &mut GcMarker) {
+        if let Some(next) = &self.next {
+            marker.mark(next);
+        }
+    }
+}
+
+#[test]
+fn test_gc() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_gc", STORAGE_SIZE, &mut buffer, 1000, |_, _| {});
+
+    let mut tail = heap.allocate(Node { value: 3, next: None }).unwrap();
+    tail.unload().unwrap();
+    let middle = heap.allocate(Node { value: 2, next: Some(tail.link()) }).unwrap();
+    let head = heap.allocate(Node { value: 1, next: Some(middle.link()) }).unwrap();
+
+    // the handles of these objects are lost, one of them links to the list
+    let orphan = heap.allocate(Node { value: 4, next: Some(tail.link()) }).unwrap();
+    let leaf_orphan = heap.allocate([5u8; 64]).unwrap();
+    let kept = heap.allocate([6u8; 64]).unwrap();
+    let kept_link = kept.link();
+    forget(orphan);
+    forget(leaf_orphan);
+    forget(kept);
+
+    let reclaimed = unsafe {
+        heap.gc(|marker| {
+            marker.mark(&head.link());
+            marker.mark_leaf(&kept_link);
+        })
+    }
+    .unwrap();
+    assert_eq!(reclaimed, 2);
+
+    // the list is still intact
+    let mut values = vec![];
+    let mut next = Some(head.link());
+    while let Some(link) = next {
+        let (value, link) = unsafe {
+            heap.with_link(&link, |obj| {
+                let node = obj.get().unwrap();
+                (node.value, node.next)
+            })
+        };
+        values.push(value);
+        next = link;
+    }
+    assert_eq!(values, [1, 2, 3]);
+    assert_eq!(unsafe { heap.with_link(&kept_link, |obj| *obj.get().unwrap()) }, [6u8; 64]);
+
+    // nothing is left to reclaim
+    let reclaimed = unsafe {
+        heap.gc(|marker| {
+            marker.mark(&head.link());
+            marker.mark_leaf(&kept_link);
+        })
+    }
+    .unwrap();
+    assert_eq!(reclaimed, 0);
+}
+
+#[test]
+fn test_gc_after_recover() {
+    {
+        let mut buffer = [0u8; 1000];
+        let heap = get_test_heap("test_gc_after_recover_src", STORAGE_SIZE, &mut buffer, 1000, |_, _| {
+            // the power is cut right after persisting
+            fs::copy("/tmp/test_gc_after_recover_src.tmp", POWER_FAILURE_IMAGE).unwrap();
+        });
+
+        let mut tail = heap.allocate(Node { value: 2, next: None }).unwrap();
+        let mut head = heap.allocate(Node { value: 1, next: Some(tail.link()) }).unwrap();
+        heap.set_root("list", &head.link()).unwrap();
+
+        // the object was not linked yet when the power failed
+        let mut pending = heap.allocate(Node { value: 3, next: None }).unwrap();
+
+        for obj in [&mut tail, &mut head, &mut pending] {
+            obj.flush().unwrap();
+        }
+
+        unsafe { vnv_persist_all() };
+
+        // the objects have to outlive the heap they were allocated in
+        forget(tail);
+        forget(head);
+        forget(pending);
+    }
+
+    let image = fs::read(POWER_FAILURE_IMAGE).unwrap();
+    let mut storage = get_test_storage("test_gc_after_recover_dest", STORAGE_SIZE);
+    storage.write(0, &image).unwrap();
+
+    let mut buffer = [0u8; 1000];
+    let heap: TestHeap = VNVHeap::recover(
+        &mut buffer,
+        storage,
+        LinkedListAllocatorModule::new(),
+        VNVConfig {
+            max_dirty_bytes: 1000,
+            write_back: WriteBack::Lazy,
+            persist_latency_budget: None,
+        },
+        |_, _| {},
+    )
+    .unwrap();
+
+    let head: VNVLink<Node> = heap.get_root("list").unwrap().unwrap();
+    let reclaimed = unsafe { heap.gc(|marker| marker.mark(&head)) }.unwrap();
+    assert_eq!(reclaimed, 1);
+
+    let next = unsafe { heap.with_link(&head, |obj| obj.get().unwrap().next) }.unwrap();
+    assert_eq!(unsafe { heap.with_link(&next, |obj| obj.get().unwrap().value) }, 2);
+}
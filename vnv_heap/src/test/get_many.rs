@@ -0,0 +1,93 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::test::{get_instrumented_test_heap, StorageOperation};
+
+#[test]
+fn test_get_many() {
+    static READS: AtomicUsize = AtomicUsize::new(0);
+    fn count_reads(op: StorageOperation) {
+        if matches!(op, StorageOperation::Read { .. }) {
+            READS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let mut buffer = [0u8; 2000];
+    let heap = get_instrumented_test_heap("test_get_many", &mut buffer, count_reads);
+
+    let mut objs: [_; 6] = core::array::from_fn(|i| heap.allocate([i as u32; 4]).unwrap());
+    for obj in objs.iter_mut() {
+        obj.unload().unwrap();
+    }
+    objs.sort_by_key(|obj| obj.get_alloc_id().offset);
+
+    // one object is already resident and must not be overwritten
+    objs[2].get_mut().unwrap()[0] = 100;
+
+    READS.store(0, Ordering::SeqCst);
+    {
+        let [a, b, c, d, e, f] = &mut objs;
+        heap.get_many(&mut [a, b, c, d, e, f]).unwrap();
+    }
+    // the recovery area changes the buddy blocks at the end of the storage, so the objects are not adjacent
+    #[cfg(not(any(feature = "access_counters", feature = "object_checksums", feature = "recovery")))]
+    assert_eq!(READS.load(Ordering::SeqCst), 1);
+
+    for obj in objs.iter() {
+        assert!(obj.is_resident());
+    }
+
+    READS.store(0, Ordering::SeqCst);
+    for (i, obj) in objs.iter_mut().enumerate() {
+        let data = *obj.get().unwrap();
+        if i == 2 {
+            assert_eq!(data[0], 100);
+        } else {
+            assert_eq!(data[1], data[0]);
+        }
+    }
+    assert_eq!(READS.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_get_many_big_objects() {
+    static READS: AtomicUsize = AtomicUsize::new(0);
+    fn count_reads(op: StorageOperation) {
+        if matches!(op, StorageOperation::Read { .. }) {
+            READS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let mut buffer = [0u8; 2000];
+    let heap = get_instrumented_test_heap("test_get_many_big_objects", &mut buffer, count_reads);
+
+    // too big to be read together
+    let mut a = heap.allocate([1u8; 200]).unwrap();
+    let mut b = heap.allocate([2u8; 200]).unwrap();
+    a.unload().unwrap();
+    b.unload().unwrap();
+
+    READS.store(0, Ordering::SeqCst);
+    heap.get_many(&mut [&mut a, &mut b]).unwrap();
+    #[cfg(not(any(feature = "access_counters", feature = "object_checksums")))]
+    assert_eq!(READS.load(Ordering::SeqCst), 2);
+
+    assert_eq!(*a.get().unwrap(), [1u8; 200]);
+    assert_eq!(*b.get().unwrap(), [2u8; 200]);
+}
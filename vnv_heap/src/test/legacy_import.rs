@@ -0,0 +1,95 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::{
+    modules::{
+        allocator::LinkedListAllocatorModule,
+        nonresident_allocator::NonResidentBuddyAllocatorModule,
+        object_management::DefaultObjectManagementModule,
+        persistent_storage::{test::get_test_storage, FilePersistentStorageModule, LegacyLayoutStorageModule, LegacyRegion, PersistentStorageModule},
+    },
+    VNVConfig, VNVHeap, WriteBack,
+};
+
+/// Struct of the old firmware, that was stored at a fixed offset
+#[derive(Debug, PartialEq, Eq)]
+struct LegacyCounter {
+    value: u32,
+    limit: u32,
+}
+
+impl LegacyCounter {
+    fn from_bytes(data: &[u8]) -> Self {
+        Self {
+            value: u32::from_le_bytes(data[0..4].try_into().unwrap()),
+            limit: u32::from_le_bytes(data[4..8].try_into().unwrap()),
+        }
+    }
+}
+
+const COUNTER_REGION: LegacyRegion = LegacyRegion::new(0, 8);
+const NAME_REGION: LegacyRegion = LegacyRegion::new(64, 16);
+
+#[test]
+fn test_import_legacy() {
+    let mut storage = get_test_storage("test_import_legacy", 4 * 4096);
+    storage.write(COUNTER_REGION.offset, &[7, 0, 0, 0, 100, 0, 0, 0]).unwrap();
+    storage.write(NAME_REGION.offset, b"legacy device 01").unwrap();
+
+    let storage = LegacyLayoutStorageModule::new(storage, &[COUNTER_REGION, NAME_REGION]);
+    assert_eq!(storage.get_heap_offset(), NAME_REGION.offset + NAME_REGION.size);
+
+    let mut buffer = [0u8; 1000];
+    let heap: VNVHeap<
+        LinkedListAllocatorModule,
+        NonResidentBuddyAllocatorModule<16>,
+        DefaultObjectManagementModule,
+        LegacyLayoutStorageModule<FilePersistentStorageModule>,
+    > = VNVHeap::new(
+        &mut buffer,
+        storage,
+        LinkedListAllocatorModule::new(),
+        VNVConfig {
+            max_dirty_bytes: 500,
+            write_back: WriteBack::Lazy,
+            persist_latency_budget: None,
+        },
+        |_, _| {},
+    )
+    .unwrap();
+
+    let mut counter = heap.import_legacy(&COUNTER_REGION, LegacyCounter::from_bytes).unwrap();
+    let mut name = heap
+        .import_legacy(&NAME_REGION, |data| <[u8; 16]>::try_from(data).unwrap())
+        .unwrap();
+
+    // use the heap, so that the whole heap area is written to
+    let mut others: Vec<_> = (0..20).map(|i| heap.allocate([i as u8; 128]).unwrap()).collect();
+    for other in others.iter_mut() {
+        other.unload().unwrap();
+    }
+    counter.get_mut().unwrap().value += 1;
+    counter.unload().unwrap();
+    name.unload().unwrap();
+
+    assert_eq!(*counter.get().unwrap(), LegacyCounter { value: 8, limit: 100 });
+    assert_eq!(*name.get().unwrap(), *b"legacy device 01");
+
+    // legacy data is still available until the transition is finished
+    let mut counter_copy = heap.import_legacy(&COUNTER_REGION, LegacyCounter::from_bytes).unwrap();
+    assert_eq!(*counter_copy.get().unwrap(), LegacyCounter { value: 7, limit: 100 });
+}
@@ -0,0 +1,75 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::{
+    modules::{
+        allocator::LinkedListAllocatorModule,
+        nonresident_allocator::NonResidentBuddyAllocatorModule,
+        object_management::DefaultObjectManagementModule,
+        persistent_storage::RamStorageModule,
+    },
+    VNVConfig, VNVHeap, WriteBack,
+};
+
+/// Objects are only read from storage directly if the access does not have to be tracked
+const MAPPED: bool = cfg!(not(any(feature = "access_counters", feature = "object_checksums")));
+
+#[test]
+fn test_get_mapped() {
+    let mut buffer = [0u8; 1000];
+    let heap: VNVHeap<
+        LinkedListAllocatorModule,
+        NonResidentBuddyAllocatorModule<16>,
+        DefaultObjectManagementModule,
+        RamStorageModule,
+    > = VNVHeap::new(
+        &mut buffer,
+        RamStorageModule::new(4 * 4096),
+        LinkedListAllocatorModule::new(),
+        VNVConfig {
+            max_dirty_bytes: 500,
+            write_back: WriteBack::Lazy,
+            persist_latency_budget: None,
+        },
+        |_, _| {},
+    )
+    .unwrap();
+
+    let mut obj = heap.allocate([3u32; 16]).unwrap();
+    obj.unload().unwrap();
+
+    {
+        let data = obj.get_mapped(&heap).unwrap();
+        assert_eq!(data.is_mapped(), MAPPED);
+        assert_eq!(*data, [3u32; 16]);
+    }
+    assert_eq!(obj.is_resident(), !MAPPED);
+
+    // resident objects are not read from storage, as their data could be dirty
+    obj.get_mut().unwrap()[5] = 10;
+    {
+        let data = obj.get_mapped(&heap).unwrap();
+        assert!(!data.is_mapped());
+        assert_eq!(data[5], 10);
+    }
+
+    obj.unload().unwrap();
+    let data = obj.get_mapped(&heap).unwrap();
+    assert_eq!(data.is_mapped(), MAPPED);
+    assert_eq!(data[4], 3);
+    assert_eq!(data[5], 10);
+}
@@ -0,0 +1,58 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::VNVError;
+
+use super::get_test_heap;
+
+#[test]
+fn test_metrics() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_metrics", 4096, &mut buffer, 200, |_, _| {});
+
+    // new objects are dirty until they are written back
+    let mut obj = heap.allocate([1u32; 10]).unwrap();
+    let allocated = heap.metrics();
+    assert!(allocated.dirty_bytes >= 40);
+    assert_eq!(allocated.peak_dirty_bytes, allocated.dirty_bytes);
+    assert!(allocated.resident_bytes >= 40);
+    assert_eq!(allocated.evictions, 0);
+    assert_eq!(allocated.allocation_failures, 0);
+
+    // unloading writes the object back
+    obj.unload().unwrap();
+    let unloaded = heap.metrics();
+    assert_eq!(unloaded.dirty_bytes, 0);
+    assert_eq!(unloaded.peak_dirty_bytes, allocated.peak_dirty_bytes);
+    assert_eq!(unloaded.evictions, 1);
+    assert_eq!(unloaded.resident_bytes, 0);
+    assert!(unloaded.storage_writes > allocated.storage_writes);
+
+    // loading reads the object from storage
+    assert_eq!(obj.get().unwrap()[0], 1);
+    let loaded = heap.metrics();
+    assert!(loaded.storage_reads > unloaded.storage_reads);
+    assert!(loaded.resident_bytes >= 40);
+
+    // modifying it makes its data dirty again
+    obj.get_mut().unwrap()[0] = 2;
+    assert!(heap.metrics().dirty_bytes >= loaded.dirty_bytes + 40);
+
+    let res = heap.allocate([0u8; 8192]);
+    assert!(matches!(res, Err(VNVError::NonResidentSpaceExhausted)));
+    assert_eq!(heap.metrics().allocation_failures, 1);
+}
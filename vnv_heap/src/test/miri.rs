@@ -0,0 +1,139 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Tests of the core heap with a RAM storage backend that can also be run under Miri.
+//!
+//! Only `LinkedListAllocatorModule` is part of this subset, the buddy allocators store their free lists as
+//! plain addresses. As metadata references are used to access the object data right behind them,
+//! Tree Borrows is required (Stacked Borrows restricts a reference to the size of its pointee).
+//!
+//! Run with: `MIRIFLAGS="-Zmiri-tree-borrows -Zmiri-strict-provenance" cargo +nightly miri test -p vnv_heap miri`
+
+use std::ptr::slice_from_raw_parts_mut;
+
+use crate::{
+    modules::{
+        allocator::LinkedListAllocatorModule,
+        nonresident_allocator::NonResidentBuddyAllocatorModule,
+        object_management::DefaultObjectManagementModule,
+        persistent_storage::RamStorageModule,
+    },
+    vnv_persist_all, VNVConfig, VNVHeap, WriteBack,
+};
+
+type MiriTestHeap<'a> = VNVHeap<
+    'a,
+    LinkedListAllocatorModule,
+    NonResidentBuddyAllocatorModule<16>,
+    DefaultObjectManagementModule,
+    RamStorageModule,
+>;
+
+fn get_miri_test_heap(resident_buffer: &mut [u8], dirty_size: usize) -> MiriTestHeap<'_> {
+    VNVHeap::new(
+        resident_buffer,
+        RamStorageModule::new(4096),
+        LinkedListAllocatorModule::new(),
+        VNVConfig {
+            max_dirty_bytes: dirty_size,
+            write_back: WriteBack::Lazy,
+            persist_latency_budget: None,
+        },
+        |base_ptr, size| {
+            // everything was persisted, so simulate losing the contents of the resident buffer
+            let buffer = unsafe { slice_from_raw_parts_mut(base_ptr, size).as_mut() }.unwrap();
+            buffer.fill(0);
+        },
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_miri_allocate_get() {
+    let mut buffer = [0u8; 600];
+    let heap = get_miri_test_heap(&mut buffer, 300);
+
+    let mut objects: Vec<_> = (0..8u32).map(|i| heap.allocate([i; 8]).unwrap()).collect();
+
+    for round in 0..3u32 {
+        for (i, obj) in objects.iter_mut().enumerate() {
+            let mut mut_ref = obj.get_mut().unwrap();
+            assert_eq!(mut_ref[0], i as u32 + round);
+            mut_ref.iter_mut().for_each(|x| *x += 1);
+        }
+    }
+
+    for (i, obj) in objects.iter_mut().enumerate() {
+        obj.unload().unwrap();
+        assert_eq!(*obj.get().unwrap(), [i as u32 + 3; 8]);
+    }
+}
+
+#[test]
+fn test_miri_persist_restore() {
+    let mut buffer = [0u8; 600];
+    let heap = get_miri_test_heap(&mut buffer, 300);
+
+    let mut objects: Vec<_> = (0..6u16).map(|i| heap.allocate([i; 10]).unwrap()).collect();
+
+    for round in 1..4u16 {
+        for (i, obj) in objects.iter_mut().enumerate() {
+            if (i as u16 + round) % 2 == 0 {
+                // keep some objects dirty and resident while persisting
+                obj.get_mut().unwrap()[0] = round;
+            } else {
+                obj.unload().unwrap();
+            }
+        }
+
+        unsafe { vnv_persist_all() };
+
+        for (i, obj) in objects.iter_mut().enumerate() {
+            let obj_ref = obj.get().unwrap();
+            assert_eq!(obj_ref[1..], [i as u16; 9]);
+            if (i as u16 + round) % 2 == 0 {
+                assert_eq!(obj_ref[0], round);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_miri_borrowed_storage() {
+    // storage without an allocator, e.g. a static buffer on an embedded device
+    let storage_buffer: &'static mut [u8] = Box::leak(vec![0u8; 4096].into_boxed_slice());
+
+    let mut buffer = [0u8; 600];
+    let heap: VNVHeap<_, NonResidentBuddyAllocatorModule<16>, DefaultObjectManagementModule, _> = VNVHeap::new(
+        &mut buffer,
+        RamStorageModule::from_buffer(storage_buffer),
+        LinkedListAllocatorModule::new(),
+        VNVConfig {
+            max_dirty_bytes: 300,
+            write_back: WriteBack::Lazy,
+            persist_latency_budget: None,
+        },
+        |_, _| {},
+    )
+    .unwrap();
+
+    let mut objects: Vec<_> = (0..4u32).map(|i| heap.allocate([i; 16]).unwrap()).collect();
+    for (i, obj) in objects.iter_mut().enumerate() {
+        obj.unload().unwrap();
+        assert_eq!(*obj.get().unwrap(), [i as u32; 16]);
+    }
+}
@@ -21,15 +21,99 @@ use crate::{
         nonresident_allocator::NonResidentBuddyAllocatorModule,
         object_management::DefaultObjectManagementModule,
         persistent_storage::test::get_test_storage,
-        persistent_storage::FilePersistentStorageModule
+        persistent_storage::{FilePersistentStorageModule, PersistentStorageModule}
     },
     VNVHeap,
 };
 
+#[cfg(feature = "access_counters")]
+mod access_counters;
+mod allocate_aligned;
+mod allocate_many;
+#[cfg(not(no_std))]
+mod async_get;
+#[cfg(feature = "capi")]
+mod capi;
+#[cfg(not(loom))]
 mod benchmarks;
+mod closure_access;
+mod compressed_object;
+mod defragment;
+#[cfg(feature = "deterministic_layout")]
+mod deterministic_layout;
+#[cfg(feature = "global_dirty_budget")]
+mod dirty_budget_pool;
+#[cfg(feature = "dirty_pools")]
+mod dirty_pools;
+mod discard_changes;
+mod drop_policy;
+#[cfg(feature = "emergency_region")]
+mod emergency_region;
+#[cfg(feature = "energy")]
+mod energy;
+mod encrypted_object;
+#[cfg(feature = "gc")]
+mod gc;
+mod get_many;
+mod legacy_import;
+mod memory_mapped;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(not(no_std))]
+mod miri;
+mod object_management;
+#[cfg(feature = "object_stats")]
+mod object_stats;
+#[cfg(feature = "object_checksums")]
+mod object_checksums;
+mod panic_safety;
 mod persist_all;
+mod persist_budget;
+mod persist_latency_budget;
+#[cfg(feature = "persist_priority")]
+mod persist_priority;
+mod persist_status;
+mod post_persist_hook;
+#[cfg(loom)]
+mod persist_lock_loom;
 mod persistency;
+mod pin;
+mod prefetch;
+mod raw_object;
+#[cfg(feature = "recovery")]
+mod recovery;
+mod reserved_storage;
+mod residency_token;
+mod resident_access;
+mod resident_usage;
+#[cfg(feature = "serde")]
+mod serde;
+mod snapshot;
+#[cfg(not(any(feature = "access_counters", feature = "object_checksums")))]
+mod split_object;
+mod static_heap;
+mod storage_calibration;
+mod storage_slice;
+mod sync_heap;
 mod unload;
+mod vnv_array;
+mod vnv_box;
+mod vnv_bytes;
+mod vnv_channel;
+mod vnv_error;
+mod vnv_link;
+mod vnv_queue;
+#[cfg(feature = "watermarks")]
+mod watermarks;
+mod write_back;
+
+pub(crate) type TestHeap<'a> = VNVHeap<
+    'a,
+    LinkedListAllocatorModule,
+    NonResidentBuddyAllocatorModule<16>,
+    DefaultObjectManagementModule,
+    FilePersistentStorageModule
+>;
 
 #[cfg(not(no_std))]
 pub(crate) fn get_test_heap<'a>(
@@ -38,14 +122,8 @@ pub(crate) fn get_test_heap<'a>(
     resident_buffer: &'a mut [u8],
     dirty_size: usize,
     persist_handler: fn(*mut u8, usize) -> ()
-) -> VNVHeap<
-    'a,
-    LinkedListAllocatorModule,
-    NonResidentBuddyAllocatorModule<16>,
-    DefaultObjectManagementModule,
-    FilePersistentStorageModule
-> {
-    use crate::VNVConfig;
+) -> TestHeap<'a> {
+    use crate::{VNVConfig, WriteBack};
 
     let storage = get_test_storage(test_name, size);
 
@@ -55,6 +133,8 @@ pub(crate) fn get_test_heap<'a>(
         LinkedListAllocatorModule::new(),
         VNVConfig {
             max_dirty_bytes: dirty_size,
+            write_back: WriteBack::Lazy,
+            persist_latency_budget: None,
         },
         persist_handler
     )
@@ -68,3 +148,79 @@ pub(crate) fn get_test_heap(
 ) -> VNVHeap<LinkedListAllocatorModule, NonResidentBuddyAllocatorModule<16>> {
     panic!("not implemented")
 }
+
+/// Operation of an `InstrumentedStorageModule`, which is passed to its hook before it is executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StorageOperation {
+    Read { offset: usize, len: usize },
+    Write { offset: usize, len: usize },
+    Flush,
+}
+
+/// Wraps the storage of a test and calls `hook` before every operation, e.g. to count or time them
+pub(crate) struct InstrumentedStorageModule {
+    inner: FilePersistentStorageModule,
+    hook: fn(StorageOperation),
+}
+
+impl InstrumentedStorageModule {
+    pub(crate) fn new(test_name: &str, size: usize, hook: fn(StorageOperation)) -> Self {
+        Self {
+            inner: get_test_storage(test_name, size),
+            hook,
+        }
+    }
+}
+
+impl PersistentStorageModule for InstrumentedStorageModule {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        (self.hook)(StorageOperation::Read { offset, len: dest.len() });
+        self.inner.read(offset, dest)
+    }
+
+    fn get_max_size(&self) -> usize {
+        self.inner.get_max_size()
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        (self.hook)(StorageOperation::Write { offset, len: src.len() });
+        self.inner.write(offset, src)
+    }
+
+    fn flush(&mut self) -> Result<(), ()> {
+        (self.hook)(StorageOperation::Flush);
+        self.inner.flush()
+    }
+}
+
+pub(crate) type InstrumentedTestHeap<'a> = VNVHeap<
+    'a,
+    LinkedListAllocatorModule,
+    NonResidentBuddyAllocatorModule<16>,
+    DefaultObjectManagementModule,
+    InstrumentedStorageModule,
+>;
+
+#[cfg(not(no_std))]
+pub(crate) fn get_instrumented_test_heap<'a>(
+    test_name: &str,
+    resident_buffer: &'a mut [u8],
+    hook: fn(StorageOperation),
+) -> InstrumentedTestHeap<'a> {
+    use crate::{VNVConfig, WriteBack};
+
+    let storage = InstrumentedStorageModule::new(test_name, 4 * 4096, hook);
+
+    VNVHeap::new(
+        resident_buffer,
+        storage,
+        LinkedListAllocatorModule::new(),
+        VNVConfig {
+            max_dirty_bytes: 1000,
+            write_back: WriteBack::Lazy,
+            persist_latency_budget: None,
+        },
+        |_, _| {},
+    )
+    .unwrap()
+}
@@ -0,0 +1,80 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::{
+    modules::persistent_storage::PersistentStorageModule,
+    resident_object_manager::resident_object_backup::calc_backup_obj_user_data_offset,
+};
+
+use super::get_test_heap;
+
+#[test]
+fn test_object_checksums() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_object_checksums", 4 * 4096, &mut buffer, 2000, |_, _| {});
+
+    let mut obj = heap.allocate([1u32; 20]).unwrap();
+    {
+        let mut mut_ref = obj.get_mut().unwrap();
+        mut_ref[0] = 2;
+    }
+    obj.unload().unwrap();
+    assert_eq!(obj.get().unwrap()[0], 2);
+    assert!(obj.verify_checksum().unwrap().is_none());
+    obj.unload().unwrap();
+
+    // simulate a torn write: data was changed, but checksum was not
+    let offset = obj.get_alloc_id().offset;
+    let data_offset = offset + calc_backup_obj_user_data_offset();
+    heap.get_inner().borrow_mut().get_storage_module().write(data_offset, &[3]).unwrap();
+
+    assert!(obj.get().is_err());
+    assert!(obj.get_mut().is_err());
+    assert!(!obj.is_resident());
+    assert_eq!(heap.get_resident_usage().resident_objects, 0);
+
+    let mismatch = obj.verify_checksum().unwrap().unwrap();
+    assert_eq!(mismatch.storage_offset, offset);
+    assert_ne!(mismatch.expected, mismatch.actual);
+
+    // restoring the original data makes the object accessible again
+    heap.get_inner().borrow_mut().get_storage_module().write(data_offset, &[2]).unwrap();
+    assert!(obj.verify_checksum().unwrap().is_none());
+    assert_eq!(*obj.get().unwrap(), {
+        let mut data = [1u32; 20];
+        data[0] = 2;
+        data
+    });
+}
+
+#[test]
+fn test_object_checksums_discard_changes() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_object_checksums_discard_changes", 4 * 4096, &mut buffer, 2000, |_, _| {});
+
+    let mut obj = heap.allocate([1u8; 20]).unwrap();
+    obj.unload().unwrap();
+    obj.get_mut().unwrap()[5] = 2;
+
+    let data_offset = obj.get_alloc_id().offset + calc_backup_obj_user_data_offset();
+    heap.get_inner().borrow_mut().get_storage_module().write(data_offset + 5, &[7]).unwrap();
+
+    // reloaded data is corrupted and has to be dropped
+    assert!(obj.discard_changes().is_err());
+    assert!(!obj.is_resident());
+    assert!(obj.verify_checksum().unwrap().is_some());
+}
@@ -0,0 +1,279 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::{
+    modules::{
+        allocator::LinkedListAllocatorModule,
+        nonresident_allocator::NonResidentBuddyAllocatorModule,
+        object_management::{
+            ClockObjectManagementModule, CostAwareObjectManagementModule, DefaultObjectManagementModule,
+            ObjectManagementModule, PreferCleanObjectManagementModule, PrefetchingObjectManagementModule,
+            SizeBiasedObjectManagementModule,
+        },
+        persistent_storage::{test::get_test_storage, FilePersistentStorageModule},
+    },
+    vnv_heap::calc_resident_buf_unpersisted_size,
+    VNVConfig, VNVHeap, WriteBack,
+};
+
+/// The tests depend on how much of the resident buffer is usable, so the buffers grow with the cutoff
+const CUTOFF_GROWTH: usize = calc_resident_buf_unpersisted_size();
+
+fn get_test_heap_with<'a, M: ObjectManagementModule>(
+    test_name: &str,
+    resident_buffer: &'a mut [u8],
+) -> VNVHeap<'a, LinkedListAllocatorModule, NonResidentBuddyAllocatorModule<16>, M, FilePersistentStorageModule> {
+    let resident_buffer_len = resident_buffer.len();
+    VNVHeap::new(
+        resident_buffer,
+        get_test_storage(test_name, 4 * 4096),
+        LinkedListAllocatorModule::new(),
+        VNVConfig {
+            max_dirty_bytes: resident_buffer_len,
+            write_back: WriteBack::Lazy,
+            persist_latency_budget: None,
+        },
+        |_, _| {},
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_prefer_clean_object_management() {
+    fn run<M: ObjectManagementModule>(test_name: &str) -> (bool, bool) {
+        let mut buffer = [0u8; 450 + CUTOFF_GROWTH];
+        let heap = get_test_heap_with::<M>(test_name, &mut buffer);
+
+        let dirty = heap.allocate([1u8; 64]).unwrap();
+        let mut clean = heap.allocate([2u8; 64]).unwrap();
+        clean.flush().unwrap();
+        assert!(dirty.is_data_dirty());
+        assert!(!clean.is_data_dirty());
+
+        // there is only space for two objects
+        let mut other = heap.allocate([3u8; 64]).unwrap();
+        assert_eq!(*other.get().unwrap(), [3u8; 64]);
+
+        (dirty.is_resident(), clean.is_resident())
+    }
+
+    // without the combinator, the dirty object is unloaded first as it comes first in the list
+    let (dirty_resident, clean_resident) = run::<DefaultObjectManagementModule>("test_prefer_clean_object_management_default");
+    assert!(!dirty_resident);
+    assert!(clean_resident);
+
+    let (dirty_resident, clean_resident) = run::<PreferCleanObjectManagementModule<DefaultObjectManagementModule>>(
+        "test_prefer_clean_object_management",
+    );
+    assert!(dirty_resident);
+    assert!(!clean_resident);
+}
+
+#[test]
+fn test_size_biased_object_management() {
+    let mut buffer = [0u8; 480 + CUTOFF_GROWTH];
+    let heap = get_test_heap_with::<SizeBiasedObjectManagementModule<DefaultObjectManagementModule>>(
+        "test_size_biased_object_management",
+        &mut buffer,
+    );
+
+    let mut small1 = heap.allocate([1u8; 8]).unwrap();
+    let mut small2 = heap.allocate([2u8; 8]).unwrap();
+    let mut big = heap.allocate([3u8; 128]).unwrap();
+    let mut small3 = heap.allocate([4u8; 8]).unwrap();
+
+    // make all objects resident (in that order) without making them dirty
+    for obj in [&mut small1, &mut small2, &mut small3] {
+        obj.unload().unwrap();
+    }
+    big.unload().unwrap();
+    assert_eq!(*small1.get().unwrap(), [1u8; 8]);
+    assert_eq!(*small2.get().unwrap(), [2u8; 8]);
+    assert_eq!(*big.get().unwrap(), [3u8; 128]);
+    assert_eq!(*small3.get().unwrap(), [4u8; 8]);
+
+    // needs space, unloading the big object is enough
+    // (without the combinator, the small objects would be unloaded first)
+    let mut other = heap.allocate([5u8; 100]).unwrap();
+    assert_eq!(*other.get().unwrap(), [5u8; 100]);
+    assert!(!big.is_resident());
+    assert!(small1.is_resident());
+    assert!(small2.is_resident());
+    assert!(small3.is_resident());
+
+    assert_eq!(*big.get().unwrap(), [3u8; 128]);
+}
+
+#[test]
+fn test_cost_aware_object_management() {
+    let mut buffer = [0u8; 480 + CUTOFF_GROWTH];
+    let heap = get_test_heap_with::<CostAwareObjectManagementModule>("test_cost_aware_object_management", &mut buffer);
+
+    let dirty = heap.allocate([1u8; 64]).unwrap();
+    let mut small = heap.allocate([2u8; 8]).unwrap();
+    small.flush().unwrap();
+    let mut clean = heap.allocate([3u8; 64]).unwrap();
+    clean.flush().unwrap();
+
+    // the clean object is big enough on its own and does not have to be written back
+    let mut other = heap.allocate([4u8; 64]).unwrap();
+    assert_eq!(*other.get().unwrap(), [4u8; 64]);
+
+    assert!(dirty.is_resident());
+    assert!(small.is_resident());
+    assert!(!clean.is_resident());
+}
+
+#[test]
+fn test_cost_aware_object_management_weights() {
+    fn run<M: ObjectManagementModule>(test_name: &str) -> (bool, bool) {
+        let mut buffer = [0u8; 800 + CUTOFF_GROWTH];
+        let heap = get_test_heap_with::<M>(test_name, &mut buffer);
+
+        let dirty = heap.allocate([1u8; 256]).unwrap();
+        let mut clean = heap.allocate([2u8; 256]).unwrap();
+        clean.flush().unwrap();
+
+        // unloads the clean object and resets which objects were used recently
+        let mut filler = heap.allocate([0u8; 256]).unwrap();
+        assert_eq!(*filler.get().unwrap(), [0u8; 256]);
+        assert!(!clean.is_resident());
+        drop(filler);
+
+        // only the clean object was used recently
+        assert_eq!(*clean.get().unwrap(), [2u8; 256]);
+
+        let mut other = heap.allocate([3u8; 256]).unwrap();
+        assert_eq!(*other.get().unwrap(), [3u8; 256]);
+
+        (dirty.is_resident(), clean.is_resident())
+    }
+
+    // writing back is more expensive than unloading a recently used object
+    let (dirty_resident, clean_resident) = run::<CostAwareObjectManagementModule<4, 2>>(
+        "test_cost_aware_object_management_weights_dirty",
+    );
+    assert!(dirty_resident);
+    assert!(!clean_resident);
+
+    // unloading a recently used object is more expensive than writing back
+    let (dirty_resident, clean_resident) = run::<CostAwareObjectManagementModule<1, 4>>(
+        "test_cost_aware_object_management_weights_recency",
+    );
+    assert!(!dirty_resident);
+    assert!(clean_resident);
+}
+
+#[test]
+fn test_stacked_object_management() {
+    type Stacked = PreferCleanObjectManagementModule<SizeBiasedObjectManagementModule<ClockObjectManagementModule>>;
+
+    let mut buffer = [0u8; 600 + CUTOFF_GROWTH];
+    let heap = get_test_heap_with::<Stacked>("test_stacked_object_management", &mut buffer);
+
+    let mut objects = vec![];
+    for i in 0..20u8 {
+        let mut obj = heap.allocate([i; 32]).unwrap();
+        if i % 2 == 0 {
+            obj.flush().unwrap();
+        }
+        objects.push(obj);
+    }
+
+    for _ in 0..5 {
+        for (i, obj) in objects.iter_mut().enumerate() {
+            if i % 3 == 0 {
+                obj.get_mut().unwrap()[0] = i as u8;
+            }
+            assert_eq!(obj.get().unwrap()[1], i as u8);
+        }
+    }
+}
+
+#[test]
+fn test_prefetching_object_management() {
+    fn run<M: ObjectManagementModule>(test_name: &str) -> bool {
+        let mut buffer = [0u8; 450 + CUTOFF_GROWTH];
+        let heap = get_test_heap_with::<M>(test_name, &mut buffer);
+
+        let mut first = heap.allocate([1u8; 64]).unwrap();
+        let mut second = heap.allocate([2u8; 64]).unwrap();
+        first.flush().unwrap();
+        second.flush().unwrap();
+
+        // second is accessed after first
+        assert_eq!(*first.get().unwrap(), [1u8; 64]);
+        assert_eq!(*second.get().unwrap(), [2u8; 64]);
+
+        first.unload().unwrap();
+        second.unload().unwrap();
+        assert_eq!(*first.get().unwrap(), [1u8; 64]);
+        let prefetched = second.is_resident();
+        assert_eq!(*second.get().unwrap(), [2u8; 64]);
+
+        // deallocated objects are not prefetched anymore
+        drop(second);
+        first.unload().unwrap();
+        let resident_before = heap.get_inner().borrow_mut().get_resident_object_manager().count_resident_objects();
+        assert_eq!(*first.get().unwrap(), [1u8; 64]);
+        let resident_after = heap.get_inner().borrow_mut().get_resident_object_manager().count_resident_objects();
+        assert_eq!(resident_after, resident_before + 1);
+
+        prefetched
+    }
+
+    assert!(!run::<DefaultObjectManagementModule>("test_prefetching_object_management_default"));
+    assert!(run::<PrefetchingObjectManagementModule<DefaultObjectManagementModule>>(
+        "test_prefetching_object_management"
+    ));
+}
+
+#[test]
+fn test_clean_dirty_data() {
+    fn run<M: ObjectManagementModule>(test_name: &str) {
+        let mut buffer = [0u8; 600 + CUTOFF_GROWTH];
+        let heap = get_test_heap_with::<M>(test_name, &mut buffer);
+
+        let first = heap.allocate([1u8; 64]).unwrap();
+        let second = heap.allocate([2u8; 64]).unwrap();
+        let mut third = heap.allocate([3u8; 64]).unwrap();
+
+        // only one object fits into the budget
+        assert_eq!(heap.clean(0), 0);
+        assert_eq!(heap.clean(100), 64);
+        let dirty_count = [&first, &second, &third].iter().filter(|obj| obj.is_data_dirty()).count();
+        assert_eq!(dirty_count, 2);
+
+        // objects that are mutably borrowed are skipped
+        {
+            let mut data = third.get_mut().unwrap();
+            data[0] = 4;
+            heap.clean(usize::MAX);
+        }
+        assert!(!first.is_data_dirty());
+        assert!(!second.is_data_dirty());
+        assert!(third.is_data_dirty());
+
+        assert_eq!(heap.clean(usize::MAX), 64);
+        assert!(!third.is_data_dirty());
+        assert!(first.is_resident() && second.is_resident() && third.is_resident());
+    }
+
+    run::<DefaultObjectManagementModule>("test_clean_dirty_data_default");
+    run::<ClockObjectManagementModule>("test_clean_dirty_data_clock");
+    run::<CostAwareObjectManagementModule>("test_clean_dirty_data_cost_aware");
+    run::<PreferCleanObjectManagementModule<ClockObjectManagementModule>>("test_clean_dirty_data_prefer_clean");
+}
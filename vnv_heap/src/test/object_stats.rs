@@ -0,0 +1,114 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{ObjectStats, OBJECT_STATS_CAPACITY};
+
+use super::get_test_heap;
+
+static TIME: AtomicU64 = AtomicU64::new(0);
+
+fn get_time() -> u64 {
+    TIME.load(Ordering::SeqCst)
+}
+
+#[test]
+fn test_object_stats() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_object_stats", 4 * 4096, &mut buffer, 200, |_, _| {});
+    heap.set_stats_clock(Some(get_time));
+    TIME.store(0, Ordering::SeqCst);
+
+    let mut obj = heap.allocate([0u32; 10]).unwrap();
+    obj.unload().unwrap();
+    let mut other = heap.allocate(0u64).unwrap();
+    other.unload().unwrap();
+
+    // one fault, followed by a write back and an eviction
+    TIME.store(10, Ordering::SeqCst);
+    obj.get_mut().unwrap()[2] = 5;
+    TIME.store(25, Ordering::SeqCst);
+    obj.unload().unwrap();
+
+    let stats = obj.stats().unwrap();
+    assert_eq!(stats.faults, 1);
+    assert_eq!(stats.evictions, 2);
+    assert_eq!(stats.write_backs, 2);
+    assert_eq!(stats.write_back_bytes, 2 * 40);
+    assert_eq!(stats.resident_time, 15);
+
+    // the time of the current residency is included
+    TIME.store(30, Ordering::SeqCst);
+    assert_eq!(*obj.get().unwrap(), [0, 0, 5, 0, 0, 0, 0, 0, 0, 0]);
+    TIME.store(32, Ordering::SeqCst);
+    let stats = obj.stats().unwrap();
+    assert_eq!(stats.faults, 2);
+    assert_eq!(stats.write_backs, 2);
+    assert_eq!(stats.resident_time, 17);
+
+    let link = obj.link();
+    let entries: Vec<_> = heap.stats_iter().collect();
+    assert_eq!(entries.len(), 2);
+    let entry = entries.iter().find(|entry| entry.is_linked_by(&link)).unwrap();
+    assert_eq!(entry.size, 40);
+    assert_eq!(entry.stats, stats);
+
+    // deallocated objects are not tracked anymore
+    drop(obj);
+    assert_eq!(heap.stats_iter().count(), 1);
+    assert_eq!(
+        other.stats(),
+        Some(ObjectStats {
+            faults: 0,
+            evictions: 1,
+            write_backs: 1,
+            write_back_bytes: 8,
+            resident_time: 0,
+        })
+    );
+
+    // the default clock counts the objects that were loaded from storage
+    heap.set_stats_clock(None);
+    let _ = other.get().unwrap();
+    let mut third = heap.allocate(0u16).unwrap();
+    third.unload().unwrap();
+    let _ = third.get().unwrap();
+    assert_eq!(other.stats().unwrap().resident_time, 1);
+}
+
+#[test]
+fn test_object_stats_capacity() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_object_stats_capacity", 4 * 4096, &mut buffer, 200, |_, _| {});
+
+    let mut objects: Vec<_> = (0..OBJECT_STATS_CAPACITY + 1)
+        .map(|i| {
+            let mut obj = heap.allocate(i as u32).unwrap();
+            obj.unload().unwrap();
+            obj
+        })
+        .collect();
+
+    for obj in objects.iter_mut() {
+        let _ = obj.get().unwrap();
+    }
+
+    assert_eq!(heap.stats_iter().count(), OBJECT_STATS_CAPACITY);
+    assert!(objects[OBJECT_STATS_CAPACITY].stats().is_none());
+    assert_eq!(objects[0].stats().unwrap().faults, 1);
+}
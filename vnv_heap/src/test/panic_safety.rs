@@ -0,0 +1,95 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::{
+    mem::forget,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+use crate::VNVError;
+
+use super::get_test_heap;
+
+#[test]
+fn test_panic_while_mut_ref_alive() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_panic_while_mut_ref_alive", 8192, &mut buffer, 1000, |_, _| {});
+
+    let mut obj = heap.allocate([0u32; 10]).unwrap();
+
+    let res = catch_unwind(AssertUnwindSafe(|| {
+        let mut mut_ref = obj.get_mut().unwrap();
+        mut_ref[0] = 1;
+        panic!("user code panicked");
+    }));
+    assert!(res.is_err());
+
+    // the reference was released while unwinding
+    assert_eq!(unsafe { heap.force_release_all() }, 0);
+    obj.unload().unwrap();
+    assert_eq!(obj.get().unwrap()[0], 1);
+}
+
+#[test]
+fn test_panic_while_ref_alive() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_panic_while_ref_alive", 8192, &mut buffer, 1000, |_, _| {});
+
+    let mut obj = heap.allocate(42u64).unwrap();
+
+    let res = catch_unwind(AssertUnwindSafe(|| {
+        let obj_ref = obj.get().unwrap();
+        assert_eq!(*obj_ref, 43);
+    }));
+    assert!(res.is_err());
+
+    assert_eq!(unsafe { heap.force_release_all() }, 0);
+    *obj.get_mut().unwrap() = 43;
+    obj.unload().unwrap();
+    assert_eq!(*obj.get().unwrap(), 43);
+}
+
+#[test]
+fn test_force_release_all() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_force_release_all", 8192, &mut buffer, 1000, |_, _| {});
+
+    let mut obj1 = heap.allocate(1u32).unwrap();
+    let mut obj2 = heap.allocate(2u32).unwrap();
+    let mut obj3 = heap.allocate(3u32).unwrap();
+
+    forget(obj1.get_mut().unwrap());
+    forget(obj2.get().unwrap());
+    assert!(obj1.unload().is_err());
+    assert!(obj2.unload().is_err());
+
+    assert_eq!(unsafe { heap.force_release_all() }, 2);
+    assert_eq!(unsafe { heap.force_release_all() }, 0);
+
+    obj1.unload().unwrap();
+    obj2.unload().unwrap();
+    obj3.unload().unwrap();
+    assert_eq!(*obj1.get().unwrap(), 1);
+    assert_eq!(*obj2.get().unwrap(), 2);
+    assert_eq!(*obj3.get().unwrap(), 3);
+
+    // pinned objects are not released
+    obj3.pin().unwrap();
+    assert_eq!(unsafe { heap.force_release_all() }, 0);
+    assert!(obj3.is_pinned());
+    assert_eq!(obj3.unload(), Err(VNVError::ObjectInUse));
+    obj3.unpin();
+}
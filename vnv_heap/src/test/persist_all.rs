@@ -18,18 +18,20 @@
 use std::{
     array,
     ptr::{null_mut, slice_from_raw_parts_mut},
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
 use rand::{rngs::SmallRng, RngCore, SeedableRng};
 
 use crate::{
     modules::{
-        allocator::LinkedListAllocatorModule,
-        nonresident_allocator::NonResidentBuddyAllocatorModule,
-        object_management::ObjectManagementModule,
+        allocator::{LinkedListAllocatorModule, SlabAllocatorModule, TlsfAllocatorModule},
+        nonresident_allocator::{NonResidentBuddyAllocatorModule, NonResidentSlabAllocator},
+        object_management::{DefaultObjectManagementModule, ObjectManagementModule},
+        persistent_storage::{test::get_test_storage, FilePersistentStorageModule},
     },
-    test::get_test_heap,
-    vnv_persist_all, VNVObject,
+    test::{get_instrumented_test_heap, get_test_heap, StorageOperation},
+    vnv_persist_all, VNVConfig, VNVHeap, VNVObject, WriteBack,
 };
 
 #[test]
@@ -293,3 +295,107 @@ fn test_persist_all_simple() {
         &mut resident,
     );
 }
+
+#[test]
+fn test_persist_all_restore_reads() {
+    static READS: AtomicUsize = AtomicUsize::new(0);
+    fn count_reads(op: StorageOperation) {
+        if matches!(op, StorageOperation::Read { .. }) {
+            READS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let mut buffer = [0u8; 2000];
+    let heap = get_instrumented_test_heap("test_persist_all_restore_reads", &mut buffer, count_reads);
+
+    // many small dirty objects, so that restoring is dominated by reading metadata
+    let mut objs: Vec<_> = (0..20).map(|i| heap.allocate(i as u32).unwrap()).collect();
+    for (i, obj) in objs.iter_mut().enumerate() {
+        *obj.get_mut().unwrap() += 100 + i as u32;
+    }
+
+    READS.store(0, Ordering::SeqCst);
+    unsafe { vnv_persist_all() };
+
+    // the persisted slice is read through a staging buffer instead of twice per object
+    assert!(READS.load(Ordering::SeqCst) < objs.len());
+
+    for (i, obj) in objs.iter_mut().enumerate() {
+        assert_eq!(*obj.get().unwrap(), 100 + 2 * i as u32);
+    }
+}
+
+#[test]
+fn test_persist_all_tlsf() {
+    let mut buffer = [0u8; 2000];
+    let heap: VNVHeap<_, NonResidentBuddyAllocatorModule<16>, DefaultObjectManagementModule, _> = VNVHeap::new(
+        &mut buffer,
+        get_test_storage("test_persist_all_tlsf", 4096),
+        TlsfAllocatorModule::<16>::new(),
+        VNVConfig {
+            max_dirty_bytes: 1200,
+            write_back: WriteBack::Lazy,
+            persist_latency_budget: None,
+        },
+        |base_ptr, size| {
+            let buffer = unsafe { slice_from_raw_parts_mut(base_ptr, size).as_mut() }.unwrap();
+            buffer.fill(0);
+        },
+    )
+    .unwrap();
+
+    // free blocks between the resident objects, the heap dump is checked after restoring (debug builds)
+    let mut objs: Vec<_> = (0..12).map(|i| heap.allocate([i as u64; 3]).unwrap()).collect();
+    for i in (0..objs.len()).rev().step_by(3) {
+        objs.remove(i);
+    }
+    for obj in objs.iter_mut() {
+        obj.get_mut().unwrap()[1] += 100;
+    }
+
+    unsafe { vnv_persist_all() };
+
+    for obj in objs.iter_mut() {
+        let data = *obj.get().unwrap();
+        assert_eq!(data[1], data[0] + 100);
+    }
+}
+
+#[test]
+fn test_persist_all_slab() {
+    type SlabHeap<'a> = VNVHeap<'a, SlabAllocatorModule<128>, NonResidentSlabAllocator<32, 4>, DefaultObjectManagementModule, FilePersistentStorageModule>;
+    assert!(SlabHeap::get_object_layout_info::<[u64; 3]>().resident_size <= 128);
+
+    let mut buffer = [0u8; 2000];
+    let heap: SlabHeap = VNVHeap::new(
+        &mut buffer,
+        get_test_storage("test_persist_all_slab", 8192),
+        SlabAllocatorModule::<128>::new(),
+        VNVConfig {
+            max_dirty_bytes: 1200,
+            write_back: WriteBack::Lazy,
+            persist_latency_budget: None,
+        },
+        |base_ptr, size| {
+            let buffer = unsafe { slice_from_raw_parts_mut(base_ptr, size).as_mut() }.unwrap();
+            buffer.fill(0);
+        },
+    )
+    .unwrap();
+
+    // free slots between the resident objects, the heap dump is checked after restoring (debug builds)
+    let mut objs: Vec<_> = (0..12).map(|i| heap.allocate([i as u64; 3]).unwrap()).collect();
+    for i in (0..objs.len()).rev().step_by(3) {
+        objs.remove(i);
+    }
+    for obj in objs.iter_mut() {
+        obj.get_mut().unwrap()[1] += 100;
+    }
+
+    unsafe { vnv_persist_all() };
+
+    for obj in objs.iter_mut() {
+        let data = *obj.get().unwrap();
+        assert_eq!(data[1], data[0] + 100);
+    }
+}
@@ -0,0 +1,138 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::{
+    fs,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{vnv_persist_budget, PartialPersistResult};
+
+use super::{get_instrumented_test_heap, get_test_heap, StorageOperation};
+
+/// Returns `true` if `pattern` is stored anywhere in the storage of the test
+pub(super) fn is_in_storage(test_name: &str, pattern: &[u8]) -> bool {
+    let image = fs::read(format!("/tmp/{}.tmp", test_name)).unwrap();
+    image.windows(pattern.len()).any(|window| window == pattern)
+}
+
+#[test]
+fn test_persist_budget() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_persist_budget", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let mut objects = [
+        heap.allocate([0u8; 64]).unwrap(),
+        heap.allocate([0u8; 64]).unwrap(),
+        heap.allocate([0u8; 64]).unwrap(),
+    ];
+    for (i, obj) in objects.iter_mut().enumerate() {
+        *obj.get_mut().unwrap() = [0x11 * (i as u8 + 1); 64];
+    }
+    let written_count = || {
+        (1..=3u8)
+            .filter(|i| is_in_storage("test_persist_budget", &[0x11 * i; 64]))
+            .count()
+    };
+
+    // only one object fits into the budget per call
+    let result = unsafe { vnv_persist_budget(100) }.unwrap();
+    assert_eq!(result, PartialPersistResult { written_bytes: 64, remaining_bytes: 128 });
+    assert_eq!(written_count(), 1);
+
+    let result = unsafe { vnv_persist_budget(100) }.unwrap();
+    assert_eq!(result, PartialPersistResult { written_bytes: 64, remaining_bytes: 64 });
+    assert_eq!(written_count(), 2);
+
+    let result = unsafe { vnv_persist_budget(1000) }.unwrap();
+    assert_eq!(result, PartialPersistResult { written_bytes: 64, remaining_bytes: 0 });
+    assert!(result.is_complete());
+    assert_eq!(written_count(), 3);
+
+    // written objects stay dirty
+    assert!(objects.iter().all(|obj| obj.is_data_dirty()));
+
+    // modified objects are written again
+    *objects[0].get_mut().unwrap() = [0x44; 64];
+    let result = unsafe { vnv_persist_budget(1000) }.unwrap();
+    assert_eq!(result, PartialPersistResult { written_bytes: 64, remaining_bytes: 0 });
+    assert!(is_in_storage("test_persist_budget", &[0x44; 64]));
+
+    let result = unsafe { vnv_persist_budget(1000) }.unwrap();
+    assert_eq!(result, PartialPersistResult { written_bytes: 0, remaining_bytes: 0 });
+}
+
+#[test]
+fn test_persist_budget_mutable_ref() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_persist_budget_mutable_ref", 4 * 4096, &mut buffer, 1000, |_, _| {});
+    let mut obj = heap.allocate([0u8; 64]).unwrap();
+
+    let mut obj_ref = obj.get_mut().unwrap();
+    obj_ref[0] = 1;
+
+    // objects that are modified right now are written, but have to be written again
+    let result = unsafe { vnv_persist_budget(1000) }.unwrap();
+    assert_eq!(result.written_bytes, 64);
+    obj_ref[0] = 2;
+    let result = unsafe { vnv_persist_budget(1000) }.unwrap();
+    assert_eq!(result.written_bytes, 64);
+    drop(obj_ref);
+
+    let mut expected = [0u8; 64];
+    expected[0] = 2;
+    assert!(is_in_storage("test_persist_budget_mutable_ref", &expected));
+
+    // the reference is closed now, so the object is written one last time
+    assert_eq!(unsafe { vnv_persist_budget(1000) }.unwrap().written_bytes, 64);
+    assert_eq!(unsafe { vnv_persist_budget(1000) }.unwrap().written_bytes, 0);
+}
+
+#[test]
+fn test_persist_budget_batched_writes() {
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+    fn count_writes(op: StorageOperation) {
+        if matches!(op, StorageOperation::Write { .. }) {
+            WRITES.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let mut buffer = [0u8; 1000];
+    let heap = get_instrumented_test_heap("test_persist_budget_batched_writes", &mut buffer, count_writes);
+
+    let mut objects = vec![];
+    for i in 0..8u8 {
+        let mut obj = heap.allocate([0u8; 16]).unwrap();
+        *obj.get_mut().unwrap() = [i + 1; 16];
+        objects.push(obj);
+    }
+
+    WRITES.store(0, Ordering::SeqCst);
+    let result = unsafe { vnv_persist_budget(1000) }.unwrap();
+    assert_eq!(result.written_bytes, 8 * 16);
+
+    // the header of an object (see `object_checksums`) is written together with its data
+    assert!(WRITES.load(Ordering::SeqCst) <= objects.len());
+
+    // without a header, the objects are stored right behind each other
+    #[cfg(not(any(feature = "object_checksums", feature = "access_counters")))]
+    assert_eq!(WRITES.load(Ordering::SeqCst), 1);
+
+    for (i, obj) in objects.iter_mut().enumerate() {
+        obj.unload().unwrap();
+        assert_eq!(*obj.get().unwrap(), [i as u8 + 1; 16]);
+    }
+}
@@ -0,0 +1,169 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::{
+    modules::{
+        allocator::LinkedListAllocatorModule,
+        nonresident_allocator::NonResidentBuddyAllocatorModule,
+        object_management::DefaultObjectManagementModule,
+        persistent_storage::{test::get_test_storage, FilePersistentStorageModule},
+    },
+    test::get_test_heap,
+    PersistLatencyBudget, VNVConfig, VNVError, VNVHeap, VNVObject, WriteBack, StorageThroughput,
+};
+
+type TestObject<'a, 'b> = VNVObject<'a, 'b, [u8; 64], LinkedListAllocatorModule, NonResidentBuddyAllocatorModule<16>, DefaultObjectManagementModule>;
+
+/// One byte per µs with a fixed latency of 100µs
+const THROUGHPUT: StorageThroughput = StorageThroughput {
+    fixed_latency_us: 100,
+    bytes_per_ms: 1000,
+};
+
+fn count_dirty(objects: &[TestObject]) -> usize {
+    objects.iter().filter(|obj| obj.is_data_dirty()).count()
+}
+
+#[test]
+fn test_write_throughput() {
+    assert_eq!(THROUGHPUT.max_bytes(600), 500);
+    assert_eq!(THROUGHPUT.max_bytes(50), 0);
+    assert_eq!(THROUGHPUT.transfer_time_us(500), 600);
+
+    let slow = StorageThroughput {
+        fixed_latency_us: 0,
+        bytes_per_ms: 3,
+    };
+    assert_eq!(slow.max_bytes(1000), 3);
+    assert_eq!(slow.transfer_time_us(1), 334);
+}
+
+#[test]
+fn test_persist_latency_budget() {
+    let mut buffer = [0u8; 2000];
+    let budget = PersistLatencyBudget {
+        max_latency_us: 600,
+        write_throughput: THROUGHPUT,
+    };
+    let heap: VNVHeap<LinkedListAllocatorModule, NonResidentBuddyAllocatorModule<16>, DefaultObjectManagementModule, FilePersistentStorageModule> = VNVHeap::new(
+        &mut buffer,
+        get_test_storage("test_persist_latency_budget", 4 * 4096),
+        LinkedListAllocatorModule::new(),
+        VNVConfig {
+            max_dirty_bytes: 1500,
+            write_back: WriteBack::Lazy,
+            persist_latency_budget: Some(budget),
+        },
+        |_, _| {},
+    )
+    .unwrap();
+    assert_eq!(heap.get_max_dirty_bytes(), 500);
+    assert_eq!(heap.get_persist_latency_budget(), Some(budget));
+
+    // not all objects can stay dirty
+    let objects: Vec<_> = (0..10).map(|i| heap.allocate([i as u8; 64]).unwrap()).collect();
+    let dirty = count_dirty(&objects);
+    assert!(dirty > 0 && dirty < 10);
+
+    // slower storage means less dirty bytes
+    heap.recalibrate_write_throughput(StorageThroughput {
+        bytes_per_ms: 500,
+        ..THROUGHPUT
+    })
+    .unwrap();
+    assert_eq!(heap.get_max_dirty_bytes(), 250);
+    assert!(count_dirty(&objects) < dirty);
+
+    // without a budget, all objects can be dirty at the same time
+    heap.set_persist_latency_budget(None).unwrap();
+    assert_eq!(heap.get_max_dirty_bytes(), 1500);
+    let mut objects: Vec<_> = (0..10).map(|i| heap.allocate([i as u8; 64]).unwrap()).collect();
+    assert_eq!(count_dirty(&objects), 10);
+
+    // objects that are in use are not synced
+    let mut refs: Vec<_> = objects.iter_mut().map(|obj| obj.get_mut().unwrap()).collect();
+    refs[0][0] = 42;
+    assert_eq!(heap.set_persist_latency_budget(Some(budget)), Err(VNVError::DirtyBudgetExhausted));
+    assert_eq!(heap.get_persist_latency_budget(), None);
+    drop(refs);
+
+    heap.set_persist_latency_budget(Some(budget)).unwrap();
+    assert_eq!(heap.get_max_dirty_bytes(), 500);
+    assert_eq!(*objects[0].get().unwrap(), {
+        let mut data = [0u8; 64];
+        data[0] = 42;
+        data
+    });
+}
+
+#[test]
+fn test_persist_latency_budget_unset() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_persist_latency_budget_unset", 4 * 4096, &mut buffer, 500, |_, _| {});
+    assert_eq!(heap.get_max_dirty_bytes(), 500);
+    assert_eq!(heap.recalibrate_write_throughput(THROUGHPUT), Err(VNVError::Unsupported));
+}
+
+#[test]
+fn test_set_max_dirty_bytes() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_set_max_dirty_bytes", 4 * 4096, &mut buffer, 1500, |_, _| {});
+
+    let mut objects: Vec<_> = (0..10).map(|i| heap.allocate([i as u8; 64]).unwrap()).collect();
+    assert_eq!(count_dirty(&objects), 10);
+
+    // shrinking syncs objects
+    heap.set_max_dirty_bytes(400).unwrap();
+    assert_eq!(heap.get_max_dirty_bytes(), 400);
+    assert!(count_dirty(&objects) < 10);
+
+    // the storage region for persisting cannot grow
+    assert_eq!(heap.set_max_dirty_bytes(1501), Err(VNVError::Unsupported));
+    assert_eq!(heap.get_max_dirty_bytes(), 400);
+
+    // removing the persist latency budget does not exceed the new maximum
+    heap.set_persist_latency_budget(None).unwrap();
+    assert_eq!(heap.get_max_dirty_bytes(), 400);
+    assert_eq!(heap.get_dirty_capacity(), 1500);
+
+    // the persist latency budget still applies
+    heap.set_persist_latency_budget(Some(PersistLatencyBudget {
+        max_latency_us: 600,
+        write_throughput: THROUGHPUT,
+    }))
+    .unwrap();
+    heap.set_max_dirty_bytes(1000).unwrap();
+    assert_eq!(heap.get_max_dirty_bytes(), 500);
+    heap.set_max_dirty_bytes(300).unwrap();
+    assert_eq!(heap.get_max_dirty_bytes(), 300);
+    heap.set_persist_latency_budget(None).unwrap();
+
+    // growing again
+    heap.set_max_dirty_bytes(1500).unwrap();
+    assert_eq!(heap.get_max_dirty_bytes(), 1500);
+    for obj in objects.iter_mut() {
+        obj.get_mut().unwrap()[0] = 1;
+    }
+    assert_eq!(count_dirty(&objects), 10);
+
+    // objects that are in use are not synced
+    let mut refs: Vec<_> = objects.iter_mut().map(|obj| obj.get_mut().unwrap()).collect();
+    refs[0][1] = 2;
+    assert_eq!(heap.set_max_dirty_bytes(200), Err(VNVError::DirtyBudgetExhausted));
+    drop(refs);
+    assert_eq!(heap.get_max_dirty_bytes(), 1500);
+}
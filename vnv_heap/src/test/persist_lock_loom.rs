@@ -0,0 +1,142 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Model checking of the protocol between `SharedPersistLock` and `vnv_persist_all` with loom.
+//!
+//! This runs the real `SharedPersistLock`, `SharedPersistGuard::drop` and the lock checks of
+//! `PersistAccessPoint::persist_if_not_empty` (`is_unlocked_or_queue`). Their lock primitives are replaced by
+//! the ones of `persist_sync`, which lets loom interrupt the main program between any two atomic operations.
+//!
+//! Run with: `RUSTFLAGS="--cfg loom" cargo test loom`
+
+use core::{ptr::null_mut, sync::atomic::Ordering};
+use std::sync::Arc;
+
+use loom::{
+    sync::atomic::{AtomicUsize, Ordering as LoomOrdering},
+    thread,
+};
+
+use crate::{
+    modules::persistent_storage::{DummyStorageModule, PersistentStorageModule, SharedStorageReference},
+    persist_access_point::is_unlocked_or_queue,
+    persist_sync::{interrupt, set_queued_persist_handler, step, AtomicBool, TryLock},
+    shared_persist_lock::SharedPersistLock,
+};
+
+/// The locks of a heap (see `ResidentBufPersistentStorage`) and some state that is modified while they are held
+struct Heap {
+    /// Mirrors the lock of `PersistAccessPoint`
+    access_point_lock: TryLock<()>,
+    heap_lock: TryLock<()>,
+    storage_lock: TryLock<()>,
+    persist_queued: AtomicBool,
+
+    /// State that is modified while the heap lock is held.
+    /// Both values are always equal if the heap lock is not held.
+    state: [AtomicUsize; 2],
+
+    /// How many times the state was persisted
+    persist_count: AtomicUsize,
+}
+
+impl Heap {
+    fn new() -> Self {
+        Self {
+            access_point_lock: TryLock::new(()),
+            heap_lock: TryLock::new(()),
+            storage_lock: TryLock::new(()),
+            persist_queued: AtomicBool::new(false),
+            state: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            persist_count: AtomicUsize::new(0),
+        }
+    }
+
+    fn heap(&self) -> SharedPersistLock<'_, ()> {
+        SharedPersistLock::new((), &self.persist_queued, &self.heap_lock)
+    }
+
+    fn storage(&self) -> SharedStorageReference<'_, '_> {
+        // the storage is only locked, but never accessed
+        let storage: *mut dyn PersistentStorageModule = null_mut::<DummyStorageModule>();
+        SharedStorageReference::new(SharedPersistLock::new(storage, &self.persist_queued, &self.storage_lock))
+    }
+
+    /// Same steps as `PersistAccessPoint::persist_if_not_empty` (without the actual persisting)
+    fn persist_all(&self) {
+        let _access_point_guard = match self.access_point_lock.try_lock() {
+            Some(guard) => guard,
+            // someone else is persisting right now
+            None => return,
+        };
+
+        if !is_unlocked_or_queue(&self.heap_lock, &self.storage(), &self.persist_queued) {
+            return;
+        }
+
+        step(|| {
+            let first = self.state[0].load(LoomOrdering::SeqCst);
+            let second = self.state[1].load(LoomOrdering::SeqCst);
+            assert_eq!(first, second, "inconsistent state was persisted");
+
+            self.persist_count.fetch_add(1, LoomOrdering::SeqCst);
+        });
+    }
+
+    /// An operation that modifies the heap and then accesses the storage (nested locks)
+    fn main_program(&self) {
+        let heap = self.heap();
+        let storage = self.storage();
+
+        let heap_guard = heap.try_lock().unwrap();
+        step(|| self.state[0].store(1, LoomOrdering::SeqCst));
+
+        let storage_guard = storage.try_lock().unwrap();
+        step(|| self.state[1].store(1, LoomOrdering::SeqCst));
+
+        drop(storage_guard);
+        drop(heap_guard);
+    }
+}
+
+#[test]
+fn test_loom_persist_lock() {
+    loom::model(|| {
+        let heap = Arc::new(Heap::new());
+
+        {
+            // a queued persist is executed by the main program
+            let heap = heap.clone();
+            set_queued_persist_handler(Arc::new(move || heap.persist_all()));
+        }
+
+        let interrupt_thread = {
+            let heap = heap.clone();
+            thread::spawn(move || interrupt(|| heap.persist_all()))
+        };
+
+        heap.main_program();
+        interrupt_thread.join().unwrap();
+
+        // the persist request of the interrupt is never lost
+        assert!(
+            heap.persist_count.load(LoomOrdering::SeqCst) >= 1,
+            "persist request was lost"
+        );
+        assert!(!heap.persist_queued.load(Ordering::SeqCst));
+        assert!(heap.access_point_lock.try_lock().is_some());
+    });
+}
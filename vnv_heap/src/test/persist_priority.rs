@@ -0,0 +1,142 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::{
+    fs,
+    mem::size_of,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{vnv_persist_all, vnv_persist_budget, PersistPriority, VNVError, PERSIST_PRIORITY_CAPACITY};
+
+use super::{get_test_heap, persist_budget::is_in_storage};
+
+const NORMAL_PATTERN: [u8; 32] = [0xAA; 32];
+const CRITICAL_PATTERN: [u8; 32] = [0xBB; 32];
+
+static NORMAL_WRITTEN_BACK: AtomicBool = AtomicBool::new(false);
+static CRITICAL_WRITTEN_BACK: AtomicBool = AtomicBool::new(false);
+
+/// Returns `true` if `pattern` is stored behind the persisted state (i.e. at the storage location of an object)
+fn is_written_back(image: &[u8], pattern: &[u8]) -> bool {
+    let slice_size = usize::from_ne_bytes(image[..size_of::<usize>()].try_into().unwrap());
+    image[slice_size..].windows(pattern.len()).any(|window| window == pattern)
+}
+
+#[test]
+fn test_persist_priority_write_back() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_persist_priority_write_back", 4 * 4096, &mut buffer, 1000, |_, _| {
+        let image = fs::read("/tmp/test_persist_priority_write_back.tmp").unwrap();
+        NORMAL_WRITTEN_BACK.store(is_written_back(&image, &NORMAL_PATTERN), Ordering::SeqCst);
+        CRITICAL_WRITTEN_BACK.store(is_written_back(&image, &CRITICAL_PATTERN), Ordering::SeqCst);
+    });
+
+    let mut normal = heap.allocate([0u8; 32]).unwrap();
+    let mut critical = heap.allocate_with_priority([0u8; 32], PersistPriority::Critical).unwrap();
+    assert_eq!(critical.get_persist_priority(), PersistPriority::Critical);
+    assert_eq!(normal.get_persist_priority(), PersistPriority::Normal);
+
+    *normal.get_mut().unwrap() = NORMAL_PATTERN;
+    *critical.get_mut().unwrap() = CRITICAL_PATTERN;
+
+    unsafe { vnv_persist_all() };
+
+    // only the critical object was written to its storage location, both are still part of the persisted state
+    assert!(CRITICAL_WRITTEN_BACK.load(Ordering::SeqCst));
+    assert!(!NORMAL_WRITTEN_BACK.load(Ordering::SeqCst));
+    assert!(critical.is_data_dirty());
+    assert_eq!(*critical.get().unwrap(), CRITICAL_PATTERN);
+    assert_eq!(*normal.get().unwrap(), NORMAL_PATTERN);
+
+    // the priority is kept if the object is unloaded
+    critical.unload().unwrap();
+    assert_eq!(critical.get_persist_priority(), PersistPriority::Critical);
+    critical.set_persist_priority(PersistPriority::Normal).unwrap();
+    assert_eq!(critical.get_persist_priority(), PersistPriority::Normal);
+}
+
+#[test]
+fn test_persist_priority_capacity() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_persist_priority_capacity", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let mut objects = vec![];
+    for i in 0..PERSIST_PRIORITY_CAPACITY {
+        objects.push(heap.allocate_with_priority(i, PersistPriority::High).unwrap());
+    }
+    assert_eq!(
+        heap.allocate_with_priority(0usize, PersistPriority::High).err(),
+        Some(VNVError::CapacityExhausted)
+    );
+
+    // changing the priority of an object does not need another entry
+    objects[0].set_persist_priority(PersistPriority::Critical).unwrap();
+
+    // deallocated objects release their entry
+    drop(objects.pop());
+    let mut obj = heap.allocate_with_priority(0usize, PersistPriority::High).unwrap();
+    assert_eq!(obj.get_persist_priority(), PersistPriority::High);
+    assert_eq!(
+        heap.allocate_with_priority(0usize, PersistPriority::High).err(),
+        Some(VNVError::CapacityExhausted)
+    );
+
+    // resetting the priority releases the entry as well
+    obj.set_persist_priority(PersistPriority::Normal).unwrap();
+    heap.allocate_with_priority(0usize, PersistPriority::Critical).unwrap();
+}
+
+#[test]
+fn test_persist_priority_budget() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_persist_priority_budget", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let mut normal = heap.allocate([0u8; 32]).unwrap();
+    let mut high = heap.allocate_with_priority([0u8; 32], PersistPriority::High).unwrap();
+    let mut critical = heap.allocate_with_priority([0u8; 32], PersistPriority::Critical).unwrap();
+    *normal.get_mut().unwrap() = NORMAL_PATTERN;
+    *high.get_mut().unwrap() = [0xCC; 32];
+    *critical.get_mut().unwrap() = CRITICAL_PATTERN;
+
+    unsafe { vnv_persist_budget(40) }.unwrap();
+    assert!(is_in_storage("test_persist_priority_budget", &CRITICAL_PATTERN));
+    assert!(!is_in_storage("test_persist_priority_budget", &[0xCC; 32]));
+
+    unsafe { vnv_persist_budget(40) }.unwrap();
+    assert!(is_in_storage("test_persist_priority_budget", &[0xCC; 32]));
+    assert!(!is_in_storage("test_persist_priority_budget", &NORMAL_PATTERN));
+
+    assert!(unsafe { vnv_persist_budget(40) }.unwrap().is_complete());
+    assert!(is_in_storage("test_persist_priority_budget", &NORMAL_PATTERN));
+}
+
+#[test]
+fn test_persist_priority_reload() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_persist_priority_reload", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    // the priority is set while the object is not resident
+    let mut critical = heap.allocate([0u8; 32]).unwrap();
+    critical.unload().unwrap();
+    critical.set_persist_priority(PersistPriority::Critical).unwrap();
+
+    *critical.get_mut().unwrap() = CRITICAL_PATTERN;
+    unsafe { vnv_persist_all() };
+
+    let image = fs::read("/tmp/test_persist_priority_reload.tmp").unwrap();
+    assert!(is_written_back(&image, &CRITICAL_PATTERN));
+}
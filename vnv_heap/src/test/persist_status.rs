@@ -0,0 +1,94 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::{
+    modules::allocator::LinkedListAllocatorModule,
+    test::{InstrumentedStorageModule, InstrumentedTestHeap, StorageOperation},
+    persist_status, vnv_persist_all, wait_persist_complete, PersistStatus, VNVConfig, VNVHeap, WriteBack,
+};
+
+use super::get_test_heap;
+
+static PERSISTED_IN_HANDLER: AtomicBool = AtomicBool::new(false);
+
+fn check_status_handler(_: *mut u8, _: usize) {
+    let status = wait_persist_complete(|| panic!("persist should be complete already"));
+    PERSISTED_IN_HANDLER.store(status == PersistStatus::Persisted, Ordering::SeqCst);
+}
+
+#[test]
+fn test_persist_status() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_persist_status", 4 * 4096, &mut buffer, 1000, check_status_handler);
+
+    let mut obj = heap.allocate(5u32).unwrap();
+    assert_eq!(persist_status(), PersistStatus::Idle);
+    assert_eq!(wait_persist_complete(|| panic!("no persist was triggered")), PersistStatus::Idle);
+
+    PERSISTED_IN_HANDLER.store(false, Ordering::SeqCst);
+    unsafe { vnv_persist_all() };
+    assert!(PERSISTED_IN_HANDLER.load(Ordering::SeqCst));
+
+    assert_eq!(persist_status(), PersistStatus::Idle);
+    assert_eq!(*obj.get().unwrap(), 5);
+}
+
+#[test]
+fn test_persist_status_queued() {
+    static TRIGGER: AtomicBool = AtomicBool::new(false);
+    static QUEUED: AtomicUsize = AtomicUsize::new(0);
+
+    /// Triggers a persist while the storage is locked
+    fn trigger_persist(op: StorageOperation) {
+        if matches!(op, StorageOperation::Write { .. }) && TRIGGER.swap(false, Ordering::SeqCst) {
+            unsafe { vnv_persist_all() };
+            if persist_status() == PersistStatus::Queued {
+                QUEUED.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    let mut buffer = [0u8; 1000];
+    let storage = InstrumentedStorageModule::new("test_persist_status_queued", 4 * 4096, trigger_persist);
+    let heap: InstrumentedTestHeap =
+        VNVHeap::new(
+            &mut buffer,
+            storage,
+            LinkedListAllocatorModule::new(),
+            VNVConfig {
+                max_dirty_bytes: 1000,
+                write_back: WriteBack::Lazy,
+                persist_latency_budget: None,
+            },
+            check_status_handler,
+        )
+        .unwrap();
+
+    let mut obj = heap.allocate([1u8; 16]).unwrap();
+
+    PERSISTED_IN_HANDLER.store(false, Ordering::SeqCst);
+    TRIGGER.store(true, Ordering::SeqCst);
+    obj.unload().unwrap();
+
+    // the persist was executed as soon as the storage was not locked anymore
+    assert_eq!(QUEUED.load(Ordering::SeqCst), 1);
+    assert!(PERSISTED_IN_HANDLER.load(Ordering::SeqCst));
+    assert_eq!(persist_status(), PersistStatus::Idle);
+    assert_eq!(*obj.get().unwrap(), [1u8; 16]);
+}
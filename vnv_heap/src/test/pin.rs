@@ -0,0 +1,83 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::{vnv_persist_all, VNVError};
+
+use super::{get_test_heap, TestHeap};
+
+#[test]
+fn test_pin() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_pin", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let mut pinned = heap.allocate([1u8; 100]).unwrap();
+    pinned.unload().unwrap();
+    assert!(!pinned.is_resident());
+
+    pinned.pin().unwrap();
+    assert!(pinned.is_pinned());
+    assert!(pinned.is_resident());
+    assert_eq!(
+        heap.get_resident_usage().pinned_bytes,
+        TestHeap::get_object_layout_info::<[u8; 100]>().resident_size
+    );
+
+    // accessing other objects must not unload the pinned object
+    let mut objects = vec![];
+    for i in 0..20u8 {
+        let mut obj = heap.allocate([i; 100]).unwrap();
+        obj.get_mut().unwrap()[0] = i;
+        objects.push(obj);
+    }
+    for obj in objects.iter_mut() {
+        obj.get().unwrap();
+    }
+    assert!(pinned.is_resident());
+    assert_eq!(pinned.unload(), Err(VNVError::ObjectInUse));
+
+    // pinned objects stay pinned after persisting
+    unsafe { vnv_persist_all() };
+    assert!(pinned.is_pinned());
+    assert_eq!(*pinned.get().unwrap(), [1u8; 100]);
+
+    pinned.unpin();
+    assert!(!pinned.is_pinned());
+    assert_eq!(heap.get_resident_usage().pinned_bytes, 0);
+    pinned.unload().unwrap();
+}
+
+#[test]
+fn test_pin_exceeds_resident_buffer() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_pin_exceeds_resident_buffer", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let mut obj1 = heap.allocate([0u8; 300]).unwrap();
+    let mut obj2 = heap.allocate([0u8; 300]).unwrap();
+    let mut obj3 = heap.allocate([0u8; 300]).unwrap();
+
+    obj1.pin().unwrap();
+    obj2.pin().unwrap();
+    assert_eq!(obj3.pin(), Err(VNVError::PinnedBytesExhausted));
+    assert!(!obj3.is_pinned());
+
+    // pinned objects cannot be unloaded to make space
+    assert_eq!(obj3.get().err(), Some(VNVError::ResidentObjectsInUse));
+
+    obj2.unpin();
+    obj3.pin().unwrap();
+    assert!(obj3.get().is_ok());
+}
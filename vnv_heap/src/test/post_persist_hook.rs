@@ -0,0 +1,74 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crate::{vnv_persist_all, PersistSummary};
+
+use super::get_test_heap;
+
+static PERSISTS: AtomicUsize = AtomicUsize::new(0);
+static PERSISTED_OBJECTS: AtomicUsize = AtomicUsize::new(0);
+static DIRTY_OBJECTS: AtomicUsize = AtomicUsize::new(0);
+static PERSISTED_BYTES: AtomicUsize = AtomicUsize::new(0);
+static DURATION_TICKS: AtomicU64 = AtomicU64::new(0);
+
+fn log_persist(summary: &PersistSummary) {
+    PERSISTS.fetch_add(1, Ordering::SeqCst);
+    PERSISTED_OBJECTS.store(summary.persisted_objects, Ordering::SeqCst);
+    DIRTY_OBJECTS.store(summary.dirty_objects, Ordering::SeqCst);
+    PERSISTED_BYTES.store(summary.persisted_bytes, Ordering::SeqCst);
+    DURATION_TICKS.store(summary.duration_ticks.unwrap_or(u64::MAX), Ordering::SeqCst);
+}
+
+fn tick() -> u64 {
+    static TICKS: AtomicU64 = AtomicU64::new(0);
+    TICKS.fetch_add(5, Ordering::SeqCst)
+}
+
+#[test]
+fn test_post_persist_hook() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_post_persist_hook", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let mut obj1 = heap.allocate([1u8; 32]).unwrap();
+    let mut obj2 = heap.allocate([2u8; 16]).unwrap();
+    obj1.unload().unwrap();
+    obj1.get().unwrap();
+    obj2.get_mut().unwrap()[0] = 3;
+
+    heap.set_post_persist_hook(log_persist, Some(tick)).unwrap();
+    unsafe { vnv_persist_all() };
+
+    assert_eq!(PERSISTS.load(Ordering::SeqCst), 1);
+    assert_eq!(PERSISTED_OBJECTS.load(Ordering::SeqCst), 2);
+    assert_eq!(DIRTY_OBJECTS.load(Ordering::SeqCst), 1);
+    assert!(PERSISTED_BYTES.load(Ordering::SeqCst) > 16);
+    assert_eq!(DURATION_TICKS.load(Ordering::SeqCst), 5);
+
+    // without a clock, no duration is measured
+    heap.set_post_persist_hook(log_persist, None).unwrap();
+    unsafe { vnv_persist_all() };
+    assert_eq!(PERSISTS.load(Ordering::SeqCst), 2);
+    assert_eq!(DURATION_TICKS.load(Ordering::SeqCst), u64::MAX);
+
+    heap.clear_post_persist_hook().unwrap();
+    unsafe { vnv_persist_all() };
+    assert_eq!(PERSISTS.load(Ordering::SeqCst), 2);
+
+    assert_eq!(obj2.get().unwrap()[0], 3);
+}
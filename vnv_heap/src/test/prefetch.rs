@@ -0,0 +1,52 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::test::{get_instrumented_test_heap, StorageOperation};
+
+#[test]
+fn test_prefetch() {
+    static READS: AtomicUsize = AtomicUsize::new(0);
+    fn count_reads(op: StorageOperation) {
+        if matches!(op, StorageOperation::Read { .. }) {
+            READS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let mut buffer = [0u8; 2000];
+    let heap = get_instrumented_test_heap("test_prefetch", &mut buffer, count_reads);
+
+    let mut obj = heap.allocate([3u32; 16]).unwrap();
+    obj.unload().unwrap();
+
+    READS.store(0, Ordering::SeqCst);
+    obj.prefetch().unwrap();
+    assert!(obj.is_resident());
+    assert!(READS.load(Ordering::SeqCst) > 0);
+
+    // the object is not in use, so it can be accessed without reading it again
+    READS.store(0, Ordering::SeqCst);
+    obj.prefetch().unwrap();
+    assert_eq!(obj.try_get_resident().unwrap()[0], 3);
+    obj.get_mut().unwrap()[0] = 4;
+    assert_eq!(READS.load(Ordering::SeqCst), 0);
+
+    // prefetched objects can be unloaded again
+    obj.unload().unwrap();
+    assert!(!obj.is_resident());
+    assert_eq!(obj.get().unwrap()[0], 4);
+}
@@ -0,0 +1,66 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use core::alloc::Layout;
+
+use crate::{vnv_persist_all, VNVError};
+
+use super::get_test_heap;
+
+#[test]
+fn test_raw_object() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_raw_object", 4 * 4096, &mut buffer, 1200, |_, _| {});
+
+    // the object header breaks bigger alignments in storage (see `test_allocate_aligned`)
+    const ALIGN: usize = if cfg!(any(feature = "access_counters", feature = "object_checksums")) { 4 } else { 16 };
+
+    let _small = heap.allocate(1u8).unwrap();
+    let mut obj = heap.allocate_layout(Layout::from_size_align(300, ALIGN).unwrap()).unwrap();
+    assert_eq!(obj.len(), 300);
+    assert_eq!(obj.layout().align(), ALIGN);
+    assert!(matches!(obj.mark_dirty(0, 1), Err(VNVError::NotResident)));
+
+    let ptr = obj.get_ptr().unwrap();
+    assert_eq!(ptr as usize % ALIGN, 0);
+    assert!(obj.is_resident());
+    assert!(!obj.is_data_dirty());
+
+    let data = unsafe { core::slice::from_raw_parts_mut(ptr, obj.len()) };
+    assert!(data.iter().all(|x| *x == 0));
+
+    // the whole object is made dirty
+    let remaining_dirty_size = || heap.get_inner().borrow_mut().get_resident_object_manager().remaining_dirty_size;
+    let before = remaining_dirty_size();
+    obj.mark_dirty(10, 10).unwrap();
+    assert!(obj.is_data_dirty());
+    assert_eq!(before - remaining_dirty_size(), 300);
+    data[10..20].fill(42);
+
+    // the object can be persisted while it is accessed
+    unsafe { vnv_persist_all() };
+    assert!(obj.is_data_dirty());
+
+    obj.unload().unwrap();
+    assert!(!obj.is_resident());
+
+    let ptr = obj.get_ptr().unwrap();
+    let data = unsafe { core::slice::from_raw_parts(ptr, obj.len()) };
+    assert!(data[..10].iter().all(|x| *x == 0));
+    assert!(data[10..20].iter().all(|x| *x == 42));
+    assert!(data[20..].iter().all(|x| *x == 0));
+    obj.release();
+}
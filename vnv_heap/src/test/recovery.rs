@@ -0,0 +1,504 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    fs,
+    mem::{forget, size_of},
+};
+
+use crate::{
+    modules::{
+        allocator::LinkedListAllocatorModule,
+        persistent_storage::{test::get_test_storage, FilePersistentStorageModule, PersistentStorageModule},
+    },
+    vnv_persist_all, LinkFixup, PersistCommitStatus, VNVConfig, VNVError, VNVHeap, VNVLink, VNVLinkFixup, WriteBack,
+    RECOVERY_ROOT_COUNT, RELOCATION_LOG_COUNT,
+};
+
+use super::{get_test_heap, TestHeap};
+
+const STORAGE_SIZE: usize = 4 * 4096;
+
+/// Copy of the storage at the time of the simulated power failure
+const POWER_FAILURE_IMAGE: &str = "/tmp/test_recover_power_failure.image";
+
+fn recover_test_heap<'a>(
+    resident_buffer: &'a mut [u8],
+    storage: FilePersistentStorageModule,
+    dirty_size: usize,
+) -> Result<TestHeap<'a>, VNVError> {
+    recover_test_heap_with_status(resident_buffer, storage, dirty_size).map(|(heap, _)| heap)
+}
+
+fn recover_test_heap_with_status<'a>(
+    resident_buffer: &'a mut [u8],
+    storage: FilePersistentStorageModule,
+    dirty_size: usize,
+) -> Result<(TestHeap<'a>, PersistCommitStatus), VNVError> {
+    VNVHeap::recover_with_status(
+        resident_buffer,
+        storage,
+        LinkedListAllocatorModule::new(),
+        VNVConfig {
+            max_dirty_bytes: dirty_size,
+            write_back: WriteBack::Lazy,
+            persist_latency_budget: None,
+        },
+        |_, _| {},
+    )
+}
+
+/// Returns a new storage with the content of `image`
+fn load_image(test_name: &str, image: &[u8]) -> FilePersistentStorageModule {
+    let mut storage = get_test_storage(test_name, STORAGE_SIZE);
+    storage.write(0, image).unwrap();
+    storage
+}
+
+#[test]
+fn test_recover() {
+    {
+        let mut buffer = [0u8; 1000];
+        let heap = get_test_heap("test_recover_src", STORAGE_SIZE, &mut buffer, 1000, |_, _| {
+            // the power is cut right after persisting
+            fs::copy("/tmp/test_recover_src.tmp", POWER_FAILURE_IMAGE).unwrap();
+        });
+
+        let mut dirty = heap.allocate([1u32; 10]).unwrap();
+        dirty.flush().unwrap();
+        dirty.get_mut().unwrap()[0] = 2;
+        assert!(dirty.is_data_dirty());
+
+        let mut clean = heap.allocate([3u64; 20]).unwrap();
+        clean.unload().unwrap();
+
+        heap.set_root("dirty", &dirty.link()).unwrap();
+        heap.set_root(1, &clean.link()).unwrap();
+
+        unsafe { vnv_persist_all() };
+
+        // changes after the last persist are lost
+        dirty.get_mut().unwrap()[1] = 4;
+
+        // the objects have to outlive the heap they were allocated in
+        forget(dirty);
+        forget(clean);
+    }
+
+    let image = fs::read(POWER_FAILURE_IMAGE).unwrap();
+    let mut buffer = [0u8; 1000];
+    let heap = recover_test_heap(&mut buffer, load_image("test_recover_dest", &image), 1000).unwrap();
+
+    let dirty: VNVLink<[u32; 10]> = heap.get_root("dirty").unwrap().unwrap();
+    let clean: VNVLink<[u64; 20]> = heap.get_root(1).unwrap().unwrap();
+    assert!(heap.get_root::<[u64; 20]>("clean").unwrap().is_none());
+
+    let mut expected = [1u32; 10];
+    expected[0] = 2;
+    assert_eq!(unsafe { heap.with_link(&dirty, |obj| *obj.get().unwrap()) }, expected);
+    assert_eq!(unsafe { heap.with_link(&clean, |obj| *obj.get().unwrap()) }, [3u64; 20]);
+
+    // new allocations must not overlap with the recovered objects
+    let mut other = heap.allocate([u32::MAX; 100]).unwrap();
+    other.unload().unwrap();
+    assert_eq!(unsafe { heap.with_link(&dirty, |obj| *obj.get().unwrap()) }, expected);
+    assert_eq!(unsafe { heap.with_link(&clean, |obj| *obj.get().unwrap()) }, [3u64; 20]);
+    assert_eq!(*other.get().unwrap(), [u32::MAX; 100]);
+}
+
+#[test]
+fn test_recover_invalid() {
+    // storage without any heap
+    {
+        let mut buffer = [0u8; 1000];
+        let res = recover_test_heap(&mut buffer, get_test_storage("test_recover_invalid", STORAGE_SIZE), 1000);
+        assert!(matches!(res, Err(VNVError::CorruptedData)));
+    }
+
+    let image = {
+        let mut buffer = [0u8; 1000];
+        let heap = get_test_heap("test_recover_invalid_src", STORAGE_SIZE, &mut buffer, 1000, |_, _| {});
+        let mut obj = heap.allocate(10u32).unwrap();
+        obj.flush().unwrap();
+        heap.set_root("counter", &obj.link()).unwrap();
+        forget(obj);
+
+        // the storage file is deleted together with the heap
+        fs::read("/tmp/test_recover_invalid_src.tmp").unwrap()
+    };
+
+    // heap was configured differently
+    {
+        let mut buffer = [0u8; 1000];
+        let res = recover_test_heap(&mut buffer, load_image("test_recover_invalid", &image), 800);
+        assert!(matches!(res, Err(VNVError::Unsupported)));
+    }
+
+    // without persisting, only the synchronized state is recovered
+    let mut buffer = [0u8; 1000];
+    let heap = recover_test_heap(&mut buffer, load_image("test_recover_invalid", &image), 1000).unwrap();
+    let link: VNVLink<u32> = heap.get_root("counter").unwrap().unwrap();
+    assert_eq!(unsafe { heap.with_link(&link, |obj| *obj.get().unwrap()) }, 10);
+}
+
+#[test]
+fn test_roots() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_roots", STORAGE_SIZE, &mut buffer, 1000, |_, _| {});
+
+    let counter = heap.allocate(0u32).unwrap();
+    let other = heap.allocate(0u32).unwrap();
+
+    // names and ids are different keys
+    heap.set_root("counter", &counter.link()).unwrap();
+    heap.set_root(0, &other.link()).unwrap();
+    assert!(counter.is_linked_by(&heap.get_root("counter").unwrap().unwrap()));
+    assert!(other.is_linked_by(&heap.get_root(0).unwrap().unwrap()));
+
+    // the type is checked by its size
+    assert_eq!(heap.get_root::<u64>("counter").err(), Some(VNVError::Unsupported));
+
+    // roots with the same key are replaced
+    heap.set_root("counter", &other.link()).unwrap();
+    assert!(other.is_linked_by(&heap.get_root("counter").unwrap().unwrap()));
+
+    heap.remove_root("counter").unwrap();
+    assert!(heap.get_root::<u32>("counter").unwrap().is_none());
+    assert!(other.is_linked_by(&heap.get_root(0).unwrap().unwrap()));
+
+    for id in 1..RECOVERY_ROOT_COUNT as u32 {
+        heap.set_root(id, &counter.link()).unwrap();
+    }
+    assert_eq!(heap.set_root("counter", &counter.link()).err(), Some(VNVError::RootsExhausted));
+
+    // replacing a root still works if all roots are used
+    heap.set_root(0, &counter.link()).unwrap();
+    assert!(counter.is_linked_by(&heap.get_root(0).unwrap().unwrap()));
+}
+
+/// Copy of the storage after the persist of `test_recover_commit_status`
+const COMMITTED_IMAGE: &str = "/tmp/test_recover_commit_status.image";
+
+#[test]
+fn test_recover_commit_status() {
+    {
+        let mut buffer = [0u8; 1000];
+        let heap = get_test_heap("test_recover_commit_status_src", STORAGE_SIZE, &mut buffer, 1000, |_, _| {
+            fs::copy("/tmp/test_recover_commit_status_src.tmp", COMMITTED_IMAGE).unwrap();
+        });
+
+        let mut obj = heap.allocate([1u32; 10]).unwrap();
+        obj.flush().unwrap();
+        obj.get_mut().unwrap()[0] = 2;
+        heap.set_root("obj", &obj.link()).unwrap();
+
+        unsafe { vnv_persist_all() };
+        forget(obj);
+    }
+    let image = fs::read(COMMITTED_IMAGE).unwrap();
+
+    let read_obj = |heap: &TestHeap| {
+        let link: VNVLink<[u32; 10]> = heap.get_root("obj").unwrap().unwrap();
+        unsafe { heap.with_link(&link, |obj| *obj.get().unwrap()) }
+    };
+    let mut persisted = [1u32; 10];
+    persisted[0] = 2;
+
+    // the persist was completed
+    let (recovered_image, slice_offsets) = {
+        let mut buffer = [0u8; 1000];
+        let (heap, status) =
+            recover_test_heap_with_status(&mut buffer, load_image("test_recover_commit_status", &image), 1000).unwrap();
+        assert_eq!(status, PersistCommitStatus::Committed { sequence: 1 });
+        assert_eq!(read_obj(&heap), persisted);
+        let slice_offsets = heap.get_inner().borrow().get_slice_offsets();
+        (fs::read("/tmp/test_recover_commit_status.tmp").unwrap(), slice_offsets)
+    };
+    let slice_size = usize::from_ne_bytes(image[..size_of::<usize>()].try_into().unwrap());
+    let first_record_offset = STORAGE_SIZE - 2 * 24;
+    assert_eq!(slice_offsets[0], 0);
+
+    // the persisted state was already recovered
+    {
+        let mut buffer = [0u8; 1000];
+        let (heap, status) =
+            recover_test_heap_with_status(&mut buffer, load_image("test_recover_commit_status", &recovered_image), 1000).unwrap();
+        assert_eq!(status, PersistCommitStatus::Empty);
+        assert_eq!(read_obj(&heap), persisted);
+    }
+
+    // the power failed while the next persist wrote its state to the other slot (the end of the state is still missing)
+    {
+        let mut torn_image = image.clone();
+        let torn_slice = slice_offsets[1]..slice_offsets[1] + slice_size / 2;
+        torn_image[torn_slice].copy_from_slice(&image[..slice_size / 2]);
+
+        let mut buffer = [0u8; 1000];
+        let (heap, status) =
+            recover_test_heap_with_status(&mut buffer, load_image("test_recover_commit_status", &torn_image), 1000).unwrap();
+
+        // the state of the previous persist is still complete
+        assert_eq!(status, PersistCommitStatus::Committed { sequence: 1 });
+        assert_eq!(read_obj(&heap), persisted);
+    }
+
+    // the power failed while the first persist wrote its state (the end of the state is still missing)
+    {
+        let mut torn_image = image.clone();
+        torn_image[first_record_offset..first_record_offset + 24].fill(0);
+        torn_image[slice_size - 1] ^= 0xFF;
+
+        let mut buffer = [0u8; 1000];
+        let (heap, status) =
+            recover_test_heap_with_status(&mut buffer, load_image("test_recover_commit_status", &torn_image), 1000).unwrap();
+        assert_eq!(status, PersistCommitStatus::RolledBack);
+
+        // only the synchronized data is recovered
+        assert_eq!(read_obj(&heap), [1u32; 10]);
+    }
+
+    // the power failed while the commit record was written
+    {
+        let mut torn_image = image.clone();
+        torn_image[first_record_offset] ^= 0xFF;
+
+        let mut buffer = [0u8; 1000];
+        let (heap, status) =
+            recover_test_heap_with_status(&mut buffer, load_image("test_recover_commit_status", &torn_image), 1000).unwrap();
+        assert_eq!(status, PersistCommitStatus::RolledBack);
+        assert_eq!(read_obj(&heap), [1u32; 10]);
+    }
+}
+
+#[test]
+fn test_recover_commit_status_alternates() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_recover_commit_status_alternates", STORAGE_SIZE, &mut buffer, 1000, |_, _| {
+        fs::copy(
+            "/tmp/test_recover_commit_status_alternates.tmp",
+            "/tmp/test_recover_commit_status_alternates.image",
+        )
+        .unwrap();
+    });
+    let mut obj = heap.allocate(0u32).unwrap();
+    obj.flush().unwrap();
+    heap.set_root("counter", &obj.link()).unwrap();
+
+    for i in 1..=3 {
+        *obj.get_mut().unwrap() = i;
+        unsafe { vnv_persist_all() };
+    }
+    *obj.get_mut().unwrap() = 4;
+    unsafe { vnv_persist_all() };
+    forget(obj);
+
+    // the last persist is still the newest one if the record of the persist before it is damaged
+    let mut image = fs::read("/tmp/test_recover_commit_status_alternates.image").unwrap();
+    drop(heap);
+    let previous_record_offset = STORAGE_SIZE - 2 * 24;
+    image[previous_record_offset + 4] ^= 0xFF;
+
+    let mut buffer = [0u8; 1000];
+    let (heap, status) =
+        recover_test_heap_with_status(&mut buffer, load_image("test_recover_commit_status_alternates_dest", &image), 1000).unwrap();
+    assert_eq!(status, PersistCommitStatus::Committed { sequence: 4 });
+    let link: VNVLink<u32> = heap.get_root("counter").unwrap().unwrap();
+    assert_eq!(unsafe { heap.with_link(&link, |obj| *obj.get().unwrap()) }, 4);
+}
+
+#[test]
+fn test_recover_defragmented_root() {
+    {
+        let mut buffer = [0u8; 1000];
+        let heap = get_test_heap("test_recover_defragmented_root_src", STORAGE_SIZE, &mut buffer, 1000, |_, _| {
+            fs::copy("/tmp/test_recover_defragmented_root_src.tmp", "/tmp/test_recover_defragmented_root.image").unwrap();
+        });
+
+        let a = heap.allocate(0u32).unwrap();
+        let b = heap.allocate(0u32).unwrap();
+        let (low, mut high) = if a.get_alloc_id().offset < b.get_alloc_id().offset { (a, b) } else { (b, a) };
+        *high.get_mut().unwrap() = 42;
+        heap.set_root("counter", &high.link()).unwrap();
+        drop(low);
+
+        let old_offset = high.get_alloc_id().offset;
+        assert_eq!(heap.defragment(&mut [&mut high], 1000, || 0, |_, _| {}).unwrap(), 1);
+        assert!(high.get_alloc_id().offset < old_offset);
+        assert!(high.is_linked_by(&heap.get_root::<u32>("counter").unwrap().unwrap()));
+
+        unsafe { vnv_persist_all() };
+        forget(high);
+    }
+
+    let image = fs::read("/tmp/test_recover_defragmented_root.image").unwrap();
+    let mut buffer = [0u8; 1000];
+    let heap = recover_test_heap(&mut buffer, load_image("test_recover_defragmented_root_dest", &image), 1000).unwrap();
+    let link: VNVLink<u32> = heap.get_root("counter").unwrap().unwrap();
+    assert_eq!(unsafe { heap.with_link(&link, |obj| *obj.get().unwrap()) }, 42);
+}
+
+struct Holder {
+    target: VNVLink<[u32; 4]>,
+}
+
+impl VNVLinkFixup for Holder {
+    fn fixup_links(&mut self, fixup: &mut LinkFixup) {
+        fixup.rebind(&mut self.target);
+    }
+}
+
+#[test]
+fn test_recover_relocated_links() {
+    let expected = {
+        let mut buffer = [0u8; 1000];
+        let heap = get_test_heap("test_recover_relocated_links_src", STORAGE_SIZE, &mut buffer, 1000, |_, _| {});
+
+        let a = heap.allocate([1u32; 4]).unwrap();
+        let b = heap.allocate([2u32; 4]).unwrap();
+        let (low, mut high) = if a.get_alloc_id().offset < b.get_alloc_id().offset { (a, b) } else { (b, a) };
+        let expected = *high.get().unwrap();
+        let mut holder = heap.allocate(Holder { target: high.link() }).unwrap();
+        holder.flush().unwrap();
+        heap.set_root("holder", &holder.link()).unwrap();
+        drop(low);
+        unsafe { vnv_persist_all() };
+
+        let moved = heap
+            .defragment(&mut [&mut high], 1000, || 0, |_, new| holder.get_mut().unwrap().target = new)
+            .unwrap();
+        assert_eq!(moved, 1);
+        assert!(high.is_linked_by(&holder.get().unwrap().target));
+
+        // the power fails before the updated link is persisted or written back
+        heap.flush_storage().unwrap();
+        fs::copy("/tmp/test_recover_relocated_links_src.tmp", "/tmp/test_recover_relocated_links.image").unwrap();
+
+        forget(high);
+        forget(holder);
+        expected
+    };
+
+    let image = fs::read("/tmp/test_recover_relocated_links.image").unwrap();
+    let mut buffer = [0u8; 1000];
+    let heap = recover_test_heap(&mut buffer, load_image("test_recover_relocated_links_dest", &image), 1000).unwrap();
+
+    let holder: VNVLink<Holder> = heap.get_root("holder").unwrap().unwrap();
+    assert_eq!(unsafe { heap.fixup_links(&holder) }.unwrap(), 1);
+    assert_eq!(unsafe { heap.fixup_links(&holder) }.unwrap(), 0);
+
+    let target = unsafe { heap.with_link(&holder, |obj| obj.get().unwrap().target) };
+    assert_eq!(unsafe { heap.with_link(&target, |obj| *obj.get().unwrap()) }, expected);
+
+    // the log is not needed anymore once the fixed links are persisted
+    unsafe { vnv_persist_all() };
+    assert_eq!(unsafe { heap.fixup_links(&holder) }.unwrap(), 0);
+}
+
+struct Node {
+    next: Option<VNVLink<Node>>,
+    value: u32,
+}
+
+impl VNVLinkFixup for Node {
+    fn fixup_links(&mut self, fixup: &mut LinkFixup) {
+        if let Some(next) = &mut self.next {
+            fixup.follow(next);
+        }
+    }
+}
+
+#[test]
+fn test_recover_with_fixups() {
+    {
+        let mut buffer = [0u8; 1000];
+        let heap = get_test_heap("test_recover_with_fixups_src", STORAGE_SIZE, &mut buffer, 1000, |_, _| {});
+
+        let filler = heap.allocate(Node { next: None, value: 0 }).unwrap();
+        let mut tail = heap.allocate(Node { next: None, value: 3 }).unwrap();
+        let mut mid = heap.allocate(Node { next: Some(tail.link()), value: 2 }).unwrap();
+        let mut head = heap.allocate(Node { next: Some(mid.link()), value: 1 }).unwrap();
+        assert!(filler.get_alloc_id().offset < tail.get_alloc_id().offset);
+        mid.flush().unwrap();
+        head.flush().unwrap();
+        heap.set_root("head", &head.link()).unwrap();
+        drop(filler);
+        unsafe { vnv_persist_all() };
+
+        let moved = heap
+            .defragment(&mut [&mut tail], 1000, || 0, |_, new| mid.get_mut().unwrap().next = Some(new))
+            .unwrap();
+        assert_eq!(moved, 1);
+
+        // the power fails before the updated link of the middle node is persisted or written back
+        heap.flush_storage().unwrap();
+        fs::copy("/tmp/test_recover_with_fixups_src.tmp", "/tmp/test_recover_with_fixups.image").unwrap();
+
+        forget(tail);
+        forget(mid);
+        forget(head);
+    }
+
+    let image = fs::read("/tmp/test_recover_with_fixups.image").unwrap();
+    let mut buffer = [0u8; 1000];
+    let (heap, _) = unsafe {
+        TestHeap::recover_with_fixups(
+            &mut buffer,
+            load_image("test_recover_with_fixups_dest", &image),
+            LinkedListAllocatorModule::new(),
+            VNVConfig {
+                max_dirty_bytes: 1000,
+                write_back: WriteBack::Lazy,
+                persist_latency_budget: None,
+            },
+            |_, _| {},
+            |heap, fixup| {
+                let mut head = heap.get_root::<Node>("head").unwrap().unwrap();
+                fixup.follow(&mut head);
+            },
+        )
+    }
+    .unwrap();
+
+    // the links were rebound without calling fixup_links
+    let mut values = vec![];
+    let mut next = heap.get_root::<Node>("head").unwrap();
+    while let Some(link) = next {
+        assert_eq!(unsafe { heap.fixup_links(&link) }.unwrap(), 0);
+        let node = unsafe { heap.with_link(&link, |obj| obj.get().map(|node| (node.value, node.next))) }.unwrap();
+        values.push(node.0);
+        next = node.1;
+    }
+    assert_eq!(values, [1, 2, 3]);
+}
+
+#[test]
+fn test_defragment_relocation_log_full() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_defragment_relocation_log_full", STORAGE_SIZE, &mut buffer, 1000, |_, _| {});
+
+    let mut objs: Vec<_> = (0..2 * RELOCATION_LOG_COUNT as u32 + 2).map(|i| heap.allocate([i; 4]).unwrap()).collect();
+    objs.sort_by_key(|obj| obj.get_alloc_id().offset);
+    let mut objs: Vec<_> = objs.into_iter().skip(1).step_by(2).collect();
+
+    // only the moves that fit into the log are done until the next persist
+    let mut refs: Vec<_> = objs.iter_mut().collect();
+    assert_eq!(heap.defragment(&mut refs, 1000, || 0, |_, _| {}).unwrap(), RELOCATION_LOG_COUNT);
+    assert_eq!(heap.defragment(&mut refs, 1000, || 0, |_, _| {}).unwrap(), 0);
+
+    unsafe { vnv_persist_all() };
+    assert!(heap.defragment(&mut refs, 1000, || 0, |_, _| {}).unwrap() > 0);
+}
@@ -0,0 +1,83 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::{
+    modules::{
+        allocator::LinkedListAllocatorModule,
+        nonresident_allocator::NonResidentBuddyAllocatorModule,
+        object_management::DefaultObjectManagementModule,
+        persistent_storage::{test::get_test_storage, FilePersistentStorageModule, PartitionedStorageModule},
+    },
+    VNVConfig, VNVHeap, WriteBack,
+};
+
+const STORAGE_SIZE: usize = 4 * 4096 + 512;
+const HEAP_REGION_START: usize = 256;
+const HEAP_REGION_END: usize = HEAP_REGION_START + 4 * 4096;
+
+#[test]
+fn test_reserved_storage() {
+    let storage = PartitionedStorageModule::new(
+        get_test_storage("test_reserved_storage", STORAGE_SIZE),
+        HEAP_REGION_START..HEAP_REGION_END,
+    );
+
+    let mut buffer = [0u8; 1000];
+    let heap: VNVHeap<
+        LinkedListAllocatorModule,
+        NonResidentBuddyAllocatorModule<16>,
+        DefaultObjectManagementModule,
+        PartitionedStorageModule<FilePersistentStorageModule>,
+    > = VNVHeap::new(
+        &mut buffer,
+        storage,
+        LinkedListAllocatorModule::new(),
+        VNVConfig {
+            max_dirty_bytes: 500,
+            write_back: WriteBack::Lazy,
+            persist_latency_budget: None,
+        },
+        |_, _| {},
+    )
+    .unwrap();
+
+    let mut reserved = heap.storage();
+    assert_eq!(reserved.get_heap_region().unwrap(), HEAP_REGION_START..HEAP_REGION_END);
+    assert!(reserved.is_reserved(0, HEAP_REGION_START).unwrap());
+    assert!(reserved.is_reserved(HEAP_REGION_END, STORAGE_SIZE - HEAP_REGION_END).unwrap());
+    assert!(!reserved.is_reserved(HEAP_REGION_START - 1, 2).unwrap());
+    assert!(!reserved.is_reserved(HEAP_REGION_END, STORAGE_SIZE - HEAP_REGION_END + 1).unwrap());
+
+    reserved.write(0, &[1u8; HEAP_REGION_START]).unwrap();
+    reserved.write(HEAP_REGION_END, &[2u8; 256]).unwrap();
+
+    // use the heap, so that the whole heap region is written to
+    let mut objs: Vec<_> = (0..50).map(|i| heap.allocate([i as u8; 128]).unwrap()).collect();
+    for obj in objs.iter_mut() {
+        obj.unload().unwrap();
+    }
+    for (i, obj) in objs.iter_mut().enumerate() {
+        assert_eq!(*obj.get().unwrap(), [i as u8; 128]);
+    }
+
+    let mut data = [0u8; 256];
+    let mut reserved = heap.storage();
+    reserved.read(0, &mut data).unwrap();
+    assert_eq!(data, [1u8; 256]);
+    reserved.read(HEAP_REGION_END, &mut data).unwrap();
+    assert_eq!(data, [2u8; 256]);
+}
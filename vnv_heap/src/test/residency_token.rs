@@ -0,0 +1,57 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::get_test_heap;
+
+#[test]
+fn test_residency_token_reload() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_residency_token_reload", 4 * 4096, &mut buffer, 1200, |_, _| {});
+
+    let mut obj = heap.allocate::<[u8; 100]>([1; 100]).unwrap();
+    for i in 0..10u8 {
+        obj.get_mut().unwrap()[0] = i;
+        assert_eq!(obj.get().unwrap()[0], i);
+    }
+
+    // the object is loaded to another location, the token has to be invalidated
+    obj.unload().unwrap();
+    let mut blocker = heap.allocate::<[u8; 100]>([2; 100]).unwrap();
+    drop(blocker.get().unwrap());
+
+    assert_eq!(obj.get().unwrap()[0], 9);
+    assert_eq!(obj.get().unwrap()[1..], [1; 99]);
+    assert_eq!(*blocker.get().unwrap(), [2; 100]);
+}
+
+#[test]
+fn test_residency_token_reused_location() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_residency_token_reused_location", 4 * 4096, &mut buffer, 1200, |_, _| {});
+
+    let mut obj1 = heap.allocate::<[u8; 100]>([1; 100]).unwrap();
+    let mut obj2 = heap.allocate::<[u8; 100]>([2; 100]).unwrap();
+    drop(obj1.get().unwrap());
+
+    // obj2 is probably made resident at the old location of obj1
+    obj1.unload().unwrap();
+    assert_eq!(*obj2.get().unwrap(), [2; 100]);
+
+    assert_eq!(*obj1.get().unwrap(), [1; 100]);
+    assert_eq!(*obj2.get().unwrap(), [2; 100]);
+    assert!(obj1.is_resident());
+}
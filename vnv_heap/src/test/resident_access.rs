@@ -0,0 +1,90 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{
+    test::{get_instrumented_test_heap, StorageOperation},
+    VNVError,
+};
+
+#[test]
+fn test_try_get_resident() {
+    static OPERATIONS: AtomicUsize = AtomicUsize::new(0);
+    fn count_operations(_: StorageOperation) {
+        OPERATIONS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    let mut buffer = [0u8; 2000];
+    let heap = get_instrumented_test_heap("test_try_get_resident", &mut buffer, count_operations);
+
+    let mut obj = heap.allocate([1u32; 16]).unwrap();
+    obj.unload().unwrap();
+
+    // the object is not loaded
+    OPERATIONS.store(0, Ordering::SeqCst);
+    assert_eq!(obj.try_get_resident().err(), Some(VNVError::NotResident));
+    assert_eq!(obj.try_get_mut_resident().err(), Some(VNVError::NotResident));
+    assert!(!obj.is_resident());
+    assert_eq!(OPERATIONS.load(Ordering::SeqCst), 0);
+
+    assert_eq!(obj.get().unwrap()[0], 1);
+    assert!(!obj.is_data_dirty());
+
+    OPERATIONS.store(0, Ordering::SeqCst);
+    assert_eq!(obj.try_get_resident().unwrap()[0], 1);
+    obj.try_get_mut_resident().unwrap()[0] = 2;
+    assert_eq!(obj.try_get_resident().unwrap()[0], 2);
+    assert_eq!(OPERATIONS.load(Ordering::SeqCst), 0);
+    assert!(obj.is_data_dirty());
+}
+
+#[test]
+fn test_try_get_mut_resident_dirty_budget() {
+    static OPERATIONS: AtomicUsize = AtomicUsize::new(0);
+    fn count_operations(_: StorageOperation) {
+        OPERATIONS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    let mut buffer = [0u8; 2000];
+    let heap = get_instrumented_test_heap("test_try_get_mut_resident_dirty_budget", &mut buffer, count_operations);
+
+    let mut clean = heap.allocate([0u8; 200]).unwrap();
+    clean.flush().unwrap();
+
+    // new objects are dirty, so they use up the dirty budget
+    let remaining_dirty_size = || heap.get_inner().borrow_mut().get_resident_object_manager().remaining_dirty_size;
+    let mut others = vec![];
+    while remaining_dirty_size() >= 200 {
+        others.push(heap.allocate([1u8; 100]).unwrap());
+    }
+    assert!(clean.is_resident());
+
+    // making the object dirty would write back other objects
+    OPERATIONS.store(0, Ordering::SeqCst);
+    assert_eq!(clean.try_get_mut_resident().err(), Some(VNVError::DirtyBudgetExhausted));
+    assert_eq!(clean.try_get_resident().unwrap()[0], 0);
+    assert_eq!(OPERATIONS.load(Ordering::SeqCst), 0);
+    assert!(!clean.is_data_dirty());
+
+    clean.get_mut().unwrap()[0] = 1;
+    assert!(OPERATIONS.load(Ordering::SeqCst) > 0);
+
+    // dirty objects can be modified without changing the dirty budget
+    OPERATIONS.store(0, Ordering::SeqCst);
+    clean.try_get_mut_resident().unwrap()[1] = 2;
+    assert_eq!(OPERATIONS.load(Ordering::SeqCst), 0);
+}
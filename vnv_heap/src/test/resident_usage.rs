@@ -0,0 +1,139 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::mem::size_of;
+
+use crate::vnv_heap::ResidentExhaustionReason;
+
+use super::{get_test_heap, TestHeap};
+
+#[test]
+fn test_resident_usage() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_resident_usage", 4 * 4096, &mut buffer, 2000, |_, _| {});
+
+    let usage = heap.get_resident_usage();
+    assert_eq!(usage.resident_objects, 0);
+    assert_eq!(usage.user_bytes, 0);
+    assert_eq!(usage.metadata_bytes, 0);
+
+    let _obj1 = heap.allocate([0u8; 100]).unwrap();
+    let _obj2 = heap.allocate(0u32).unwrap();
+
+    let info1 = TestHeap::get_object_layout_info::<[u8; 100]>();
+    let info2 = TestHeap::get_object_layout_info::<u32>();
+    assert_eq!(info1.data_size, 100);
+    assert_eq!(info1.resident_size, info1.data_size + info1.resident_overhead);
+
+    let usage = heap.get_resident_usage();
+    assert_eq!(usage.resident_objects, 2);
+    assert_eq!(usage.user_bytes, info1.data_size + info2.data_size);
+    assert_eq!(usage.metadata_bytes, info1.resident_overhead + info2.resident_overhead);
+    assert!(usage.user_bytes + usage.metadata_bytes <= 2000);
+    assert!(heap.get_last_resident_exhaustion().is_none());
+}
+
+#[test]
+fn test_resident_exhaustion_metadata_overhead() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_resident_exhaustion_metadata_overhead", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let mut objects = vec![];
+    for i in 0..64u8 {
+        objects.push(heap.allocate(i).unwrap());
+    }
+
+    let mut big = heap.allocate([0u8; 200]).unwrap();
+
+    {
+        // keep all small objects in use, so none of them can be unloaded
+        let mut refs = vec![];
+        for obj in objects.iter_mut() {
+            match obj.get() {
+                Ok(obj_ref) => refs.push(obj_ref),
+                Err(_) => break,
+            }
+        }
+
+        assert!(heap.get_resident_usage().is_metadata_dominated());
+        assert!(big.get().is_err());
+        assert_eq!(
+            heap.get_last_resident_exhaustion(),
+            Some(ResidentExhaustionReason::MetadataOverhead)
+        );
+    }
+
+    assert!(big.get().is_ok());
+}
+
+#[test]
+fn test_non_resident_layout_info() {
+    let layout_info = TestHeap::get_layout_info();
+    assert_eq!(layout_info.non_resident_min_allocation_size, size_of::<usize>());
+
+    let info = TestHeap::get_object_layout_info::<[u8; 100]>();
+    assert_eq!(info.non_resident_size, 100 + layout_info.non_resident_object_header);
+    assert_eq!(info.non_resident_allocated_size, 128);
+    assert_eq!(info.non_resident_overhead, 28);
+
+    let info = TestHeap::get_object_layout_info::<u8>();
+    assert_eq!(info.non_resident_allocated_size, size_of::<usize>().max((1 + layout_info.non_resident_object_header).next_power_of_two()));
+
+    // buddy allocator places two objects of the same size class next to each other
+    // (the recovery area changes the buddy blocks at the end of the storage)
+    #[cfg(not(feature = "recovery"))]
+    {
+        let mut buffer = [0u8; 2000];
+        let heap = get_test_heap("test_non_resident_layout_info", 4 * 4096, &mut buffer, 2000, |_, _| {});
+        let obj1 = heap.allocate([0u8; 100]).unwrap();
+        let obj2 = heap.allocate([0u8; 100]).unwrap();
+        assert_eq!(
+            obj1.get_alloc_id().offset.abs_diff(obj2.get_alloc_id().offset),
+            TestHeap::get_object_layout_info::<[u8; 100]>().non_resident_allocated_size
+        );
+    }
+}
+
+#[test]
+fn test_required_buffer_size() {
+    const BUFFER_SIZE: usize = TestHeap::required_buffer_size::<[u8; 100]>(3);
+    const DIRTY_BUDGET: usize = TestHeap::required_dirty_budget::<[u8; 100]>(3);
+
+    let mut buffer = [0u8; BUFFER_SIZE];
+    let heap = get_test_heap("test_required_buffer_size", 4 * 4096, &mut buffer, DIRTY_BUDGET, |_, _| {});
+
+    let mut obj1 = heap.allocate([1u8; 100]).unwrap();
+    let mut obj2 = heap.allocate([2u8; 100]).unwrap();
+    let mut obj3 = heap.allocate([3u8; 100]).unwrap();
+    let mut obj4 = heap.allocate([4u8; 100]).unwrap();
+
+    {
+        // all three objects fit into the buffer and the dirty budget at the same time
+        let mut ref1 = obj1.get_mut().unwrap();
+        let mut ref2 = obj2.get_mut().unwrap();
+        let mut ref3 = obj3.get_mut().unwrap();
+        ref1[0] = 10;
+        ref2[0] = 20;
+        ref3[0] = 30;
+
+        // but there is no space for a fourth one
+        assert!(obj4.get().is_err());
+    }
+
+    assert_eq!(obj1.get().unwrap()[0], 10);
+    assert_eq!(obj4.get().unwrap()[0], 4);
+}
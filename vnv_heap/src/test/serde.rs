@@ -0,0 +1,65 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use serde::{Deserialize, Serialize};
+
+use crate::VNVError;
+
+use super::get_test_heap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Config {
+    id: u32,
+    name: [u8; 4],
+    thresholds: [i16; 3],
+    enabled: bool,
+}
+
+#[test]
+fn test_serde_roundtrip() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_serde_roundtrip", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let received = br#"{"id":7,"name":[1,2,3,4],"thresholds":[-5,0,12],"enabled":true}"#;
+    let mut obj = heap.allocate_from_deserialize::<Config>(received).unwrap();
+    obj.unload().unwrap();
+
+    let expected = Config { id: 7, name: [1, 2, 3, 4], thresholds: [-5, 0, 12], enabled: true };
+    assert_eq!(*obj.get().unwrap(), expected);
+
+    obj.with_mut(|config| config.enabled = false).unwrap();
+    obj.unload().unwrap();
+
+    let mut serialized = vec![];
+    obj.serialize_into(&mut serialized).unwrap();
+    assert_eq!(serialized, br#"{"id":7,"name":[1,2,3,4],"thresholds":[-5,0,12],"enabled":false}"#);
+}
+
+#[test]
+fn test_serde_invalid_data() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_serde_invalid_data", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let res = heap.allocate_from_deserialize::<Config>(br#"{"id":7,"enabled":true}"#);
+    assert_eq!(res.err(), Some(VNVError::SerializationFailed));
+
+    let res = heap.allocate_from_deserialize::<u32>(b"not json");
+    assert_eq!(res.err(), Some(VNVError::SerializationFailed));
+
+    // nothing was allocated
+    assert!(heap.allocate([0u8; 256]).is_ok());
+}
@@ -0,0 +1,139 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::mem::forget;
+
+use crate::{
+    modules::{
+        allocator::LinkedListAllocatorModule,
+        persistent_storage::{test::get_test_storage, PersistentStorageModule},
+    },
+    VNVConfig, VNVError, VNVHeap, VNVLink, WriteBack, SNAPSHOT_VERSION,
+};
+
+use super::{get_test_heap, TestHeap};
+
+const STORAGE_SIZE: usize = 4 * 4096;
+
+/// Enough space for the header, the allocator state and the storage content
+const SNAPSHOT_SIZE: usize = STORAGE_SIZE + 4096;
+
+fn restore_test_heap<'a, S: PersistentStorageModule>(
+    test_name: &str,
+    resident_buffer: &'a mut [u8],
+    dirty_size: usize,
+    source: &mut S,
+) -> Result<TestHeap<'a>, VNVError> {
+    VNVHeap::restore_from(
+        resident_buffer,
+        get_test_storage(test_name, STORAGE_SIZE),
+        LinkedListAllocatorModule::new(),
+        VNVConfig {
+            max_dirty_bytes: dirty_size,
+            write_back: WriteBack::Lazy,
+            persist_latency_budget: None,
+        },
+        |_, _| {},
+        source,
+    )
+}
+
+#[test]
+fn test_snapshot_restore() {
+    let mut snapshot = get_test_storage("test_snapshot_restore_snapshot", SNAPSHOT_SIZE);
+    let links: Vec<VNVLink<[u32; 10]>> = {
+        let mut buffer = [0u8; 1000];
+        let mut heap = get_test_heap("test_snapshot_restore_src", STORAGE_SIZE, &mut buffer, 1000, |_, _| {});
+
+        let mut links = vec![];
+        for i in 0..5 {
+            let mut obj = heap.allocate([i; 10]).unwrap();
+            obj.get_mut().unwrap()[0] = i + 100;
+            links.push(obj.link());
+
+            // the objects have to outlive the heap they were allocated in
+            forget(obj);
+        }
+
+        // dirty objects are written back before the snapshot is taken
+        heap.snapshot_to(&mut snapshot).unwrap();
+        links
+    };
+
+    let mut buffer = [0u8; 1000];
+    let heap = restore_test_heap("test_snapshot_restore_dest", &mut buffer, 1000, &mut snapshot).unwrap();
+
+    // new allocations must not overlap with the restored objects
+    let mut other = heap.allocate([u32::MAX; 10]).unwrap();
+    other.unload().unwrap();
+
+    for (i, link) in links.iter().enumerate() {
+        let i = i as u32;
+        let mut expected = [i; 10];
+        expected[0] = i + 100;
+
+        let data = unsafe { heap.with_link(link, |obj| *obj.get().unwrap()) };
+        assert_eq!(data, expected);
+    }
+    assert_eq!(*other.get().unwrap(), [u32::MAX; 10]);
+}
+
+#[test]
+fn test_snapshot_target_too_small() {
+    let mut buffer = [0u8; 1000];
+    let mut heap = get_test_heap("test_snapshot_target_too_small", STORAGE_SIZE, &mut buffer, 1000, |_, _| {});
+
+    let mut snapshot = get_test_storage("test_snapshot_target_too_small_snapshot", STORAGE_SIZE);
+    assert!(matches!(heap.snapshot_to(&mut snapshot), Err(VNVError::NonResidentSpaceExhausted)));
+}
+
+#[test]
+fn test_snapshot_invalid_header() {
+    let mut snapshot = get_test_storage("test_snapshot_invalid_header_snapshot", SNAPSHOT_SIZE);
+
+    // no snapshot was written yet
+    snapshot.write(0, &[0u8; 64]).unwrap();
+    {
+        let mut buffer = [0u8; 1000];
+        let res = restore_test_heap("test_snapshot_invalid_header", &mut buffer, 1000, &mut snapshot);
+        assert!(matches!(res, Err(VNVError::CorruptedData)));
+    }
+
+    {
+        let mut buffer = [0u8; 1000];
+        let mut heap = get_test_heap("test_snapshot_invalid_header_src", STORAGE_SIZE, &mut buffer, 1000, |_, _| {});
+        heap.snapshot_to(&mut snapshot).unwrap();
+    }
+
+    // heap was configured differently
+    {
+        let mut buffer = [0u8; 1000];
+        let res = restore_test_heap("test_snapshot_invalid_header", &mut buffer, 800, &mut snapshot);
+        assert!(matches!(res, Err(VNVError::Unsupported)));
+    }
+
+    // snapshot of another version
+    let mut version = [0u8; 4];
+    snapshot.read(8, &mut version).unwrap();
+    assert_eq!(u32::from_le_bytes(version), SNAPSHOT_VERSION);
+    snapshot.write(8, &(SNAPSHOT_VERSION + 1).to_le_bytes()).unwrap();
+    {
+        let mut buffer = [0u8; 1000];
+        let res = restore_test_heap("test_snapshot_invalid_header", &mut buffer, 1000, &mut snapshot);
+        assert!(matches!(res, Err(VNVError::Unsupported)));
+    }
+}
@@ -0,0 +1,123 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::SplittableObject;
+
+use super::get_test_heap;
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct Data {
+    hot: u16,
+    cold: [u32; 100],
+}
+
+unsafe impl SplittableObject<u16, [u32; 100]> for Data {}
+
+#[test]
+fn test_split_object() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_split_object", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let mut obj = heap.allocate(Data { hot: 1, cold: [2; 100] }).unwrap();
+    {
+        let mut obj_ref = obj.get_mut().unwrap();
+        obj_ref.hot = 3;
+        obj_ref.cold[99] = 4;
+    }
+    let offset = obj.get_alloc_id().offset;
+
+    let mut split = obj.split::<u16, [u32; 100]>().unwrap();
+    {
+        let (mut hot, mut cold) = split.parts();
+        assert!(!hot.is_resident());
+        assert!(!cold.is_resident());
+
+        // only the accessed part is loaded
+        assert_eq!(*hot.get().unwrap(), 3);
+        assert!(hot.is_resident());
+        assert!(!cold.is_resident());
+
+        *hot.get_mut().unwrap() += 1;
+        assert!(hot.is_data_dirty());
+
+        {
+            let cold_ref = cold.get().unwrap();
+            assert_eq!(cold_ref[0], 2);
+            assert_eq!(cold_ref[99], 4);
+        }
+
+        cold.unload().unwrap();
+        assert!(!cold.is_resident());
+        assert!(hot.is_resident());
+
+        // both parts can be accessed at the same time
+        let mut hot_ref = hot.get_mut().unwrap();
+        let mut cold_ref = cold.get_mut().unwrap();
+        *hot_ref += 1;
+        cold_ref[0] = 5;
+    }
+
+    split.first().unload().unwrap();
+    split.second().unload().unwrap();
+    assert_eq!(*split.first().get().unwrap(), 5);
+    assert_eq!(split.second().get().unwrap()[0], 5);
+    drop(split);
+
+    // storage region of the original object is available again
+    let obj = heap.allocate(Data { hot: 0, cold: [0; 100] }).unwrap();
+    assert_eq!(obj.get_alloc_id().offset, offset);
+}
+
+static DROP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+struct DropCounter(u32);
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        DROP_COUNTER.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[repr(C)]
+struct DropData {
+    first: DropCounter,
+    second: DropCounter,
+}
+
+unsafe impl SplittableObject<DropCounter, DropCounter> for DropData {}
+
+#[test]
+fn test_split_object_no_drop() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_split_object_no_drop", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let obj = heap
+        .allocate(DropData {
+            first: DropCounter(1),
+            second: DropCounter(2),
+        })
+        .unwrap();
+
+    // neither allocating nor splitting drops any of the parts
+    let mut split = obj.split::<DropCounter, DropCounter>().unwrap();
+    assert_eq!(split.second().get().unwrap().0, 2);
+    assert_eq!(split.first().get().unwrap().0, 1);
+    assert_eq!(DROP_COUNTER.load(Ordering::SeqCst), 0);
+}
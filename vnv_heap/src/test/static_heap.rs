@@ -0,0 +1,82 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::{
+    modules::{
+        allocator::LinkedListAllocatorModule, persistent_storage::test::get_test_storage,
+        persistent_storage::FilePersistentStorageModule,
+    },
+    VNVConfig, VNVError, WriteBack,
+};
+
+crate::static_vnv_heap!(static TEST_HEAP: 2000, FilePersistentStorageModule);
+
+fn get_config() -> VNVConfig {
+    VNVConfig {
+        max_dirty_bytes: 500,
+        write_back: WriteBack::Lazy,
+        persist_latency_budget: None,
+    }
+}
+
+#[test]
+fn test_static_heap() {
+    // the heap is only used by this test
+    assert!(unsafe { TEST_HEAP.get() }.is_none());
+
+    let heap = unsafe {
+        TEST_HEAP.init(
+            get_test_storage("test_static_heap", 4 * 4096),
+            LinkedListAllocatorModule::new(),
+            get_config(),
+            |_, _| {},
+        )
+    }
+    .unwrap();
+
+    {
+        let mut obj = heap.allocate([1u32; 10]).unwrap();
+        {
+            let mut data = obj.get_mut().unwrap();
+            data[3] = 7;
+        }
+
+        // the heap can be accessed from anywhere
+        let mut other = unsafe { TEST_HEAP.get() }.unwrap().allocate(5u64).unwrap();
+        assert_eq!(*other.get().unwrap(), 5);
+
+        let data = obj.get().unwrap();
+        assert_eq!(data[3], 7);
+        assert_eq!(data[4], 1);
+    }
+
+    // the heap cannot be initialized twice
+    assert!(matches!(
+        unsafe {
+            TEST_HEAP.init(
+                get_test_storage("test_static_heap_2", 4 * 4096),
+                LinkedListAllocatorModule::new(),
+                get_config(),
+                |_, _| {},
+            )
+        },
+        Err(VNVError::Unsupported)
+    ));
+
+    unsafe { TEST_HEAP.deinit() };
+    assert!(unsafe { TEST_HEAP.get() }.is_none());
+}
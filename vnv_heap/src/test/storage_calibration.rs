@@ -0,0 +1,92 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{
+    modules::allocator::LinkedListAllocatorModule,
+    test::{InstrumentedStorageModule, InstrumentedTestHeap, StorageOperation},
+    PersistLatencyBudget, StorageThroughput, StorageTiming, VNVConfig, VNVError, VNVHeap, WriteBack,
+};
+
+static TIME: AtomicU64 = AtomicU64::new(0);
+
+fn get_time() -> u64 {
+    TIME.load(Ordering::SeqCst)
+}
+
+/// Advances `TIME` as if reading took `5µs + 0.25µs/byte`, writing `10µs + 0.5µs/byte` and flushing `20µs`
+fn advance_time(op: StorageOperation) {
+    let duration = match op {
+        StorageOperation::Read { len, .. } => 5 + len as u64 / 4,
+        StorageOperation::Write { len, .. } => 10 + len as u64 / 2,
+        StorageOperation::Flush => 20,
+    };
+    TIME.fetch_add(duration, Ordering::SeqCst);
+}
+
+#[test]
+fn test_storage_calibration() {
+    let mut buffer = [0u8; 2000];
+    let heap: InstrumentedTestHeap = VNVHeap::new(
+        &mut buffer,
+        InstrumentedStorageModule::new("test_storage_calibration", 4 * 4096, advance_time),
+        LinkedListAllocatorModule::new(),
+        VNVConfig {
+            max_dirty_bytes: 1500,
+            write_back: WriteBack::Lazy,
+            persist_latency_budget: Some(PersistLatencyBudget {
+                max_latency_us: 600,
+                write_throughput: StorageThroughput {
+                    fixed_latency_us: 100,
+                    bytes_per_ms: 1000,
+                },
+            }),
+        },
+        |_, _| {},
+    )
+    .unwrap();
+
+    assert_eq!(heap.get_max_dirty_bytes(), 500);
+    assert_eq!(heap.get_storage_timing(), None);
+    assert_eq!(heap.calibrate_storage(0, get_time), Err(VNVError::Unsupported));
+
+    let timing = heap.calibrate_storage(3, get_time).unwrap();
+    assert_eq!(
+        timing,
+        StorageTiming {
+            read: StorageThroughput {
+                fixed_latency_us: 5,
+                bytes_per_ms: 4000,
+            },
+            write: StorageThroughput {
+                fixed_latency_us: 30,
+                bytes_per_ms: 2000,
+            },
+        }
+    );
+    assert_eq!(heap.get_storage_timing(), Some(timing));
+
+    // the persist latency budget uses the measured write throughput now
+    assert_eq!(heap.get_persist_latency_budget().unwrap().write_throughput, timing.write);
+    assert_eq!(heap.get_max_dirty_bytes(), 1140);
+
+    // the estimate grows with the dirty bytes
+    let empty_estimate = heap.estimate_persist_latency_us().unwrap();
+    let _obj = heap.allocate([0u8; 200]).unwrap();
+    assert!(heap.estimate_persist_latency_us().unwrap() >= empty_estimate + 100);
+}
@@ -0,0 +1,65 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::VNVError;
+
+use super::get_test_heap;
+
+#[test]
+fn test_storage_slice() {
+    const LEN: usize = 16 * 1024;
+
+    // the slice is much bigger than the resident buffer
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_storage_slice", 16 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let mut table = heap.allocate_from_iter(LEN, (0..LEN).map(|i| (i % 251) as u8)).unwrap();
+    assert_eq!(table.len(), LEN);
+    assert_eq!(heap.count_resident_objects::<u8>(), 0);
+
+    let mut chunk = [0u8; 300];
+    table.read(LEN - 300, &mut chunk).unwrap();
+    for (i, item) in chunk.iter().enumerate() {
+        assert_eq!(*item, ((LEN - 300 + i) % 251) as u8);
+    }
+
+    table.write(1000, &[7; 10]).unwrap();
+    assert_eq!(table.get(999).unwrap(), (999 % 251) as u8);
+    assert_eq!(table.get(1000).unwrap(), 7);
+    assert_eq!(table.get(1009).unwrap(), 7);
+    assert_eq!(table.get(1010).unwrap(), (1010 % 251) as u8);
+}
+
+#[test]
+fn test_storage_slice_big_elements() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_storage_slice_big_elements", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let table = heap.allocate_from_iter(5, (0..5u8).map(|i| [i; 300])).unwrap();
+    for i in 0..5u8 {
+        assert_eq!(table.get(i as usize).unwrap(), [i; 300]);
+    }
+}
+
+#[test]
+fn test_storage_slice_too_big() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_storage_slice_too_big", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let res = heap.allocate_from_iter(8 * 4096, core::iter::repeat(0u8));
+    assert_eq!(res.err(), Some(VNVError::NonResidentSpaceExhausted));
+}
@@ -0,0 +1,60 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{mem::forget, thread};
+
+use crate::{modules::lock::SpinLockModule, SyncVNVHeap, VNVLink};
+
+use super::get_test_heap;
+
+const THREAD_COUNT: usize = 4;
+const INCREMENTS: u32 = 200;
+
+#[test]
+fn test_sync_heap() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_sync_heap", 4 * 4096, &mut buffer, 200, |_, _| {});
+    let heap = SyncVNVHeap::new(heap, SpinLockModule::new());
+
+    let counter: VNVLink<u32> = heap.with(|heap| {
+        let obj = heap.allocate(0u32).unwrap();
+        let link = obj.link();
+        forget(obj);
+        link
+    });
+
+    thread::scope(|scope| {
+        for i in 0..THREAD_COUNT {
+            let heap = &heap;
+            let counter = &counter;
+            scope.spawn(move || {
+                for _ in 0..INCREMENTS {
+                    heap.with(|heap| {
+                        // allocate temporary objects so that the threads also compete for the resident buffer
+                        let tmp = heap.allocate([i as u8; 50]).unwrap();
+                        unsafe { heap.with_link(counter, |obj| obj.with_mut(|val| *val += 1)) }.unwrap();
+                        drop(tmp);
+                    });
+                }
+            });
+        }
+    });
+
+    let heap = heap.into_inner();
+    let val = unsafe { heap.with_link(&counter, |obj| obj.with(|val| *val)) }.unwrap();
+    assert_eq!(val, THREAD_COUNT as u32 * INCREMENTS);
+}
@@ -0,0 +1,168 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::VNVArray;
+
+use super::{get_instrumented_test_heap, get_test_heap, StorageOperation};
+
+#[test]
+fn test_array_for_each_resident() {
+    // the dirty budget is too small to make the whole array dirty at once
+    let mut buffer = [0u8; 3000];
+    let heap = get_test_heap("test_array_for_each_resident", 4 * 4096, &mut buffer, 700, |_, _| {});
+
+    // partial dirtiness tracking is not available via `allocate_pd_array` yet
+    let identifier = unsafe { heap.get_inner().borrow_mut().allocate([0u32; 256], true) }.unwrap();
+    let mut arr = VNVArray::new(heap.get_inner(), identifier);
+    arr.unload().unwrap();
+    assert!(arr.get_mut().unwrap().get_range_mut(0..256).is_err());
+
+    let mut visited = 0;
+    arr.for_each_resident(16, |elems| {
+        assert!(elems.len() <= 16);
+        for elem in elems.iter_mut() {
+            *elem = visited;
+            visited += 1;
+        }
+    })
+    .unwrap();
+    assert_eq!(visited, 256);
+
+    arr.unload().unwrap();
+    let data = arr.get().unwrap();
+    for (i, elem) in data.iter().enumerate() {
+        assert_eq!(*elem, i as u32);
+    }
+}
+
+#[test]
+fn test_array_iter_chunks() {
+    static READS: AtomicUsize = AtomicUsize::new(0);
+    fn count_reads(op: StorageOperation) {
+        if matches!(op, StorageOperation::Read { .. }) {
+            READS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let mut buffer = [0u8; 3000];
+    let heap = get_instrumented_test_heap("test_array_iter_chunks", &mut buffer, count_reads);
+
+    let identifier = unsafe { heap.get_inner().borrow_mut().allocate(core::array::from_fn::<u32, 500, _>(|i| i as u32), true) }.unwrap();
+    let mut arr = VNVArray::new(heap.get_inner(), identifier);
+    arr.unload().unwrap();
+
+    READS.store(0, Ordering::SeqCst);
+    let mut visited = 0;
+    let mut chunks = arr.iter_chunks(64);
+    while let Some(chunk) = chunks.next() {
+        let chunk = chunk.unwrap();
+        assert_eq!(chunk.len(), if visited == 448 { 52 } else { 64 });
+        for elem in chunk {
+            assert_eq!(*elem, visited);
+            visited += 1;
+        }
+        assert_eq!(chunks.position(), visited as usize);
+    }
+    assert_eq!(visited, 500);
+
+    // every read also prefetches the next chunk
+    #[cfg(not(any(feature = "access_counters", feature = "object_checksums")))]
+    {
+        assert!(!arr.is_resident());
+        assert_eq!(READS.load(Ordering::SeqCst), 4);
+    }
+
+    // resident data is more recent than the data in storage
+    arr.get_mut().unwrap().get_range_mut(100..101).unwrap()[0] = 1000;
+    READS.store(0, Ordering::SeqCst);
+    let mut chunks = arr.iter_chunks(300);
+    assert_eq!(chunks.next().unwrap().unwrap()[100], 1000);
+    assert_eq!(chunks.next().unwrap().unwrap().len(), 200);
+    assert!(chunks.next().is_none());
+    assert_eq!(READS.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_array_update() {
+    let mut buffer = [0u8; 3000];
+    let heap = get_test_heap("test_array_update", 4 * 4096, &mut buffer, 700, |_, _| {});
+
+    let identifier = unsafe { heap.get_inner().borrow_mut().allocate([0u32; 256], true) }.unwrap();
+    let mut arr = VNVArray::new(heap.get_inner(), identifier);
+    arr.flush().unwrap();
+    drop(arr.get().unwrap());
+    assert!(arr.is_resident());
+
+    // only the block of the element is made dirty
+    let remaining_dirty = || heap.get_inner().borrow_mut().get_resident_object_manager().remaining_dirty_size;
+    let before = remaining_dirty();
+    assert_eq!(arr.update(10, |elem| { *elem = 5; *elem + 1 }).unwrap(), 6);
+    let used = before - remaining_dirty();
+    assert!(used > 0 && used < 256 * 4, "{} dirty bytes were used", used);
+
+    // the dirty budget is too small for the whole array, so the array is synchronized in between
+    for round in 0..2 {
+        for i in 0..256 {
+            arr.update(i, |elem| *elem += 1).unwrap();
+        }
+        assert_eq!(arr.get().unwrap()[10], 5 + round + 1);
+    }
+
+    arr.unload().unwrap();
+    let data = arr.get().unwrap();
+    for (i, elem) in data.iter().enumerate() {
+        assert_eq!(*elem, if i == 10 { 7 } else { 2 });
+    }
+}
+
+#[test]
+fn test_array_update_not_resident() {
+    static READS: AtomicUsize = AtomicUsize::new(0);
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+    fn count_operations(op: StorageOperation) {
+        match op {
+            StorageOperation::Read { .. } => READS.fetch_add(1, Ordering::SeqCst),
+            StorageOperation::Write { .. } => WRITES.fetch_add(1, Ordering::SeqCst),
+            StorageOperation::Flush => 0,
+        };
+    }
+
+    let mut buffer = [0u8; 3000];
+    let heap = get_instrumented_test_heap("test_array_update_not_resident", &mut buffer, count_operations);
+
+    let identifier = unsafe { heap.get_inner().borrow_mut().allocate([7u64; 200], true) }.unwrap();
+    let mut arr = VNVArray::new(heap.get_inner(), identifier);
+    arr.unload().unwrap();
+
+    READS.store(0, Ordering::SeqCst);
+    WRITES.store(0, Ordering::SeqCst);
+    arr.update(150, |elem| *elem *= 6).unwrap();
+
+    #[cfg(not(any(feature = "access_counters", feature = "object_checksums")))]
+    {
+        assert!(!arr.is_resident());
+        assert_eq!(READS.load(Ordering::SeqCst), 1);
+        assert_eq!(WRITES.load(Ordering::SeqCst), 1);
+    }
+
+    let data = arr.get().unwrap();
+    for (i, elem) in data.iter().enumerate() {
+        assert_eq!(*elem, if i == 150 { 42 } else { 7 });
+    }
+}
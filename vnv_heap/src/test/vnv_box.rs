@@ -0,0 +1,61 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use super::get_test_heap;
+
+fn remaining_dirty_size(heap: &super::TestHeap) -> usize {
+    heap.get_inner().borrow_mut().get_resident_object_manager().remaining_dirty_size
+}
+
+#[test]
+fn test_vnv_box() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_vnv_box", 4 * 4096, &mut buffer, 2000, |_, _| {});
+
+    let mut boxed = heap.allocate_box([1u32; 20]).unwrap();
+    assert!(!boxed.is_pinned());
+
+    // read access pins the object without making it dirty
+    assert_eq!(boxed[3], 1);
+    assert!(boxed.is_pinned());
+    boxed.unpin();
+
+    let mut obj = boxed.into_inner();
+    obj.unload().unwrap();
+    let clean_dirty_size = remaining_dirty_size(&heap);
+
+    let mut boxed = crate::VNVBox::new(obj);
+    assert!(!boxed.is_resident());
+    assert_eq!(boxed.iter().sum::<u32>(), 20);
+
+    // upgrade to a mutable pin
+    boxed[3] = 42;
+    boxed[4] = 43;
+    assert!(remaining_dirty_size(&heap) < clean_dirty_size);
+
+    // object is pinned and cannot be unloaded
+    let alloc_id = boxed.get_alloc_id().clone();
+    assert!(heap.get_inner().borrow_mut().unload_object(&alloc_id, false).is_err());
+
+    boxed.unload().unwrap();
+    assert!(!boxed.is_pinned());
+    assert!(!boxed.is_resident());
+    assert_eq!(remaining_dirty_size(&heap), clean_dirty_size);
+
+    assert_eq!(boxed[3], 42);
+    assert_eq!(boxed[4], 43);
+}
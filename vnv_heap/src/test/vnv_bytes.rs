@@ -0,0 +1,135 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::VNVError;
+
+use super::get_test_heap;
+
+#[test]
+fn test_vnv_bytes_read_write() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_vnv_bytes_read_write", 4 * 4096, &mut buffer, 1200, |_, _| {});
+
+    let data: Vec<u8> = (0..777).map(|i| i as u8).collect();
+    let mut bytes = heap.allocate_bytes(&data).unwrap();
+    assert_eq!(bytes.len(), data.len());
+    assert!(!bytes.is_resident());
+
+    assert_eq!(&*bytes.get().unwrap(), &data[..]);
+    assert!(bytes.is_resident());
+    assert!(!bytes.is_data_dirty());
+
+    {
+        let mut mut_ref = bytes.get_mut().unwrap();
+        mut_ref[0] = 42;
+        mut_ref[776] = 43;
+    }
+    assert!(bytes.is_data_dirty());
+
+    bytes.unload().unwrap();
+    assert!(!bytes.is_resident());
+
+    let bytes_ref = bytes.get().unwrap();
+    assert_eq!(bytes_ref[0], 42);
+    assert_eq!(&bytes_ref[1..776], &data[1..776]);
+    assert_eq!(bytes_ref[776], 43);
+}
+
+#[test]
+fn test_vnv_bytes_dirty_size() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_vnv_bytes_dirty_size", 4 * 4096, &mut buffer, 1200, |_, _| {});
+
+    let remaining_dirty_size = || heap.get_inner().borrow_mut().get_resident_object_manager().remaining_dirty_size;
+    let start = remaining_dirty_size();
+
+    // a buffer uses as many dirty bytes as an object of the same size
+    let mut obj = heap.allocate([0u8; 300]).unwrap();
+    drop(obj.get_mut().unwrap());
+    let obj_dirty_size = start - remaining_dirty_size();
+    obj.unload().unwrap();
+    assert_eq!(remaining_dirty_size(), start);
+
+    let mut bytes = heap.allocate_bytes(&[0u8; 300]).unwrap();
+    drop(bytes.get_mut().unwrap());
+    assert_eq!(start - remaining_dirty_size(), obj_dirty_size);
+
+    bytes.unload().unwrap();
+    assert_eq!(remaining_dirty_size(), start);
+
+    // dirty data is thrown away when the buffer is dropped
+    drop(bytes.get_mut().unwrap());
+    drop(bytes);
+    assert_eq!(remaining_dirty_size(), start);
+}
+
+#[test]
+fn test_vnv_bytes_deallocate() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_vnv_bytes_deallocate", 4 * 4096, &mut buffer, 1200, |_, _| {});
+
+    // all buffers together are way larger than the storage
+    for i in 0..100u8 {
+        let mut bytes = heap.allocate_bytes(&[i; 1000]).unwrap();
+        assert!(bytes.get().unwrap().iter().all(|x| *x == i));
+    }
+}
+
+#[test]
+fn test_vnv_bytes_resize() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_vnv_bytes_resize", 4 * 4096, &mut buffer, 1200, |_, _| {});
+
+    let data: Vec<u8> = (0..300).map(|i| i as u8).collect();
+    let mut bytes = heap.allocate_bytes(&data).unwrap();
+
+    // dirty data is moved to the new region as well
+    bytes.get_mut().unwrap()[299] = 42;
+    bytes.resize(700).unwrap();
+    assert_eq!(bytes.len(), 700);
+    assert!(!bytes.is_resident());
+
+    {
+        let bytes_ref = bytes.get().unwrap();
+        assert_eq!(&bytes_ref[..299], &data[..299]);
+        assert_eq!(bytes_ref[299], 42);
+        assert!(bytes_ref[300..].iter().all(|x| *x == 0));
+    }
+
+    bytes.get_mut().unwrap()[699] = 43;
+    bytes.resize(100).unwrap();
+    assert_eq!(&*bytes.get().unwrap(), &data[..100]);
+
+    // resizing to the same length does nothing
+    bytes.get_mut().unwrap()[0] = 44;
+    bytes.resize(100).unwrap();
+    assert!(bytes.is_data_dirty());
+    assert_eq!(bytes.get().unwrap()[0], 44);
+}
+
+#[test]
+fn test_vnv_bytes_resize_storage_exhausted() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_vnv_bytes_resize_storage_exhausted", 2 * 4096, &mut buffer, 1200, |_, _| {});
+
+    let mut bytes = heap.allocate_bytes(&[7u8; 500]).unwrap();
+    assert_eq!(bytes.resize(16000).err(), Some(VNVError::NonResidentSpaceExhausted));
+
+    // the buffer is left unchanged
+    assert_eq!(bytes.len(), 500);
+    assert!(bytes.get().unwrap().iter().all(|x| *x == 7));
+}
@@ -0,0 +1,144 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::thread;
+
+use crate::{vnv_persist_all, VNVError};
+
+use super::get_test_heap;
+
+#[test]
+fn test_channel() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_channel", 8192, &mut buffer, 1000, |_, _| {});
+
+    let mut channel = heap.new_channel::<u32, 4>().unwrap();
+    assert_eq!(channel.capacity(), 4);
+
+    {
+        let (mut producer, mut consumer) = channel.split();
+        assert_eq!(consumer.consume(), None);
+
+        // wraps around several times
+        let mut next_consumed = 0;
+        for i in 0..20u32 {
+            producer.produce(i).unwrap();
+            if i % 2 == 1 {
+                assert_eq!(consumer.consume(), Some(next_consumed));
+                next_consumed += 1;
+            }
+
+            if producer.is_full() {
+                assert_eq!(producer.produce(100), Err(100));
+                while let Some(item) = consumer.consume() {
+                    assert_eq!(item, next_consumed);
+                    next_consumed += 1;
+                }
+            }
+        }
+        assert_eq!(consumer.len(), (20 - next_consumed) as usize);
+    }
+
+    assert!(!channel.is_empty());
+}
+
+#[test]
+fn test_channel_stays_resident() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_channel_stays_resident", 4 * 4096, &mut buffer, 600, |_, _| {});
+
+    let mut channel = heap.new_channel::<[u8; 16], 8>().unwrap();
+    let identifier = channel.allocation_identifier.clone();
+
+    // other objects can neither evict the channel nor use its dirty bytes
+    let mut objects: Vec<_> = (0..8).map(|i| heap.allocate([i as u8; 100]).unwrap()).collect();
+    for obj in objects.iter_mut() {
+        obj.get_mut().unwrap()[0] = 1;
+        assert!(heap.get_inner().borrow_mut().is_resident(&identifier));
+    }
+    assert_eq!(heap.get_inner().borrow_mut().get_dirty_bytes() >= 8 * 16, true);
+
+    let (mut producer, mut consumer) = channel.split();
+    producer.produce([7; 16]).unwrap();
+    assert_eq!(consumer.consume(), Some([7; 16]));
+}
+
+#[test]
+fn test_channel_dirty_budget() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_channel_dirty_budget", 4 * 4096, &mut buffer, 300, |_, _| {});
+
+    let res = heap.new_channel::<[u8; 64], 8>();
+    assert_eq!(res.err(), Some(VNVError::DirtyBudgetExhausted));
+}
+
+#[test]
+fn test_channel_concurrent_producer() {
+    const COUNT: u64 = 20_000;
+
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_channel_concurrent_producer", 8192, &mut buffer, 1000, |_, _| {});
+
+    let mut channel = heap.new_channel::<u64, 16>().unwrap();
+    let (mut producer, mut consumer) = channel.split();
+
+    thread::scope(|scope| {
+        // simulates an interrupt service routine
+        scope.spawn(move || {
+            for i in 0..COUNT {
+                let mut item = i;
+                while let Err(rejected) = producer.produce(item) {
+                    item = rejected;
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let mut expected = 0;
+        while expected < COUNT {
+            match consumer.consume() {
+                Some(item) => {
+                    assert_eq!(item, expected);
+                    expected += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+    });
+
+    assert!(channel.is_empty());
+}
+
+#[test]
+fn test_channel_persist() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_channel_persist", 8192, &mut buffer, 1000, |_, _| {});
+
+    let mut channel = heap.new_channel::<u16, 8>().unwrap();
+    let (mut producer, mut consumer) = channel.split();
+    for i in 0..5 {
+        producer.produce(i).unwrap();
+    }
+    consumer.consume().unwrap();
+
+    unsafe { vnv_persist_all() };
+
+    producer.produce(5).unwrap();
+    for i in 1..6 {
+        assert_eq!(consumer.consume(), Some(i));
+    }
+}
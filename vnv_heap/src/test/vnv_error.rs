@@ -0,0 +1,68 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::VNVError;
+
+use super::get_test_heap;
+
+#[test]
+fn test_error_resident_buffer_exhausted() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_error_resident_buffer_exhausted", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let mut obj = heap.allocate([0u8; 2000]).unwrap();
+    assert_eq!(obj.get().err(), Some(VNVError::ResidentBufferExhausted));
+    assert_eq!(obj.get_mut().err(), Some(VNVError::ResidentBufferExhausted));
+}
+
+#[test]
+fn test_error_non_resident_space_exhausted() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_error_non_resident_space_exhausted", 8192, &mut buffer, 1000, |_, _| {});
+
+    let mut objects = vec![];
+    loop {
+        match heap.allocate([0u8; 256]) {
+            Ok(obj) => objects.push(obj),
+            Err(err) => {
+                assert_eq!(err, VNVError::NonResidentSpaceExhausted);
+                break;
+            }
+        }
+    }
+    assert!(!objects.is_empty());
+}
+
+#[test]
+fn test_error_resident_objects_in_use() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_error_resident_objects_in_use", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let mut obj1 = heap.allocate([0u8; 300]).unwrap();
+    let mut obj2 = heap.allocate([0u8; 300]).unwrap();
+    let mut obj3 = heap.allocate([0u8; 300]).unwrap();
+
+    {
+        let _ref1 = obj1.get().unwrap();
+        let _ref2 = obj2.get().unwrap();
+
+        // no object can be unloaded to make space
+        assert_eq!(obj3.get().err(), Some(VNVError::ResidentObjectsInUse));
+    }
+
+    assert!(obj3.get().is_ok());
+}
@@ -0,0 +1,55 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::{vnv_persist_all, VNVLink};
+
+use super::get_test_heap;
+
+struct Node {
+    value: u32,
+    next: Option<VNVLink<Node>>,
+}
+
+#[test]
+fn test_vnv_link() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_vnv_link", 4 * 4096, &mut buffer, 1000, |_, _| {});
+
+    let mut tail = heap.allocate(Node { value: 2, next: None }).unwrap();
+    let mut head = heap.allocate(Node { value: 1, next: Some(tail.link()) }).unwrap();
+
+    unsafe { vnv_persist_all() };
+    head.unload().unwrap();
+
+    // follow the link that was stored inside of the object
+    let link = head.get().unwrap().next.unwrap();
+    assert!(tail.is_linked_by(&link));
+    assert!(!head.is_linked_by(&link));
+
+    let value = unsafe {
+        heap.with_link(&link, |obj| {
+            obj.get_mut().unwrap().value += 10;
+            obj.get().unwrap().value
+        })
+    };
+    assert_eq!(value, 12);
+
+    // the linked object is still owned by `tail`
+    tail.unload().unwrap();
+    assert_eq!(tail.get().unwrap().value, 12);
+    assert!(tail.get().unwrap().next.is_none());
+}
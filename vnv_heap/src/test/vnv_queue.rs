@@ -0,0 +1,105 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::VecDeque;
+
+use crate::{vnv_persist_all, VNVError};
+
+use super::get_test_heap;
+
+#[test]
+fn test_queue() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_queue", 8192, &mut buffer, 1000, |_, _| {});
+
+    let mut queue = heap.new_queue::<u32, 5>().unwrap();
+    let mut check_queue = VecDeque::new();
+    assert_eq!(queue.capacity(), 5);
+    assert_eq!(queue.consume().unwrap(), None);
+
+    // wraps around several times
+    for i in 0..40u32 {
+        if i % 3 != 2 || check_queue.is_empty() {
+            if check_queue.len() == 5 {
+                assert_eq!(queue.produce(i).err(), Some(VNVError::CapacityExhausted));
+            } else {
+                queue.produce(i).unwrap();
+                check_queue.push_back(i);
+            }
+        } else {
+            assert_eq!(queue.consume().unwrap(), check_queue.pop_front());
+        }
+
+        assert_eq!(queue.len(), check_queue.len());
+        assert_eq!(queue.is_empty(), check_queue.is_empty());
+        assert_eq!(queue.is_full(), check_queue.len() == 5);
+        assert_eq!(queue.peek().unwrap(), check_queue.front().copied());
+    }
+
+    queue.clear().unwrap();
+    assert!(queue.is_empty());
+    assert_eq!(queue.peek().unwrap(), None);
+}
+
+#[test]
+fn test_queue_items_not_resident() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_queue_items_not_resident", 8 * 4096, &mut buffer, 1000, |_, _| {});
+
+    // the items would not fit into the resident buffer
+    let mut queue = heap.new_queue::<[u8; 100], 64>().unwrap();
+    for i in 0..64 {
+        queue.produce([i; 100]).unwrap();
+    }
+    assert_eq!(heap.get_resident_usage().resident_objects, 1);
+
+    queue.unload().unwrap();
+    for i in 0..64 {
+        assert_eq!(queue.consume().unwrap(), Some([i; 100]));
+    }
+    assert_eq!(queue.consume().unwrap(), None);
+}
+
+#[test]
+fn test_queue_persist() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_queue_persist", 8192, &mut buffer, 1000, |_, _| {});
+
+    let mut queue = heap.new_queue::<u64, 8>().unwrap();
+    for i in 0..6u64 {
+        queue.produce(i * 1000).unwrap();
+    }
+    queue.consume().unwrap();
+
+    unsafe { vnv_persist_all() };
+
+    for i in 1..6u64 {
+        assert_eq!(queue.consume().unwrap(), Some(i * 1000));
+    }
+}
+
+#[test]
+fn test_queue_drop() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_queue_drop", 2 * 4096, &mut buffer, 1000, |_, _| {});
+
+    // storage is freed again if the queue is dropped
+    for _ in 0..20 {
+        let mut queue = heap.new_queue::<[u8; 64], 12>().unwrap();
+        queue.produce([1; 64]).unwrap();
+    }
+}
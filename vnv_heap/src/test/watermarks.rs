@@ -0,0 +1,126 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::{
+    modules::{
+        allocator::LinkedListAllocatorModule,
+        nonresident_allocator::NonResidentBuddyAllocatorModule,
+        object_management::DefaultObjectManagementModule,
+        persistent_storage::{test::get_test_storage, FilePersistentStorageModule, PartitionedStorageModule},
+    },
+    VNVConfig, VNVHeap, Watermarks, WriteBack,
+};
+
+use super::{get_test_heap, TestHeap};
+
+#[test]
+fn test_watermarks() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_watermarks", 4 * 4096, &mut buffer, 2000, |_, _| {});
+    assert_eq!(heap.get_watermarks(), Watermarks::default());
+
+    let info = TestHeap::get_object_layout_info::<[u8; 100]>();
+
+    let mut obj1 = heap.allocate([1u8; 100]).unwrap();
+    let mut obj2 = heap.allocate([2u8; 100]).unwrap();
+
+    let usage = heap.get_resident_usage();
+    let watermarks = heap.get_watermarks();
+    assert_eq!(watermarks.max_storage_bytes, 2 * info.non_resident_allocated_size);
+    assert_eq!(watermarks.max_resident_bytes, usage.user_bytes + usage.metadata_bytes);
+    assert!(watermarks.max_dirty_bytes >= 2 * info.data_size);
+
+    obj1.unload().unwrap();
+    obj2.unload().unwrap();
+    drop(obj1);
+
+    // the watermarks do not decrease
+    assert_eq!(heap.get_resident_usage().resident_objects, 0);
+    assert_eq!(heap.get_watermarks(), watermarks);
+
+    // storage of deallocated objects is not counted anymore
+    let _obj3 = heap.allocate([3u8; 100]).unwrap();
+    assert_eq!(heap.get_watermarks().max_storage_bytes, 2 * info.non_resident_allocated_size);
+    let _obj4 = heap.allocate([4u8; 100]).unwrap();
+    assert_eq!(heap.get_watermarks().max_storage_bytes, 3 * info.non_resident_allocated_size);
+
+    drop(obj2);
+}
+
+#[test]
+fn test_watermarks_reset() {
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_watermarks_reset", 4 * 4096, &mut buffer, 2000, |_, _| {});
+    let _obj = heap.allocate([1u8; 100]).unwrap();
+    assert_ne!(heap.get_watermarks(), Watermarks::default());
+    drop(_obj);
+    drop(heap);
+
+    // a new heap starts with new watermarks
+    let mut buffer = [0u8; 2000];
+    let heap = get_test_heap("test_watermarks_reset", 4 * 4096, &mut buffer, 2000, |_, _| {});
+    assert_eq!(heap.get_watermarks(), Watermarks::default());
+}
+
+#[test]
+fn test_watermarks_reserved_storage() {
+    const HEAP_REGION_START: usize = 256;
+
+    let watermarks = Watermarks {
+        max_storage_bytes: 1,
+        max_resident_bytes: 2,
+        max_dirty_bytes: 3,
+    };
+    assert_eq!(Watermarks::from_bytes(&watermarks.to_bytes()), watermarks);
+
+    let storage = PartitionedStorageModule::new(
+        get_test_storage("test_watermarks_reserved_storage", HEAP_REGION_START + 4 * 4096),
+        HEAP_REGION_START..HEAP_REGION_START + 4 * 4096,
+    );
+
+    let mut buffer = [0u8; 1000];
+    let heap: VNVHeap<
+        LinkedListAllocatorModule,
+        NonResidentBuddyAllocatorModule<16>,
+        DefaultObjectManagementModule,
+        PartitionedStorageModule<FilePersistentStorageModule>,
+    > = VNVHeap::new(
+        &mut buffer,
+        storage,
+        LinkedListAllocatorModule::new(),
+        VNVConfig {
+            max_dirty_bytes: 500,
+            write_back: WriteBack::Lazy,
+            persist_latency_budget: None,
+        },
+        |_, _| {},
+    )
+    .unwrap();
+
+    let _obj = heap.allocate([1u8; 100]).unwrap();
+
+    // combine the watermarks of a previous session with the current ones
+    let mut reserved = heap.storage();
+    reserved.write_watermarks(0, &watermarks).unwrap();
+    let previous = reserved.read_watermarks(0).unwrap();
+    assert_eq!(previous, watermarks);
+
+    let merged = previous.merge(heap.get_watermarks());
+    assert_eq!(merged, heap.get_watermarks());
+    reserved.write_watermarks(0, &merged).unwrap();
+    assert_eq!(reserved.read_watermarks(0).unwrap(), merged);
+}
@@ -0,0 +1,113 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{
+    modules::allocator::LinkedListAllocatorModule,
+    test::{get_test_heap, InstrumentedStorageModule, InstrumentedTestHeap, StorageOperation},
+    VNVConfig, VNVHeap, WriteBack,
+};
+
+#[test]
+fn test_write_back_lazy() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_write_back_lazy", 4 * 4096, &mut buffer, 500, |_, _| {});
+    assert_eq!(heap.get_write_back(), WriteBack::Lazy);
+
+    let mut obj = heap.allocate([0u32; 10]).unwrap();
+    obj.flush().unwrap();
+
+    obj.get_mut().unwrap()[0] = 1;
+    assert!(obj.is_data_dirty());
+}
+
+#[test]
+fn test_write_back_on_release() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_write_back_on_release", 4 * 4096, &mut buffer, 500, |_, _| {});
+    heap.set_write_back(WriteBack::OnRelease);
+
+    let mut obj = heap.allocate([0u32; 10]).unwrap();
+    obj.flush().unwrap();
+
+    {
+        let mut mut_ref = obj.get_mut().unwrap();
+        mut_ref[0] = 1;
+    }
+    assert!(!obj.is_data_dirty());
+
+    // nothing is lost if the object is unloaded afterwards
+    obj.unload().unwrap();
+    assert_eq!(obj.get().unwrap()[0], 1);
+}
+
+#[test]
+fn test_write_back_immediate() {
+    static FLUSHES: AtomicUsize = AtomicUsize::new(0);
+    fn count_flushes(op: StorageOperation) {
+        if op == StorageOperation::Flush {
+            FLUSHES.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let mut buffer = [0u8; 1000];
+    let storage = InstrumentedStorageModule::new("test_write_back_immediate", 4 * 4096, count_flushes);
+    let heap: InstrumentedTestHeap = VNVHeap::new(
+        &mut buffer,
+        storage,
+        LinkedListAllocatorModule::new(),
+        VNVConfig {
+            max_dirty_bytes: 500,
+            write_back: WriteBack::Immediate,
+            persist_latency_budget: None,
+        },
+        |_, _| {},
+    )
+    .unwrap();
+    assert_eq!(heap.get_write_back(), WriteBack::Immediate);
+
+    let mut obj = heap.allocate([0u32; 10]).unwrap();
+    obj.flush().unwrap();
+
+    FLUSHES.store(0, Ordering::SeqCst);
+    obj.get_mut().unwrap()[0] = 1;
+    assert!(!obj.is_data_dirty());
+    assert_eq!(FLUSHES.load(Ordering::SeqCst), 1);
+
+    // immutable references do not trigger a write-back
+    assert_eq!(obj.get().unwrap()[0], 1);
+    assert_eq!(FLUSHES.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_flush_returns_cleaned_bytes() {
+    let mut buffer = [0u8; 1000];
+    let heap = get_test_heap("test_flush_returns_cleaned_bytes", 4 * 4096, &mut buffer, 500, |_, _| {});
+
+    let mut obj = heap.allocate([0u32; 10]).unwrap();
+    obj.flush().unwrap();
+    assert_eq!(obj.flush().unwrap(), 0);
+
+    obj.get_mut().unwrap()[0] = 1;
+    assert!(obj.is_data_dirty());
+
+    assert!(obj.flush().unwrap() > 0);
+    assert!(!obj.is_data_dirty());
+    assert_eq!(obj.flush().unwrap(), 0);
+    assert_eq!(obj.get().unwrap()[0], 1);
+}
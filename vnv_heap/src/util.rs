@@ -39,3 +39,32 @@ pub(crate) const fn round_up_to_nearest(num: usize, multiple: usize) -> usize {
 pub(crate) const fn div_ceil(num: usize, div: usize) -> usize {
     (num + div - 1) / div
 }
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB88320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32 (IEEE 802.3) of `data`
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    !crc32_update(!0u32, data)
+}
+
+/// Feeds `data` into a running CRC-32 calculation, so that data can be processed in chunks
+pub(crate) fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc = CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    crc
+}
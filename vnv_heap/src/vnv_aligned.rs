@@ -0,0 +1,106 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::ops::{Deref, DerefMut};
+
+/// Alignment of `ALIGN` bytes, only alignments that implement `SupportedAlignment` can be used
+pub struct Alignment<const ALIGN: usize>;
+
+/// Implemented for all powers of two from 1 to 4096 bytes (see `VNVAligned`)
+pub trait SupportedAlignment {
+    /// Zero sized type with the requested alignment
+    type Marker: Copy;
+}
+
+#[doc(hidden)]
+pub mod alignment_markers {
+    macro_rules! alignment_markers {
+        ($($name: ident = $align: literal),*) => {
+            $(
+                #[derive(Clone, Copy)]
+                #[repr(align($align))]
+                pub struct $name;
+
+                impl super::SupportedAlignment for super::Alignment<$align> {
+                    type Marker = $name;
+                }
+            )*
+        };
+    }
+
+    alignment_markers!(
+        Align1 = 1, Align2 = 2, Align4 = 4, Align8 = 8, Align16 = 16, Align32 = 32, Align64 = 64,
+        Align128 = 128, Align256 = 256, Align512 = 512, Align1024 = 1024, Align2048 = 2048, Align4096 = 4096
+    );
+}
+
+/// Wrapper of `T` that is aligned to (at least) `ALIGN` bytes, see `VNVHeap::allocate_aligned`.
+///
+/// As `ResidentObject` uses the alignment of its data, the resident copy of the object is aligned as well
+/// (e.g. for buffers that are accessed with DMA).
+#[repr(C)]
+pub struct VNVAligned<T, const ALIGN: usize>
+where
+    Alignment<ALIGN>: SupportedAlignment,
+{
+    _align: [<Alignment<ALIGN> as SupportedAlignment>::Marker; 0],
+    value: T,
+}
+
+impl<T, const ALIGN: usize> VNVAligned<T, ALIGN>
+where
+    Alignment<ALIGN>: SupportedAlignment,
+{
+    pub const fn new(value: T) -> Self {
+        Self { _align: [], value }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, const ALIGN: usize> Deref for VNVAligned<T, ALIGN>
+where
+    Alignment<ALIGN>: SupportedAlignment,
+{
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T, const ALIGN: usize> DerefMut for VNVAligned<T, ALIGN>
+where
+    Alignment<ALIGN>: SupportedAlignment,
+{
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: Clone, const ALIGN: usize> Clone for VNVAligned<T, ALIGN>
+where
+    Alignment<ALIGN>: SupportedAlignment,
+{
+    fn clone(&self) -> Self {
+        Self::new(self.value.clone())
+    }
+}
+
+impl<T: Copy, const ALIGN: usize> Copy for VNVAligned<T, ALIGN> where Alignment<ALIGN>: SupportedAlignment {}
@@ -15,7 +15,13 @@
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use core::{cell::RefCell, marker::PhantomData};
+use core::{
+    cell::RefCell,
+    cmp::min,
+    marker::PhantomData,
+    mem::{size_of, MaybeUninit},
+    ptr::slice_from_raw_parts_mut,
+};
 
 use crate::{
     allocation_identifier::AllocationIdentifier,
@@ -25,6 +31,7 @@ use crate::{
     },
     vnv_heap::VNVHeapInner,
     vnv_array_mut_ref::VNVArrayMutRef,
+    vnv_error::VNVError,
     vnv_mut_ref::VNVMutRef,
     vnv_ref::VNVRef,
 };
@@ -64,7 +71,7 @@ impl<
         }
     }
 
-    pub fn get(&mut self) -> Result<VNVRef<'a, '_, '_, 'b, [T; SIZE], A, N, M>, ()> {
+    pub fn get(&mut self) -> Result<VNVRef<'a, '_, '_, 'b, [T; SIZE], A, N, M>, VNVError> {
         let mut heap = self.vnv_heap.borrow_mut();
         unsafe {
             let ptr: *const [T; SIZE] = heap.get_ref(&self.allocation_identifier, true)?;
@@ -77,7 +84,7 @@ impl<
         }
     }
 
-    pub fn get_mut(&mut self) -> Result<VNVArrayMutRef<'a, '_, '_, 'b, T, SIZE, A, N, M>, ()> {
+    pub fn get_mut(&mut self) -> Result<VNVArrayMutRef<'a, '_, '_, 'b, T, SIZE, A, N, M>, VNVError> {
         let mut heap = self.vnv_heap.borrow_mut();
         let (meta_ptr, data_ptr) =
             unsafe { heap.get_partial_mut::<[T; SIZE]>(&self.allocation_identifier)? };
@@ -89,7 +96,7 @@ impl<
 
     pub fn get_mut_whole_arr(
         &mut self,
-    ) -> Result<VNVMutRef<'a, '_, '_, 'b, [T; SIZE], A, N, M>, ()> {
+    ) -> Result<VNVMutRef<'a, '_, '_, 'b, [T; SIZE], A, N, M>, VNVError> {
         let mut heap = self.vnv_heap.borrow_mut();
         unsafe {
             let ptr: *mut [T; SIZE] = heap.get_mut(&self.allocation_identifier, false)?;
@@ -102,22 +109,216 @@ impl<
         }
     }
 
+    /// Calls `func` for consecutive chunks of at most `chunk_elems` elements until the whole array was visited.
+    ///
+    /// Only the elements of the current chunk are made dirty. If there are not enough dirty bytes left for the next chunk,
+    /// the previous chunks of this array are synchronized first.
+    /// This bounds the dirty bytes required by this call to about `chunk_elems` elements.
+    pub fn for_each_resident<F: FnMut(&mut [T])>(&mut self, chunk_elems: usize, mut func: F) -> Result<(), VNVError> {
+        assert!(chunk_elems > 0, "chunk has to contain at least one element");
+
+        let mut start = 0;
+        while start < SIZE {
+            let end = min(start + chunk_elems, SIZE);
+
+            match self.visit_chunk(start, end, &mut func) {
+                Err(VNVError::DirtyBudgetExhausted) => {
+                    // this array cannot be synchronized while it is in use, so do it now and retry
+                    self.flush()?;
+                    self.visit_chunk(start, end, &mut func)?;
+                }
+                res => res?,
+            }
+
+            start = end;
+        }
+
+        Ok(())
+    }
+
+    fn visit_chunk<F: FnMut(&mut [T])>(&mut self, start: usize, end: usize, func: &mut F) -> Result<(), VNVError> {
+        let mut mut_ref = self.get_mut()?;
+        func(mut_ref.get_range_mut(start..end)?);
+        Ok(())
+    }
+
+    /// Calls `func` with the element at `index` and returns its result.
+    ///
+    /// If the array is resident, only the dirtiness tracking block that contains the element is made dirty
+    /// (if there are not enough dirty bytes left, this array is synchronized first).
+    /// Otherwise, the element is read from storage, changed and written back directly, so the array is not made
+    /// resident. Only if `access_counters` or `object_checksums` is enabled, the array is always made resident
+    /// to count the access and keep its checksum up to date.
+    pub fn update<R, F: FnOnce(&mut T) -> R>(&mut self, index: usize, func: F) -> Result<R, VNVError> {
+        assert!(index < SIZE, "index is out of bounds (index: {}, len: {})", index, SIZE);
+
+        if !cfg!(any(feature = "access_counters", feature = "object_checksums")) && !self.is_resident() {
+            return self.update_in_storage(index, func);
+        }
+
+        match self.get_mut()?.get_range_mut(index..index + 1) {
+            Ok(elem) => return Ok(func(&mut elem[0])),
+            Err(VNVError::DirtyBudgetExhausted) => {}
+            Err(err) => return Err(err),
+        }
+
+        // this array cannot be synchronized while it is in use, so do it now and retry
+        self.flush()?;
+        let mut mut_ref = self.get_mut()?;
+        let elem = mut_ref.get_range_mut(index..index + 1)?;
+        Ok(func(&mut elem[0]))
+    }
+
+    fn update_in_storage<R, F: FnOnce(&mut T) -> R>(&mut self, index: usize, func: F) -> Result<R, VNVError> {
+        let mut elem = [MaybeUninit::<T>::uninit()];
+        self.read_range(index, &mut elem)?;
+
+        // the element was read from a valid `[T; SIZE]`
+        let mut elem = unsafe { elem[0].assume_init() };
+        let res = func(&mut elem);
+
+        let src = unsafe {
+            core::slice::from_raw_parts((&elem as *const T) as *const u8, size_of::<T>())
+        };
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.write_object_range(self.allocation_identifier.offset, index * size_of::<T>(), src)?;
+        Ok(res)
+    }
+
+    /// Returns a cursor over consecutive chunks of at most `chunk_len` elements, see `VNVArrayChunks`.
+    ///
+    /// The array is not made resident for this, so arrays that are bigger than the resident buffer can be scanned.
+    pub fn iter_chunks(&mut self, chunk_len: usize) -> VNVArrayChunks<'_, 'a, 'b, T, SIZE, A, N, M> {
+        assert!(chunk_len > 0, "chunk has to contain at least one element");
+
+        let window_len = min(chunk_len.saturating_mul(2), SIZE);
+        let mut window = Vec::with_capacity(window_len);
+        window.resize(window_len, MaybeUninit::uninit());
+
+        VNVArrayChunks {
+            array: self,
+            chunk_len,
+            window,
+            window_start: 0,
+            window_len: 0,
+            next: 0,
+        }
+    }
+
+    /// Copies the elements starting at `index` to `dest` without making the array resident
+    fn read_range(&mut self, index: usize, dest: &mut [MaybeUninit<T>]) -> Result<(), VNVError> {
+        debug_assert!(index + dest.len() <= SIZE);
+
+        // loading the array is required to count the access and verify its checksum
+        #[cfg(any(feature = "access_counters", feature = "object_checksums"))]
+        drop(self.get()?);
+
+        let dest = unsafe {
+            slice_from_raw_parts_mut(dest.as_mut_ptr() as *mut u8, dest.len() * size_of::<T>())
+                .as_mut()
+                .unwrap()
+        };
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.read_object_range(self.allocation_identifier.offset, index * size_of::<T>(), dest)
+    }
+
     pub fn is_resident(&self) -> bool {
         let mut heap = self.vnv_heap.borrow_mut();
         heap.is_resident(&self.allocation_identifier)
     }
 
-    pub fn unload(&mut self) -> Result<(), ()> {
+    pub fn unload(&mut self) -> Result<(), VNVError> {
         let mut heap = self.vnv_heap.borrow_mut();
         heap.unload_object(&self.allocation_identifier, true)
     }
 
+    /// Writes the dirty blocks of this array back to storage and returns how many dirty bytes were cleaned
+    pub fn flush(&mut self) -> Result<usize, VNVError> {
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.flush_object(&self.allocation_identifier)
+    }
+
+    /// Throws away all changes that were not synchronized yet and restores the last synchronized state.
+    ///
+    /// Only the dirty blocks are reloaded from storage.
+    pub fn discard_changes(&mut self) -> Result<(), VNVError> {
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.discard_changes(&self.allocation_identifier)
+    }
+
     #[allow(unused)]
     pub(crate) fn get_alloc_id(&self) -> &AllocationIdentifier<[T; SIZE]> {
         return &self.allocation_identifier;
     }
 }
 
+/// Cursor over consecutive chunks of a `VNVArray`, see `VNVArray::iter_chunks`.
+///
+/// The chunks are copied to a window that is separate from the resident buffer.
+/// If the array is resident, its resident data is copied. Otherwise, it is read directly from storage and
+/// the next chunk is prefetched with the same storage read, so only every second chunk requires a storage access.
+/// Only if `access_counters` or `object_checksums` is enabled, the array is made resident to count the access
+/// and verify its checksum.
+pub struct VNVArrayChunks<
+    'r,
+    'a,
+    'b: 'a,
+    T: Sized + Copy,
+    const SIZE: usize,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    array: &'r mut VNVArray<'a, 'b, T, SIZE, A, N, M>,
+    chunk_len: usize,
+    window: Vec<MaybeUninit<T>>,
+    /// index of the first element in `window`
+    window_start: usize,
+    /// how many elements of `window` are valid
+    window_len: usize,
+    /// index of the first element of the next chunk
+    next: usize,
+}
+
+impl<
+        T: Sized + Copy,
+        const SIZE: usize,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > VNVArrayChunks<'_, '_, '_, T, SIZE, A, N, M>
+{
+    /// Returns the next chunk or `None` if the whole array was visited
+    pub fn next(&mut self) -> Option<Result<&[T], VNVError>> {
+        if self.next >= SIZE {
+            return None;
+        }
+
+        let start = self.next;
+        let end = min(start + self.chunk_len, SIZE);
+        if end > self.window_start + self.window_len {
+            // load this chunk and prefetch the next one
+            let load_len = min(self.window.len(), SIZE - start);
+            if let Err(err) = self.array.read_range(start, &mut self.window[..load_len]) {
+                return Some(Err(err));
+            }
+            self.window_start = start;
+            self.window_len = load_len;
+        }
+
+        self.next = end;
+
+        let chunk = &self.window[(start - self.window_start)..(end - self.window_start)];
+        // all elements in the window were read from a valid `[T; SIZE]`
+        Some(Ok(unsafe { &*(chunk as *const [MaybeUninit<T>] as *const [T]) }))
+    }
+
+    /// Returns the index of the first element of the chunk that is returned by `next`
+    pub fn position(&self) -> usize {
+        self.next
+    }
+}
+
 impl<
         T: Sized + Copy,
         const SIZE: usize,
@@ -132,7 +333,7 @@ impl<
             // TODO handle this error somehow?
             match obj.deallocate(&self.allocation_identifier, true) {
                 Ok(()) => {}
-                Err(()) => {
+                Err(_) => {
                     println!("could not deallocate");
                 }
             }
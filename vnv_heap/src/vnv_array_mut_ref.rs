@@ -15,7 +15,7 @@
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use core::{cell::RefCell, ops::Deref, mem::size_of};
+use core::{cell::RefCell, ops::{Deref, Range}, mem::size_of};
 
 use crate::{
     modules::{
@@ -23,7 +23,8 @@ use crate::{
         object_management::ObjectManagementModule,
     },
     resident_object_manager::resident_object_metadata::ResidentObjectMetadata,
-    vnv_heap::VNVHeapInner,
+    vnv_error::VNVError,
+    vnv_heap::{release_on_drop, VNVHeapInner},
 };
 
 pub struct VNVArrayMutRef<
@@ -75,7 +76,7 @@ impl<
         M: ObjectManagementModule,
     > VNVArrayMutRef<'_, '_, '_, '_, T, SIZE, A, N, M>
 {
-    pub fn set(&mut self, index: usize, data: T) -> Result<(), ()> {
+    pub fn set(&mut self, index: usize, data: T) -> Result<(), VNVError> {
         let mut vnv_heap = self.vnv_heap.borrow_mut();
         let offset = index * size_of::<T>();
 
@@ -89,6 +90,17 @@ impl<
     pub fn get(&self, index: usize) -> T {
         self.data_ref[index]
     }
+
+    /// Makes the elements in `range` dirty and returns them
+    pub(crate) fn get_range_mut(&mut self, range: Range<usize>) -> Result<&mut [T], VNVError> {
+        let mut vnv_heap = self.vnv_heap.borrow_mut();
+        let offset = range.start * size_of::<T>();
+        let size = range.len() * size_of::<T>();
+
+        vnv_heap.partial_mut_make_range_dirty(self.meta_ref, offset, size)?;
+
+        Ok(&mut self.data_ref[range])
+    }
 }
 
 impl<
@@ -115,10 +127,6 @@ impl<
     > Drop for VNVArrayMutRef<'_, '_, '_, '_, T, SIZE, A, N, M>
 {
     fn drop(&mut self) {
-        unsafe {
-            self.vnv_heap
-            .borrow_mut()
-            .release_partial_mut::<[T; SIZE]>(self.meta_ref)
-        }
+        release_on_drop(self.vnv_heap, |heap| unsafe { heap.release_partial_mut::<[T; SIZE]>(self.meta_ref) });
     }
 }
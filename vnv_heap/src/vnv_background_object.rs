@@ -0,0 +1,99 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::ops::{Deref, DerefMut};
+
+use crate::{
+    modules::{
+        allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
+        object_management::ObjectManagementModule,
+    },
+    vnv_error::VNVError,
+    vnv_mut_ref::VNVMutRef,
+    vnv_object::VNVObject,
+};
+
+/// Object whose dirty user data belongs to the background dirty pool.
+///
+/// Modifying it with `get_mut` never exceeds the limit set with `VNVHeap::set_background_dirty_limit`
+/// (other background objects are synced instead), so bulk background work (e.g. logging) cannot use up
+/// the dirty budget that is needed by latency-critical foreground objects.
+///
+/// All other methods of `VNVObject` are available via `Deref`.
+pub struct VNVBackgroundObject<
+    'a,
+    'b: 'a,
+    T: Sized,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    inner: VNVObject<'a, 'b, T, A, N, M>,
+}
+
+impl<
+        'a,
+        'b: 'a,
+        T: Sized,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > VNVBackgroundObject<'a, 'b, T, A, N, M>
+{
+    pub(crate) fn new(object: VNVObject<'a, 'b, T, A, N, M>) -> Self {
+        Self { inner: object }
+    }
+
+    /// Same as `VNVObject::get_mut`, but the object is accounted to the background pool.
+    ///
+    /// Returns `VNVError::DirtyPoolExhausted` if not enough background objects could be synced.
+    pub fn get_mut(&mut self) -> Result<VNVMutRef<'a, '_, '_, 'b, T, A, N, M>, VNVError> {
+        let mut heap = self.inner.get_heap();
+        unsafe {
+            let ptr: *mut T = heap.get_mut_background(self.inner.get_alloc_id())?;
+            let data_ref = ptr.as_mut().unwrap();
+            Ok(VNVMutRef::new(
+                self.inner.get_heap_cell(),
+                self.inner.get_alloc_id(),
+                data_ref,
+            ))
+        }
+    }
+
+    /// Returns the underlying object (its next modification with `VNVObject::get_mut` is accounted to the foreground pool)
+    pub fn into_inner(self) -> VNVObject<'a, 'b, T, A, N, M> {
+        self.inner
+    }
+}
+
+impl<'a, 'b: 'a, T: Sized, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> Deref
+    for VNVBackgroundObject<'a, 'b, T, A, N, M>
+{
+    type Target = VNVObject<'a, 'b, T, A, N, M>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<'a, 'b: 'a, T: Sized, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> DerefMut
+    for VNVBackgroundObject<'a, 'b, T, A, N, M>
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
@@ -0,0 +1,184 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{
+    cell::Cell,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{
+    allocation_identifier::AllocationIdentifier,
+    modules::{
+        allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
+        object_management::ObjectManagementModule,
+    },
+    vnv_error::VNVError,
+    vnv_object::VNVObject,
+};
+
+enum BoxPin<T> {
+    None,
+    Ref(*const T),
+    Mut(*mut T),
+}
+
+// derive would require `T: Copy`
+impl<T> Clone for BoxPin<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for BoxPin<T> {}
+
+/// Owning pointer to an object of the heap that can be used like a normal reference.
+///
+/// The first access via `Deref` or `DerefMut` makes the object resident and pins it (it will not be unloaded)
+/// until `unpin` is called or the box is dropped. A mutable access marks the object as dirty, as `VNVObject::get_mut` does.
+///
+/// **Panics** if the object cannot be made resident on access (e.g. the resident buffer or the dirty budget is exhausted).
+/// Use `get` or `get_mut` of `VNVObject` if this should be handled.
+pub struct VNVBox<
+    'a,
+    'b: 'a,
+    T: Sized,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    inner: VNVObject<'a, 'b, T, A, N, M>,
+    pin: Cell<BoxPin<T>>,
+}
+
+impl<
+        'a,
+        'b: 'a,
+        T: Sized,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > VNVBox<'a, 'b, T, A, N, M>
+{
+    pub fn new(object: VNVObject<'a, 'b, T, A, N, M>) -> Self {
+        Self {
+            inner: object,
+            pin: Cell::new(BoxPin::None),
+        }
+    }
+
+    /// Releases the pin, so that the object can be unloaded again
+    pub fn unpin(&mut self) {
+        let mut heap = self.inner.get_heap();
+        let alloc_id = self.inner.get_alloc_id();
+
+        match self.pin.replace(BoxPin::None) {
+            BoxPin::None => {}
+            BoxPin::Ref(_) => unsafe { heap.release_ref(alloc_id) },
+            BoxPin::Mut(_) => unsafe { heap.release_mut(alloc_id) },
+        }
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        !matches!(self.pin.get(), BoxPin::None)
+    }
+
+    /// Releases the pin and unloads the object
+    pub fn unload(&mut self) -> Result<(), VNVError> {
+        self.unpin();
+        self.inner.unload()
+    }
+
+    pub fn is_resident(&self) -> bool {
+        self.inner.is_resident()
+    }
+
+    #[allow(unused)]
+    pub(crate) fn get_alloc_id(&self) -> &AllocationIdentifier<T> {
+        self.inner.get_alloc_id()
+    }
+
+    /// Releases the pin and returns the underlying object
+    pub fn into_inner(mut self) -> VNVObject<'a, 'b, T, A, N, M> {
+        self.unpin();
+
+        // self was unpinned, so nothing else has to be dropped
+        let this = core::mem::ManuallyDrop::new(self);
+        unsafe { core::ptr::read(&this.inner) }
+    }
+}
+
+impl<T: Sized, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> Deref
+    for VNVBox<'_, '_, T, A, N, M>
+{
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        let ptr = match self.pin.get() {
+            BoxPin::Ref(ptr) => ptr,
+            BoxPin::Mut(ptr) => ptr as *const T,
+            BoxPin::None => {
+                let mut heap = self.inner.get_heap();
+                let ptr = unsafe { heap.get_ref(self.inner.get_alloc_id(), false) }
+                    .expect("could not make object resident");
+
+                self.pin.set(BoxPin::Ref(ptr));
+                ptr
+            }
+        };
+
+        unsafe { ptr.as_ref().unwrap() }
+    }
+}
+
+impl<T: Sized, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> DerefMut
+    for VNVBox<'_, '_, T, A, N, M>
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        let ptr = match self.pin.get() {
+            BoxPin::Mut(ptr) => ptr,
+            pin => {
+                let mut heap = self.inner.get_heap();
+                let alloc_id = self.inner.get_alloc_id();
+
+                if let BoxPin::Ref(_) = pin {
+                    // upgrade to a mutable pin, no references are left as we have `&mut self`
+                    unsafe { heap.release_ref(alloc_id) };
+                    self.pin.set(BoxPin::None);
+                }
+
+                let ptr = unsafe { heap.get_mut(alloc_id, false) }
+                    .expect("could not make object resident");
+
+                self.pin.set(BoxPin::Mut(ptr));
+                ptr
+            }
+        };
+
+        unsafe { ptr.as_mut().unwrap() }
+    }
+}
+
+impl<T: Sized, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> Drop
+    for VNVBox<'_, '_, T, A, N, M>
+{
+    fn drop(&mut self) {
+        // release the pin before the object is deallocated by `VNVObject`
+        if self.is_pinned() {
+            self.unpin();
+        }
+    }
+}
@@ -0,0 +1,127 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::cell::RefCell;
+
+use crate::{
+    modules::{
+        allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
+        object_management::ObjectManagementModule,
+    },
+    vnv_bytes_mut_ref::VNVBytesMutRef,
+    vnv_bytes_ref::VNVBytesRef,
+    vnv_error::VNVError,
+    vnv_heap::VNVHeapInner,
+};
+
+/// Byte buffer whose length is chosen when it is allocated (see `VNVHeap::allocate_bytes`).
+///
+/// Behaves like a `VNVObject`, but `get` and `get_mut` return slices.
+/// Making the buffer dirty uses as many dirty bytes as the buffer is long.
+pub struct VNVBytes<
+    'a,
+    'b: 'a,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>,
+    offset: usize,
+    len: usize,
+}
+
+impl<
+        'a,
+        'b: 'a,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > VNVBytes<'a, 'b, A, N, M>
+{
+    pub(crate) fn new(vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>, offset: usize, len: usize) -> Self {
+        VNVBytes {
+            vnv_heap,
+            offset,
+            len,
+        }
+    }
+
+    /// Returns the length of this buffer in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&mut self) -> Result<VNVBytesRef<'a, '_, 'b, A, N, M>, VNVError> {
+        let mut heap = self.vnv_heap.borrow_mut();
+        unsafe {
+            let ptr = heap.get_bytes_ref(self.offset, self.len)?;
+            Ok(VNVBytesRef::new(self.vnv_heap, self.offset, ptr.as_ref().unwrap()))
+        }
+    }
+
+    pub fn get_mut(&mut self) -> Result<VNVBytesMutRef<'a, '_, 'b, A, N, M>, VNVError> {
+        let mut heap = self.vnv_heap.borrow_mut();
+        unsafe {
+            let ptr = heap.get_bytes_mut(self.offset, self.len)?;
+            Ok(VNVBytesMutRef::new(self.vnv_heap, self.offset, ptr.as_mut().unwrap()))
+        }
+    }
+
+    pub fn is_resident(&self) -> bool {
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.is_bytes_resident(self.offset)
+    }
+
+    pub fn is_data_dirty(&self) -> bool {
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.is_bytes_data_dirty(self.offset)
+    }
+
+    pub fn unload(&mut self) -> Result<(), VNVError> {
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.unload_bytes(self.offset)
+    }
+
+    /// Resizes this buffer to `new_len` bytes, bytes that are added are zero.
+    ///
+    /// The data is moved to a new region in storage, so the buffer is unloaded first.
+    /// If this fails (e.g. because the storage is exhausted), the buffer is left unchanged.
+    pub fn resize(&mut self, new_len: usize) -> Result<(), VNVError> {
+        let mut heap = self.vnv_heap.borrow_mut();
+        self.offset = heap.resize_bytes(self.offset, self.len, new_len)?;
+        self.len = new_len;
+        Ok(())
+    }
+}
+
+impl<A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> Drop
+    for VNVBytes<'_, '_, A, N, M>
+{
+    fn drop(&mut self) {
+        let mut heap = self.vnv_heap.borrow_mut();
+        match heap.deallocate_bytes(self.offset, self.len) {
+            Ok(()) => {}
+            Err(_) => {
+                println!("could not deallocate");
+            }
+        }
+    }
+}
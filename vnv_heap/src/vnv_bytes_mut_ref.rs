@@ -0,0 +1,87 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{cell::RefCell, ops::{Deref, DerefMut}};
+
+use crate::{
+    modules::{
+        allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
+        object_management::ObjectManagementModule,
+    },
+    vnv_heap::{release_on_drop, VNVHeapInner},
+};
+
+pub struct VNVBytesMutRef<
+    'a,
+    'b,
+    'c: 'a,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    vnv_heap: &'a RefCell<VNVHeapInner<'c, A, N, M>>,
+    offset: usize,
+    data_ref: &'b mut [u8],
+}
+
+impl<
+        'a,
+        'b,
+        'c: 'a,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > VNVBytesMutRef<'a, 'b, 'c, A, N, M>
+{
+    pub(crate) unsafe fn new(
+        vnv_heap: &'a RefCell<VNVHeapInner<'c, A, N, M>>,
+        offset: usize,
+        data_ref: &'b mut [u8],
+    ) -> Self {
+        VNVBytesMutRef {
+            vnv_heap,
+            offset,
+            data_ref,
+        }
+    }
+}
+
+impl<A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> Deref
+    for VNVBytesMutRef<'_, '_, '_, A, N, M>
+{
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.data_ref
+    }
+}
+
+impl<A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> DerefMut
+    for VNVBytesMutRef<'_, '_, '_, A, N, M>
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.data_ref
+    }
+}
+
+impl<A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> Drop
+    for VNVBytesMutRef<'_, '_, '_, A, N, M>
+{
+    fn drop(&mut self) {
+        release_on_drop(self.vnv_heap, |heap| unsafe { heap.release_bytes_mut(self.offset) });
+    }
+}
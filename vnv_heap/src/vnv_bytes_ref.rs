@@ -0,0 +1,79 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{cell::RefCell, ops::Deref};
+
+use crate::{
+    modules::{
+        allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
+        object_management::ObjectManagementModule,
+    },
+    vnv_heap::{release_on_drop, VNVHeapInner},
+};
+
+pub struct VNVBytesRef<
+    'a,
+    'b,
+    'c: 'a,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    vnv_heap: &'a RefCell<VNVHeapInner<'c, A, N, M>>,
+    offset: usize,
+    data_ref: &'b [u8],
+}
+
+impl<
+        'a,
+        'b,
+        'c: 'a,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > VNVBytesRef<'a, 'b, 'c, A, N, M>
+{
+    pub(crate) unsafe fn new(
+        vnv_heap: &'a RefCell<VNVHeapInner<'c, A, N, M>>,
+        offset: usize,
+        data_ref: &'b [u8],
+    ) -> Self {
+        VNVBytesRef {
+            vnv_heap,
+            offset,
+            data_ref,
+        }
+    }
+}
+
+impl<A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> Deref
+    for VNVBytesRef<'_, '_, '_, A, N, M>
+{
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.data_ref
+    }
+}
+
+impl<A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> Drop
+    for VNVBytesRef<'_, '_, '_, A, N, M>
+{
+    fn drop(&mut self) {
+        release_on_drop(self.vnv_heap, |heap| unsafe { heap.release_bytes_ref(self.offset) });
+    }
+}
@@ -0,0 +1,216 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{
+    cell::{RefCell, UnsafeCell},
+    marker::PhantomData,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    allocation_identifier::AllocationIdentifier,
+    modules::{
+        allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
+        object_management::ObjectManagementModule,
+    },
+    vnv_error::VNVError,
+    vnv_heap::VNVHeapInner,
+};
+
+/// Ring buffer of a `VNVChannel`, this is the object that is stored in the heap
+pub(crate) struct ChannelBuffer<T: Sized + Copy, const CAP: usize> {
+    /// Number of consumed items (wraps around)
+    head: AtomicUsize,
+    /// Number of produced items (wraps around)
+    tail: AtomicUsize,
+    slots: [UnsafeCell<MaybeUninit<T>>; CAP],
+}
+
+// slots are only accessed by one producer and one consumer, which are synchronized with `head` and `tail`
+unsafe impl<T: Sized + Copy + Send, const CAP: usize> Sync for ChannelBuffer<T, CAP> {}
+
+impl<T: Sized + Copy, const CAP: usize> ChannelBuffer<T, CAP> {
+    fn new() -> Self {
+        Self {
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            slots: [(); CAP].map(|_| UnsafeCell::new(MaybeUninit::uninit())),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.tail
+            .load(Ordering::Acquire)
+            .wrapping_sub(self.head.load(Ordering::Acquire))
+    }
+}
+
+/// A bounded single-producer single-consumer channel whose items are persisted with the heap.
+///
+/// Other than `VNVQueue`, items can be produced from an interrupt service routine (see `split`):
+/// The ring buffer of the channel is made resident and dirty when the channel is created and stays this way
+/// until the channel is dropped. So it occupies its size in the resident buffer and the dirty budget all the time,
+/// but producing and consuming items never has to access the heap (no locks, no evictions, no storage accesses).
+pub struct VNVChannel<
+    'a,
+    'b: 'a,
+    T: Sized + Copy + Send,
+    const CAP: usize,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>,
+    pub(crate) allocation_identifier: AllocationIdentifier<ChannelBuffer<T, CAP>>,
+    /// resident ring buffer, stays valid as the mutable reference is not released before the channel is dropped
+    buffer: *const ChannelBuffer<T, CAP>,
+}
+
+impl<
+        'a,
+        'b: 'a,
+        T: Sized + Copy + Send,
+        const CAP: usize,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > VNVChannel<'a, 'b, T, CAP, A, N, M>
+{
+    pub(crate) fn new(vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>) -> Result<Self, VNVError> {
+        assert!(CAP > 0, "channel has to hold at least one item");
+
+        let mut heap = vnv_heap.borrow_mut();
+        let identifier = unsafe { heap.allocate(ChannelBuffer::<T, CAP>::new(), false)? };
+
+        // keep the buffer resident and reserve its dirty bytes for the lifetime of the channel
+        let buffer = match unsafe { heap.get_mut(&identifier, false) } {
+            Ok(buffer) => buffer,
+            Err(err) => {
+                unsafe { heap.deallocate(&identifier, false)? };
+                return Err(err);
+            }
+        };
+
+        Ok(Self {
+            vnv_heap,
+            allocation_identifier: identifier,
+            buffer,
+        })
+    }
+
+    /// Splits the channel into its producer and consumer.
+    ///
+    /// The producer can be moved to an interrupt service routine (or another thread).
+    pub fn split(&mut self) -> (VNVChannelProducer<'_, T, CAP>, VNVChannelConsumer<'_, T, CAP>) {
+        let buffer = self.buffer();
+        (
+            VNVChannelProducer { buffer },
+            VNVChannelConsumer {
+                buffer,
+                phantom_data: PhantomData,
+            },
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub const fn capacity(&self) -> usize {
+        CAP
+    }
+
+    fn buffer(&self) -> &ChannelBuffer<T, CAP> {
+        unsafe { self.buffer.as_ref().unwrap() }
+    }
+}
+
+impl<T: Sized + Copy + Send, const CAP: usize, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule>
+    Drop for VNVChannel<'_, '_, T, CAP, A, N, M>
+{
+    fn drop(&mut self) {
+        let mut heap = self.vnv_heap.borrow_mut();
+        unsafe {
+            heap.release_mut(&self.allocation_identifier);
+            if heap.deallocate(&self.allocation_identifier, false).is_err() {
+                println!("could not deallocate");
+            }
+        }
+    }
+}
+
+/// Producing half of a `VNVChannel`, which can be used from an interrupt service routine
+pub struct VNVChannelProducer<'c, T: Sized + Copy + Send, const CAP: usize> {
+    buffer: &'c ChannelBuffer<T, CAP>,
+}
+
+impl<T: Sized + Copy + Send, const CAP: usize> VNVChannelProducer<'_, T, CAP> {
+    /// Appends `item` to the channel.
+    ///
+    /// This is lock-free and never accesses the heap. Returns `item` again if the channel is full.
+    pub fn produce(&mut self, item: T) -> Result<(), T> {
+        let tail = self.buffer.tail.load(Ordering::Relaxed);
+        let head = self.buffer.head.load(Ordering::Acquire);
+        if tail.wrapping_sub(head) == CAP {
+            return Err(item);
+        }
+
+        unsafe { (*self.buffer.slots[tail % CAP].get()).write(item) };
+        self.buffer.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.buffer.len() == CAP
+    }
+}
+
+/// Consuming half of a `VNVChannel`
+pub struct VNVChannelConsumer<'c, T: Sized + Copy + Send, const CAP: usize> {
+    buffer: &'c ChannelBuffer<T, CAP>,
+    /// the consumer stays in the context of the heap
+    phantom_data: PhantomData<*const ()>,
+}
+
+impl<T: Sized + Copy + Send, const CAP: usize> VNVChannelConsumer<'_, T, CAP> {
+    /// Removes the oldest item from the channel and returns it
+    pub fn consume(&mut self) -> Option<T> {
+        let head = self.buffer.head.load(Ordering::Relaxed);
+        let tail = self.buffer.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+
+        // the slot was written by the producer before `tail` was updated
+        let item = unsafe { (*self.buffer.slots[head % CAP].get()).assume_init() };
+        self.buffer.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(item)
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,219 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{
+    alloc::Layout,
+    cell::RefCell,
+    marker::PhantomData,
+    mem::{size_of, MaybeUninit},
+    ptr::slice_from_raw_parts_mut,
+};
+
+use crate::{
+    modules::{
+        allocator::AllocatorModule, compression::CompressionModule,
+        nonresident_allocator::NonResidentAllocatorModule, object_management::ObjectManagementModule,
+    },
+    vnv_error::VNVError,
+    vnv_heap::VNVHeapInner,
+};
+
+/// An object that is stored compressed in non-volatile storage and is never made resident.
+///
+/// Its value only exists temporarily on the stack while accessing it, so every access costs a storage
+/// transaction and (de)compressing the object. This is meant for big and compressible (e.g. sparse) data.
+///
+/// The compressed image is stored in an extent that has the size of the compressed data.
+/// If the compressed size grows beyond the extent or shrinks to less than half of it, a new extent is allocated.
+/// If the data does not compress, it is stored uncompressed.
+pub struct VNVCompressedObject<
+    'a,
+    'b: 'a,
+    T: Sized + Copy,
+    C: CompressionModule,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>,
+    compression: C,
+    /// offset of the extent in storage
+    offset: usize,
+    /// size of the extent as requested from the non-resident allocator
+    extent_size: usize,
+    /// number of bytes of the extent that are used, `size_of::<T>()` if the data is not compressed
+    stored_size: usize,
+    phantom_data: PhantomData<T>,
+}
+
+impl<
+        'a,
+        'b: 'a,
+        T: Sized + Copy,
+        C: CompressionModule,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > VNVCompressedObject<'a, 'b, T, C, A, N, M>
+{
+    pub(crate) fn new(
+        vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>,
+        compression: C,
+        initial_value: T,
+    ) -> Result<Self, VNVError> {
+        assert!(size_of::<T>() != 0, "zero sized types are not supported");
+
+        let mut buffer = MaybeUninit::<T>::uninit();
+        let compressed = compress(&compression, &initial_value, &mut buffer);
+
+        let mut heap = vnv_heap.borrow_mut();
+        let offset = heap.allocate_storage(Self::calc_layout(compressed.len()))?;
+        if let Err(err) = heap.write_storage(offset, compressed) {
+            heap.deallocate_storage(offset, Self::calc_layout(compressed.len()))?;
+            return Err(err);
+        }
+        drop(heap);
+
+        Ok(Self {
+            vnv_heap,
+            compression,
+            offset,
+            extent_size: compressed.len(),
+            stored_size: compressed.len(),
+            phantom_data: PhantomData,
+        })
+    }
+
+    /// Reads and decompresses this object and returns a copy of its value.
+    ///
+    /// Returns an error if the data could not be decompressed (e.g. it was modified in storage).
+    pub fn get(&self) -> Result<T, VNVError> {
+        let mut buffer = MaybeUninit::<T>::uninit();
+        let stored = &mut as_bytes(&mut buffer)[..self.stored_size];
+        self.vnv_heap.borrow_mut().read_storage(self.offset, stored)?;
+
+        if self.stored_size == size_of::<T>() {
+            // data is stored uncompressed
+            return Ok(unsafe { buffer.assume_init() });
+        }
+
+        let mut value = MaybeUninit::<T>::uninit();
+        self.compression
+            .decompress(stored, as_bytes(&mut value))
+            .map_err(|()| VNVError::CorruptedData)?;
+
+        // the data was compressed from a valid `T`
+        Ok(unsafe { value.assume_init() })
+    }
+
+    /// Compresses `value` and replaces the value of this object.
+    ///
+    /// A new extent is allocated if the compressed data does not fit into the current one
+    /// or takes less than half of it. The old extent is only freed after the new one was written.
+    pub fn set(&mut self, value: T) -> Result<(), VNVError> {
+        let mut buffer = MaybeUninit::<T>::uninit();
+        let compressed = compress(&self.compression, &value, &mut buffer);
+
+        let mut heap = self.vnv_heap.borrow_mut();
+        let capacity = N::ALLOCATION_ROUNDING.apply(self.extent_size);
+        if compressed.len() <= capacity && compressed.len() * 2 > capacity {
+            heap.write_storage(self.offset, compressed)?;
+            self.stored_size = compressed.len();
+            return Ok(());
+        }
+
+        let new_layout = Self::calc_layout(compressed.len());
+        let new_offset = heap.allocate_storage(new_layout)?;
+        if let Err(err) = heap.write_storage(new_offset, compressed) {
+            heap.deallocate_storage(new_offset, new_layout)?;
+            return Err(err);
+        }
+
+        let old_offset = self.offset;
+        let old_layout = Self::calc_layout(self.extent_size);
+        self.offset = new_offset;
+        self.extent_size = compressed.len();
+        self.stored_size = compressed.len();
+
+        heap.deallocate_storage(old_offset, old_layout)
+    }
+
+    /// Reads this object, calls `func` to modify it and writes it back afterwards.
+    pub fn update<F: FnOnce(&mut T)>(&mut self, func: F) -> Result<(), VNVError> {
+        let mut value = self.get()?;
+        func(&mut value);
+        self.set(value)
+    }
+
+    /// Returns the number of bytes the current value takes in storage
+    pub fn get_stored_size(&self) -> usize {
+        self.stored_size
+    }
+
+    /// Returns the number of bytes that are allocated in storage for this object
+    pub fn get_allocated_size(&self) -> usize {
+        N::ALLOCATION_ROUNDING.apply(self.extent_size)
+    }
+
+    /// Returns `true` if the current value is stored compressed
+    pub fn is_compressed(&self) -> bool {
+        self.stored_size < size_of::<T>()
+    }
+
+    fn calc_layout(size: usize) -> Layout {
+        Layout::from_size_align(size, 1).unwrap()
+    }
+}
+
+impl<T: Sized + Copy, C: CompressionModule, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule>
+    Drop for VNVCompressedObject<'_, '_, T, C, A, N, M>
+{
+    fn drop(&mut self) {
+        let mut heap = self.vnv_heap.borrow_mut();
+        if heap.deallocate_storage(self.offset, Self::calc_layout(self.extent_size)).is_err() {
+            println!("could not deallocate");
+        }
+    }
+}
+
+/// Compresses `value` into `buffer` and returns the data that should be stored
+fn compress<'c, T: Sized + Copy, C: CompressionModule>(
+    compression: &C,
+    value: &T,
+    buffer: &'c mut MaybeUninit<T>,
+) -> &'c [u8] {
+    let mut src = MaybeUninit::new(*value);
+    let src = as_bytes(&mut src);
+    let dest = as_bytes(buffer);
+
+    // only keep the compressed data if it is actually smaller
+    match compression.compress(src, &mut dest[..size_of::<T>() - 1]) {
+        Some(len) => &dest[..len],
+        None => {
+            dest.copy_from_slice(src);
+            dest
+        }
+    }
+}
+
+fn as_bytes<T: Sized>(data: &mut MaybeUninit<T>) -> &mut [u8] {
+    unsafe {
+        slice_from_raw_parts_mut(data.as_mut_ptr() as *mut u8, size_of::<T>())
+            .as_mut()
+            .unwrap()
+    }
+}
@@ -16,5 +16,83 @@
  */
 
 pub struct VNVConfig {
-    pub max_dirty_bytes: usize
+    /// How many bytes can be dirty at the same time (i.e. have to be written back while persisting)
+    pub max_dirty_bytes: usize,
+
+    /// When modified objects are written back to storage
+    pub write_back: WriteBack,
+
+    /// If set, the dirty bytes are limited further, so that persisting does not take longer than this budget.
+    ///
+    /// `max_dirty_bytes` is still the upper bound, as the storage region for persisting is reserved once.
+    pub persist_latency_budget: Option<PersistLatencyBudget>,
+}
+
+/// Maximum time persisting may take (see `VNVConfig::persist_latency_budget`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PersistLatencyBudget {
+    /// Maximum time persisting may take in µs
+    pub max_latency_us: u32,
+
+    /// How fast the persistent storage module can be written to
+    pub write_throughput: StorageThroughput,
+}
+
+impl PersistLatencyBudget {
+    /// Returns how many bytes can be persisted within this budget
+    pub const fn max_persisted_bytes(&self) -> usize {
+        self.write_throughput.max_bytes(self.max_latency_us)
+    }
+}
+
+/// Linear model of how long transferring `n` bytes from or to a persistent storage module takes:
+/// `fixed_latency_us` µs plus `n / bytes_per_ms` ms.
+///
+/// Measure it on the target device (e.g. with `VNVHeap::calibrate_storage`),
+/// as it depends on the storage module (and e.g. its bus speed).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageThroughput {
+    /// Time that is needed independent of the number of bytes in µs (e.g. for flushing)
+    pub fixed_latency_us: u32,
+
+    /// How many bytes can be transferred per millisecond
+    pub bytes_per_ms: u32,
+}
+
+impl StorageThroughput {
+    /// Returns how many bytes can be transferred within `latency_us`
+    pub const fn max_bytes(&self, latency_us: u32) -> usize {
+        let available_us = latency_us.saturating_sub(self.fixed_latency_us) as u64;
+        (available_us * self.bytes_per_ms as u64 / 1000) as usize
+    }
+
+    /// Returns how long transferring `bytes` takes in µs
+    pub const fn transfer_time_us(&self, bytes: usize) -> u64 {
+        self.fixed_latency_us as u64 + (bytes as u64 * 1000).div_ceil(self.bytes_per_ms as u64)
+    }
+}
+
+/// Read and write throughput of a persistent storage module (see `VNVHeap::calibrate_storage`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageTiming {
+    pub read: StorageThroughput,
+
+    /// Includes flushing the storage module after writing
+    pub write: StorageThroughput,
+}
+
+/// When modified (dirty) objects are written back to persistent storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WriteBack {
+    /// Dirty objects are only written back if the object management module
+    /// needs space or the heap is persisted
+    #[default]
+    Lazy,
+
+    /// An object is written back as soon as its mutable reference is released
+    OnRelease,
+
+    /// Like `OnRelease`, but the persistent storage module is flushed afterwards,
+    /// so the changes have reached the storage device once the reference is released
+    Immediate,
 }
\ No newline at end of file
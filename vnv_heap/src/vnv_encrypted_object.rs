@@ -0,0 +1,216 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use core::{
+    mem::{size_of, MaybeUninit},
+    ptr::slice_from_raw_parts_mut,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    allocation_identifier::AllocationIdentifier,
+    modules::{
+        allocator::AllocatorModule,
+        nonresident_allocator::NonResidentAllocatorModule,
+        object_encryption::{
+            ObjectEncryptionKey, ObjectEncryptionModule, ObjectEncryptionNonce, ObjectEncryptionTag,
+        },
+        object_management::ObjectManagementModule,
+    },
+    vnv_error::VNVError,
+    vnv_object::VNVObject,
+};
+
+/// Highest nonce counter that was used since the last restart
+static NONCE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns a nonce counter that is higher than `stored_counter` (the counter that is stored with the object)
+/// and higher than all counters that were used since the last restart.
+///
+/// `NONCE_COUNTER` restarts at 0 after a reboot, but the stored counter is persistent.
+/// So the object never reuses a nonce with its key, even across reboots.
+fn next_nonce_counter(stored_counter: u64) -> Result<u64, VNVError> {
+    // a nonce must never be reused
+    let stored_counter = usize::try_from(stored_counter).map_err(|_| VNVError::Unsupported)?;
+
+    NONCE_COUNTER
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |curr| curr.max(stored_counter).checked_add(1))
+        .map(|prev| (prev.max(stored_counter) + 1) as u64)
+        .map_err(|_| VNVError::Unsupported)
+}
+
+/// Layout of an encrypted object, both in RAM and in storage
+#[repr(C)]
+pub(crate) struct EncryptedData<T: Sized> {
+    nonce_counter: u64,
+    tag: ObjectEncryptionTag,
+    /// ciphertext, this never contains a valid `T`
+    data: MaybeUninit<T>,
+}
+
+impl<T: Sized> EncryptedData<T> {
+    pub(crate) const fn empty() -> Self {
+        Self {
+            nonce_counter: 0,
+            tag: [0; 16],
+            data: MaybeUninit::uninit(),
+        }
+    }
+}
+
+/// An object that is encrypted and authenticated with its own key.
+///
+/// Its plaintext only exists temporarily on the stack while accessing it.
+/// The resident copy, the persisted state, and the data in storage only contain ciphertext.
+/// As accessing this object requires decrypting it each time, only use it for small and sensitive data (e.g. credentials).
+pub struct VNVEncryptedObject<
+    'a,
+    'b: 'a,
+    T: Sized + Copy,
+    E: ObjectEncryptionModule,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    inner: VNVObject<'a, 'b, EncryptedData<T>, A, N, M>,
+    key: ObjectEncryptionKey,
+    encryption: E,
+}
+
+impl<
+        'a,
+        'b: 'a,
+        T: Sized + Copy,
+        E: ObjectEncryptionModule,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > VNVEncryptedObject<'a, 'b, T, E, A, N, M>
+{
+    /// Encrypts `initial_value` and stores it in the (not yet initialized) object `inner`
+    pub(crate) fn new(
+        inner: VNVObject<'a, 'b, EncryptedData<T>, A, N, M>,
+        key: ObjectEncryptionKey,
+        encryption: E,
+        initial_value: T,
+    ) -> Result<Self, VNVError> {
+        let mut obj = Self {
+            inner,
+            key,
+            encryption,
+        };
+        obj.set(initial_value)?;
+        Ok(obj)
+    }
+
+    /// Decrypts this object and returns a copy of its value.
+    ///
+    /// Returns an error if the data could not be authenticated (e.g. it was modified in storage).
+    pub fn get(&mut self) -> Result<T, VNVError> {
+        let nonce = self.get_nonce_base();
+        let obj_ref = self.inner.get()?;
+
+        let mut data = obj_ref.data;
+        let nonce = Self::finish_nonce(nonce, obj_ref.nonce_counter);
+
+        self.encryption.decrypt(
+            &self.key,
+            &nonce,
+            &Self::get_associated_data(),
+            Self::as_bytes(&mut data),
+            &obj_ref.tag,
+        )
+        .map_err(|()| VNVError::CorruptedData)?;
+
+        Ok(unsafe { data.assume_init() })
+    }
+
+    /// Encrypts `value` with a fresh nonce and replaces the value of this object.
+    pub fn set(&mut self, value: T) -> Result<(), VNVError> {
+        let nonce_base = self.get_nonce_base();
+        let mut obj_ref = self.inner.get_mut()?;
+
+        let nonce_counter = next_nonce_counter(obj_ref.nonce_counter)?;
+        let nonce = Self::finish_nonce(nonce_base, nonce_counter);
+
+        let mut data = MaybeUninit::new(value);
+        let tag = self.encryption.encrypt(
+            &self.key,
+            &nonce,
+            &Self::get_associated_data(),
+            Self::as_bytes(&mut data),
+        );
+
+        obj_ref.nonce_counter = nonce_counter;
+        obj_ref.tag = tag;
+        obj_ref.data = data;
+
+        Ok(())
+    }
+
+    /// Decrypts this object, calls `func` to modify it and encrypts it again afterwards.
+    pub fn update<F: FnOnce(&mut T)>(&mut self, func: F) -> Result<(), VNVError> {
+        let mut value = self.get()?;
+        func(&mut value);
+        self.set(value)
+    }
+
+    pub fn is_resident(&self) -> bool {
+        self.inner.is_resident()
+    }
+
+    pub fn is_data_dirty(&self) -> bool {
+        self.inner.is_data_dirty()
+    }
+
+    pub fn unload(&mut self) -> Result<(), VNVError> {
+        self.inner.unload()
+    }
+
+    pub fn flush(&mut self) -> Result<usize, VNVError> {
+        self.inner.flush()
+    }
+
+    #[allow(unused)]
+    pub(crate) fn get_alloc_id(&self) -> &AllocationIdentifier<EncryptedData<T>> {
+        self.inner.get_alloc_id()
+    }
+
+    /// The first half of the nonce is the location of this object.
+    /// This way, ciphertext cannot be moved to another object.
+    fn get_nonce_base(&self) -> ObjectEncryptionNonce {
+        let mut nonce = [0u8; 16];
+        nonce[0..8].copy_from_slice(&(self.inner.get_alloc_id().offset as u64).to_le_bytes());
+        nonce
+    }
+
+    fn finish_nonce(mut nonce: ObjectEncryptionNonce, nonce_counter: u64) -> ObjectEncryptionNonce {
+        nonce[8..16].copy_from_slice(&nonce_counter.to_le_bytes());
+        nonce
+    }
+
+    fn get_associated_data() -> [u8; 8] {
+        (size_of::<T>() as u64).to_le_bytes()
+    }
+
+    fn as_bytes(data: &mut MaybeUninit<T>) -> &mut [u8] {
+        unsafe {
+            slice_from_raw_parts_mut(data.as_mut_ptr() as *mut u8, size_of::<T>())
+                .as_mut()
+                .unwrap()
+        }
+    }
+}
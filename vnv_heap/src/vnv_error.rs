@@ -0,0 +1,97 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::fmt::Display;
+
+/// Error of a fallible operation of a heap or one of its objects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VNVError {
+    /// There is not enough space in the resident buffer to make the object resident
+    /// (see `VNVHeap::get_last_resident_exhaustion` for details)
+    ResidentBufferExhausted,
+
+    /// There is not enough space in the resident buffer and all resident objects are in use (or pinned),
+    /// so none of them can be unloaded (see `VNVHeap::reserve_emergency_region`)
+    ResidentObjectsInUse,
+
+    /// Pinning the object would exceed the resident buffer (see `VNVObject::pin`)
+    PinnedBytesExhausted,
+
+    /// Not enough dirty bytes could be made available (by syncing other objects) to modify the object
+    DirtyBudgetExhausted,
+
+    /// The background objects that are in use already have more dirty bytes than the background pool allows
+    /// (see `VNVHeap::set_background_dirty_limit`)
+    DirtyPoolExhausted,
+
+    /// There is not enough space left in non-volatile storage
+    NonResidentSpaceExhausted,
+
+    /// The persistent storage module could not read or write data
+    StorageError,
+
+    /// The stored data of the object is corrupted (e.g. its checksum or authentication tag does not match)
+    CorruptedData,
+
+    /// The object is still in use (i.e. there is a reference to it)
+    ObjectInUse,
+
+    /// The object is not resident and the operation does not load it (see `VNVObject::try_get_resident`)
+    NotResident,
+
+    /// All roots of the heap are used already (see `VNVHeap::set_root`)
+    RootsExhausted,
+
+    /// The data structure or table is full (see `VNVQueue::produce` or `VNVObject::set_persist_priority`)
+    CapacityExhausted,
+
+    /// The operation is not supported for this object
+    Unsupported,
+
+    /// The data could not be serialized or deserialized (see `VNVHeap::allocate_from_deserialize`)
+    SerializationFailed,
+}
+
+/// Modules (e.g. `PersistentStorageModule`) report their errors with `()`,
+/// which is treated as storage error if it is not handled otherwise
+impl From<()> for VNVError {
+    fn from(_: ()) -> Self {
+        VNVError::StorageError
+    }
+}
+
+impl Display for VNVError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let msg = match self {
+            VNVError::ResidentBufferExhausted => "resident buffer is exhausted",
+            VNVError::ResidentObjectsInUse => "all resident objects are in use",
+            VNVError::PinnedBytesExhausted => "pinned objects would exceed the resident buffer",
+            VNVError::DirtyBudgetExhausted => "dirty budget is exhausted",
+            VNVError::DirtyPoolExhausted => "background dirty pool is exhausted",
+            VNVError::NonResidentSpaceExhausted => "non-volatile storage is exhausted",
+            VNVError::StorageError => "storage error",
+            VNVError::CorruptedData => "object data is corrupted",
+            VNVError::ObjectInUse => "object is still in use",
+            VNVError::NotResident => "object is not resident",
+            VNVError::RootsExhausted => "all roots are used",
+            VNVError::CapacityExhausted => "capacity is exhausted",
+            VNVError::Unsupported => "operation is not supported for this object",
+            VNVError::SerializationFailed => "data could not be serialized or deserialized",
+        };
+        f.write_str(msg)
+    }
+}
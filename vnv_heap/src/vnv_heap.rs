@@ -16,33 +16,103 @@
  */
 
 use log::trace;
-use try_lock::TryLock;
 
 use crate::{
-    allocation_identifier::AllocationIdentifier, modules::{
-        allocator::AllocatorModule,
-        nonresident_allocator::NonResidentAllocatorModule,
+    allocation_identifier::AllocationIdentifier, heap_snapshot::{SnapshotHeader, SNAPSHOT_MAGIC}, modules::{
+        allocator::{AllocationRoundingInfo, AllocatorModule},
+        compression::CompressionModule,
+        nonresident_allocator::{NonResidentAllocationRounding, NonResidentAllocatorModule},
+        object_encryption::{ObjectEncryptionKey, ObjectEncryptionModule},
         object_management::ObjectManagementModule,
         persistent_storage::{
-            persistent_storage_util::write_storage_data, PersistentStorageModule,
-            SharedStorageReference,
+            AsyncPersistentStorageModule, LegacyLayoutStorageModule, MemoryMappedStorageModule,
+            LegacyRegion, PartitionedStorageModule, PersistentStorageModule, SharedStorageReference,
         },
-    }, persist_access_point::PersistAccessPoint, resident_object_manager::{
+    }, persist_access_point::{get_persist_status, PersistAccessPoint, PostPersistHook}, persist_sync::{AtomicBool, TryLock}, resident_object_manager::{
+        residency_token::ResidencyToken,
         resident_list::ResidentList,
-        resident_object_backup::{calc_backup_obj_layout_static, calc_backup_obj_user_data_offset},
+        get_total_resident_size,
+        resident_object_backup::{calc_backup_obj_layout_dynamic, calc_backup_obj_layout_static, calc_backup_obj_user_data_offset},
         resident_object_metadata::ResidentObjectMetadata,
-        ResidentObjectManager,
-    }, shared_persist_lock::SharedPersistLock, vnv_list::VNVList, vnv_object::VNVObject, VNVArray, VNVConfig
+        ResidentObjectManager, HeapStats,
+    }, shared_persist_lock::SharedPersistLock, vnv_box::VNVBox, vnv_bytes::VNVBytes, vnv_channel::VNVChannel, vnv_compressed_object::VNVCompressedObject, vnv_encrypted_object::{EncryptedData, VNVEncryptedObject}, vnv_list::VNVList, vnv_map::VNVMap, vnv_object::VNVObject, vnv_error::VNVError, vnv_link::VNVLink, vnv_queue::VNVQueue, vnv_raw_object::VNVRawObject, vnv_stack::VNVStack, vnv_storage_slice::VNVStorageSlice, vnv_aligned::{Alignment, SupportedAlignment, VNVAligned}, VNVArray, VNVConfig, WriteBack, PersistLatencyBudget, StorageThroughput, StorageTiming
+};
+#[cfg(feature = "serde")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "dirty_pools")]
+use crate::vnv_background_object::VNVBackgroundObject;
+#[cfg(feature = "global_dirty_budget")]
+use crate::dirty_budget_pool::{DirtyBudgetPool, DirtyBudgetReservation};
+#[cfg(feature = "emergency_region")]
+use crate::resident_object_manager::{
+    emergency_reserve::EmergencyReserve,
+    resident_object::calc_resident_obj_layout_static,
+};
+use crate::storage_calibration::{LinearFit, CALIBRATION_BUFFER_SIZE, CALIBRATION_TRANSFER_SIZES};
+#[cfg(feature = "persist_priority")]
+use crate::resident_object_manager::persist_priority::PersistPriority;
+#[cfg(feature = "metrics")]
+use crate::resident_object_manager::metrics::StorageCounters;
+#[cfg(feature = "recovery")]
+use crate::{
+    heap_recovery::{
+        get_slice_offsets, read_committed_sequence, read_persist_commit_status, CommitCursor, CommitRecord, PendingRelocations,
+        PersistCommitStatus, RecoveryArea, RelocationEntry, RootEntry, RootKey, COMMIT_RECORD_COUNT,
+        RECOVERY_ROOT_COUNT, RELOCATION_LOG_COUNT,
+    },
+    heap_snapshot::RECOVERY_MAGIC,
+    resident_object_manager::PersistedObjectIter,
+    vnv_link::{LinkFixup, LinkFixupHeap, VNVLinkFixup},
 };
+#[cfg(feature = "allocator_journal")]
+use crate::allocator_journal::{AllocatorJournal, JournaledStorage};
+#[cfg(feature = "gc")]
+use crate::heap_gc::{AllocationEntry, AllocationSlots, GcHeap, GcMarker, ALLOCATION_TABLE_CAPACITY};
+#[cfg(feature = "access_counters")]
+use crate::resident_object_manager::resident_object_backup::{calc_backup_obj_access_count_offset, ObjectAccessCount};
+#[cfg(feature = "object_checksums")]
+use crate::resident_object_manager::resident_object_backup::{calc_backup_obj_checksum_offset, ObjectChecksum};
+#[cfg(any(feature = "access_counters", feature = "object_checksums"))]
+use crate::modules::persistent_storage::persistent_storage_util::read_storage_data;
+#[cfg(feature = "deterministic_layout")]
+use crate::modules::persistent_storage::persistent_storage_util::write_zeros;
+#[cfg(any(feature = "access_counters", feature = "object_checksums", feature = "recovery"))]
+use crate::modules::persistent_storage::persistent_storage_util::write_storage_data;
+
 use core::{
-    cell::RefCell,
+    alloc::Layout,
+    cell::{Cell, RefCell},
+    cmp::min,
+    hash::Hash,
     marker::PhantomData,
-    mem::{size_of, ManuallyDrop},
-    sync::atomic::AtomicBool,
+    mem::{align_of, size_of, ManuallyDrop, MaybeUninit},
+    ops::Range,
+    ptr, slice,
 };
 
 static mut PERSIST_ACCESS_POINT: PersistAccessPoint = PersistAccessPoint::empty();
 
+/// Size of the stack buffers that are used to transfer storage content in chunks,
+/// i.e. the objects of `VNVHeap::allocate_many` and everything that is copied with `copy_in_chunks`
+/// (snapshots, resized byte buffers and objects that are moved by `VNVHeap::defragment`)
+const STORAGE_CHUNK_SIZE: usize = 256;
+
+/// Calls `copy` for each chunk of a `len` bytes long copy with the position of the chunk
+/// and a buffer of the chunk length that `copy` can read into and write from
+fn copy_in_chunks(
+    len: usize,
+    mut copy: impl FnMut(usize, &mut [u8]) -> Result<(), VNVError>,
+) -> Result<(), VNVError> {
+    let mut buffer = [0u8; STORAGE_CHUNK_SIZE];
+    let mut pos = 0;
+    while pos < len {
+        let chunk = &mut buffer[..min(STORAGE_CHUNK_SIZE, len - pos)];
+        copy(pos, chunk)?;
+        pos += chunk.len();
+    }
+    Ok(())
+}
+
 /// For test environment we want to wait until a new heap can be created
 #[cfg(test)]
 static PERSIST_MUTEX: std::sync::Mutex<()> = std::sync::Mutex::new(());
@@ -52,7 +122,286 @@ pub struct LayoutInfo {
     pub cutoff_size: usize,
     pub resident_object_metadata: usize,
     pub object_dirty_size: usize,
-    pub persist_access_point_size: usize
+    pub persist_access_point_size: usize,
+
+    /// Size of the header that is stored in front of each object in non-volatile storage
+    pub non_resident_object_header: usize,
+
+    /// Smallest amount of non-volatile storage that is used up by an allocation
+    pub non_resident_min_allocation_size: usize,
+
+    /// How the non-resident allocator rounds up the size of allocations
+    pub non_resident_allocation_rounding: NonResidentAllocationRounding,
+}
+
+/// Per-object layout information of an object with type `T`
+#[derive(Debug)]
+pub struct ObjectLayoutInfo {
+    /// Size of the user data `T`
+    pub data_size: usize,
+
+    /// Total amount of bytes this object occupies in the resident buffer (metadata + data)
+    pub resident_size: usize,
+
+    /// Amount of bytes that are used by metadata (including padding) while this object is resident
+    pub resident_overhead: usize,
+
+    /// Amount of dirty bytes the metadata of this object uses up while this object is resident
+    pub metadata_dirty_size: usize,
+
+    /// Amount of bytes this object requests from the non-resident allocator (header + data)
+    pub non_resident_size: usize,
+
+    /// Amount of bytes this object actually uses up in non-volatile storage (after rounding)
+    pub non_resident_allocated_size: usize,
+
+    /// Amount of bytes of non-volatile storage that are not used by the user data (header + rounding)
+    pub non_resident_overhead: usize,
+}
+
+/// Splits the current usage of the resident buffer into user data and metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResidentUsage {
+    /// Number of objects that are currently resident
+    pub resident_objects: usize,
+
+    /// Bytes used by user data of resident objects
+    pub user_bytes: usize,
+
+    /// Bytes used by metadata (and padding) of resident objects
+    pub metadata_bytes: usize,
+
+    /// Bytes used by pinned objects (user data and metadata, see `VNVObject::pin`)
+    pub pinned_bytes: usize,
+}
+
+/// Highest usage of storage, resident buffer and dirty bytes since the heap was created
+#[cfg(feature = "watermarks")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Watermarks {
+    /// Most bytes that were allocated in storage at the same time (including allocator rounding)
+    pub max_storage_bytes: usize,
+
+    /// Most bytes of the resident buffer that were used by user data and metadata at the same time
+    pub max_resident_bytes: usize,
+
+    /// Most bytes that were dirty at the same time
+    pub max_dirty_bytes: usize,
+}
+
+#[cfg(feature = "watermarks")]
+impl Watermarks {
+    /// Size of the representation returned by `to_bytes`
+    pub const SERIALIZED_SIZE: usize = 3 * size_of::<u64>();
+
+    /// Returns the maximum of both watermarks, e.g. to combine the watermarks of multiple sessions
+    pub fn merge(self, other: Watermarks) -> Watermarks {
+        Watermarks {
+            max_storage_bytes: self.max_storage_bytes.max(other.max_storage_bytes),
+            max_resident_bytes: self.max_resident_bytes.max(other.max_resident_bytes),
+            max_dirty_bytes: self.max_dirty_bytes.max(other.max_dirty_bytes),
+        }
+    }
+
+    /// Little-endian representation that does not depend on the target
+    pub fn to_bytes(&self) -> [u8; Self::SERIALIZED_SIZE] {
+        let mut bytes = [0u8; Self::SERIALIZED_SIZE];
+        let values = [self.max_storage_bytes, self.max_resident_bytes, self.max_dirty_bytes];
+        for (chunk, value) in bytes.chunks_exact_mut(size_of::<u64>()).zip(values) {
+            chunk.copy_from_slice(&(value as u64).to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; Self::SERIALIZED_SIZE]) -> Watermarks {
+        let mut values = [0usize; 3];
+        for (value, chunk) in values.iter_mut().zip(bytes.chunks_exact(size_of::<u64>())) {
+            *value = u64::from_le_bytes(chunk.try_into().unwrap()) as usize;
+        }
+        Watermarks {
+            max_storage_bytes: values[0],
+            max_resident_bytes: values[1],
+            max_dirty_bytes: values[2],
+        }
+    }
+}
+
+/// Counters and current usage of the heap (see `VNVHeap::metrics`)
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HeapMetrics {
+    /// Bytes of the resident buffer that are currently used by user data and metadata
+    pub resident_bytes: usize,
+
+    /// Bytes that are currently dirty
+    pub dirty_bytes: usize,
+
+    /// Most bytes that were dirty at the same time (see `Watermarks::max_dirty_bytes`)
+    pub peak_dirty_bytes: usize,
+
+    /// How often the heap read from storage
+    pub storage_reads: usize,
+
+    /// How often the heap wrote to storage (including persisting)
+    pub storage_writes: usize,
+
+    /// How often objects were unloaded from the resident buffer
+    pub evictions: usize,
+
+    /// How many allocations failed because storage was exhausted
+    pub allocation_failures: usize,
+}
+
+/// Statistics of an object since the heap was created (see `VNVObject::stats`)
+#[cfg(feature = "object_stats")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ObjectStats {
+    /// How often the object was loaded from storage
+    pub faults: u32,
+
+    /// How often the object was unloaded from the resident buffer
+    pub evictions: u32,
+
+    /// How often dirty data of the object was written back to storage
+    pub write_backs: u32,
+
+    /// Bytes of the object that were written back to storage in total
+    pub write_back_bytes: usize,
+
+    /// How long the object was resident in total (see `VNVHeap::set_stats_clock`)
+    pub resident_time: u64,
+}
+
+/// Statistics of one object returned by `VNVHeap::stats_iter`
+#[cfg(feature = "object_stats")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectStatsEntry {
+    pub(crate) offset: usize,
+
+    /// Size of the user data of the object
+    pub size: usize,
+
+    pub stats: ObjectStats,
+}
+
+#[cfg(feature = "object_stats")]
+impl ObjectStatsEntry {
+    /// Returns `true` if these are the statistics of the object `link` points to
+    pub fn is_linked_by<T: Sized>(&self, link: &VNVLink<T>) -> bool {
+        link.points_to(&AllocationIdentifier::from_offset(self.offset))
+    }
+}
+
+/// Iterator over the statistics of all tracked objects (see `VNVHeap::stats_iter`)
+#[cfg(feature = "object_stats")]
+pub struct ObjectStatsIter<'a> {
+    index: usize,
+    heap: &'a dyn ObjectStatsSource,
+}
+
+/// Heap whose object statistics are iterated by `ObjectStatsIter` (so that it does not depend on the modules of the heap)
+#[cfg(feature = "object_stats")]
+trait ObjectStatsSource {
+    fn get_stats_entry(&self, index: usize) -> Result<Option<ObjectStatsEntry>, ()>;
+}
+
+#[cfg(feature = "object_stats")]
+impl<A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> ObjectStatsSource
+    for RefCell<VNVHeapInner<'_, A, N, M>>
+{
+    fn get_stats_entry(&self, index: usize) -> Result<Option<ObjectStatsEntry>, ()> {
+        self.borrow().resident_object_manager.stats.objects.get_entry(index)
+    }
+}
+
+#[cfg(feature = "object_stats")]
+impl Iterator for ObjectStatsIter<'_> {
+    type Item = ObjectStatsEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = self.heap.get_stats_entry(self.index).ok()?;
+            self.index += 1;
+            if entry.is_some() {
+                return entry;
+            }
+        }
+    }
+}
+
+/// Dirty user data of resident objects split by dirty pool (see `VNVBackgroundObject`)
+#[cfg(feature = "dirty_pools")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirtyPoolUsage {
+    /// Dirty bytes of objects that were modified with `VNVObject::get_mut`
+    pub foreground_bytes: usize,
+
+    /// Dirty bytes of objects that were modified with `VNVBackgroundObject::get_mut`
+    pub background_bytes: usize,
+
+    /// Maximum of `background_bytes` or `None` if it is not limited
+    pub background_limit: Option<usize>,
+}
+
+impl ResidentUsage {
+    /// Returns `true` if more bytes of the resident buffer are used by metadata than by user data
+    pub fn is_metadata_dominated(&self) -> bool {
+        self.metadata_bytes > self.user_bytes
+    }
+}
+
+/// Reason why an object could not be made resident
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResidentExhaustionReason {
+    /// The resident buffer is mostly used up by the metadata of (many small) resident objects
+    MetadataOverhead,
+
+    /// The resident buffer is used up by user data or is too small for the requested object
+    InsufficientSpace,
+}
+
+/// What happens if an object is dropped whose type needs to be dropped (see `core::mem::needs_drop`),
+/// but it cannot be made resident (e.g. because the resident buffer is exhausted)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DropPolicy {
+    /// Keep the object allocated in storage.
+    ///
+    /// `VNVObject::try_drop` returns the object again, so dropping it can be retried later.
+    /// If the object is dropped normally, its storage is leaked.
+    #[default]
+    KeepAlive,
+
+    /// Unload all other objects that are currently not in use (bypassing the object management module) and try again.
+    ///
+    /// If this still fails, the object is kept alive (see `KeepAlive`).
+    ForceEvictOthers,
+
+    /// Deallocate the object without running the `Drop` implementation of its type
+    LeakWithoutDrop,
+}
+
+/// Outcome of dropping an object successfully (see `VNVObject::try_drop`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropOutcome {
+    /// The object was dropped and deallocated
+    Dropped,
+
+    /// The object was deallocated without running the `Drop` implementation of its type (see `DropPolicy::LeakWithoutDrop`)
+    LeakedWithoutDrop,
+}
+
+/// The data of an object in storage does not match its checksum (e.g. because of a torn write during a power failure)
+#[cfg(feature = "object_checksums")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectChecksumMismatch {
+    /// Offset of the corrupted object in storage
+    pub storage_offset: usize,
+
+    /// Checksum that was stored together with the object
+    pub expected: ObjectChecksum,
+
+    /// Checksum of the data that was read
+    pub actual: ObjectChecksum,
 }
 
 /// Persists all existing heaps.
@@ -67,6 +416,101 @@ pub unsafe fn vnv_persist_all() {
     PERSIST_ACCESS_POINT.persist_if_not_empty();
 }
 
+/// Writes back dirty objects to their storage location until `budget_bytes` bytes of user data are written
+/// (e.g. if the energy buffer only lasts for a few hundred microseconds).
+///
+/// Objects with a higher `PersistPriority` are written first (feature `persist_priority`).
+/// Objects that were written back are skipped by the next call unless they were modified in the meantime,
+/// so calling this function repeatedly continues where the previous call stopped until `PartialPersistResult::is_complete`.
+/// Written objects stay resident and dirty. In contrast to `vnv_persist_all`, the state of the heap itself is not persisted.
+///
+/// Returns `None` if there is no heap or if the heap is in use right now (the call is not queued).
+///
+/// **Make sure that no other thread of this program is running except for the one running this function!**
+pub unsafe fn vnv_persist_budget(budget_bytes: usize) -> Option<PartialPersistResult> {
+    PERSIST_ACCESS_POINT.persist_budget_if_not_empty(budget_bytes)
+}
+
+/// Result of `vnv_persist_budget`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartialPersistResult {
+    /// Bytes of user data that were written back by this call
+    pub written_bytes: usize,
+
+    /// Bytes of user data that did not fit into the budget and are written back by the next call
+    pub remaining_bytes: usize,
+}
+
+impl PartialPersistResult {
+    /// Returns `true` if the data of all dirty objects is in storage now
+    pub fn is_complete(&self) -> bool {
+        self.remaining_bytes == 0
+    }
+}
+
+/// Summary of a persist that was triggered with `vnv_persist_all` (see `VNVHeap::set_post_persist_hook`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PersistSummary {
+    /// Bytes that were written to persist the state of the heap (metadata of resident objects and their dirty data)
+    pub persisted_bytes: usize,
+
+    /// Number of resident objects whose state was persisted
+    pub persisted_objects: usize,
+
+    /// Number of persisted objects whose dirty data was written as well
+    pub dirty_objects: usize,
+
+    /// Time it took to persist the state in ticks of the clock of the hook (`None` if no clock is set)
+    pub duration_ticks: Option<u64>,
+
+    /// `false` if the commit record could not be written. The state is restored anyway,
+    /// but `VNVHeap::recover` would not recover it if the power failed now.
+    #[cfg(feature = "recovery")]
+    pub committed: bool,
+}
+
+/// State of a persist that was triggered with `vnv_persist_all` (see `persist_status`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PersistStatus {
+    /// No persist is in progress
+    Idle = 0,
+
+    /// The heap was locked when the persist was triggered.
+    /// It is executed as soon as the lock is released.
+    Queued = 1,
+
+    /// The state of the heap is written to storage right now
+    Persisting = 2,
+
+    /// The state of the heap is in storage completely and the persist handler is running.
+    /// It is safe to cut the power now.
+    Persisted = 3,
+
+    /// The persist handler returned and the state of the heap is restored right now
+    Restoring = 4,
+}
+
+/// Returns the state of the current persist.
+///
+/// This only reads an atomic flag, so it can be called from any context (e.g. a power-management task).
+pub fn persist_status() -> PersistStatus {
+    get_persist_status()
+}
+
+/// Waits until a triggered persist has written the state of the heap to storage, i.e. until it is safe to cut the power.
+///
+/// `spin_hook` is called repeatedly while waiting (e.g. to yield to other tasks).
+/// Returns the status at that time, which is `PersistStatus::Idle` if no persist was triggered.
+pub fn wait_persist_complete(mut spin_hook: impl FnMut()) -> PersistStatus {
+    loop {
+        match persist_status() {
+            PersistStatus::Queued | PersistStatus::Persisting => spin_hook(),
+            status => return status,
+        }
+    }
+}
+
 pub(crate) struct ResidentBufPersistentStorage<A: AllocatorModule, S: PersistentStorageModule> {
     resident_list: ResidentList,
     storage_lock: TryLock<()>,
@@ -74,6 +518,10 @@ pub(crate) struct ResidentBufPersistentStorage<A: AllocatorModule, S: Persistent
     persist_queued: AtomicBool,
     storage: S,
     heap: A,
+    #[cfg(feature = "emergency_region")]
+    emergency_reserve: EmergencyReserve,
+    #[cfg(feature = "metrics")]
+    storage_counters: StorageCounters,
 }
 
 pub const fn calc_resident_buf_cutoff_size<A: AllocatorModule, S: PersistentStorageModule>() -> usize
@@ -85,7 +533,23 @@ pub(crate) const fn calc_resident_buf_default_dirty_size<
     A: AllocatorModule,
     S: PersistentStorageModule,
 >() -> usize {
-    size_of::<ResidentBufPersistentStorage<A, S>>() + size_of::<usize>()
+    size_of::<ResidentBufPersistentStorage<A, S>>() + size_of::<usize>() - calc_resident_buf_unpersisted_size()
+}
+
+/// Size of the part of the cutoff that is not persisted (the emergency region and the storage counters)
+pub(crate) const fn calc_resident_buf_unpersisted_size() -> usize {
+    #[allow(unused_mut)]
+    let mut size = 0;
+    #[cfg(feature = "emergency_region")]
+    {
+        size += size_of::<EmergencyReserve>();
+    }
+    #[cfg(feature = "metrics")]
+    {
+        size += size_of::<StorageCounters>();
+    }
+
+    size
 }
 
 pub struct VNVHeap<
@@ -112,13 +576,133 @@ impl<
         S: PersistentStorageModule + 'static,
     > VNVHeap<'a, A, N, M, S>
 {
+    /// Creates a new heap.
+    ///
+    /// Only one heap can exist at a time, as persisting is triggered globally (see `vnv_persist_all`).
+    /// Creating another heap fails with `VNVError::Unsupported`.
+    /// To share the power-fail window with other components, see `set_dirty_budget_pool`.
     pub fn new(
+        resident_buffer: &'a mut [u8],
+        storage_module: S,
+        heap: A,
+        config: VNVConfig,
+        persist_handler: fn(*mut u8, usize) -> (),
+    ) -> Result<Self, VNVError> {
+        Self::new_internal(resident_buffer, storage_module, heap, config, persist_handler, None)
+    }
+
+    /// Recovers a heap after a reboot (e.g. after a power failure) from the state that was persisted with `vnv_persist_all`.
+    ///
+    /// All objects are restored in the state of the last persist. Use `get_root` and `with_link` to access them.
+    /// If the last persist was interrupted, it is rolled back (see `recover_with_status`).
+    /// The heap has to be created with the same modules and `config` as before.
+    /// Otherwise, this fails with `VNVError::Unsupported`.
+    #[cfg(feature = "recovery")]
+    pub fn recover(
+        resident_buffer: &'a mut [u8],
+        storage_module: S,
+        heap: A,
+        config: VNVConfig,
+        persist_handler: fn(*mut u8, usize) -> (),
+    ) -> Result<Self, VNVError> {
+        Self::recover_with_status(resident_buffer, storage_module, heap, config, persist_handler).map(|(heap, _)| heap)
+    }
+
+    /// Like `recover`, but also returns whether the state of the last persist was complete.
+    ///
+    /// Persisting first writes the state and then a commit record. The state is written alternately to one of two slots,
+    /// so an interrupted persist never overwrites the state of the last committed persist.
+    /// If the power failed before the commit record was written, the incomplete state is discarded
+    /// and the state of the previous persist is recovered instead (`PersistCommitStatus::Committed` with its commit number).
+    /// `PersistCommitStatus::RolledBack` is only returned if no complete state is left,
+    /// in which case only the data that was synchronized to storage is recovered.
+    #[cfg(feature = "recovery")]
+    pub fn recover_with_status(
+        resident_buffer: &'a mut [u8],
+        mut storage_module: S,
+        heap: A,
+        config: VNVConfig,
+        persist_handler: fn(*mut u8, usize) -> (),
+    ) -> Result<(Self, PersistCommitStatus), VNVError> {
+        let recovery_area = RecoveryArea::new(storage_module.get_max_size(), size_of::<N>());
+
+        let mut header_bytes = [0u8; SnapshotHeader::SERIALIZED_SIZE];
+        storage_module.read(recovery_area.get_offset(), &mut header_bytes)?;
+        let header = SnapshotHeader::from_bytes(&header_bytes, &RECOVERY_MAGIC)?;
+
+        if header.storage_size != storage_module.get_max_size() || header.allocator_state_size != size_of::<N>() {
+            return Err(VNVError::Unsupported);
+        }
+
+        // revert the allocator changes that were interrupted by the power failure
+        #[cfg(feature = "allocator_journal")]
+        AllocatorJournal::new(recovery_area.get_journal_offset())
+            .rollback(&mut storage_module)
+            .map_err(|()| VNVError::CorruptedData)?;
+
+        let mut allocator_state = MaybeUninit::<N>::uninit();
+        storage_module.read(recovery_area.get_allocator_state_offset(), unsafe {
+            slice::from_raw_parts_mut(allocator_state.as_mut_ptr() as *mut u8, size_of::<N>())
+        })?;
+        let non_resident_allocator = unsafe { allocator_state.assume_init() };
+
+        let heap = Self::new_internal(
+            resident_buffer,
+            storage_module,
+            heap,
+            config,
+            persist_handler,
+            Some(non_resident_allocator),
+        )?;
+        let status = heap.inner.borrow_mut().recover_persisted_objects(&header)?;
+
+        Ok((heap, status))
+    }
+
+    /// Like `recover_with_status`, but also rebinds the links to objects that were moved after the last committed persist
+    /// (see `VNVLinkFixup`) before the heap is returned.
+    ///
+    /// `roots` has to pass the links of all roots (e.g. of `get_root`) to `LinkFixup::follow`,
+    /// which rebinds the links of all objects that are reachable from them.
+    /// At most `LINK_FIXUP_CAPACITY` objects can be followed, otherwise this fails with `VNVError::CapacityExhausted`.
+    /// `roots` is not called if no object was moved.
+    ///
+    /// ### Safety
+    ///
+    /// The links that are passed to `LinkFixup::follow` have to point to objects of their type that were not dropped yet.
+    /// `VNVLinkFixup` has to pass all links that are stored in an object to `LinkFixup::follow` (or `LinkFixup::rebind`).
+    #[cfg(feature = "recovery")]
+    pub unsafe fn recover_with_fixups(
+        resident_buffer: &'a mut [u8],
+        storage_module: S,
+        heap: A,
+        config: VNVConfig,
+        persist_handler: fn(*mut u8, usize) -> (),
+        roots: impl FnOnce(&Self, &mut LinkFixup),
+    ) -> Result<(Self, PersistCommitStatus), VNVError> {
+        let (heap, status) = Self::recover_with_status(resident_buffer, storage_module, heap, config, persist_handler)?;
+
+        let relocations = heap.inner.borrow_mut().read_pending_relocations()?;
+        if relocations.is_empty() {
+            return Ok((heap, status));
+        }
+
+        let mut fixup = LinkFixup::new(relocations, true);
+        roots(&heap, &mut fixup);
+        fixup.finish(&mut *heap.inner.borrow_mut())?;
+
+        Ok((heap, status))
+    }
+
+    /// Creates a new heap. If `recovered_allocator` is set, the storage content is kept as is.
+    fn new_internal(
         resident_buffer: &'a mut [u8],
         storage_module: S,
         heap: A,
         mut config: VNVConfig,
         persist_handler: fn(*mut u8, usize) -> (),
-    ) -> Result<Self, ()> {
+        recovered_allocator: Option<N>,
+    ) -> Result<Self, VNVError> {
         assert!(
             resident_buffer.len() >= config.max_dirty_bytes,
             "dirty size has to be smaller or equal to the resident buffer"
@@ -127,9 +711,9 @@ impl<
         // for test environment wait until new heap can be created
         // (until PERSIST_ACCESS_POINT is unset)
         #[cfg(test)]
-        let mutex_guard = PERSIST_MUTEX.lock().map_err(|_| {
+        let mutex_guard = PERSIST_MUTEX.lock().unwrap_or_else(|_| {
             panic!("Error while locking PERSIST_MUTEX! This normally happens if one thread panics and still has access to a VNVHeap!");
-        })?;
+        });
 
         let cutoff_ptr =
             (&mut resident_buffer[0] as *mut u8) as *mut ResidentBufPersistentStorage<A, S>;
@@ -166,37 +750,141 @@ impl<
                 heap_lock,
                 persist_queued,
                 *heap.try_lock().unwrap(),
-            )?
+            )
+            // there can only be one heap at a time
+            .map_err(|()| VNVError::Unsupported)?
         }
 
         let resident_object_manager = ResidentObjectManager::<A, M>::new(
             resident_buffer,
             config.max_dirty_bytes,
+            config.write_back,
             resident_list,
             heap,
         )?;
 
+        // the emergency region is stored in the cutoff, so vnv_persist_all can restore it
+        #[cfg(feature = "emergency_region")]
+        let resident_object_manager = {
+            let emergency_reserve = unsafe { core::ptr::addr_of_mut!((*cutoff_ptr).emergency_reserve) };
+            unsafe { PERSIST_ACCESS_POINT.set_emergency_reserve(emergency_reserve) }.map_err(|()| VNVError::Unsupported)?;
+
+            let mut resident_object_manager = resident_object_manager;
+            resident_object_manager.emergency_reserve = emergency_reserve;
+            resident_object_manager
+        };
+
+        let mut resident_object_manager = resident_object_manager;
+        resident_object_manager.persist_latency_budget = config.persist_latency_budget;
+        if let Some(budget) = config.persist_latency_budget {
+            resident_object_manager.set_dirty_limit(
+                budget.max_persisted_bytes().saturating_sub(calc_resident_buf_default_dirty_size::<A, S>()),
+                &mut storage_reference,
+            )?;
+        }
+
+        // start with zeroed storage, so the storage content only depends on the operations on this heap
+        #[cfg(feature = "deterministic_layout")]
+        if recovered_allocator.is_none() {
+            let storage_size = storage_reference.get_max_size();
+            write_zeros(&mut storage_reference, 0, storage_size)?;
+        }
+
         // persist() needs one usize to specify its slice size
         let non_resident_offset = config.max_dirty_bytes + size_of::<usize>();
-        let mut non_resident_allocator = N::new();
-        non_resident_allocator.init(
-            non_resident_offset,
-            storage_reference.get_max_size() - non_resident_offset,
-            &mut storage_reference,
-        )?;
 
-        Ok(VNVHeap {
+        // the slice of the last committed persist is kept while the next one is written (see `get_slice_offsets`)
+        #[cfg(feature = "recovery")]
+        let non_resident_offset = COMMIT_RECORD_COUNT * non_resident_offset;
+        let non_resident_end = storage_reference.get_max_size();
+
+        // the recovery area is located behind the region of the non-resident allocator
+        #[cfg(feature = "recovery")]
+        let recovery_area = RecoveryArea::new(non_resident_end, size_of::<N>());
+        #[cfg(feature = "recovery")]
+        let non_resident_end = recovery_area.get_offset();
+
+        #[cfg(feature = "recovery")]
+        let init_recovery_area = recovered_allocator.is_none();
+        let non_resident_allocator = match recovered_allocator {
+            Some(non_resident_allocator) => non_resident_allocator,
+            None => {
+                let non_resident_size = non_resident_end
+                    .checked_sub(non_resident_offset)
+                    .ok_or(VNVError::NonResidentSpaceExhausted)?;
+                let mut non_resident_allocator = N::new();
+                non_resident_allocator.init(
+                    non_resident_offset,
+                    non_resident_size,
+                    &mut storage_reference,
+                )
+                .map_err(|()| VNVError::NonResidentSpaceExhausted)?;
+                non_resident_allocator
+            }
+        };
+
+        let heap = VNVHeap {
             inner: ManuallyDrop::new(RefCell::new(VNVHeapInner {
                 storage_reference,
                 resident_object_manager,
                 non_resident_allocator,
+                storage_timing: None,
+                #[cfg(feature = "gc")]
+                allocation_slots: AllocationSlots::EMPTY,
                 _phantom_data: PhantomData,
             })),
             cutoff_ptr,
 
             #[cfg(test)]
             _mutex_guard: mutex_guard,
-        })
+        };
+
+        #[cfg(feature = "recovery")]
+        {
+            let mut inner = heap.inner.borrow_mut();
+            if init_recovery_area {
+                inner.init_recovery_area()?;
+            }
+
+            #[cfg(feature = "gc")]
+            if !init_recovery_area {
+                inner.load_allocation_slots()?;
+            }
+
+            // continue after the commit records of the recovered heap
+            inner.read_commit_cursor()?;
+        }
+
+        Ok(heap)
+    }
+
+    /// Creates a new heap from a snapshot that was written by `snapshot_to`.
+    ///
+    /// `storage_module` is overwritten with the storage content of the snapshot. Objects of the snapshot
+    /// can be accessed with `with_link` afterwards.
+    ///
+    /// The snapshot has to be created with the same modules and `config`.
+    /// Otherwise, this fails with `VNVError::Unsupported` (as well as for snapshots of other versions).
+    pub fn restore_from<S2: PersistentStorageModule>(
+        resident_buffer: &'a mut [u8],
+        storage_module: S,
+        heap: A,
+        config: VNVConfig,
+        persist_handler: fn(*mut u8, usize) -> (),
+        source: &mut S2,
+    ) -> Result<Self, VNVError> {
+        let mut header_bytes = [0u8; SnapshotHeader::SERIALIZED_SIZE];
+        source.read(0, &mut header_bytes)?;
+        let header = SnapshotHeader::from_bytes(&header_bytes, &SNAPSHOT_MAGIC)?;
+
+        if header.storage_size != storage_module.get_max_size() || header.allocator_state_size != size_of::<N>() {
+            return Err(VNVError::Unsupported);
+        }
+
+        let heap = Self::new(resident_buffer, storage_module, heap, config, persist_handler)?;
+        heap.inner.borrow_mut().restore_snapshot(&header, source)?;
+
+        Ok(heap)
     }
 
     fn prepare_access_point(
@@ -229,6 +917,10 @@ impl<
             persist_queued: AtomicBool::new(false),
             resident_list: ResidentList::new(),
             storage: storage_module,
+            #[cfg(feature = "emergency_region")]
+            emergency_reserve: EmergencyReserve::new(),
+            #[cfg(feature = "metrics")]
+            storage_counters: StorageCounters::new(),
         };
 
         // write inner
@@ -244,11 +936,18 @@ impl<
         Ok((
             calc_resident_buf_cutoff_size::<A, S>(),
             &mut resident_buffer[calc_resident_buf_cutoff_size::<A, S>()..],
-            SharedStorageReference::new(SharedPersistLock::new(
-                &mut inner_ref.storage,
-                &inner_ref.persist_queued,
-                &inner_ref.storage_lock,
-            )),
+            {
+                let storage_reference = SharedStorageReference::new(SharedPersistLock::new(
+                    &mut inner_ref.storage,
+                    &inner_ref.persist_queued,
+                    &inner_ref.storage_lock,
+                ));
+
+                #[cfg(feature = "metrics")]
+                let storage_reference = storage_reference.with_storage_counters(&inner_ref.storage_counters);
+
+                storage_reference
+            },
             &mut inner_ref.resident_list,
             SharedPersistLock::new(
                 &mut inner_ref.heap,
@@ -263,7 +962,7 @@ impl<
     pub fn allocate<'b, T: Sized + 'b>(
         &'b self,
         initial_value: T,
-    ) -> Result<VNVObject<'b, 'a, T, A, N, M>, ()>
+    ) -> Result<VNVObject<'b, 'a, T, A, N, M>, VNVError>
     where
         'a: 'b,
     {
@@ -273,59 +972,1099 @@ impl<
         Ok(VNVObject::new(&self.inner, identifier))
     }
 
-    /// pd = partial dirty
-    pub fn allocate_pd_array<'b, T: Sized + Copy + 'b, const SIZE: usize>(
+    /// Same as `allocate`, but returns a `VNVBox` that can be accessed like a normal reference
+    pub fn allocate_box<'b, T: Sized + 'b>(
         &'b self,
-        _initial_value: [T; SIZE],
-    ) -> Result<VNVArray<'b, 'a, T, SIZE, A, N, M>, ()>
+        initial_value: T,
+    ) -> Result<VNVBox<'b, 'a, T, A, N, M>, VNVError>
     where
         'a: 'b,
     {
-        panic!("partial dirtiness currently not 100% supported.");
+        Ok(VNVBox::new(self.allocate(initial_value)?))
+    }
 
-        /*let mut inner = self.inner.borrow_mut();
-        let identifier = unsafe { inner.allocate(initial_value, true)? };
+    /// Same as `allocate`, but the object is aligned to `ALIGN` bytes (see `VNVAligned`).
+    ///
+    /// The resident copy is always aligned. In storage, the user data of the object is aligned relative to the
+    /// start of the storage if `N` honors the alignment of the requested layout (e.g. `NonResidentBuddyAllocatorModule`)
+    /// and the object header (with `access_counters` or `object_checksums`) does not break the alignment.
+    /// Otherwise, `VNVError::Unsupported` is returned.
+    pub fn allocate_aligned<'b, T: Sized + 'b, const ALIGN: usize>(
+        &'b self,
+        initial_value: T,
+    ) -> Result<VNVObject<'b, 'a, VNVAligned<T, ALIGN>, A, N, M>, VNVError>
+    where
+        'a: 'b,
+        Alignment<ALIGN>: SupportedAlignment,
+    {
+        let object = self.allocate(VNVAligned::<T, ALIGN>::new(initial_value))?;
+        if (object.get_alloc_id().offset + calc_backup_obj_user_data_offset()) % ALIGN != 0 {
+            return Err(VNVError::Unsupported);
+        }
 
-        Ok(VNVArray::new(&self.inner, identifier))*/
+        Ok(object)
     }
 
-    pub fn new_list<'b, T: Sized + Clone>(
+    /// Same as `allocate`, but the object is written back with the given priority during persisting
+    /// (see `VNVObject::set_persist_priority`)
+    #[cfg(feature = "persist_priority")]
+    pub fn allocate_with_priority<'b, T: Sized + 'b>(
         &'b self,
-    ) -> VNVList<'b, 'a, T, A, N, M>
+        initial_value: T,
+        priority: PersistPriority,
+    ) -> Result<VNVObject<'b, 'a, T, A, N, M>, VNVError>
     where
         'a: 'b,
     {
-        VNVList::new(&self.inner)
+        let mut object = self.allocate(initial_value)?;
+        object.set_persist_priority(priority)?;
+        Ok(object)
     }
 
-    /// Returns the size which the `resident_buffer` has to be, so `usable_resident_buffer_size` bytes can be used effectively
-    pub const fn calc_resident_buffer_size(usable_resident_buffer_size: usize) -> usize {
-        usable_resident_buffer_size + calc_resident_buf_cutoff_size::<A, S>()
-    }
+    /// Same as `allocate`, but the object belongs to the background dirty pool (see `VNVBackgroundObject`)
+    #[cfg(feature = "dirty_pools")]
+    pub fn allocate_background<'b, T: Sized + 'b>(
+        &'b self,
+        initial_value: T,
+    ) -> Result<VNVBackgroundObject<'b, 'a, T, A, N, M>, VNVError>
+    where
+        'a: 'b,
+    {
+        let object = self.allocate(initial_value)?;
 
-    #[cfg(feature = "benchmarks")]
-    pub(crate) fn get_inner(&self) -> &RefCell<VNVHeapInner<'a, A, N, M>> {
-        &self.inner
-    }
+        let mut inner = self.inner.borrow_mut();
+        unsafe { inner.add_to_background_pool(object.get_alloc_id()) }?;
+        drop(inner);
 
-    pub const fn get_layout_info() -> LayoutInfo {
-        LayoutInfo {
-            resident_object_metadata: size_of::<ResidentObjectMetadata>(),
-            cutoff_size: calc_resident_buf_cutoff_size::<A, S>(),
-            object_dirty_size: ResidentObjectMetadata::fresh_object_dirty_size::<()>(false),
-            persist_access_point_size: size_of::<PersistAccessPoint>()
-        }
+        Ok(VNVBackgroundObject::new(object))
     }
 
-    pub fn count_resident_objects<T: Sized>(&self) -> usize {
-        let inner = self.inner.borrow();
-        inner.count_resident_objects()
+    /// Same as `allocate`, but the initial value is deserialized from `bytes` (JSON, e.g. received over the network).
+    ///
+    /// Use `VNVObject::serialize_into` to serialize the object again.
+    #[cfg(feature = "serde")]
+    pub fn allocate_from_deserialize<'b, T: Sized + DeserializeOwned + 'b>(
+        &'b self,
+        bytes: &[u8],
+    ) -> Result<VNVObject<'b, 'a, T, A, N, M>, VNVError>
+    where
+        'a: 'b,
+    {
+        let initial_value: T = serde_json::from_slice(bytes).map_err(|_| VNVError::SerializationFailed)?;
+        self.allocate(initial_value)
     }
 
-}
-
-impl<
-        A: AllocatorModule,
+    /// Allocates an object that is encrypted and authenticated with `key` using `encryption`.
+    ///
+    /// The key should be unique for each object. The object keeps using its key after a reboot,
+    /// as new nonces continue after the nonce counter that is stored with the object.
+    pub fn allocate_encrypted<'b, T: Sized + Copy + 'b, E: ObjectEncryptionModule>(
+        &'b self,
+        initial_value: T,
+        key: ObjectEncryptionKey,
+        encryption: E,
+    ) -> Result<VNVEncryptedObject<'b, 'a, T, E, A, N, M>, VNVError>
+    where
+        'a: 'b,
+    {
+        let obj = self.allocate(EncryptedData::<T>::empty())?;
+        VNVEncryptedObject::new(obj, key, encryption, initial_value)
+    }
+
+    /// Allocates an object that is stored compressed with `compression` and never made resident (see `VNVCompressedObject`).
+    ///
+    /// This saves storage for big objects that compress well, but every access reads and decompresses the whole object.
+    pub fn allocate_compressed<'b, T: Sized + Copy + 'b, C: CompressionModule>(
+        &'b self,
+        initial_value: T,
+        compression: C,
+    ) -> Result<VNVCompressedObject<'b, 'a, T, C, A, N, M>, VNVError>
+    where
+        'a: 'b,
+    {
+        VNVCompressedObject::new(&self.inner, compression, initial_value)
+    }
+
+    /// Allocates `COUNT` objects at once, the `i`-th object is initialized with `init(i)`.
+    ///
+    /// The storage for all objects is reserved in one allocator pass (if supported by `N`, see
+    /// `NonResidentAllocatorModule::allocate_many`) and objects that are placed right after each other are written
+    /// to storage together. This is a lot cheaper than calling `allocate` in a loop, but the objects are not resident afterwards.
+    pub fn allocate_many<'b, T: Sized + 'b, const COUNT: usize>(
+        &'b self,
+        init: impl FnMut(usize) -> T,
+    ) -> Result<[VNVObject<'b, 'a, T, A, N, M>; COUNT], VNVError>
+    where
+        'a: 'b,
+    {
+        let mut inner = self.inner.borrow_mut();
+        let identifiers = unsafe { inner.allocate_many::<T, COUNT>(init)? };
+
+        Ok(identifiers.map(|identifier| VNVObject::new(&self.inner, identifier)))
+    }
+
+    /// Allocates `len` elements that are written directly to storage from `iter` and never made resident.
+    ///
+    /// This is meant for huge data (e.g. lookup tables) that does not fit into the resident buffer, see `VNVStorageSlice`.
+    ///
+    /// **Panics** if `iter` yields less than `len` items.
+    pub fn allocate_from_iter<'b, T: Sized + Copy + 'b, I: IntoIterator<Item = T>>(
+        &'b self,
+        len: usize,
+        iter: I,
+    ) -> Result<VNVStorageSlice<'b, 'a, T, A, N, M>, VNVError>
+    where
+        'a: 'b,
+    {
+        VNVStorageSlice::new(&self.inner, len, iter)
+    }
+
+    /// Allocates a byte buffer whose length is only known at runtime (e.g. a received packet) and initializes it with `data`.
+    ///
+    /// The buffer is written to storage right away and made resident on its first access, see `VNVBytes`.
+    pub fn allocate_bytes<'b>(&'b self, data: &[u8]) -> Result<VNVBytes<'b, 'a, A, N, M>, VNVError>
+    where
+        'a: 'b,
+    {
+        let offset = self.inner.borrow_mut().allocate_bytes(data)?;
+        Ok(VNVBytes::new(&self.inner, offset, data.len()))
+    }
+
+    /// Allocates an untyped object with the given data layout (e.g. for data of C code) that is initialized with zeros.
+    ///
+    /// See `VNVRawObject` for how to access it. Like `allocate_aligned`, `VNVError::Unsupported` is returned
+    /// if the data in storage would not be aligned to `layout.align()`.
+    pub fn allocate_layout<'b>(&'b self, layout: Layout) -> Result<VNVRawObject<'b, 'a, A, N, M>, VNVError>
+    where
+        'a: 'b,
+    {
+        let offset = self.inner.borrow_mut().allocate_layout(layout)?;
+        let object = VNVRawObject::new(&self.inner, offset, layout);
+        if (offset + calc_backup_obj_user_data_offset()) % layout.align() != 0 {
+            return Err(VNVError::Unsupported);
+        }
+
+        Ok(object)
+    }
+
+    /// pd = partial dirty
+    pub fn allocate_pd_array<'b, T: Sized + Copy + 'b, const SIZE: usize>(
+        &'b self,
+        _initial_value: [T; SIZE],
+    ) -> Result<VNVArray<'b, 'a, T, SIZE, A, N, M>, VNVError>
+    where
+        'a: 'b,
+    {
+        panic!("partial dirtiness currently not 100% supported.");
+
+        /*let mut inner = self.inner.borrow_mut();
+        let identifier = unsafe { inner.allocate(initial_value, true)? };
+
+        Ok(VNVArray::new(&self.inner, identifier))*/
+    }
+
+    /// Creates a doubly-linked list whose nodes are separate objects, see `VNVList`
+    pub fn new_list<'b, T: Sized + Clone>(
+        &'b self,
+    ) -> VNVList<'b, 'a, T, A, N, M>
+    where
+        'a: 'b,
+    {
+        VNVList::new(&self.inner)
+    }
+
+    /// Creates a hash map with `bucket_count` buckets, see `VNVMap`
+    pub fn new_map<'b, K: Sized + Hash + Eq + Clone, V: Sized + Clone>(
+        &'b self,
+        bucket_count: usize,
+    ) -> Result<VNVMap<'b, 'a, K, V, A, N, M>, VNVError>
+    where
+        'a: 'b,
+    {
+        VNVMap::new(&self.inner, bucket_count)
+    }
+
+    pub fn new_stack<'b, T: Sized + Clone>(
+        &'b self,
+    ) -> VNVStack<'b, 'a, T, A, N, M>
+    where
+        'a: 'b,
+    {
+        VNVStack::new(&self.inner)
+    }
+
+    /// Creates a FIFO queue with space for `CAP` items that live in storage, see `VNVQueue`
+    pub fn new_queue<'b, T: Sized + Copy, const CAP: usize>(
+        &'b self,
+    ) -> Result<VNVQueue<'b, 'a, T, CAP, A, N, M>, VNVError>
+    where
+        'a: 'b,
+    {
+        VNVQueue::new(&self.inner)
+    }
+
+    /// Creates a channel with space for `CAP` items that can be produced from an interrupt service routine, see `VNVChannel`.
+    ///
+    /// Fails if the ring buffer does not fit into the resident buffer or the dirty budget.
+    pub fn new_channel<'b, T: Sized + Copy + Send, const CAP: usize>(
+        &'b self,
+    ) -> Result<VNVChannel<'b, 'a, T, CAP, A, N, M>, VNVError>
+    where
+        'a: 'b,
+    {
+        VNVChannel::new(&self.inner)
+    }
+
+    /// Returns the size which the `resident_buffer` has to be, so `usable_resident_buffer_size` bytes can be used effectively
+    pub const fn calc_resident_buffer_size(usable_resident_buffer_size: usize) -> usize {
+        usable_resident_buffer_size + calc_resident_buf_cutoff_size::<A, S>()
+    }
+
+    /// Returns the size which the `resident_buffer` has to be, so `n_objects` objects of type `T` can be resident at the same time.
+    ///
+    /// Includes the metadata of the objects, the rounding of the allocator `A` and the cutoff of the heap,
+    /// but assumes that the resident buffer is not fragmented. This can be used to size static buffers:
+    /// `static mut BUFFER: [u8; MyHeap::required_buffer_size::<Sensor>(8)] = ...`
+    pub const fn required_buffer_size<T: Sized>(n_objects: usize) -> usize
+    where
+        A: AllocationRoundingInfo,
+    {
+        Self::calc_resident_buffer_size(n_objects * A::ALLOCATION_ROUNDING.apply(get_total_resident_size::<T>()))
+    }
+
+    /// Returns the `max_dirty_bytes` (see `VNVConfig`) that are required so `n_dirty` objects of type `T`
+    /// can be dirty at the same time (including the dirty metadata of the objects and of the heap).
+    pub const fn required_dirty_budget<T: Sized>(n_dirty: usize) -> usize {
+        calc_resident_buf_default_dirty_size::<A, S>()
+            + n_dirty * (size_of::<T>() + ResidentObjectMetadata::fresh_object_dirty_size::<T>(false))
+    }
+
+    pub(crate) fn get_inner(&self) -> &RefCell<VNVHeapInner<'a, A, N, M>> {
+        &self.inner
+    }
+
+    pub const fn get_layout_info() -> LayoutInfo {
+        LayoutInfo {
+            resident_object_metadata: size_of::<ResidentObjectMetadata>(),
+            cutoff_size: calc_resident_buf_cutoff_size::<A, S>(),
+            object_dirty_size: ResidentObjectMetadata::fresh_object_dirty_size::<()>(false),
+            persist_access_point_size: size_of::<PersistAccessPoint>(),
+            non_resident_object_header: calc_backup_obj_user_data_offset(),
+            non_resident_min_allocation_size: N::ALLOCATION_ROUNDING.min_size(),
+            non_resident_allocation_rounding: N::ALLOCATION_ROUNDING,
+        }
+    }
+
+    /// Returns the layout information of an object of type `T`.
+    ///
+    /// This can be used to estimate how much of the resident buffer and of the non-volatile storage
+    /// is used up by metadata.
+    pub const fn get_object_layout_info<T: Sized>() -> ObjectLayoutInfo {
+        let non_resident_size = calc_backup_obj_layout_static::<T>().size();
+        let non_resident_allocated_size = N::ALLOCATION_ROUNDING.apply(non_resident_size);
+
+        ObjectLayoutInfo {
+            data_size: size_of::<T>(),
+            resident_size: get_total_resident_size::<T>(),
+            resident_overhead: get_total_resident_size::<T>() - size_of::<T>(),
+            metadata_dirty_size: ResidentObjectMetadata::fresh_object_dirty_size::<T>(false),
+            non_resident_size,
+            non_resident_allocated_size,
+            non_resident_overhead: non_resident_allocated_size - size_of::<T>(),
+        }
+    }
+
+    /// Makes all `objects` resident (if not already), so that `get` and `get_mut` do not have to access the storage afterwards.
+    /// This is the bulk variant of `VNVObject::prefetch`.
+    ///
+    /// Objects that follow each other in storage (e.g. because they were allocated together) are read with a
+    /// single storage read, which saves the per transaction overhead of e.g. SPI storage modules.
+    /// For this, `objects` should be sorted by their allocation order.
+    ///
+    /// If the resident buffer is too small for all `objects`, some of them may be unloaded again.
+    pub fn get_many<T: Sized>(&self, objects: &mut [&mut VNVObject<'_, 'a, T, A, N, M>]) -> Result<(), VNVError> {
+        for obj in objects.iter() {
+            assert!(ptr::eq(obj.get_heap_cell(), self.get_inner()), "object was not allocated with this heap");
+        }
+
+        let mut inner = self.inner.borrow_mut();
+        unsafe { inner.load_many(objects.len(), |i| objects[i].get_alloc_id().clone()) }
+    }
+
+    /// Moves `objects` to lower offsets in storage, so that the freed regions can be merged again
+    /// and big allocations do not fail because the storage is fragmented.
+    ///
+    /// `clock` has to return the current time in µs. No further object is moved after `budget_us` µs have passed
+    /// (or if moving it would take longer according to `calibrate_storage`), so this can be called repeatedly
+    /// (e.g. from idle loops) until it returns 0. The first movable object is always moved, so repeated calls make
+    /// progress even if the budget is smaller than the time it takes to move one object.
+    /// Returns how many objects were moved. Pinned objects are skipped and moved objects are not resident afterwards.
+    ///
+    /// Roots (see `set_root`) are updated, but links (see `VNVLink`) that are stored elsewhere still point to the
+    /// old location. To update them, `relocated` is called with the old and the new link of every moved object.
+    /// The heap is not borrowed while `relocated` runs, so it can e.g. use `with_link`.
+    /// With the `recovery` feature, at most `RELOCATION_LOG_COUNT` objects are moved between two persists,
+    /// so the links can be updated with `recover_with_fixups` after a power failure.
+    pub fn defragment<T: Sized>(
+        &self,
+        objects: &mut [&mut VNVObject<'_, 'a, T, A, N, M>],
+        budget_us: u64,
+        clock: fn() -> u64,
+        mut relocated: impl FnMut(VNVLink<T>, VNVLink<T>),
+    ) -> Result<usize, VNVError> {
+        for obj in objects.iter() {
+            assert!(ptr::eq(obj.get_heap_cell(), self.get_inner()), "object was not allocated with this heap");
+        }
+
+        let layout = calc_backup_obj_layout_static::<T>();
+        let estimated_us = self.get_storage_timing().map_or(0, |timing| {
+            timing.read.transfer_time_us(layout.size()) + timing.write.transfer_time_us(layout.size())
+        });
+
+        let start = clock();
+        let mut moved = 0;
+        for obj in objects.iter_mut() {
+            if moved > 0 && clock().saturating_sub(start) + estimated_us > budget_us {
+                break;
+            }
+
+            let new_offset = {
+                let mut inner = self.inner.borrow_mut();
+                if inner.is_pinned(obj.get_alloc_id()) {
+                    continue;
+                }
+                inner.relocate_object(obj.get_alloc_id().offset, layout)?
+            };
+
+            if let Some(new_offset) = new_offset {
+                let old_link = VNVLink::new(obj.get_alloc_id());
+                obj.set_alloc_id(AllocationIdentifier::from_offset(new_offset));
+                moved += 1;
+                relocated(old_link, VNVLink::new(obj.get_alloc_id()));
+            }
+        }
+
+        Ok(moved)
+    }
+
+    /// Calls `f` with the object that `link` points to.
+    ///
+    /// The object is not owned by `f`, so it is not deallocated afterwards.
+    ///
+    /// ### Safety
+    ///
+    /// `link` has to point to an object of type `T` of this heap that was not dropped yet.
+    pub unsafe fn with_link<T: Sized, R>(
+        &self,
+        link: &VNVLink<T>,
+        f: impl FnOnce(&mut VNVObject<'_, 'a, T, A, N, M>) -> R,
+    ) -> R {
+        let mut obj = ManuallyDrop::new(VNVObject::new(&self.inner, link.get_alloc_id()));
+        f(&mut obj)
+    }
+
+    pub fn count_resident_objects<T: Sized>(&self) -> usize {
+        let inner = self.inner.borrow();
+        inner.count_resident_objects()
+    }
+
+    /// Writes back up to `budget_bytes` of dirty user data and returns how many dirty bytes are available again.
+    ///
+    /// The time it takes to persist the heap grows with the amount of dirty bytes.
+    /// Call this from idle loops to shrink the worst-case persist latency.
+    /// Which objects are written back is decided by the `ObjectManagementModule` (see `ObjectManagementModule::clean_dirty_data`).
+    pub fn clean(&self, budget_bytes: usize) -> usize {
+        let mut inner = self.inner.borrow_mut();
+        inner.clean(budget_bytes)
+    }
+
+    /// Writes back all data that is still buffered by the storage module (see `PersistentStorageModule::flush`).
+    pub fn flush_storage(&self) -> Result<(), VNVError> {
+        let mut inner = self.inner.borrow_mut();
+        inner.flush_storage()
+    }
+
+    /// Writes the state of the whole heap to `target`, so it can be restored later with `restore_from`.
+    ///
+    /// All dirty objects are written back first, which fails with `VNVError::ObjectInUse`
+    /// if one of them is still mutably borrowed.
+    pub fn snapshot_to<S2: PersistentStorageModule>(&mut self, target: &mut S2) -> Result<(), VNVError> {
+        let mut inner = self.inner.borrow_mut();
+        inner.snapshot_to(target)
+    }
+
+    /// Stores `link` as root with the name or id `key`, so the object can be found again after `recover`.
+    ///
+    /// A previous root with the same key is replaced. At most `RECOVERY_ROOT_COUNT` roots can be stored,
+    /// otherwise this fails with `VNVError::RootsExhausted`.
+    #[cfg(feature = "recovery")]
+    pub fn set_root<'k, T: Sized>(&self, key: impl Into<RootKey<'k>>, link: &VNVLink<T>) -> Result<(), VNVError> {
+        let mut inner = self.inner.borrow_mut();
+        inner.set_root(RootEntry {
+            key: key.into().encode(),
+            offset: link.get_alloc_id().offset,
+            size: size_of::<T>(),
+        })
+    }
+
+    /// Removes the root with the name or id `key` (the object itself is not deallocated)
+    #[cfg(feature = "recovery")]
+    pub fn remove_root<'k>(&self, key: impl Into<RootKey<'k>>) -> Result<(), VNVError> {
+        let mut inner = self.inner.borrow_mut();
+        inner.remove_root(key.into().encode())
+    }
+
+    /// Returns the link that was stored with `set_root`, or `None` if there is no root with this key.
+    ///
+    /// Fails with `VNVError::Unsupported` if the root was stored for a type of another size.
+    /// Other than that, `T` is not checked, so it has to be the same type as in `set_root`.
+    #[cfg(feature = "recovery")]
+    pub fn get_root<'k, T: Sized>(&self, key: impl Into<RootKey<'k>>) -> Result<Option<VNVLink<T>>, VNVError> {
+        let mut inner = self.inner.borrow_mut();
+        match inner.get_root(key.into().encode())? {
+            Some((_, entry)) if entry.size != size_of::<T>() => Err(VNVError::Unsupported),
+            Some((_, entry)) => Ok(Some(VNVLink::new(&AllocationIdentifier::from_offset(entry.offset)))),
+            None => Ok(None),
+        }
+    }
+
+    /// Rebinds the links that are stored in the object `link` points to (see `VNVLinkFixup`)
+    /// and returns how many of them were rebound.
+    ///
+    /// Links that were updated in `relocated` of `defragment` are lost if the power fails before the next persist
+    /// commits, while the objects have already moved. `recover_with_fixups` rebinds the links of all reachable objects.
+    /// This only rebinds the links of a single object (`LinkFixup::follow` does not follow them),
+    /// e.g. for objects that are not reachable from the roots. It has to be called before the heap is persisted again.
+    /// The moves are logged in the recovery area, which is why `defragment` moves at most `RELOCATION_LOG_COUNT`
+    /// objects between two persists.
+    ///
+    /// ### Safety
+    ///
+    /// `link` has to point to an object of type `T` of this heap that was not dropped yet.
+    #[cfg(feature = "recovery")]
+    pub unsafe fn fixup_links<T: VNVLinkFixup + Sized>(&self, link: &VNVLink<T>) -> Result<usize, VNVError> {
+        let relocations = self.inner.borrow_mut().read_pending_relocations()?;
+        if relocations.is_empty() {
+            return Ok(0);
+        }
+
+        let mut fixup = LinkFixup::new(relocations, false);
+        let mut link = *link;
+        fixup.rebind(&mut link);
+        self.with_link(&link, |obj| obj.get_mut().map(|mut data| data.fixup_links(&mut fixup)))?;
+        Ok(fixup.get_rebound_count())
+    }
+
+    /// Deallocates all objects that are not reachable from the roots and returns how many objects were deallocated.
+    ///
+    /// Objects are owned by their handles, so only objects whose handles are lost are leaked,
+    /// e.g. objects that were not linked from any root when the power failed and the heap was recovered.
+    /// `roots` has to mark all roots (e.g. the links of `get_root`) with `GcMarker::mark`,
+    /// which follows the links that are stored in the objects (see `VNVTrace`).
+    /// Marking and sweeping reads the whole allocation table and all reachable objects,
+    /// so this can take a long time and is only done if it is called explicitly (e.g. once after `recover`).
+    ///
+    /// Only objects of `allocate` (and its variants) are tracked, at most `ALLOCATION_TABLE_CAPACITY` at a time.
+    /// Byte buffers, storage slices and the objects that did not fit into the table are never deallocated.
+    /// Objects that are in use or pinned are kept as well. Deallocated objects are not dropped.
+    ///
+    /// ### Safety
+    ///
+    /// All objects that are accessed afterwards have to be marked, including objects with handles that still exist.
+    /// `VNVTrace` has to mark all links that are stored in an object.
+    #[cfg(feature = "gc")]
+    pub unsafe fn gc(&self, roots: impl FnOnce(&mut GcMarker)) -> Result<usize, VNVError> {
+        let mut inner = self.inner.borrow_mut();
+        let relocations = inner.read_pending_relocations()?;
+
+        let mut marker = GcMarker::new(&mut *inner, relocations);
+        roots(&mut marker);
+        let reachable = marker.finish()?;
+
+        inner.sweep_allocations(reachable)
+    }
+
+    /// Returns how many bytes can be dirty at the same time.
+    ///
+    /// This is `VNVConfig::max_dirty_bytes` or less if it is limited by `set_max_dirty_bytes` or the persist latency budget.
+    pub fn get_max_dirty_bytes(&self) -> usize {
+        let inner = self.inner.borrow();
+        inner.get_dirty_budget() + calc_resident_buf_default_dirty_size::<A, S>()
+    }
+
+    /// Returns `VNVConfig::max_dirty_bytes` this heap was created with, i.e. the upper bound of `set_max_dirty_bytes`
+    pub fn get_dirty_capacity(&self) -> usize {
+        let inner = self.inner.borrow();
+        inner.get_dirty_capacity() + calc_resident_buf_default_dirty_size::<A, S>()
+    }
+
+    /// Changes how many bytes can be dirty at the same time, e.g. temporarily or
+    /// if the energy that is available for persisting changes at runtime (see `EnergyMonitor`).
+    ///
+    /// The storage region for persisting is reserved when the heap is created,
+    /// so `VNVError::Unsupported` is returned if `max_dirty_bytes` is larger than `get_dirty_capacity`.
+    /// If a persist latency budget is set, the limit is reduced further to its limit.
+    /// If more bytes are dirty than the new limit allows, objects are synced.
+    /// If this is not possible (e.g. because they are in use), `VNVError::DirtyBudgetExhausted` is returned
+    /// and the previous limit stays in place.
+    pub fn set_max_dirty_bytes(&self, max_dirty_bytes: usize) -> Result<(), VNVError> {
+        if max_dirty_bytes > self.get_dirty_capacity() {
+            return Err(VNVError::Unsupported);
+        }
+
+        let prev = core::mem::replace(
+            &mut self.inner.borrow_mut().resident_object_manager.max_dirty_bytes,
+            max_dirty_bytes,
+        );
+        let res = self.update_dirty_limit();
+        if res.is_err() {
+            self.inner.borrow_mut().resident_object_manager.max_dirty_bytes = prev;
+        }
+        res
+    }
+
+    /// Limits the dirty bytes to the lower of `set_max_dirty_bytes` and the persist latency budget
+    fn update_dirty_limit(&self) -> Result<(), VNVError> {
+        let mut inner = self.inner.borrow_mut();
+        let mut limit = inner.resident_object_manager.max_dirty_bytes;
+        if let Some(budget) = inner.resident_object_manager.persist_latency_budget {
+            limit = limit.min(budget.max_persisted_bytes());
+        }
+        let limit = limit.saturating_sub(calc_resident_buf_default_dirty_size::<A, S>());
+
+        // additional bytes have to be drawn from the pool before they can be dirtied
+        #[cfg(feature = "global_dirty_budget")]
+        if inner.resident_object_manager.dirty_budget_reservation.is_some() {
+            let max_dirty_bytes = limit.min(inner.get_dirty_capacity()).max(inner.get_dirty_budget())
+                + calc_resident_buf_default_dirty_size::<A, S>();
+            let reservation = inner.resident_object_manager.dirty_budget_reservation.as_mut().unwrap();
+            reservation.resize(max_dirty_bytes)?;
+        }
+
+        let res = inner.set_dirty_limit(limit);
+
+        // returns the bytes that are not needed anymore (or all additional bytes if the limit could not be changed)
+        #[cfg(feature = "global_dirty_budget")]
+        if inner.resident_object_manager.dirty_budget_reservation.is_some() {
+            let max_dirty_bytes = inner.get_dirty_budget() + calc_resident_buf_default_dirty_size::<A, S>();
+            let reservation = inner.resident_object_manager.dirty_budget_reservation.as_mut().unwrap();
+            reservation.resize(max_dirty_bytes).unwrap();
+        }
+
+        res
+    }
+
+    /// Draws the dirty bytes of this heap (see `get_max_dirty_bytes`) from `pool`, so that the dirty data of
+    /// all heaps and other components that share the power-fail window never exceeds the budget of the pool.
+    ///
+    /// The bytes are returned to the pool if the heap is dropped or if it is unregistered with `None`.
+    /// Afterwards, `set_max_dirty_bytes` and `set_persist_latency_budget` draw additional bytes from the pool
+    /// and return unused ones. If the pool has not enough bytes left, `VNVError::DirtyBudgetExhausted` is returned
+    /// and nothing changes. Use `set_max_dirty_bytes` to register a heap with a lower limit in that case.
+    #[cfg(feature = "global_dirty_budget")]
+    pub fn set_dirty_budget_pool(&self, pool: Option<&'static DirtyBudgetPool>) -> Result<(), VNVError> {
+        let mut inner = self.inner.borrow_mut();
+        let curr = &inner.resident_object_manager.dirty_budget_reservation;
+        if let (Some(pool), Some(curr)) = (pool, curr) {
+            if curr.is_from(pool) {
+                return Ok(());
+            }
+        }
+
+        let reservation = match pool {
+            Some(pool) => {
+                let max_dirty_bytes = inner.get_dirty_budget() + calc_resident_buf_default_dirty_size::<A, S>();
+                Some(DirtyBudgetReservation::new(pool, max_dirty_bytes)?)
+            }
+            None => None,
+        };
+
+        // the previous reservation (if any) is returned to its pool
+        inner.resident_object_manager.dirty_budget_reservation = reservation;
+        Ok(())
+    }
+
+    /// Returns the persist latency budget of this heap (see `VNVConfig::persist_latency_budget`)
+    pub fn get_persist_latency_budget(&self) -> Option<PersistLatencyBudget> {
+        self.inner.borrow().resident_object_manager.persist_latency_budget
+    }
+
+    /// Changes the persist latency budget of this heap (see `VNVConfig::persist_latency_budget`).
+    ///
+    /// If more bytes are dirty than the new budget allows, objects are synced.
+    /// If this is not possible (e.g. because they are in use), `VNVError::DirtyBudgetExhausted` is returned.
+    /// `None` removes the limit, so that the value of `set_max_dirty_bytes` can be dirty again.
+    pub fn set_persist_latency_budget(&self, budget: Option<PersistLatencyBudget>) -> Result<(), VNVError> {
+        let prev = core::mem::replace(
+            &mut self.inner.borrow_mut().resident_object_manager.persist_latency_budget,
+            budget,
+        );
+        let res = self.update_dirty_limit();
+        if res.is_err() {
+            self.inner.borrow_mut().resident_object_manager.persist_latency_budget = prev;
+        }
+        res
+    }
+
+    /// Updates the write throughput of the persist latency budget, e.g. after the storage timing was measured again.
+    ///
+    /// Returns `VNVError::Unsupported` if this heap has no persist latency budget.
+    pub fn recalibrate_write_throughput(&self, write_throughput: StorageThroughput) -> Result<(), VNVError> {
+        let budget = self.get_persist_latency_budget().ok_or(VNVError::Unsupported)?;
+        self.set_persist_latency_budget(Some(PersistLatencyBudget {
+            write_throughput,
+            ..budget
+        }))
+    }
+
+    /// Measures how long reading and writing takes on the actual storage module, e.g. at startup.
+    ///
+    /// `clock` has to return the current time in µs. Transfers of different sizes are measured `samples` times each
+    /// using a temporarily allocated region of storage. The fitted model is stored for `estimate_persist_latency_us`
+    /// and updates the persist latency budget (see `recalibrate_write_throughput`) if there is one.
+    pub fn calibrate_storage(&self, samples: usize, clock: fn() -> u64) -> Result<StorageTiming, VNVError> {
+        if samples == 0 {
+            return Err(VNVError::Unsupported);
+        }
+
+        let timing = {
+            let mut inner = self.inner.borrow_mut();
+            let timing = inner.calibrate_storage(samples, clock)?;
+            inner.storage_timing = Some(timing);
+            timing
+        };
+
+        if self.get_persist_latency_budget().is_some() {
+            self.recalibrate_write_throughput(timing.write)?;
+        }
+
+        Ok(timing)
+    }
+
+    /// Returns the storage timing that was measured with `calibrate_storage`
+    pub fn get_storage_timing(&self) -> Option<StorageTiming> {
+        self.inner.borrow().storage_timing
+    }
+
+    /// Estimates how long persisting the heap would take right now in µs.
+    ///
+    /// Uses the storage timing of `calibrate_storage` or the write throughput of the persist latency budget.
+    /// Returns `None` if neither is available.
+    pub fn estimate_persist_latency_us(&self) -> Option<u64> {
+        let write_throughput = match self.get_storage_timing() {
+            Some(timing) => timing.write,
+            None => self.get_persist_latency_budget()?.write_throughput,
+        };
+
+        let inner = self.inner.borrow();
+        let persisted_bytes = inner.get_dirty_bytes() + calc_resident_buf_default_dirty_size::<A, S>();
+        Some(write_throughput.transfer_time_us(persisted_bytes))
+    }
+
+    /// Returns how much of the resident buffer is currently used by user data and metadata
+    pub fn get_resident_usage(&self) -> ResidentUsage {
+        let inner = self.inner.borrow();
+        inner.get_resident_usage()
+    }
+
+    /// Returns the highest usage of storage, resident buffer and dirty bytes since this heap was created.
+    ///
+    /// Use `ReservedStorage::write_watermarks` to keep them across reboots.
+    #[cfg(feature = "watermarks")]
+    pub fn get_watermarks(&self) -> Watermarks {
+        self.inner.borrow().resident_object_manager.watermarks.get_watermarks()
+    }
+
+    /// Returns the counters of this heap since it was created together with its current usage
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> HeapMetrics {
+        let inner = self.inner.borrow();
+        let usage = inner.get_resident_usage();
+        let counters = inner.resident_object_manager.stats.metrics;
+        let storage_counters = unsafe { &(*self.cutoff_ptr).storage_counters };
+
+        HeapMetrics {
+            resident_bytes: usage.user_bytes + usage.metadata_bytes,
+            dirty_bytes: inner.get_dirty_bytes(),
+            peak_dirty_bytes: inner.resident_object_manager.watermarks.get_watermarks().max_dirty_bytes,
+            storage_reads: storage_counters.get_reads(),
+            storage_writes: storage_counters.get_writes(),
+            evictions: counters.evictions,
+            allocation_failures: counters.allocation_failures,
+        }
+    }
+
+    /// Returns the statistics of all tracked objects (up to `OBJECT_STATS_CAPACITY`)
+    #[cfg(feature = "object_stats")]
+    pub fn stats_iter(&self) -> ObjectStatsIter<'_> {
+        ObjectStatsIter {
+            index: 0,
+            heap: &*self.inner,
+        }
+    }
+
+    /// Sets the clock that is used to measure `ObjectStats::resident_time` (e.g. a timer in microseconds).
+    ///
+    /// By default (or if `None` is passed), time is measured in the number of objects that were loaded from storage.
+    /// Changing the clock does not convert times that were already measured.
+    #[cfg(feature = "object_stats")]
+    pub fn set_stats_clock(&self, clock: Option<fn() -> u64>) {
+        self.inner.borrow_mut().resident_object_manager.stats.objects.set_clock(clock);
+    }
+
+    /// Sets a hook that is called after the state of this heap was persisted by `vnv_persist_all`
+    /// and before the persist handler is executed (e.g. to log persist events or update energy bookkeeping).
+    ///
+    /// `handler` is called from the same context as `vnv_persist_all` with a summary of the persist.
+    /// If `clock` is set, it is used to measure `PersistSummary::duration_ticks`.
+    /// Replaces the hook that was set before.
+    pub fn set_post_persist_hook(
+        &self,
+        handler: fn(&PersistSummary),
+        clock: Option<fn() -> u64>,
+    ) -> Result<(), VNVError> {
+        let hook = PostPersistHook { handler, clock };
+        unsafe { PERSIST_ACCESS_POINT.set_post_persist_hook(Some(hook)) }.map_err(|()| VNVError::ObjectInUse)
+    }
+
+    /// Removes the hook that was set with `set_post_persist_hook`
+    pub fn clear_post_persist_hook(&self) -> Result<(), VNVError> {
+        unsafe { PERSIST_ACCESS_POINT.set_post_persist_hook(None) }.map_err(|()| VNVError::ObjectInUse)
+    }
+
+    /// Returns the reason why the last attempt to make an object resident failed.
+    ///
+    /// Returns `None` if there was no such failure yet.
+    pub fn get_last_resident_exhaustion(&self) -> Option<ResidentExhaustionReason> {
+        let inner = self.inner.borrow();
+        inner.get_last_resident_exhaustion()
+    }
+
+    /// Sets what happens if an object cannot be made resident while it is dropped (see `DropPolicy`)
+    pub fn set_drop_policy(&self, policy: DropPolicy) {
+        let mut inner = self.inner.borrow_mut();
+        inner.set_drop_policy(policy)
+    }
+
+    pub fn get_drop_policy(&self) -> DropPolicy {
+        let inner = self.inner.borrow();
+        inner.get_drop_policy()
+    }
+
+    /// Sets when modified objects are written back to storage (see `WriteBack`).
+    ///
+    /// Objects that are already dirty are not written back by this call.
+    pub fn set_write_back(&self, write_back: WriteBack) {
+        let mut inner = self.inner.borrow_mut();
+        inner.set_write_back(write_back)
+    }
+
+    pub fn get_write_back(&self) -> WriteBack {
+        let inner = self.inner.borrow();
+        inner.get_write_back()
+    }
+
+    /// Reserves a region of the resident buffer that is big enough to make one object of type `T` resident.
+    ///
+    /// If an object cannot be made resident because all resident objects are in use (see `VNVError::ResidentObjectsInUse`),
+    /// it is made resident in this region instead. The region is reserved again once it is free.
+    /// A previously reserved region is replaced.
+    #[cfg(feature = "emergency_region")]
+    pub fn reserve_emergency_region<T: Sized>(&self) -> Result<(), VNVError> {
+        let mut inner = self.inner.borrow_mut();
+        inner.set_emergency_region(Some(calc_resident_obj_layout_static::<T>(false).0))
+    }
+
+    /// Gives the region reserved with `reserve_emergency_region` back to the resident buffer
+    #[cfg(feature = "emergency_region")]
+    pub fn release_emergency_region(&self) {
+        let mut inner = self.inner.borrow_mut();
+        inner.set_emergency_region(None).expect("releasing the emergency region should not fail")
+    }
+
+    /// Returns `true` if an emergency region is reserved and currently not used by an object
+    #[cfg(feature = "emergency_region")]
+    pub fn is_emergency_region_available(&self) -> bool {
+        let inner = self.inner.borrow();
+        inner.is_emergency_region_available()
+    }
+
+    /// Limits the dirty bytes of background objects (see `VNVBackgroundObject`), so they cannot use up
+    /// the dirty budget that is needed by foreground objects. `None` removes the limit.
+    ///
+    /// If the background objects have more dirty bytes than `limit`, some of them are synced.
+    /// Returns `VNVError::DirtyPoolExhausted` (and keeps the previous limit) if this is not possible because they are in use.
+    #[cfg(feature = "dirty_pools")]
+    pub fn set_background_dirty_limit(&self, limit: Option<usize>) -> Result<(), VNVError> {
+        let mut inner = self.inner.borrow_mut();
+        inner.set_background_dirty_limit(limit)
+    }
+
+    /// Returns how many dirty bytes are currently used by foreground and background objects
+    #[cfg(feature = "dirty_pools")]
+    pub fn get_dirty_pool_usage(&self) -> DirtyPoolUsage {
+        let inner = self.inner.borrow();
+        inner.get_dirty_pool_usage()
+    }
+
+    /// Marks all resident objects as not in use anymore and returns how many objects were still in use.
+    ///
+    /// References release their objects when they are dropped, also while unwinding from a panic.
+    /// This is meant for test harnesses that want to recover from leaked references (e.g. via `core::mem::forget`).
+    ///
+    /// Pinned objects stay pinned (see `VNVObject::pin`), so they are still not unloaded until they are unpinned.
+    ///
+    /// ### Safety
+    ///
+    /// There must not be any `VNVRef`, `VNVMutRef` or other reference to an object of this heap that is still alive.
+    /// Otherwise, the object could be unloaded while it is still being accessed.
+    /// This includes `VNVChannel`s: their ring buffer is kept resident by a reference that is held until
+    /// the channel is dropped, so it would be released as well while producers and consumers still access it.
+    pub unsafe fn force_release_all(&self) -> usize {
+        let mut inner = self.inner.borrow_mut();
+        inner.force_release_all()
+    }
+
+}
+
+impl<
+        'a,
+        A: AllocatorModule + 'static,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+        S: PersistentStorageModule + 'static,
+    > VNVHeap<'a, A, N, M, S>
+{
+    /// Calls `f` with the storage module of this heap.
+    ///
+    /// The storage is locked in the meantime, so persisting is queued until `f` returns.
+    fn with_storage<R>(&self, f: impl FnOnce(&mut S) -> Result<R, ()>) -> Result<R, VNVError> {
+        let inner = self.inner.borrow();
+        let res = match inner.storage_reference.try_lock() {
+            Some(_guard) => f(unsafe { &mut (*self.cutoff_ptr).storage }).map_err(VNVError::from),
+            None => Err(VNVError::StorageError),
+        };
+        res
+    }
+}
+
+#[cfg(not(no_std))]
+impl<
+        'a,
+        A: AllocatorModule + 'static,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+        S: PersistentStorageModule + 'static,
+    > VNVHeap<'a, A, N, M, LegacyLayoutStorageModule<S>>
+{
+    /// Copies the data of a legacy region into a newly allocated object.
+    ///
+    /// `constructor` gets the raw data of the region and creates the initial value of the object.
+    /// The legacy region itself is not modified.
+    pub fn import_legacy<'b, T: Sized + 'b>(
+        &'b self,
+        region: &LegacyRegion,
+        constructor: impl FnOnce(&[u8]) -> T,
+    ) -> Result<VNVObject<'b, 'a, T, A, N, M>, VNVError>
+    where
+        'a: 'b,
+    {
+        let mut data = vec![0u8; region.size];
+        self.with_storage(|storage| storage.read_legacy(region, &mut data))?;
+
+        self.allocate(constructor(&data))
+    }
+}
+
+impl<
+        'a,
+        A: AllocatorModule + 'static,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+        S: PersistentStorageModule + 'static,
+    > VNVHeap<'a, A, N, M, PartitionedStorageModule<S>>
+{
+    /// Returns an accessor for the storage regions that are not managed by this heap
+    /// (see `PartitionedStorageModule`).
+    pub fn storage(&self) -> ReservedStorage<'_, 'a, A, N, M, S> {
+        ReservedStorage { vnv_heap: self }
+    }
+}
+
+/// Accessor for the reserved regions of a storage device, whose remaining region is managed by a heap.
+///
+/// All offsets are offsets on the storage device. Accessing the heap region panics.
+pub struct ReservedStorage<
+    'b,
+    'a: 'b,
+    A: AllocatorModule + 'static,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+    S: PersistentStorageModule + 'static,
+> {
+    vnv_heap: &'b VNVHeap<'a, A, N, M, PartitionedStorageModule<S>>,
+}
+
+impl<
+        A: AllocatorModule + 'static,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+        S: PersistentStorageModule + 'static,
+    > ReservedStorage<'_, '_, A, N, M, S>
+{
+    pub fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), VNVError> {
+        self.vnv_heap.with_storage(|storage| storage.read_reserved(offset, dest))
+    }
+
+    pub fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), VNVError> {
+        self.vnv_heap.with_storage(|storage| storage.write_reserved(offset, src))
+    }
+
+    /// Returns whether `[offset, offset + len)` can be accessed
+    pub fn is_reserved(&self, offset: usize, len: usize) -> Result<bool, VNVError> {
+        self.vnv_heap.with_storage(|storage| Ok(storage.is_reserved(offset, len)))
+    }
+
+    /// Region of the storage device that is managed by the heap
+    pub fn get_heap_region(&self) -> Result<Range<usize>, VNVError> {
+        self.vnv_heap.with_storage(|storage| Ok(storage.get_heap_region()))
+    }
+
+    /// Writes `watermarks` to the reserved region at `offset` (`Watermarks::SERIALIZED_SIZE` bytes)
+    #[cfg(feature = "watermarks")]
+    pub fn write_watermarks(&mut self, offset: usize, watermarks: &Watermarks) -> Result<(), VNVError> {
+        self.write(offset, &watermarks.to_bytes())
+    }
+
+    /// Reads watermarks that were written with `write_watermarks`
+    #[cfg(feature = "watermarks")]
+    pub fn read_watermarks(&mut self, offset: usize) -> Result<Watermarks, VNVError> {
+        let mut bytes = [0u8; Watermarks::SERIALIZED_SIZE];
+        self.read(offset, &mut bytes)?;
+        Ok(Watermarks::from_bytes(&bytes))
+    }
+}
+
+impl<
+        'a,
+        A: AllocatorModule + 'static,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+        S: AsyncPersistentStorageModule + 'static,
+    > VNVHeap<'a, A, N, M, S>
+{
+    /// Makes the given object resident (if not already) and awaits the storage read of its data.
+    ///
+    /// Making space in the resident buffer (i.e. writing back or unloading other objects) is still blocking.
+    /// The heap is not borrowed while the read is pending, but the storage stays locked until it finished:
+    /// Persisting the heap is queued in the meantime and other heap operations that need to access the storage
+    /// (including a second `load_async`) fail with `VNVError::StorageError`.
+    /// The loading object is marked as in use, so it is not unloaded before its data arrived.
+    pub(crate) async fn load_async<T: Sized>(
+        &self,
+        identifier: &AllocationIdentifier<T>,
+        use_partial_dirtiness_tracking: bool,
+    ) -> Result<(), VNVError> {
+        let (meta_ptr, storage_reference) = {
+            let mut inner = self.inner.borrow_mut();
+            let meta_ptr = match unsafe { inner.prepare_load(identifier, use_partial_dirtiness_tracking)? } {
+                Some(meta_ptr) => meta_ptr,
+                None => return Ok(()),
+            };
+
+            (meta_ptr, inner.storage_reference.try_lock_clone())
+        };
+
+        let mut pending = PendingLoad::<T, A, N, M> {
+            inner: &self.inner,
+            meta_ptr: Some(meta_ptr),
+            use_partial_dirtiness_tracking,
+            _phantom_data: PhantomData,
+        };
+
+        let res = {
+            // lock the storage so that persisting is queued until this read finished
+            match storage_reference.as_ref().and_then(|storage_reference| storage_reference.try_lock()) {
+                Some(_guard) => unsafe {
+                    let storage = &mut (*self.cutoff_ptr).storage;
+                    let data = (*meta_ptr).dynamic_metadata_to_data_range_mut();
+                    storage
+                        .read_async(identifier.offset + calc_backup_obj_user_data_offset(), data)
+                        .await
+                        .map_err(VNVError::from)
+                },
+                None => Err(VNVError::StorageError),
+            }
+        };
+
+        pending.meta_ptr = None;
+        unsafe {
+            self.inner
+                .borrow_mut()
+                .finish_load::<T>(meta_ptr, use_partial_dirtiness_tracking, res)
+        }
+    }
+}
+
+impl<
+        'a,
+        A: AllocatorModule + 'static,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+        S: MemoryMappedStorageModule + 'static,
+    > VNVHeap<'a, A, N, M, S>
+{
+    /// Returns a pointer to the data of the given object in the mapped storage.
+    ///
+    /// Returns `None` if the object has to be accessed in the resident buffer instead
+    /// (e.g. because it is resident or its data is not mapped or not aligned).
+    pub(crate) fn get_mapped_ptr<T: Sized>(&self, identifier: &AllocationIdentifier<T>) -> Option<*const T> {
+        if cfg!(any(feature = "access_counters", feature = "object_checksums")) {
+            // loading the object is required to count the access and verify its checksum
+            return None;
+        }
+
+        let mut inner = self.inner.borrow_mut();
+        if inner.is_resident(identifier) {
+            return None;
+        }
+
+        let _guard = inner.storage_reference.try_lock()?;
+        let storage = unsafe { &(*self.cutoff_ptr).storage };
+        let ptr = storage.get_mapped_ptr(identifier.offset + calc_backup_obj_user_data_offset(), size_of::<T>())?;
+        if ptr as usize % align_of::<T>() != 0 {
+            return None;
+        }
+
+        Some(ptr as *const T)
+    }
+}
+
+/// Removes a partially loaded object from the resident buffer again if `load_async` is cancelled
+/// (i.e. its future is dropped before the storage read finished).
+struct PendingLoad<'r, 'a, T: Sized, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> {
+    inner: &'r RefCell<VNVHeapInner<'a, A, N, M>>,
+    meta_ptr: Option<*mut ResidentObjectMetadata>,
+    use_partial_dirtiness_tracking: bool,
+    _phantom_data: PhantomData<T>,
+}
+
+impl<T: Sized, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> Drop
+    for PendingLoad<'_, '_, T, A, N, M>
+{
+    fn drop(&mut self) {
+        if let Some(meta_ptr) = self.meta_ptr.take() {
+            let _ = unsafe {
+                self.inner
+                    .borrow_mut()
+                    .finish_load::<T>(meta_ptr, self.use_partial_dirtiness_tracking, Err(VNVError::StorageError))
+            };
+        }
+    }
+}
+
+impl<
+        A: AllocatorModule,
         N: NonResidentAllocatorModule,
         M: ObjectManagementModule,
         S: PersistentStorageModule,
@@ -340,6 +2079,55 @@ impl<
     }
 }
 
+/// Error that is returned if the non-resident allocator could not allocate a region
+#[allow(unused_variables)]
+fn non_resident_space_exhausted(stats: &mut HeapStats) -> VNVError {
+    #[cfg(feature = "metrics")]
+    {
+        stats.metrics.allocation_failures += 1;
+    }
+
+    VNVError::NonResidentSpaceExhausted
+}
+
+/// Releases a reference from within a `Drop` implementation.
+///
+/// If we are unwinding from a panic that happened while the heap was borrowed,
+/// the release is skipped instead of causing a double panic (which would abort).
+/// The flags of such objects can be cleared with `VNVHeap::force_release_all`.
+pub(crate) fn release_on_drop<'a, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule>(
+    vnv_heap: &RefCell<VNVHeapInner<'a, A, N, M>>,
+    release: impl FnOnce(&mut VNVHeapInner<'a, A, N, M>),
+) {
+    match vnv_heap.try_borrow_mut() {
+        Ok(mut heap) => release(&mut heap),
+        Err(_) if std::thread::panicking() => {
+            trace!("Heap is still borrowed while unwinding, could not release reference");
+        }
+        Err(err) => panic!("could not release reference: {}", err),
+    }
+}
+
+/// Storage that is passed to the non-resident allocator of `VNVHeapInner`.
+///
+/// With the `allocator_journal` feature, every write of the allocator is journaled until the next `sync_allocator_state`.
+#[cfg(not(feature = "allocator_journal"))]
+macro_rules! allocator_storage {
+    ($inner:expr) => {
+        $inner.storage_reference
+    };
+}
+
+#[cfg(feature = "allocator_journal")]
+macro_rules! allocator_storage {
+    ($inner:expr) => {
+        {
+            let journal = AllocatorJournal::new($inner.get_recovery_area().get_journal_offset());
+            JournaledStorage::new(&mut $inner.storage_reference, journal)
+        }
+    };
+}
+
 pub(crate) struct VNVHeapInner<
     'a,
     A: AllocatorModule,
@@ -349,6 +2137,14 @@ pub(crate) struct VNVHeapInner<
     storage_reference: SharedStorageReference<'a, 'a>,
     resident_object_manager: ResidentObjectManager<'a, 'a, A, M>,
     non_resident_allocator: N,
+
+    /// Storage timing that was measured with `VNVHeap::calibrate_storage`
+    storage_timing: Option<StorageTiming>,
+
+    /// Slots of the allocation table that are in use (see `VNVHeap::gc`)
+    #[cfg(feature = "gc")]
+    allocation_slots: AllocationSlots,
+
     _phantom_data: PhantomData<A>,
 }
 
@@ -359,14 +2155,31 @@ impl<'a, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementM
         &mut self,
         initial_value: T,
         use_partial_dirtiness_tracking: bool,
-    ) -> Result<AllocationIdentifier<T>, ()> {
+    ) -> Result<AllocationIdentifier<T>, VNVError> {
         trace!("Allocate new object with {} bytes", size_of::<T>());
 
         let backup_obj_layout = calc_backup_obj_layout_static::<T>();
 
+        // objects are stored byte aligned, but non-resident allocators that support it
+        // place over-aligned types (see `VNVAligned`) at an aligned offset
+        let placement_layout = backup_obj_layout.align_to(align_of::<T>()).unwrap();
+
         let metadata_offset = self
             .non_resident_allocator
-            .allocate(backup_obj_layout, &mut self.storage_reference)?;
+            .allocate(placement_layout, &mut allocator_storage!(self))
+            .map_err(|()| non_resident_space_exhausted(&mut self.resident_object_manager.stats))?;
+
+        #[cfg(feature = "watermarks")]
+        self.resident_object_manager.watermarks.storage_allocated(N::ALLOCATION_ROUNDING.apply(backup_obj_layout.size()));
+
+        #[cfg(feature = "recovery")]
+        self.sync_allocator_state()?;
+
+        #[cfg(feature = "gc")]
+        self.track_allocation(metadata_offset, backup_obj_layout)?;
+
+        #[cfg(feature = "deterministic_layout")]
+        self.zero_allocation_slack(metadata_offset, backup_obj_layout)?;
 
         let initial_value = match self.resident_object_manager.try_to_allocate(
             initial_value,
@@ -381,77 +2194,840 @@ impl<'a, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementM
             }
         };
 
+        self.write_new_object(metadata_offset, &initial_value)?;
+
+        // the object now lives in storage, so it must not be dropped here
+        core::mem::forget(initial_value);
+
+        Ok(AllocationIdentifier::<T>::from_offset(metadata_offset))
+    }
+
+    /// Allocates `COUNT` objects that are only written to storage, see `VNVHeap::allocate_many`
+    pub(crate) unsafe fn allocate_many<T: Sized, const COUNT: usize>(
+        &mut self,
+        mut init: impl FnMut(usize) -> T,
+    ) -> Result<[AllocationIdentifier<T>; COUNT], VNVError> {
+        trace!("Allocate {} new objects with {} bytes", COUNT, size_of::<T>());
+
+        let backup_obj_layout = calc_backup_obj_layout_static::<T>();
+
+        let mut offsets = [0usize; COUNT];
+        self.non_resident_allocator
+            .allocate_many(backup_obj_layout, &mut offsets, &mut allocator_storage!(self))
+            .map_err(|()| non_resident_space_exhausted(&mut self.resident_object_manager.stats))?;
+
+        #[cfg(feature = "watermarks")]
+        self.resident_object_manager.watermarks.storage_allocated(COUNT * N::ALLOCATION_ROUNDING.apply(backup_obj_layout.size()));
+
+        #[cfg(feature = "recovery")]
+        self.sync_allocator_state()?;
+
+        #[cfg(feature = "gc")]
+        for offset in offsets {
+            self.track_allocation(offset, backup_obj_layout)?;
+        }
+
+        if let Err(err) = self.write_new_objects(&offsets, &mut init) {
+            for offset in offsets {
+                #[cfg(feature = "gc")]
+                self.untrack_allocation(offset)?;
+
+                self.non_resident_allocator.deallocate(offset, backup_obj_layout, &mut allocator_storage!(self))?;
+
+                #[cfg(feature = "watermarks")]
+                self.resident_object_manager.watermarks.storage_deallocated(N::ALLOCATION_ROUNDING.apply(backup_obj_layout.size()));
+            }
+
+            #[cfg(feature = "recovery")]
+            self.sync_allocator_state()?;
+            return Err(err);
+        }
+
+        Ok(offsets.map(AllocationIdentifier::<T>::from_offset))
+    }
+
+    /// Writes the initial state of a new object that is not resident to storage
+    fn write_new_object<T: Sized>(&mut self, metadata_offset: usize, initial_value: &T) -> Result<(), VNVError> {
+        let data = core::ptr::slice_from_raw_parts((initial_value as *const T) as *const u8, size_of::<T>());
+        self.write_new_data(metadata_offset, unsafe { data.as_ref().unwrap() })
+    }
+
+    /// Same as `write_new_object` for the raw user data of an object
+    fn write_new_data(&mut self, metadata_offset: usize, data: &[u8]) -> Result<(), VNVError> {
+        self.storage_reference
+            .write(metadata_offset + calc_backup_obj_user_data_offset(), data)?;
+
+        #[cfg(feature = "object_checksums")]
         write_storage_data(
             &mut self.storage_reference,
-            metadata_offset + calc_backup_obj_user_data_offset(),
-            &initial_value,
+            metadata_offset + calc_backup_obj_checksum_offset(),
+            &crate::util::crc32(data),
         )?;
-        Ok(AllocationIdentifier::<T>::from_offset(metadata_offset))
+
+        #[cfg(feature = "access_counters")]
+        write_storage_data(
+            &mut self.storage_reference,
+            metadata_offset + calc_backup_obj_access_count_offset(),
+            &(0 as ObjectAccessCount),
+        )?;
+
+        Ok(())
+    }
+
+    /// Same as `write_new_object` for many objects at once.
+    ///
+    /// Objects that are allocated right after each other in storage are collected in a stack buffer
+    /// and written with a single storage write.
+    fn write_new_objects<T: Sized>(
+        &mut self,
+        offsets: &[usize],
+        init: &mut impl FnMut(usize) -> T,
+    ) -> Result<(), VNVError> {
+        let backup_obj_layout = calc_backup_obj_layout_static::<T>();
+        let allocated_size = N::ALLOCATION_ROUNDING.apply(backup_obj_layout.size());
+
+        let mut buffer = [0u8; STORAGE_CHUNK_SIZE];
+        // storage offset of `buffer` and how many bytes of it are used
+        let mut chunk_offset = 0;
+        let mut chunk_len = 0;
+        // end of the region that was allocated for the previous object
+        let mut prev_end = 0;
+
+        for (i, offset) in offsets.iter().copied().enumerate() {
+            #[cfg(feature = "deterministic_layout")]
+            self.zero_allocation_slack(offset, backup_obj_layout)?;
+
+            let continues_chunk = chunk_len != 0
+                && offset == prev_end
+                && offset + backup_obj_layout.size() <= chunk_offset + STORAGE_CHUNK_SIZE;
+            if !continues_chunk && chunk_len != 0 {
+                self.storage_reference.write(chunk_offset, &buffer[..chunk_len])?;
+                chunk_len = 0;
+            }
+
+            let value = init(i);
+            if backup_obj_layout.size() > STORAGE_CHUNK_SIZE {
+                // too big for the buffer
+                self.write_new_object(offset, &value)?;
+            } else {
+                if chunk_len == 0 {
+                    chunk_offset = offset;
+                }
+                let start = offset - chunk_offset;
+                // slack of the previous object
+                buffer[chunk_len..start].fill(0);
+
+                let data = unsafe {
+                    core::slice::from_raw_parts((&value as *const T) as *const u8, size_of::<T>())
+                };
+                let user_data_start = start + calc_backup_obj_user_data_offset();
+                buffer[user_data_start..user_data_start + size_of::<T>()].copy_from_slice(data);
+
+                #[cfg(feature = "object_checksums")]
+                {
+                    let checksum_start = start + calc_backup_obj_checksum_offset();
+                    buffer[checksum_start..checksum_start + size_of::<ObjectChecksum>()]
+                        .copy_from_slice(&crate::util::crc32(data).to_ne_bytes());
+                }
+
+                #[cfg(feature = "access_counters")]
+                {
+                    let access_count_start = start + calc_backup_obj_access_count_offset();
+                    buffer[access_count_start..access_count_start + size_of::<ObjectAccessCount>()]
+                        .copy_from_slice(&(0 as ObjectAccessCount).to_ne_bytes());
+                }
+
+                chunk_len = start + backup_obj_layout.size();
+            }
+
+            // the object now lives in storage, so it must not be dropped here
+            core::mem::forget(value);
+            prev_end = offset + allocated_size;
+        }
+
+        if chunk_len != 0 {
+            self.storage_reference.write(chunk_offset, &buffer[..chunk_len])?;
+        }
+
+        Ok(())
     }
 
     pub(crate) unsafe fn deallocate<T: Sized>(
         &mut self,
         identifier: &AllocationIdentifier<T>,
         use_partial_dirtiness_tracking: bool,
-    ) -> Result<(), ()> {
+    ) -> Result<(), VNVError> {
+        self.try_deallocate(identifier, use_partial_dirtiness_tracking)
+            .map(|_| ())
+    }
+
+    /// Drops and deallocates the object, see `DropPolicy` for what happens if it cannot be made resident
+    pub(crate) unsafe fn try_deallocate<T: Sized>(
+        &mut self,
+        identifier: &AllocationIdentifier<T>,
+        use_partial_dirtiness_tracking: bool,
+    ) -> Result<DropOutcome, VNVError> {
         trace!(
             "Deallocate object with {} bytes (offset {})",
             size_of::<T>(),
             identifier.offset
         );
 
-        self.resident_object_manager.drop(
+        let outcome = self.resident_object_manager.drop(
             identifier,
             use_partial_dirtiness_tracking,
             &mut self.storage_reference,
         )?;
 
+        #[cfg(feature = "object_stats")]
+        self.resident_object_manager.stats.objects.object_deallocated(identifier.offset);
+
+        #[cfg(feature = "persist_priority")]
+        self.resident_object_manager.persist_priority_object_deallocated(identifier.offset);
+
+        #[cfg(feature = "gc")]
+        self.untrack_allocation(identifier.offset)?;
+
         let backup_layout = calc_backup_obj_layout_static::<T>();
         self.non_resident_allocator.deallocate(
             identifier.offset,
             backup_layout,
+            &mut allocator_storage!(self),
+        )?;
+
+        #[cfg(feature = "watermarks")]
+        self.resident_object_manager.watermarks.storage_deallocated(N::ALLOCATION_ROUNDING.apply(backup_layout.size()));
+
+        #[cfg(feature = "recovery")]
+        self.sync_allocator_state()?;
+
+        Ok(outcome)
+    }
+
+    /// Deallocates an object that was split into the parts `first` and `second`
+    #[cfg(not(any(feature = "access_counters", feature = "object_checksums")))]
+    pub(crate) unsafe fn deallocate_split<T: Sized, X: Sized, Y: Sized>(
+        &mut self,
+        first: &AllocationIdentifier<X>,
+        second: &AllocationIdentifier<Y>,
+    ) -> Result<(), VNVError> {
+        trace!(
+            "Deallocate split object with {} bytes (offset {})",
+            size_of::<T>(),
+            first.offset
+        );
+
+        self.resident_object_manager
+            .drop(first, false, &mut self.storage_reference)?;
+        self.resident_object_manager
+            .drop(second, false, &mut self.storage_reference)?;
+
+        #[cfg(feature = "object_stats")]
+        {
+            self.resident_object_manager.stats.objects.object_deallocated(first.offset);
+            self.resident_object_manager.stats.objects.object_deallocated(second.offset);
+        }
+
+        #[cfg(feature = "persist_priority")]
+        {
+            self.resident_object_manager.persist_priority_object_deallocated(first.offset);
+            self.resident_object_manager.persist_priority_object_deallocated(second.offset);
+        }
+
+        #[cfg(feature = "gc")]
+        self.untrack_allocation(first.offset)?;
+
+        let backup_layout = calc_backup_obj_layout_static::<T>();
+        self.non_resident_allocator.deallocate(
+            first.offset,
+            backup_layout,
+            &mut allocator_storage!(self),
+        )?;
+
+        #[cfg(feature = "watermarks")]
+        self.resident_object_manager.watermarks.storage_deallocated(N::ALLOCATION_ROUNDING.apply(backup_layout.size()));
+
+        #[cfg(feature = "recovery")]
+        self.sync_allocator_state()?;
+
+        Ok(())
+    }
+
+    pub(crate) unsafe fn get_mut<T: Sized>(
+        &mut self,
+        identifier: &AllocationIdentifier<T>,
+        use_partial_dirtiness_tracking: bool,
+    ) -> Result<*mut T, VNVError> {
+        self.resident_object_manager.get_mut(
+            identifier,
+            use_partial_dirtiness_tracking,
+            None,
+            &mut self.storage_reference,
+        )
+    }
+
+    /// Same as `get_mut`, but uses (and updates) `token` to find the object if it is still resident
+    pub(crate) unsafe fn get_mut_cached<T: Sized>(
+        &mut self,
+        identifier: &AllocationIdentifier<T>,
+        token: &Cell<ResidencyToken>,
+    ) -> Result<*mut T, VNVError> {
+        self.resident_object_manager.get_mut(
+            identifier,
+            false,
+            Some(token),
+            &mut self.storage_reference,
+        )
+    }
+
+    pub(crate) fn flush_storage(&mut self) -> Result<(), VNVError> {
+        Ok(self.storage_reference.flush()?)
+    }
+
+    fn get_non_resident_offset(&self) -> usize {
+        let non_resident_offset = self.get_slice_slot_size();
+
+        // the slice slots of persist() are located in front of the region of the non-resident allocator
+        #[cfg(feature = "recovery")]
+        let non_resident_offset = COMMIT_RECORD_COUNT * non_resident_offset;
+        non_resident_offset
+    }
+
+    /// Maximal size of the slice that persist() writes
+    fn get_slice_slot_size(&self) -> usize {
+        // persist() needs one usize to specify its slice size
+        self.get_dirty_capacity() + size_of::<usize>()
+    }
+
+    pub(crate) fn snapshot_to<S2: PersistentStorageModule>(&mut self, target: &mut S2) -> Result<(), VNVError> {
+        self.resident_object_manager.flush_all(&mut self.storage_reference)?;
+        self.storage_reference.flush()?;
+
+        let header = SnapshotHeader {
+            storage_size: self.storage_reference.get_max_size(),
+            non_resident_offset: self.get_non_resident_offset(),
+            allocator_state_size: size_of::<N>(),
+        };
+        if target.get_max_size() < header.snapshot_size() {
+            return Err(VNVError::NonResidentSpaceExhausted);
+        }
+
+        let allocator_state = unsafe {
+            slice::from_raw_parts(&self.non_resident_allocator as *const N as *const u8, size_of::<N>())
+        };
+        target.write(0, &header.to_bytes(&SNAPSHOT_MAGIC))?;
+        target.write(header.allocator_state_offset(), allocator_state)?;
+
+        copy_in_chunks(header.storage_size, |offset, chunk| {
+            self.storage_reference.read(offset, chunk)?;
+            target.write(header.storage_offset() + offset, chunk)?;
+            Ok(())
+        })?;
+
+        target.flush()?;
+        Ok(())
+    }
+
+    fn restore_snapshot<S2: PersistentStorageModule>(
+        &mut self,
+        header: &SnapshotHeader,
+        source: &mut S2,
+    ) -> Result<(), VNVError> {
+        // the non-resident allocator state is only valid for the same storage layout
+        if header.non_resident_offset != self.get_non_resident_offset() {
+            return Err(VNVError::Unsupported);
+        }
+
+        copy_in_chunks(header.storage_size, |offset, chunk| {
+            source.read(header.storage_offset() + offset, chunk)?;
+            self.storage_reference.write(offset, chunk)?;
+            Ok(())
+        })?;
+        self.storage_reference.flush()?;
+
+        let mut allocator_state = MaybeUninit::<N>::uninit();
+        source.read(header.allocator_state_offset(), unsafe {
+            slice::from_raw_parts_mut(allocator_state.as_mut_ptr() as *mut u8, size_of::<N>())
+        })?;
+        self.non_resident_allocator = unsafe { allocator_state.assume_init() };
+
+        // the persisted state of the source heap must not be recovered,
+        // and the next persist has to continue after the commit records of the source heap
+        #[cfg(feature = "recovery")]
+        {
+            self.discard_persisted_slices()?;
+            self.read_commit_cursor()?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "recovery")]
+    fn get_recovery_area(&self) -> RecoveryArea {
+        RecoveryArea::new(self.storage_reference.get_max_size(), size_of::<N>())
+    }
+
+    #[cfg(feature = "recovery")]
+    pub(crate) fn get_slice_offsets(&self) -> [usize; COMMIT_RECORD_COUNT] {
+        get_slice_offsets(self.get_slice_slot_size())
+    }
+
+    /// Marks the slices of all slots as empty, so that no persisted state is recovered after a reboot
+    #[cfg(feature = "recovery")]
+    fn discard_persisted_slices(&mut self) -> Result<(), VNVError> {
+        for slice_offset in self.get_slice_offsets() {
+            write_storage_data(&mut self.storage_reference, slice_offset, &size_of::<usize>())?;
+        }
+        self.storage_reference.flush()?;
+        Ok(())
+    }
+
+    /// Lets the next persist continue after the commit records in storage (see `commit_persisted_slice`)
+    #[cfg(feature = "recovery")]
+    fn read_commit_cursor(&mut self) -> Result<(), VNVError> {
+        let slice_offsets = self.get_slice_offsets();
+        let commit_cursor =
+            CommitCursor::read(&mut self.storage_reference, slice_offsets).map_err(|()| VNVError::CorruptedData)?;
+        unsafe { PERSIST_ACCESS_POINT.set_commit_cursor(commit_cursor) }.map_err(|()| VNVError::Unsupported)
+    }
+
+    /// Writes the header, the allocator state and empty roots of a new heap to the recovery area
+    #[cfg(feature = "recovery")]
+    fn init_recovery_area(&mut self) -> Result<(), VNVError> {
+        let recovery_area = self.get_recovery_area();
+        let header = SnapshotHeader {
+            storage_size: self.storage_reference.get_max_size(),
+            non_resident_offset: self.get_non_resident_offset(),
+            allocator_state_size: size_of::<N>(),
+        };
+
+        // nothing was persisted yet
+        self.discard_persisted_slices()?;
+
+        self.storage_reference.write(recovery_area.get_offset(), &header.to_bytes(&RECOVERY_MAGIC))?;
+
+        #[cfg(feature = "allocator_journal")]
+        AllocatorJournal::new(recovery_area.get_journal_offset()).init(&mut self.storage_reference)?;
+
+        let allocator_state = unsafe {
+            slice::from_raw_parts(&self.non_resident_allocator as *const N as *const u8, size_of::<N>())
+        };
+        self.storage_reference.write(recovery_area.get_allocator_state_offset(), allocator_state)?;
+        for slot in 0..RECOVERY_ROOT_COUNT {
+            self.write_root(slot, &RootEntry::EMPTY)?;
+        }
+        for slot in 0..RELOCATION_LOG_COUNT {
+            self.write_relocation(slot, &RelocationEntry::EMPTY)?;
+        }
+        #[cfg(feature = "gc")]
+        for slot in 0..ALLOCATION_TABLE_CAPACITY {
+            self.write_allocation_entry(slot, &AllocationEntry::EMPTY)?;
+        }
+
+        // records of a previous heap must not be continued
+        let storage_size = self.storage_reference.get_max_size();
+        for slot in 0..COMMIT_RECORD_COUNT {
+            let offset = RecoveryArea::get_commit_record_offset(storage_size, slot);
+            self.storage_reference.write(offset, &[0u8; CommitRecord::SERIALIZED_SIZE])?;
+        }
+        Ok(())
+    }
+
+    /// Writes the state of the non-resident allocator to the recovery area.
+    ///
+    /// This has to be called whenever the allocator changes, so the state is up to date whenever the heap is persisted.
+    /// With the `allocator_journal` feature, only the changed parts are written and all journaled changes are committed.
+    #[cfg(feature = "recovery")]
+    fn sync_allocator_state(&mut self) -> Result<(), VNVError> {
+        let allocator_state = unsafe {
+            slice::from_raw_parts(&self.non_resident_allocator as *const N as *const u8, size_of::<N>())
+        };
+        let allocator_state_offset = self.get_recovery_area().get_allocator_state_offset();
+
+        #[cfg(not(feature = "allocator_journal"))]
+        self.storage_reference.write(allocator_state_offset, allocator_state)?;
+
+        #[cfg(feature = "allocator_journal")]
+        {
+            let journal = AllocatorJournal::new(self.get_recovery_area().get_journal_offset());
+            journal.write_changes(&mut self.storage_reference, allocator_state_offset, allocator_state)?;
+            journal.commit(&mut self.storage_reference)?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "recovery")]
+    fn read_root(&mut self, slot: usize) -> Result<RootEntry, VNVError> {
+        let mut bytes = [0u8; RootEntry::SERIALIZED_SIZE];
+        self.storage_reference.read(self.get_recovery_area().get_root_offset(slot), &mut bytes)?;
+        Ok(RootEntry::from_bytes(&bytes))
+    }
+
+    #[cfg(feature = "recovery")]
+    fn write_root(&mut self, slot: usize, entry: &RootEntry) -> Result<(), VNVError> {
+        let root_offset = self.get_recovery_area().get_root_offset(slot);
+        self.storage_reference.write(root_offset, &entry.to_bytes())?;
+        Ok(())
+    }
+
+    /// Returns the slot and the entry of the root with the encoded key `key`
+    #[cfg(feature = "recovery")]
+    pub(crate) fn get_root(&mut self, key: u64) -> Result<Option<(usize, RootEntry)>, VNVError> {
+        for slot in 0..RECOVERY_ROOT_COUNT {
+            let entry = self.read_root(slot)?;
+            if entry.key == key {
+                return Ok(Some((slot, entry)));
+            }
+        }
+        Ok(None)
+    }
+
+    #[cfg(feature = "recovery")]
+    pub(crate) fn set_root(&mut self, entry: RootEntry) -> Result<(), VNVError> {
+        let slot = match self.get_root(entry.key)? {
+            Some((slot, _)) => slot,
+            None => match self.get_root(RootEntry::EMPTY.key)? {
+                Some((slot, _)) => slot,
+                None => return Err(VNVError::RootsExhausted),
+            },
+        };
+        self.write_root(slot, &entry)
+    }
+
+    #[cfg(feature = "recovery")]
+    fn read_relocation(&mut self, slot: usize) -> Result<RelocationEntry, VNVError> {
+        let mut bytes = [0u8; RelocationEntry::SERIALIZED_SIZE];
+        self.storage_reference.read(self.get_recovery_area().get_relocation_offset(slot), &mut bytes)?;
+        Ok(RelocationEntry::from_bytes(&bytes))
+    }
+
+    #[cfg(feature = "recovery")]
+    fn write_relocation(&mut self, slot: usize, entry: &RelocationEntry) -> Result<(), VNVError> {
+        let relocation_offset = self.get_recovery_area().get_relocation_offset(slot);
+        self.storage_reference.write(relocation_offset, &entry.to_bytes())?;
+        Ok(())
+    }
+
+    /// Returns a slot of the relocation log that is not pending anymore
+    /// and the commit number of the next persist, which is stored in the new entry
+    #[cfg(feature = "recovery")]
+    fn find_free_relocation_slot(&mut self) -> Result<Option<(usize, u64)>, VNVError> {
+        let committed_sequence = read_committed_sequence(&mut self.storage_reference)?;
+        for slot in 0..RELOCATION_LOG_COUNT {
+            if !self.read_relocation(slot)?.is_pending(committed_sequence) {
+                return Ok(Some((slot, committed_sequence + 1)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the objects that were moved after the last committed persist
+    #[cfg(feature = "recovery")]
+    pub(crate) fn read_pending_relocations(&mut self) -> Result<PendingRelocations, VNVError> {
+        let committed_sequence = read_committed_sequence(&mut self.storage_reference)?;
+        let mut relocations = PendingRelocations::new();
+        for slot in 0..RELOCATION_LOG_COUNT {
+            let entry = self.read_relocation(slot)?;
+            if entry.is_pending(committed_sequence) {
+                relocations.push(entry);
+            }
+        }
+        Ok(relocations)
+    }
+
+    /// Points all roots that point to the object at `offset` to `new_offset` instead
+    #[cfg(feature = "recovery")]
+    fn relocate_roots(&mut self, offset: usize, new_offset: usize) -> Result<(), VNVError> {
+        for slot in 0..RECOVERY_ROOT_COUNT {
+            let entry = self.read_root(slot)?;
+            if entry.key != RootEntry::EMPTY.key && entry.offset == offset {
+                self.write_root(slot, &RootEntry { offset: new_offset, ..entry })?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "recovery")]
+    pub(crate) fn remove_root(&mut self, key: u64) -> Result<(), VNVError> {
+        if let Some((slot, _)) = self.get_root(key)? {
+            self.write_root(slot, &RootEntry::EMPTY)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "gc")]
+    fn read_allocation_entry(&mut self, slot: usize) -> Result<AllocationEntry, VNVError> {
+        let mut bytes = [0u8; AllocationEntry::SERIALIZED_SIZE];
+        self.storage_reference.read(self.get_recovery_area().get_allocation_table_offset(slot), &mut bytes)?;
+        Ok(AllocationEntry::from_bytes(&bytes))
+    }
+
+    #[cfg(feature = "gc")]
+    fn write_allocation_entry(&mut self, slot: usize, entry: &AllocationEntry) -> Result<(), VNVError> {
+        let entry_offset = self.get_recovery_area().get_allocation_table_offset(slot);
+        self.storage_reference.write(entry_offset, &entry.to_bytes())?;
+        Ok(())
+    }
+
+    /// Reads which slots of the allocation table are in use after the heap was recovered
+    #[cfg(feature = "gc")]
+    fn load_allocation_slots(&mut self) -> Result<(), VNVError> {
+        self.allocation_slots = AllocationSlots::EMPTY;
+        for slot in 0..ALLOCATION_TABLE_CAPACITY {
+            if !self.read_allocation_entry(slot)?.is_empty() {
+                self.allocation_slots.insert(slot);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the object that was allocated at `offset` to the allocation table (see `VNVHeap::gc`).
+    ///
+    /// If the table is full, the object is not tracked.
+    #[cfg(feature = "gc")]
+    fn track_allocation(&mut self, offset: usize, layout: Layout) -> Result<(), VNVError> {
+        if let Some(slot) = self.allocation_slots.find_free() {
+            self.write_allocation_entry(slot, &AllocationEntry::new(offset, layout))?;
+            self.allocation_slots.insert(slot);
+        }
+        Ok(())
+    }
+
+    /// Removes the object at `offset` from the allocation table, this has to happen before it is deallocated
+    #[cfg(feature = "gc")]
+    fn untrack_allocation(&mut self, offset: usize) -> Result<(), VNVError> {
+        for slot in self.allocation_slots.iter() {
+            if self.read_allocation_entry(slot)?.offset == offset {
+                self.write_allocation_entry(slot, &AllocationEntry::EMPTY)?;
+                self.allocation_slots.remove(slot);
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Deallocates all tracked objects that are not in `reachable` and returns how many objects were deallocated.
+    ///
+    /// Objects that are in use or pinned are kept, even if they were not marked.
+    #[cfg(feature = "gc")]
+    pub(crate) fn sweep_allocations(&mut self, reachable: AllocationSlots) -> Result<usize, VNVError> {
+        let mut reclaimed = 0;
+        for slot in self.allocation_slots.iter() {
+            if reachable.contains(slot) {
+                continue;
+            }
+
+            let entry = self.read_allocation_entry(slot)?;
+            if self.resident_object_manager.is_in_use_dynamic(entry.offset) {
+                continue;
+            }
+            let layout = entry.get_layout()?;
+
+            // the object is unreachable, so its data is thrown away without dropping it
+            self.resident_object_manager.drop_dynamic(entry.offset);
+
+            #[cfg(feature = "object_stats")]
+            self.resident_object_manager.stats.objects.object_deallocated(entry.offset);
+
+            #[cfg(feature = "persist_priority")]
+            self.resident_object_manager.persist_priority_object_deallocated(entry.offset);
+
+            self.write_allocation_entry(slot, &AllocationEntry::EMPTY)?;
+            self.allocation_slots.remove(slot);
+
+            self.non_resident_allocator
+                .deallocate(entry.offset, layout, &mut allocator_storage!(self))?;
+
+            #[cfg(feature = "watermarks")]
+            self.resident_object_manager.watermarks.storage_deallocated(N::ALLOCATION_ROUNDING.apply(layout.size()));
+
+            self.sync_allocator_state()?;
+            reclaimed += 1;
+        }
+        Ok(reclaimed)
+    }
+
+    /// Writes the dirty data of the objects that were resident at the last persist back to storage,
+    /// if the persist was committed
+    #[cfg(feature = "recovery")]
+    fn recover_persisted_objects(&mut self, header: &SnapshotHeader) -> Result<PersistCommitStatus, VNVError> {
+        let non_resident_offset = self.get_non_resident_offset();
+        if header.non_resident_offset != non_resident_offset {
+            return Err(VNVError::Unsupported);
+        }
+        let non_resident_end = self.get_recovery_area().get_offset();
+
+        // the power may have failed before the roots of the last moved object were updated
+        let relocations = self.read_pending_relocations()?;
+        for slot in 0..RECOVERY_ROOT_COUNT {
+            let entry = self.read_root(slot)?;
+            let offset = relocations.resolve(entry.offset);
+            if entry.key != RootEntry::EMPTY.key && offset != entry.offset {
+                self.write_root(slot, &RootEntry { offset, ..entry })?;
+            }
+        }
+
+        let slice_offsets = self.get_slice_offsets();
+        let slice_slot_size = self.get_slice_slot_size();
+        let (status, slice_offset) = read_persist_commit_status(&mut self.storage_reference, &slice_offsets, slice_slot_size)
+            .map_err(|()| VNVError::CorruptedData)?;
+        let slice_offset = match slice_offset {
+            Some(slice_offset) => slice_offset,
+            None => {
+                // roll back the incomplete state
+                self.discard_persisted_slices()?;
+                return Ok(status);
+            }
+        };
+
+        let mut iter = PersistedObjectIter::new(&mut self.storage_reference, slice_offset, slice_slot_size)
+            .map_err(|()| VNVError::CorruptedData)?;
+        while let Some(object) = iter.next(&mut self.storage_reference).map_err(|()| VNVError::CorruptedData)? {
+            // the data of clean objects is already up to date in storage
+            let data_offset = match object.dirty_data_offset {
+                Some(data_offset) => data_offset,
+                None => continue,
+            };
+
+            let backup_obj_layout = calc_backup_obj_layout_dynamic(object.layout.size());
+            if object.offset < non_resident_offset || object.offset + backup_obj_layout.size() > non_resident_end {
+                return Err(VNVError::CorruptedData);
+            }
+
+            // load the object as a byte buffer and restore its dirty data,
+            // so it is written back (with its metadata) like any other object
+            let data = unsafe { self.get_bytes_mut(object.offset, object.layout.size())?.as_mut().unwrap() };
+            let res = self.storage_reference.read(data_offset, data);
+            unsafe { self.release_bytes_mut(object.offset) };
+            res?;
+        }
+
+        self.resident_object_manager.flush_all(&mut self.storage_reference)?;
+
+        // the persisted state is part of the storage now
+        self.discard_persisted_slices()?;
+        Ok(status)
+    }
+
+    pub(crate) fn clean(&mut self, budget_bytes: usize) -> usize {
+        self.resident_object_manager.clean(budget_bytes, &mut self.storage_reference)
+    }
+
+    pub(crate) fn flush_object<T: Sized>(
+        &mut self,
+        identifier: &AllocationIdentifier<T>,
+    ) -> Result<usize, VNVError> {
+        self.resident_object_manager.flush_object(
+            identifier,
+            &mut self.storage_reference,
+        )
+    }
+
+    pub(crate) fn discard_changes<T: Sized>(
+        &mut self,
+        identifier: &AllocationIdentifier<T>,
+    ) -> Result<(), VNVError> {
+        self.resident_object_manager.discard_changes(
+            identifier,
             &mut self.storage_reference,
         )
     }
 
-    pub(crate) unsafe fn get_mut<T: Sized>(
+    pub(crate) unsafe fn get_ref<T: Sized>(
         &mut self,
         identifier: &AllocationIdentifier<T>,
         use_partial_dirtiness_tracking: bool,
-    ) -> Result<*mut T, ()> {
-        self.resident_object_manager.get_mut(
+    ) -> Result<*const T, VNVError> {
+        self.resident_object_manager.get_ref(
             identifier,
             use_partial_dirtiness_tracking,
+            None,
             &mut self.storage_reference,
         )
     }
 
-    pub(crate) fn flush_object<T: Sized>(
+    /// Same as `get_ref`, but uses (and updates) `token` to find the object if it is still resident
+    /// Same as `get_ref_cached`, but fails instead of reading from storage (see `VNVObject::try_get_resident`)
+    pub(crate) unsafe fn get_ref_resident<T: Sized>(
         &mut self,
         identifier: &AllocationIdentifier<T>,
-    ) -> Result<(), ()> {
-        self.resident_object_manager.flush_object(
+        token: &Cell<ResidencyToken>,
+    ) -> Result<*const T, VNVError> {
+        self.resident_object_manager.check_resident_access(identifier.offset, false, Some(token))?;
+        self.get_ref_cached(identifier, token)
+    }
+
+    /// Same as `get_mut_cached`, but fails instead of accessing storage (see `VNVObject::try_get_mut_resident`)
+    pub(crate) unsafe fn get_mut_resident<T: Sized>(
+        &mut self,
+        identifier: &AllocationIdentifier<T>,
+        token: &Cell<ResidencyToken>,
+    ) -> Result<*mut T, VNVError> {
+        self.resident_object_manager.check_resident_access(identifier.offset, true, Some(token))?;
+        self.get_mut_cached(identifier, token)
+    }
+
+    pub(crate) unsafe fn get_ref_cached<T: Sized>(
+        &mut self,
+        identifier: &AllocationIdentifier<T>,
+        token: &Cell<ResidencyToken>,
+    ) -> Result<*const T, VNVError> {
+        self.resident_object_manager.get_ref(
             identifier,
+            false,
+            Some(token),
             &mut self.storage_reference,
         )
     }
 
-    pub(crate) unsafe fn get_ref<T: Sized>(
+    pub(crate) unsafe fn prefetch<T: Sized>(
+        &mut self,
+        identifier: &AllocationIdentifier<T>,
+        token: &Cell<ResidencyToken>,
+    ) -> Result<(), VNVError> {
+        self.resident_object_manager.prefetch(identifier, Some(token), &mut self.storage_reference)
+    }
+
+    pub(crate) unsafe fn load_many<T: Sized>(
+        &mut self,
+        count: usize,
+        get_id: impl Fn(usize) -> AllocationIdentifier<T>,
+    ) -> Result<(), VNVError> {
+        self.resident_object_manager
+            .load_many(count, get_id, &mut self.storage_reference)
+    }
+
+    pub(crate) unsafe fn prepare_load<T: Sized>(
         &mut self,
         identifier: &AllocationIdentifier<T>,
         use_partial_dirtiness_tracking: bool,
-    ) -> Result<*const T, ()> {
-        self.resident_object_manager.get_ref(
+    ) -> Result<Option<*mut ResidentObjectMetadata>, VNVError> {
+        self.resident_object_manager.prepare_load(
             identifier,
             use_partial_dirtiness_tracking,
             &mut self.storage_reference,
         )
     }
 
+    pub(crate) unsafe fn finish_load<T: Sized>(
+        &mut self,
+        meta_ptr: *mut ResidentObjectMetadata,
+        use_partial_dirtiness_tracking: bool,
+        res: Result<(), VNVError>,
+    ) -> Result<(), VNVError> {
+        self.resident_object_manager.finish_load::<T, _>(
+            meta_ptr,
+            use_partial_dirtiness_tracking,
+            res,
+            &mut self.storage_reference,
+        )
+    }
+
     pub(crate) unsafe fn get_partial_mut<T: Sized>(
         &mut self,
         identifier: &AllocationIdentifier<T>,
-    ) -> Result<(*mut ResidentObjectMetadata, *mut T), ()> {
+    ) -> Result<(*mut ResidentObjectMetadata, *mut T), VNVError> {
         self.resident_object_manager
             .get_partial_mut(identifier, &mut self.storage_reference)
     }
@@ -461,7 +3037,7 @@ impl<'a, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementM
         meta_ptr: &mut ResidentObjectMetadata,
         addr_offset: usize,
         size: usize,
-    ) -> Result<(), ()> {
+    ) -> Result<(), VNVError> {
         self.resident_object_manager.partial_mut_make_range_dirty(
             meta_ptr,
             addr_offset,
@@ -475,11 +3051,11 @@ impl<'a, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementM
         meta_ptr: *mut ResidentObjectMetadata,
     ) {
         self.resident_object_manager
-            .release_partial_mut::<T>(meta_ptr)
+            .release_partial_mut::<T, _>(meta_ptr, &mut self.storage_reference)
     }
 
     pub(crate) unsafe fn release_mut<T: Sized>(&mut self, identifier: &AllocationIdentifier<T>) {
-        self.resident_object_manager.release_mut(identifier)
+        self.resident_object_manager.release_mut(identifier, &mut self.storage_reference)
     }
 
     pub(crate) unsafe fn release_ref<T: Sized>(&mut self, identifier: &AllocationIdentifier<T>) {
@@ -494,15 +3070,584 @@ impl<'a, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementM
         self.resident_object_manager.is_data_dirty(identifier)
     }
 
+    pub(crate) fn pin<T: Sized>(&mut self, identifier: &AllocationIdentifier<T>) -> Result<(), VNVError> {
+        self.resident_object_manager.pin(identifier, &mut self.storage_reference)
+    }
+
+    pub(crate) fn unpin<T: Sized>(&mut self, identifier: &AllocationIdentifier<T>) {
+        self.resident_object_manager.unpin(identifier)
+    }
+
+    pub(crate) fn is_pinned<T: Sized>(&mut self, identifier: &AllocationIdentifier<T>) -> bool {
+        self.resident_object_manager.is_pinned(identifier)
+    }
+
+    #[cfg(feature = "persist_priority")]
+    pub(crate) fn set_persist_priority<T: Sized>(
+        &mut self,
+        identifier: &AllocationIdentifier<T>,
+        priority: PersistPriority,
+    ) -> Result<(), VNVError> {
+        self.resident_object_manager
+            .set_persist_priority(identifier.offset, priority)
+            .map_err(|()| VNVError::CapacityExhausted)
+    }
+
+    #[cfg(feature = "persist_priority")]
+    pub(crate) fn get_persist_priority<T: Sized>(&self, identifier: &AllocationIdentifier<T>) -> PersistPriority {
+        self.resident_object_manager.get_persist_priority(identifier.offset)
+    }
+
+    #[cfg(feature = "access_counters")]
+    pub(crate) fn get_access_count<T: Sized>(
+        &mut self,
+        identifier: &AllocationIdentifier<T>,
+    ) -> Result<ObjectAccessCount, VNVError> {
+        self.resident_object_manager
+            .get_access_count(identifier, &mut self.storage_reference)
+    }
+
+    pub(crate) unsafe fn force_release_all(&mut self) -> usize {
+        self.resident_object_manager.force_release_all()
+    }
+
     pub(crate) fn count_resident_objects(&self) -> usize {
         self.resident_object_manager.count_resident_objects()
     }
 
+    pub(crate) fn get_resident_usage(&self) -> ResidentUsage {
+        self.resident_object_manager.get_resident_usage()
+    }
+
+    pub(crate) fn get_dirty_budget(&self) -> usize {
+        self.resident_object_manager.get_dirty_budget()
+    }
+
+    /// Returns the dirty budget the heap was created with (including bytes that are withheld by the dirty limit)
+    pub(crate) fn get_dirty_capacity(&self) -> usize {
+        self.resident_object_manager.get_dirty_budget() + self.resident_object_manager.withheld_dirty_size
+    }
+
+    pub(crate) fn set_dirty_limit(&mut self, limit: usize) -> Result<(), VNVError> {
+        self.resident_object_manager.set_dirty_limit(limit, &mut self.storage_reference)
+    }
+
+    /// Returns how many bytes are currently dirty
+    pub(crate) fn get_dirty_bytes(&self) -> usize {
+        self.resident_object_manager.get_dirty_budget() - self.resident_object_manager.remaining_dirty_size
+    }
+
+    #[cfg(feature = "object_stats")]
+    pub(crate) fn get_object_stats(&self, offset: usize) -> Option<ObjectStats> {
+        self.resident_object_manager.stats.objects.get_stats(offset)
+    }
+
+    pub(crate) fn get_last_resident_exhaustion(&self) -> Option<ResidentExhaustionReason> {
+        self.resident_object_manager.last_exhaustion
+    }
+
+    pub(crate) fn set_drop_policy(&mut self, policy: DropPolicy) {
+        self.resident_object_manager.drop_policy = policy;
+    }
+
+    pub(crate) fn get_drop_policy(&self) -> DropPolicy {
+        self.resident_object_manager.drop_policy
+    }
+
+    pub(crate) fn set_write_back(&mut self, write_back: WriteBack) {
+        self.resident_object_manager.write_back = write_back;
+    }
+
+    pub(crate) fn get_write_back(&self) -> WriteBack {
+        self.resident_object_manager.write_back
+    }
+
+    #[cfg(feature = "emergency_region")]
+    pub(crate) fn set_emergency_region(&mut self, layout: Option<Layout>) -> Result<(), VNVError> {
+        self.resident_object_manager.set_emergency_region(layout, &mut self.storage_reference)
+    }
+
+    #[cfg(feature = "emergency_region")]
+    pub(crate) fn is_emergency_region_available(&self) -> bool {
+        self.resident_object_manager.is_emergency_region_available()
+    }
+
+    #[cfg(feature = "dirty_pools")]
+    pub(crate) fn set_background_dirty_limit(&mut self, limit: Option<usize>) -> Result<(), VNVError> {
+        self.resident_object_manager.set_background_dirty_limit(limit, &mut self.storage_reference)
+    }
+
+    #[cfg(feature = "dirty_pools")]
+    pub(crate) fn get_dirty_pool_usage(&self) -> DirtyPoolUsage {
+        self.resident_object_manager.get_dirty_pool_usage()
+    }
+
+    #[cfg(feature = "dirty_pools")]
+    pub(crate) unsafe fn add_to_background_pool<T: Sized>(
+        &mut self,
+        identifier: &AllocationIdentifier<T>,
+    ) -> Result<(), VNVError> {
+        self.resident_object_manager.add_to_background_pool(identifier, &mut self.storage_reference)
+    }
+
+    #[cfg(feature = "dirty_pools")]
+    pub(crate) unsafe fn get_mut_background<T: Sized>(
+        &mut self,
+        identifier: &AllocationIdentifier<T>,
+    ) -> Result<*mut T, VNVError> {
+        self.resident_object_manager.get_mut_background(identifier, &mut self.storage_reference)
+    }
+
+    #[cfg(feature = "object_checksums")]
+    pub(crate) fn verify_checksum<T: Sized>(
+        &mut self,
+        identifier: &AllocationIdentifier<T>,
+    ) -> Result<Option<ObjectChecksumMismatch>, VNVError> {
+        let expected: ObjectChecksum = unsafe {
+            read_storage_data(
+                &mut self.storage_reference,
+                identifier.offset + calc_backup_obj_checksum_offset(),
+            )
+        }?;
+
+        // the object could be bigger than the stack, so read it in chunks
+        let mut buf = [0u8; 64];
+        let mut crc = !0u32;
+        let data_offset = identifier.offset + calc_backup_obj_user_data_offset();
+        let mut pos = 0;
+        while pos < size_of::<T>() {
+            let len = min(buf.len(), size_of::<T>() - pos);
+            self.storage_reference.read(data_offset + pos, &mut buf[..len])?;
+            crc = crate::util::crc32_update(crc, &buf[..len]);
+            pos += len;
+        }
+        let actual = !crc;
+
+        if expected == actual {
+            Ok(None)
+        } else {
+            Ok(Some(ObjectChecksumMismatch {
+                storage_offset: identifier.offset,
+                expected,
+                actual,
+            }))
+        }
+    }
+
+    /// Allocates a region in storage that is not managed as an object (see `VNVStorageSlice`)
+    pub(crate) fn allocate_storage(&mut self, layout: Layout) -> Result<usize, VNVError> {
+        let offset = self
+            .non_resident_allocator
+            .allocate(layout, &mut allocator_storage!(self))
+            .map_err(|()| non_resident_space_exhausted(&mut self.resident_object_manager.stats))?;
+
+        #[cfg(feature = "watermarks")]
+        self.resident_object_manager.watermarks.storage_allocated(N::ALLOCATION_ROUNDING.apply(layout.size()));
+
+        #[cfg(feature = "recovery")]
+        self.sync_allocator_state()?;
+
+        #[cfg(feature = "deterministic_layout")]
+        self.zero_allocation_slack(offset, layout)?;
+
+        Ok(offset)
+    }
+
+    /// Zeroes the bytes that are only allocated because the non-resident allocator rounds up the size.
+    ///
+    /// Otherwise, they would still contain data of previous allocations.
+    #[cfg(feature = "deterministic_layout")]
+    fn zero_allocation_slack(&mut self, offset: usize, layout: Layout) -> Result<(), VNVError> {
+        let allocated_size = N::ALLOCATION_ROUNDING.apply(layout.size());
+        write_zeros(
+            &mut self.storage_reference,
+            offset + layout.size(),
+            allocated_size - layout.size(),
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn deallocate_storage(&mut self, offset: usize, layout: Layout) -> Result<(), VNVError> {
+        self.non_resident_allocator
+            .deallocate(offset, layout, &mut allocator_storage!(self))?;
+
+        #[cfg(feature = "watermarks")]
+        self.resident_object_manager.watermarks.storage_deallocated(N::ALLOCATION_ROUNDING.apply(layout.size()));
+
+        #[cfg(feature = "recovery")]
+        self.sync_allocator_state()?;
+
+        Ok(())
+    }
+
+    /// Measures the storage timing with a temporarily allocated region (see `VNVHeap::calibrate_storage`)
+    pub(crate) fn calibrate_storage(&mut self, samples: usize, clock: fn() -> u64) -> Result<StorageTiming, VNVError> {
+        let layout = Layout::new::<[u8; CALIBRATION_BUFFER_SIZE]>();
+        let offset = self.allocate_storage(layout)?;
+        let res = self.measure_storage_timing(offset, samples, clock);
+        self.deallocate_storage(offset, layout)?;
+        res
+    }
+
+    fn measure_storage_timing(&mut self, offset: usize, samples: usize, clock: fn() -> u64) -> Result<StorageTiming, VNVError> {
+        // only zeros are written, so that the storage content does not depend on the calibration
+        let mut buffer = [0u8; CALIBRATION_BUFFER_SIZE];
+        let mut read = LinearFit::new();
+        let mut write = LinearFit::new();
+
+        for _ in 0..samples {
+            for size in CALIBRATION_TRANSFER_SIZES {
+                let start = clock();
+                self.storage_reference.write(offset, &buffer[..size])?;
+                self.storage_reference.flush()?;
+                write.add(size, clock().saturating_sub(start));
+
+                let start = clock();
+                self.storage_reference.read(offset, &mut buffer[..size])?;
+                read.add(size, clock().saturating_sub(start));
+            }
+        }
+
+        Ok(StorageTiming {
+            read: read.fit(),
+            write: write.fit(),
+        })
+    }
+
+    /// Allocates a byte buffer of `data.len()` bytes and writes `data` to storage (see `VNVBytes`)
+    pub(crate) fn allocate_bytes(&mut self, data: &[u8]) -> Result<usize, VNVError> {
+        let backup_obj_layout = calc_backup_obj_layout_dynamic(data.len());
+
+        let metadata_offset = self
+            .non_resident_allocator
+            .allocate(backup_obj_layout, &mut allocator_storage!(self))
+            .map_err(|()| non_resident_space_exhausted(&mut self.resident_object_manager.stats))?;
+
+        #[cfg(feature = "watermarks")]
+        self.resident_object_manager.watermarks.storage_allocated(N::ALLOCATION_ROUNDING.apply(backup_obj_layout.size()));
+
+        #[cfg(feature = "recovery")]
+        self.sync_allocator_state()?;
+
+        #[cfg(feature = "deterministic_layout")]
+        self.zero_allocation_slack(metadata_offset, backup_obj_layout)?;
+
+        if let Err(err) = self.write_new_data(metadata_offset, data) {
+            self.deallocate_bytes(metadata_offset, data.len())?;
+            return Err(err);
+        }
+
+        Ok(metadata_offset)
+    }
+
+    /// Allocates zeroed storage for an untyped object with the given data layout (see `VNVRawObject`)
+    pub(crate) fn allocate_layout(&mut self, layout: Layout) -> Result<usize, VNVError> {
+        let backup_obj_layout = calc_backup_obj_layout_dynamic(layout.size());
+        let metadata_offset = self.allocate_storage(backup_obj_layout.align_to(layout.align()).unwrap())?;
+
+        if let Err(err) = self.write_new_zeroed_data(metadata_offset, layout.size()) {
+            self.deallocate_bytes(metadata_offset, layout.size())?;
+            return Err(err);
+        }
+
+        Ok(metadata_offset)
+    }
+
+    /// Same as `write_new_data` for `len` zero bytes
+    fn write_new_zeroed_data(&mut self, metadata_offset: usize, len: usize) -> Result<(), VNVError> {
+        let data_offset = metadata_offset + calc_backup_obj_user_data_offset();
+
+        #[cfg(feature = "object_checksums")]
+        let mut crc = !0u32;
+        copy_in_chunks(len, |pos, chunk| {
+            chunk.fill(0);
+            self.storage_reference.write(data_offset + pos, chunk)?;
+
+            #[cfg(feature = "object_checksums")]
+            {
+                crc = crate::util::crc32_update(crc, chunk);
+            }
+            Ok(())
+        })?;
+
+        #[cfg(feature = "object_checksums")]
+        write_storage_data(
+            &mut self.storage_reference,
+            metadata_offset + calc_backup_obj_checksum_offset(),
+            &(!crc as ObjectChecksum),
+        )?;
+
+        #[cfg(feature = "access_counters")]
+        write_storage_data(
+            &mut self.storage_reference,
+            metadata_offset + calc_backup_obj_access_count_offset(),
+            &(0 as ObjectAccessCount),
+        )?;
+
+        Ok(())
+    }
+
+    /// Deallocates a byte buffer, unsynchronized changes are thrown away
+    pub(crate) fn deallocate_bytes(&mut self, offset: usize, len: usize) -> Result<(), VNVError> {
+        self.resident_object_manager.drop_dynamic(offset);
+
+        #[cfg(feature = "object_stats")]
+        self.resident_object_manager.stats.objects.object_deallocated(offset);
+
+        #[cfg(feature = "persist_priority")]
+        self.resident_object_manager.persist_priority_object_deallocated(offset);
+
+        let backup_obj_layout = calc_backup_obj_layout_dynamic(len);
+        self.non_resident_allocator
+            .deallocate(offset, backup_obj_layout, &mut allocator_storage!(self))?;
+
+        #[cfg(feature = "watermarks")]
+        self.resident_object_manager.watermarks.storage_deallocated(N::ALLOCATION_ROUNDING.apply(backup_obj_layout.size()));
+
+        #[cfg(feature = "recovery")]
+        self.sync_allocator_state()?;
+
+        Ok(())
+    }
+
+    /// Moves a byte buffer of `len` bytes to a new region of `new_len` bytes and returns its new offset.
+    ///
+    /// The old region is only deallocated after all data was copied, so the buffer stays intact if this fails.
+    pub(crate) fn resize_bytes(&mut self, offset: usize, len: usize, new_len: usize) -> Result<usize, VNVError> {
+        if new_len == len {
+            return Ok(offset);
+        }
+
+        // the most recent data has to be in storage before it is copied
+        self.unload_bytes(offset)?;
+
+        let new_offset = self.allocate_storage(calc_backup_obj_layout_dynamic(new_len))?;
+        if let Err(err) = self.copy_bytes(offset, len, new_offset, new_len) {
+            self.deallocate_bytes(new_offset, new_len)?;
+            return Err(err);
+        }
+
+        self.deallocate_bytes(offset, len)?;
+        Ok(new_offset)
+    }
+
+    /// Copies the user data (and header) of the byte buffer at `offset` to `new_offset`,
+    /// bytes that did not exist before are zero
+    fn copy_bytes(&mut self, offset: usize, len: usize, new_offset: usize, new_len: usize) -> Result<(), VNVError> {
+        let data_offset = calc_backup_obj_user_data_offset();
+        let copy_len = min(len, new_len);
+
+        #[cfg(feature = "object_checksums")]
+        let mut crc = !0u32;
+        copy_in_chunks(new_len, |pos, chunk| {
+            let read_len = min(chunk.len(), copy_len.saturating_sub(pos));
+            if read_len > 0 {
+                self.storage_reference.read(offset + data_offset + pos, &mut chunk[..read_len])?;
+            }
+            chunk[read_len..].fill(0);
+            self.storage_reference.write(new_offset + data_offset + pos, chunk)?;
+
+            #[cfg(feature = "object_checksums")]
+            {
+                crc = crate::util::crc32_update(crc, chunk);
+            }
+            Ok(())
+        })?;
+
+        #[cfg(feature = "object_checksums")]
+        write_storage_data(
+            &mut self.storage_reference,
+            new_offset + calc_backup_obj_checksum_offset(),
+            &(!crc as ObjectChecksum),
+        )?;
+
+        #[cfg(feature = "access_counters")]
+        {
+            let access_count: ObjectAccessCount = unsafe {
+                read_storage_data(&mut self.storage_reference, offset + calc_backup_obj_access_count_offset())
+            }?;
+            write_storage_data(
+                &mut self.storage_reference,
+                new_offset + calc_backup_obj_access_count_offset(),
+                &access_count,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves the object at `offset` to a free region with a lower offset and returns its new offset,
+    /// or `None` if the allocator has no such region (or the relocation log is full, see `RelocationEntry`).
+    ///
+    /// The old region is only deallocated after all data was copied, so the object stays intact if this fails.
+    pub(crate) fn relocate_object(&mut self, offset: usize, layout: Layout) -> Result<Option<usize>, VNVError> {
+        #[cfg(feature = "recovery")]
+        let (relocation_slot, sequence) = match self.find_free_relocation_slot()? {
+            Some(slot) => slot,
+            None => return Ok(None),
+        };
+
+        let new_offset = match self
+            .non_resident_allocator
+            .allocate_below(layout, offset, &mut allocator_storage!(self))?
+        {
+            Some(new_offset) => new_offset,
+            None => return Ok(None),
+        };
+
+        #[cfg(feature = "watermarks")]
+        self.resident_object_manager.watermarks.storage_allocated(N::ALLOCATION_ROUNDING.apply(layout.size()));
+
+        #[cfg(feature = "recovery")]
+        self.sync_allocator_state()?;
+
+        #[cfg(feature = "gc")]
+        self.track_allocation(new_offset, layout)?;
+
+        #[cfg(feature = "deterministic_layout")]
+        self.zero_allocation_slack(new_offset, layout)?;
+
+        // the most recent data has to be in storage before it is copied
+        let res = self
+            .resident_object_manager
+            .unload_object_dynamic(offset, &mut self.storage_reference)
+            .and_then(|()| self.copy_storage(offset, new_offset, layout.size()));
+        if let Err(err) = res {
+            #[cfg(feature = "gc")]
+            self.untrack_allocation(new_offset)?;
+
+            self.deallocate_storage(new_offset, layout)?;
+            return Err(err);
+        }
+
+        // the copy has to be complete before the relocation is logged
+        #[cfg(feature = "recovery")]
+        {
+            self.storage_reference.flush()?;
+            self.write_relocation(relocation_slot, &RelocationEntry {
+                sequence,
+                old_offset: offset,
+                new_offset,
+            })?;
+            self.relocate_roots(offset, new_offset)?;
+            self.storage_reference.flush()?;
+        }
+
+        #[cfg(feature = "object_stats")]
+        self.resident_object_manager.stats.objects.object_deallocated(offset);
+
+        self.resident_object_manager.object_relocated(offset);
+
+        #[cfg(feature = "persist_priority")]
+        self.resident_object_manager.persist_priority_object_relocated(offset, new_offset);
+
+        #[cfg(feature = "gc")]
+        self.untrack_allocation(offset)?;
+
+        self.deallocate_storage(offset, layout)?;
+        Ok(Some(new_offset))
+    }
+
+    /// Copies `len` bytes in storage from `offset` to `new_offset` (the regions must not overlap)
+    fn copy_storage(&mut self, offset: usize, new_offset: usize, len: usize) -> Result<(), VNVError> {
+        copy_in_chunks(len, |pos, chunk| {
+            self.storage_reference.read(offset + pos, chunk)?;
+            self.storage_reference.write(new_offset + pos, chunk)?;
+            Ok(())
+        })
+    }
+
+    pub(crate) unsafe fn get_bytes_ref(&mut self, offset: usize, len: usize) -> Result<*const [u8], VNVError> {
+        let meta_ptr = self.resident_object_manager.get_ref_dynamic(
+            offset,
+            Layout::array::<u8>(len).unwrap(),
+            false,
+            None,
+            &mut self.storage_reference,
+        )?;
+        Ok(meta_ptr.as_ref().unwrap().dynamic_metadata_to_data_range() as *const [u8])
+    }
+
+    pub(crate) unsafe fn get_bytes_mut(&mut self, offset: usize, len: usize) -> Result<*mut [u8], VNVError> {
+        let meta_ptr = self.resident_object_manager.get_mut_dynamic(
+            offset,
+            Layout::array::<u8>(len).unwrap(),
+            false,
+            None,
+            &mut self.storage_reference,
+        )?;
+        Ok(meta_ptr.as_mut().unwrap().dynamic_metadata_to_data_range_mut() as *mut [u8])
+    }
+
+    /// Makes the untyped object at `offset` resident without making it dirty (see `VNVRawObject::get_ptr`)
+    pub(crate) unsafe fn get_raw_mut(&mut self, offset: usize, layout: Layout) -> Result<*mut ResidentObjectMetadata, VNVError> {
+        self.resident_object_manager
+            .get_clean_mut_dynamic(offset, layout, false, &mut self.storage_reference)
+    }
+
+    /// Makes the whole data of the untyped object dirty (see `VNVRawObject::mark_dirty`)
+    pub(crate) unsafe fn make_raw_dirty(&mut self, meta_ptr: *mut ResidentObjectMetadata) -> Result<(), VNVError> {
+        self.resident_object_manager
+            .make_data_dirty(meta_ptr, &mut self.storage_reference)
+    }
+
+    // byte buffers have no type, but releasing and checking the status of an object only depends on its offset
+
+    pub(crate) unsafe fn release_bytes_ref(&mut self, offset: usize) {
+        self.release_ref(&AllocationIdentifier::<u8>::from_offset(offset))
+    }
+
+    pub(crate) unsafe fn release_bytes_mut(&mut self, offset: usize) {
+        self.release_mut(&AllocationIdentifier::<u8>::from_offset(offset))
+    }
+
+    pub(crate) fn is_bytes_resident(&mut self, offset: usize) -> bool {
+        self.is_resident(&AllocationIdentifier::<u8>::from_offset(offset))
+    }
+
+    pub(crate) fn is_bytes_data_dirty(&mut self, offset: usize) -> bool {
+        self.is_data_dirty(&AllocationIdentifier::<u8>::from_offset(offset))
+    }
+
+    pub(crate) fn unload_bytes(&mut self, offset: usize) -> Result<(), VNVError> {
+        self.resident_object_manager
+            .unload_object_dynamic(offset, &mut self.storage_reference)
+    }
+
+    /// Copies the user data of the object at `offset` (starting at byte `start`) to `dest` without making it resident.
+    ///
+    /// Resident objects may be more recent than their data in storage, so they are read from the resident buffer.
+    pub(crate) fn read_object_range(&mut self, offset: usize, start: usize, dest: &mut [u8]) -> Result<(), VNVError> {
+        if self.resident_object_manager.read_resident_data(offset, start, dest)? {
+            return Ok(());
+        }
+        self.storage_reference.read(offset + calc_backup_obj_user_data_offset() + start, dest)?;
+        Ok(())
+    }
+
+    /// Writes `src` to the user data of the non-resident object at `offset` (starting at byte `start`)
+    pub(crate) fn write_object_range(&mut self, offset: usize, start: usize, src: &[u8]) -> Result<(), VNVError> {
+        debug_assert!(!self.is_resident(&AllocationIdentifier::<u8>::from_offset(offset)));
+
+        self.storage_reference.write(offset + calc_backup_obj_user_data_offset() + start, src)?;
+        Ok(())
+    }
+
+    pub(crate) fn read_storage(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), VNVError> {
+        self.storage_reference.read(offset, dest)?;
+        Ok(())
+    }
+
+    pub(crate) fn write_storage(&mut self, offset: usize, src: &[u8]) -> Result<(), VNVError> {
+        self.storage_reference.write(offset, src)?;
+        Ok(())
+    }
+
     pub(crate) fn unload_object<T: Sized>(
         &mut self,
         identifier: &AllocationIdentifier<T>,
         use_partial_dirtiness_tracking: bool,
-    ) -> Result<(), ()> {
+    ) -> Result<(), VNVError> {
         self.resident_object_manager.unload_object(
             identifier,
             &mut self.storage_reference,
@@ -549,3 +3694,40 @@ impl<'a, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementM
         )
     }
 }
+
+#[cfg(feature = "gc")]
+impl<A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> GcHeap
+    for VNVHeapInner<'_, A, N, M>
+{
+    fn find_allocation(&mut self, offset: usize) -> Result<Option<usize>, VNVError> {
+        for slot in self.allocation_slots.iter() {
+            if self.read_allocation_entry(slot)?.contains(offset) {
+                return Ok(Some(slot));
+            }
+        }
+        Ok(None)
+    }
+
+    fn read_object_data(&mut self, offset: usize, data: &mut [u8]) -> Result<(), VNVError> {
+        self.read_object_range(offset, 0, data)
+    }
+}
+
+#[cfg(feature = "recovery")]
+impl<A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> LinkFixupHeap
+    for VNVHeapInner<'_, A, N, M>
+{
+    fn read_object_data(&mut self, offset: usize, data: &mut [u8]) -> Result<(), VNVError> {
+        self.read_object_range(offset, 0, data)
+    }
+
+    fn write_object_data(&mut self, offset: usize, data: &[u8]) -> Result<(), VNVError> {
+        // the object is written back like any other dirty object, so its checksum is updated as well
+        unsafe {
+            let dest = self.get_bytes_mut(offset, data.len())?;
+            (*dest).copy_from_slice(data);
+            self.release_bytes_mut(offset);
+        }
+        Ok(())
+    }
+}
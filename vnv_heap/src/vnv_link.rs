@@ -0,0 +1,215 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::marker::PhantomData;
+#[cfg(feature = "recovery")]
+use core::{
+    mem::{size_of, ManuallyDrop, MaybeUninit},
+    slice,
+};
+
+use crate::allocation_identifier::AllocationIdentifier;
+#[cfg(feature = "recovery")]
+use crate::{heap_recovery::PendingRelocations, VNVError};
+
+/// Plain data handle to a `VNVObject` that can be stored inside other objects of the same heap.
+///
+/// A link only stores the storage offset of the object, which does not change when the heap is
+/// persisted and restored. Thus, links stay valid across `vnv_persist_all` without any fixups.
+/// Only `VNVHeap::defragment` moves objects, see `VNVLinkFixup` for how links are updated after recovery.
+///
+/// A link does not own the object: Dropping the `VNVObject` invalidates all of its links.
+/// Use `VNVHeap::with_link` to access the linked object.
+#[repr(C)]
+pub struct VNVLink<T: Sized> {
+    offset: usize,
+    _phantom_data: PhantomData<fn() -> T>,
+}
+
+impl<T: Sized> VNVLink<T> {
+    pub(crate) fn new(identifier: &AllocationIdentifier<T>) -> Self {
+        Self {
+            offset: identifier.offset,
+            _phantom_data: PhantomData,
+        }
+    }
+
+    pub(crate) fn get_alloc_id(&self) -> AllocationIdentifier<T> {
+        AllocationIdentifier::from_offset(self.offset)
+    }
+
+    /// Returns `true` if this link points to the object with the given identifier
+    pub(crate) fn points_to(&self, identifier: &AllocationIdentifier<T>) -> bool {
+        self.offset == identifier.offset
+    }
+}
+
+impl<T: Sized> Clone for VNVLink<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Sized> Copy for VNVLink<T> {}
+
+impl<T: Sized> PartialEq for VNVLink<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.offset == other.offset
+    }
+}
+
+impl<T: Sized> Eq for VNVLink<T> {}
+
+impl<T: Sized> core::fmt::Debug for VNVLink<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("VNVLink").field("offset", &self.offset).finish()
+    }
+}
+
+/// Types that store links to other objects of the heap.
+///
+/// If the power fails after `VNVHeap::defragment` moved an object, but before the next persist committed,
+/// the updated links are lost. `VNVHeap::recover_with_fixups` uses this to rebind them while walking the
+/// object graph from its roots, `VNVHeap::fixup_links` rebinds the links of a single object.
+#[cfg(feature = "recovery")]
+pub trait VNVLinkFixup {
+    /// Passes every link that is stored in `self` to `fixup.follow` (or `fixup.rebind`)
+    fn fixup_links(&mut self, fixup: &mut LinkFixup);
+}
+
+/// Number of objects that `VNVHeap::recover_with_fixups` can follow.
+///
+/// If more objects are reachable, recovering fails with `VNVError::CapacityExhausted`.
+#[cfg(feature = "recovery")]
+pub const LINK_FIXUP_CAPACITY: usize = 32;
+
+/// Access to the heap while rebinding the links of the reachable objects
+#[cfg(feature = "recovery")]
+pub(crate) trait LinkFixupHeap {
+    /// Copies the current user data of the object at `offset` to `data` without making it resident
+    fn read_object_data(&mut self, offset: usize, data: &mut [u8]) -> Result<(), VNVError>;
+
+    /// Overwrites the user data of the object at `offset` with `data`
+    fn write_object_data(&mut self, offset: usize, data: &[u8]) -> Result<(), VNVError>;
+}
+
+/// Reads the object at `offset`, rebinds its links and follows them
+#[cfg(feature = "recovery")]
+type FixupFn = fn(&mut LinkFixup, &mut dyn LinkFixupHeap, usize) -> Result<(), VNVError>;
+
+/// Rebinds links to objects that were moved after the last committed persist (see `VNVLinkFixup`)
+#[cfg(feature = "recovery")]
+pub struct LinkFixup {
+    relocations: PendingRelocations,
+    rebound: usize,
+
+    /// `false` if only the links of a single object are rebound (see `VNVHeap::fixup_links`)
+    follow_links: bool,
+    followed: [usize; LINK_FIXUP_CAPACITY],
+    followed_len: usize,
+
+    /// Objects that were followed, but whose links were not rebound yet
+    pending: [Option<(usize, FixupFn)>; LINK_FIXUP_CAPACITY],
+    error: Option<VNVError>,
+}
+
+#[cfg(feature = "recovery")]
+impl LinkFixup {
+    pub(crate) fn new(relocations: PendingRelocations, follow_links: bool) -> Self {
+        Self {
+            relocations,
+            rebound: 0,
+            follow_links,
+            followed: [0; LINK_FIXUP_CAPACITY],
+            followed_len: 0,
+            pending: [None; LINK_FIXUP_CAPACITY],
+            error: None,
+        }
+    }
+
+    /// Points `link` to the new location of its object if the object was moved
+    pub fn rebind<T: Sized>(&mut self, link: &mut VNVLink<T>) {
+        let offset = self.relocations.resolve(link.offset);
+        if offset != link.offset {
+            link.offset = offset;
+            self.rebound += 1;
+        }
+    }
+
+    /// Like `rebind`, but also rebinds the links that are stored in the object `link` points to
+    /// and all objects that are reachable from it (only in `VNVHeap::recover_with_fixups`)
+    pub fn follow<T: VNVLinkFixup>(&mut self, link: &mut VNVLink<T>) {
+        self.rebind(link);
+        if !self.follow_links || self.error.is_some() {
+            return;
+        }
+
+        // objects that are linked multiple times (or cycles) are only fixed once
+        if self.followed[..self.followed_len].contains(&link.offset) {
+            return;
+        }
+        if self.followed_len == LINK_FIXUP_CAPACITY {
+            self.error = Some(VNVError::CapacityExhausted);
+            return;
+        }
+
+        self.followed[self.followed_len] = link.offset;
+        self.pending[self.followed_len] = Some((link.offset, fixup_object::<T>));
+        self.followed_len += 1;
+    }
+
+    /// Rebinds the links of all followed objects
+    pub(crate) fn finish(&mut self, heap: &mut dyn LinkFixupHeap) -> Result<(), VNVError> {
+        // objects are fixed one after another instead of recursively, so long lists do not exhaust the stack
+        while let Some(i) = self.pending.iter().position(|pending| pending.is_some()) {
+            if let Some(err) = self.error {
+                return Err(err);
+            }
+            let (offset, fixup) = self.pending[i].take().unwrap();
+            fixup(self, heap, offset)?;
+        }
+
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns how many links were rebound
+    pub(crate) fn get_rebound_count(&self) -> usize {
+        self.rebound
+    }
+}
+
+#[cfg(feature = "recovery")]
+fn fixup_object<T: VNVLinkFixup>(fixup: &mut LinkFixup, heap: &mut dyn LinkFixupHeap, offset: usize) -> Result<(), VNVError> {
+    let mut data = MaybeUninit::<T>::uninit();
+    let bytes = unsafe { slice::from_raw_parts_mut(data.as_mut_ptr() as *mut u8, size_of::<T>()) };
+    heap.read_object_data(offset, bytes)?;
+
+    // this is only a copy of the object, so it must not be dropped
+    let mut data = ManuallyDrop::new(unsafe { data.assume_init() });
+    let rebound = fixup.get_rebound_count();
+    data.fixup_links(fixup);
+
+    // objects whose links did not change are not written
+    if fixup.get_rebound_count() != rebound {
+        let bytes = unsafe { slice::from_raw_parts(&*data as *const T as *const u8, size_of::<T>()) };
+        heap.write_object_data(offset, bytes)?;
+    }
+    Ok(())
+}
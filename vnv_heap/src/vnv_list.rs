@@ -21,7 +21,7 @@ use crate::{
     allocation_identifier::AllocationIdentifier, modules::{
         allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
         object_management::ObjectManagementModule,
-    }, vnv_heap::VNVHeapInner, vnv_list_mut_ref::VNVListMutRef, vnv_list_ref::VNVListRef
+    }, vnv_error::VNVError, vnv_heap::VNVHeapInner, vnv_list_mut_ref::VNVListMutRef, vnv_list_ref::VNVListRef
 };
 
 pub(crate) struct ListItemContainer<T> {
@@ -30,6 +30,11 @@ pub(crate) struct ListItemContainer<T> {
     pub(crate) data: T,
 }
 
+/// A doubly-linked list whose nodes live in the non-volatile storage.
+///
+/// Every node is a separate object, so nodes are made resident and unloaded individually.
+/// Iterating (see `iter` and `for_each_mut`) unloads every node that was not resident before right after visiting
+/// it, so a traversal only keeps one node resident at a time, no matter how long the list is.
 pub struct VNVList<
     'a,
     'b: 'a,
@@ -41,6 +46,7 @@ pub struct VNVList<
     vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>,
     head: AllocationIdentifier<ListItemContainer<T>>,
     tail: AllocationIdentifier<ListItemContainer<T>>,
+    len: usize,
     phantom_data: PhantomData<T>,
 }
 
@@ -58,11 +64,12 @@ impl<
             vnv_heap,
             head: AllocationIdentifier::new_invalid(),
             tail: AllocationIdentifier::new_invalid(),
+            len: 0,
             phantom_data: PhantomData,
         }
     }
 
-    pub fn push_front(&mut self, data: T) -> Result<(), ()> {
+    pub fn push_front(&mut self, data: T) -> Result<(), VNVError> {
         let item = ListItemContainer {
             data,
             next: self.head.clone(),
@@ -70,34 +77,87 @@ impl<
         };
 
         let mut heap = self.vnv_heap.borrow_mut();
-        
-        let new_id = unsafe { heap.allocate(item, false).unwrap() };
+        let new_id = unsafe { heap.allocate(item, false)? };
 
         if !self.head.is_invalid() {
-            let prev_obj = unsafe {
-                let tmp = match heap.get_mut(&self.head, false) {
-                    Ok(tmp) => tmp,
-                    Err(()) => {
-                        heap.deallocate(&new_id, false).unwrap();
-                        return Err(());
-                    }
-                };
-
-                tmp.as_mut().unwrap()
-            };
-
-            prev_obj.prev = new_id.clone();
-            unsafe { heap.release_mut(&self.head) };
+            if let Err(err) = update_item(&mut heap, &self.head, |item| item.prev = new_id.clone()) {
+                unsafe { heap.deallocate(&new_id, false).unwrap() };
+                return Err(err);
+            }
         } else {
             self.tail = new_id.clone();
         }
 
         self.head = new_id;
+        self.len += 1;
 
         return Ok(())
     }
 
-    pub fn pop_back(&mut self) -> Result<Option<T>, ()> {
+    pub fn push_back(&mut self, data: T) -> Result<(), VNVError> {
+        let item = ListItemContainer {
+            data,
+            next: AllocationIdentifier::new_invalid(),
+            prev: self.tail.clone(),
+        };
+
+        let mut heap = self.vnv_heap.borrow_mut();
+        let new_id = unsafe { heap.allocate(item, false)? };
+
+        if !self.tail.is_invalid() {
+            if let Err(err) = update_item(&mut heap, &self.tail, |item| item.next = new_id.clone()) {
+                unsafe { heap.deallocate(&new_id, false).unwrap() };
+                return Err(err);
+            }
+        } else {
+            self.head = new_id.clone();
+        }
+
+        self.tail = new_id;
+        self.len += 1;
+
+        Ok(())
+    }
+
+    pub fn pop_front(&mut self) -> Result<Option<T>, VNVError> {
+        if self.head.is_invalid() {
+            // no elements left
+            return Ok(None);
+        }
+
+        let mut heap = self.vnv_heap.borrow_mut();
+
+        let (data, next) = unsafe {
+            let item = heap.get_ref(&self.head, false)?;
+            let item = item.as_ref().unwrap();
+            debug_assert!(item.prev.is_invalid());
+
+            (item.data.clone(), item.next.clone())
+        };
+
+        unsafe { heap.release_ref(&self.head) };
+
+        if !next.is_invalid() {
+            update_item(&mut heap, &next, |item| item.prev = AllocationIdentifier::new_invalid())?;
+        } else {
+            // this was the last item in the list, its empty now
+            debug_assert_eq!(self.head.offset, self.tail.offset);
+            self.tail = AllocationIdentifier::new_invalid();
+        }
+
+        unsafe {
+            // TODO: handle this error somehow
+            // we now would be in a invalid state
+            heap.deallocate(&self.head, false).expect("invalid state");
+        }
+
+        self.head = next;
+        self.len -= 1;
+
+        Ok(Some(data))
+    }
+
+    pub fn pop_back(&mut self) -> Result<Option<T>, VNVError> {
         if self.tail.is_invalid() {
             // no elements left
             return Ok(None);
@@ -106,24 +166,17 @@ impl<
         let mut heap = self.vnv_heap.borrow_mut();
 
         let (data, prev) = unsafe {
-            let item = heap.get_mut(&self.tail, false)?;
-            let item = item.as_mut().unwrap();
+            let item = heap.get_ref(&self.tail, false)?;
+            let item = item.as_ref().unwrap();
             debug_assert!(item.next.is_invalid());
 
             (item.data.clone(), item.prev.clone())
         };
 
-        unsafe { heap.release_mut(&self.tail) };
-
+        unsafe { heap.release_ref(&self.tail) };
 
         if !prev.is_invalid() {
-            let prev_obj = unsafe {
-                let tmp = heap.get_mut(&prev, false)?;
-                tmp.as_mut().unwrap()
-            };
-
-            prev_obj.next = AllocationIdentifier::new_invalid();
-            unsafe { heap.release_mut(&prev) };
+            update_item(&mut heap, &prev, |item| item.next = AllocationIdentifier::new_invalid())?;
         } else {
             // this was the last item in the list, its empty now
             debug_assert_eq!(self.head.offset, self.tail.offset);
@@ -137,42 +190,125 @@ impl<
         }
 
         self.tail = prev;
+        self.len -= 1;
 
         return Ok(Some(data))
     }
 
-    pub fn peek_back(&mut self) -> Result<Option<VNVListRef<'a, '_, '_, 'b, T, A, N, M>>, ()> {
-        if self.tail.is_invalid() {
+    pub fn peek_front(&mut self) -> Result<Option<VNVListRef<'a, '_, '_, 'b, T, A, N, M>>, VNVError> {
+        Self::peek(self.vnv_heap, &self.head)
+    }
+
+    pub fn peek_front_mut(&mut self) -> Result<Option<VNVListMutRef<'a, '_, '_, 'b, T, A, N, M>>, VNVError> {
+        Self::peek_mut(self.vnv_heap, &self.head)
+    }
+
+    pub fn peek_back(&mut self) -> Result<Option<VNVListRef<'a, '_, '_, 'b, T, A, N, M>>, VNVError> {
+        Self::peek(self.vnv_heap, &self.tail)
+    }
+
+    pub fn peek_back_mut(&mut self) -> Result<Option<VNVListMutRef<'a, '_, '_, 'b, T, A, N, M>>, VNVError> {
+        Self::peek_mut(self.vnv_heap, &self.tail)
+    }
+
+    fn peek<'c>(
+        vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>,
+        id: &'c AllocationIdentifier<ListItemContainer<T>>,
+    ) -> Result<Option<VNVListRef<'a, 'c, 'c, 'b, T, A, N, M>>, VNVError> {
+        if id.is_invalid() {
             // no elements in list
             return Ok(None);
         }
 
-        let mut heap = self.vnv_heap.borrow_mut();
+        let mut heap = vnv_heap.borrow_mut();
 
         let item = unsafe {
-            let tmp = heap.get_ref(&self.tail, false)?;
+            let tmp = heap.get_ref(id, false)?;
             tmp.as_ref().unwrap()
         };
 
-        Ok(Some(unsafe { VNVListRef::new(self.vnv_heap, &self.tail, item) }))
+        Ok(Some(unsafe { VNVListRef::new(vnv_heap, id, item) }))
     }
 
-    pub fn peek_back_mut(&mut self) -> Result<Option<VNVListMutRef<'a, '_, '_, 'b, T, A, N, M>>, ()> {
-        if self.tail.is_invalid() {
+    fn peek_mut<'c>(
+        vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>,
+        id: &'c AllocationIdentifier<ListItemContainer<T>>,
+    ) -> Result<Option<VNVListMutRef<'a, 'c, 'c, 'b, T, A, N, M>>, VNVError> {
+        if id.is_invalid() {
             // no elements in list
             return Ok(None);
         }
 
-        let mut heap = self.vnv_heap.borrow_mut();
+        let mut heap = vnv_heap.borrow_mut();
 
         let item = unsafe {
-            let tmp = heap.get_mut(&self.tail, false)?;
+            let tmp = heap.get_mut(id, false)?;
             tmp.as_mut().unwrap()
         };
 
-        Ok(Some(unsafe { VNVListMutRef::new(self.vnv_heap, &self.tail, item) }))
+        Ok(Some(unsafe { VNVListMutRef::new(vnv_heap, id, item) }))
+    }
+
+    /// Returns an iterator over copies of the items, from the front to the back (or reversed with `rev`).
+    ///
+    /// Nodes that were not resident before are unloaded again after they were visited.
+    pub fn iter(&mut self) -> VNVListIter<'_, 'a, 'b, T, A, N, M> {
+        VNVListIter {
+            front: self.head.clone(),
+            back: self.tail.clone(),
+            remaining: self.len,
+            list: self,
+        }
+    }
+
+    /// Calls `f` for every item, from the front to the back.
+    ///
+    /// Nodes that were not resident before are written back and unloaded again after they were visited.
+    pub fn for_each_mut<F: FnMut(&mut T)>(&mut self, mut f: F) -> Result<(), VNVError> {
+        let mut heap = self.vnv_heap.borrow_mut();
+
+        let mut curr = self.head.clone();
+        while !curr.is_invalid() {
+            let was_resident = heap.is_resident(&curr);
+
+            let next = unsafe {
+                let item = heap.get_mut(&curr, false)?.as_mut().unwrap();
+                f(&mut item.data);
+                item.next.clone()
+            };
+            unsafe { heap.release_mut(&curr) };
+
+            if !was_resident {
+                heap.unload_object(&curr, false)?;
+            }
+
+            curr = next;
+        }
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Changes the node `id` with `f` (e.g. to update one of its links)
+fn update_item<T: Sized, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule, F: FnOnce(&mut ListItemContainer<T>)>(
+    heap: &mut VNVHeapInner<'_, A, N, M>,
+    id: &AllocationIdentifier<ListItemContainer<T>>,
+    f: F,
+) -> Result<(), VNVError> {
+    unsafe {
+        f(heap.get_mut(id, false)?.as_mut().unwrap());
+        heap.release_mut(id);
+    }
+
+    Ok(())
 }
 
 impl<T: Sized + Clone, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> Drop
@@ -188,51 +324,86 @@ impl<T: Sized + Clone, A: AllocatorModule, N: NonResidentAllocatorModule, M: Obj
     }
 }
 
-/*
-pub struct VNVListIterMut<
+/// Iterator over the items of a `VNVList`, see `VNVList::iter`
+pub struct VNVListIter<
+    'l,
     'a,
     'b: 'a,
-    'c,
-    T: Sized,
+    T: Sized + Clone,
     A: AllocatorModule,
     N: NonResidentAllocatorModule,
     M: ObjectManagementModule,
 > {
-    list: &'c mut VNVList<'a, 'b, T, A, N, M>,
-
-    curr_head: AllocationIdentifier<ListItemContainer<T>>,
-    curr_tail: AllocationIdentifier<ListItemContainer<T>>,
+    list: &'l mut VNVList<'a, 'b, T, A, N, M>,
+    front: AllocationIdentifier<ListItemContainer<T>>,
+    back: AllocationIdentifier<ListItemContainer<T>>,
+    remaining: usize,
 }
 
-impl<
-        'a,
-        'b: 'a,
-        'c,
-        T: Sized,
-        A: AllocatorModule,
-        N: NonResidentAllocatorModule,
-        M: ObjectManagementModule,
-    > VNVListIterMut<'a, 'b, 'c, T, A, N, M>
+impl<T: Sized + Clone, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule>
+    VNVListIter<'_, '_, '_, T, A, N, M>
 {
-    pub fn next<'d>(&mut self) -> Option<VNVListItem<'a, 'b, 'c, 'd, T, A, N, M>> {
-        todo!();
+    /// Returns a copy of the data and the links (`prev`, `next`) of the node `id`
+    fn visit(
+        &mut self,
+        id: &AllocationIdentifier<ListItemContainer<T>>,
+    ) -> Result<(T, AllocationIdentifier<ListItemContainer<T>>, AllocationIdentifier<ListItemContainer<T>>), VNVError> {
+        let mut heap = self.list.vnv_heap.borrow_mut();
+        let was_resident = heap.is_resident(id);
+
+        let res = unsafe {
+            let item = heap.get_ref(id, false)?.as_ref().unwrap();
+            (item.data.clone(), item.prev.clone(), item.next.clone())
+        };
+        unsafe { heap.release_ref(id) };
+
+        if !was_resident {
+            heap.unload_object(id, false)?;
+        }
+
+        Ok(res)
     }
 }
 
-pub struct VNVListItem<
-    'a,
-    'b: 'a,
-    'c,
-    'd,
-    T: Sized,
-    A: AllocatorModule,
-    N: NonResidentAllocatorModule,
-    M: ObjectManagementModule,
-> {
-    iter: &'d VNVListIterMut<'a, 'b, 'c, T, A, N, M>
+impl<T: Sized + Clone, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> Iterator
+    for VNVListIter<'_, '_, '_, T, A, N, M>
+{
+    type Item = Result<T, VNVError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let front = self.front.clone();
+        Some(self.visit(&front).map(|(data, _, next)| {
+            self.front = next;
+            self.remaining -= 1;
+            data
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
 }
 
-*/
+impl<T: Sized + Clone, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> DoubleEndedIterator
+    for VNVListIter<'_, '_, '_, T, A, N, M>
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let back = self.back.clone();
+        Some(self.visit(&back).map(|(data, prev, _)| {
+            self.back = prev;
+            self.remaining -= 1;
+            data
+        }))
+    }
+}
 
 #[cfg(test)]
 mod test {
@@ -243,7 +414,7 @@ mod test {
     #[test]
     fn test_list_1() {
         let mut buffer = [0u8; 1024];
-        let heap = get_test_heap("test_list_1", 4 * 1024, &mut buffer, 1024, |_, _| {});
+        let heap = get_test_heap("test_list_1", 8 * 1024, &mut buffer, 1024, |_, _| {});
 
         let mut list = heap.new_list::<u64>();
         let mut check_list: VecDeque<u64> = VecDeque::new();
@@ -251,15 +422,29 @@ mod test {
         macro_rules! check_integrity {
             () => {
                 {
+                    assert_eq!(list.len(), check_list.len());
+                    assert_eq!(list.is_empty(), check_list.is_empty());
+
                     if check_list.len() == 0 {
                         assert!(list.peek_back().unwrap().is_none());
                         assert!(list.peek_back_mut().unwrap().is_none());
+                        assert!(list.peek_front().unwrap().is_none());
+                        assert!(list.peek_front_mut().unwrap().is_none());
                 
                         assert!(list.pop_back().unwrap().is_none());                
+                        assert!(list.pop_front().unwrap().is_none());
                     } else {
                         let x = list.peek_back().unwrap().unwrap();
                         assert_eq!(check_list[check_list.len() - 1], *x);
+                        drop(x);
+                        let x = list.peek_front().unwrap().unwrap();
+                        assert_eq!(check_list[0], *x);
                     }
+
+                    let items: Vec<u64> = list.iter().map(|item| item.unwrap()).collect();
+                    assert!(items.iter().eq(check_list.iter()));
+                    let items: Vec<u64> = list.iter().rev().map(|item| item.unwrap()).collect();
+                    assert!(items.iter().eq(check_list.iter().rev()));
                 }
             };
         }
@@ -283,6 +468,21 @@ mod test {
             };
         }
 
+        macro_rules! push_back {
+            ($item: expr) => {
+                list.push_back($item).unwrap();
+                check_list.push_back($item);
+                check_integrity!();
+            };
+        }
+
+        macro_rules! pop_front {
+            () => {
+                assert_eq!(list.pop_front().unwrap(), check_list.pop_front());
+                check_integrity!();
+            };
+        }
+
         check_integrity!();
         pop!();
 
@@ -307,6 +507,70 @@ mod test {
         pop!();
         pop!();
 
+        push_back!(3);
+        pop_front!();
+        pop_front!();
+
+        push_back!(4);
+        push!(8);
+        push_back!(15);
+        push!(16);
+
+        *list.peek_front_mut().unwrap().unwrap() = 17;
+        *check_list.front_mut().unwrap() = 17;
+        check_integrity!();
+
+        list.for_each_mut(|item| *item += 1).unwrap();
+        check_list.iter_mut().for_each(|item| *item += 1);
+        check_integrity!();
+
+        pop_front!();
+        pop!();
+        pop_front!();
+        pop!();
+        pop_front!();
+    }
+
+    #[test]
+    fn test_list_iter_one_node_resident() {
+        let mut buffer = [0u8; 1024];
+        let heap = get_test_heap("test_list_iter_one_node_resident", 8 * 1024, &mut buffer, 1024, |_, _| {});
 
+        let mut list = heap.new_list::<[u8; 32]>();
+        for i in 0..20 {
+            list.push_back([i; 32]).unwrap();
+        }
+
+        // unload all nodes
+        {
+            let mut inner = heap.get_inner().borrow_mut();
+            let mut curr = list.head.clone();
+            while !curr.is_invalid() {
+                let next = unsafe {
+                    let next = inner.get_ref(&curr, false).unwrap().as_ref().unwrap().next.clone();
+                    inner.release_ref(&curr);
+                    next
+                };
+                inner.unload_object(&curr, false).unwrap();
+                curr = next;
+            }
+        }
+        assert_eq!(heap.get_resident_usage().resident_objects, 0);
+
+        let mut iter = list.iter();
+        for i in 0..20 {
+            assert_eq!(iter.next().unwrap().unwrap(), [i; 32]);
+            assert_eq!(heap.get_resident_usage().resident_objects, 0);
+        }
+        assert!(iter.next().is_none());
+
+        list.for_each_mut(|item| item[0] = 100).unwrap();
+        assert_eq!(heap.get_resident_usage().resident_objects, 0);
+
+        for (i, item) in list.iter().rev().enumerate() {
+            let mut expected = [19 - i as u8; 32];
+            expected[0] = 100;
+            assert_eq!(item.unwrap(), expected);
+        }
     }
 }
\ No newline at end of file
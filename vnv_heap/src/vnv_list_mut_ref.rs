@@ -17,7 +17,7 @@
 
 use core::{cell::RefCell, ops::{Deref, DerefMut}};
 
-use crate::{allocation_identifier::AllocationIdentifier, modules::{allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule, object_management::ObjectManagementModule}, vnv_list::ListItemContainer, VNVHeapInner};
+use crate::{allocation_identifier::AllocationIdentifier, modules::{allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule, object_management::ObjectManagementModule}, vnv_heap::release_on_drop, vnv_list::ListItemContainer, VNVHeapInner};
 pub struct VNVListMutRef<
     'a,
     'b,
@@ -80,10 +80,6 @@ impl<T: Sized, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManag
     for VNVListMutRef<'_, '_, '_, '_, T, A, N, M>
 {
     fn drop(&mut self) {
-        unsafe {
-            self.vnv_heap
-                .borrow_mut()
-                .release_mut(self.allocation_identifier)
-        }
+        release_on_drop(self.vnv_heap, |heap| unsafe { heap.release_mut(self.allocation_identifier) });
     }
 }
@@ -0,0 +1,424 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{
+    cell::RefCell,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+use std::collections::hash_map::DefaultHasher;
+
+use crate::{
+    allocation_identifier::AllocationIdentifier,
+    modules::{
+        allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
+        object_management::ObjectManagementModule,
+    },
+    vnv_error::VNVError,
+    vnv_heap::VNVHeapInner,
+    vnv_map_mut_ref::VNVMapMutRef,
+    vnv_map_ref::VNVMapRef,
+    vnv_storage_slice::VNVStorageSlice,
+};
+
+pub(crate) struct MapEntryContainer<K, V> {
+    pub(crate) next: AllocationIdentifier<MapEntryContainer<K, V>>,
+    pub(crate) key: K,
+    pub(crate) data: V,
+}
+
+type EntryId<K, V> = AllocationIdentifier<MapEntryContainer<K, V>>;
+
+/// A hash map whose entries live in the non-volatile storage.
+///
+/// The bucket array only lives in storage (see `VNVStorageSlice`) and each bucket is a chain of entries.
+/// Every entry is a separate object, so only the entries that are accessed are made resident.
+/// Modifying a value via `get_mut` makes only its entry dirty.
+pub struct VNVMap<
+    'a,
+    'b: 'a,
+    K: Sized + Hash + Eq + Clone,
+    V: Sized + Clone,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>,
+    /// storage offset of the first entry of each bucket
+    buckets: VNVStorageSlice<'a, 'b, usize, A, N, M>,
+    len: usize,
+    phantom_data: PhantomData<(K, V)>,
+}
+
+impl<
+        'a,
+        'b: 'a,
+        K: Sized + Hash + Eq + Clone,
+        V: Sized + Clone,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > VNVMap<'a, 'b, K, V, A, N, M>
+{
+    pub(crate) fn new(vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>, bucket_count: usize) -> Result<Self, VNVError> {
+        assert!(bucket_count > 0, "map needs at least one bucket");
+
+        let invalid = EntryId::<K, V>::new_invalid().offset;
+        Ok(Self {
+            vnv_heap,
+            buckets: VNVStorageSlice::new(vnv_heap, bucket_count, core::iter::repeat(invalid))?,
+            len: 0,
+            phantom_data: PhantomData,
+        })
+    }
+
+    /// Inserts `value` for `key` and returns the previous value of `key` (if any)
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, VNVError> {
+        let bucket = self.bucket_of(&key);
+        let head = self.get_bucket_head(bucket)?;
+
+        if let Some(entry) = self.find_entry(head.clone(), &key)? {
+            let mut heap = self.vnv_heap.borrow_mut();
+            let prev = unsafe {
+                let item = heap.get_mut(&entry, false)?.as_mut().unwrap();
+                core::mem::replace(&mut item.data, value)
+            };
+            unsafe { heap.release_mut(&entry) };
+
+            return Ok(Some(prev));
+        }
+
+        let item = MapEntryContainer {
+            next: head,
+            key,
+            data: value,
+        };
+        let entry = unsafe { self.vnv_heap.borrow_mut().allocate(item, false)? };
+
+        if let Err(err) = self.buckets.write(bucket, &[entry.offset]) {
+            unsafe { self.vnv_heap.borrow_mut().deallocate(&entry, false) }.expect("invalid state");
+            return Err(err);
+        }
+
+        self.len += 1;
+        Ok(None)
+    }
+
+    pub fn get(&mut self, key: &K) -> Result<Option<VNVMapRef<'a, '_, 'b, K, V, A, N, M>>, VNVError> {
+        let head = self.get_bucket_head(self.bucket_of(key))?;
+        let entry = match self.find_entry(head, key)? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        let mut heap = self.vnv_heap.borrow_mut();
+        let item = unsafe {
+            let tmp = heap.get_ref(&entry, false)?;
+            tmp.as_ref().unwrap()
+        };
+
+        Ok(Some(unsafe { VNVMapRef::new(self.vnv_heap, entry, item) }))
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Result<Option<VNVMapMutRef<'a, '_, 'b, K, V, A, N, M>>, VNVError> {
+        let head = self.get_bucket_head(self.bucket_of(key))?;
+        let entry = match self.find_entry(head, key)? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        let mut heap = self.vnv_heap.borrow_mut();
+        let item = unsafe {
+            let tmp = heap.get_mut(&entry, false)?;
+            tmp.as_mut().unwrap()
+        };
+
+        Ok(Some(unsafe { VNVMapMutRef::new(self.vnv_heap, entry, item) }))
+    }
+
+    pub fn contains_key(&mut self, key: &K) -> Result<bool, VNVError> {
+        let head = self.get_bucket_head(self.bucket_of(key))?;
+        Ok(self.find_entry(head, key)?.is_some())
+    }
+
+    /// Removes `key` and returns its value (if any)
+    pub fn remove(&mut self, key: &K) -> Result<Option<V>, VNVError> {
+        let bucket = self.bucket_of(key);
+        let mut prev = EntryId::<K, V>::new_invalid();
+        let mut curr = self.get_bucket_head(bucket)?;
+
+        let mut heap = self.vnv_heap.borrow_mut();
+        while !curr.is_invalid() {
+            let (found, next) = unsafe {
+                let item = heap.get_ref(&curr, false)?.as_ref().unwrap();
+                let data = if item.key == *key { Some(item.data.clone()) } else { None };
+                (data, item.next.clone())
+            };
+            unsafe { heap.release_ref(&curr) };
+
+            let data = match found {
+                Some(data) => data,
+                None => {
+                    prev = curr;
+                    curr = next;
+                    continue;
+                }
+            };
+
+            // unlink the entry
+            if prev.is_invalid() {
+                drop(heap);
+                self.buckets.write(bucket, &[next.offset])?;
+                heap = self.vnv_heap.borrow_mut();
+            } else {
+                unsafe {
+                    heap.get_mut(&prev, false)?.as_mut().unwrap().next = next;
+                    heap.release_mut(&prev);
+                }
+            }
+
+            unsafe {
+                // TODO: handle this error somehow
+                // the entry is not reachable anymore, so it would be leaked
+                heap.deallocate(&curr, false).expect("invalid state");
+            }
+
+            self.len -= 1;
+            return Ok(Some(data));
+        }
+
+        Ok(None)
+    }
+
+    /// Returns an iterator over copies of all keys and values (in no particular order)
+    pub fn iter(&mut self) -> VNVMapIter<'_, 'a, 'b, K, V, A, N, M> {
+        VNVMapIter {
+            map: self,
+            bucket: 0,
+            next: EntryId::<K, V>::new_invalid(),
+        }
+    }
+
+    /// Removes all entries
+    pub fn clear(&mut self) -> Result<(), VNVError> {
+        for bucket in 0..self.buckets.len() {
+            let mut curr = self.get_bucket_head(bucket)?;
+            if curr.is_invalid() {
+                continue;
+            }
+
+            self.buckets.write(bucket, &[EntryId::<K, V>::new_invalid().offset])?;
+
+            let mut heap = self.vnv_heap.borrow_mut();
+            while !curr.is_invalid() {
+                let next = unsafe {
+                    let next = heap.get_ref(&curr, false)?.as_ref().unwrap().next.clone();
+                    heap.release_ref(&curr);
+                    next
+                };
+
+                unsafe { heap.deallocate(&curr, false).expect("invalid state") };
+                self.len -= 1;
+                curr = next;
+            }
+        }
+
+        debug_assert_eq!(self.len, 0);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn bucket_of(&self, key: &K) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.buckets.len() as u64) as usize
+    }
+
+    fn get_bucket_head(&self, bucket: usize) -> Result<EntryId<K, V>, VNVError> {
+        Ok(EntryId::<K, V>::from_offset(self.buckets.get(bucket)?))
+    }
+
+    /// Walks the chain starting at `curr` and returns the entry of `key`
+    fn find_entry(&self, mut curr: EntryId<K, V>, key: &K) -> Result<Option<EntryId<K, V>>, VNVError> {
+        let mut heap = self.vnv_heap.borrow_mut();
+        while !curr.is_invalid() {
+            let (found, next) = unsafe {
+                let item = heap.get_ref(&curr, false)?.as_ref().unwrap();
+                (item.key == *key, item.next.clone())
+            };
+            unsafe { heap.release_ref(&curr) };
+
+            if found {
+                return Ok(Some(curr));
+            }
+            curr = next;
+        }
+
+        Ok(None)
+    }
+}
+
+impl<
+        K: Sized + Hash + Eq + Clone,
+        V: Sized + Clone,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > Drop for VNVMap<'_, '_, K, V, A, N, M>
+{
+    fn drop(&mut self) {
+        self.clear().unwrap();
+    }
+}
+
+/// Iterator over copies of the entries of a `VNVMap`
+pub struct VNVMapIter<
+    'm,
+    'a,
+    'b: 'a,
+    K: Sized + Hash + Eq + Clone,
+    V: Sized + Clone,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    map: &'m mut VNVMap<'a, 'b, K, V, A, N, M>,
+    bucket: usize,
+    next: EntryId<K, V>,
+}
+
+impl<
+        K: Sized + Hash + Eq + Clone,
+        V: Sized + Clone,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > Iterator for VNVMapIter<'_, '_, '_, K, V, A, N, M>
+{
+    type Item = Result<(K, V), VNVError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.next.is_invalid() {
+            if self.bucket >= self.map.buckets.len() {
+                return None;
+            }
+
+            match self.map.get_bucket_head(self.bucket) {
+                Ok(head) => self.next = head,
+                Err(err) => return Some(Err(err)),
+            }
+            self.bucket += 1;
+        }
+
+        let mut heap = self.map.vnv_heap.borrow_mut();
+        let res = unsafe { heap.get_ref(&self.next, false) }.map(|item| {
+            let item = unsafe { item.as_ref().unwrap() };
+            (item.key.clone(), item.data.clone(), item.next.clone())
+        });
+
+        match res {
+            Ok((key, data, next)) => {
+                unsafe { heap.release_ref(&self.next) };
+                self.next = next;
+                Some(Ok((key, data)))
+            }
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::test::get_test_heap;
+
+    #[test]
+    fn test_map() {
+        let mut buffer = [0u8; 1024];
+        let heap = get_test_heap("test_map", 4 * 4096, &mut buffer, 1024, |_, _| {});
+
+        let mut map = heap.new_map::<u32, u64>(8).unwrap();
+        let mut check_map: HashMap<u32, u64> = HashMap::new();
+
+        macro_rules! check_integrity {
+            () => {{
+                assert_eq!(map.len(), check_map.len());
+                assert_eq!(map.is_empty(), check_map.is_empty());
+
+                let mut items: Vec<_> = map.iter().map(|item| item.unwrap()).collect();
+                let mut check_items: Vec<_> = check_map.iter().map(|(k, v)| (*k, *v)).collect();
+                items.sort();
+                check_items.sort();
+                assert_eq!(items, check_items);
+            }};
+        }
+
+        check_integrity!();
+        assert!(map.get(&1).unwrap().is_none());
+        assert_eq!(map.remove(&1).unwrap(), None);
+
+        for i in 0..40 {
+            assert_eq!(map.insert(i, i as u64 * 3).unwrap(), check_map.insert(i, i as u64 * 3));
+        }
+        check_integrity!();
+
+        assert_eq!(map.insert(5, 100).unwrap(), check_map.insert(5, 100));
+        assert_eq!(*map.get(&5).unwrap().unwrap(), 100);
+        assert_eq!(*map.get(&6).unwrap().unwrap(), 18);
+        assert!(map.get(&40).unwrap().is_none());
+        assert!(map.contains_key(&39).unwrap());
+        assert!(!map.contains_key(&41).unwrap());
+
+        *map.get_mut(&7).unwrap().unwrap() = 1234;
+        *check_map.get_mut(&7).unwrap() = 1234;
+        check_integrity!();
+
+        for i in (0..40).step_by(3) {
+            assert_eq!(map.remove(&i).unwrap(), check_map.remove(&i));
+        }
+        assert_eq!(map.remove(&0).unwrap(), None);
+        check_integrity!();
+
+        map.clear().unwrap();
+        check_map.clear();
+        check_integrity!();
+    }
+
+    #[test]
+    fn test_map_only_accessed_entries_resident() {
+        let mut buffer = [0u8; 1024];
+        let heap = get_test_heap("test_map_only_accessed_entries_resident", 4 * 4096, &mut buffer, 1024, |_, _| {});
+
+        // the entries do not fit into the resident buffer at the same time
+        let mut map = heap.new_map::<u32, [u8; 64]>(4).unwrap();
+        for i in 0..30u32 {
+            map.insert(i, [i as u8; 64]).unwrap();
+        }
+
+        for i in 0..30u32 {
+            assert_eq!(*map.get(&i).unwrap().unwrap(), [i as u8; 64]);
+        }
+    }
+}
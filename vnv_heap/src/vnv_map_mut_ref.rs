@@ -0,0 +1,96 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{cell::RefCell, ops::{Deref, DerefMut}};
+
+use crate::{
+    allocation_identifier::AllocationIdentifier,
+    modules::{
+        allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
+        object_management::ObjectManagementModule,
+    },
+    vnv_heap::{release_on_drop, VNVHeapInner}, vnv_map::MapEntryContainer,
+};
+
+pub struct VNVMapMutRef<
+    'a,
+    'c,
+    'd: 'a,
+    K: Sized,
+    V: Sized,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    vnv_heap: &'a RefCell<VNVHeapInner<'d, A, N, M>>,
+    allocation_identifier: AllocationIdentifier<MapEntryContainer<K, V>>,
+    data_ref: &'c mut MapEntryContainer<K, V>,
+}
+
+impl<
+        'a,
+        'c,
+        'd: 'a,
+        K: Sized,
+        V: Sized,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > VNVMapMutRef<'a, 'c, 'd, K, V, A, N, M>
+{
+    pub(crate) unsafe fn new(
+        vnv_heap: &'a RefCell<VNVHeapInner<'d, A, N, M>>,
+        allocation_identifier: AllocationIdentifier<MapEntryContainer<K, V>>,
+        data_ref: &'c mut MapEntryContainer<K, V>,
+    ) -> Self {
+        VNVMapMutRef {
+            vnv_heap,
+            allocation_identifier,
+            data_ref,
+        }
+    }
+
+    pub fn key(&self) -> &K {
+        &self.data_ref.key
+    }
+}
+
+impl<K: Sized, V: Sized, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> Deref
+    for VNVMapMutRef<'_, '_, '_, K, V, A, N, M>
+{
+    type Target = V;
+
+    fn deref(&self) -> &Self::Target {
+        &self.data_ref.data
+    }
+}
+
+impl<K: Sized, V: Sized, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule>
+    DerefMut for VNVMapMutRef<'_, '_, '_, K, V, A, N, M>
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.data_ref.data
+    }
+}
+
+impl<K: Sized, V: Sized, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> Drop
+    for VNVMapMutRef<'_, '_, '_, K, V, A, N, M>
+{
+    fn drop(&mut self) {
+        release_on_drop(self.vnv_heap, |heap| unsafe { heap.release_mut(&self.allocation_identifier) });
+    }
+}
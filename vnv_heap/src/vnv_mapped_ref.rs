@@ -0,0 +1,96 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::ops::Deref;
+
+use crate::{
+    modules::{
+        allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
+        object_management::ObjectManagementModule,
+    },
+    vnv_ref::VNVRef,
+};
+
+enum MappedData<
+    'a,
+    'b,
+    'c,
+    'd: 'a,
+    T: Sized,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    Resident(VNVRef<'a, 'b, 'c, 'd, T, A, N, M>),
+    Mapped(&'c T),
+}
+
+/// Reference to the data of an object returned by `VNVObject::get_mapped`
+pub struct VNVMappedRef<
+    'a,
+    'b,
+    'c,
+    'd: 'a,
+    T: Sized,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    data: MappedData<'a, 'b, 'c, 'd, T, A, N, M>,
+}
+
+impl<
+        'a,
+        'b,
+        'c,
+        'd: 'a,
+        T: Sized,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > VNVMappedRef<'a, 'b, 'c, 'd, T, A, N, M>
+{
+    pub(crate) fn resident(data_ref: VNVRef<'a, 'b, 'c, 'd, T, A, N, M>) -> Self {
+        Self {
+            data: MappedData::Resident(data_ref),
+        }
+    }
+
+    pub(crate) unsafe fn mapped(data_ref: &'c T) -> Self {
+        Self {
+            data: MappedData::Mapped(data_ref),
+        }
+    }
+
+    /// Returns `true` if the data is read directly from storage instead of the resident buffer
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.data, MappedData::Mapped(_))
+    }
+}
+
+impl<T: Sized, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> Deref
+    for VNVMappedRef<'_, '_, '_, '_, T, A, N, M>
+{
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        match &self.data {
+            MappedData::Resident(data_ref) => data_ref,
+            MappedData::Mapped(data_ref) => data_ref,
+        }
+    }
+}
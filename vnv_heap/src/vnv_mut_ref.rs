@@ -26,7 +26,7 @@ use crate::{
         allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
         object_management::ObjectManagementModule,
     },
-    vnv_heap::VNVHeapInner,
+    vnv_heap::{release_on_drop, VNVHeapInner},
 };
 
 pub struct VNVMutRef<
@@ -95,10 +95,6 @@ impl<T: Sized, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManag
     for VNVMutRef<'_, '_, '_, '_, T, A, N, M>
 {
     fn drop(&mut self) {
-        unsafe {
-            self.vnv_heap
-                .borrow_mut()
-                .release_mut(self.allocation_identifier)
-        }
+        release_on_drop(self.vnv_heap, |heap| unsafe { heap.release_mut(self.allocation_identifier) });
     }
 }
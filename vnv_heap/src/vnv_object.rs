@@ -15,20 +15,40 @@
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use core::{cell::RefCell, marker::PhantomData};
+use core::{cell::{Cell, RefCell}, marker::PhantomData, mem::ManuallyDrop, ptr};
 use std::cell::RefMut;
 
+#[cfg(feature = "access_counters")]
+use crate::resident_object_manager::resident_object_backup::ObjectAccessCount;
+#[cfg(feature = "object_checksums")]
+use crate::vnv_heap::ObjectChecksumMismatch;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+#[cfg(feature = "object_stats")]
+use crate::vnv_heap::ObjectStats;
+#[cfg(feature = "persist_priority")]
+use crate::resident_object_manager::persist_priority::PersistPriority;
+use crate::vnv_heap::DropOutcome;
+
 use crate::{
     allocation_identifier::AllocationIdentifier,
     modules::{
         allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
         object_management::ObjectManagementModule,
+        persistent_storage::{AsyncPersistentStorageModule, MemoryMappedStorageModule},
     },
-    vnv_heap::VNVHeapInner,
+    resident_object_manager::residency_token::ResidencyToken,
+    vnv_heap::{VNVHeap, VNVHeapInner},
+    vnv_error::VNVError,
+    vnv_link::VNVLink,
+    vnv_mapped_ref::VNVMappedRef,
     vnv_mut_ref::VNVMutRef,
     vnv_ref::VNVRef,
 };
 
+#[cfg(not(any(feature = "access_counters", feature = "object_checksums")))]
+use crate::vnv_split_object::{SplittableObject, VNVSplitObject};
+
 pub struct VNVObject<
     'a,
     'b: 'a,
@@ -39,6 +59,8 @@ pub struct VNVObject<
 > {
     vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>,
     allocation_identifier: AllocationIdentifier<T>,
+    /// Speeds up repeated accesses (see `ResidencyToken`)
+    residency_token: Cell<ResidencyToken>,
     phantom_data: PhantomData<T>,
 }
 
@@ -58,14 +80,15 @@ impl<
         VNVObject {
             vnv_heap,
             allocation_identifier: identifier,
+            residency_token: Cell::new(ResidencyToken::invalid()),
             phantom_data: PhantomData,
         }
     }
 
-    pub fn get(&mut self) -> Result<VNVRef<'a, '_, '_, 'b, T, A, N, M>, ()> {
+    pub fn get(&mut self) -> Result<VNVRef<'a, '_, '_, 'b, T, A, N, M>, VNVError> {
         let mut heap = self.vnv_heap.borrow_mut();
         unsafe {
-            let ptr: *const T = heap.get_ref(&self.allocation_identifier, false)?;
+            let ptr: *const T = heap.get_ref_cached(&self.allocation_identifier, &self.residency_token)?;
             let data_ref = ptr.as_ref().unwrap();
             Ok(VNVRef::new(
                 self.vnv_heap,
@@ -77,10 +100,56 @@ impl<
 
     pub fn get_mut(
         &mut self,
-    ) -> Result<VNVMutRef<'a, '_, '_, 'b, T, A, N, M>, ()> {
+    ) -> Result<VNVMutRef<'a, '_, '_, 'b, T, A, N, M>, VNVError> {
+        let mut heap = self.vnv_heap.borrow_mut();
+        unsafe {
+            let ptr: *mut T = heap.get_mut_cached(&self.allocation_identifier, &self.residency_token)?;
+            let data_ref = ptr.as_mut().unwrap();
+            Ok(VNVMutRef::new(
+                self.vnv_heap,
+                &self.allocation_identifier,
+                data_ref,
+            ))
+        }
+    }
+
+    /// Makes this object resident without creating a reference, e.g. from a low-priority context
+    /// before a time-critical section (see `try_get_resident`).
+    ///
+    /// The object can still be unloaded again to make space for other objects, use `pin` to prevent this.
+    /// To prefetch multiple objects at once, use `VNVHeap::get_many`.
+    pub fn prefetch(&mut self) -> Result<(), VNVError> {
+        let mut heap = self.vnv_heap.borrow_mut();
+        unsafe { heap.prefetch(&self.allocation_identifier, &self.residency_token) }
+    }
+
+    /// Same as `get`, but never reads from storage.
+    ///
+    /// Fails with `NotResident` if this object is not resident, so the execution time does not depend on the storage
+    /// (e.g. in hard real-time sections). Use `pin` to keep the object resident until then.
+    pub fn try_get_resident(&mut self) -> Result<VNVRef<'a, '_, '_, 'b, T, A, N, M>, VNVError> {
+        let mut heap = self.vnv_heap.borrow_mut();
+        unsafe {
+            let ptr: *const T = heap.get_ref_resident(&self.allocation_identifier, &self.residency_token)?;
+            let data_ref = ptr.as_ref().unwrap();
+            Ok(VNVRef::new(
+                self.vnv_heap,
+                &self.allocation_identifier,
+                data_ref,
+            ))
+        }
+    }
+
+    /// Same as `get_mut`, but never reads from or writes to storage.
+    ///
+    /// Fails with `NotResident` if this object is not resident. If it is not dirty yet and other objects would have
+    /// to be written back to make it dirty, this fails with `DirtyBudgetExhausted` (see `VNVHeap::clean`).
+    pub fn try_get_mut_resident(
+        &mut self,
+    ) -> Result<VNVMutRef<'a, '_, '_, 'b, T, A, N, M>, VNVError> {
         let mut heap = self.vnv_heap.borrow_mut();
         unsafe {
-            let ptr: *mut T = heap.get_mut(&self.allocation_identifier, false)?;
+            let ptr: *mut T = heap.get_mut_resident(&self.allocation_identifier, &self.residency_token)?;
             let data_ref = ptr.as_mut().unwrap();
             Ok(VNVMutRef::new(
                 self.vnv_heap,
@@ -90,6 +159,73 @@ impl<
         }
     }
 
+    /// Makes this object resident, calls `f` with a reference to its data and releases it again.
+    ///
+    /// Same as `get`, but the object is only in use while `f` runs.
+    pub fn with<R>(&mut self, f: impl FnOnce(&T) -> R) -> Result<R, VNVError> {
+        let data_ref = self.get()?;
+        Ok(f(&data_ref))
+    }
+
+    /// Makes this object resident, calls `f` with a mutable reference to its data and releases it again.
+    ///
+    /// Same as `get_mut`, but the object is only in use while `f` runs.
+    pub fn with_mut<R>(&mut self, f: impl FnOnce(&mut T) -> R) -> Result<R, VNVError> {
+        let mut data_ref = self.get_mut()?;
+        Ok(f(&mut data_ref))
+    }
+
+    /// Same as `get`, but awaits the storage read if this object is not resident yet.
+    ///
+    /// `heap` has to be the heap this object was allocated with. See `AsyncPersistentStorageModule` for details.
+    pub async fn get_async<S: AsyncPersistentStorageModule + 'static>(
+        &mut self,
+        heap: &VNVHeap<'b, A, N, M, S>,
+    ) -> Result<VNVRef<'a, '_, '_, 'b, T, A, N, M>, VNVError>
+    where
+        A: 'static,
+    {
+        assert!(ptr::eq(self.vnv_heap, heap.get_inner()), "object was not allocated with this heap");
+        heap.load_async(&self.allocation_identifier, false).await?;
+        self.get()
+    }
+
+    /// Same as `get_mut`, but awaits the storage read if this object is not resident yet.
+    ///
+    /// `heap` has to be the heap this object was allocated with. See `AsyncPersistentStorageModule` for details.
+    pub async fn get_mut_async<S: AsyncPersistentStorageModule + 'static>(
+        &mut self,
+        heap: &VNVHeap<'b, A, N, M, S>,
+    ) -> Result<VNVMutRef<'a, '_, '_, 'b, T, A, N, M>, VNVError>
+    where
+        A: 'static,
+    {
+        assert!(ptr::eq(self.vnv_heap, heap.get_inner()), "object was not allocated with this heap");
+        heap.load_async(&self.allocation_identifier, false).await?;
+        self.get_mut()
+    }
+
+    /// Same as `get`, but reads the data directly from storage if this object is not resident,
+    /// so no space in the resident buffer is used (see `MemoryMappedStorageModule`).
+    ///
+    /// Falls back to `get` if the object is resident or its data cannot be accessed directly
+    /// (and always if `access_counters` or `object_checksums` is enabled).
+    /// `heap` has to be the heap this object was allocated with.
+    pub fn get_mapped<S: MemoryMappedStorageModule + 'static>(
+        &mut self,
+        heap: &VNVHeap<'b, A, N, M, S>,
+    ) -> Result<VNVMappedRef<'a, '_, '_, 'b, T, A, N, M>, VNVError>
+    where
+        A: 'static,
+    {
+        assert!(ptr::eq(self.vnv_heap, heap.get_inner()), "object was not allocated with this heap");
+        match heap.get_mapped_ptr(&self.allocation_identifier) {
+            // the object cannot be modified, unloaded or deallocated while self is borrowed
+            Some(ptr) => Ok(unsafe { VNVMappedRef::mapped(ptr.as_ref().unwrap()) }),
+            None => Ok(VNVMappedRef::resident(self.get()?)),
+        }
+    }
+
     pub fn is_resident(&self) -> bool {
         let mut heap = self.vnv_heap.borrow_mut();
         heap.is_resident(&self.allocation_identifier)
@@ -100,21 +236,179 @@ impl<
         heap.is_data_dirty(&self.allocation_identifier)
     }
 
-    pub fn unload(&mut self) -> Result<(), ()> {
+    pub fn unload(&mut self) -> Result<(), VNVError> {
         let mut heap = self.vnv_heap.borrow_mut();
         heap.unload_object(&self.allocation_identifier, false)
     }
 
-    pub fn flush(&mut self) -> Result<(), ()> {
+    /// Makes this object resident and keeps it resident until `unpin` is called,
+    /// so accessing it does not require any storage reads.
+    ///
+    /// The object management module does not unload pinned objects and `unload` fails with `VNVError::ObjectInUse`.
+    /// Returns `VNVError::PinnedBytesExhausted` if all pinned objects together would not fit into the resident buffer.
+    pub fn pin(&mut self) -> Result<(), VNVError> {
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.pin(&self.allocation_identifier)
+    }
+
+    /// Allows this object to be unloaded again (see `pin`)
+    pub fn unpin(&mut self) {
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.unpin(&self.allocation_identifier)
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.is_pinned(&self.allocation_identifier)
+    }
+
+    /// Sets the order in which the dirty data of this object is written back during `vnv_persist_all`.
+    ///
+    /// Dirty objects with a higher priority are written to their storage location before the rest of the state is persisted,
+    /// so their latest data is recovered even if the power fails in the middle of persisting.
+    /// Their data is written twice (to its storage location and as part of the persisted state), which has to be considered for the energy budget.
+    ///
+    /// Returns `VNVError::CapacityExhausted` if `PERSIST_PRIORITY_CAPACITY` objects already have a priority.
+    /// Priorities are not persisted, so they have to be set again after a reboot.
+    #[cfg(feature = "persist_priority")]
+    pub fn set_persist_priority(&mut self, priority: PersistPriority) -> Result<(), VNVError> {
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.set_persist_priority(&self.allocation_identifier, priority)
+    }
+
+    #[cfg(feature = "persist_priority")]
+    pub fn get_persist_priority(&self) -> PersistPriority {
+        let heap = self.vnv_heap.borrow();
+        heap.get_persist_priority(&self.allocation_identifier)
+    }
+
+    /// Returns a link to this object that can be stored inside other objects (see `VNVLink`)
+    pub fn link(&self) -> VNVLink<T> {
+        VNVLink::new(&self.allocation_identifier)
+    }
+
+    /// Returns `true` if `link` points to this object
+    pub fn is_linked_by(&self, link: &VNVLink<T>) -> bool {
+        link.points_to(&self.allocation_identifier)
+    }
+
+    /// Writes the dirty data of this object back to storage (without unloading it).
+    ///
+    /// Returns how many dirty bytes were cleaned, i.e. are available again to modify other objects.
+    pub fn flush(&mut self) -> Result<usize, VNVError> {
         let mut heap = self.vnv_heap.borrow_mut();
         heap.flush_object(&self.allocation_identifier)
     }
 
+    /// Throws away all changes that were not synchronized yet and restores the last synchronized state.
+    ///
+    /// The dirty bytes used by this object are available again immediately.
+    /// Returns an error if `T` needs to be dropped or if this object was never synchronized
+    /// (e.g. it was allocated and kept resident since then).
+    pub fn discard_changes(&mut self) -> Result<(), VNVError> {
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.discard_changes(&self.allocation_identifier)
+    }
+
+    /// Returns the statistics of this object or `None` if it is not tracked
+    /// (e.g. because it was never accessed or `OBJECT_STATS_CAPACITY` objects are already tracked)
+    #[cfg(feature = "object_stats")]
+    pub fn stats(&self) -> Option<ObjectStats> {
+        let heap = self.vnv_heap.borrow();
+        heap.get_object_stats(self.allocation_identifier.offset)
+    }
+
+    /// Returns how often this object was accessed with `get` or `get_mut` since it was allocated.
+    ///
+    /// The counter is stored together with the object in non-volatile storage,
+    /// so it survives reboots and power failures.
+    #[cfg(feature = "access_counters")]
+    pub fn get_access_count(&self) -> Result<ObjectAccessCount, VNVError> {
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.get_access_count(&self.allocation_identifier)
+    }
+
+    /// Checks whether the data of this object in storage still matches its checksum.
+    ///
+    /// Loading a corrupted object fails (e.g. `get` returns an error), so corrupted data is never handed out.
+    /// In that case, this returns the details of the mismatch. Returns an error if the storage could not be read.
+    #[cfg(feature = "object_checksums")]
+    pub fn verify_checksum(&self) -> Result<Option<ObjectChecksumMismatch>, VNVError> {
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.verify_checksum(&self.allocation_identifier)
+    }
+
+    /// Serializes the data of this object as JSON into `writer` (e.g. for debugging).
+    ///
+    /// The object is made resident for this, see `VNVHeap::allocate_from_deserialize` for the reverse direction.
+    #[cfg(feature = "serde")]
+    pub fn serialize_into<W: std::io::Write>(&mut self, writer: W) -> Result<(), VNVError>
+    where
+        T: Serialize,
+    {
+        self.with(|data| serde_json::to_writer(writer, data))?
+            .map_err(|_| VNVError::SerializationFailed)
+    }
+
+    /// Splits this object into its parts `X` and `Y` without copying its data.
+    ///
+    /// Both parts share the storage region of this object, but are tracked independently.
+    /// For example, a large cold part can stay unloaded while the small hot part is resident.
+    ///
+    /// Not available with the `access_counters` and `object_checksums` features, as every part would require its own counter and checksum.
+    #[cfg(not(any(feature = "access_counters", feature = "object_checksums")))]
+    pub fn split<X: Sized, Y: Sized>(mut self) -> Result<VNVSplitObject<'a, 'b, T, X, Y, A, N, M>, VNVError>
+    where
+        T: SplittableObject<X, Y>,
+    {
+        // the most recent data has to be in storage before the parts are loaded separately
+        self.unload()?;
+
+        let this = ManuallyDrop::new(self);
+        Ok(VNVSplitObject::new(this.vnv_heap, this.allocation_identifier.clone()))
+    }
+
+    /// Drops and deallocates this object.
+    ///
+    /// If the object has to be made resident to be dropped, but this fails, the configured `DropPolicy` decides what happens.
+    /// With `DropPolicy::KeepAlive` (and if `DropPolicy::ForceEvictOthers` does not help), the object is returned again.
+    pub fn try_drop(self) -> Result<DropOutcome, Self> {
+        let res = {
+            let mut heap = self.vnv_heap.borrow_mut();
+            unsafe { heap.try_deallocate(&self.allocation_identifier, false) }
+        };
+
+        match res {
+            Ok(outcome) => {
+                // already deallocated
+                let _ = ManuallyDrop::new(self);
+                Ok(outcome)
+            }
+            Err(_) => Err(self),
+        }
+    }
+
+    /// Forces the next access to search the resident list (used by benchmarks)
+    #[allow(unused)]
+    pub(crate) fn invalidate_residency_token(&self) {
+        self.residency_token.set(ResidencyToken::invalid());
+    }
+
     #[allow(unused)]
     pub(crate) fn get_alloc_id(&self) -> &AllocationIdentifier<T> {
         return &self.allocation_identifier;
     }
 
+    /// Points this object to its new location after it was moved (see `VNVHeap::defragment`)
+    pub(crate) fn set_alloc_id(&mut self, identifier: AllocationIdentifier<T>) {
+        self.allocation_identifier = identifier;
+        self.residency_token.set(ResidencyToken::invalid());
+    }
+
+    pub(crate) fn get_heap_cell(&self) -> &'a RefCell<VNVHeapInner<'b, A, N, M>> {
+        self.vnv_heap
+    }
+
     #[allow(unused)]
     pub(crate) fn get_heap<'c>(&'c self) -> RefMut<'c, VNVHeapInner<'b, A, N, M>> {
         self.vnv_heap.borrow_mut()
@@ -127,10 +421,10 @@ impl<T: Sized, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManag
     fn drop(&mut self) {
         let mut obj = self.vnv_heap.borrow_mut();
         unsafe {
-            // TODO handle this error somehow?
+            // see DropPolicy for what happens if the object cannot be made resident
             match obj.deallocate(&self.allocation_identifier, false) {
                 Ok(()) => {}
-                Err(()) => {
+                Err(_) => {
                     println!("could not deallocate");
                 }
             }
@@ -0,0 +1,222 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{
+    alloc::Layout,
+    cell::RefCell,
+    marker::PhantomData,
+    mem::{size_of, MaybeUninit},
+    ptr::{slice_from_raw_parts, slice_from_raw_parts_mut},
+};
+
+use crate::{
+    modules::{
+        allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
+        object_management::ObjectManagementModule,
+    },
+    vnv_error::VNVError,
+    vnv_heap::VNVHeapInner,
+    vnv_object::VNVObject,
+};
+
+/// Position of the items in the ring buffer of a `VNVQueue`
+#[derive(Clone, Copy, Default)]
+pub(crate) struct QueueIndices {
+    head: usize,
+    len: usize,
+}
+
+/// A FIFO ring buffer with space for `CAP` items that live in non-volatile storage.
+///
+/// Items are written directly to storage when they are produced and read from storage when they are consumed,
+/// so the queue only needs the resident memory of its indices, no matter how many items it contains.
+/// The indices are a normal object of the heap and are only updated after the item was written.
+/// This way, a persisted queue never contains partially written items.
+pub struct VNVQueue<
+    'a,
+    'b: 'a,
+    T: Sized + Copy,
+    const CAP: usize,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>,
+    indices: VNVObject<'a, 'b, QueueIndices, A, N, M>,
+    /// copy of `indices`, so they do not have to be loaded to check the length
+    cached_indices: QueueIndices,
+    /// offset of the item slots in storage
+    offset: usize,
+    phantom_data: PhantomData<T>,
+}
+
+impl<
+        'a,
+        'b: 'a,
+        T: Sized + Copy,
+        const CAP: usize,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > VNVQueue<'a, 'b, T, CAP, A, N, M>
+{
+    pub(crate) fn new(vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>) -> Result<Self, VNVError> {
+        assert!(size_of::<T>() != 0, "zero sized types are not supported");
+        assert!(CAP > 0, "queue has to hold at least one item");
+
+        let mut heap = vnv_heap.borrow_mut();
+        let offset = heap.allocate_storage(Self::calc_layout())?;
+        let indices = match unsafe { heap.allocate(QueueIndices::default(), false) } {
+            Ok(identifier) => identifier,
+            Err(err) => {
+                heap.deallocate_storage(offset, Self::calc_layout())?;
+                return Err(err);
+            }
+        };
+        drop(heap);
+
+        Ok(Self {
+            vnv_heap,
+            indices: VNVObject::new(vnv_heap, indices),
+            cached_indices: QueueIndices::default(),
+            offset,
+            phantom_data: PhantomData,
+        })
+    }
+
+    /// Appends `item` to the end of the queue.
+    ///
+    /// Returns `VNVError::CapacityExhausted` if the queue already contains `CAP` items.
+    pub fn produce(&mut self, item: T) -> Result<(), VNVError> {
+        if self.is_full() {
+            return Err(VNVError::CapacityExhausted);
+        }
+
+        let indices = self.cached_indices;
+        let slot = (indices.head + indices.len) % CAP;
+        self.vnv_heap
+            .borrow_mut()
+            .write_storage(self.slot_offset(slot), Self::as_bytes(&item))?;
+
+        // the item is only part of the queue once it was written completely
+        self.update_indices(QueueIndices {
+            head: indices.head,
+            len: indices.len + 1,
+        })
+    }
+
+    /// Removes the first item of the queue and returns it
+    pub fn consume(&mut self) -> Result<Option<T>, VNVError> {
+        let item = match self.peek()? {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+
+        let indices = self.cached_indices;
+        self.update_indices(QueueIndices {
+            head: (indices.head + 1) % CAP,
+            len: indices.len - 1,
+        })?;
+
+        Ok(Some(item))
+    }
+
+    /// Returns the first item of the queue without removing it
+    pub fn peek(&self) -> Result<Option<T>, VNVError> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+
+        let mut item = MaybeUninit::<T>::uninit();
+        let dest = unsafe {
+            slice_from_raw_parts_mut(item.as_mut_ptr() as *mut u8, size_of::<T>())
+                .as_mut()
+                .unwrap()
+        };
+        self.vnv_heap
+            .borrow_mut()
+            .read_storage(self.slot_offset(self.cached_indices.head), dest)?;
+
+        // the data was written from a valid `T`
+        Ok(Some(unsafe { item.assume_init() }))
+    }
+
+    /// Removes all items from the queue
+    pub fn clear(&mut self) -> Result<(), VNVError> {
+        self.update_indices(QueueIndices::default())
+    }
+
+    pub fn len(&self) -> usize {
+        self.cached_indices.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cached_indices.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.cached_indices.len == CAP
+    }
+
+    pub const fn capacity(&self) -> usize {
+        CAP
+    }
+
+    /// Writes the indices of this queue back to storage and returns how many dirty bytes were cleaned
+    pub fn flush(&mut self) -> Result<usize, VNVError> {
+        self.indices.flush()
+    }
+
+    /// Unloads the indices of this queue from the resident buffer
+    pub fn unload(&mut self) -> Result<(), VNVError> {
+        self.indices.unload()
+    }
+
+    fn update_indices(&mut self, indices: QueueIndices) -> Result<(), VNVError> {
+        *self.indices.get_mut()? = indices;
+        self.cached_indices = indices;
+        Ok(())
+    }
+
+    fn slot_offset(&self, slot: usize) -> usize {
+        self.offset + slot * size_of::<T>()
+    }
+
+    fn as_bytes(item: &T) -> &[u8] {
+        unsafe {
+            slice_from_raw_parts(item as *const T as *const u8, size_of::<T>())
+                .as_ref()
+                .unwrap()
+        }
+    }
+
+    fn calc_layout() -> Layout {
+        Layout::array::<T>(CAP).expect("queue is too big")
+    }
+}
+
+impl<T: Sized + Copy, const CAP: usize, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> Drop
+    for VNVQueue<'_, '_, T, CAP, A, N, M>
+{
+    fn drop(&mut self) {
+        // the indices are deallocated when they are dropped
+        let mut heap = self.vnv_heap.borrow_mut();
+        if heap.deallocate_storage(self.offset, Self::calc_layout()).is_err() {
+            println!("could not deallocate");
+        }
+    }
+}
@@ -0,0 +1,156 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{alloc::Layout, cell::RefCell};
+
+use crate::{
+    modules::{
+        allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
+        object_management::ObjectManagementModule,
+    },
+    resident_object_manager::resident_object_metadata::ResidentObjectMetadata,
+    vnv_error::VNVError,
+    vnv_heap::VNVHeapInner,
+};
+
+/// Untyped object whose data layout is chosen when it is allocated (see `VNVHeap::allocate_layout`).
+///
+/// This is meant for data that is accessed by foreign (e.g. C) code through a raw pointer.
+/// As the heap cannot see writes through this pointer, modified bytes have to be marked with `mark_dirty`.
+/// Like `VNVBytes`, the object is made dirty as a whole, so it uses as many dirty bytes as it is long.
+pub struct VNVRawObject<
+    'a,
+    'b: 'a,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>,
+    offset: usize,
+    layout: Layout,
+
+    /// Metadata of the object while a pointer returned by `get_ptr` is valid
+    meta_ptr: Option<*mut ResidentObjectMetadata>,
+}
+
+impl<
+        'a,
+        'b: 'a,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > VNVRawObject<'a, 'b, A, N, M>
+{
+    pub(crate) fn new(vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>, offset: usize, layout: Layout) -> Self {
+        VNVRawObject {
+            vnv_heap,
+            offset,
+            layout,
+            meta_ptr: None,
+        }
+    }
+
+    /// Returns the length of this object in bytes
+    pub fn len(&self) -> usize {
+        self.layout.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layout.size() == 0
+    }
+
+    /// Returns the layout this object was allocated with
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Makes this object resident and returns a pointer to its data, which is aligned to `layout().align()`.
+    ///
+    /// The object stays resident (and the pointer valid) until `release` or `unload` is called or this object is dropped.
+    /// Bytes that are modified through the pointer have to be marked with `mark_dirty`, otherwise the changes can be lost.
+    pub fn get_ptr(&mut self) -> Result<*mut u8, VNVError> {
+        let meta_ptr = match self.meta_ptr {
+            Some(meta_ptr) => meta_ptr,
+            None => {
+                let mut heap = self.vnv_heap.borrow_mut();
+                let meta_ptr = unsafe { heap.get_raw_mut(self.offset, self.layout)? };
+                self.meta_ptr = Some(meta_ptr);
+                meta_ptr
+            }
+        };
+
+        Ok(unsafe { meta_ptr.as_mut().unwrap().dynamic_metadata_to_data_range_mut().as_mut_ptr() })
+    }
+
+    /// Marks `len` bytes starting at `offset` as dirty, so that they are persisted.
+    ///
+    /// Currently, this makes the whole object dirty (persisting objects with partial dirtiness tracking is not supported yet).
+    /// Call this before modifying the bytes through the pointer of `get_ptr`, as syncing other objects
+    /// to free dirty bytes can fail. Returns `VNVError::NotResident` if `get_ptr` was not called before.
+    ///
+    /// **Panics** if the range is out of bounds.
+    pub fn mark_dirty(&mut self, offset: usize, len: usize) -> Result<(), VNVError> {
+        assert!(
+            offset.checked_add(len).is_some_and(|end| end <= self.layout.size()),
+            "range is out of bounds"
+        );
+
+        let meta_ptr = self.meta_ptr.ok_or(VNVError::NotResident)?;
+        let mut heap = self.vnv_heap.borrow_mut();
+        unsafe { heap.make_raw_dirty(meta_ptr) }
+    }
+
+    /// Invalidates the pointer returned by `get_ptr`, so that the object can be unloaded again
+    pub fn release(&mut self) {
+        if let Some(meta_ptr) = self.meta_ptr.take() {
+            let mut heap = self.vnv_heap.borrow_mut();
+            unsafe { heap.release_partial_mut::<u8>(meta_ptr) };
+        }
+    }
+
+    pub fn is_resident(&self) -> bool {
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.is_bytes_resident(self.offset)
+    }
+
+    pub fn is_data_dirty(&self) -> bool {
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.is_bytes_data_dirty(self.offset)
+    }
+
+    /// Writes back the dirty bytes and unloads this object, the pointer returned by `get_ptr` is invalid afterwards
+    pub fn unload(&mut self) -> Result<(), VNVError> {
+        self.release();
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.unload_bytes(self.offset)
+    }
+}
+
+impl<A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> Drop
+    for VNVRawObject<'_, '_, A, N, M>
+{
+    fn drop(&mut self) {
+        self.release();
+        let mut heap = self.vnv_heap.borrow_mut();
+        match heap.deallocate_bytes(self.offset, self.layout.size()) {
+            Ok(()) => {}
+            Err(_) => {
+                println!("could not deallocate");
+            }
+        }
+    }
+}
@@ -23,7 +23,7 @@ use crate::{
         allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
         object_management::ObjectManagementModule,
     },
-    vnv_heap::VNVHeapInner,
+    vnv_heap::{release_on_drop, VNVHeapInner},
 };
 
 pub struct VNVRef<
@@ -84,10 +84,6 @@ impl<T: Sized, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManag
     for VNVRef<'_, '_, '_, '_, T, A, N, M>
 {
     fn drop(&mut self) {
-        unsafe {
-            self.vnv_heap
-                .borrow_mut()
-                .release_ref(self.allocation_identifier)
-        }
+        release_on_drop(self.vnv_heap, |heap| unsafe { heap.release_ref(self.allocation_identifier) });
     }
 }
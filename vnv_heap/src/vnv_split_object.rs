@@ -0,0 +1,222 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{
+    cell::RefCell,
+    marker::PhantomData,
+    mem::{align_of, size_of, ManuallyDrop},
+};
+
+use crate::{
+    allocation_identifier::AllocationIdentifier,
+    modules::{
+        allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
+        object_management::ObjectManagementModule,
+    },
+    vnv_heap::VNVHeapInner,
+    vnv_mut_ref::VNVMutRef,
+    vnv_error::VNVError,
+    vnv_object::VNVObject,
+    vnv_ref::VNVRef,
+};
+
+/// Marks types that can be split into an `X` and a `Y` with `VNVObject::split`.
+///
+/// # Safety
+///
+/// `Self` has to be a `#[repr(C)]` struct that consists of exactly two fields:
+/// a field of type `X` followed by a field of type `Y`.
+/// `Self` must not implement `Drop` itself, as the fields are dropped separately after splitting.
+///
+/// ```ignore
+/// #[repr(C)]
+/// struct Data {
+///     hot: Hot,
+///     cold: Cold,
+/// }
+///
+/// unsafe impl SplittableObject<Hot, Cold> for Data {}
+/// ```
+pub unsafe trait SplittableObject<X: Sized, Y: Sized>: Sized {}
+
+const fn align_up(value: usize, align: usize) -> usize {
+    (value + align - 1) / align * align
+}
+
+/// Offset of `Y` in a `#[repr(C)]` struct with the fields `X` and `Y`
+const fn calc_second_offset<X, Y>() -> usize {
+    align_up(size_of::<X>(), align_of::<Y>())
+}
+
+/// An object that was split into two parts with `VNVObject::split`.
+///
+/// Both parts share the storage region of the original object,
+/// but are loaded, synchronized, and unloaded independently of each other.
+/// The storage region is freed once this object is dropped.
+pub struct VNVSplitObject<
+    'a,
+    'b: 'a,
+    T: SplittableObject<X, Y>,
+    X: Sized,
+    Y: Sized,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>,
+    first: ManuallyDrop<VNVObject<'a, 'b, X, A, N, M>>,
+    second: ManuallyDrop<VNVObject<'a, 'b, Y, A, N, M>>,
+    phantom_data: PhantomData<T>,
+}
+
+impl<
+        'a,
+        'b: 'a,
+        T: SplittableObject<X, Y>,
+        X: Sized,
+        Y: Sized,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > VNVSplitObject<'a, 'b, T, X, Y, A, N, M>
+{
+    pub(crate) fn new(
+        vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>,
+        identifier: AllocationIdentifier<T>,
+    ) -> Self {
+        // check that T has the layout of a #[repr(C)] struct with the fields X and Y
+        let second_offset = calc_second_offset::<X, Y>();
+        let align = align_of::<X>().max(align_of::<Y>());
+        assert_eq!(
+            size_of::<T>(),
+            align_up(second_offset + size_of::<Y>(), align),
+            "size of T does not match its parts"
+        );
+        assert_eq!(align_of::<T>(), align, "alignment of T does not match its parts");
+
+        // parts are identified by their offset, so they have to start at different offsets
+        // that are still part of the original allocation
+        assert!(
+            size_of::<X>() > 0 && size_of::<Y>() > 0,
+            "zero sized parts are not supported"
+        );
+
+        let first = AllocationIdentifier::<X>::from_offset(identifier.offset);
+        let second = AllocationIdentifier::<Y>::from_offset(identifier.offset + second_offset);
+
+        Self {
+            vnv_heap,
+            first: ManuallyDrop::new(VNVObject::new(vnv_heap, first)),
+            second: ManuallyDrop::new(VNVObject::new(vnv_heap, second)),
+            phantom_data: PhantomData,
+        }
+    }
+
+    /// Returns the first part (`X`) of this object
+    pub fn first(&mut self) -> VNVObjectPart<'a, 'b, '_, X, A, N, M> {
+        VNVObjectPart { inner: &mut self.first }
+    }
+
+    /// Returns the second part (`Y`) of this object
+    pub fn second(&mut self) -> VNVObjectPart<'a, 'b, '_, Y, A, N, M> {
+        VNVObjectPart { inner: &mut self.second }
+    }
+
+    /// Returns both parts, so that they can be accessed at the same time
+    #[allow(clippy::type_complexity)]
+    pub fn parts(
+        &mut self,
+    ) -> (
+        VNVObjectPart<'a, 'b, '_, X, A, N, M>,
+        VNVObjectPart<'a, 'b, '_, Y, A, N, M>,
+    ) {
+        (
+            VNVObjectPart { inner: &mut self.first },
+            VNVObjectPart { inner: &mut self.second },
+        )
+    }
+}
+
+impl<
+        T: SplittableObject<X, Y>,
+        X: Sized,
+        Y: Sized,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > Drop for VNVSplitObject<'_, '_, T, X, Y, A, N, M>
+{
+    fn drop(&mut self) {
+        let mut heap = self.vnv_heap.borrow_mut();
+        unsafe {
+            // TODO handle this error somehow?
+            match heap.deallocate_split::<T, X, Y>(self.first.get_alloc_id(), self.second.get_alloc_id()) {
+                Ok(()) => {}
+                Err(_) => {
+                    println!("could not deallocate");
+                }
+            }
+        }
+    }
+}
+
+/// One part of a `VNVSplitObject`
+pub struct VNVObjectPart<
+    'a,
+    'b: 'a,
+    'c,
+    T: Sized,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    inner: &'c mut VNVObject<'a, 'b, T, A, N, M>,
+}
+
+impl<
+        'a,
+        'b: 'a,
+        T: Sized,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > VNVObjectPart<'a, 'b, '_, T, A, N, M>
+{
+    pub fn get(&mut self) -> Result<VNVRef<'a, '_, '_, 'b, T, A, N, M>, VNVError> {
+        self.inner.get()
+    }
+
+    pub fn get_mut(&mut self) -> Result<VNVMutRef<'a, '_, '_, 'b, T, A, N, M>, VNVError> {
+        self.inner.get_mut()
+    }
+
+    pub fn is_resident(&self) -> bool {
+        self.inner.is_resident()
+    }
+
+    pub fn is_data_dirty(&self) -> bool {
+        self.inner.is_data_dirty()
+    }
+
+    pub fn unload(&mut self) -> Result<(), VNVError> {
+        self.inner.unload()
+    }
+
+    pub fn flush(&mut self) -> Result<usize, VNVError> {
+        self.inner.flush()
+    }
+}
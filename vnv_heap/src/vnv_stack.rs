@@ -0,0 +1,269 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{cell::RefCell, marker::PhantomData};
+
+use crate::{
+    allocation_identifier::AllocationIdentifier, modules::{
+        allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
+        object_management::ObjectManagementModule,
+    }, vnv_error::VNVError, vnv_heap::VNVHeapInner, vnv_stack_mut_ref::VNVStackMutRef, vnv_stack_ref::VNVStackRef
+};
+
+pub(crate) struct StackItemContainer<T> {
+    pub(crate) next: AllocationIdentifier<StackItemContainer<T>>,
+    pub(crate) data: T,
+}
+
+/// A LIFO stack whose items live in the non-volatile storage.
+///
+/// Only the top item is kept resident: pushing an item unloads the previous top item
+/// and popping an item loads the new top item again. So the stack itself only needs
+/// the resident memory of one item, no matter how many items it contains.
+pub struct VNVStack<
+    'a,
+    'b: 'a,
+    T: Sized + Clone,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>,
+    top: AllocationIdentifier<StackItemContainer<T>>,
+    len: usize,
+    phantom_data: PhantomData<T>,
+}
+
+impl<
+        'a,
+        'b: 'a,
+        T: Sized + Clone,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > VNVStack<'a, 'b, T, A, N, M>
+{
+    pub(crate) fn new(vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>) -> Self {
+        Self {
+            vnv_heap,
+            top: AllocationIdentifier::new_invalid(),
+            len: 0,
+            phantom_data: PhantomData,
+        }
+    }
+
+    pub fn push(&mut self, data: T) -> Result<(), VNVError> {
+        let mut heap = self.vnv_heap.borrow_mut();
+
+        if !self.top.is_invalid() {
+            // make space for the new top item
+            heap.unload_object(&self.top, false)?;
+        }
+
+        let item = StackItemContainer {
+            data,
+            next: self.top.clone(),
+        };
+
+        self.top = unsafe { heap.allocate(item, false)? };
+        self.len += 1;
+
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Result<Option<T>, VNVError> {
+        if self.top.is_invalid() {
+            // no elements left
+            return Ok(None);
+        }
+
+        let mut heap = self.vnv_heap.borrow_mut();
+
+        let (data, next) = unsafe {
+            let item = heap.get_ref(&self.top, false)?;
+            let item = item.as_ref().unwrap();
+
+            (item.data.clone(), item.next.clone())
+        };
+
+        unsafe { heap.release_ref(&self.top) };
+
+        unsafe {
+            // TODO: handle this error somehow
+            // we now would be in a invalid state
+            heap.deallocate(&self.top, false).expect("invalid state");
+        }
+
+        self.top = next;
+        self.len -= 1;
+
+        if !self.top.is_invalid() {
+            // load the new top item, so it is resident when it is accessed next
+            // this is only an optimization, so it does not matter if it fails
+            if unsafe { heap.get_ref(&self.top, false) }.is_ok() {
+                unsafe { heap.release_ref(&self.top) };
+            }
+        }
+
+        Ok(Some(data))
+    }
+
+    pub fn peek(&mut self) -> Result<Option<VNVStackRef<'a, '_, '_, 'b, T, A, N, M>>, VNVError> {
+        if self.top.is_invalid() {
+            // no elements in stack
+            return Ok(None);
+        }
+
+        let mut heap = self.vnv_heap.borrow_mut();
+
+        let item = unsafe {
+            let tmp = heap.get_ref(&self.top, false)?;
+            tmp.as_ref().unwrap()
+        };
+
+        Ok(Some(unsafe { VNVStackRef::new(self.vnv_heap, &self.top, item) }))
+    }
+
+    pub fn peek_mut(&mut self) -> Result<Option<VNVStackMutRef<'a, '_, '_, 'b, T, A, N, M>>, VNVError> {
+        if self.top.is_invalid() {
+            // no elements in stack
+            return Ok(None);
+        }
+
+        let mut heap = self.vnv_heap.borrow_mut();
+
+        let item = unsafe {
+            let tmp = heap.get_mut(&self.top, false)?;
+            tmp.as_mut().unwrap()
+        };
+
+        Ok(Some(unsafe { VNVStackMutRef::new(self.vnv_heap, &self.top, item) }))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.top.is_invalid()
+    }
+}
+
+impl<T: Sized + Clone, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> Drop
+    for VNVStack<'_, '_, T, A, N, M>
+{
+    fn drop(&mut self) {
+        while let Some(_) = self.pop().unwrap() {
+            // nothing todo, we just drop all values
+        }
+
+        debug_assert!(self.top.is_invalid());
+        debug_assert_eq!(self.len, 0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::get_test_heap;
+
+    #[test]
+    fn test_stack() {
+        let mut buffer = [0u8; 1024];
+        let heap = get_test_heap("test_stack", 8 * 1024, &mut buffer, 1024, |_, _| {});
+
+        let mut stack = heap.new_stack::<u64>();
+        let mut check_stack: Vec<u64> = Vec::new();
+
+        macro_rules! check_integrity {
+            () => {
+                {
+                    assert_eq!(stack.len(), check_stack.len());
+                    assert_eq!(stack.is_empty(), check_stack.is_empty());
+
+                    if let Some(top) = check_stack.last() {
+                        assert_eq!(*stack.peek().unwrap().unwrap(), *top);
+                    } else {
+                        assert!(stack.peek().unwrap().is_none());
+                        assert!(stack.peek_mut().unwrap().is_none());
+                    }
+                }
+            };
+        }
+
+        macro_rules! push {
+            ($item: expr) => {
+                stack.push($item).unwrap();
+                check_stack.push($item);
+                check_integrity!();
+            };
+        }
+
+        macro_rules! pop {
+            () => {
+                assert_eq!(stack.pop().unwrap(), check_stack.pop());
+                check_integrity!();
+            };
+        }
+
+        check_integrity!();
+        pop!();
+
+        push!(23);
+        pop!();
+        pop!();
+
+        push!(1);
+        push!(5);
+        push!(93);
+
+        *stack.peek_mut().unwrap().unwrap() = 94;
+        *check_stack.last_mut().unwrap() = 94;
+        check_integrity!();
+
+        push!(2);
+        push!(7);
+
+        pop!();
+
+        push!(9);
+
+        for _ in 0..6 {
+            pop!();
+        }
+    }
+
+    #[test]
+    fn test_stack_only_top_resident() {
+        let mut buffer = [0u8; 1024];
+        let heap = get_test_heap("test_stack_only_top_resident", 8 * 1024, &mut buffer, 1024, |_, _| {});
+
+        let mut stack = heap.new_stack::<[u8; 32]>();
+
+        for i in 0..20 {
+            stack.push([i; 32]).unwrap();
+            assert_eq!(heap.get_resident_usage().resident_objects, 1);
+            assert!(heap.get_inner().borrow_mut().is_resident(&stack.top));
+        }
+
+        for i in (0..20).rev() {
+            assert_eq!(stack.pop().unwrap(), Some([i; 32]));
+            assert_eq!(heap.get_resident_usage().resident_objects, if i == 0 { 0 } else { 1 });
+        }
+
+        assert!(stack.pop().unwrap().is_none());
+    }
+}
@@ -0,0 +1,202 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::{
+    alloc::Layout,
+    cell::RefCell,
+    marker::PhantomData,
+    mem::{size_of, size_of_val, MaybeUninit},
+    ptr::{slice_from_raw_parts, slice_from_raw_parts_mut},
+};
+
+use crate::{
+    modules::{
+        allocator::AllocatorModule, nonresident_allocator::NonResidentAllocatorModule,
+        object_management::ObjectManagementModule,
+    },
+    vnv_error::VNVError,
+    vnv_heap::VNVHeapInner,
+};
+
+/// Size of the stack buffer that is used to write the initial data to storage
+const FILL_CHUNK_SIZE: usize = 256;
+
+/// Slice of `len` elements that only lives in non-volatile storage and is never made resident.
+///
+/// This is meant for huge data (e.g. lookup tables) that would not fit into the resident buffer.
+/// Elements are read and written in chunks directly from/to storage, so every access costs a storage transaction.
+/// The data is not covered by access counters or checksums.
+pub struct VNVStorageSlice<
+    'a,
+    'b: 'a,
+    T: Sized + Copy,
+    A: AllocatorModule,
+    N: NonResidentAllocatorModule,
+    M: ObjectManagementModule,
+> {
+    vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>,
+    offset: usize,
+    len: usize,
+    phantom_data: PhantomData<T>,
+}
+
+impl<
+        'a,
+        'b: 'a,
+        T: Sized + Copy,
+        A: AllocatorModule,
+        N: NonResidentAllocatorModule,
+        M: ObjectManagementModule,
+    > VNVStorageSlice<'a, 'b, T, A, N, M>
+{
+    /// Allocates storage for `len` elements and writes the first `len` items of `iter` there.
+    ///
+    /// **Panics** if `iter` yields less than `len` items.
+    pub(crate) fn new<I: IntoIterator<Item = T>>(
+        vnv_heap: &'a RefCell<VNVHeapInner<'b, A, N, M>>,
+        len: usize,
+        iter: I,
+    ) -> Result<Self, VNVError> {
+        assert!(size_of::<T>() != 0, "zero sized types are not supported");
+
+        let offset = vnv_heap.borrow_mut().allocate_storage(Self::calc_layout(len))?;
+
+        // the storage region is deallocated again if filling it fails
+        let slice = Self {
+            vnv_heap,
+            offset,
+            len,
+            phantom_data: PhantomData,
+        };
+        slice.fill(iter)?;
+
+        Ok(slice)
+    }
+
+    fn fill<I: IntoIterator<Item = T>>(&self, iter: I) -> Result<(), VNVError> {
+        let mut iter = iter.into_iter();
+        let mut heap = self.vnv_heap.borrow_mut();
+
+        // bigger elements are written one by one
+        let elems_per_chunk = (FILL_CHUNK_SIZE / size_of::<T>()).max(1);
+        let mut buffer = [0u8; FILL_CHUNK_SIZE];
+
+        let mut index = 0;
+        while index < self.len {
+            let count = elems_per_chunk.min(self.len - index);
+            let byte_offset = self.offset + index * size_of::<T>();
+
+            if size_of::<T>() > FILL_CHUNK_SIZE {
+                let item = iter.next().expect("iterator yielded less items than requested");
+                heap.write_storage(byte_offset, Self::as_bytes(core::slice::from_ref(&item)))?;
+            } else {
+                for i in 0..count {
+                    let item = iter.next().expect("iterator yielded less items than requested");
+                    let item_bytes = Self::as_bytes(core::slice::from_ref(&item));
+                    buffer[i * size_of::<T>()..(i + 1) * size_of::<T>()].copy_from_slice(item_bytes);
+                }
+                heap.write_storage(byte_offset, &buffer[..count * size_of::<T>()])?;
+            }
+
+            index += count;
+        }
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Reads `dest.len()` elements starting at `index` from storage
+    pub fn read(&self, index: usize, dest: &mut [T]) -> Result<(), VNVError> {
+        self.check_range(index, dest.len());
+
+        let dest = unsafe {
+            slice_from_raw_parts_mut(dest.as_mut_ptr() as *mut u8, size_of_val(dest))
+                .as_mut()
+                .unwrap()
+        };
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.read_storage(self.offset + index * size_of::<T>(), dest)
+    }
+
+    /// Reads the element at `index` from storage
+    pub fn get(&self, index: usize) -> Result<T, VNVError> {
+        self.check_range(index, 1);
+
+        let mut item = MaybeUninit::<T>::uninit();
+        let dest = unsafe {
+            slice_from_raw_parts_mut(item.as_mut_ptr() as *mut u8, size_of::<T>())
+                .as_mut()
+                .unwrap()
+        };
+        self.vnv_heap
+            .borrow_mut()
+            .read_storage(self.offset + index * size_of::<T>(), dest)?;
+
+        // the data was written from a valid `T`
+        Ok(unsafe { item.assume_init() })
+    }
+
+    /// Writes `src` to storage, starting at element `index`
+    pub fn write(&mut self, index: usize, src: &[T]) -> Result<(), VNVError> {
+        self.check_range(index, src.len());
+
+        let mut heap = self.vnv_heap.borrow_mut();
+        heap.write_storage(self.offset + index * size_of::<T>(), Self::as_bytes(src))
+    }
+
+    fn check_range(&self, index: usize, count: usize) {
+        assert!(
+            index.checked_add(count).map_or(false, |end| end <= self.len),
+            "range is out of bounds (index: {}, count: {}, len: {})",
+            index,
+            count,
+            self.len
+        );
+    }
+
+    fn as_bytes(items: &[T]) -> &[u8] {
+        unsafe {
+            slice_from_raw_parts(items.as_ptr() as *const u8, size_of_val(items))
+                .as_ref()
+                .unwrap()
+        }
+    }
+
+    fn calc_layout(len: usize) -> Layout {
+        len.checked_mul(size_of::<T>())
+            .and_then(|size| Layout::from_size_align(size, 1).ok())
+            .expect("slice is too big")
+    }
+}
+
+impl<T: Sized + Copy, A: AllocatorModule, N: NonResidentAllocatorModule, M: ObjectManagementModule> Drop
+    for VNVStorageSlice<'_, '_, T, A, N, M>
+{
+    fn drop(&mut self) {
+        let mut heap = self.vnv_heap.borrow_mut();
+        if heap.deallocate_storage(self.offset, Self::calc_layout(self.len)).is_err() {
+            println!("could not deallocate");
+        }
+    }
+}
@@ -0,0 +1,76 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+// Bridge between the zephyr flash map and FlashArea.
+// Requires CONFIG_FLASH=y, CONFIG_FLASH_MAP=y and CONFIG_FLASH_PAGE_LAYOUT=y.
+
+#ifndef VNV_FLASH_AREA_STORAGE_H
+#define VNV_FLASH_AREA_STORAGE_H
+
+#include <errno.h>
+#include <zephyr/kernel.h>
+#include <zephyr/drivers/flash.h>
+#include <zephyr/storage/flash_map.h>
+
+#if FIXED_PARTITION_EXISTS(storage_partition)
+uint8_t vnv_flash_area_default_id(void) {
+	return FIXED_PARTITION_ID(storage_partition);
+}
+#endif
+
+int vnv_flash_area_open(uint8_t id, const struct flash_area** area) {
+	return flash_area_open(id, area);
+}
+
+void vnv_flash_area_close(const struct flash_area* area) {
+	flash_area_close(area);
+}
+
+size_t vnv_flash_area_size(const struct flash_area* area) {
+	return area->fa_size;
+}
+
+int vnv_flash_area_layout(const struct flash_area* area, size_t* write_size, size_t* erase_size) {
+	// the storage module expects erased bytes to be 0xFF
+	if (flash_area_erased_val(area) != 0xFF) {
+		return -ENOTSUP;
+	}
+
+	struct flash_pages_info info;
+	int err = flash_get_page_info_by_offs(flash_area_get_device(area), area->fa_off, &info);
+	if (err) {
+		return err;
+	}
+
+	*write_size = flash_area_align(area);
+	*erase_size = info.size;
+	return 0;
+}
+
+int vnv_flash_area_read(const struct flash_area* area, size_t offset, uint8_t* data, size_t len) {
+	return flash_area_read(area, offset, data, len);
+}
+
+int vnv_flash_area_write(const struct flash_area* area, size_t offset, const uint8_t* data, size_t len) {
+	return flash_area_write(area, offset, data, len);
+}
+
+int vnv_flash_area_erase(const struct flash_area* area, size_t offset, size_t len) {
+	return flash_area_erase(area, offset, len);
+}
+
+#endif
@@ -0,0 +1,150 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::ffi::{c_int, c_void};
+
+use embedded_storage::nor_flash::{
+    check_erase, check_read, check_write, ErrorType, NorFlash, NorFlashErrorKind, ReadNorFlash,
+};
+use vnv_heap::modules::persistent_storage::NorFlashStorageModule;
+
+extern "C" {
+    fn vnv_flash_area_open(id: u8, area: *mut *const c_void) -> c_int;
+    fn vnv_flash_area_close(area: *const c_void);
+    fn vnv_flash_area_size(area: *const c_void) -> usize;
+    fn vnv_flash_area_layout(area: *const c_void, write_size: *mut usize, erase_size: *mut usize) -> c_int;
+    fn vnv_flash_area_read(area: *const c_void, offset: usize, data: *mut u8, len: usize) -> c_int;
+    fn vnv_flash_area_write(area: *const c_void, offset: usize, data: *const u8, len: usize) -> c_int;
+    fn vnv_flash_area_erase(area: *const c_void, offset: usize, len: usize) -> c_int;
+}
+
+/// Flash area (partition) of the zephyr flash map, accessed with the `embedded-storage` NOR flash traits.
+///
+/// `WRITE_SIZE` and `ERASE_SIZE` have to match the write block size and the page size of the flash device,
+/// which is checked by `open`. Use `FlashAreaStorageModule` to use the area as storage of a heap.
+pub struct FlashArea<const WRITE_SIZE: usize, const ERASE_SIZE: usize> {
+    area: *const c_void,
+    size: usize,
+}
+
+/// Storage module on top of a flash area (e.g. the internal flash of a board without FRAM).
+///
+/// Writes to regions that are not erased are executed as read-erase-write of the whole page (see `NorFlashStorageModule`),
+/// so the buffer of one page is kept in RAM. Use `FlashTranslationStorageModule` instead if the data of a page
+/// must survive a power failure while the page is rewritten.
+pub type FlashAreaStorageModule<const WRITE_SIZE: usize, const ERASE_SIZE: usize> =
+    NorFlashStorageModule<FlashArea<WRITE_SIZE, ERASE_SIZE>, ERASE_SIZE>;
+
+impl<const WRITE_SIZE: usize, const ERASE_SIZE: usize> FlashArea<WRITE_SIZE, ERASE_SIZE> {
+    /// Opens the flash area `id` (e.g. `vnv_flash_area_default_id()` for the `storage_partition`).
+    ///
+    /// Fails if the area does not exist or its layout does not match `WRITE_SIZE` and `ERASE_SIZE`.
+    ///
+    /// The area must not be used by anything else (e.g. NVS or the settings subsystem) while this object exists.
+    pub unsafe fn open(id: u8) -> Result<Self, ()> {
+        assert!(
+            WRITE_SIZE > 0 && ERASE_SIZE % WRITE_SIZE == 0,
+            "erase size has to be a multiple of the write size"
+        );
+
+        let mut area: *const c_void = core::ptr::null();
+        if vnv_flash_area_open(id, &mut area) != 0 {
+            return Err(());
+        }
+
+        // the object closes the area again if the layout does not fit
+        let flash_area = Self {
+            area,
+            size: vnv_flash_area_size(area),
+        };
+
+        let mut write_size = 0;
+        let mut erase_size = 0;
+        if vnv_flash_area_layout(area, &mut write_size, &mut erase_size) != 0 {
+            return Err(());
+        }
+        if write_size == 0 || WRITE_SIZE % write_size != 0 || ERASE_SIZE != erase_size {
+            return Err(());
+        }
+        if flash_area.size % ERASE_SIZE != 0 {
+            return Err(());
+        }
+
+        Ok(flash_area)
+    }
+
+    /// Returns the storage module for this area (see `FlashAreaStorageModule`)
+    pub fn into_storage_module(self) -> FlashAreaStorageModule<WRITE_SIZE, ERASE_SIZE> {
+        NorFlashStorageModule::new(self)
+    }
+}
+
+impl<const WRITE_SIZE: usize, const ERASE_SIZE: usize> Drop for FlashArea<WRITE_SIZE, ERASE_SIZE> {
+    fn drop(&mut self) {
+        unsafe { vnv_flash_area_close(self.area) };
+    }
+}
+
+impl<const WRITE_SIZE: usize, const ERASE_SIZE: usize> ErrorType for FlashArea<WRITE_SIZE, ERASE_SIZE> {
+    type Error = NorFlashErrorKind;
+}
+
+impl<const WRITE_SIZE: usize, const ERASE_SIZE: usize> ReadNorFlash for FlashArea<WRITE_SIZE, ERASE_SIZE> {
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        check_read(self, offset, bytes.len())?;
+
+        let res = unsafe { vnv_flash_area_read(self.area, offset as usize, bytes.as_mut_ptr(), bytes.len()) };
+        if res != 0 {
+            return Err(NorFlashErrorKind::Other);
+        }
+
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.size
+    }
+}
+
+impl<const WRITE_SIZE: usize, const ERASE_SIZE: usize> NorFlash for FlashArea<WRITE_SIZE, ERASE_SIZE> {
+    const WRITE_SIZE: usize = WRITE_SIZE;
+    const ERASE_SIZE: usize = ERASE_SIZE;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        check_erase(self, from, to)?;
+
+        let res = unsafe { vnv_flash_area_erase(self.area, from as usize, (to - from) as usize) };
+        if res != 0 {
+            return Err(NorFlashErrorKind::Other);
+        }
+
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        check_write(self, offset, bytes.len())?;
+
+        let res = unsafe { vnv_flash_area_write(self.area, offset as usize, bytes.as_ptr(), bytes.len()) };
+        if res != 0 {
+            return Err(NorFlashErrorKind::Other);
+        }
+
+        Ok(())
+    }
+}
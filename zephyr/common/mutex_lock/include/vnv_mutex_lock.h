@@ -0,0 +1,35 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+// Bridge between the zephyr kernel mutex and ZephyrMutexLockModule.
+
+#ifndef VNV_MUTEX_LOCK_H
+#define VNV_MUTEX_LOCK_H
+
+#include <zephyr/kernel.h>
+
+K_MUTEX_DEFINE(vnv_heap_mutex);
+
+int vnv_mutex_lock(void) {
+	return k_mutex_lock(&vnv_heap_mutex, K_FOREVER);
+}
+
+int vnv_mutex_unlock(void) {
+	return k_mutex_unlock(&vnv_heap_mutex);
+}
+
+#endif
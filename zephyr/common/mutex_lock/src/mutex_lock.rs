@@ -0,0 +1,69 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::ffi::c_int;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use vnv_heap::modules::lock::LockModule;
+
+extern "C" {
+    fn vnv_mutex_lock() -> c_int;
+    fn vnv_mutex_unlock() -> c_int;
+}
+
+static ALREADY_INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Lock module that uses a zephyr kernel mutex, so threads that wait for the heap are blocked instead of spinning.
+///
+/// Include `vnv_mutex_lock.h` in your C code to define the mutex.
+/// As the mutex is not an ISR-safe primitive, the heap must not be accessed from interrupt handlers
+/// (calling `vnv_persist_all` from an interrupt handler is fine).
+pub struct ZephyrMutexLockModule {
+    _private: (),
+}
+
+impl ZephyrMutexLockModule {
+    /// Creates the lock module for the mutex defined in `vnv_mutex_lock.h`.
+    ///
+    /// There is only one mutex, so you can only create one object of this struct
+    pub fn new() -> Self {
+        if ALREADY_INITIALIZED.swap(true, Ordering::SeqCst) {
+            panic!("Creating multiple instances of \"ZephyrMutexLockModule\" is invalid!");
+        }
+
+        Self { _private: () }
+    }
+}
+
+impl Drop for ZephyrMutexLockModule {
+    fn drop(&mut self) {
+        ALREADY_INITIALIZED.store(false, Ordering::SeqCst);
+    }
+}
+
+unsafe impl LockModule for ZephyrMutexLockModule {
+    fn lock(&self) {
+        // waiting forever cannot fail
+        let res = unsafe { vnv_mutex_lock() };
+        debug_assert_eq!(res, 0);
+    }
+
+    fn unlock(&self) {
+        let res = unsafe { vnv_mutex_unlock() };
+        debug_assert_eq!(res, 0, "mutex was not locked by this thread");
+    }
+}
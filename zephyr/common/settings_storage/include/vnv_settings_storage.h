@@ -0,0 +1,111 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+// Bridge between the zephyr settings subsystem and SettingsStorageModule.
+// Requires CONFIG_SETTINGS=y and a settings backend (e.g. CONFIG_SETTINGS_NVS=y).
+
+#ifndef VNV_SETTINGS_STORAGE_H
+#define VNV_SETTINGS_STORAGE_H
+
+#include <errno.h>
+#include <string.h>
+#include <zephyr/kernel.h>
+#include <zephyr/sys/printk.h>
+#include <zephyr/settings/settings.h>
+
+#define VNV_SETTINGS_KEY_LEN (SETTINGS_MAX_NAME_LEN + 1)
+
+struct vnv_settings_read_ctx {
+	uint8_t* data;
+	uint32_t len;
+	int found;
+	int error;
+};
+
+static void vnv_settings_build_key(char* key, const char* prefix, uint32_t index) {
+	snprintk(key, VNV_SETTINGS_KEY_LEN, "%s/%x", prefix, index);
+}
+
+static int vnv_settings_read_cb(const char* key, size_t len, settings_read_cb read_cb,
+				void* cb_arg, void* param)
+{
+	struct vnv_settings_read_ctx* ctx = (struct vnv_settings_read_ctx*) param;
+
+	// only accept the exact key, not any of its children
+	if (settings_name_next(key, NULL) != 0) {
+		return 0;
+	}
+
+	if (len != ctx->len) {
+		ctx->error = -EINVAL;
+		return 0;
+	}
+
+	ssize_t res = read_cb(cb_arg, ctx->data, len);
+	if (res < 0 || (size_t) res != len) {
+		ctx->error = -EIO;
+		return 0;
+	}
+
+	ctx->found = 1;
+	return 0;
+}
+
+int vnv_settings_init(void) {
+	return settings_subsys_init();
+}
+
+int vnv_settings_read_chunk(const char* prefix, uint32_t index, uint8_t* data, uint32_t len) {
+	char key[VNV_SETTINGS_KEY_LEN];
+	vnv_settings_build_key(key, prefix, index);
+
+	struct vnv_settings_read_ctx ctx = {
+		.data = data,
+		.len = len,
+		.found = 0,
+		.error = 0
+	};
+
+	int err = settings_load_subtree_direct(key, vnv_settings_read_cb, &ctx);
+	if (err) {
+		return -EIO;
+	}
+
+	if (ctx.error) {
+		return ctx.error;
+	}
+
+	if (!ctx.found) {
+		// chunk was never written: behave like erased storage
+		memset(data, 0, len);
+	}
+
+	return 0;
+}
+
+int vnv_settings_write_chunk(const char* prefix, uint32_t index, const uint8_t* data, uint32_t len) {
+	char key[VNV_SETTINGS_KEY_LEN];
+	vnv_settings_build_key(key, prefix, index);
+
+	if (settings_save_one(key, data, len)) {
+		return -EIO;
+	}
+
+	return 0;
+}
+
+#endif
@@ -0,0 +1,155 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use core::ffi::{c_char, c_int, CStr};
+use core::sync::atomic::AtomicBool;
+
+use vnv_heap::modules::persistent_storage::PersistentStorageModule;
+
+extern "C" {
+    fn vnv_settings_init() -> c_int;
+    fn vnv_settings_read_chunk(prefix: *const c_char, index: u32, data: *mut u8, len: u32) -> c_int;
+    fn vnv_settings_write_chunk(prefix: *const c_char, index: u32, data: *const u8, len: u32) -> c_int;
+}
+
+static ALREADY_INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Storage module that stores its data as entries of the zephyr settings subsystem.
+///
+/// The flat address space `[0, size)` is split into chunks of `CHUNK_SIZE` bytes.
+/// Each chunk is stored as its own setting `<prefix>/<chunk index>`, so this module
+/// can share the settings backend (e.g. NVS) with other parts of the application.
+/// Chunks that were never written read as zeros.
+///
+/// Writes that do not cover a whole chunk are executed as read-modify-write.
+/// Keep `CHUNK_SIZE` small enough for your settings backend (NVS limits the size of one entry).
+pub struct SettingsStorageModule<const CHUNK_SIZE: usize> {
+    prefix: &'static CStr,
+    size: usize,
+    chunk_buffer: [u8; CHUNK_SIZE],
+}
+
+impl<const CHUNK_SIZE: usize> SettingsStorageModule<CHUNK_SIZE> {
+    /// Creates a new settings storage module with `size` bytes using the settings subtree `prefix`.
+    ///
+    /// You can only create one object of this struct safely
+    pub unsafe fn new(prefix: &'static CStr, size: usize) -> Result<Self, ()> {
+        assert!(CHUNK_SIZE > 0, "chunk size has to be greater than zero");
+
+        if ALREADY_INITIALIZED.swap(true, core::sync::atomic::Ordering::SeqCst) {
+            panic!("Creating multiple instances of \"SettingsStorageModule\" is invalid!");
+        }
+
+        if vnv_settings_init() != 0 {
+            ALREADY_INITIALIZED.store(false, core::sync::atomic::Ordering::SeqCst);
+            return Err(());
+        }
+
+        Ok(Self {
+            prefix,
+            size,
+            chunk_buffer: [0u8; CHUNK_SIZE],
+        })
+    }
+
+    fn read_chunk(&mut self, index: usize) -> Result<(), ()> {
+        let res = unsafe {
+            vnv_settings_read_chunk(
+                self.prefix.as_ptr(),
+                index as u32,
+                self.chunk_buffer.as_mut_ptr(),
+                CHUNK_SIZE as u32,
+            )
+        };
+        if res != 0 {
+            return Err(());
+        }
+
+        Ok(())
+    }
+
+    fn write_chunk(&mut self, index: usize) -> Result<(), ()> {
+        let res = unsafe {
+            vnv_settings_write_chunk(
+                self.prefix.as_ptr(),
+                index as u32,
+                self.chunk_buffer.as_ptr(),
+                CHUNK_SIZE as u32,
+            )
+        };
+        if res != 0 {
+            return Err(());
+        }
+
+        Ok(())
+    }
+}
+
+impl<const CHUNK_SIZE: usize> Drop for SettingsStorageModule<CHUNK_SIZE> {
+    fn drop(&mut self) {
+        ALREADY_INITIALIZED.store(false, core::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+impl<const CHUNK_SIZE: usize> PersistentStorageModule for SettingsStorageModule<CHUNK_SIZE> {
+    fn read(&mut self, offset: usize, dest: &mut [u8]) -> Result<(), ()> {
+        debug_assert!(offset + dest.len() <= self.get_max_size());
+
+        let mut rel_offset = 0;
+        while rel_offset < dest.len() {
+            let addr = offset + rel_offset;
+            let chunk_offset = addr % CHUNK_SIZE;
+            let len = (CHUNK_SIZE - chunk_offset).min(dest.len() - rel_offset);
+
+            self.read_chunk(addr / CHUNK_SIZE)?;
+            dest[rel_offset..rel_offset + len]
+                .copy_from_slice(&self.chunk_buffer[chunk_offset..chunk_offset + len]);
+
+            rel_offset += len;
+        }
+
+        Ok(())
+    }
+
+    fn write(&mut self, offset: usize, src: &[u8]) -> Result<(), ()> {
+        debug_assert!(offset + src.len() <= self.get_max_size());
+
+        let mut rel_offset = 0;
+        while rel_offset < src.len() {
+            let addr = offset + rel_offset;
+            let chunk_offset = addr % CHUNK_SIZE;
+            let len = (CHUNK_SIZE - chunk_offset).min(src.len() - rel_offset);
+
+            if len != CHUNK_SIZE {
+                // only part of this chunk is written: keep the rest of it
+                self.read_chunk(addr / CHUNK_SIZE)?;
+            }
+
+            self.chunk_buffer[chunk_offset..chunk_offset + len]
+                .copy_from_slice(&src[rel_offset..rel_offset + len]);
+            self.write_chunk(addr / CHUNK_SIZE)?;
+
+            rel_offset += len;
+        }
+
+        Ok(())
+    }
+
+    fn get_max_size(&self) -> usize {
+        self.size
+    }
+}
@@ -0,0 +1,29 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+// Bridge between the zephyr SPI API and ZephyrSpiDevice.
+
+#ifndef VNV_ZEPHYR_SPI_DEVICE_H
+#define VNV_ZEPHYR_SPI_DEVICE_H
+
+#include <zephyr/drivers/spi.h>
+
+int vnv_spi_transceive(const struct spi_dt_spec* device, const struct spi_buf_set* tx, const struct spi_buf_set* rx) {
+	return spi_transceive_dt(device, tx, rx);
+}
+
+#endif
@@ -18,8 +18,10 @@
 #[cfg(debug_assertions)]
 pub use xxhash_rust::xxh32 as xxhash;
 
+mod zephyr_spi_device;
 mod mb85rs4mt_fram_storage;
 mod mb85rs64v_fram_storage;
 
 pub use mb85rs4mt_fram_storage::*;
 pub use mb85rs64v_fram_storage::*;
+pub use zephyr_spi_device::ZephyrSpiDevice;
@@ -15,26 +15,23 @@
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-extern crate zephyr_sys;
-
 use core::ffi::c_int;
 use core::sync::atomic::AtomicBool;
 
+use generic_spi_fram_storage::{SpiDeviceFramStorageModule, MB85RS4MT};
 use vnv_heap::modules::persistent_storage::PersistentStorageModule;
 
-type SPISpec = zephyr_sys::raw::spi_dt_spec;
+use crate::zephyr_spi_device::{SPISpec, ZephyrSpiDevice};
 
 extern "C" {
     fn mb85rs4mt_init(error: *mut c_int) -> SPISpec;
-    fn mb85rs4mt_validate_id(device: *const SPISpec) -> c_int;
-    fn mb85rs4mt_write_bytes(device: *const SPISpec, addr: u32, data: *const u8, num_bytes: u32) -> c_int;
-    fn mb85rs4mt_read_bytes(device: *const SPISpec, addr: u32, data: *mut u8, num_bytes: u32) -> c_int;
 }
 
 static ALREADY_INITIALIZED: AtomicBool = AtomicBool::new(false);
 
+/// Zephyr wrapper of the `generic_spi_fram_storage` driver for the MB85RS4MT (512KB) connected to `spi2`
 pub struct MB85RS4MTFramStorageModule {
-    spi_spec: SPISpec
+    inner: SpiDeviceFramStorageModule<ZephyrSpiDevice>
 }
 
 impl MB85RS4MTFramStorageModule {
@@ -56,12 +53,11 @@ impl MB85RS4MTFramStorageModule {
             return Err(());
         }
 
-        if mb85rs4mt_validate_id(&spec) != 0 {
-            return Err(());
-        }
+        // checks the device id
+        let inner = SpiDeviceFramStorageModule::from_device(ZephyrSpiDevice::new(spec), MB85RS4MT)?;
 
         Ok(Self {
-            spi_spec: spec
+            inner
         })
     }
 }
@@ -74,23 +70,14 @@ impl Drop for MB85RS4MTFramStorageModule {
 
 impl PersistentStorageModule for MB85RS4MTFramStorageModule {
     fn read(&mut self, address: usize, buffer: &mut [u8]) -> Result<(), ()> {
-        debug_assert!(address + buffer.len() <= self.get_max_size());
-
-        let res = unsafe { mb85rs4mt_read_bytes(&self.spi_spec, address as u32, buffer.as_mut_ptr(), buffer.len() as u32) };
-        if res != 0 {
-            return Err(())
-        }
-
-        Ok(())
+        self.inner.read(address, buffer)
     }
 
     fn write(&mut self, address: usize, buffer: &[u8]) -> Result<(), ()> {
-        debug_assert!(address + buffer.len() <= self.get_max_size());
-        
         #[cfg(debug_assertions)]
         let before_hash: u32 = crate::xxhash::xxh32(buffer, 1780281484);
 
-        let res = unsafe { mb85rs4mt_write_bytes(&self.spi_spec, address as u32, buffer.as_ptr(), buffer.len() as u32) };
+        let res = self.inner.write(address, buffer);
 
         #[cfg(debug_assertions)]
         {
@@ -98,15 +85,10 @@ impl PersistentStorageModule for MB85RS4MTFramStorageModule {
             debug_assert_eq!(before_hash, after_hash, "buffer should not change when writing bytes!");
         }
 
-        if res != 0 {
-            return Err(());
-        }
-
-        Ok(())
+        res
     }
 
     fn get_max_size(&self) -> usize {
-        // 512KB
-        524288
+        self.inner.get_max_size()
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,110 @@
+/*
+ *  Copyright (C) 2025  Markus Elias Gerber
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+extern crate zephyr_sys;
+
+use core::ffi::{c_int, c_void};
+use core::ptr::null_mut;
+
+use embedded_hal::spi::{ErrorKind, ErrorType, Operation, SpiDevice};
+
+pub(crate) type SPISpec = zephyr_sys::raw::spi_dt_spec;
+
+type SPIBuf = zephyr_sys::raw::spi_buf;
+type SPIBufSet = zephyr_sys::raw::spi_buf_set;
+
+extern "C" {
+    fn vnv_spi_transceive(device: *const SPISpec, tx: *const SPIBufSet, rx: *const SPIBufSet) -> c_int;
+}
+
+/// Maximum number of operations in one transaction
+const MAX_OPERATIONS: usize = 4;
+
+/// `embedded-hal` `SpiDevice` on top of a zephyr SPI spec (the chip select is managed by zephyr).
+///
+/// Used to run the platform independent `generic_spi_fram_storage` driver on zephyr.
+/// Transactions with delays (`Operation::DelayNs`) are not supported and fail with `ErrorKind::Other`.
+pub struct ZephyrSpiDevice {
+    spi_spec: SPISpec,
+}
+
+impl ZephyrSpiDevice {
+    pub fn new(spi_spec: SPISpec) -> Self {
+        Self { spi_spec }
+    }
+}
+
+impl ErrorType for ZephyrSpiDevice {
+    type Error = ErrorKind;
+}
+
+impl SpiDevice for ZephyrSpiDevice {
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        if operations.len() > MAX_OPERATIONS {
+            return Err(ErrorKind::Other);
+        }
+
+        let empty = SPIBuf {
+            buf: null_mut(),
+            len: 0,
+        };
+        let mut tx_bufs = [empty; MAX_OPERATIONS];
+        let mut rx_bufs = [empty; MAX_OPERATIONS];
+
+        // a buffer without data is skipped (rx) or filled with dummy bytes (tx) by zephyr
+        for (i, op) in operations.iter_mut().enumerate() {
+            let (tx, rx): ((*mut c_void, usize), (*mut c_void, usize)) = match op {
+                Operation::Read(buf) => ((null_mut(), buf.len()), (buf.as_mut_ptr() as *mut c_void, buf.len())),
+                Operation::Write(buf) => ((buf.as_ptr() as *mut c_void, buf.len()), (null_mut(), buf.len())),
+                Operation::Transfer(read, write) => {
+                    if read.len() != write.len() {
+                        return Err(ErrorKind::Other);
+                    }
+                    (
+                        (write.as_ptr() as *mut c_void, write.len()),
+                        (read.as_mut_ptr() as *mut c_void, read.len()),
+                    )
+                }
+                Operation::TransferInPlace(buf) => (
+                    (buf.as_mut_ptr() as *mut c_void, buf.len()),
+                    (buf.as_mut_ptr() as *mut c_void, buf.len()),
+                ),
+                // not supported, nothing was transferred yet
+                Operation::DelayNs(_) => return Err(ErrorKind::Other),
+            };
+
+            tx_bufs[i] = SPIBuf { buf: tx.0, len: tx.1 };
+            rx_bufs[i] = SPIBuf { buf: rx.0, len: rx.1 };
+        }
+
+        let tx = SPIBufSet {
+            buffers: tx_bufs.as_ptr(),
+            count: operations.len(),
+        };
+        let rx = SPIBufSet {
+            buffers: rx_bufs.as_ptr(),
+            count: operations.len(),
+        };
+
+        let res = unsafe { vnv_spi_transceive(&self.spi_spec, &tx, &rx) };
+        if res != 0 {
+            return Err(ErrorKind::Other);
+        }
+
+        Ok(())
+    }
+}
@@ -23,7 +23,7 @@ extern crate zephyr_sys;
 
 use spi_fram_storage::MB85RS4MTFramStorageModule;
 use vnv_heap::benchmarks::{
-    PersistTrigger, BenchmarkRunOptions, Timer, run_all_benchmarks, RunAllBenchmarkOptions
+    PersistTrigger, BenchmarkRunOptions, BenchmarkSuiteOptions, Timer, run_all_benchmarks, RunAllBenchmarkOptions
 };
 use vnv_heap::modules::persistent_storage::SlicedStorageModule;
 use core::mem::MaybeUninit;
@@ -58,9 +58,13 @@ pub extern "C" fn rust_main() {
     >(
         BenchmarkRunOptions {
             cold_start: 0,
+            cold_start_buffer: &mut [],
             machine_name: "esp32c3",
             repetitions: REPETITIONS as u32,
             result_buffer: &mut [0; REPETITIONS],
+            comparison: None,
+            sizes: None,
+            suites: BenchmarkSuiteOptions::new(),
         },
         RunAllBenchmarkOptions {
             run_allocate_benchmarks: option_env!("VNV_HEAP_RUN_ALLOCATE_BENCHMARKS").is_some(),
@@ -21,9 +21,12 @@ extern crate zephyr_logger;
 extern crate zephyr_macros;
 extern crate zephyr_sys;
 
+#[cfg(not(feature = "flash_storage"))]
 use spi_fram_storage::MB85RS4MTFramStorageModule;
+#[cfg(feature = "flash_storage")]
+use flash_area_storage::{FlashArea, FlashAreaStorageModule};
 use vnv_heap::benchmarks::{
-    PersistTrigger, BenchmarkRunOptions, Timer, run_all_benchmarks, RunAllBenchmarkOptions
+    PersistTrigger, BenchmarkRunOptions, BenchmarkSuiteOptions, Timer, run_all_benchmarks, RunAllBenchmarkOptions
 };
 use vnv_heap::modules::persistent_storage::SlicedStorageModule;
 use core::mem::{MaybeUninit, size_of};
@@ -35,6 +38,8 @@ extern "C" {
     pub fn helper_irq_lock() -> u64;
     pub fn helper_irq_unlock(key: u64);
     pub fn Cache_Invalidate_ICache_All();
+    #[cfg(feature = "flash_storage")]
+    pub fn vnv_flash_area_default_id() -> u8;
 }
 
 #[no_mangle]
@@ -44,14 +49,18 @@ pub extern "C" fn rust_main() {
     run_all_benchmarks::<
         ZephyrTimer,
         ZephyrPersistTrigger,
-        SlicedStorageModule::<SLICE_SIZE, MB85RS4MTFramStorageModule>,
+        BenchStorageModule,
         _
     >(
         BenchmarkRunOptions {
             cold_start: 0,
-            machine_name: "esp32c3",
+            cold_start_buffer: &mut [],
+            machine_name: MACHINE_NAME,
             repetitions: 10,
             result_buffer: &mut [0; 10],
+            comparison: None,
+            sizes: None,
+            suites: BenchmarkSuiteOptions::new(),
         },
         // select benchmarks to run
         RunAllBenchmarkOptions {
@@ -212,8 +221,27 @@ impl Drop for ZephyrPersistTrigger {
 
 const SLICE_SIZE: usize = 4;
 
-fn get_storage() -> SlicedStorageModule::<SLICE_SIZE, MB85RS4MTFramStorageModule> {
+#[cfg(not(feature = "flash_storage"))]
+type BenchStorageModule = SlicedStorageModule::<SLICE_SIZE, MB85RS4MTFramStorageModule>;
+#[cfg(not(feature = "flash_storage"))]
+const MACHINE_NAME: &str = "esp32c3";
+
+#[cfg(not(feature = "flash_storage"))]
+fn get_storage() -> BenchStorageModule {
     let inner_storage = unsafe { MB85RS4MTFramStorageModule::new() }.unwrap();
 
     SlicedStorageModule::new(inner_storage)
 }
+
+// write block and sector size of the esp32c3 on-chip flash
+#[cfg(feature = "flash_storage")]
+type BenchStorageModule = SlicedStorageModule::<SLICE_SIZE, FlashAreaStorageModule<4, 4096>>;
+#[cfg(feature = "flash_storage")]
+const MACHINE_NAME: &str = "esp32c3_flash";
+
+#[cfg(feature = "flash_storage")]
+fn get_storage() -> BenchStorageModule {
+    let flash_area = unsafe { FlashArea::open(vnv_flash_area_default_id()) }.unwrap();
+
+    SlicedStorageModule::new(flash_area.into_storage_module())
+}
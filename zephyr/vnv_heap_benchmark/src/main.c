@@ -24,6 +24,7 @@
 
 #include "../../common/atomics/atomics.h"
 #include "../../common/spi_fram_storage/include/mb85rs4mt_spi_fram.h"
+#include "../../common/flash_area_storage/include/vnv_flash_area_storage.h"
 
 #include <stdio.h>
 
@@ -84,10 +84,39 @@ pub extern "C" fn rust_main() {
     println!("Resident Object Metadata\n-> {} bytes", layout_info.resident_object_metadata);
     println!("Resident Object Dirty Size\n-> {} bytes", layout_info.object_dirty_size);
     println!("Persist Access Point Size\n-> {} bytes", layout_info.persist_access_point_size);
+    println!("Non-Resident Object Header\n-> {} bytes", layout_info.non_resident_object_header);
+    println!("Non-Resident Min Allocation Size\n-> {} bytes", layout_info.non_resident_min_allocation_size);
+    println!("Non-Resident Allocation Rounding\n-> {:?}", layout_info.non_resident_allocation_rounding);
+
+    println!("########## PER OBJECT OVERHEAD #########");
+
+    print_object_layout_info::<u8>();
+    print_object_layout_info::<u32>();
+    print_object_layout_info::<[u8; 100]>();
 
     println!("############### FINISHED ###############")
 }
 
+fn print_object_layout_info<T>() {
+    type A = LinkedListAllocatorModule;
+    type N = NonResidentBuddyAllocatorModule<19>;
+    type M = DefaultObjectManagementModule;
+    type S = MB85RS4MTFramStorageModule;
+
+    let info = VNVHeap::<A, N, M, S>::get_object_layout_info::<T>();
+    println!(
+        "{}\n-> {} bytes resident ({} bytes data, {} bytes overhead, {} bytes metadata dirty size)\n-> {} bytes non-resident ({} bytes requested, {} bytes overhead)",
+        std::any::type_name::<T>(),
+        info.resident_size,
+        info.data_size,
+        info.resident_overhead,
+        info.metadata_dirty_size,
+        info.non_resident_allocated_size,
+        info.non_resident_size,
+        info.non_resident_overhead
+    );
+}
+
 fn print_size<T>() {
     let size = size_of::<T>();
     println!("{}\n-> {} bytes", std::any::type_name::<T>(), size);
@@ -31,7 +31,7 @@ use vnv_heap::{
         nonresident_allocator::NonResidentBuddyAllocatorModule,
         object_management::DefaultObjectManagementModule,
     },
-    vnv_persist_all, VNVConfig, VNVHeap,
+    vnv_persist_all, VNVConfig, VNVHeap, WriteBack,
 };
 
 #[no_mangle]
@@ -56,6 +56,8 @@ pub extern "C" fn rust_main() {
 
     let config = VNVConfig {
         max_dirty_bytes: 600,
+        write_back: WriteBack::Lazy,
+        persist_latency_budget: None,
     };
     let mut buffer = [0u8; 1000];
 
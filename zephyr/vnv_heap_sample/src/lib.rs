@@ -32,7 +32,7 @@ use vnv_heap::{
         nonresident_allocator::NonResidentBuddyAllocatorModule,
         object_management::DefaultObjectManagementModule,
     },
-    VNVConfig, VNVHeap,
+    VNVConfig, VNVHeap, WriteBack,
 };
 
 #[no_mangle]
@@ -43,6 +43,8 @@ pub extern "C" fn rust_main() {
 
     let config = VNVConfig {
         max_dirty_bytes: 100,
+        write_back: WriteBack::Lazy,
+        persist_latency_budget: None,
     };
     let mut buffer = [0u8; 100];
 
@@ -17,7 +17,7 @@
 
 use std::{array, vec};
 use rand::{rngs::SmallRng, RngCore, SeedableRng};
-use vnv_heap::{VNVConfig, VNVHeap, modules::{nonresident_allocator::NonResidentBuddyAllocatorModule, allocator::LinkedListAllocatorModule, object_management::DefaultObjectManagementModule}};
+use vnv_heap::{VNVConfig, VNVHeap, WriteBack, modules::{nonresident_allocator::NonResidentBuddyAllocatorModule, allocator::LinkedListAllocatorModule, object_management::DefaultObjectManagementModule}};
 use spi_fram_storage::MB85RS4MTFramStorageModule;
 
 pub fn test_heap_persistency() {
@@ -30,7 +30,9 @@ pub fn test_heap_persistency() {
     let storage = unsafe { MB85RS4MTFramStorageModule::new() }.unwrap();
     
     let config = VNVConfig {
-        max_dirty_bytes: 1000
+        max_dirty_bytes: 1000,
+        write_back: WriteBack::Lazy,
+        persist_latency_budget: None,
     };
     let mut buffer = [0u8; 1000];
     